@@ -1,6 +1,7 @@
 use {
     crate::{
         decoration::{Decoration, DecorationType},
+        find_bar::{FindBar, FindBarAction},
         layout::{BlockElement, WrappedElement},
         selection::Affinity,
         session::{SelectionMode, Session},
@@ -17,6 +18,7 @@ use {
 
 live_design! {
     import makepad_draw::shader::std::*;
+    import makepad_code_editor::find_bar::FindBar;
     import makepad_widgets::theme_desktop_dark::*;
 
     TokenColors = {{TokenColors}} {
@@ -37,6 +39,10 @@ live_design! {
         delimiter_highlight: #f,
         error_decoration: #f00,
         warning_decoration: #0f0,
+        find_match_decoration: #fc04,
+        remote_selection_decoration: #c6f6,
+        composition_decoration: #D4D4D4,
+        inlay: #808080,
     }
 
     DrawIndentGuide = {{DrawIndentGuide}} {
@@ -51,7 +57,12 @@ live_design! {
 
     DrawDecoration = {{DrawDecoration}} {
         fn pixel(self) -> vec4 {
-            let transformed_pos = vec2(self.pos.x, self.pos.y + 0.03 * sin(self.pos.x * self.rect_size.x));
+            if self.fill > 0.5 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0.0, 0.0, self.rect_size.x, self.rect_size.y, 1.5);
+                return sdf.fill(self.color);
+            }
+            let transformed_pos = vec2(self.pos.x, self.pos.y + self.wavy * 0.03 * sin(self.pos.x * self.rect_size.x));
             let cx = Sdf2d::viewport(transformed_pos * self.rect_size);
             cx.move_to(0.0, self.rect_size.y - 1.0);
             cx.line_to(self.rect_size.x, self.rect_size.y - 1.0);
@@ -116,6 +127,7 @@ live_design! {
         height: Fill,
         margin: 0,
         scroll_bars: <ScrollBars> {}
+        find_bar: <FindBar> {}
         draw_bg: {
            // draw_depth: 0.0,
             color: #2a
@@ -142,6 +154,15 @@ live_design! {
            // draw_depth: 1.0,
             color: #5,
         }
+        draw_popup_bg: {
+            draw_depth: 5.0,
+            color: #3a3a3a,
+        }
+        draw_popup_text: {
+            draw_depth: 6.0,
+            text_style: <THEME_FONT_CODE> {}
+            color: #D4D4D4,
+        }
         draw_decoration: {
           //  draw_depth: 2.0,
         }
@@ -231,6 +252,10 @@ pub struct CodeEditor {
     #[live] draw_cursor: DrawColor,
     #[live] draw_cursor_bg: DrawColor,
     #[live] draw_bg: DrawColor,
+    #[live] draw_popup_bg: DrawColor,
+    #[live] draw_popup_text: DrawText,
+    #[live] find_bar: FindBar,
+    #[rust] is_find_bar_open: bool,
     #[rust(KeepCursorInView::Off)] keep_cursor_in_view: KeepCursorInView,
     #[rust] last_cursor_screen_pos: Option<DVec2>,
 
@@ -244,10 +269,13 @@ pub struct CodeEditor {
     #[live(true)] word_wrap: bool,
 
     #[live(0.5)] blink_speed: f64,
+    #[live(0.5)] hover_delay: f64,
 
     #[animator] animator: Animator,
 
     #[rust] blink_timer: Timer,
+    #[rust] hover_timer: Timer,
+    #[rust] hover_position: Option<Position>,
 }
 
 enum KeepCursorInView {
@@ -321,6 +349,10 @@ impl CodeEditor {
         self.scroll_bars.redraw(cx);
     }
     
+    pub fn area(&self) -> Area {
+        self.scroll_bars.area()
+    }
+    
     pub fn walk(&self, _cx:&mut Cx)->Walk{
         self.walk
     }
@@ -337,6 +369,7 @@ impl CodeEditor {
 
         let last_added_selection =
             session.selections()[session.last_added_selection_index().unwrap()];
+        session.layout_until(last_added_selection.cursor.position.line_index + 1);
         let (cursor_x, cursor_y) = session.layout().logical_to_normalized_position(
             last_added_selection.cursor.position,
             last_added_selection.cursor.affinity,
@@ -386,6 +419,7 @@ impl CodeEditor {
                 //self.keep_cursor_in_view = KeepCursorInView::Locked(cursor_pos);
             }
             KeepCursorInView::LockedCenter(screen_pos, pos, aff) => {
+                session.layout_until(pos.line_index + 1);
                 let (cursor_x, cursor_y) =
                     session.layout().logical_to_normalized_position(pos, aff);
                 let new_pos =
@@ -418,6 +452,15 @@ impl CodeEditor {
             KeepCursorInView::Off => {}
         }
 
+        // The find bar is drawn above the editor, which takes up the rest of the space.
+        let walk = if self.is_find_bar_open {
+            cx.begin_turtle(walk, Layout::flow_down());
+            self.find_bar.draw_all(cx, &mut Scope::empty());
+            Walk::fill()
+        } else {
+            walk
+        };
+
         self.scroll_bars.begin(cx, walk, Layout::default());
 
         let turtle_rect = cx.turtle().rect();
@@ -461,6 +504,17 @@ impl CodeEditor {
         });
 
         let scroll_pos = self.scroll_bars.get_scroll_pos();
+        // Only the lines up to the bottom of the viewport, and the cursor, have to be laid out.
+        session.layout_until(last_added_selection.cursor.position.line_index + 1);
+        session.layout_until_y((scroll_pos.y + self.viewport_rect.size.y) / self.cell_size.y);
+
+        if cx.has_key_focus(self.scroll_bars.area()) {
+            // Keep the IME next to the cursor.
+            cx.show_text_ime(
+                self.scroll_bars.area(),
+                self.viewport_rect.pos - turtle_rect.pos + cursor_pos - scroll_pos,
+            );
+        }
 
         self.line_start = session
             .layout()
@@ -468,6 +522,7 @@ impl CodeEditor {
         self.line_end = session.layout().find_first_line_starting_after_y(
             (scroll_pos.y + self.viewport_rect.size.y) / self.cell_size.y,
         );
+        session.document().tokenize_until(self.line_end);
         self.unscrolled_rect = cx.turtle().unscrolled_rect();
         self.draw_bg.draw_abs(cx, cx.turtle().unscrolled_rect());
 
@@ -475,8 +530,13 @@ impl CodeEditor {
         self.draw_selection_layer(cx, session);
         self.draw_text_layer(cx, session);
         self.draw_indent_guide_layer(cx, session);
-        self.draw_decoration_layer(cx, session);
+        self.draw_decoration_layer(cx, session, &session.find_matches());
+        self.draw_decoration_layer(cx, session, &session.document().decorations());
+        self.draw_decoration_layer(cx, session, &session.document().remote_selections());
+        self.draw_decoration_layer(cx, session, &session.composition());
         self.draw_selection_layer(cx, session);
+        self.draw_completions(cx, session);
+        self.draw_hover(cx, session);
 
         // Get the last added selection.
         // Get the normalized cursor position. To go from normalized to screen position, multiply by
@@ -488,6 +548,9 @@ impl CodeEditor {
         );
 
         self.scroll_bars.end(cx);
+        if self.is_find_bar_open {
+            cx.end_turtle();
+        }
         if session.update_folds() {
             self.scroll_bars.area().redraw(cx);
         } else if self.keep_cursor_in_view.is_locked() {
@@ -495,6 +558,68 @@ impl CodeEditor {
         }
     }
 
+    fn handle_find_bar_event(
+        &mut self,
+        cx: &mut Cx,
+        event: &Event,
+        session: &mut Session,
+        actions: &mut Vec<CodeEditorAction>,
+    ) {
+        for action in self.find_bar.handle_find_bar_event(cx, event) {
+            match action {
+                FindBarAction::QueryChanged => self.update_find(cx, session),
+                FindBarAction::FindNext => {
+                    if session.find_next() {
+                        self.keep_cursor_in_view = KeepCursorInView::Once;
+                    }
+                }
+                FindBarAction::FindPrev => {
+                    if session.find_prev() {
+                        self.keep_cursor_in_view = KeepCursorInView::Once;
+                    }
+                }
+                FindBarAction::Replace(replacement) => {
+                    session.replace(&replacement);
+                    self.keep_cursor_in_view = KeepCursorInView::Once;
+                    actions.push(CodeEditorAction::TextDidChange);
+                }
+                FindBarAction::ReplaceAll(replacement) => {
+                    if session.replace_all(&replacement) > 0 {
+                        actions.push(CodeEditorAction::TextDidChange);
+                    }
+                }
+                FindBarAction::Close => {
+                    self.is_find_bar_open = false;
+                    session.clear_find();
+                    self.set_key_focus(cx);
+                }
+            }
+            self.redraw(cx);
+        }
+        // Edits can add or remove matches, so keep the count up to date.
+        if self.is_find_bar_open && session.is_finding() {
+            self.find_bar
+                .set_match_count(cx, Some(session.find_matches().len()));
+        }
+    }
+
+    /// Searches the document again for the query in the find bar.
+    fn update_find(&mut self, cx: &mut Cx, session: &mut Session) {
+        let query = self.find_bar.query(cx);
+        if query.pattern.is_empty() {
+            session.clear_find();
+            self.find_bar.set_match_count(cx, Some(0));
+            return;
+        }
+        match session.find(&query) {
+            Ok(match_count) => self.find_bar.set_match_count(cx, Some(match_count)),
+            Err(_) => {
+                session.clear_find();
+                self.find_bar.set_match_count(cx, None);
+            }
+        }
+    }
+
     pub fn set_key_focus(&mut self, cx: &mut Cx) {
         cx.set_key_focus(self.scroll_bars.area());
     }
@@ -568,14 +693,100 @@ impl CodeEditor {
             }
             self.blink_timer = cx.start_timeout(self.blink_speed)
         }
+        if let Event::Signal = event {
+            match session.handle_format_result() {
+                Some(Ok(true)) => {
+                    self.redraw(cx);
+                    actions.push(CodeEditorAction::TextDidChange);
+                }
+                Some(Ok(false)) | None => {}
+                Some(Err(error)) => log!("Could not format document: {}", error),
+            }
+        }
+        if self.hover_timer.is_event(event).is_some() {
+            if let Some(position) = self.hover_position {
+                actions.push(CodeEditorAction::RequestHover(position));
+            }
+        }
+        if self.is_find_bar_open {
+            self.handle_find_bar_event(cx, event, session, &mut actions);
+        }
         let mut keyboard_moved_cursor = false;
         match event.hits(cx, self.scroll_bars.area()) {
             Hit::KeyFocusLost(_) => {
+                session.end_composition();
                 self.animator_play(cx, id!(focus.off));
             }
             Hit::KeyFocus(_) => {
                 self.animator_play(cx, id!(focus.on));
             }
+            // The IME owns the keyboard while composing.
+            Hit::KeyDown(_) if session.is_composing() => {}
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowUp,
+                ..
+            }) if !session.completions().is_empty() => {
+                session.select_prev_completion();
+                self.redraw(cx);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowDown,
+                ..
+            }) if !session.completions().is_empty() => {
+                session.select_next_completion();
+                self.redraw(cx);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::ReturnKey | KeyCode::Tab,
+                ..
+            }) if !session.completions().is_empty() => {
+                session.accept_completion();
+                self.redraw(cx);
+                keyboard_moved_cursor = true;
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::Escape,
+                ..
+            }) if !session.completions().is_empty() => {
+                session.clear_completions();
+                self.redraw(cx);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::Space,
+                modifiers: KeyModifiers { control: true, .. },
+                ..
+            }) => {
+                let last_added_selection =
+                    session.selections()[session.last_added_selection_index().unwrap()];
+                actions.push(CodeEditorAction::RequestCompletion(
+                    last_added_selection.cursor.position,
+                ));
+            }
+            Hit::TextComposition(TextCompositionEvent::Start) => {
+                session.start_composition();
+            }
+            Hit::TextComposition(TextCompositionEvent::Update {
+                text,
+                cursor_start,
+                cursor_end,
+            }) => {
+                session.update_composition(&text, cursor_start..cursor_end);
+                self.redraw(cx);
+                keyboard_moved_cursor = true;
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+            Hit::TextComposition(TextCompositionEvent::Commit { text }) => {
+                session.commit_composition(&text);
+                self.redraw(cx);
+                keyboard_moved_cursor = true;
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+            Hit::TextComposition(TextCompositionEvent::Cancel) => {
+                session.cancel_composition();
+                self.redraw(cx);
+                actions.push(CodeEditorAction::TextDidChange);
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::Escape,
                 is_repeat: false,
@@ -597,6 +808,86 @@ impl CodeEditor {
                 }
                 self.redraw(cx);
             }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::LBracket,
+                modifiers:
+                    KeyModifiers {
+                        shift: true,
+                        control,
+                        logo,
+                        ..
+                    },
+                ..
+            }) if (control || logo) && session.toggle_block_fold() => {
+                self.redraw(cx);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyF,
+                modifiers:
+                    KeyModifiers {
+                        shift: true,
+                        alt: true,
+                        ..
+                    },
+                is_repeat: false,
+                ..
+            }) => session.format_document(),
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::F12,
+                is_repeat: false,
+                ..
+            }) => {
+                let last_added_selection =
+                    session.selections()[session.last_added_selection_index().unwrap()];
+                actions.push(CodeEditorAction::GoToDefinition(
+                    last_added_selection.cursor.position,
+                ));
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyF,
+                modifiers: KeyModifiers { shift, control, logo, .. },
+                ..
+            }) => {
+                if (control || logo) && shift {
+                    actions.push(CodeEditorAction::SearchWorkspace);
+                } else if control || logo {
+                    // Open the find bar, filled in with the selected text if there is any.
+                    let text = session.copy();
+                    let pattern = (!text.is_empty() && !text.contains('\n')).then_some(text);
+                    self.is_find_bar_open = true;
+                    self.find_bar.focus(cx, pattern.as_deref());
+                    self.update_find(cx, session);
+                    self.redraw(cx);
+                }
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::F3,
+                modifiers: KeyModifiers { shift, .. },
+                ..
+            }) => {
+                let found = if shift {
+                    session.find_prev()
+                } else {
+                    session.find_next()
+                };
+                if found {
+                    self.keep_cursor_in_view = KeepCursorInView::Once;
+                    self.redraw(cx);
+                }
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyL,
+                modifiers:
+                    KeyModifiers {
+                        shift: true,
+                        control,
+                        logo,
+                        ..
+                    },
+                ..
+            }) if (control || logo) && session.select_all_matches() => {
+                self.redraw(cx);
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::Minus,
                 modifiers: KeyModifiers { control, logo, .. },
@@ -653,12 +944,14 @@ impl CodeEditor {
                     KeyModifiers {
                         shift,
                         control,
+                        alt,
                         logo,
-                        ..
                     },
                 ..
             }) => {
-                if control || logo {
+                if alt && shift {
+                    session.shrink_selection();
+                } else if control || logo {
                     //session.move_to_start_of_line(!shift);
                 } else {
                     session.move_left(!shift);
@@ -672,12 +965,14 @@ impl CodeEditor {
                     KeyModifiers {
                         shift,
                         control,
+                        alt,
                         logo,
-                        ..
                     },
                 ..
             }) => {
-                if control || logo {
+                if alt && shift {
+                    session.expand_selection();
+                } else if control || logo {
                     //session.move_to_end_of_line(!shift);
                 } else {
                     session.move_right(!shift);
@@ -749,10 +1044,21 @@ impl CodeEditor {
                 was_paste: false,
                 ..
             }) if input.len() > 0 => {
-                session.insert(input.into());
+                if session.is_composing() {
+                    session.commit_composition(input);
+                } else {
+                    session.insert(input.into());
+                }
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
+                if input == "." || input.ends_with("::") {
+                    let last_added_selection =
+                        session.selections()[session.last_added_selection_index().unwrap()];
+                    actions.push(CodeEditorAction::RequestCompletion(
+                        last_added_selection.cursor.position,
+                    ));
+                }
             }
             Hit::TextInput(TextInputEvent {
                 ref input,
@@ -921,8 +1227,28 @@ impl CodeEditor {
                 self.reset_cursor_blinker(cx);
                 self.keep_cursor_in_view = KeepCursorInView::Off;
             }
-            Hit::FingerHoverIn(_) | Hit::FingerHoverOver(_) => {
+            Hit::FingerHoverIn(FingerHoverEvent { abs, .. })
+            | Hit::FingerHoverOver(FingerHoverEvent { abs, .. }) => {
                 cx.set_cursor(MouseCursor::Text);
+                let ((position, _), is_in_gutter) = self.pick(session, abs);
+                let position = if is_in_gutter { None } else { Some(position) };
+                if position != self.hover_position {
+                    self.hover_position = position;
+                    cx.stop_timer(self.hover_timer);
+                    if position.is_some() {
+                        self.hover_timer = cx.start_timeout(self.hover_delay);
+                    }
+                    if session.clear_hover() {
+                        self.redraw(cx);
+                    }
+                }
+            }
+            Hit::FingerHoverOut(_) => {
+                self.hover_position = None;
+                cx.stop_timer(self.hover_timer);
+                if session.clear_hover() {
+                    self.redraw(cx);
+                }
             }
             Hit::FingerDown(FingerDownEvent {
                 abs,
@@ -945,6 +1271,13 @@ impl CodeEditor {
         if keyboard_moved_cursor {
             self.keep_cursor_in_view = KeepCursorInView::Once;
             self.reset_cursor_blinker(cx);
+            if session.clear_hover() {
+                self.redraw(cx);
+            }
+        }
+        // The completions are done with once the cursor leaves the word that they complete.
+        if session.completions().is_empty() && session.clear_completions() {
+            self.redraw(cx);
         }
         if let KeepCursorInView::Always(abs, next) = &mut self.keep_cursor_in_view {
             if next.is_event(event).is_some() {
@@ -1090,6 +1423,8 @@ impl CodeEditor {
                             } => {
                                 let (x, y) =
                                     line.grid_to_normalized_position(row_index, column_index);
+                                self.draw_text.color = self.token_colors.inlay;
+                                self.draw_text.outline = 0.0;
                                 self.draw_text.draw_abs(
                                     cx,
                                     DVec2 { x, y: origin_y + y } * self.cell_size
@@ -1155,9 +1490,13 @@ impl CodeEditor {
         }
     }
 
-    fn draw_decoration_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+    fn draw_decoration_layer(
+        &mut self,
+        cx: &mut Cx2d<'_>,
+        session: &Session,
+        decorations: &[Decoration],
+    ) {
         let mut active_decoration = None;
-        let decorations = session.document().decorations();
         let mut decorations = decorations.iter();
         while decorations.as_slice().first().map_or(false, |decoration| {
             decoration.end().line_index < self.line_start
@@ -1180,6 +1519,101 @@ impl CodeEditor {
         .draw_decoration_layer(cx, session)
     }
 
+    fn draw_completions(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        const MAX_VISIBLE_COMPLETION_COUNT: usize = 10;
+
+        let completions = session.completions();
+        if completions.is_empty() {
+            return;
+        }
+        let selected_index = session.selected_completion_index();
+        let start = selected_index.saturating_sub(MAX_VISIBLE_COMPLETION_COUNT - 1);
+        let end = (start + MAX_VISIBLE_COMPLETION_COUNT).min(completions.len());
+        let rows: Vec<String> = completions[start..end]
+            .iter()
+            .map(|completion| match &completion.detail {
+                Some(detail) => format!("{}  {}", completion.label, detail),
+                None => completion.label.clone(),
+            })
+            .collect();
+        let last_added_selection =
+            session.selections()[session.last_added_selection_index().unwrap()];
+        let (x, y) = session.layout().logical_to_normalized_position(
+            last_added_selection.cursor.position,
+            last_added_selection.cursor.affinity,
+        );
+        // Below the line of the cursor.
+        let origin = DVec2 { x, y: y + 1.0 } * self.cell_size + self.viewport_rect.pos;
+        self.draw_popup(cx, origin, &rows, Some(selected_index - start));
+    }
+
+    fn draw_hover(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        const MAX_HOVER_LINE_COUNT: usize = 20;
+
+        let Some(hover) = session.hover() else {
+            return;
+        };
+        let rows: Vec<String> = hover
+            .text
+            .lines()
+            .take(MAX_HOVER_LINE_COUNT)
+            .map(|line| line.to_string())
+            .collect();
+        session.layout_until(hover.position.line_index + 1);
+        let (x, y) = session
+            .layout()
+            .logical_to_normalized_position(hover.position, Affinity::Before);
+        // Above the line that is hovered, unless that does not fit.
+        let mut origin =
+            DVec2 { x, y: y - rows.len() as f64 } * self.cell_size + self.viewport_rect.pos;
+        if origin.y < self.viewport_rect.pos.y + self.scroll_bars.get_scroll_pos().y {
+            origin.y += (rows.len() as f64 + 1.0) * self.cell_size.y;
+        }
+        self.draw_popup(cx, origin, &rows, None);
+    }
+
+    fn draw_popup(
+        &mut self,
+        cx: &mut Cx2d<'_>,
+        origin: DVec2,
+        rows: &[String],
+        selected_row_index: Option<usize>,
+    ) {
+        let padding = dvec2(4.0, 2.0);
+        let column_count = rows.iter().map(|row| row.column_count()).max().unwrap_or(0);
+        let background_color = self.draw_popup_bg.color;
+        self.draw_popup_bg.draw_abs(
+            cx,
+            Rect {
+                pos: origin,
+                size: dvec2(column_count as f64, rows.len() as f64) * self.cell_size
+                    + 2.0 * padding,
+            },
+        );
+        if let Some(row_index) = selected_row_index {
+            self.draw_popup_bg.color = self.token_colors.find_match_decoration;
+            self.draw_popup_bg.draw_abs(
+                cx,
+                Rect {
+                    pos: origin + dvec2(0.0, row_index as f64 * self.cell_size.y + padding.y),
+                    size: dvec2(
+                        column_count as f64 * self.cell_size.x + 2.0 * padding.x,
+                        self.cell_size.y,
+                    ),
+                },
+            );
+            self.draw_popup_bg.color = background_color;
+        }
+        self.draw_popup_text.text_style.font_size = self.draw_text.text_style.font_size;
+        for (row_index, row) in rows.iter().enumerate() {
+            self.draw_popup_text.draw_abs(
+                cx,
+                origin + padding + dvec2(0.0, row_index as f64 * self.cell_size.y),
+                row,
+            );
+        }
+    }
+
     fn draw_selection_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
         let mut active_selection = None;
         let selections = session.selections();
@@ -1219,6 +1653,7 @@ impl CodeEditor {
                 false,
             );
         }
+        session.layout_until_y(position.y);
         let layout = session.layout();
         if position.y > session.layout().height() {
             let lines = layout.as_text().as_lines();
@@ -1419,6 +1854,14 @@ impl CodeEditor {
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, DefaultNone)]
 pub enum CodeEditorAction {
     TextDidChange,
+    GoToDefinition(Position),
+    /// The mouse rested on the text at this position, so hover information can be shown for it
+    /// with `Session::set_hover`.
+    RequestHover(Position),
+    /// Completions for the word before this position can be shown with
+    /// `Session::show_completions`.
+    RequestCompletion(Position),
+    SearchWorkspace,
     None
 }
 
@@ -1588,11 +2031,24 @@ impl<'a> DrawDecorationLayer<'a> {
     ) {
         let start_x = mem::take(&mut self.active_decoration.as_mut().unwrap().start_x);
         let (x, y) = line.grid_to_normalized_position(row_index, column_index);
-        self.code_editor.draw_decoration.color =
-            match self.active_decoration.as_mut().unwrap().decoration.ty {
-                DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
-                DecorationType::Error => self.code_editor.token_colors.error_decoration,
-            };
+        let ty = self.active_decoration.as_ref().unwrap().decoration.ty;
+        self.code_editor.draw_decoration.color = match ty {
+            DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
+            DecorationType::Error => self.code_editor.token_colors.error_decoration,
+            DecorationType::FindMatch => self.code_editor.token_colors.find_match_decoration,
+            DecorationType::RemoteSelection => {
+                self.code_editor.token_colors.remote_selection_decoration
+            }
+            DecorationType::Composition => self.code_editor.token_colors.composition_decoration,
+        };
+        self.code_editor.draw_decoration.fill = match ty {
+            DecorationType::FindMatch | DecorationType::RemoteSelection => 1.0,
+            _ => 0.0,
+        };
+        self.code_editor.draw_decoration.wavy = match ty {
+            DecorationType::Error | DecorationType::Warning => 1.0,
+            _ => 0.0,
+        };
 
         self.code_editor.draw_decoration.draw_abs(
             cx,
@@ -1902,6 +2358,14 @@ struct TokenColors {
     error_decoration: Vec4,
     #[live]
     warning_decoration: Vec4,
+    #[live]
+    find_match_decoration: Vec4,
+    #[live]
+    remote_selection_decoration: Vec4,
+    #[live]
+    composition_decoration: Vec4,
+    #[live]
+    inlay: Vec4,
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
@@ -1914,11 +2378,16 @@ pub struct DrawIndentGuide {
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
 struct DrawDecoration {
     #[deref]
     draw_super: DrawQuad,
     #[live]
     color: Vec4,
+    #[live]
+    fill: f32,
+    #[live]
+    wavy: f32,
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
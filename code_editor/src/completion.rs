@@ -0,0 +1,44 @@
+use crate::text::Position;
+
+/// A completion suggested by a language server.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub detail: Option<String>,
+    /// The text that replaces the word being completed when this completion is accepted.
+    pub text: String,
+}
+
+/// The completions that are shown for the word that starts at `start`.
+#[derive(Clone, Debug)]
+pub struct CompletionList {
+    pub start: Position,
+    pub completions: Vec<Completion>,
+    pub selected_index: usize,
+}
+
+impl CompletionList {
+    pub fn new(start: Position, completions: Vec<Completion>) -> Self {
+        Self {
+            start,
+            completions,
+            selected_index: 0,
+        }
+    }
+
+    /// Returns the completions whose label starts with `prefix`, ignoring case.
+    pub fn filter(&self, prefix: &str) -> Vec<&Completion> {
+        let prefix = prefix.to_lowercase();
+        self.completions
+            .iter()
+            .filter(|completion| completion.label.to_lowercase().starts_with(&prefix))
+            .collect()
+    }
+}
+
+/// The hover information for the text at `position`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Hover {
+    pub position: Position,
+    pub text: String,
+}
@@ -7,6 +7,17 @@ use {
 pub enum DecorationType {
     Error,
     Warning,
+    FindMatch,
+    RemoteSelection,
+    Composition,
+}
+
+/// Where a decoration came from. Each source replaces and clears only its own decorations.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DecorationSource {
+    Build,
+    Test,
+    LanguageServer,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -90,6 +101,31 @@ impl DecorationSet {
         self.remove_overlapping_decorations(index);
     }
 
+    /// Adds the decorations of `other` that do not overlap with any decoration in this set.
+    pub fn merge(&mut self, other: &DecorationSet) {
+        for &decoration in other {
+            let index = match self
+                .decorations
+                .binary_search_by_key(&decoration.start(), |decoration| decoration.start())
+            {
+                Ok(_) => continue,
+                Err(index) => index,
+            };
+            if index > 0 && self.decorations[index - 1].overlaps_with(decoration) {
+                continue;
+            }
+            if index < self.decorations.len() && decoration.overlaps_with(self.decorations[index]) {
+                continue;
+            }
+            self.decorations.insert(index, decoration);
+        }
+    }
+
+    /// Keeps only the decorations for which `f` returns `true`.
+    pub fn retain(&mut self, f: impl FnMut(&Decoration) -> bool) {
+        self.decorations.retain(f);
+    }
+
     pub fn clear(&mut self) {
         self.decorations.clear();
     }
@@ -0,0 +1,84 @@
+use crate::text::Change;
+
+/// Tracks the lines of a document that changed since some per-line state derived from them was
+/// last brought up to date.
+///
+/// Only the first and the last changed line are remembered, so updating the state can start at
+/// the first changed line rather than at the start of the document. Lines after the last changed
+/// line only need to be updated until their old state turns out to be still valid.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DirtyLines {
+    range: Option<(usize, usize)>,
+}
+
+impl DirtyLines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks all of the given number of lines as changed.
+    pub fn all(line_count: usize) -> Self {
+        Self {
+            range: if line_count == 0 {
+                None
+            } else {
+                Some((0, line_count - 1))
+            },
+        }
+    }
+
+    /// Returns the first and last changed line, or `None` if no lines changed.
+    pub fn range(self) -> Option<(usize, usize)> {
+        self.range
+    }
+
+    /// Marks the lines that `change` touches as changed, and moves the lines that were already
+    /// marked along with it.
+    pub fn apply_change(&mut self, change: &Change) {
+        let (line_index, last_line_index) = match *change {
+            Change::Insert(position, ref text) => {
+                let line_count = text.length().line_count;
+                self.range = self.range.map(|(first, last)| {
+                    (
+                        first,
+                        if last > position.line_index {
+                            last + line_count
+                        } else {
+                            last
+                        },
+                    )
+                });
+                (position.line_index, position.line_index + line_count)
+            }
+            Change::Delete(start, length) => {
+                let line_count = length.line_count;
+                let move_line_index = |line_index: usize| {
+                    if line_index > start.line_index + line_count {
+                        line_index - line_count
+                    } else {
+                        line_index.min(start.line_index)
+                    }
+                };
+                self.range = self
+                    .range
+                    .map(|(first, last)| (move_line_index(first), move_line_index(last)));
+                (start.line_index, start.line_index)
+            }
+        };
+        self.range = Some(match self.range {
+            Some((first, last)) => (first.min(line_index), last.max(last_line_index)),
+            None => (line_index, last_line_index),
+        });
+    }
+
+    /// Marks the lines before `line_index` as up to date.
+    pub fn mark_clean_until(&mut self, line_index: usize) {
+        self.range = self
+            .range
+            .map(|(first, last)| (first.max(line_index), last.max(line_index)));
+    }
+
+    pub fn clear(&mut self) {
+        self.range = None;
+    }
+}
@@ -1,7 +1,8 @@
 use {
     crate::{
         char::CharExt,
-        decoration::{Decoration, DecorationSet},
+        decoration::{Decoration, DecorationSet, DecorationSource},
+        dirty_lines::DirtyLines,
         history::{EditKind, History},
         inlays::{BlockInlay, InlineInlay},
         iter::IteratorExt,
@@ -14,9 +15,9 @@ use {
         tokenizer::Tokenizer,
     },
     std::{
-        cell::{Ref, RefCell},
+        cell::{Cell, Ref, RefCell},
         cmp::Ordering,
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         iter,
         ops::Range,
         rc::Rc,
@@ -24,49 +25,119 @@ use {
     },
 };
 
+/// Documents with more lines than this are only tokenized as far as they are looked at, see
+/// `Document::tokenize_until`.
+pub const LARGE_FILE_LINE_COUNT: usize = 10_000;
+
 #[derive(Clone, Debug)]
 pub struct Document(Rc<DocumentInner>);
 
 impl Document {
+    /// Creates a document with the given build decorations.
     pub fn new(text: Text, decorations: DecorationSet) -> Self {
+        Self::with_extension(text, decorations, "rs")
+    }
+
+    /// Creates a document that is highlighted according to the language for the given file
+    /// extension.
+    pub fn with_extension(text: Text, decorations: DecorationSet, extension: &str) -> Self {
+        Self::with_history(History::from(text), decorations, extension)
+    }
+
+    /// Creates a document from a previously saved history, so that edits made before it was
+    /// saved can still be undone.
+    pub fn with_history(history: History, decorations: DecorationSet, extension: &str) -> Self {
+        let text = history.as_text();
         let line_count = text.as_lines().len();
         let tokens: Vec<_> = (0..line_count)
             .map(|line| tokenize(&text.as_lines()[line]).collect::<Vec<_>>())
             .collect();
         let inner = Self(Rc::new(DocumentInner {
-            history: RefCell::new(History::from(text)),
+            history: RefCell::new(history),
             layout: RefCell::new(DocumentLayout {
                 indent_state: (0..line_count).map(|_| None).collect(),
                 tokens,
                 inline_inlays: (0..line_count).map(|_| Vec::new()).collect(),
                 block_inlays: Vec::new(),
             }),
-            tokenizer: RefCell::new(Tokenizer::new(line_count)),
-            decorations: RefCell::new(decorations),
+            tokenizer: RefCell::new(Tokenizer::for_extension(extension, line_count)),
+            decorations: RefCell::new(decorations.clone()),
+            decoration_sources: RefCell::new(BTreeMap::from([(
+                DecorationSource::Build,
+                decorations,
+            )])),
+            remote_selections: RefCell::new(DecorationSet::new()),
             edit_senders: RefCell::new(HashMap::new()),
+            relayout_senders: RefCell::new(HashMap::new()),
+            local_edit_sender: RefCell::new(None),
+            indent_dirty_lines: Cell::new(DirtyLines::all(line_count)),
+            is_large_file: line_count > LARGE_FILE_LINE_COUNT,
         }));
         inner.update_indent_state();
-        inner.0.tokenizer.borrow_mut().update(
-            &inner.0.history.borrow().as_text(),
-            &mut inner.0.layout.borrow_mut().tokens,
-        );
+        // Until the language tokenizer gets to them, the lines of a large file keep the tokens
+        // from splitting them on whitespace.
+        if !inner.is_large_file() {
+            inner.0.tokenizer.borrow_mut().update(
+                inner.0.history.borrow().as_text(),
+                &mut inner.0.layout.borrow_mut().tokens,
+            );
+        }
         inner
     }
 
+    /// Returns `true` if this document is so large that it is tokenized lazily.
+    pub fn is_large_file(&self) -> bool {
+        self.0.is_large_file
+    }
+
+    /// Makes sure that the lines before `line_end` are tokenized. This only has an effect for
+    /// large files, since other documents are always fully tokenized. Tokenizing the lines that
+    /// are visible is enough for drawing them.
+    pub fn tokenize_until(&self, line_end: usize) {
+        self.0.tokenizer.borrow_mut().update_until(
+            self.0.history.borrow().as_text(),
+            &mut self.0.layout.borrow_mut().tokens,
+            line_end,
+        );
+    }
+
+    /// The number of lines the tokenizer went over so far, see `Tokenizer::tokenized_line_count`.
+    pub fn tokenized_line_count(&self) -> usize {
+        self.0.tokenizer.borrow().tokenized_line_count()
+    }
+
     pub fn as_text(&self) -> Ref<'_, Text> {
         Ref::map(self.0.history.borrow(), |history| history.as_text())
     }
 
+    pub fn history(&self) -> Ref<'_, History> {
+        self.0.history.borrow()
+    }
+
     pub fn layout(&self) -> Ref<'_, DocumentLayout> {
         self.0.layout.borrow()
     }
 
+    /// The decorations from all sources. Where decorations from different sources overlap, the
+    /// one from the first source in `DecorationSource` order is kept.
     pub fn decorations(&self) -> Ref<'_, [Decoration]> {
         Ref::map(self.0.decorations.borrow(), |decorations| {
             decorations.as_decorations()
         })
     }
 
+    /// The decorations of each source, as they were passed to `replace_decorations`.
+    pub fn decorations_by_source(&self) -> BTreeMap<DecorationSource, DecorationSet> {
+        self.0.decoration_sources.borrow().clone()
+    }
+
+    /// The selections of other participants that are editing this document remotely.
+    pub fn remote_selections(&self) -> Ref<'_, [Decoration]> {
+        Ref::map(self.0.remote_selections.borrow(), |decorations| {
+            decorations.as_decorations()
+        })
+    }
+
     pub fn edit_selections(
         &self,
         session_id: SessionId,
@@ -101,6 +172,7 @@ impl Document {
                     Change::Insert(position, ref text) => {
                         if let Some(char) = text.to_single_char() {
                             if char == '}'
+                                && settings.outdent_closing_brace
                                 && history.as_text().as_lines()[position.line_index]
                                     [..position.byte_index]
                                     .chars()
@@ -134,8 +206,10 @@ impl Document {
             prev_edit_start = edit_start;
         }
         drop(history);
-        self.autoindent(&line_ranges, settings.tab_column_count, &mut edits);
-        self.update_after_edit(session_id, None, &edits);
+        if kind != EditKind::Format {
+            self.autoindent(&line_ranges, settings.tab_column_count, &mut edits);
+        }
+        self.update_after_edit(Some(session_id), None, &edits);
     }
 
     pub fn edit_linewise(
@@ -171,30 +245,116 @@ impl Document {
             }
         }
         drop(history);
-        self.update_after_edit(origin_id, None, &edits);
+        self.update_after_edit(Some(origin_id), None, &edits);
     }
 
-    pub fn add_decoration(&mut self, decoration: Decoration) {
-        self.0.decorations.borrow_mut().add_decoration(decoration);
+    pub fn add_decoration(&mut self, source: DecorationSource, decoration: Decoration) {
+        self.0
+            .decoration_sources
+            .borrow_mut()
+            .entry(source)
+            .or_default()
+            .add_decoration(decoration);
+        self.update_decorations();
+    }
+
+    pub fn clear_decorations(&mut self, source: DecorationSource) {
+        self.0.decoration_sources.borrow_mut().remove(&source);
+        self.update_decorations();
     }
 
-    pub fn clear_decorations(&mut self) {
-        self.0.decorations.borrow_mut().clear()
+    pub fn replace_decorations(&mut self, source: DecorationSource, decorations: DecorationSet) {
+        self.0
+            .decoration_sources
+            .borrow_mut()
+            .insert(source, decorations);
+        self.update_decorations();
+    }
+
+    /// Replaces all inline inlays, such as the inlay hints from a language server. Each inlay is
+    /// placed before the given position.
+    pub fn replace_inline_inlays(&self, inlays: Vec<(Position, InlineInlay)>) {
+        let line_count = self.0.history.borrow().as_text().as_lines().len();
+        let mut new_inline_inlays: Vec<Vec<(usize, InlineInlay)>> =
+            (0..line_count).map(|_| Vec::new()).collect();
+        for (position, inlay) in inlays {
+            if let Some(line_inlays) = new_inline_inlays.get_mut(position.line_index) {
+                line_inlays.push((position.byte_index, inlay));
+            }
+        }
+        let mut dirty_lines = Vec::new();
+        let mut layout = self.0.layout.borrow_mut();
+        for (line_index, mut line_inlays) in new_inline_inlays.into_iter().enumerate() {
+            line_inlays.sort_by_key(|&(byte_index, _)| byte_index);
+            if layout.inline_inlays[line_index] != line_inlays {
+                layout.inline_inlays[line_index] = line_inlays;
+                dirty_lines.push(line_index);
+            }
+        }
+        drop(layout);
+        if dirty_lines.is_empty() {
+            return;
+        }
+        for relayout_sender in self.0.relayout_senders.borrow().values() {
+            relayout_sender.send(dirty_lines.clone()).unwrap();
+        }
     }
 
+    pub fn replace_remote_selections(&self, remote_selections: DecorationSet) {
+        *self.0.remote_selections.borrow_mut() = remote_selections;
+    }
+
+    /// Sets a sender that receives the edits made by the sessions of this document, including
+    /// undo and redo, so that they can be sent to other participants. Edits applied with
+    /// `apply_remote_edits` are not sent.
+    pub fn set_local_edit_sender(&self, local_edit_sender: Sender<Vec<Edit>>) {
+        *self.0.local_edit_sender.borrow_mut() = Some(local_edit_sender);
+    }
+
+    /// Applies edits that were made by another participant. The edits are kept out of the undo
+    /// history, see `History::apply_remote_edit`, and the selections of every session are moved
+    /// past text that is inserted at them.
+    pub fn apply_remote_edits(&self, edits: Vec<Edit>) {
+        if edits.is_empty() {
+            return;
+        }
+        let mut history = self.0.history.borrow_mut();
+        for edit in &edits {
+            history.apply_remote_edit(edit.clone());
+        }
+        drop(history);
+        self.update_after_edit(None, None, &edits);
+    }
+
+    /// Registers a session. Edits are sent to `edit_sender`, and the lines that need to be laid
+    /// out again for other reasons, such as changed inlays, to `relayout_sender`.
     pub fn add_session(
         &mut self,
         session_id: SessionId,
         edit_sender: Sender<(Option<SelectionSet>, Vec<Edit>)>,
+        relayout_sender: Sender<Vec<usize>>,
     ) {
         self.0
             .edit_senders
             .borrow_mut()
             .insert(session_id, edit_sender);
+        self.0
+            .relayout_senders
+            .borrow_mut()
+            .insert(session_id, relayout_sender);
     }
 
     pub fn remove_session(&mut self, session_id: SessionId) {
         self.0.edit_senders.borrow_mut().remove(&session_id);
+        self.0.relayout_senders.borrow_mut().remove(&session_id);
+    }
+
+    fn update_decorations(&self) {
+        let mut decorations = self.0.decorations.borrow_mut();
+        decorations.clear();
+        for source_decorations in self.0.decoration_sources.borrow().values() {
+            decorations.merge(source_decorations);
+        }
     }
 
     fn autoindent(
@@ -239,9 +399,10 @@ impl Document {
                 }
             })
         {
-            let mut desired_indentation_column_count = self.as_text().as_lines()
-                [..line_range.start]
-                .iter()
+            let mut desired_indentation_column_count = self
+                .as_text()
+                .as_lines()
+                .range(..line_range.start)
                 .rev()
                 .find_map(|line| next_line_indent_column_count(line, indent_column_count))
                 .unwrap_or(0);
@@ -259,7 +420,8 @@ impl Document {
                     })
                     .unwrap_or(false)
                 {
-                    desired_indentation_column_count -= 4;
+                    desired_indentation_column_count =
+                        desired_indentation_column_count.saturating_sub(indent_column_count);
                 }
                 self.edit_lines_internal(line, edits, |line| {
                     crate::session::reindent(line, |_| desired_indentation_column_count)
@@ -323,32 +485,54 @@ impl Document {
         let mut changes = Vec::new();
         let selections = self.0.history.borrow_mut().undo(selections, &mut changes);
         if let Some(selections) = selections {
-            self.update_after_edit(origin_id, Some(selections), &changes);
+            self.update_after_edit(Some(origin_id), Some(selections), &changes);
             true
         } else {
             false
         }
     }
 
-    pub fn redo(&self, origin_id: SessionId, selections: &SelectionSet) -> bool {
+    pub fn redo(&self, origin_id: SessionId) -> bool {
         let mut changes = Vec::new();
-        let selections = self.0.history.borrow_mut().redo(selections, &mut changes);
+        let selections = self.0.history.borrow_mut().redo(&mut changes);
         if let Some(selections) = selections {
-            self.update_after_edit(origin_id, Some(selections), &changes);
+            self.update_after_edit(Some(origin_id), Some(selections), &changes);
             true
         } else {
             false
         }
     }
 
-    fn update_after_edit(
+    pub fn jump_to_revision(
         &self,
         origin_id: SessionId,
+        revision: usize,
+        selections: &SelectionSet,
+    ) -> bool {
+        let mut changes = Vec::new();
+        let selections = self
+            .0
+            .history
+            .borrow_mut()
+            .jump_to(revision, selections, &mut changes);
+        if let Some(selections) = selections {
+            self.update_after_edit(Some(origin_id), Some(selections), &changes);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn update_after_edit(
+        &self,
+        origin_id: Option<SessionId>,
         selections: Option<SelectionSet>,
         edits: &[Edit],
     ) {
         let mut layout = self.0.layout.borrow_mut();
+        let mut indent_dirty_lines = self.0.indent_dirty_lines.get();
         for edit in edits {
+            indent_dirty_lines.apply_change(&edit.change);
             match edit.change {
                 Change::Insert(position, ref text) => {
                     layout.indent_state[position.line_index] = None;
@@ -371,23 +555,38 @@ impl Document {
             }
         }
         drop(layout);
+        self.0.indent_dirty_lines.set(indent_dirty_lines);
         for edit in edits {
             self.apply_change_to_tokens(&edit.change);
             self.apply_change_to_inline_inlays(&edit.change, edit.drift);
             self.0.tokenizer.borrow_mut().apply_change(&edit.change);
         }
         self.update_indent_state();
-        self.0.tokenizer.borrow_mut().update(
-            self.0.history.borrow().as_text(),
-            &mut self.0.layout.borrow_mut().tokens,
-        );
-        let mut decorations = self.0.decorations.borrow_mut();
+        if !self.is_large_file() {
+            self.0.tokenizer.borrow_mut().update(
+                self.0.history.borrow().as_text(),
+                &mut self.0.layout.borrow_mut().tokens,
+            );
+        }
+        for decorations in self.0.decoration_sources.borrow_mut().values_mut() {
+            for edit in edits {
+                decorations.apply_edit(edit);
+            }
+        }
+        self.update_decorations();
+        let mut remote_selections = self.0.remote_selections.borrow_mut();
         for edit in edits {
-            decorations.apply_edit(edit);
+            remote_selections.apply_edit(edit);
+        }
+        drop(remote_selections);
+        if origin_id.is_some() {
+            if let Some(local_edit_sender) = &*self.0.local_edit_sender.borrow() {
+                // The receiver is gone once nobody is interested in the edits anymore.
+                let _ = local_edit_sender.send(edits.to_vec());
+            }
         }
-        drop(decorations);
         for (&session_id, edit_sender) in &*self.0.edit_senders.borrow() {
-            if session_id == origin_id {
+            if Some(session_id) == origin_id {
                 edit_sender
                     .send((selections.clone(), edits.to_vec()))
                     .unwrap();
@@ -605,14 +804,30 @@ impl Document {
     }
 
     fn update_indent_state(&self) {
+        let Some((first_line_index, last_line_index)) = self.0.indent_dirty_lines.get().range()
+        else {
+            return;
+        };
         let mut layout = self.0.layout.borrow_mut();
         let indent_state = &mut layout.indent_state;
         let history = self.0.history.borrow();
         let lines = history.as_text().as_lines();
-        let mut current_indent_column_count = 0;
-        for line_index in 0..lines.len() {
+        let mut current_indent_column_count = match first_line_index
+            .checked_sub(1)
+            .and_then(|prev_line_index| indent_state[prev_line_index])
+        {
+            Some(IndentState::Empty(indent_column_count))
+            | Some(IndentState::NonEmpty(_, indent_column_count)) => indent_column_count,
+            None => 0,
+        };
+        for line_index in first_line_index..lines.len() {
             match indent_state[line_index] {
                 Some(IndentState::NonEmpty(_, next_indent_column_count)) => {
+                    // Empty lines only depend on the non-empty lines before them, so everything
+                    // after the last changed line is up to date from here on.
+                    if line_index > last_line_index {
+                        break;
+                    }
                     current_indent_column_count = next_indent_column_count;
                 }
                 _ => {
@@ -644,6 +859,7 @@ impl Document {
                 }
             }
         }
+        self.0.indent_dirty_lines.set(DirtyLines::new());
     }
 }
 
@@ -684,7 +900,13 @@ struct DocumentInner {
     layout: RefCell<DocumentLayout>,
     tokenizer: RefCell<Tokenizer>,
     decorations: RefCell<DecorationSet>,
+    decoration_sources: RefCell<BTreeMap<DecorationSource, DecorationSet>>,
+    remote_selections: RefCell<DecorationSet>,
     edit_senders: RefCell<HashMap<SessionId, Sender<(Option<SelectionSet>, Vec<Edit>)>>>,
+    relayout_senders: RefCell<HashMap<SessionId, Sender<Vec<usize>>>>,
+    local_edit_sender: RefCell<Option<Sender<Vec<Edit>>>>,
+    indent_dirty_lines: Cell<DirtyLines>,
+    is_large_file: bool,
 }
 
 fn tokenize(text: &str) -> impl Iterator<Item = Token> + '_ {
@@ -0,0 +1,102 @@
+use {
+    crate::{
+        decoration::{Decoration, DecorationSet, DecorationType},
+        rope::Rope,
+        text::Position,
+    },
+    makepad_regex::{Regex, RegexError, RegexOptions},
+    std::ops::Range,
+};
+
+/// What to search for in a document.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FindQuery {
+    pub pattern: String,
+    /// Whether `pattern` is a regular expression. If not, it is matched literally, and
+    /// replacement strings are inserted as is.
+    pub is_regex: bool,
+    pub case_insensitive: bool,
+    pub whole_word: bool,
+}
+
+impl FindQuery {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn to_regex(&self) -> Result<Regex, RegexError> {
+        let mut pattern = if self.is_regex {
+            self.pattern.clone()
+        } else {
+            makepad_regex::escape(&self.pattern)
+        };
+        if self.whole_word {
+            pattern = format!("\\b(?:{})\\b", pattern);
+        }
+        Regex::with_options(
+            &pattern,
+            RegexOptions {
+                case_insensitive: self.case_insensitive,
+            },
+        )
+    }
+}
+
+/// Finds all non-empty matches of `regex` in `lines`. Matches never span more than one line.
+pub fn find_matches(lines: &Rope, regex: &Regex) -> DecorationSet {
+    let mut matches = DecorationSet::new();
+    update_matches(&mut matches, lines, regex, 0..lines.len());
+    matches
+}
+
+/// Searches the lines in `line_range` again after they were edited, and replaces their matches in
+/// `matches`. The matches on other lines must already have been moved along with the edits.
+pub fn update_matches(
+    matches: &mut DecorationSet,
+    lines: &Rope,
+    regex: &Regex,
+    line_range: Range<usize>,
+) {
+    matches.retain(|decoration| !line_range.contains(&decoration.start().line_index));
+    for line_index in line_range {
+        for m in regex.find_iter(&lines[line_index]) {
+            if m.is_empty() {
+                continue;
+            }
+            matches.add_decoration(Decoration::new(
+                0,
+                Position {
+                    line_index,
+                    byte_index: m.start,
+                },
+                Position {
+                    line_index,
+                    byte_index: m.end,
+                },
+                DecorationType::FindMatch,
+            ));
+        }
+    }
+}
+
+/// Computes the text that replaces the match starting at `byte_index` in `line`. For regex
+/// queries, `$n` and `${n}` in `replacement` are expanded to the corresponding capture group.
+pub fn expand_replacement(
+    line: &str,
+    byte_index: usize,
+    regex: &Regex,
+    is_regex: bool,
+    replacement: &str,
+) -> String {
+    if !is_regex {
+        return replacement.to_string();
+    }
+    let mut string = String::new();
+    if let Some(captures) = regex.captures_at(line, byte_index) {
+        captures.expand(line, replacement, &mut string);
+    }
+    string
+}
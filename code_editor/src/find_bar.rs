@@ -0,0 +1,162 @@
+use {crate::find::FindQuery, makepad_widgets::*};
+
+live_design! {
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    FindBarButton = <Button> {
+        padding: {left: 6, right: 6, top: 4, bottom: 4}
+    }
+
+    FindBar = {{FindBar}} {
+        width: Fill,
+        height: Fit,
+        flow: Down,
+        padding: 4,
+        spacing: 2,
+        show_bg: true,
+        draw_bg: {color: #333}
+
+        <View> {
+            width: Fill,
+            height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            find_input = <TextInput> {
+                width: Fill,
+                empty_message: "Find"
+            }
+            match_count = <Label> {
+                width: 80,
+                text: ""
+            }
+            prev_button = <FindBarButton> {text: "Prev"}
+            next_button = <FindBarButton> {text: "Next"}
+            regex_toggle = <CheckBox> {text: ".*"}
+            case_toggle = <CheckBox> {text: "Aa"}
+            close_button = <FindBarButton> {text: "Close"}
+        }
+        <View> {
+            width: Fill,
+            height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            replace_input = <TextInput> {
+                width: Fill,
+                empty_message: "Replace"
+            }
+            replace_button = <FindBarButton> {text: "Replace"}
+            replace_all_button = <FindBarButton> {text: "Replace all"}
+        }
+    }
+}
+
+/// The bar for finding and replacing text in the code editor.
+#[derive(Live, LiveHook, Widget)]
+pub struct FindBar {
+    #[deref]
+    view: View,
+}
+
+/// What the user asked for in the find bar.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum FindBarAction {
+    /// The pattern or one of the toggles changed, so the document needs to be searched again for
+    /// `FindBar::query`.
+    QueryChanged,
+    FindNext,
+    FindPrev,
+    Replace(String),
+    ReplaceAll(String),
+    Close,
+}
+
+impl Widget for FindBar {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        self.view.handle_event(cx, event, scope);
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.view.draw_walk(cx, scope, walk)
+    }
+}
+
+impl FindBar {
+    pub fn handle_find_bar_event(&mut self, cx: &mut Cx, event: &Event) -> Vec<FindBarAction> {
+        let actions =
+            cx.capture_actions(|cx| self.view.handle_event(cx, event, &mut Scope::empty()));
+        let mut find_bar_actions = Vec::new();
+        let find_input = self.view.text_input(id!(find_input));
+        let replace_input = self.view.text_input(id!(replace_input));
+        let regex_toggle = self.view.check_box(id!(regex_toggle));
+        let case_toggle = self.view.check_box(id!(case_toggle));
+        if find_input.changed(&actions).is_some()
+            || regex_toggle.changed(&actions).is_some()
+            || case_toggle.changed(&actions).is_some()
+        {
+            find_bar_actions.push(FindBarAction::QueryChanged);
+        }
+        match actions.find_widget_action_cast(find_input.widget_uid()) {
+            TextInputAction::Return(_) => find_bar_actions.push(FindBarAction::FindNext),
+            TextInputAction::Escape => find_bar_actions.push(FindBarAction::Close),
+            _ => {}
+        }
+        match actions.find_widget_action_cast(replace_input.widget_uid()) {
+            TextInputAction::Return(_) => {
+                find_bar_actions.push(FindBarAction::Replace(replace_input.text()))
+            }
+            TextInputAction::Escape => find_bar_actions.push(FindBarAction::Close),
+            _ => {}
+        }
+        if self.view.button(id!(next_button)).clicked(&actions) {
+            find_bar_actions.push(FindBarAction::FindNext);
+        }
+        if self.view.button(id!(prev_button)).clicked(&actions) {
+            find_bar_actions.push(FindBarAction::FindPrev);
+        }
+        if self.view.button(id!(replace_button)).clicked(&actions) {
+            find_bar_actions.push(FindBarAction::Replace(replace_input.text()));
+        }
+        if self.view.button(id!(replace_all_button)).clicked(&actions) {
+            find_bar_actions.push(FindBarAction::ReplaceAll(replace_input.text()));
+        }
+        if self.view.button(id!(close_button)).clicked(&actions) {
+            find_bar_actions.push(FindBarAction::Close);
+        }
+        find_bar_actions
+    }
+
+    pub fn query(&mut self, cx: &Cx) -> FindQuery {
+        FindQuery {
+            pattern: self.view.text_input(id!(find_input)).text(),
+            is_regex: self.view.check_box(id!(regex_toggle)).selected(cx),
+            case_insensitive: !self.view.check_box(id!(case_toggle)).selected(cx),
+            whole_word: false,
+        }
+    }
+
+    /// Focuses the find input, and fills it in with `pattern` if there is one.
+    pub fn focus(&mut self, cx: &mut Cx, pattern: Option<&str>) {
+        let find_input = self.view.text_input(id!(find_input));
+        if let Some(pattern) = pattern {
+            find_input.set_text(pattern);
+        }
+        cx.set_key_focus(find_input.area());
+        self.view.redraw(cx);
+    }
+
+    /// Shows the number of matches, or that the pattern is not a valid regex if there is none.
+    pub fn set_match_count(&mut self, cx: &mut Cx, match_count: Option<usize>) {
+        let text = match match_count {
+            Some(1) => "1 match".to_string(),
+            Some(match_count) => format!("{} matches", match_count),
+            None => "Invalid regex".to_string(),
+        };
+        let label = self.view.label(id!(match_count));
+        if label.text() != text {
+            label.set_text_and_redraw(cx, &text);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use {
+    crate::text::{Change, Position, Text},
+    makepad_widgets::Signal,
+    std::{
+        error, fmt,
+        io::{self, Write},
+        ops::Range,
+        process::{Command, Stdio},
+        sync::{mpsc, mpsc::Receiver},
+        thread,
+    },
+};
+
+// Diffing takes time proportional to the number of line pairs. Beyond this many, the lines that
+// changed are replaced as a whole rather than diffed.
+const MAX_DIFF_LINE_PAIR_COUNT: usize = 1 << 26;
+
+/// Runs `command` with `args`, passing `text` on its standard input, and returns what it writes
+/// to its standard output.
+pub fn run_formatter(command: &str, args: &[String], text: &str) -> Result<String, FormatError> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // The input is written on a separate thread, so that a formatter that starts writing before
+    // it has read everything does not block on a full pipe.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = text.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().unwrap()?;
+    if !output.status.success() {
+        return Err(FormatError::Failed(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|_| FormatError::InvalidOutput)
+}
+
+/// Runs `run_formatter` on a background thread, so that the UI does not wait for it. The result
+/// is sent on the returned receiver, after which the UI is woken up with `Event::Signal`.
+pub fn spawn_formatter(
+    command: String,
+    args: Vec<String>,
+    text: String,
+) -> Receiver<Result<String, FormatError>> {
+    let (result_sender, result_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        if result_sender
+            .send(run_formatter(&command, &args, &text))
+            .is_ok()
+        {
+            Signal::set_ui_signal();
+        }
+    });
+    result_receiver
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    /// The formatter exited with an error. Contains what it wrote to its standard error.
+    Failed(String),
+    InvalidOutput,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to run formatter: {}", error),
+            Self::Failed(stderr) => write!(f, "formatter failed: {}", stderr.trim_end()),
+            Self::InvalidOutput => write!(f, "formatter output is not valid UTF-8"),
+        }
+    }
+}
+
+impl error::Error for FormatError {}
+
+impl From<io::Error> for FormatError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A range of lines in the old text that has to be replaced by a range of lines in the new text.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Hunk {
+    pub old_lines: Range<usize>,
+    pub new_lines: Range<usize>,
+}
+
+/// Returns the hunks that turn `old_lines` into `new_lines`, in order, while keeping as many
+/// lines as possible.
+pub fn diff_lines<T: PartialEq>(old_lines: &[T], new_lines: &[T]) -> Vec<Hunk> {
+    let prefix_len = old_lines
+        .iter()
+        .zip(new_lines)
+        .take_while(|(old_line, new_line)| old_line == new_line)
+        .count();
+    let suffix_len = old_lines[prefix_len..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix_len..].iter().rev())
+        .take_while(|(old_line, new_line)| old_line == new_line)
+        .count();
+    let old = &old_lines[prefix_len..old_lines.len() - suffix_len];
+    let new = &new_lines[prefix_len..new_lines.len() - suffix_len];
+    if old.is_empty() && new.is_empty() {
+        return Vec::new();
+    }
+    if old.len() * new.len() > MAX_DIFF_LINE_PAIR_COUNT {
+        return vec![Hunk {
+            old_lines: prefix_len..prefix_len + old.len(),
+            new_lines: prefix_len..prefix_len + new.len(),
+        }];
+    }
+
+    let mut matches = Vec::new();
+    push_lcs_matches(old, new, 0, 0, &mut matches);
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (match_i, match_j) in matches.into_iter().chain([(old.len(), new.len())]) {
+        if i < match_i || j < match_j {
+            hunks.push(Hunk {
+                old_lines: prefix_len + i..prefix_len + match_i,
+                new_lines: prefix_len + j..prefix_len + match_j,
+            });
+        }
+        i = match_i + 1;
+        j = match_j + 1;
+    }
+    hunks
+}
+
+// Pushes the indices of the line pairs of a longest common subsequence of `old` and `new` to
+// `matches`, in order, offset by `old_start` and `new_start`. This is Hirschberg's algorithm: the
+// middle line of `old` is matched up with the place in `new` where the subsequences of both halves
+// are longest together, so that only a single row of lengths is needed at a time.
+fn push_lcs_matches<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    old_start: usize,
+    new_start: usize,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    if old.is_empty() || new.is_empty() {
+        return;
+    }
+    if old.len() == 1 {
+        if let Some(j) = new.iter().position(|new_line| *new_line == old[0]) {
+            matches.push((old_start, new_start + j));
+        }
+        return;
+    }
+    let mid = old.len() / 2;
+    let forward_lens = lcs_lens(old[..mid].iter(), new.iter());
+    let backward_lens = lcs_lens(old[mid..].iter().rev(), new.iter().rev());
+    let split = (0..=new.len())
+        .max_by_key(|&j| forward_lens[j] + backward_lens[new.len() - j])
+        .unwrap();
+    push_lcs_matches(&old[..mid], &new[..split], old_start, new_start, matches);
+    push_lcs_matches(
+        &old[mid..],
+        &new[split..],
+        old_start + mid,
+        new_start + split,
+        matches,
+    );
+}
+
+// Returns the lengths of the longest common subsequences of `old` and each prefix of `new`,
+// indexed by the length of the prefix.
+fn lcs_lens<'a, T: PartialEq + 'a>(
+    old: impl Iterator<Item = &'a T>,
+    new: impl Iterator<Item = &'a T> + Clone,
+) -> Vec<usize> {
+    let mut lens = vec![0; new.clone().count() + 1];
+    for old_line in old {
+        // The length for the previous prefix of `new`, before it was updated for `old_line`.
+        let mut prev_len = 0;
+        for (j, new_line) in new.clone().enumerate() {
+            let len = lens[j + 1];
+            lens[j + 1] = if old_line == new_line {
+                prev_len + 1
+            } else {
+                len.max(lens[j])
+            };
+            prev_len = len;
+        }
+    }
+    lens
+}
+
+/// A part of the old text, from `start` to `end`, that has to be replaced by `text`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Replacement {
+    pub start: Position,
+    pub end: Position,
+    pub text: Text,
+}
+
+/// Returns the replacements that turn `old` into `new`, in order. Only lines that differ are
+/// replaced, and no two replacements touch each other.
+pub fn diff(old: &Text, new: &Text) -> Vec<Replacement> {
+    let old_lines = old.as_lines();
+    let new_lines: Vec<&String> = new.as_lines().iter().collect();
+    let mut replacements: Vec<Replacement> = Vec::new();
+    for hunk in diff_lines(&old_lines.iter().collect::<Vec<_>>(), &new_lines) {
+        let replacement = if hunk.old_lines.end < old_lines.len() {
+            // There is a line after the hunk, so we can replace whole lines including their
+            // newlines.
+            let mut string = String::new();
+            for line in &new_lines[hunk.new_lines.clone()] {
+                string.push_str(line);
+                string.push('\n');
+            }
+            Replacement {
+                start: Position {
+                    line_index: hunk.old_lines.start,
+                    byte_index: 0,
+                },
+                end: Position {
+                    line_index: hunk.old_lines.end,
+                    byte_index: 0,
+                },
+                text: string.into(),
+            }
+        } else if hunk.old_lines.start > 0 && hunk.new_lines.start > 0 {
+            // The hunk extends to the end of the text, so we replace everything from the newline
+            // before it instead.
+            let line_index = hunk.old_lines.start - 1;
+            let mut string = String::new();
+            for line in &new_lines[hunk.new_lines.clone()] {
+                string.push('\n');
+                string.push_str(line);
+            }
+            Replacement {
+                start: Position {
+                    line_index,
+                    byte_index: old_lines[line_index].len(),
+                },
+                end: Position::zero() + old.length(),
+                text: string.into(),
+            }
+        } else {
+            Replacement {
+                start: Position::zero(),
+                end: Position::zero() + old.length(),
+                text: new.clone(),
+            }
+        };
+        match replacements.last_mut() {
+            Some(last_replacement) if last_replacement.end >= replacement.start => {
+                last_replacement.end = replacement.end;
+                last_replacement.text.apply_change(Change::Insert(
+                    Position::zero() + last_replacement.text.length(),
+                    replacement.text,
+                ));
+            }
+            _ => replacements.push(replacement),
+        }
+    }
+    replacements
+}
@@ -1,15 +1,28 @@
-use crate::{
-    selection::SelectionSet,
-    session::SessionId,
-    text::{Edit, Text},
+use {
+    crate::{
+        selection::SelectionSet,
+        session::SessionId,
+        text::{Change, Edit, Position, Text},
+    },
+    makepad_widgets::makepad_micro_serde::*,
+    std::time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+/// The edit history of a document.
+///
+/// The history is a tree of revisions rather than a stack. Undoing some edits and then making a
+/// new edit starts a new branch, so the undone edits are never lost, and any earlier state of the
+/// document can be returned to with `jump_to`.
+///
+/// Revision 0 is the root of the tree, and represents the text the history was created with. Each
+/// other revision stores the edits that turn the text of its parent into its own text, together
+/// with their inverses.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct History {
     text: Text,
     current_desc: Option<GroupDesc>,
-    undo_stack: Stack,
-    redo_stack: Stack,
+    revisions: Vec<Revision>,
+    current_revision: usize,
 }
 
 impl History {
@@ -21,6 +34,22 @@ impl History {
         &self.text
     }
 
+    pub fn revisions(&self) -> &[Revision] {
+        &self.revisions
+    }
+
+    pub fn current_revision(&self) -> usize {
+        self.current_revision
+    }
+
+    /// Returns the last revision that was created at or before `time`.
+    pub fn revision_at(&self, time: SystemTime) -> usize {
+        self.revisions
+            .iter()
+            .rposition(|revision| revision.time() <= time)
+            .unwrap_or(0)
+    }
+
     pub fn force_new_group(&mut self) {
         self.current_desc = None;
     }
@@ -39,59 +68,223 @@ impl History {
             .current_desc
             .map_or(false, |current_desc| current_desc.can_merge_with(desc))
         {
-            self.undo_stack.push_group(selections.clone());
+            self.push_revision(selections.clone());
             self.current_desc = Some(desc);
         }
     }
 
+    /// Starts a new revision, even if the edits that follow could be merged into the current
+    /// one.
+    pub fn push_group(&mut self, selections: &SelectionSet) {
+        self.push_revision(selections.clone());
+        self.current_desc = None;
+    }
+
     pub fn apply_edit(&mut self, edit: Edit) {
+        if self.current_revision == 0 {
+            self.push_revision(SelectionSet::new());
+        }
         let inverted_edit = edit.clone().invert(&self.text);
-        self.text.apply_change(edit.change);
-        self.undo_stack.push_edit(inverted_edit);
-        self.redo_stack.clear();
+        self.text.apply_change(edit.change.clone());
+        let revision = &mut self.revisions[self.current_revision];
+        revision.edits.push(edit);
+        revision.inverted_edits.push(inverted_edit);
     }
 
+    /// Moves to the parent of the current revision. The edits that this makes are appended to
+    /// `edits`, and the selections from before the undone revision are returned.
     pub fn undo(
         &mut self,
         selections: &SelectionSet,
         edits: &mut Vec<Edit>,
     ) -> Option<SelectionSet> {
-        if let Some(new_selections) = self.undo_stack.pop_group(edits) {
-            self.redo_stack.push_group(selections.clone());
-            for edit in edits {
-                let inverted_edit = edit.clone().invert(&self.text);
-                self.text.apply_change(edit.change.clone());
-                self.redo_stack.push_edit(inverted_edit);
-            }
-            self.current_desc = None;
-            Some(new_selections)
-        } else {
-            None
+        let index = self.current_revision;
+        let parent_index = self.revisions[index].parent?;
+        let revision = &mut self.revisions[index];
+        revision.selections_after = Some(selections.clone());
+        let edit_start = edits.len();
+        edits.extend(revision.inverted_edits.iter().rev().cloned());
+        for edit in &edits[edit_start..] {
+            self.text.apply_change(edit.change.clone());
         }
+        self.revisions[parent_index].redo_child = Some(index);
+        self.current_revision = parent_index;
+        self.current_desc = None;
+        Some(self.revisions[index].selections_before.clone())
     }
 
-    pub fn redo(
+    /// Moves to the child of the current revision that was most recently undone or created. The
+    /// edits that this makes are appended to `edits`, and the selections from before the revision
+    /// was undone are returned.
+    pub fn redo(&mut self, edits: &mut Vec<Edit>) -> Option<SelectionSet> {
+        let index = self.revisions[self.current_revision].redo_child?;
+        let revision = &self.revisions[index];
+        let edit_start = edits.len();
+        edits.extend(revision.edits.iter().cloned());
+        for edit in &edits[edit_start..] {
+            self.text.apply_change(edit.change.clone());
+        }
+        self.current_revision = index;
+        self.current_desc = None;
+        let revision = &self.revisions[index];
+        Some(
+            revision
+                .selections_after
+                .clone()
+                .unwrap_or_else(|| revision.selections_before.clone()),
+        )
+    }
+
+    /// Moves to the given revision, which can be on any branch, by undoing up to the closest
+    /// common ancestor and then redoing down from there. Returns `None` if the revision does not
+    /// exist or is the current revision.
+    pub fn jump_to(
         &mut self,
+        revision: usize,
         selections: &SelectionSet,
         edits: &mut Vec<Edit>,
     ) -> Option<SelectionSet> {
-        if let Some(new_selections) = self.redo_stack.pop_group(edits) {
-            self.undo_stack.push_group(selections.clone());
-            for edit in edits {
-                let inverted_edit = edit.clone().invert(&self.text);
-                self.text.apply_change(edit.change.clone());
-                self.undo_stack.push_edit(inverted_edit);
+        if revision >= self.revisions.len() || revision == self.current_revision {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut index = Some(revision);
+        while let Some(current_index) = index {
+            path.push(current_index);
+            index = self.revisions[current_index].parent;
+        }
+        let mut selections = selections.clone();
+        while !path.contains(&self.current_revision) {
+            selections = self.undo(&selections, edits).unwrap();
+        }
+        let ancestor_index = path
+            .iter()
+            .position(|&index| index == self.current_revision)
+            .unwrap();
+        for &index in path[..ancestor_index].iter().rev() {
+            self.revisions[self.current_revision].redo_child = Some(index);
+            selections = self.redo(edits).unwrap();
+        }
+        Some(selections)
+    }
+
+    /// Applies an edit that was made by another participant to the current text. Unlike edits
+    /// made with `apply_edit`, it is not part of any revision, so undo and redo leave it alone.
+    /// Instead, the edits of every revision are moved past it. Revisions with edits that overlap
+    /// it can't be moved, so they are dropped, together with the revisions that can only be
+    /// reached through them.
+    pub fn apply_remote_edit(&mut self, edit: Edit) {
+        // The remote edit as it applies to the text of each revision that is kept.
+        let mut remote_edits: Vec<Option<Edit>> = vec![None; self.revisions.len()];
+        remote_edits[self.current_revision] = Some(edit.clone());
+
+        // Move the revisions from the current one up to the root past the remote edit. If one of
+        // them overlaps it, that one becomes the new root.
+        let mut root_index = self.current_revision;
+        let mut remote_edit = edit.clone();
+        while let Some(parent_index) = self.revisions[root_index].parent {
+            let revision = &self.revisions[root_index];
+            let mut inverted_edits = revision.inverted_edits.clone();
+            let mut parent_remote_edit = remote_edit.clone();
+            if !transform_edits(inverted_edits.iter_mut().rev(), &mut parent_remote_edit) {
+                break;
             }
-            self.current_desc = None;
-            Some(new_selections)
-        } else {
-            None
+            let revision = &mut self.revisions[root_index];
+            revision.edits = uninvert_edits(&revision.edits, &inverted_edits);
+            revision.inverted_edits = inverted_edits;
+            transform_selections(revision, &parent_remote_edit, &remote_edit);
+            remote_edits[parent_index] = Some(parent_remote_edit.clone());
+            remote_edit = parent_remote_edit;
+            root_index = parent_index;
         }
+
+        // Move the other branches past the remote edit, starting from where they leave the path
+        // to the current revision. Parents always come before their children.
+        for index in root_index + 1..self.revisions.len() {
+            if remote_edits[index].is_some() {
+                continue;
+            }
+            let Some(parent_remote_edit) = self.revisions[index]
+                .parent
+                .and_then(|parent_index| remote_edits[parent_index].clone())
+            else {
+                continue;
+            };
+            let revision = &mut self.revisions[index];
+            let mut edits = revision.edits.clone();
+            let mut remote_edit = parent_remote_edit.clone();
+            if !transform_edits(edits.iter_mut(), &mut remote_edit) {
+                continue;
+            }
+            revision.inverted_edits = uninvert_edits(&revision.inverted_edits, &edits);
+            revision.edits = edits;
+            transform_selections(revision, &parent_remote_edit, &remote_edit);
+            remote_edits[index] = Some(remote_edit);
+        }
+
+        // Drop the revisions that couldn't be moved.
+        let mut new_indices = vec![None; self.revisions.len()];
+        let mut new_revisions = Vec::new();
+        for (index, revision) in self.revisions.drain(..).enumerate() {
+            if remote_edits[index].is_some() {
+                new_indices[index] = Some(new_revisions.len());
+                new_revisions.push(revision);
+            }
+        }
+        for revision in &mut new_revisions {
+            revision.parent = revision.parent.and_then(|parent| new_indices[parent]);
+            revision.redo_child = revision.redo_child.and_then(|child| new_indices[child]);
+        }
+        let root = &mut new_revisions[0];
+        root.parent = None;
+        root.edits.clear();
+        root.inverted_edits.clear();
+        self.revisions = new_revisions;
+        self.current_revision = new_indices[self.current_revision].unwrap();
+        self.current_desc = None;
+        self.text.apply_change(edit.change);
     }
 
     pub fn into_text(self) -> Text {
         self.text
     }
+
+    fn push_revision(&mut self, selections_before: SelectionSet) {
+        let index = self.revisions.len();
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+        self.revisions.push(Revision {
+            parent: Some(self.current_revision),
+            redo_child: None,
+            time,
+            selections_before,
+            selections_after: None,
+            edits: Vec::new(),
+            inverted_edits: Vec::new(),
+        });
+        self.revisions[self.current_revision].redo_child = Some(index);
+        self.current_revision = index;
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            text: Text::default(),
+            current_desc: None,
+            revisions: vec![Revision {
+                parent: None,
+                redo_child: None,
+                time: 0,
+                selections_before: SelectionSet::new(),
+                selections_after: None,
+                edits: Vec::new(),
+                inverted_edits: Vec::new(),
+            }],
+            current_revision: 0,
+        }
+    }
 }
 
 impl From<Text> for History {
@@ -103,6 +296,191 @@ impl From<Text> for History {
     }
 }
 
+// The current group is not serialized, so edits after deserializing always start a new revision.
+impl SerBin for History {
+    fn ser_bin(&self, s: &mut Vec<u8>) {
+        self.text.ser_bin(s);
+        self.revisions.ser_bin(s);
+        self.current_revision.ser_bin(s);
+    }
+}
+
+impl DeBin for History {
+    fn de_bin(o: &mut usize, d: &[u8]) -> Result<Self, DeBinErr> {
+        let text: Text = DeBin::de_bin(o, d)?;
+        let revisions: Vec<Revision> = DeBin::de_bin(o, d)?;
+        let current_revision: usize = DeBin::de_bin(o, d)?;
+        let is_valid = |index: Option<usize>| index.is_none_or(|index| index < revisions.len());
+        if current_revision >= revisions.len()
+            || revisions.first().is_none_or(|root| root.parent.is_some())
+            || !revisions.iter().enumerate().all(|(index, revision)| {
+                revision.parent.map_or(index == 0, |parent| parent < index)
+                    && is_valid(revision.redo_child)
+            })
+        {
+            return Err(DeBinErr {
+                msg: "Invalid history".into(),
+                o: *o,
+                l: 0,
+                s: d.len(),
+            });
+        }
+        Ok(Self {
+            text,
+            current_desc: None,
+            revisions,
+            current_revision,
+        })
+    }
+}
+
+// Transforms each of `edits` in turn, which are applied one after the other, and `remote_edit`,
+// which applies to the same text as the first of them, so that `remote_edit` ends up applying to
+// the text after the last of them. Returns `false` if one of them overlaps `remote_edit`.
+fn transform_edits<'a>(edits: impl Iterator<Item = &'a mut Edit>, remote_edit: &mut Edit) -> bool {
+    for edit in edits {
+        let Some((change, remote_change)) = transform_changes(&edit.change, &remote_edit.change)
+        else {
+            return false;
+        };
+        edit.change = change;
+        remote_edit.change = remote_change;
+    }
+    true
+}
+
+// Transforms two changes to the same text, so that each applies to the text after the other.
+// Where both insert at the same position, the text inserted by `change` comes first. Returns
+// `None` if the changes overlap, since there is no way to keep both intact then.
+fn transform_changes(change: &Change, remote_change: &Change) -> Option<(Change, Change)> {
+    match (change, remote_change) {
+        (Change::Insert(position, text), Change::Insert(remote_position, remote_text)) => {
+            if position <= remote_position {
+                Some((
+                    change.clone(),
+                    Change::Insert(
+                        move_past_insert(*remote_position, *position, text),
+                        remote_text.clone(),
+                    ),
+                ))
+            } else {
+                Some((
+                    Change::Insert(
+                        move_past_insert(*position, *remote_position, remote_text),
+                        text.clone(),
+                    ),
+                    remote_change.clone(),
+                ))
+            }
+        }
+        (Change::Insert(..), Change::Delete(..)) => {
+            let (remote_change, change) = transform_changes(remote_change, change)?;
+            Some((change, remote_change))
+        }
+        (Change::Delete(start, length), Change::Insert(position, text)) => {
+            let end = *start + *length;
+            if *position <= *start {
+                Some((
+                    Change::Delete(move_past_insert(*start, *position, text), *length),
+                    remote_change.clone(),
+                ))
+            } else if *position >= end {
+                Some((
+                    change.clone(),
+                    Change::Insert(move_past_delete(*position, *start, end), text.clone()),
+                ))
+            } else {
+                None
+            }
+        }
+        (Change::Delete(start, length), Change::Delete(remote_start, remote_length)) => {
+            let end = *start + *length;
+            let remote_end = *remote_start + *remote_length;
+            if end <= *remote_start {
+                Some((
+                    change.clone(),
+                    Change::Delete(move_past_delete(*remote_start, *start, end), *remote_length),
+                ))
+            } else if remote_end <= *start {
+                Some((
+                    Change::Delete(move_past_delete(*start, *remote_start, remote_end), *length),
+                    remote_change.clone(),
+                ))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn move_past_insert(position: Position, insert_position: Position, text: &Text) -> Position {
+    insert_position + text.length() + (position - insert_position)
+}
+
+fn move_past_delete(position: Position, start: Position, end: Position) -> Position {
+    start + (position - end)
+}
+
+// Given the edits or inverted edits of a revision and their transformed counterparts, returns
+// the other ones transformed the same way. Since the edits don't overlap anything, only their
+// positions change.
+fn uninvert_edits(edits: &[Edit], transformed_counterparts: &[Edit]) -> Vec<Edit> {
+    edits
+        .iter()
+        .zip(transformed_counterparts)
+        .map(|(edit, counterpart)| {
+            let position = match counterpart.change {
+                Change::Insert(position, _) | Change::Delete(position, _) => position,
+            };
+            let change = match &edit.change {
+                Change::Insert(_, text) => Change::Insert(position, text.clone()),
+                Change::Delete(_, length) => Change::Delete(position, *length),
+            };
+            Edit {
+                change,
+                drift: edit.drift,
+            }
+        })
+        .collect()
+}
+
+fn transform_selections(revision: &mut Revision, parent_remote_edit: &Edit, remote_edit: &Edit) {
+    revision
+        .selections_before
+        .apply_edit(parent_remote_edit, None);
+    if let Some(selections_after) = &mut revision.selections_after {
+        selections_after.apply_edit(remote_edit, None);
+    }
+}
+
+/// A node in the revision tree of a `History`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub struct Revision {
+    parent: Option<usize>,
+    redo_child: Option<usize>,
+    // Milliseconds since the Unix epoch.
+    time: u64,
+    selections_before: SelectionSet,
+    selections_after: Option<SelectionSet>,
+    edits: Vec<Edit>,
+    inverted_edits: Vec<Edit>,
+}
+
+impl Revision {
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// The time at which the first edit of this revision was made.
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.time)
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum EditKind {
     Insert,
@@ -110,12 +488,17 @@ pub enum EditKind {
     Delete,
     Indent,
     Outdent,
+    /// Replaces lines with their formatted version. The result is never autoindented.
+    Format,
     Other,
+    /// Replaces the preedit text of an IME composition. All edits of a composition, including
+    /// its commit, are merged into a single revision.
+    Composition,
 }
 
 impl EditKind {
     fn can_merge_with(self, other: Self) -> bool {
-        if self == Self::Other {
+        if self == Self::Format || self == Self::Other {
             return false;
         }
         self == other
@@ -133,43 +516,3 @@ impl GroupDesc {
         self.session_id == other.session_id && self.edit_kind.can_merge_with(other.edit_kind)
     }
 }
-
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
-struct Stack {
-    groups: Vec<Group>,
-    edits: Vec<Edit>,
-}
-
-impl Stack {
-    fn push_group(&mut self, selections: SelectionSet) {
-        self.groups.push(Group {
-            selections,
-            edit_start: self.edits.len(),
-        });
-    }
-
-    fn push_edit(&mut self, edit: Edit) {
-        self.edits.push(edit);
-    }
-
-    fn pop_group(&mut self, edits: &mut Vec<Edit>) -> Option<SelectionSet> {
-        match self.groups.pop() {
-            Some(group) => {
-                edits.extend(self.edits.drain(group.edit_start..).rev());
-                Some(group.selections)
-            }
-            None => None,
-        }
-    }
-
-    fn clear(&mut self) {
-        self.groups.clear();
-        self.edits.clear();
-    }
-}
-
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-struct Group {
-    selections: SelectionSet,
-    edit_start: usize,
-}
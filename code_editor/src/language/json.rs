@@ -0,0 +1,122 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, Language},
+};
+
+/// JSON, with support for the comments that most JSON config files end up containing anyway.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Json;
+
+impl Language for Json {
+    type State = State;
+
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn next(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => initial(cursor),
+            State::BlockCommentTail => block_comment_tail(cursor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    BlockCommentTail,
+}
+
+fn initial(cursor: &mut Cursor) -> (State, TokenKind) {
+    match (cursor.peek(0), cursor.peek(1)) {
+        ('/', '/') => {
+            while cursor.skip_if(|char| char != '\0') {}
+            (State::Initial, TokenKind::Comment)
+        }
+        ('/', '*') => {
+            cursor.skip(2);
+            block_comment_tail(cursor)
+        }
+        ('"', _) => string_or_key(cursor),
+        ('[', _) | (']', _) | ('{', _) | ('}', _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Delimiter)
+        }
+        (':', _) | (',', _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        ('-', char) | (char, _) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_ascii_alphabetic() => keyword(cursor),
+        (char, _) if char.is_whitespace() => {
+            while cursor.skip_if(|char| char.is_whitespace()) {}
+            (State::Initial, TokenKind::Whitespace)
+        }
+        _ => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+fn block_comment_tail(cursor: &mut Cursor) -> (State, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('*', '/') => {
+                cursor.skip(2);
+                break (State::Initial, TokenKind::Comment);
+            }
+            ('\0', _) => break (State::BlockCommentTail, TokenKind::Comment),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+/// Strings that are followed by a `:` are object keys, and are highlighted as identifiers.
+fn string_or_key(cursor: &mut Cursor) -> (State, TokenKind) {
+    debug_assert!(cursor.peek(0) == '"');
+    cursor.skip(1);
+    loop {
+        match cursor.peek(0) {
+            '"' => {
+                cursor.skip(1);
+                break;
+            }
+            '\0' => return (State::Initial, TokenKind::Unknown),
+            '\\' => cursor.skip(2),
+            _ => cursor.skip(1),
+        }
+    }
+    if cursor.rest().trim_start().starts_with(':') {
+        (State::Initial, TokenKind::Identifier)
+    } else {
+        (State::Initial, TokenKind::String)
+    }
+}
+
+fn number(cursor: &mut Cursor) -> (State, TokenKind) {
+    cursor.skip_if(|char| char == '-');
+    cursor.skip_digits(10);
+    if cursor.peek(0) == '.' {
+        cursor.skip(1);
+        cursor.skip_digits(10);
+    }
+    if (cursor.peek(0) == 'e' || cursor.peek(0) == 'E') && !cursor.skip_exponent() {
+        return (State::Initial, TokenKind::Unknown);
+    }
+    (State::Initial, TokenKind::Number)
+}
+
+fn keyword(cursor: &mut Cursor) -> (State, TokenKind) {
+    let start = cursor.index();
+    while cursor.skip_if(|char| char.is_ascii_alphanumeric()) {}
+    (
+        State::Initial,
+        match cursor.slice(start, cursor.index()) {
+            "true" | "false" | "null" => TokenKind::OtherKeyword,
+            _ => TokenKind::Unknown,
+        },
+    )
+}
@@ -0,0 +1,181 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{CharExt, Cursor, Language},
+};
+
+/// The language inside `live_design!` blocks, including the shader code embedded in it. Since the
+/// shader language is close to GLSL, this is also used for standalone shader files.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LiveDesign;
+
+impl Language for LiveDesign {
+    type State = State;
+
+    fn name(&self) -> &'static str {
+        "live_design"
+    }
+
+    fn next(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => initial(cursor),
+            State::BlockCommentTail { depth } => block_comment_tail(cursor, depth),
+            State::StringTail => string_tail(cursor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    BlockCommentTail {
+        depth: usize,
+    },
+    StringTail,
+}
+
+fn initial(cursor: &mut Cursor) -> (State, TokenKind) {
+    match (cursor.peek(0), cursor.peek(1)) {
+        ('/', '/') => {
+            while cursor.skip_if(|char| char != '\0') {}
+            (State::Initial, TokenKind::Comment)
+        }
+        ('/', '*') => {
+            cursor.skip(2);
+            block_comment_tail(cursor, 0)
+        }
+        ('"', _) => {
+            cursor.skip(1);
+            string_tail(cursor)
+        }
+        ('#', char) if char.is_ascii_hexdigit() || char == 'x' => color(cursor),
+        ('{', '{') | ('}', '}') => {
+            cursor.skip(2);
+            (State::Initial, TokenKind::Delimiter)
+        }
+        ('(', _) | (')', _) | ('[', _) | (']', _) | ('{', _) | ('}', _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Delimiter)
+        }
+        ('-', '>')
+        | (':', ':')
+        | ('=', '=')
+        | ('!', '=')
+        | ('<', '=')
+        | ('>', '=')
+        | ('&', '&')
+        | ('|', '|')
+        | ('+', '=')
+        | ('-', '=')
+        | ('*', '=')
+        | ('/', '=') => {
+            cursor.skip(2);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        ('.', char) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_identifier_start() => identifier_or_keyword(cursor),
+        (char, _) if char.is_whitespace() => {
+            while cursor.skip_if(|char| char.is_whitespace()) {}
+            (State::Initial, TokenKind::Whitespace)
+        }
+        (char, _) if char.is_ascii_punctuation() => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        _ => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+fn block_comment_tail(cursor: &mut Cursor, depth: usize) -> (State, TokenKind) {
+    let mut depth = depth;
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '*') => {
+                cursor.skip(2);
+                depth += 1;
+            }
+            ('*', '/') => {
+                cursor.skip(2);
+                if depth == 0 {
+                    break (State::Initial, TokenKind::Comment);
+                }
+                depth -= 1;
+            }
+            ('\0', _) => break (State::BlockCommentTail { depth }, TokenKind::Comment),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+fn string_tail(cursor: &mut Cursor) -> (State, TokenKind) {
+    loop {
+        match cursor.peek(0) {
+            '"' => {
+                cursor.skip(1);
+                break (State::Initial, TokenKind::String);
+            }
+            '\0' => break (State::StringTail, TokenKind::String),
+            '\\' => cursor.skip(2),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+/// Colors are written as `#f0f`, `#ff00ff`, `#ff00ff80`, or `#x...` when the hex digits would
+/// otherwise start with a letter that looks like an identifier.
+fn color(cursor: &mut Cursor) -> (State, TokenKind) {
+    debug_assert!(cursor.peek(0) == '#');
+    cursor.skip(1);
+    cursor.skip_if(|char| char == 'x');
+    while cursor.skip_if(|char| char.is_ascii_hexdigit()) {}
+    (State::Initial, TokenKind::Number)
+}
+
+fn number(cursor: &mut Cursor) -> (State, TokenKind) {
+    cursor.skip_digits(10);
+    if cursor.peek(0) == '.' && cursor.peek(1) != '.' {
+        cursor.skip(1);
+        cursor.skip_digits(10);
+    }
+    if (cursor.peek(0) == 'e' || cursor.peek(0) == 'E') && !cursor.skip_exponent() {
+        return (State::Initial, TokenKind::Unknown);
+    }
+    (State::Initial, TokenKind::Number)
+}
+
+fn identifier_or_keyword(cursor: &mut Cursor) -> (State, TokenKind) {
+    let start = cursor.index();
+    cursor.skip(1);
+    while cursor.skip_if(|char| char.is_identifier_continue()) {}
+    let string = cursor.slice(start, cursor.index());
+    (
+        State::Initial,
+        match string {
+            "if" | "else" | "return" | "match" => TokenKind::BranchKeyword,
+            "for" | "while" | "loop" | "break" | "continue" => TokenKind::LoopKeyword,
+            "import" | "fn" | "let" | "var" | "const" | "struct" | "impl" | "self" | "Self"
+            | "use" | "pub" | "true" | "false" | "instance" | "uniform" | "varying"
+            | "texture" | "geometry" | "in" | "out" | "inout" | "float" | "int" | "uint"
+            | "bool" | "vec2" | "vec3" | "vec4" | "ivec2" | "ivec3" | "ivec4" | "bvec2"
+            | "bvec3" | "bvec4" | "mat2" | "mat3" | "mat4" | "texture2d" | "textureOES"
+            | "sampler2D" | "void" => TokenKind::OtherKeyword,
+            _ => {
+                let mut chars = string.chars();
+                if chars.next().unwrap().is_uppercase() {
+                    match chars.next() {
+                        Some(char) if char.is_uppercase() => TokenKind::Constant,
+                        _ => TokenKind::Typename,
+                    }
+                } else if cursor.peek(0) == '(' {
+                    TokenKind::Function
+                } else {
+                    TokenKind::Identifier
+                }
+            }
+        },
+    )
+}
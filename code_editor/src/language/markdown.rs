@@ -0,0 +1,203 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, Language},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Markdown;
+
+impl Language for Markdown {
+    type State = State;
+
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn next(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => initial(cursor),
+            State::FencedCodeBlock(fence) => fenced_code_block(cursor, fence),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    FencedCodeBlock(Fence),
+}
+
+/// The opening line of a fenced code block, which the closing line has to match.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Fence {
+    char: char,
+    count: usize,
+}
+
+fn initial(cursor: &mut Cursor) -> (State, TokenKind) {
+    if is_at_line_start(cursor) {
+        if let Some(fence) = fence(cursor.rest()) {
+            // The rest of the opening line is the info string, which we treat as part of the fence.
+            while cursor.skip_if(|char| char != '\0') {}
+            return (State::FencedCodeBlock(fence), TokenKind::Delimiter);
+        }
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('#', _) => {
+                while cursor.skip_if(|char| char != '\0') {}
+                return (State::Initial, TokenKind::Typename);
+            }
+            ('>', _) => {
+                cursor.skip(1);
+                return (State::Initial, TokenKind::Comment);
+            }
+            ('-', ' ') | ('*', ' ') | ('+', ' ') => {
+                cursor.skip(1);
+                return (State::Initial, TokenKind::Punctuator);
+            }
+            (char, _) if char.is_ascii_digit() => {
+                cursor.skip_digits(10);
+                if cursor.skip_if(|char| char == '.' || char == ')') {
+                    return (State::Initial, TokenKind::Punctuator);
+                }
+                return (State::Initial, TokenKind::Unknown);
+            }
+            _ => {}
+        }
+    }
+    match (cursor.peek(0), cursor.peek(1)) {
+        ('`', _) => code_span(cursor),
+        ('_', _) if is_inside_word(cursor) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Unknown)
+        }
+        ('*', '*') | ('_', '_') => emphasis(cursor, 2),
+        ('*', char) | ('_', char) if !char.is_whitespace() && char != '\0' => emphasis(cursor, 1),
+        ('!', '[') | ('[', _) => link(cursor),
+        ('\\', _) => {
+            cursor.skip(2);
+            (State::Initial, TokenKind::Unknown)
+        }
+        (char, _) if char.is_whitespace() => {
+            while cursor.skip_if(|char| char.is_whitespace()) {}
+            (State::Initial, TokenKind::Whitespace)
+        }
+        _ => {
+            cursor.skip(1);
+            while cursor.skip_if(|char| !is_special_char(char)) {}
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+fn fenced_code_block(cursor: &mut Cursor, open_fence: Fence) -> (State, TokenKind) {
+    if is_at_line_start(cursor) {
+        if let Some(fence) = fence(cursor.rest()) {
+            if fence.char == open_fence.char
+                && fence.count >= open_fence.count
+                && cursor.rest().trim().len() == fence.count
+            {
+                while cursor.skip_if(|char| char != '\0') {}
+                return (State::Initial, TokenKind::Delimiter);
+            }
+        }
+    }
+    while cursor.skip_if(|char| char != '\0') {}
+    (State::FencedCodeBlock(open_fence), TokenKind::String)
+}
+
+fn is_at_line_start(cursor: &Cursor) -> bool {
+    cursor.slice(0, cursor.index()).trim().is_empty()
+}
+
+/// Underscores inside words, as in `snake_case`, don't start emphasis.
+fn is_inside_word(cursor: &Cursor) -> bool {
+    cursor
+        .slice(0, cursor.index())
+        .chars()
+        .next_back()
+        .is_some_and(|char| char.is_alphanumeric())
+}
+
+fn fence(string: &str) -> Option<Fence> {
+    let string = string.trim_start();
+    let char = string.chars().next()?;
+    if char != '`' && char != '~' {
+        return None;
+    }
+    let count = string.chars().take_while(|&other| other == char).count();
+    if count < 3 {
+        return None;
+    }
+    Some(Fence { char, count })
+}
+
+fn code_span(cursor: &mut Cursor) -> (State, TokenKind) {
+    let start = cursor.index();
+    while cursor.skip_if(|char| char == '`') {}
+    let ticks = cursor.slice(start, cursor.index());
+    match cursor.rest().find(ticks) {
+        Some(index) => {
+            skip_to(cursor, cursor.index() + index + ticks.len());
+            (State::Initial, TokenKind::String)
+        }
+        None => (State::Initial, TokenKind::Unknown),
+    }
+}
+
+fn emphasis(cursor: &mut Cursor, count: usize) -> (State, TokenKind) {
+    let delimiter = &cursor.rest()[..count];
+    match cursor.rest()[count..].find(delimiter) {
+        Some(index) if index > 0 => {
+            skip_to(cursor, cursor.index() + count + index + count);
+            (
+                State::Initial,
+                if count == 2 {
+                    TokenKind::OtherKeyword
+                } else {
+                    TokenKind::Constant
+                },
+            )
+        }
+        _ => {
+            cursor.skip(count);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+/// Links and images, which are highlighted as a whole up to the closing parenthesis.
+fn link(cursor: &mut Cursor) -> (State, TokenKind) {
+    let rest = cursor.rest();
+    let text_start = if rest.starts_with('!') { 2 } else { 1 };
+    let len = rest[text_start..].find(']').and_then(|index| {
+        let url_start = text_start + index + 1;
+        if !rest[url_start..].starts_with('(') {
+            return None;
+        }
+        rest[url_start..].find(')').map(|index| url_start + index + 1)
+    });
+    match len {
+        Some(len) => {
+            skip_to(cursor, cursor.index() + len);
+            (State::Initial, TokenKind::Function)
+        }
+        None => {
+            cursor.skip(text_start);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+fn skip_to(cursor: &mut Cursor, index: usize) {
+    while cursor.index() < index {
+        cursor.skip(1);
+    }
+}
+
+fn is_special_char(char: char) -> bool {
+    match char {
+        '`' | '*' | '_' | '[' | '!' | '\\' | '\0' => true,
+        char => char.is_whitespace(),
+    }
+}
@@ -0,0 +1,7 @@
+pub mod json;
+pub mod live_design;
+pub mod markdown;
+pub mod rust;
+pub mod toml;
+
+pub use self::{json::Json, live_design::LiveDesign, markdown::Markdown, rust::Rust, toml::Toml};
@@ -0,0 +1,173 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{CharExt, Cursor, Language},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Toml;
+
+impl Language for Toml {
+    type State = State;
+
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn next(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => initial(cursor),
+            State::MultiLineBasicStringTail => multi_line_string_tail(cursor, '"'),
+            State::MultiLineLiteralStringTail => multi_line_string_tail(cursor, '\''),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    MultiLineBasicStringTail,
+    MultiLineLiteralStringTail,
+}
+
+fn initial(cursor: &mut Cursor) -> (State, TokenKind) {
+    match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+        ('#', _, _) => {
+            while cursor.skip_if(|char| char != '\0') {}
+            (State::Initial, TokenKind::Comment)
+        }
+        ('"', '"', '"') => {
+            cursor.skip(3);
+            multi_line_string_tail(cursor, '"')
+        }
+        ('\'', '\'', '\'') => {
+            cursor.skip(3);
+            multi_line_string_tail(cursor, '\'')
+        }
+        ('"', _, _) | ('\'', _, _) => string(cursor),
+        ('[', _, _) if is_at_table_header(cursor) => table_header(cursor),
+        ('[', _, _) | (']', _, _) | ('{', _, _) | ('}', _, _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Delimiter)
+        }
+        ('=', _, _) | (',', _, _) | ('.', _, _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        ('+', char, _) | ('-', char, _) if char.is_ascii_digit() => {
+            cursor.skip(1);
+            number_or_date(cursor)
+        }
+        (char, _, _) if char.is_ascii_digit() => number_or_date(cursor),
+        (char, _, _) if is_bare_key_char(char) => bare_key_or_keyword(cursor),
+        (char, _, _) if char.is_whitespace() => {
+            while cursor.skip_if(|char| char.is_whitespace()) {}
+            (State::Initial, TokenKind::Whitespace)
+        }
+        _ => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+/// A `[` is the start of a table header if only whitespace precedes it on the line.
+fn is_at_table_header(cursor: &Cursor) -> bool {
+    cursor.slice(0, cursor.index()).trim().is_empty()
+}
+
+fn table_header(cursor: &mut Cursor) -> (State, TokenKind) {
+    debug_assert!(cursor.peek(0) == '[');
+    loop {
+        match cursor.peek(0) {
+            ']' => {
+                while cursor.skip_if(|char| char == ']') {}
+                break;
+            }
+            '#' | '\0' => break,
+            _ => cursor.skip(1),
+        }
+    }
+    (State::Initial, TokenKind::Typename)
+}
+
+fn string(cursor: &mut Cursor) -> (State, TokenKind) {
+    let quote = cursor.peek(0);
+    cursor.skip(1);
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            (char, _) if char == quote => {
+                cursor.skip(1);
+                break;
+            }
+            ('\0', _) => return (State::Initial, TokenKind::Unknown),
+            ('\\', _) if quote == '"' => cursor.skip(2),
+            _ => cursor.skip(1),
+        }
+    }
+    (State::Initial, TokenKind::String)
+}
+
+fn multi_line_string_tail(cursor: &mut Cursor, quote: char) -> (State, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            (a, b, c) if a == quote && b == quote && c == quote => {
+                cursor.skip(3);
+                // Up to two additional quotes are allowed to be part of the string.
+                cursor.skip_if(|char| char == quote);
+                cursor.skip_if(|char| char == quote);
+                break (State::Initial, TokenKind::String);
+            }
+            ('\0', _, _) => {
+                break (
+                    if quote == '"' {
+                        State::MultiLineBasicStringTail
+                    } else {
+                        State::MultiLineLiteralStringTail
+                    },
+                    TokenKind::String,
+                );
+            }
+            ('\\', _, _) if quote == '"' => cursor.skip(2),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+/// Numbers and dates share a prefix, so we just consume everything that can appear in either.
+fn number_or_date(cursor: &mut Cursor) -> (State, TokenKind) {
+    while cursor.skip_if(|char| {
+        char.is_ascii_alphanumeric()
+            || char == '_'
+            || char == '.'
+            || char == ':'
+            || char == '-'
+            || char == '+'
+    }) {}
+    // A space can separate a date from a time.
+    if cursor.peek(0) == ' ' && cursor.peek(1).is_ascii_digit() && cursor.peek(3) == ':' {
+        cursor.skip(1);
+        while cursor.skip_if(|char| {
+            char.is_ascii_alphanumeric() || char == '.' || char == ':' || char == '-' || char == '+'
+        }) {}
+    }
+    (State::Initial, TokenKind::Number)
+}
+
+fn bare_key_or_keyword(cursor: &mut Cursor) -> (State, TokenKind) {
+    let start = cursor.index();
+    while cursor.skip_if(is_bare_key_char) {}
+    let string = cursor.slice(start, cursor.index());
+    (
+        State::Initial,
+        match string {
+            "true" | "false" => TokenKind::OtherKeyword,
+            "inf" | "nan" => TokenKind::Number,
+            _ => TokenKind::Identifier,
+        },
+    )
+}
+
+fn is_bare_key_char(char: char) -> bool {
+    char.is_identifier_continue() || char == '-'
+}
@@ -2,6 +2,7 @@ use {
     crate::{
         document::{DocumentLayout, IndentState},
         inlays::{BlockInlay, InlineInlay},
+        rope,
         selection::Affinity,
         session::SessionLayout,
         str::StrExt,
@@ -25,20 +26,26 @@ impl<'a> Layout<'a> {
         &self.text
     }
 
+    /// The width of the widest line that is laid out.
     pub fn width(&self) -> f64 {
         let mut width: f64 = 0.0;
-        for line in self.lines(0, self.as_text().as_lines().len()) {
+        for line in self.lines(0, self.line_ys().len()) {
             width = width.max(line.width());
         }
         width
     }
 
+    /// The height of all lines. Lines that aren't laid out yet are assumed to be a single row
+    /// high.
     pub fn height(&self) -> f64 {
-        *self.session_layout.y.last().unwrap()
+        let y = &self.session_layout.y;
+        let line_count = self.as_text().as_lines().len();
+        y.last().copied().unwrap_or(0.0) + (line_count - y.len().saturating_sub(1)) as f64
     }
 
     pub fn find_first_line_ending_after_y(&self, y: f64) -> usize {
-        match self.session_layout.y[..self.session_layout.y.len() - 1]
+        match self
+            .line_ys()
             .binary_search_by(|current_y| current_y.partial_cmp(&y).unwrap())
         {
             Ok(line) => line,
@@ -46,8 +53,10 @@ impl<'a> Layout<'a> {
         }
     }
 
+    /// Only lines that are laid out are considered, see `Session::layout_until_y`.
     pub fn find_first_line_starting_after_y(&self, y: f64) -> usize {
-        match self.session_layout.y[..self.session_layout.y.len() - 1]
+        match self
+            .line_ys()
             .binary_search_by(|current_y| current_y.partial_cmp(&y).unwrap())
         {
             Ok(line) => line + 1,
@@ -89,7 +98,7 @@ impl<'a> Layout<'a> {
             column_count: self.session_layout.column_count[start..end].iter(),
             fold: self.session_layout.fold_column[start..end].iter(),
             scale: self.session_layout.scale[start..end].iter(),
-            text: self.text.as_lines()[start..end].iter(),
+            text: self.text.as_lines().range(start..end),
             indent_state: self.document_layout.indent_state[start..end].iter(),
             tokens: self.document_layout.tokens[start..end].iter(),
             inline_inlays: self.document_layout.inline_inlays[start..end].iter(),
@@ -97,6 +106,12 @@ impl<'a> Layout<'a> {
         }
     }
 
+    // The y positions of the lines that are laid out, without the end of the last line.
+    fn line_ys(&self) -> &[f64] {
+        let y = &self.session_layout.y;
+        &y[..y.len().min(self.as_text().as_lines().len())]
+    }
+
     pub fn block_elements(&self, line_start: usize, line_end: usize) -> BlockElements<'_> {
         let mut block_inlays = self.document_layout.block_inlays.iter();
         while block_inlays
@@ -120,7 +135,7 @@ pub struct Lines<'a> {
     column_count: Iter<'a, Option<usize>>,
     fold: Iter<'a, usize>,
     scale: Iter<'a, f64>,
-    text: Iter<'a, String>,
+    text: rope::Iter<'a>,
     indent_state: Iter<'a, Option<IndentState>>,
     tokens: Iter<'a, Vec<Token>>,
     inline_inlays: Iter<'a, Vec<(usize, InlineInlay)>>,
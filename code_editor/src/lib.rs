@@ -3,12 +3,20 @@ use makepad_widgets::*;
 
 pub mod char;
 pub mod code_editor;
+pub mod completion;
 pub mod decoration;
+pub mod dirty_lines;
 pub mod document;
+pub mod find;
+pub mod find_bar;
+pub mod format;
 pub mod history;
 pub mod inlays;
 pub mod iter;
+pub mod language;
 pub mod layout;
+pub mod lsp;
+pub mod rope;
 pub mod selection;
 pub mod session;
 pub mod settings;
@@ -25,5 +33,6 @@ pub use self::{
 };
 
 pub fn live_design(cx: &mut Cx) {
+    crate::find_bar::live_design(cx);
     crate::code_editor::live_design(cx);
 }
@@ -0,0 +1,897 @@
+use {
+    crate::{
+        completion::Completion,
+        decoration::{Decoration, DecorationSet, DecorationType},
+        inlays::InlineInlay,
+        rope::Rope,
+        text::{Position, Text},
+    },
+    makepad_widgets::{makepad_micro_serde::*, Signal},
+    std::{
+        collections::HashMap,
+        io,
+        io::{BufRead, BufReader, Read, Write},
+        path::{Path, PathBuf},
+        process::{Child, Command, Stdio},
+        sync::mpsc,
+        sync::mpsc::{Receiver, Sender, TryRecvError},
+        thread,
+    },
+};
+
+/// A client for an external language server.
+///
+/// The client talks JSON-RPC to the server over a pair of byte streams, which are normally the
+/// stdin and stdout of a child process. Reading and writing happens on background threads, so
+/// none of the methods on the client ever block. Responses and notifications from the server are
+/// collected with `poll_events`, which should be called whenever `signal` is set.
+pub struct LspClient {
+    child: Option<Child>,
+    signal: Signal,
+    next_request_id: u64,
+    pending_requests: HashMap<LspRequestId, LspRequestKind>,
+    is_initialized: bool,
+    queued_messages: Vec<JsonValue>,
+    message_sender: Sender<JsonValue>,
+    message_receiver: Receiver<Result<JsonValue, String>>,
+}
+
+impl LspClient {
+    /// Spawns the language server `command` and connects to it over its stdio.
+    pub fn spawn(command: &str, args: &[String], current_dir: &Path) -> io::Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(current_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let mut client = Self::with_transport(stdout, stdin);
+        client.child = Some(child);
+        Ok(client)
+    }
+
+    /// Connects to a language server that reads messages from `writer` and writes messages to
+    /// `reader`.
+    pub fn with_transport(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> Self {
+        let signal = Signal::new();
+        let (message_sender, outgoing_receiver) = mpsc::channel::<JsonValue>();
+        let (incoming_sender, message_receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut writer = writer;
+            while let Ok(message) = outgoing_receiver.recv() {
+                let body = message.serialize_json();
+                let header = format!("Content-Length: {}\r\n\r\n", body.len());
+                if writer.write_all(header.as_bytes()).is_err()
+                    || writer.write_all(body.as_bytes()).is_err()
+                    || writer.flush().is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        thread::spawn({
+            let signal = signal.clone();
+            move || {
+                let mut reader = BufReader::new(reader);
+                loop {
+                    let message = match read_message(&mut reader) {
+                        Ok(Some(body)) => JsonValue::deserialize_json(&body)
+                            .map_err(|err| format!("{:?}", err)),
+                        Ok(None) => break,
+                        Err(err) => Err(err.to_string()),
+                    };
+                    if incoming_sender.send(message).is_err() {
+                        break;
+                    }
+                    signal.set();
+                }
+                let _ = incoming_sender.send(Err("language server closed the connection".into()));
+                signal.set();
+            }
+        });
+
+        Self {
+            child: None,
+            signal,
+            next_request_id: 0,
+            pending_requests: HashMap::new(),
+            is_initialized: false,
+            queued_messages: Vec::new(),
+            message_sender,
+            message_receiver,
+        }
+    }
+
+    /// The signal that is set whenever a message from the server is ready to be polled.
+    pub fn signal(&self) -> &Signal {
+        &self.signal
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    /// Starts the initialization handshake for a workspace rooted at `root_path`. Any
+    /// notifications and requests sent before the server has responded are queued up.
+    pub fn initialize(&mut self, root_path: &Path) -> LspRequestId {
+        let root_uri = path_to_uri(root_path);
+        let params = JsonValue::object()
+            .with("processId", std::process::id() as u64)
+            .with("rootUri", root_uri.clone())
+            .with(
+                "capabilities",
+                JsonValue::object().with(
+                    "textDocument",
+                    JsonValue::object()
+                        .with(
+                            "publishDiagnostics",
+                            JsonValue::object().with("relatedInformation", false),
+                        )
+                        .with(
+                            "hover",
+                            JsonValue::object().with("contentFormat", vec!["plaintext".into()]),
+                        )
+                        .with("definition", JsonValue::object())
+                        .with(
+                            "completion",
+                            JsonValue::object().with(
+                                "completionItem",
+                                JsonValue::object().with("snippetSupport", false),
+                            ),
+                        )
+                        .with("inlayHint", JsonValue::object()),
+                ),
+            )
+            .with(
+                "workspaceFolders",
+                vec![JsonValue::object()
+                    .with("uri", root_uri)
+                    .with("name", root_path.file_name().map_or(String::new(), |name| {
+                        name.to_string_lossy().to_string()
+                    }))],
+            );
+        let request_id = self.alloc_request_id();
+        self.pending_requests
+            .insert(request_id, LspRequestKind::Initialize);
+        self.send(request_message(request_id, "initialize", params));
+        request_id
+    }
+
+    pub fn did_open(&mut self, uri: &str, language_id: &str, version: u64, text: &Text) {
+        self.notify(
+            "textDocument/didOpen",
+            JsonValue::object().with(
+                "textDocument",
+                JsonValue::object()
+                    .with("uri", uri)
+                    .with("languageId", language_id)
+                    .with("version", version)
+                    .with("text", text.to_string()),
+            ),
+        );
+    }
+
+    /// Sends the full new contents of the document, which every server supports.
+    pub fn did_change(&mut self, uri: &str, version: u64, text: &Text) {
+        self.notify(
+            "textDocument/didChange",
+            JsonValue::object()
+                .with(
+                    "textDocument",
+                    JsonValue::object().with("uri", uri).with("version", version),
+                )
+                .with(
+                    "contentChanges",
+                    vec![JsonValue::object().with("text", text.to_string())],
+                ),
+        );
+    }
+
+    pub fn did_save(&mut self, uri: &str) {
+        self.notify(
+            "textDocument/didSave",
+            JsonValue::object().with("textDocument", JsonValue::object().with("uri", uri)),
+        );
+    }
+
+    pub fn did_close(&mut self, uri: &str) {
+        self.notify(
+            "textDocument/didClose",
+            JsonValue::object().with("textDocument", JsonValue::object().with("uri", uri)),
+        );
+    }
+
+    pub fn hover(&mut self, uri: &str, position: Position, text: &Text) -> LspRequestId {
+        self.request_at(LspRequestKind::Hover, "textDocument/hover", uri, position, text)
+    }
+
+    pub fn definition(&mut self, uri: &str, position: Position, text: &Text) -> LspRequestId {
+        self.request_at(
+            LspRequestKind::Definition,
+            "textDocument/definition",
+            uri,
+            position,
+            text,
+        )
+    }
+
+    pub fn completion(&mut self, uri: &str, position: Position, text: &Text) -> LspRequestId {
+        self.request_at(
+            LspRequestKind::Completion,
+            "textDocument/completion",
+            uri,
+            position,
+            text,
+        )
+    }
+
+    /// Requests the inlay hints for the whole document.
+    pub fn inlay_hints(&mut self, uri: &str, text: &Text) -> LspRequestId {
+        let lines = text.as_lines();
+        let end = Position {
+            line_index: lines.len() - 1,
+            byte_index: lines[lines.len() - 1].len(),
+        };
+        let request_id = self.alloc_request_id();
+        self.pending_requests
+            .insert(request_id, LspRequestKind::InlayHint);
+        self.send(request_message(
+            request_id,
+            "textDocument/inlayHint",
+            JsonValue::object()
+                .with("textDocument", JsonValue::object().with("uri", uri))
+                .with(
+                    "range",
+                    LspRange {
+                        start: LspPosition::default(),
+                        end: LspPosition::from_position(lines, end),
+                    }
+                    .to_json(),
+                ),
+        ));
+        request_id
+    }
+
+    /// Asks the server to shut down, and tells it to exit once it has.
+    pub fn shutdown(&mut self) {
+        let request_id = self.alloc_request_id();
+        self.pending_requests
+            .insert(request_id, LspRequestKind::Shutdown);
+        self.send(request_message(request_id, "shutdown", JsonValue::Null));
+    }
+
+    /// Processes all messages that have arrived from the server since the last call.
+    pub fn poll_events(&mut self) -> Vec<LspEvent> {
+        let mut events = Vec::new();
+        loop {
+            match self.message_receiver.try_recv() {
+                Ok(Ok(message)) => self.handle_message(message, &mut events),
+                Ok(Err(message)) => events.push(LspEvent::Error {
+                    request_id: None,
+                    message,
+                }),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        events
+    }
+
+    fn handle_message(&mut self, message: JsonValue, events: &mut Vec<LspEvent>) {
+        let id = message.get("id").cloned();
+        match (message.get("method").and_then(|method| method.as_str()), id) {
+            (Some(method), Some(id)) => {
+                // This is a request from the server. We don't implement any of these, but we
+                // have to respond anyway so the server doesn't wait for us forever.
+                let result = match method {
+                    "workspace/configuration" => JsonValue::Array(
+                        message
+                            .get("params")
+                            .and_then(|params| params.get("items"))
+                            .and_then(|items| items.as_array())
+                            .map_or(Vec::new(), |items| {
+                                items.iter().map(|_| JsonValue::Null).collect()
+                            }),
+                    ),
+                    _ => JsonValue::Null,
+                };
+                self.message_sender
+                    .send(
+                        JsonValue::object()
+                            .with("jsonrpc", "2.0")
+                            .with("id", id)
+                            .with("result", result),
+                    )
+                    .ok();
+            }
+            (Some(method), None) => {
+                let params = message.get("params").cloned().unwrap_or_default();
+                match method {
+                    "textDocument/publishDiagnostics" => {
+                        events.push(LspEvent::Diagnostics {
+                            uri: params
+                                .get("uri")
+                                .and_then(|uri| uri.as_str())
+                                .unwrap_or("")
+                                .to_string(),
+                            diagnostics: params
+                                .get("diagnostics")
+                                .and_then(|diagnostics| diagnostics.as_array())
+                                .unwrap_or(&[])
+                                .iter()
+                                .filter_map(LspDiagnostic::from_json)
+                                .collect(),
+                        });
+                    }
+                    "window/showMessage" | "window/logMessage" => {
+                        events.push(LspEvent::Message {
+                            severity: LspSeverity::from_json(params.get("type")),
+                            message: params
+                                .get("message")
+                                .and_then(|message| message.as_str())
+                                .unwrap_or("")
+                                .to_string(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            (None, Some(id)) => {
+                let Some(request_id) = id.as_u64().map(LspRequestId) else {
+                    return;
+                };
+                let Some(kind) = self.pending_requests.remove(&request_id) else {
+                    return;
+                };
+                if let Some(error) = message.get("error") {
+                    events.push(LspEvent::Error {
+                        request_id: Some(request_id),
+                        message: error
+                            .get("message")
+                            .and_then(|message| message.as_str())
+                            .unwrap_or("unknown error")
+                            .to_string(),
+                    });
+                    return;
+                }
+                let result = message.get("result").cloned().unwrap_or_default();
+                match kind {
+                    LspRequestKind::Initialize => {
+                        self.is_initialized = true;
+                        self.message_sender
+                            .send(notification_message("initialized", JsonValue::object()))
+                            .ok();
+                        for message in self.queued_messages.drain(..) {
+                            self.message_sender.send(message).ok();
+                        }
+                        events.push(LspEvent::Initialized);
+                    }
+                    LspRequestKind::Hover => events.push(LspEvent::Hover {
+                        request_id,
+                        contents: result.get("contents").map(hover_contents_to_string),
+                        range: result.get("range").and_then(LspRange::from_json),
+                    }),
+                    LspRequestKind::Definition => events.push(LspEvent::Definition {
+                        request_id,
+                        locations: match &result {
+                            JsonValue::Array(items) => {
+                                items.iter().filter_map(LspLocation::from_json).collect()
+                            }
+                            _ => LspLocation::from_json(&result).into_iter().collect(),
+                        },
+                    }),
+                    LspRequestKind::Completion => events.push(LspEvent::Completion {
+                        request_id,
+                        items: result
+                            .as_array()
+                            .or_else(|| result.get("items").and_then(|items| items.as_array()))
+                            .unwrap_or(&[])
+                            .iter()
+                            .filter_map(LspCompletionItem::from_json)
+                            .collect(),
+                    }),
+                    LspRequestKind::InlayHint => events.push(LspEvent::InlayHints {
+                        request_id,
+                        hints: result
+                            .as_array()
+                            .unwrap_or(&[])
+                            .iter()
+                            .filter_map(LspInlayHint::from_json)
+                            .collect(),
+                    }),
+                    LspRequestKind::Shutdown => {
+                        self.message_sender
+                            .send(notification_message("exit", JsonValue::Null))
+                            .ok();
+                        events.push(LspEvent::Exited);
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn request_at(
+        &mut self,
+        kind: LspRequestKind,
+        method: &str,
+        uri: &str,
+        position: Position,
+        text: &Text,
+    ) -> LspRequestId {
+        let request_id = self.alloc_request_id();
+        self.pending_requests.insert(request_id, kind);
+        self.send(request_message(
+            request_id,
+            method,
+            JsonValue::object()
+                .with("textDocument", JsonValue::object().with("uri", uri))
+                .with(
+                    "position",
+                    LspPosition::from_position(text.as_lines(), position).to_json(),
+                ),
+        ));
+        request_id
+    }
+
+    fn notify(&mut self, method: &str, params: JsonValue) {
+        self.send(notification_message(method, params));
+    }
+
+    fn send(&mut self, message: JsonValue) {
+        let is_initialize = message.get("method").and_then(|method| method.as_str())
+            == Some("initialize");
+        if self.is_initialized || is_initialize {
+            self.message_sender.send(message).ok();
+        } else {
+            self.queued_messages.push(message);
+        }
+    }
+
+    fn alloc_request_id(&mut self) -> LspRequestId {
+        let request_id = LspRequestId(self.next_request_id);
+        self.next_request_id += 1;
+        request_id
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LspRequestId(pub u64);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum LspRequestKind {
+    Initialize,
+    Hover,
+    Definition,
+    Completion,
+    InlayHint,
+    Shutdown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LspEvent {
+    Initialized,
+    Diagnostics {
+        uri: String,
+        diagnostics: Vec<LspDiagnostic>,
+    },
+    Hover {
+        request_id: LspRequestId,
+        contents: Option<String>,
+        range: Option<LspRange>,
+    },
+    Definition {
+        request_id: LspRequestId,
+        locations: Vec<LspLocation>,
+    },
+    Completion {
+        request_id: LspRequestId,
+        items: Vec<LspCompletionItem>,
+    },
+    InlayHints {
+        request_id: LspRequestId,
+        hints: Vec<LspInlayHint>,
+    },
+    Message {
+        severity: LspSeverity,
+        message: String,
+    },
+    Error {
+        request_id: Option<LspRequestId>,
+        message: String,
+    },
+    Exited,
+}
+
+/// A position as the language server sees it: a line and a UTF-16 code unit offset into that line.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LspPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl LspPosition {
+    pub fn from_position(lines: &Rope, position: Position) -> Self {
+        let character = lines
+            .get(position.line_index)
+            .map_or(0, |line| {
+                line[..position.byte_index.min(line.len())]
+                    .chars()
+                    .map(|char| char.len_utf16())
+                    .sum()
+            });
+        Self {
+            line: position.line_index,
+            character,
+        }
+    }
+
+    /// Converts back to a `Position`, clamping to the end of the text where the server is out of
+    /// date.
+    pub fn to_position(self, lines: &Rope) -> Position {
+        if self.line >= lines.len() {
+            return Position {
+                line_index: lines.len() - 1,
+                byte_index: lines[lines.len() - 1].len(),
+            };
+        }
+        let line = &lines[self.line];
+        let mut character = 0;
+        let mut byte_index = line.len();
+        for (index, char) in line.char_indices() {
+            if character >= self.character {
+                byte_index = index;
+                break;
+            }
+            character += char.len_utf16();
+        }
+        Position {
+            line_index: self.line,
+            byte_index,
+        }
+    }
+
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(Self {
+            line: json.get("line")?.as_u64()? as usize,
+            character: json.get("character")?.as_u64()? as usize,
+        })
+    }
+
+    fn to_json(self) -> JsonValue {
+        JsonValue::object()
+            .with("line", self.line)
+            .with("character", self.character)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+impl LspRange {
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(Self {
+            start: LspPosition::from_json(json.get("start")?)?,
+            end: LspPosition::from_json(json.get("end")?)?,
+        })
+    }
+
+    fn to_json(self) -> JsonValue {
+        JsonValue::object()
+            .with("start", self.start.to_json())
+            .with("end", self.end.to_json())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LspSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl LspSeverity {
+    fn from_json(json: Option<&JsonValue>) -> Self {
+        match json.and_then(|json| json.as_u64()) {
+            Some(1) => Self::Error,
+            Some(2) => Self::Warning,
+            Some(4) => Self::Hint,
+            _ => Self::Information,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LspDiagnostic {
+    pub range: LspRange,
+    pub severity: LspSeverity,
+    pub message: String,
+    pub source: Option<String>,
+}
+
+impl LspDiagnostic {
+    pub fn to_decoration(&self, id: usize, lines: &Rope) -> Decoration {
+        Decoration::new(
+            id,
+            self.range.start.to_position(lines),
+            self.range.end.to_position(lines),
+            match self.severity {
+                LspSeverity::Error => DecorationType::Error,
+                _ => DecorationType::Warning,
+            },
+        )
+    }
+
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(Self {
+            range: LspRange::from_json(json.get("range")?)?,
+            severity: LspSeverity::from_json(json.get("severity")),
+            message: json.get("message")?.as_str()?.to_string(),
+            source: json
+                .get("source")
+                .and_then(|source| source.as_str())
+                .map(|source| source.to_string()),
+        })
+    }
+}
+
+/// Converts the diagnostics for a document into decorations. Hints are left out, since they
+/// would otherwise drown out the warnings.
+pub fn diagnostics_to_decorations(diagnostics: &[LspDiagnostic], text: &Text) -> DecorationSet {
+    let mut decorations = DecorationSet::new();
+    for (index, diagnostic) in diagnostics.iter().enumerate() {
+        if diagnostic.severity == LspSeverity::Hint {
+            continue;
+        }
+        decorations.add_decoration(diagnostic.to_decoration(index, text.as_lines()));
+    }
+    decorations
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LspLocation {
+    pub uri: String,
+    pub range: LspRange,
+}
+
+impl LspLocation {
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        // Servers may answer with either a `Location` or a `LocationLink`.
+        if let Some(uri) = json.get("targetUri") {
+            return Some(Self {
+                uri: uri.as_str()?.to_string(),
+                range: LspRange::from_json(json.get("targetSelectionRange")?)?,
+            });
+        }
+        Some(Self {
+            uri: json.get("uri")?.as_str()?.to_string(),
+            range: LspRange::from_json(json.get("range")?)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LspCompletionItem {
+    pub label: String,
+    pub detail: Option<String>,
+    pub insert_text: String,
+    pub edit_range: Option<LspRange>,
+}
+
+impl LspCompletionItem {
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        let label = json.get("label")?.as_str()?.to_string();
+        let text_edit = json.get("textEdit");
+        let insert_text = text_edit
+            .and_then(|edit| edit.get("newText"))
+            .or_else(|| json.get("insertText"))
+            .and_then(|text| text.as_str())
+            .unwrap_or(&label)
+            .to_string();
+        Some(Self {
+            detail: json
+                .get("detail")
+                .and_then(|detail| detail.as_str())
+                .map(|detail| detail.to_string()),
+            insert_text,
+            edit_range: text_edit.and_then(|edit| {
+                edit.get("range")
+                    .or_else(|| edit.get("replace"))
+                    .and_then(LspRange::from_json)
+            }),
+            label,
+        })
+    }
+}
+
+impl LspCompletionItem {
+    pub fn to_completion(&self) -> Completion {
+        Completion {
+            label: self.label.clone(),
+            detail: self.detail.clone(),
+            text: self.insert_text.clone(),
+        }
+    }
+}
+
+/// Returns where the word that `items` complete starts. This is the start of the edit range of the
+/// items, if the server sent one, or otherwise the start of the identifier before `position`.
+pub fn completion_start(items: &[LspCompletionItem], lines: &Rope, position: Position) -> Position {
+    if let Some(range) = items.iter().find_map(|item| item.edit_range) {
+        return range.start.to_position(lines);
+    }
+    let line = &lines[position.line_index];
+    let byte_index = line[..position.byte_index]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, char)| char.is_alphanumeric() || char == '_')
+        .last()
+        .map_or(position.byte_index, |(index, _)| index);
+    Position {
+        line_index: position.line_index,
+        byte_index,
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LspInlayHint {
+    pub position: LspPosition,
+    pub label: String,
+    pub padding_left: bool,
+    pub padding_right: bool,
+}
+
+impl LspInlayHint {
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        // The label is either a string, or a list of parts that may each link somewhere.
+        let label = match json.get("label")? {
+            JsonValue::Array(parts) => parts
+                .iter()
+                .filter_map(|part| part.get("value").and_then(|value| value.as_str()))
+                .collect(),
+            label => label.as_str()?.to_string(),
+        };
+        Some(Self {
+            position: LspPosition::from_json(json.get("position")?)?,
+            label,
+            padding_left: json
+                .get("paddingLeft")
+                .and_then(|padding| padding.as_bool())
+                .unwrap_or(false),
+            padding_right: json
+                .get("paddingRight")
+                .and_then(|padding| padding.as_bool())
+                .unwrap_or(false),
+        })
+    }
+
+    pub fn to_inlay(&self, lines: &Rope) -> (Position, InlineInlay) {
+        let mut text = String::new();
+        if self.padding_left {
+            text.push(' ');
+        }
+        text.push_str(&self.label);
+        if self.padding_right {
+            text.push(' ');
+        }
+        (self.position.to_position(lines), InlineInlay::Text(text))
+    }
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // Windows paths look like `/C:/...`.
+    if path.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&path[1..]));
+    }
+    Some(PathBuf::from(path))
+}
+
+fn request_message(request_id: LspRequestId, method: &str, params: JsonValue) -> JsonValue {
+    JsonValue::object()
+        .with("jsonrpc", "2.0")
+        .with("id", request_id.0)
+        .with("method", method)
+        .with("params", params)
+}
+
+fn notification_message(method: &str, params: JsonValue) -> JsonValue {
+    JsonValue::object()
+        .with("jsonrpc", "2.0")
+        .with("method", method)
+        .with("params", params)
+}
+
+fn hover_contents_to_string(contents: &JsonValue) -> String {
+    match contents {
+        JsonValue::String(string) => string.clone(),
+        JsonValue::Array(items) => items
+            .iter()
+            .map(hover_contents_to_string)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        _ => contents
+            .get("value")
+            .and_then(|value| value.as_str())
+            .unwrap_or("")
+            .to_string(),
+    }
+}
+
+/// Reads a single message body, or `None` at the end of the stream.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; content_length.unwrap()];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
@@ -0,0 +1,482 @@
+use {
+    makepad_widgets::makepad_micro_serde::*,
+    std::{
+        fmt,
+        hash::{Hash, Hasher},
+        iter::FusedIterator,
+        ops::{Bound, Index, IndexMut, RangeBounds},
+        rc::Rc,
+    },
+};
+
+const MIN_LEAF_LEN: usize = 32;
+const MAX_LEAF_LEN: usize = 2 * MIN_LEAF_LEN;
+const MIN_CHILD_COUNT: usize = 4;
+const MAX_CHILD_COUNT: usize = 2 * MIN_CHILD_COUNT;
+
+/// A sequence of lines, stored as a balanced tree.
+///
+/// Lines can be looked up, replaced, inserted and removed anywhere in logarithmic time, so that
+/// editing a large text is not slowed down by the size of it. Nodes are shared between clones,
+/// which makes cloning cheap, and only the nodes on the path to an edited line are copied.
+#[derive(Clone)]
+pub struct Rope {
+    root: Node,
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&String> {
+        if index >= self.len() {
+            return None;
+        }
+        let (leaf, start) = self.root.leaf_at(index);
+        Some(&leaf[index - start])
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut String> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(self.root.get_mut(index))
+    }
+
+    pub fn first(&self) -> Option<&String> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&String> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.range(..)
+    }
+
+    /// Returns an iterator over the lines in `range`.
+    pub fn range(&self, range: impl RangeBounds<usize>) -> Iter<'_> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end && end <= self.len());
+        Iter {
+            root: &self.root,
+            start,
+            end,
+            front: &[],
+            front_start: 0,
+            back: &[],
+            back_end: 0,
+        }
+    }
+
+    /// Replaces the lines in `range` with `lines`.
+    pub fn splice(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        lines: impl IntoIterator<Item = String>,
+    ) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end && end <= self.len());
+        let (left, rest) = self.root.split(start);
+        let (_, right) = rest.split(end - start);
+        let middle = Node::from_lines(lines.into_iter().collect());
+        self.root = Node::concat(Node::concat(left, middle), right);
+    }
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self {
+            root: Node::Leaf(Rc::new(Vec::new())),
+        }
+    }
+}
+
+impl fmt::Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Rope {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Rope {}
+
+impl Hash for Rope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for line in self {
+            line.hash(state);
+        }
+    }
+}
+
+impl Index<usize> for Rope {
+    type Output = String;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("line index out of bounds")
+    }
+}
+
+impl IndexMut<usize> for Rope {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("line index out of bounds")
+    }
+}
+
+impl From<Vec<String>> for Rope {
+    fn from(lines: Vec<String>) -> Self {
+        Self {
+            root: Node::from_lines(lines),
+        }
+    }
+}
+
+impl FromIterator<String> for Rope {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for &'a Rope {
+    type Item = &'a String;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl SerBin for Rope {
+    fn ser_bin(&self, s: &mut Vec<u8>) {
+        self.len().ser_bin(s);
+        for line in self {
+            line.ser_bin(s);
+        }
+    }
+}
+
+impl DeBin for Rope {
+    fn de_bin(o: &mut usize, d: &[u8]) -> Result<Self, DeBinErr> {
+        Ok(Self::from(Vec::<String>::de_bin(o, d)?))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    root: &'a Node,
+    // The lines in `start..end` are the ones that are left.
+    start: usize,
+    end: usize,
+    // The leaf that `start` was last found in, which starts at line `front_start`.
+    front: &'a [String],
+    front_start: usize,
+    // The leaf that `end - 1` was last found in, which ends at line `back_end`.
+    back: &'a [String],
+    back_end: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        if !(self.front_start..self.front_start + self.front.len()).contains(&self.start) {
+            (self.front, self.front_start) = self.root.leaf_at(self.start);
+        }
+        let line = &self.front[self.start - self.front_start];
+        self.start += 1;
+        Some(line)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        let index = self.end - 1;
+        if !(self.back_end - self.back.len()..self.back_end).contains(&index) {
+            let (back, back_start) = self.root.leaf_at(index);
+            self.back = back;
+            self.back_end = back_start + back.len();
+        }
+        self.end = index;
+        Some(&self.back[self.back.len() - (self.back_end - index)])
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl<'a> FusedIterator for Iter<'a> {}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Leaf(Rc<Vec<String>>),
+    Branch(Rc<Branch>),
+}
+
+#[derive(Clone, Debug)]
+struct Branch {
+    height: usize,
+    len: usize,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn from_lines(lines: Vec<String>) -> Self {
+        if lines.len() <= MAX_LEAF_LEN {
+            return Node::Leaf(Rc::new(lines));
+        }
+        // Spread the lines evenly, so that every leaf has at least the minimum length.
+        let leaf_count = lines.len().div_ceil(MAX_LEAF_LEN);
+        let mut lines = lines.into_iter();
+        let nodes = even_chunk_lens(lines.len(), leaf_count)
+            .map(|len| Node::Leaf(Rc::new(lines.by_ref().take(len).collect())))
+            .collect();
+        Self::from_nodes(nodes)
+    }
+
+    // Builds a tree from nodes of the same height that are all ok children.
+    fn from_nodes(mut nodes: Vec<Node>) -> Self {
+        while nodes.len() > 1 {
+            let branch_count = nodes.len().div_ceil(MAX_CHILD_COUNT);
+            let mut children = nodes.into_iter();
+            nodes = even_chunk_lens(children.len(), branch_count)
+                .map(|len| Node::from_children(children.by_ref().take(len).collect()))
+                .collect();
+        }
+        nodes.pop().unwrap()
+    }
+
+    fn from_children(children: Vec<Node>) -> Self {
+        Node::Branch(Rc::new(Branch {
+            height: children[0].height() + 1,
+            len: children.iter().map(|child| child.len()).sum(),
+            children,
+        }))
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(lines) => lines.len(),
+            Node::Branch(branch) => branch.len,
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Branch(branch) => branch.height,
+        }
+    }
+
+    fn children(&self) -> &[Node] {
+        match self {
+            Node::Leaf(_) => &[],
+            Node::Branch(branch) => &branch.children,
+        }
+    }
+
+    // Returns `true` if this node is full enough to be the child of a branch.
+    fn is_ok_child(&self) -> bool {
+        match self {
+            Node::Leaf(lines) => lines.len() >= MIN_LEAF_LEN,
+            Node::Branch(branch) => branch.children.len() >= MIN_CHILD_COUNT,
+        }
+    }
+
+    // Returns the leaf that contains the line at `index`, and the index of its first line.
+    fn leaf_at(&self, index: usize) -> (&[String], usize) {
+        let mut node = self;
+        let mut start = 0;
+        loop {
+            match node {
+                Node::Leaf(lines) => return (lines, start),
+                Node::Branch(branch) => {
+                    let (child_index, child_start) = branch.child_at(index - start);
+                    node = &branch.children[child_index];
+                    start += child_start;
+                }
+            }
+        }
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut String {
+        match self {
+            Node::Leaf(lines) => &mut Rc::make_mut(lines)[index],
+            Node::Branch(branch) => {
+                let (child_index, child_start) = branch.child_at(index);
+                Rc::make_mut(branch).children[child_index].get_mut(index - child_start)
+            }
+        }
+    }
+
+    // Splits this node into the lines before `index` and the lines after it.
+    fn split(&self, index: usize) -> (Node, Node) {
+        match self {
+            Node::Leaf(lines) => (
+                Node::Leaf(Rc::new(lines[..index].to_vec())),
+                Node::Leaf(Rc::new(lines[index..].to_vec())),
+            ),
+            Node::Branch(_) if index == 0 => (Node::Leaf(Rc::new(Vec::new())), self.clone()),
+            Node::Branch(branch) if index == branch.len => {
+                (self.clone(), Node::Leaf(Rc::new(Vec::new())))
+            }
+            Node::Branch(branch) => {
+                let (child_index, child_start) = branch.child_at(index);
+                let (left_child, right_child) =
+                    branch.children[child_index].split(index - child_start);
+                let left = branch.children[..child_index]
+                    .iter()
+                    .cloned()
+                    .chain([left_child])
+                    .fold(Node::Leaf(Rc::new(Vec::new())), Node::concat);
+                let right = [right_child]
+                    .into_iter()
+                    .chain(branch.children[child_index + 1..].iter().cloned())
+                    .fold(Node::Leaf(Rc::new(Vec::new())), Node::concat);
+                (left, right)
+            }
+        }
+    }
+
+    // Returns a balanced node with the lines of `left` followed by those of `right`.
+    fn concat(left: Node, right: Node) -> Node {
+        if left.len() == 0 {
+            return right;
+        }
+        if right.len() == 0 {
+            return left;
+        }
+        let left_height = left.height();
+        let right_height = right.height();
+        if left_height < right_height {
+            let children = right.children();
+            if left_height == right_height - 1 && left.is_ok_child() {
+                return merge_nodes(&[left], children);
+            }
+            let node = Node::concat(left, children[0].clone());
+            if node.height() == right_height - 1 {
+                merge_nodes(&[node], &children[1..])
+            } else {
+                merge_nodes(node.children(), &children[1..])
+            }
+        } else if left_height > right_height {
+            let children = left.children();
+            let (last_child, children) = children.split_last().unwrap();
+            if right_height == left_height - 1 && right.is_ok_child() {
+                return merge_nodes(left.children(), &[right]);
+            }
+            let node = Node::concat(last_child.clone(), right);
+            if node.height() == left_height - 1 {
+                merge_nodes(children, &[node])
+            } else {
+                merge_nodes(children, node.children())
+            }
+        } else if left.is_ok_child() && right.is_ok_child() {
+            Node::from_children(vec![left, right])
+        } else {
+            match (&left, &right) {
+                (Node::Leaf(left_lines), Node::Leaf(right_lines)) => {
+                    let lines: Vec<_> = left_lines
+                        .iter()
+                        .chain(right_lines.iter())
+                        .cloned()
+                        .collect();
+                    if lines.len() <= MAX_LEAF_LEN {
+                        return Node::Leaf(Rc::new(lines));
+                    }
+                    let mut right_lines = lines;
+                    let left_lines = right_lines.drain(..right_lines.len() / 2).collect();
+                    Node::from_children(vec![
+                        Node::Leaf(Rc::new(left_lines)),
+                        Node::Leaf(Rc::new(right_lines)),
+                    ])
+                }
+                _ => merge_nodes(left.children(), right.children()),
+            }
+        }
+    }
+}
+
+impl Branch {
+    // Returns the index of the child that contains the line at `index`, and the index of its
+    // first line.
+    fn child_at(&self, index: usize) -> (usize, usize) {
+        let mut start = 0;
+        for (child_index, child) in self.children.iter().enumerate() {
+            if index < start + child.len() {
+                return (child_index, start);
+            }
+            start += child.len();
+        }
+        panic!("line index out of bounds")
+    }
+}
+
+// Returns a node with the given children, which are all of the same height, splitting it in two
+// if there are too many of them.
+fn merge_nodes(left_children: &[Node], right_children: &[Node]) -> Node {
+    let mut children: Vec<_> = left_children
+        .iter()
+        .chain(right_children)
+        .cloned()
+        .collect();
+    if children.len() <= MAX_CHILD_COUNT {
+        return Node::from_children(children);
+    }
+    let right_children = children.split_off(children.len() / 2);
+    Node::from_children(vec![
+        Node::from_children(children),
+        Node::from_children(right_children),
+    ])
+}
+
+// Splits `len` items into `count` chunks whose lengths differ by at most one.
+fn even_chunk_lens(len: usize, count: usize) -> impl Iterator<Item = usize> {
+    (0..count).map(move |index| len / count + usize::from(index < len % count))
+}
@@ -1,13 +1,15 @@
 use {
     crate::{
         layout::Layout,
+        rope::Rope,
         str::StrExt,
         text::{Edit, Length, Position},
     },
+    makepad_widgets::makepad_micro_serde::*,
     std::{ops, ops::Deref, slice::Iter},
 };
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Hash, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash, Eq, SerBin, DeBin)]
 pub struct Selection {
     pub cursor: Cursor,
     pub anchor: Position,
@@ -116,7 +118,7 @@ impl From<Cursor> for Selection {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub struct SelectionSet {
     selections: Vec<Selection>,
 }
@@ -245,7 +247,7 @@ impl<'a> IntoIterator for &'a SelectionSet {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub struct Cursor {
     pub position: Position,
     pub affinity: Affinity,
@@ -265,7 +267,7 @@ impl Cursor {
         self.position.byte_index == 0
     }
 
-    pub fn is_at_end_of_line(self, lines: &[String]) -> bool {
+    pub fn is_at_end_of_line(self, lines: &Rope) -> bool {
         self.position.byte_index == lines[self.position.line_index].len()
     }
 
@@ -282,7 +284,7 @@ impl Cursor {
         row == line.row_count() - 1
     }
 
-    pub fn move_left(self, lines: &[String]) -> Self {
+    pub fn move_left(self, lines: &Rope) -> Self {
         if !self.is_at_start_of_line() {
             return self.move_to_prev_grapheme(lines);
         }
@@ -292,7 +294,7 @@ impl Cursor {
         self
     }
 
-    pub fn move_right(self, lines: &[String]) -> Self {
+    pub fn move_right(self, lines: &Rope) -> Self {
         if !self.is_at_end_of_line(lines) {
             return self.move_to_next_grapheme(lines);
         }
@@ -322,7 +324,7 @@ impl Cursor {
         self.move_to_end_of_line(layout.as_text().as_lines())
     }
 
-    pub fn home(self, lines: &[String]) -> Self {
+    pub fn home(self, lines: &Rope) -> Self {
         if !self.is_at_start_of_line() {
             let indent_len = lines[self.position.line_index].indent().unwrap_or("").len();
             if self.position.byte_index <= indent_len {
@@ -341,7 +343,7 @@ impl Cursor {
         self
     }
 
-    pub fn end(self, lines: &[String]) -> Self {
+    pub fn end(self, lines: &Rope) -> Self {
         if !self.is_at_end_of_line(lines) {
             let indent_len = lines[self.position.line_index].indent().unwrap_or("").len();
             if self.position.byte_index >= indent_len {
@@ -360,7 +362,7 @@ impl Cursor {
         self
     }
 
-    pub fn move_to_end_of_line(self, lines: &[String]) -> Self {
+    pub fn move_to_end_of_line(self, lines: &Rope) -> Self {
         let mut me = self.clone();
         while !me.is_at_end_of_line(lines) {
             me = me.move_to_next_grapheme(lines);
@@ -390,7 +392,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_file_end(self, lines: &[String]) -> Self {
+    pub fn move_to_file_end(self, lines: &Rope) -> Self {
         Self {
             position: Position {
                 line_index: lines.len() - 1,
@@ -401,7 +403,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_prev_grapheme(self, lines: &[String]) -> Self {
+    pub fn move_to_prev_grapheme(self, lines: &Rope) -> Self {
         Self {
             position: Position {
                 line_index: self.position.line_index,
@@ -416,7 +418,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_next_grapheme(self, lines: &[String]) -> Self {
+    pub fn move_to_next_grapheme(self, lines: &Rope) -> Self {
         let line = &lines[self.position.line_index];
         Self {
             position: Position {
@@ -432,7 +434,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_end_of_prev_line(self, lines: &[String]) -> Self {
+    pub fn move_to_end_of_prev_line(self, lines: &Rope) -> Self {
         let prev_line_index = self.position.line_index - 1;
         Self {
             position: Position {
@@ -549,7 +551,7 @@ impl From<Position> for Cursor {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
 pub enum Affinity {
     Before,
     After,
@@ -1,23 +1,37 @@
 use {
     crate::{
         char::CharExt,
+        completion::{Completion, CompletionList, Hover},
+        decoration::{Decoration, DecorationSet, DecorationType},
+        dirty_lines::DirtyLines,
         document::Document,
+        find::{self, FindQuery},
+        format::{self, FormatError},
         history::EditKind,
         layout::{BlockElement, Layout, WrappedElement},
+        rope::Rope,
         selection::{Affinity, Cursor, SelectionSet},
         str::StrExt,
         text::{Change, Drift, Edit, Length, Position, Text},
+        token::{Token, TokenKind},
         wrap,
         wrap::WrapData,
         Selection, Settings,
     },
+    makepad_regex::{Regex, RegexError},
     std::{
         cell::{Cell, Ref, RefCell},
         collections::HashSet,
         fmt::Write,
         iter, mem,
+        ops::Range,
         rc::Rc,
-        sync::{atomic, atomic::AtomicUsize, mpsc, mpsc::Receiver},
+        sync::{
+            atomic,
+            atomic::AtomicUsize,
+            mpsc,
+            mpsc::{Receiver, TryRecvError},
+        },
     },
 };
 
@@ -29,19 +43,31 @@ pub struct Session {
     layout: RefCell<SessionLayout>,
     selection_state: RefCell<SelectionState>,
     wrap_column: Cell<Option<usize>>,
+    laid_out_line_count: Cell<usize>,
     fold_state: RefCell<FoldState>,
+    find_state: RefCell<FindState>,
+    composition_state: RefCell<Option<CompositionState>>,
+    completion_list: RefCell<Option<CompletionList>>,
+    hover: RefCell<Option<Hover>>,
+    pending_format: Option<PendingFormat>,
     edit_receiver: Receiver<(Option<SelectionSet>, Vec<Edit>)>,
+    relayout_receiver: Receiver<Vec<usize>>,
 }
 
 impl Session {
     pub fn new(document: Document) -> Self {
+        Self::with_settings(document, Rc::new(Settings::default()))
+    }
+
+    pub fn with_settings(document: Document, settings: Rc<Settings>) -> Self {
         static ID: AtomicUsize = AtomicUsize::new(0);
 
         let (edit_sender, edit_receiver) = mpsc::channel();
+        let (relayout_sender, relayout_receiver) = mpsc::channel();
         let line_count = document.as_text().as_lines().len();
         let mut session = Self {
             id: SessionId(ID.fetch_add(1, atomic::Ordering::AcqRel)),
-            settings: Rc::new(Settings::default()),
+            settings,
             document,
             layout: RefCell::new(SessionLayout {
                 y: Vec::new(),
@@ -56,20 +82,31 @@ impl Session {
                 last_added_selection_index: Some(0),
                 injected_char_stack: Vec::new(),
                 highlighted_delimiter_positions: HashSet::new(),
+                expand_stack: Vec::new(),
+                expanded_selections: SelectionSet::new(),
             }),
             wrap_column: Cell::new(None),
+            laid_out_line_count: Cell::new(0),
             fold_state: RefCell::new(FoldState {
                 folding_lines: HashSet::new(),
                 folded_lines: HashSet::new(),
                 unfolding_lines: HashSet::new(),
             }),
+            find_state: RefCell::new(FindState {
+                regex: None,
+                is_regex: false,
+                matches: DecorationSet::new(),
+            }),
+            composition_state: RefCell::new(None),
+            completion_list: RefCell::new(None),
+            hover: RefCell::new(None),
+            pending_format: None,
             edit_receiver,
+            relayout_receiver,
         };
-        for line in 0..line_count {
-            session.update_wrap_data(line);
-        }
-        session.update_y();
-        session.document.add_session(session.id, edit_sender);
+        session
+            .document
+            .add_session(session.id, edit_sender, relayout_sender);
         session
     }
 
@@ -85,6 +122,8 @@ impl Session {
         &self.document
     }
 
+    /// The layout of the lines that were laid out so far. Lines are only laid out once they are
+    /// needed, see `layout_until` and `layout_until_y`.
     pub fn layout(&self) -> Layout<'_> {
         Layout {
             text: self.document.as_text(),
@@ -93,6 +132,24 @@ impl Session {
         }
     }
 
+    /// Makes sure that the lines before `line_end` are laid out, so that their position and size
+    /// are known. Only the lines that weren't laid out yet, or changed since, are laid out again.
+    pub fn layout_until(&self, line_end: usize) {
+        self.update_y(line_end, None);
+    }
+
+    /// Like `layout_until`, but lays out lines until the first one that starts after `y`. This is
+    /// enough for drawing the lines above `y`, or finding the line at `y`.
+    pub fn layout_until_y(&self, y: f64) {
+        self.update_y(0, Some(y));
+    }
+
+    /// The number of times a line was laid out so far, counting lines that were laid out again
+    /// after they changed.
+    pub fn laid_out_line_count(&self) -> usize {
+        self.laid_out_line_count.get()
+    }
+
     pub fn wrap_column(&self) -> Option<usize> {
         self.wrap_column.get()
     }
@@ -113,16 +170,166 @@ impl Session {
         })
     }
 
+    pub fn find_matches(&self) -> Ref<'_, [Decoration]> {
+        Ref::map(self.find_state.borrow(), |find_state| {
+            find_state.matches.as_decorations()
+        })
+    }
+
+    /// Returns whether an IME composition is in progress. Moving the selections in any other way
+    /// implicitly ends it.
+    pub fn is_composing(&self) -> bool {
+        self.composition_state
+            .borrow()
+            .as_ref()
+            .is_some_and(|composition_state| self.is_composition_valid(composition_state))
+    }
+
+    /// Returns the range of the preedit text in each selection, while composing.
+    pub fn composition(&self) -> Vec<Decoration> {
+        if !self.is_composing() {
+            return Vec::new();
+        }
+        let composition_state = self.composition_state.borrow();
+        let composition_state = composition_state.as_ref().unwrap();
+        if composition_state.len == 0 {
+            return Vec::new();
+        }
+        self.selections()
+            .iter()
+            .map(|selection| {
+                let start = composition_state.preedit_start(*selection);
+                Decoration::new(
+                    0,
+                    start,
+                    Position {
+                        line_index: start.line_index,
+                        byte_index: start.byte_index + composition_state.len,
+                    },
+                    DecorationType::Composition,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the hover information that is shown, if any.
+    pub fn hover(&self) -> Option<Hover> {
+        self.hover.borrow().clone()
+    }
+
+    pub fn set_hover(&self, hover: Hover) {
+        *self.hover.borrow_mut() = Some(hover);
+    }
+
+    /// Hides the hover information. Returns `false` if none was shown.
+    pub fn clear_hover(&self) -> bool {
+        self.hover.borrow_mut().take().is_some()
+    }
+
+    /// Shows `completions` for the word that starts at `start` and ends at the cursor of the
+    /// last added selection.
+    pub fn show_completions(&self, start: Position, completions: Vec<Completion>) {
+        *self.completion_list.borrow_mut() = Some(CompletionList::new(start, completions));
+    }
+
+    /// Returns the completions that match the word that is being completed. This is empty if the
+    /// cursor has since moved away from that word.
+    pub fn completions(&self) -> Vec<Completion> {
+        let completion_list = self.completion_list.borrow();
+        let Some(completion_list) = completion_list.as_ref() else {
+            return Vec::new();
+        };
+        let Some(prefix) = self.completion_prefix(completion_list.start) else {
+            return Vec::new();
+        };
+        completion_list
+            .filter(&prefix)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn selected_completion_index(&self) -> usize {
+        let completion_count = self.completions().len();
+        self.completion_list
+            .borrow()
+            .as_ref()
+            .map_or(0, |completion_list| {
+                completion_list
+                    .selected_index
+                    .min(completion_count.saturating_sub(1))
+            })
+    }
+
+    pub fn select_next_completion(&self) {
+        let completion_count = self.completions().len();
+        let selected_index = self.selected_completion_index();
+        if let Some(completion_list) = self.completion_list.borrow_mut().as_mut() {
+            completion_list.selected_index = (selected_index + 1) % completion_count.max(1);
+        }
+    }
+
+    pub fn select_prev_completion(&self) {
+        let completion_count = self.completions().len();
+        let selected_index = self.selected_completion_index();
+        if let Some(completion_list) = self.completion_list.borrow_mut().as_mut() {
+            completion_list.selected_index =
+                (selected_index + completion_count.max(1) - 1) % completion_count.max(1);
+        }
+    }
+
+    /// Replaces the word that is being completed with the selected completion. Returns `false`
+    /// if there are no completions to choose from.
+    pub fn accept_completion(&mut self) -> bool {
+        let completions = self.completions();
+        let Some(completion) = completions.get(self.selected_completion_index()) else {
+            self.clear_completions();
+            return false;
+        };
+        let start = self.completion_list.borrow().as_ref().unwrap().start;
+        let mut selections = SelectionSet::new();
+        selections.set_selection(Selection {
+            anchor: start,
+            cursor: Cursor::from(self.last_added_selection().cursor.position),
+        });
+        let text = Text::from(completion.text.as_str());
+        self.document.edit_selections(
+            self.id,
+            EditKind::Other,
+            &selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, text.clone()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+        self.document.force_new_group();
+        self.clear_completions();
+        self.handle_changes();
+        true
+    }
+
+    /// Hides the completions. Returns `false` if none were shown.
+    pub fn clear_completions(&self) -> bool {
+        self.completion_list.borrow_mut().take().is_some()
+    }
+
     pub fn set_wrap_column(&self, wrap_column: Option<usize>) {
         if self.wrap_column.get() == wrap_column {
             return;
         }
         self.wrap_column.set(wrap_column);
-        let line_count = self.document.as_text().as_lines().len();
-        for line in 0..line_count {
-            self.update_wrap_data(line);
+        let mut layout = self.layout.borrow_mut();
+        for wrap_data in &mut layout.wrap_data {
+            *wrap_data = None;
         }
-        self.update_y();
+        layout.y.clear();
     }
 
     pub fn fold(&self) {
@@ -154,6 +361,35 @@ impl Session {
         }
     }
 
+    /// Folds the lines inside the block that the last added cursor is in, or that starts on its
+    /// line. A block is a pair of matching delimiters on different lines, and its opening and
+    /// closing lines stay visible. If the block is already folded, it is unfolded instead.
+    /// Returns `false` if there is no block to fold.
+    pub fn toggle_block_fold(&self) -> bool {
+        let position = self.last_added_selection().cursor.position;
+        let Some(line_range) = find_block_lines(self.document.as_text().as_lines(), position)
+        else {
+            return false;
+        };
+        let mut fold_state = self.fold_state.borrow_mut();
+        let is_folded = line_range.clone().all(|line_index| {
+            fold_state.folded_lines.contains(&line_index)
+                || fold_state.folding_lines.contains(&line_index)
+        });
+        for line_index in line_range {
+            if is_folded {
+                fold_state.folding_lines.remove(&line_index);
+                fold_state.folded_lines.remove(&line_index);
+                fold_state.unfolding_lines.insert(line_index);
+            } else if !fold_state.folded_lines.contains(&line_index) {
+                self.layout.borrow_mut().fold_column[line_index] = 0;
+                fold_state.unfolding_lines.remove(&line_index);
+                fold_state.folding_lines.insert(line_index);
+            }
+        }
+        true
+    }
+
     pub fn update_folds(&self) -> bool {
         let mut fold_state_ref = self.fold_state.borrow_mut();
         if fold_state_ref.folding_lines.is_empty() && fold_state_ref.unfolding_lines.is_empty() {
@@ -185,9 +421,6 @@ impl Session {
             layout.y.truncate(line + 1);
         }
         fold_state_ref.unfolding_lines = new_unfolding_lines;
-        drop(layout);
-        drop(fold_state_ref);
-        self.update_y();
         true
     }
 
@@ -259,6 +492,67 @@ impl Session {
         self.document().force_new_group();
     }
 
+    /// Expands each selection to the smallest syntactic unit that contains it. From small to
+    /// large, these are the word at the cursor, a string and its contents, the contents of
+    /// enclosing delimiters and the delimiters themselves, and for blocks that span several lines,
+    /// the lines of the item they belong to. Returns `false` if no selection could be expanded.
+    pub fn expand_selection(&self) -> bool {
+        let selection_state = self.selection_state.borrow();
+        let selections = selection_state.selections.clone();
+        let last_added_selection_index = selection_state.last_added_selection_index;
+        drop(selection_state);
+        self.modify_selections(false, |selection, layout| {
+            let Some((start, end)) = expand_range(
+                layout.as_text().as_lines(),
+                &layout.document_layout.tokens,
+                selection.start(),
+                selection.end(),
+                &self.settings.word_separators,
+            ) else {
+                return selection;
+            };
+            Selection {
+                anchor: start,
+                cursor: Cursor {
+                    position: end,
+                    affinity: Affinity::Before,
+                    preferred_column_index: None,
+                },
+            }
+        });
+        let mut selection_state = self.selection_state.borrow_mut();
+        if selection_state.selections == selections {
+            return false;
+        }
+        if selection_state.expanded_selections != selections {
+            selection_state.expand_stack.clear();
+        }
+        selection_state
+            .expand_stack
+            .push((selections, last_added_selection_index));
+        selection_state.expanded_selections = selection_state.selections.clone();
+        true
+    }
+
+    /// Undoes the last call to `expand_selection`, if the selections did not change since.
+    pub fn shrink_selection(&self) -> bool {
+        let mut selection_state = self.selection_state.borrow_mut();
+        if selection_state.expanded_selections != selection_state.selections {
+            selection_state.expand_stack.clear();
+            return false;
+        }
+        let Some((selections, last_added_selection_index)) = selection_state.expand_stack.pop()
+        else {
+            return false;
+        };
+        selection_state.expanded_selections = selections.clone();
+        selection_state.selections = selections;
+        selection_state.last_added_selection_index = last_added_selection_index;
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        true
+    }
+
     pub fn move_left(&self, reset_anchor: bool) {
         self.modify_selections(reset_anchor, |selection, layout| {
             selection.update_cursor(|cursor| cursor.move_left(layout.as_text().as_lines()))
@@ -272,12 +566,14 @@ impl Session {
     }
 
     pub fn move_up(&self, reset_anchor: bool) {
+        self.wrap_lines_around_cursors();
         self.modify_selections(reset_anchor, |selection, layout| {
             selection.update_cursor(|cursor| cursor.move_up(layout))
         });
     }
 
     pub fn move_down(&self, reset_anchor: bool) {
+        self.wrap_lines_around_cursors();
         self.modify_selections(reset_anchor, |selection, layout| {
             selection.update_cursor(|cursor| cursor.move_down(layout))
         });
@@ -304,7 +600,9 @@ impl Session {
             let mut selection_state = self.selection_state.borrow_mut();
             if char == ' ' {
                 edit_kind = EditKind::InsertSpace;
-            } else if char == '"' || char.is_opening_delimiter() {
+            } else if self.settings.auto_close_pairs
+                && (char == '"' || char.is_opening_delimiter())
+            {
                 if selection_state
                     .selections
                     .iter()
@@ -423,6 +721,7 @@ impl Session {
             .borrow_mut()
             .injected_char_stack
             .clear();
+        let continue_comments = self.settings.continue_comments;
         self.document.edit_selections(
             self.id,
             EditKind::Other,
@@ -430,11 +729,17 @@ impl Session {
             &self.settings,
             |mut editor, position, length| {
                 let line = &editor.as_text().as_lines()[position.line_index];
+                let comment_prefix = if continue_comments {
+                    line_comment_prefix(line, position.byte_index)
+                } else {
+                    None
+                };
                 let delete_whitespace = !line.is_empty()
                     && line[..position.byte_index]
                         .chars()
                         .all(|char| char.is_whitespace());
-                let inject_newline = line[..position.byte_index]
+                let inject_newline = comment_prefix.is_none()
+                    && line[..position.byte_index]
                     .chars()
                     .rev()
                     .find_map(|char| {
@@ -486,6 +791,14 @@ impl Session {
                 });
                 position.line_index += 1;
                 position.byte_index = 0;
+                if let Some(comment_prefix) = comment_prefix {
+                    // The new line is indented along with the comment prefix, because the
+                    // newline is autoindented afterwards.
+                    editor.apply_edit(Edit {
+                        change: Change::Insert(position, comment_prefix.into()),
+                        drift: Drift::Before,
+                    });
+                }
                 if inject_newline {
                     editor.apply_edit(Edit {
                         change: Change::Insert(position, Text::newline()),
@@ -761,6 +1074,199 @@ impl Session {
         string
     }
 
+    /// Highlights all matches of `query` in the document, and keeps them up to date as the
+    /// document is edited. Returns the number of matches.
+    pub fn find(&self, query: &FindQuery) -> Result<usize, RegexError> {
+        let regex = query.to_regex()?;
+        let matches = find::find_matches(self.document.as_text().as_lines(), &regex);
+        let match_count = matches.len();
+        *self.find_state.borrow_mut() = FindState {
+            regex: Some(regex),
+            is_regex: query.is_regex,
+            matches,
+        };
+        Ok(match_count)
+    }
+
+    /// Returns `true` if the document is being searched, that is, if `find` was called and
+    /// `clear_find` was not called since.
+    pub fn is_finding(&self) -> bool {
+        self.find_state.borrow().regex.is_some()
+    }
+
+    pub fn clear_find(&self) {
+        let mut find_state = self.find_state.borrow_mut();
+        find_state.regex = None;
+        find_state.matches.clear();
+    }
+
+    /// Selects the first match after the last added selection, wrapping around to the start of
+    /// the document. Returns `false` if there are no matches.
+    pub fn find_next(&self) -> bool {
+        let end = self.last_added_selection().end();
+        let find_state = self.find_state.borrow();
+        let Some(&decoration) = find_state
+            .matches
+            .iter()
+            .find(|decoration| decoration.start() >= end)
+            .or_else(|| find_state.matches.first())
+        else {
+            return false;
+        };
+        drop(find_state);
+        self.select_matches(&[decoration], 0);
+        true
+    }
+
+    /// Selects the last match before the last added selection, wrapping around to the end of the
+    /// document. Returns `false` if there are no matches.
+    pub fn find_prev(&self) -> bool {
+        let start = self.last_added_selection().start();
+        let find_state = self.find_state.borrow();
+        let Some(&decoration) = find_state
+            .matches
+            .iter()
+            .rev()
+            .find(|decoration| decoration.end() <= start)
+            .or_else(|| find_state.matches.last())
+        else {
+            return false;
+        };
+        drop(find_state);
+        self.select_matches(&[decoration], 0);
+        true
+    }
+
+    /// Adds a selection for every match, so that they can all be edited at once.
+    pub fn select_all_matches(&self) -> bool {
+        let matches = self.find_state.borrow().matches.clone();
+        if matches.is_empty() {
+            return false;
+        }
+        let start = self.last_added_selection().start();
+        let last_added_index = matches
+            .iter()
+            .position(|decoration| decoration.start() >= start)
+            .unwrap_or(matches.len() - 1);
+        self.select_matches(&matches, last_added_index);
+        true
+    }
+
+    /// Replaces the match that is currently selected, if any, and selects the next match.
+    pub fn replace(&mut self, replacement: &str) {
+        let selection = self.last_added_selection();
+        let is_match_selected = self.find_state.borrow().matches.iter().any(|decoration| {
+            decoration.start() == selection.start() && decoration.end() == selection.end()
+        });
+        if is_match_selected {
+            let mut selections = SelectionSet::new();
+            selections.set_selection(selection);
+            self.replace_selections(&selections, replacement);
+            self.handle_changes();
+        }
+        self.find_next();
+    }
+
+    /// Replaces all matches as a single undoable edit. Returns the number of replaced matches.
+    pub fn replace_all(&mut self, replacement: &str) -> usize {
+        let matches = self.find_state.borrow().matches.clone();
+        if matches.is_empty() {
+            return 0;
+        }
+        let mut selections = SelectionSet::new();
+        for (index, decoration) in matches.iter().enumerate() {
+            let selection = Selection {
+                anchor: decoration.start(),
+                cursor: Cursor::from(decoration.end()),
+            };
+            if index == 0 {
+                selections.set_selection(selection);
+            } else {
+                selections.add_selection(selection);
+            }
+        }
+        self.replace_selections(&selections, replacement);
+        self.handle_changes();
+        matches.len()
+    }
+
+    /// Starts the formatter from the settings on the document, on a background thread. Once it
+    /// is done, `handle_format_result` applies its output.
+    pub fn format_document(&mut self) {
+        let text = self.document.as_text().clone();
+        let result_receiver = format::spawn_formatter(
+            self.settings.formatter_command.clone(),
+            self.settings.formatter_args.clone(),
+            text.to_string(),
+        );
+        self.pending_format = Some(PendingFormat {
+            text,
+            result_receiver,
+        });
+    }
+
+    /// Applies the output of the formatter started by `format_document` with
+    /// `apply_formatted_text`, if it is done. Returns `None` if it is still running or was not
+    /// started. If the document was edited since it was started, the output is dropped and
+    /// `Ok(false)` is returned.
+    pub fn handle_format_result(&mut self) -> Option<Result<bool, FormatError>> {
+        let pending_format = self.pending_format.as_ref()?;
+        let result = match pending_format.result_receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                self.pending_format = None;
+                return None;
+            }
+        };
+        let pending_format = self.pending_format.take().unwrap();
+        Some(result.map(|formatted_text| {
+            *self.document.as_text() == pending_format.text
+                && self.apply_formatted_text(formatted_text.into())
+        }))
+    }
+
+    /// Replaces the text of the document with `text` as a single undoable edit. Only the lines
+    /// that differ are replaced, so that selections elsewhere stay where they are. Returns
+    /// `false` if the text is the same.
+    pub fn apply_formatted_text(&mut self, text: Text) -> bool {
+        let replacements = format::diff(&self.document.as_text(), &text);
+        if replacements.is_empty() {
+            return false;
+        }
+        let mut selections = SelectionSet::new();
+        for (index, replacement) in replacements.iter().enumerate() {
+            let selection = Selection {
+                anchor: replacement.start,
+                cursor: Cursor::from(replacement.end),
+            };
+            if index == 0 {
+                selections.set_selection(selection);
+            } else {
+                selections.add_selection(selection);
+            }
+        }
+        let mut texts = replacements.into_iter().map(|replacement| replacement.text);
+        self.document.edit_selections(
+            self.id,
+            EditKind::Format,
+            &selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, texts.next().unwrap()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+        self.handle_changes();
+        true
+    }
+
     pub fn undo(&self) -> bool {
         self.selection_state
             .borrow_mut()
@@ -775,14 +1281,272 @@ impl Session {
             .borrow_mut()
             .injected_char_stack
             .clear();
-        self.document
-            .redo(self.id, &self.selection_state.borrow().selections)
+        self.document.redo(self.id)
+    }
+
+    /// Moves the document to the given revision of its history. See `History::jump_to`.
+    pub fn jump_to_revision(&self, revision: usize) -> bool {
+        self.selection_state
+            .borrow_mut()
+            .injected_char_stack
+            .clear();
+        self.document.jump_to_revision(
+            self.id,
+            revision,
+            &self.selection_state.borrow().selections,
+        )
+    }
+
+    /// Starts an IME composition at each selection. Does nothing if one is already in progress.
+    pub fn start_composition(&self) {
+        if self.is_composing() {
+            return;
+        }
+        self.selection_state
+            .borrow_mut()
+            .injected_char_stack
+            .clear();
+        self.document.force_new_group();
+        *self.composition_state.borrow_mut() = Some(CompositionState {
+            len: 0,
+            cursor: 0..0,
+            selections: self.selection_state.borrow().selections.clone(),
+        });
+    }
+
+    /// Replaces the preedit text of the composition with `text`, and selects `cursor`, a byte
+    /// range into `text`, within it. Starts a composition if none is in progress.
+    pub fn update_composition(&mut self, text: &str, cursor: Range<usize>) {
+        // The preedit text is shown inline, so it has to stay on a single line.
+        let text: String = text
+            .chars()
+            .filter(|&char| char != '\n' && char != '\r')
+            .collect();
+        let clamp = |mut index: usize| {
+            index = index.min(text.len());
+            while !text.is_char_boundary(index) {
+                index -= 1;
+            }
+            index
+        };
+        let cursor = clamp(cursor.start)..clamp(cursor.end.max(cursor.start));
+        self.start_composition();
+        if !self.select_composition() && text.is_empty() {
+            return;
+        }
+        self.edit_composition(Text::from(text.as_str()));
+        let mut selection_state = self.selection_state.borrow_mut();
+        let last_added_selection_index = selection_state.last_added_selection_index;
+        // The selections are now right after the preedit text.
+        selection_state.last_added_selection_index = selection_state
+            .selections
+            .update_all_selections(last_added_selection_index, |selection| {
+                let end = selection.cursor.position;
+                let position = |byte_index| Position {
+                    line_index: end.line_index,
+                    byte_index: end.byte_index - text.len() + byte_index,
+                };
+                Selection {
+                    anchor: position(cursor.start),
+                    cursor: Cursor::from(position(cursor.end)),
+                }
+            });
+        let selections = selection_state.selections.clone();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        *self.composition_state.borrow_mut() = Some(CompositionState {
+            len: text.len(),
+            cursor,
+            selections,
+        });
+    }
+
+    /// Replaces the preedit text of the composition with `text`, and ends the composition. The
+    /// whole composition is undone as a single edit.
+    pub fn commit_composition(&mut self, text: &str) {
+        self.start_composition();
+        self.select_composition();
+        self.edit_composition(Text::from(text));
+        *self.composition_state.borrow_mut() = None;
+        self.document.force_new_group();
+    }
+
+    /// Removes the preedit text of the composition, if any, and ends the composition.
+    pub fn cancel_composition(&mut self) {
+        if self.is_composing() && self.select_composition() {
+            self.edit_composition(Text::new());
+        }
+        self.end_composition();
+    }
+
+    /// Ends the composition, keeping the preedit text as it is.
+    pub fn end_composition(&self) {
+        if self.composition_state.borrow_mut().take().is_some() {
+            self.document.force_new_group();
+        }
     }
 
     pub fn handle_changes(&mut self) {
         while let Ok((selections, edits)) = self.edit_receiver.try_recv() {
             self.update_after_edit(selections, &edits);
         }
+        while let Ok(lines) = self.relayout_receiver.try_recv() {
+            let line_count = self.document.as_text().as_lines().len();
+            for line in lines {
+                if line < line_count {
+                    self.invalidate_line(line);
+                }
+            }
+        }
+    }
+
+    // The text between `start` and the cursor of the last added selection, if that is part of a
+    // single word.
+    fn completion_prefix(&self, start: Position) -> Option<String> {
+        let end = self.last_added_selection().cursor.position;
+        if end.line_index != start.line_index || end.byte_index < start.byte_index {
+            return None;
+        }
+        let text = self.document.as_text();
+        let prefix = text.as_lines()[start.line_index].get(start.byte_index..end.byte_index)?;
+        if !prefix
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_')
+        {
+            return None;
+        }
+        Some(prefix.to_string())
+    }
+
+    fn is_composition_valid(&self, composition_state: &CompositionState) -> bool {
+        composition_state.selections == self.selection_state.borrow().selections
+    }
+
+    /// Selects the preedit text in each selection, so that editing the selections replaces it.
+    /// Returns whether there is any preedit text.
+    fn select_composition(&self) -> bool {
+        let mut composition_state = self.composition_state.borrow_mut();
+        let composition_state = composition_state.as_mut().unwrap();
+        if !self.is_composition_valid(composition_state) {
+            // The selections were changed by something else, so the preedit text is left where it
+            // was, and the composition continues at the new selections.
+            composition_state.len = 0;
+            composition_state.cursor = 0..0;
+        }
+        if composition_state.len == 0 {
+            // Keep the selections as they are, so that the preedit text replaces them.
+            return false;
+        }
+        let mut selection_state = self.selection_state.borrow_mut();
+        let last_added_selection_index = selection_state.last_added_selection_index;
+        selection_state.last_added_selection_index = selection_state
+            .selections
+            .update_all_selections(last_added_selection_index, |selection| {
+                let start = composition_state.preedit_start(selection);
+                Selection {
+                    anchor: start,
+                    cursor: Cursor::from(Position {
+                        line_index: start.line_index,
+                        byte_index: start.byte_index + composition_state.len,
+                    }),
+                }
+            });
+        true
+    }
+
+    fn edit_composition(&mut self, text: Text) {
+        self.document.edit_selections(
+            self.id,
+            EditKind::Composition,
+            &self.selection_state.borrow().selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, text.clone()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+        self.handle_changes();
+    }
+
+    fn last_added_selection(&self) -> Selection {
+        let selection_state = self.selection_state.borrow();
+        selection_state.selections[selection_state.last_added_selection_index.unwrap_or(0)]
+    }
+
+    fn select_matches(&self, matches: &[Decoration], last_added_index: usize) {
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        let mut last_added_selection_index = 0;
+        for (index, decoration) in matches.iter().enumerate() {
+            let selection = Selection {
+                anchor: decoration.start(),
+                cursor: Cursor {
+                    position: decoration.end(),
+                    affinity: Affinity::Before,
+                    preferred_column_index: None,
+                },
+            };
+            if index == 0 {
+                selection_state.selections.set_selection(selection);
+            } else {
+                selection_state.selections.add_selection(selection);
+            }
+            if index == last_added_index {
+                last_added_selection_index = index;
+            }
+        }
+        selection_state.last_added_selection_index = Some(last_added_selection_index);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+    }
+
+    fn replace_selections(&self, selections: &SelectionSet, replacement: &str) {
+        let find_state = self.find_state.borrow();
+        let Some(regex) = find_state.regex.as_ref() else {
+            return;
+        };
+        let text = self.document.as_text();
+        let replacements: Vec<Text> = selections
+            .iter()
+            .map(|selection| {
+                let start = selection.start();
+                find::expand_replacement(
+                    &text.as_lines()[start.line_index],
+                    start.byte_index,
+                    regex,
+                    find_state.is_regex,
+                    replacement,
+                )
+                .into()
+            })
+            .collect();
+        drop(text);
+        drop(find_state);
+        let mut replacements = replacements.into_iter();
+        self.document.edit_selections(
+            self.id,
+            EditKind::Other,
+            selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, replacements.next().unwrap()),
+                    drift: Drift::Before,
+                });
+            },
+        );
     }
 
     fn modify_selections(
@@ -814,15 +1578,15 @@ impl Session {
     }
 
     fn update_after_edit(&self, selections: Option<SelectionSet>, edits: &[Edit]) {
+        let mut dirty_lines = DirtyLines::new();
         for edit in edits {
+            dirty_lines.apply_change(&edit.change);
             match edit.change {
                 Change::Insert(point, ref text) => {
-                    self.layout.borrow_mut().column_count[point.line_index] = None;
-                    self.layout.borrow_mut().wrap_data[point.line_index] = None;
+                    self.invalidate_line(point.line_index);
                     let line_count = text.length().line_count;
                     if line_count > 0 {
                         let line = point.line_index + 1;
-                        self.layout.borrow_mut().y.truncate(line);
                         self.layout
                             .borrow_mut()
                             .column_count
@@ -842,13 +1606,11 @@ impl Session {
                     }
                 }
                 Change::Delete(start, length) => {
-                    self.layout.borrow_mut().column_count[start.line_index] = None;
-                    self.layout.borrow_mut().wrap_data[start.line_index] = None;
+                    self.invalidate_line(start.line_index);
                     let line_count = length.line_count;
                     if line_count > 0 {
                         let start_line = start.line_index + 1;
                         let end_line = start_line + line_count;
-                        self.layout.borrow_mut().y.truncate(start_line);
                         self.layout
                             .borrow_mut()
                             .column_count
@@ -866,13 +1628,6 @@ impl Session {
                 }
             }
         }
-        let line_count = self.document.as_text().as_lines().len();
-        for line in 0..line_count {
-            if self.layout.borrow().wrap_data[line].is_none() {
-                self.update_wrap_data(line);
-            }
-        }
-        self.update_y();
         let mut selection_state = self.selection_state.borrow_mut();
         if let Some(selections) = selections {
             selection_state.selections = selections;
@@ -886,37 +1641,100 @@ impl Session {
         }
         drop(selection_state);
         self.update_highlighted_delimiter_positions();
+        // Only the edited lines can have new matches, so those are the only ones searched again.
+        let mut find_state = self.find_state.borrow_mut();
+        let FindState { regex, matches, .. } = &mut *find_state;
+        if let (Some(regex), Some((first_line, last_line))) = (regex.as_ref(), dirty_lines.range())
+        {
+            for edit in edits {
+                matches.apply_edit(edit);
+            }
+            let text = self.document.as_text();
+            let line_end = (last_line + 1).min(text.as_lines().len());
+            find::update_matches(matches, text.as_lines(), regex, first_line..line_end);
+        }
     }
 
-    fn update_y(&self) {
-        let start = self.layout.borrow().y.len();
-        let end = self.document.as_text().as_lines().len();
-        if start == end + 1 {
-            return;
-        }
-        let mut y = if start == 0 {
-            0.0
-        } else {
-            let layout = self.layout();
-            let line = layout.line(start - 1);
-            line.y() + line.height()
-        };
-        let mut ys = mem::take(&mut self.layout.borrow_mut().y);
-        for block in self.layout().block_elements(start, end) {
-            match block {
-                BlockElement::Line { is_inlay, line } => {
-                    if !is_inlay {
-                        ys.push(y);
+    // Extends the y positions of the lines until they are known for the lines before `line_end`,
+    // and for the first line that starts after `y_end`. Lines that don't have any wrap data yet
+    // are laid out first.
+    fn update_y(&self, line_end: usize, y_end: Option<f64>) {
+        let line_count = self.document.as_text().as_lines().len();
+        let line_end = line_end.min(line_count);
+        loop {
+            let start = self.layout.borrow().y.len();
+            if start == line_count + 1 {
+                return;
+            }
+            let mut y = if start == 0 {
+                0.0
+            } else {
+                let layout = self.layout();
+                let line = layout.line(start - 1);
+                line.y() + line.height()
+            };
+            let end = match y_end {
+                Some(y_end) => {
+                    if start >= line_end && start > 0 && self.layout.borrow().y[start - 1] > y_end {
+                        return;
                     }
-                    y += line.height();
+                    // Lines are at least as high as a single row, unless they are folded, so this
+                    // is usually enough to get past `y_end`.
+                    line_end.max(start + (y_end - y).max(0.0) as usize + 1)
                 }
-                BlockElement::Widget(widget) => {
-                    y += widget.height;
+                None => {
+                    if start >= line_end {
+                        return;
+                    }
+                    line_end
+                }
+            }
+            .min(line_count);
+            for line in start..end {
+                if self.layout.borrow().wrap_data[line].is_none() {
+                    self.update_wrap_data(line);
+                }
+            }
+            let mut ys = mem::take(&mut self.layout.borrow_mut().y);
+            for block in self.layout().block_elements(start, end) {
+                match block {
+                    BlockElement::Line { is_inlay, line } => {
+                        if !is_inlay {
+                            ys.push(y);
+                        }
+                        y += line.height();
+                    }
+                    BlockElement::Widget(widget) => {
+                        y += widget.height;
+                    }
+                }
+            }
+            if end == line_count {
+                ys.push(y);
+            }
+            self.layout.borrow_mut().y = ys;
+        }
+    }
+
+    // Forgets the layout of a line that changed, and the positions of the lines after it.
+    fn invalidate_line(&self, line: usize) {
+        let mut layout = self.layout.borrow_mut();
+        layout.column_count[line] = None;
+        layout.wrap_data[line] = None;
+        layout.y.truncate(line);
+    }
+
+    // Moving a cursor up or down depends on how its line and the lines next to it are wrapped.
+    fn wrap_lines_around_cursors(&self) {
+        let line_count = self.document.as_text().as_lines().len();
+        for selection in self.selections().iter() {
+            let line = selection.cursor.position.line_index;
+            for line in line.saturating_sub(1)..(line + 2).min(line_count) {
+                if self.layout.borrow().wrap_data[line].is_none() {
+                    self.update_wrap_data(line);
                 }
             }
         }
-        ys.push(y);
-        self.layout.borrow_mut().y = ys;
     }
 
     fn update_column_count(&self, index: usize) {
@@ -954,6 +1772,8 @@ impl Session {
         self.layout.borrow_mut().wrap_data[line] = Some(wrap_data);
         self.layout.borrow_mut().y.truncate(line + 1);
         self.update_column_count(line);
+        self.laid_out_line_count
+            .set(self.laid_out_line_count.get() + 1);
     }
 
     fn update_highlighted_delimiter_positions(&self) {
@@ -988,6 +1808,13 @@ impl Drop for Session {
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct SessionId(usize);
 
+#[derive(Debug)]
+struct PendingFormat {
+    // The text the formatter was started on.
+    text: Text,
+    result_receiver: Receiver<Result<String, FormatError>>,
+}
+
 #[derive(Debug)]
 pub struct SessionLayout {
     pub y: Vec<f64>,
@@ -1012,6 +1839,38 @@ struct SelectionState {
     last_added_selection_index: Option<usize>,
     injected_char_stack: Vec<char>,
     highlighted_delimiter_positions: HashSet<Position>,
+    // The selections from before each call to `expand_selection`, so that they can be restored
+    // by `shrink_selection`.
+    expand_stack: Vec<(SelectionSet, Option<usize>)>,
+    expanded_selections: SelectionSet,
+}
+
+#[derive(Debug)]
+struct CompositionState {
+    // The length of the preedit text, and the byte range of the IME cursor within it. The
+    // selections select this range of the preedit text.
+    len: usize,
+    cursor: Range<usize>,
+    // The selections right after the last update, to tell whether anything else has changed them
+    // since.
+    selections: SelectionSet,
+}
+
+impl CompositionState {
+    fn preedit_start(&self, selection: Selection) -> Position {
+        let start = selection.start();
+        Position {
+            line_index: start.line_index,
+            byte_index: start.byte_index - self.cursor.start,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FindState {
+    regex: Option<Regex>,
+    is_regex: bool,
+    matches: DecorationSet,
 }
 
 #[derive(Debug)]
@@ -1034,9 +1893,27 @@ pub fn reindent(string: &str, f: impl FnOnce(usize) -> usize) -> (usize, usize,
     )
 }
 
+// Returns the prefix that continues the line comment in `line`, if the line is a comment and
+// `byte_index` lies past its start. Doc comments are continued as doc comments.
+fn line_comment_prefix(line: &str, byte_index: usize) -> Option<String> {
+    let indent_len = line.indent().map_or(line.len(), |indent| indent.len());
+    let comment = &line[indent_len..];
+    let marker = ["///", "//!", "//"]
+        .into_iter()
+        .find(|marker| comment.starts_with(marker))?;
+    if byte_index < indent_len + marker.len() {
+        return None;
+    }
+    let mut prefix = marker.to_string();
+    if comment[marker.len()..].starts_with(' ') {
+        prefix.push(' ');
+    }
+    Some(prefix)
+}
+
 fn grow_selection(
     selection: Selection,
-    lines: &[String],
+    lines: &Rope,
     mode: SelectionMode,
     word_separators: &[char],
 ) -> Selection {
@@ -1160,7 +2037,7 @@ fn new_indentation(column_count: usize) -> String {
 }
 
 fn find_highlighted_delimiter_pair(
-    lines: &[String],
+    lines: &Rope,
     position: Position,
 ) -> Option<(Position, Position)> {
     // Cursor is before an opening delimiter
@@ -1233,8 +2110,272 @@ fn find_highlighted_delimiter_pair(
     None
 }
 
+// Returns the smallest syntactic range that strictly contains the range from `start` to `end`.
+fn expand_range(
+    lines: &Rope,
+    tokens: &[Vec<Token>],
+    start: Position,
+    end: Position,
+    word_separators: &[char],
+) -> Option<(Position, Position)> {
+    let contains = |(range_start, range_end): (Position, Position)| {
+        range_start <= start && end <= range_end && (range_start, range_end) != (start, end)
+    };
+    if start == end {
+        let line = &lines[start.line_index];
+        let word = (
+            Position {
+                line_index: start.line_index,
+                byte_index: line.find_prev_word_boundary(start.byte_index, word_separators),
+            },
+            Position {
+                line_index: start.line_index,
+                byte_index: line.find_next_word_boundary(start.byte_index, word_separators),
+            },
+        );
+        if contains(word) {
+            return Some(word);
+        }
+    }
+    if start.line_index == end.line_index {
+        if let Some((string_start, string_end)) = find_string_token(
+            &lines[start.line_index],
+            &tokens[start.line_index],
+            start.byte_index,
+            end.byte_index,
+        ) {
+            let line_index = start.line_index;
+            let string = (
+                Position {
+                    line_index,
+                    byte_index: string_start,
+                },
+                Position {
+                    line_index,
+                    byte_index: string_end,
+                },
+            );
+            let contents = (
+                Position {
+                    line_index,
+                    byte_index: string_start + 1,
+                },
+                Position {
+                    line_index,
+                    byte_index: string_end - 1,
+                },
+            );
+            if string_end - string_start >= 2 && contains(contents) {
+                return Some(contents);
+            }
+            if contains(string) {
+                return Some(string);
+            }
+        }
+    }
+    if start.line_index != end.line_index && is_delimited_range(lines, start, end) {
+        let item = (
+            Position {
+                line_index: start.line_index,
+                byte_index: lines[start.line_index].indent().unwrap_or("").len(),
+            },
+            Position {
+                line_index: end.line_index,
+                byte_index: lines[end.line_index].len(),
+            },
+        );
+        if contains(item) {
+            return Some(item);
+        }
+    }
+    if let Some((opening_delimiter_position, closing_delimiter_position)) =
+        find_enclosing_delimiters(lines, start, end)
+    {
+        let opening_delimiter_len = lines[opening_delimiter_position.line_index]
+            [opening_delimiter_position.byte_index..]
+            .chars()
+            .next()
+            .unwrap()
+            .len_utf8();
+        let contents = (
+            Position {
+                line_index: opening_delimiter_position.line_index,
+                byte_index: opening_delimiter_position.byte_index + opening_delimiter_len,
+            },
+            closing_delimiter_position,
+        );
+        if contains(contents) {
+            return Some(contents);
+        }
+        let closing_delimiter_len = lines[closing_delimiter_position.line_index]
+            [closing_delimiter_position.byte_index..]
+            .chars()
+            .next()
+            .unwrap()
+            .len_utf8();
+        return Some((
+            opening_delimiter_position,
+            Position {
+                line_index: closing_delimiter_position.line_index,
+                byte_index: closing_delimiter_position.byte_index + closing_delimiter_len,
+            },
+        ));
+    }
+    let all = (
+        Position::zero(),
+        Position {
+            line_index: lines.len() - 1,
+            byte_index: lines[lines.len() - 1].len(),
+        },
+    );
+    if contains(all) {
+        return Some(all);
+    }
+    None
+}
+
+// Returns the byte range of the string token on `line` that contains the byte range from `start`
+// to `end`, if any.
+fn find_string_token(
+    line: &str,
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+) -> Option<(usize, usize)> {
+    let mut token_start = 0;
+    for token in tokens {
+        let token_end = token_start + token.len;
+        if token.kind == TokenKind::String && token_start <= start && end <= token_end {
+            let is_quoted = line[token_start..token_end]
+                .chars()
+                .next()
+                .is_some_and(|char| char == '"' || char == '\'');
+            return if is_quoted {
+                Some((token_start, token_end))
+            } else {
+                None
+            };
+        }
+        if token_end > end {
+            break;
+        }
+        token_start = token_end;
+    }
+    None
+}
+
+// Returns `true` if the range from `start` to `end` consists of an opening delimiter, its
+// matching closing delimiter, and everything in between.
+fn is_delimited_range(lines: &Rope, start: Position, end: Position) -> bool {
+    let Some(opening_delimiter) = lines[start.line_index][start.byte_index..].chars().next() else {
+        return false;
+    };
+    let Some(closing_delimiter) = lines[end.line_index][..end.byte_index].chars().next_back()
+    else {
+        return false;
+    };
+    opening_delimiter.is_opening_delimiter()
+        && closing_delimiter.is_closing_delimiter()
+        && find_closing_delimiter(
+            lines,
+            Position {
+                line_index: start.line_index,
+                byte_index: start.byte_index + opening_delimiter.len_utf8(),
+            },
+            opening_delimiter,
+        ) == Some(Position {
+            line_index: end.line_index,
+            byte_index: end.byte_index - closing_delimiter.len_utf8(),
+        })
+}
+
+// Returns the positions of the innermost pair of matching delimiters that encloses the range
+// from `start` to `end`.
+fn find_enclosing_delimiters(
+    lines: &Rope,
+    start: Position,
+    end: Position,
+) -> Option<(Position, Position)> {
+    let mut delimiter_stack = Vec::new();
+    let mut position = start;
+    loop {
+        for char in lines[position.line_index][..position.byte_index]
+            .chars()
+            .rev()
+        {
+            position.byte_index -= char.len_utf8();
+            if char.is_closing_delimiter() {
+                delimiter_stack.push(char);
+            }
+            if char.is_opening_delimiter() {
+                if let Some(closing_delimiter) = delimiter_stack.pop() {
+                    if closing_delimiter != char.opposite_delimiter().unwrap() {
+                        return None;
+                    }
+                    continue;
+                }
+                let closing_delimiter_position = find_closing_delimiter(
+                    lines,
+                    Position {
+                        line_index: position.line_index,
+                        byte_index: position.byte_index + char.len_utf8(),
+                    },
+                    char,
+                )?;
+                if closing_delimiter_position >= end {
+                    return Some((position, closing_delimiter_position));
+                }
+            }
+        }
+        if position.line_index == 0 {
+            return None;
+        }
+        position.line_index -= 1;
+        position.byte_index = lines[position.line_index].len();
+    }
+}
+
+// Returns the lines inside the block that starts on the line at `position`, or that encloses
+// `position`, excluding the lines with the delimiters themselves. Returns `None` if there are no
+// such lines.
+fn find_block_lines(lines: &Rope, position: Position) -> Option<Range<usize>> {
+    let line = &lines[position.line_index];
+    for (byte_index, char) in line.char_indices() {
+        if !char.is_opening_delimiter() {
+            continue;
+        }
+        if let Some(closing_delimiter_position) = find_closing_delimiter(
+            lines,
+            Position {
+                line_index: position.line_index,
+                byte_index: byte_index + char.len_utf8(),
+            },
+            char,
+        ) {
+            if closing_delimiter_position.line_index > position.line_index + 1 {
+                return Some(position.line_index + 1..closing_delimiter_position.line_index);
+            }
+        }
+    }
+    let (mut start, mut end) = (position, position);
+    loop {
+        let (opening_delimiter_position, closing_delimiter_position) =
+            find_enclosing_delimiters(lines, start, end)?;
+        if closing_delimiter_position.line_index > opening_delimiter_position.line_index + 1 {
+            return Some(
+                opening_delimiter_position.line_index + 1..closing_delimiter_position.line_index,
+            );
+        }
+        start = opening_delimiter_position;
+        end = Position {
+            line_index: closing_delimiter_position.line_index,
+            byte_index: closing_delimiter_position.byte_index + 1,
+        };
+    }
+}
+
 fn find_opening_delimiter(
-    lines: &[String],
+    lines: &Rope,
     position: Position,
     closing_delimiter: char,
 ) -> Option<Position> {
@@ -1268,7 +2409,7 @@ fn find_opening_delimiter(
 }
 
 fn find_closing_delimiter(
-    lines: &[String],
+    lines: &Rope,
     position: Position,
     opening_delimiter: char,
 ) -> Option<Position> {
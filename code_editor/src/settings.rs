@@ -3,6 +3,17 @@ pub struct Settings {
     pub tab_column_count: usize,
     pub fold_level: usize,
     pub word_separators: Vec<char>,
+    /// Whether typing an opening delimiter or a quote also inserts the closing one.
+    pub auto_close_pairs: bool,
+    /// Whether typing a closing brace at the start of a line outdents it to match the line with
+    /// the opening brace.
+    pub outdent_closing_brace: bool,
+    /// Whether pressing enter in a line comment starts the next line as a comment as well.
+    pub continue_comments: bool,
+    /// The command that `Session::format_document` runs. It gets the text of the document on its
+    /// standard input, and writes the formatted text to its standard output.
+    pub formatter_command: String,
+    pub formatter_args: Vec<String>,
 }
 
 impl Default for Settings {
@@ -14,6 +25,11 @@ impl Default for Settings {
                 ' ', '`', '~', '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '-', '=', '+',
                 '[', '{', ']', '}', '\\', '|', ';', ':', '\'', '"', '.', '<', '>', '/', '?', ',',
             ],
+            auto_close_pairs: true,
+            outdent_closing_brace: true,
+            continue_comments: true,
+            formatter_command: "rustfmt".into(),
+            formatter_args: vec!["--edition".into(), "2021".into()],
         }
     }
 }
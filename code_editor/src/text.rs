@@ -1,14 +1,18 @@
-use std::{
-    cmp::Ordering,
-    fmt, io,
-    io::BufRead,
-    iter,
-    ops::{Add, AddAssign, Sub, SubAssign},
+use {
+    crate::rope::Rope,
+    makepad_widgets::makepad_micro_serde::*,
+    std::{
+        cmp::Ordering,
+        fmt, io,
+        io::BufRead,
+        iter,
+        ops::{Add, AddAssign, Sub, SubAssign},
+    },
 };
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub struct Text {
-    lines: Vec<String>,
+    lines: Rope,
 }
 
 impl Text {
@@ -18,7 +22,7 @@ impl Text {
 
     pub fn newline() -> Self {
         Self {
-            lines: vec![String::new(), String::new()],
+            lines: Rope::from(vec![String::new(), String::new()]),
         }
     }
 
@@ -53,7 +57,7 @@ impl Text {
         }
     }
 
-    pub fn as_lines(&self) -> &[String] {
+    pub fn as_lines(&self) -> &Rope {
         &self.lines
     }
 
@@ -66,13 +70,15 @@ impl Text {
             lines.reserve(end.line_index - start.line_index + 1);
             lines.push(self.lines[start.line_index][start.byte_index..].to_string());
             lines.extend(
-                self.lines[start.line_index + 1..end.line_index]
-                    .iter()
+                self.lines
+                    .range(start.line_index + 1..end.line_index)
                     .cloned(),
             );
             lines.push(self.lines[end.line_index][..end.byte_index].to_string());
         }
-        Text { lines }
+        Text {
+            lines: Rope::from(lines),
+        }
     }
 
     pub fn apply_change(&mut self, change: Change) {
@@ -83,26 +89,28 @@ impl Text {
     }
 
     pub fn into_lines(self) -> Vec<String> {
-        self.lines
+        self.lines.iter().cloned().collect()
     }
 
-    fn insert(&mut self, point: Position, mut text: Self) {
+    fn insert(&mut self, point: Position, text: Self) {
         if text.length().line_count == 0 {
             self.lines[point.line_index].replace_range(
                 point.byte_index..point.byte_index,
                 text.lines.first().unwrap(),
             );
         } else {
-            text.lines
+            let line = &self.lines[point.line_index];
+            let mut lines = text.into_lines();
+            lines
                 .first_mut()
                 .unwrap()
-                .replace_range(..0, &self.lines[point.line_index][..point.byte_index]);
-            text.lines
+                .replace_range(..0, &line[..point.byte_index]);
+            lines
                 .last_mut()
                 .unwrap()
-                .push_str(&self.lines[point.line_index][point.byte_index..]);
+                .push_str(&line[point.byte_index..]);
             self.lines
-                .splice(point.line_index..point.line_index + 1, text.lines);
+                .splice(point.line_index..point.line_index + 1, lines);
         }
     }
 
@@ -122,15 +130,16 @@ impl Text {
 impl Default for Text {
     fn default() -> Self {
         Self {
-            lines: vec![String::new()],
+            lines: Rope::from(vec![String::new()]),
         }
     }
 }
 
 impl fmt::Display for Text {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let (last_line, remaining_lines) = self.lines.split_last().unwrap();
-        for line in remaining_lines {
+        let mut lines = self.lines.iter();
+        let last_line = lines.next_back().unwrap();
+        for line in lines {
             writeln!(f, "{}", line)?;
         }
         write!(f, "{}", last_line)
@@ -140,7 +149,7 @@ impl fmt::Display for Text {
 impl From<char> for Text {
     fn from(char: char) -> Self {
         Self {
-            lines: vec![String::from(char)],
+            lines: Rope::from(vec![String::from(char)]),
         }
     }
 }
@@ -174,7 +183,7 @@ impl FromIterator<char> for Text {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub struct Edit {
     pub change: Change,
     pub drift: Drift,
@@ -189,7 +198,7 @@ impl Edit {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub enum Change {
     Insert(Position, Text),
     Delete(Position, Length),
@@ -204,7 +213,7 @@ impl Change {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
 pub struct Position {
     pub line_index: usize,
     pub byte_index: usize,
@@ -279,7 +288,7 @@ impl Sub for Position {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
 pub struct Length {
     pub line_count: usize,
     pub byte_count: usize,
@@ -339,7 +348,7 @@ impl SubAssign for Length {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub enum Drift {
     Before,
     After,
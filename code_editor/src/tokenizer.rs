@@ -1,472 +1,199 @@
-use crate::{
-    text::{Change, Text},
-    token::TokenKind,
-    Token,
+use {
+    crate::{
+        dirty_lines::DirtyLines,
+        language::{Json, LiveDesign, Markdown, Rust, Toml},
+        text::{Change, Text},
+        token::TokenKind,
+        Token,
+    },
+    std::fmt,
 };
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// Incrementally tokenizes a document, line by line.
+///
+/// For each line we remember the state the language was in at the start and at the end of that
+/// line. When the document changes, only the lines whose start state changed need to be
+/// tokenized again, starting from the first line that changed.
 pub struct Tokenizer {
-    state: Vec<Option<(State, State)>>,
+    lines: Box<dyn LineTokenizer>,
+    tokenized_line_count: usize,
 }
 
 impl Tokenizer {
     pub fn new(line_count: usize) -> Self {
-        Self {
-            state: (0..line_count).map(|_| None).collect(),
-        }
-    }
-
-    pub fn apply_change(&mut self, change: &Change) {
-        match *change {
-            Change::Insert(point, ref text) => {
-                self.state[point.line_index] = None;
-                let line_count = text.length().line_count;
-                if line_count > 0 {
-                    let line = point.line_index + 1;
-                    self.state.splice(line..line, (0..line_count).map(|_| None));
-                }
-            }
-            Change::Delete(start, length) => {
-                self.state[start.line_index] = None;
-                let line_count = length.line_count;
-                if line_count > 0 {
-                    let start_line = start.line_index + 1;
-                    let end_line = start_line + line_count;
-                    self.state.drain(start_line..end_line);
-                }
-            }
-        }
+        Self::with_language(Rust, line_count)
     }
 
-    pub fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
-        let mut state = State::default();
-        for line in 0..text.as_lines().len() {
-            match self.state[line] {
-                Some((start_state, end_state)) if state == start_state => {
-                    state = end_state;
-                }
-                _ => {
-                    let start_state = state;
-                    let mut new_tokens = Vec::new();
-                    let mut cursor = Cursor::new(&text.as_lines()[line]);
-                    loop {
-                        let (next_state, token) = state.next(&mut cursor);
-                        state = next_state;
-                        match token {
-                            Some(token) => new_tokens.push(token),
-                            None => break,
-                        }
-                    }
-                    self.state[line] = Some((start_state, state));
-                    tokens[line] = new_tokens;
-                }
-            }
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum State {
-    Initial(InitialState),
-    BlockCommentTail(BlockCommentTailState),
-    DoubleQuotedStringTail(DoubleQuotedStringTailState),
-    RawDoubleQuotedStringTail(RawDoubleQuotedStringTailState),
-}
-
-impl Default for State {
-    fn default() -> State {
-        State::Initial(InitialState)
-    }
-}
-
-impl State {
-    pub fn next(self, cursor: &mut Cursor) -> (State, Option<Token>) {
-        if cursor.peek(0) == '\0' {
-            return (self, None);
-        }
-        let start = cursor.index;
-        let (next_state, kind) = match self {
-            State::Initial(state) => state.next(cursor),
-            State::BlockCommentTail(state) => state.next(cursor),
-            State::DoubleQuotedStringTail(state) => state.next(cursor),
-            State::RawDoubleQuotedStringTail(state) => state.next(cursor),
-        };
-        let end = cursor.index;
-        assert!(start < end);
-        (
-            next_state,
-            Some(Token {
-                len: end - start,
-                kind,
+    pub fn with_language<L>(language: L, line_count: usize) -> Self
+    where
+        L: Language + 'static,
+    {
+        Self {
+            lines: Box::new(LanguageTokenizer {
+                language,
+                state: (0..line_count).map(|_| None).collect(),
+                dirty_lines: DirtyLines::all(line_count),
             }),
-        )
+            tokenized_line_count: 0,
+        }
     }
-}
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct InitialState;
-
-impl InitialState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
-            ('r', '#', '"') | ('r', '#', '#') => self.raw_string(cursor),
-            ('b', 'r', '"') | ('b', 'r', '#') => self.raw_byte_string(cursor),
-            ('/', '/', _) => self.line_comment(cursor),
-            ('/', '*', _) => self.block_comment(cursor),
-            ('b', '\'', _) => self.byte(cursor),
-            ('b', '"', _) => self.byte_string(cursor),
-            ('!', '=', _)
-            | ('%', '=', _)
-            | ('&', '&', _)
-            | ('&', '=', _)
-            | ('*', '=', _)
-            | ('+', '=', _)
-            | ('-', '=', _)
-            | ('-', '>', _)
-            | ('.', '.', _)
-            | ('/', '=', _)
-            | (':', ':', _)
-            | ('<', '<', _)
-            | ('<', '=', _)
-            | ('=', '=', _)
-            | ('=', '>', _)
-            | ('>', '=', _)
-            | ('>', '>', _)
-            | ('^', '=', _)
-            | ('|', '=', _)
-            | ('|', '|', _) => {
-                cursor.skip(2);
-                (State::Initial(InitialState), TokenKind::Punctuator)
-            }
-            ('\'', _, _) => self.char_or_lifetime(cursor),
-            ('"', _, _) => self.string(cursor),
-            ('(', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            (')', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('[', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            (']', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('{', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('}', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('.', char, _) if char.is_digit(10) => self.number(cursor),
-            ('!', _, _)
-            | ('#', _, _)
-            | ('$', _, _)
-            | ('%', _, _)
-            | ('&', _, _)
-            | ('*', _, _)
-            | ('+', _, _)
-            | (',', _, _)
-            | ('-', _, _)
-            | ('.', _, _)
-            | ('/', _, _)
-            | (':', _, _)
-            | (';', _, _)
-            | ('<', _, _)
-            | ('=', _, _)
-            | ('>', _, _)
-            | ('?', _, _)
-            | ('@', _, _)
-            | ('^', _, _)
-            | ('_', _, _)
-            | ('|', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Punctuator)
-            }
-            (char, _, _) if char.is_identifier_start() => self.identifier_or_keyword(cursor),
-            (char, _, _) if char.is_digit(10) => self.number(cursor),
-            (char, _, _) if char.is_whitespace() => self.whitespace(cursor),
-            _ => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Unknown)
+    /// Picks a language based on a file extension, falling back to Rust for unknown extensions.
+    pub fn for_extension(extension: &str, line_count: usize) -> Self {
+        match extension {
+            "toml" => Self::with_language(Toml, line_count),
+            "json" => Self::with_language(Json, line_count),
+            "md" | "markdown" => Self::with_language(Markdown, line_count),
+            "live" | "glsl" | "vert" | "frag" | "wgsl" => {
+                Self::with_language(LiveDesign, line_count)
             }
+            _ => Self::with_language(Rust, line_count),
         }
     }
 
-    fn line_comment(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '/' && cursor.peek(1) == '/');
-        cursor.skip(2);
-        while cursor.skip_if(|ch| ch != '\0') {}
-        (State::Initial(InitialState), TokenKind::Comment)
-    }
-
-    fn block_comment(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '/' && cursor.peek(1) == '*');
-        cursor.skip(2);
-        BlockCommentTailState { depth: 0 }.next(cursor)
-    }
-
-    fn identifier_or_keyword(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0).is_identifier_start());
-        let start = cursor.index;
-        cursor.skip(1);
-        while cursor.skip_if(|char| char.is_identifier_continue()) {}
-        let end = cursor.index;
-        let string = &cursor.string[start..end];
-        (
-            State::Initial(InitialState),
-            match string {
-                "else" | "if" | "match" | "return" => TokenKind::BranchKeyword,
-                "break" | "continue" | "for" | "loop" | "while" => TokenKind::LoopKeyword,
-                "Self" | "as" | "async" | "await" | "const" | "crate" | "dyn" | "enum"
-                | "extern" | "false" | "fn" | "impl" | "in" | "let" | "mod" | "move" | "mut"
-                | "pub" | "ref" | "self" | "static" | "struct" | "super" | "trait" | "true"
-                | "type" | "unsafe" | "use" | "where" | "usize" | "isize" | "u8" | "u16"
-                | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "vec2" | "vec3" | "vec4"
-                | "bool" | "f32" | "f64" => TokenKind::OtherKeyword,
-                _ => {
-                    let mut chars = string.chars();
-                    if chars.next().unwrap().is_uppercase() {
-                        match chars.next() {
-                            Some(char) if char.is_uppercase() => TokenKind::Constant,
-                            _ => TokenKind::Typename,
-                        }
-                    } else if cursor.peek(0) == '(' {
-                        TokenKind::Function
-                    } else {
-                        TokenKind::Identifier
-                    }
-                }
-            },
-        )
+    pub fn language_name(&self) -> &'static str {
+        self.lines.language_name()
     }
 
-    fn number(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        match (cursor.peek(0), cursor.peek(1)) {
-            ('0', 'b') => {
-                cursor.skip(2);
-                if !cursor.skip_digits(2) {
-                    return (State::Initial(InitialState), TokenKind::Unknown);
-                }
-                return (State::Initial(InitialState), TokenKind::Number);
-            }
-            ('0', 'o') => {
-                cursor.skip(2);
-                if !cursor.skip_digits(8) {
-                    return (State::Initial(InitialState), TokenKind::Unknown);
-                }
-                return (State::Initial(InitialState), TokenKind::Number);
-            }
-            ('0', 'x') => {
-                cursor.skip(2);
-                if !cursor.skip_digits(16) {
-                    return (State::Initial(InitialState), TokenKind::Unknown);
-                }
-                return (State::Initial(InitialState), TokenKind::Number);
-            }
-            _ => {
-                cursor.skip_digits(10);
-                match cursor.peek(0) {
-                    '.' if cursor.peek(1) != '.' && !cursor.peek(0).is_identifier_start() => {
-                        cursor.skip(1);
-                        if cursor.skip_digits(10) {
-                            if cursor.peek(0) == 'E' || cursor.peek(0) == 'e' {
-                                if !cursor.skip_exponent() {
-                                    return (State::Initial(InitialState), TokenKind::Unknown);
-                                }
-                            }
-                        }
-                        cursor.skip_suffix();
-                        return (State::Initial(InitialState), TokenKind::Number);
-                    }
-                    'E' | 'e' => {
-                        if !cursor.skip_exponent() {
-                            return (State::Initial(InitialState), TokenKind::Unknown);
-                        }
-                        cursor.skip_suffix();
-                        return (State::Initial(InitialState), TokenKind::Number);
-                    }
-                    _ => {
-                        cursor.skip_suffix();
-                        return (State::Initial(InitialState), TokenKind::Number);
-                    }
-                }
-            }
-        };
+    pub fn apply_change(&mut self, change: &Change) {
+        self.lines.apply_change(change)
     }
 
-    fn char_or_lifetime(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        if cursor.peek(1).is_identifier_start() && cursor.peek(2) != '\'' {
-            debug_assert!(cursor.peek(0) == '\'');
-            cursor.skip(2);
-            while cursor.skip_if(|ch| ch.is_identifier_continue()) {}
-            if cursor.peek(0) == '\'' {
-                cursor.skip(1);
-                cursor.skip_suffix();
-                (State::Initial(InitialState), TokenKind::String)
-            } else {
-                (State::Initial(InitialState), TokenKind::String)
-            }
-        } else {
-            self.single_quoted_string(cursor)
-        }
+    pub fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
+        self.update_until(text, tokens, usize::MAX)
     }
 
-    fn byte(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'b');
-        cursor.skip(1);
-        self.single_quoted_string(cursor)
+    /// Like `update`, but only tokenizes the lines before `line_end`. The lines after it are
+    /// tokenized by a later update.
+    pub fn update_until(&mut self, text: &Text, tokens: &mut [Vec<Token>], line_end: usize) {
+        self.tokenized_line_count += self.lines.update_until(text, tokens, line_end);
     }
 
-    fn string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        self.double_quoted_string(cursor)
+    /// The number of lines that were tokenized so far, counting lines that were tokenized again
+    /// after they changed.
+    pub fn tokenized_line_count(&self) -> usize {
+        self.tokenized_line_count
     }
+}
 
-    fn byte_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'b');
-        cursor.skip(1);
-        self.double_quoted_string(cursor)
+impl fmt::Debug for Tokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tokenizer")
+            .field("language", &self.language_name())
+            .finish()
     }
+}
 
-    fn raw_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'r');
-        cursor.skip(1);
-        self.raw_double_quoted_string(cursor)
-    }
+/// A language that can be tokenized one line at a time.
+///
+/// Constructs that span multiple lines, such as block comments, are handled by ending the line in
+/// a state other than the default one. That state is then passed back in for the next line.
+pub trait Language {
+    type State: Clone + Copy + fmt::Debug + Default + Eq;
 
-    fn raw_byte_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'b' && cursor.peek(1) == 'r');
-        cursor.skip(2);
-        self.raw_double_quoted_string(cursor)
-    }
+    fn name(&self) -> &'static str;
 
-    fn single_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '\'');
-        cursor.skip(1);
-        loop {
-            match (cursor.peek(0), cursor.peek(1)) {
-                ('\'', _) => {
-                    cursor.skip(1);
-                    cursor.skip_suffix();
-                    break;
-                }
-                ('\0', _) => return (State::Initial(InitialState), TokenKind::Unknown),
-                ('\\', '\'') | ('\\', '\\') => cursor.skip(2),
-                _ => cursor.skip(1),
-            }
-        }
-        (State::Initial(InitialState), TokenKind::String)
-    }
+    /// Returns the kind of the next token, and the state to continue in after it. The cursor is
+    /// never at the end of the line when this is called, and must be advanced by at least one
+    /// char.
+    fn next(&self, state: Self::State, cursor: &mut Cursor) -> (Self::State, TokenKind);
+}
 
-    fn double_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '"');
-        cursor.skip(1);
-        DoubleQuotedStringTailState.next(cursor)
-    }
+trait LineTokenizer {
+    fn language_name(&self) -> &'static str;
 
-    fn raw_double_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        let mut start_hash_count = 0;
-        while cursor.skip_if(|ch| ch == '#') {
-            start_hash_count += 1;
-        }
-        RawDoubleQuotedStringTailState { start_hash_count }.next(cursor)
-    }
+    fn apply_change(&mut self, change: &Change);
 
-    fn whitespace(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0).is_whitespace());
-        cursor.skip(1);
-        while cursor.skip_if(|char| char.is_whitespace()) {}
-        (State::Initial(InitialState), TokenKind::Whitespace)
-    }
+    // Returns the number of lines that were tokenized.
+    fn update_until(&mut self, text: &Text, tokens: &mut [Vec<Token>], line_end: usize) -> usize;
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct BlockCommentTailState {
-    depth: usize,
+struct LanguageTokenizer<L: Language> {
+    language: L,
+    state: Vec<Option<(L::State, L::State)>>,
+    dirty_lines: DirtyLines,
 }
 
-impl BlockCommentTailState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        let mut state = self;
-        loop {
-            match (cursor.peek(0), cursor.peek(1)) {
-                ('/', '*') => {
-                    cursor.skip(2);
-                    state.depth += 1;
-                }
-                ('*', '/') => {
-                    cursor.skip(2);
-                    if state.depth == 0 {
-                        break (State::Initial(InitialState), TokenKind::Comment);
-                    }
-                    state.depth -= 1;
-                }
-                ('\0', _) => {
-                    break (State::BlockCommentTail(state), TokenKind::Comment);
-                }
-                _ => cursor.skip(1),
-            }
-        }
+impl<L: Language> LineTokenizer for LanguageTokenizer<L> {
+    fn language_name(&self) -> &'static str {
+        self.language.name()
     }
-}
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct DoubleQuotedStringTailState;
-
-impl DoubleQuotedStringTailState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        loop {
-            match (cursor.peek(0), cursor.peek(1)) {
-                ('"', _) => {
-                    cursor.skip(1);
-                    cursor.skip_suffix();
-                    break (State::Initial(InitialState), TokenKind::String);
+    fn apply_change(&mut self, change: &Change) {
+        self.dirty_lines.apply_change(change);
+        match *change {
+            Change::Insert(point, ref text) => {
+                self.state[point.line_index] = None;
+                let line_count = text.length().line_count;
+                if line_count > 0 {
+                    let line = point.line_index + 1;
+                    self.state.splice(line..line, (0..line_count).map(|_| None));
                 }
-                ('\0', _) => {
-                    break (
-                        State::DoubleQuotedStringTail(DoubleQuotedStringTailState),
-                        TokenKind::String,
-                    );
+            }
+            Change::Delete(start, length) => {
+                self.state[start.line_index] = None;
+                let line_count = length.line_count;
+                if line_count > 0 {
+                    let start_line = start.line_index + 1;
+                    let end_line = start_line + line_count;
+                    self.state.drain(start_line..end_line);
                 }
-                ('\\', '"') | ('\\', '\\') => cursor.skip(2),
-                _ => cursor.skip(1),
             }
         }
     }
-}
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct RawDoubleQuotedStringTailState {
-    start_hash_count: usize,
-}
-
-impl RawDoubleQuotedStringTailState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        loop {
-            match cursor.peek(0) {
-                '"' => {
-                    cursor.skip(1);
-                    let mut end_hash_count = 0;
-                    while end_hash_count < self.start_hash_count && cursor.skip_if(|ch| ch == '#') {
-                        end_hash_count += 1;
-                    }
-                    if end_hash_count == self.start_hash_count {
-                        cursor.skip_suffix();
-                        break (State::Initial(InitialState), TokenKind::String);
+    fn update_until(&mut self, text: &Text, tokens: &mut [Vec<Token>], line_end: usize) -> usize {
+        let Some((first_line, last_line)) = self.dirty_lines.range() else {
+            return 0;
+        };
+        let mut tokenized_line_count = 0;
+        let mut state = match first_line.checked_sub(1) {
+            Some(prev_line) => self.state[prev_line].unwrap().1,
+            None => L::State::default(),
+        };
+        let line_count = text.as_lines().len();
+        for (line, line_tokens) in tokens
+            .iter_mut()
+            .enumerate()
+            .take(line_count)
+            .skip(first_line)
+        {
+            if line >= line_end {
+                self.dirty_lines.mark_clean_until(line);
+                return tokenized_line_count;
+            }
+            match self.state[line] {
+                Some((start_state, end_state)) if state == start_state => {
+                    // The lines after the last changed one were tokenized with the same start
+                    // state before, so they are still up to date.
+                    if line > last_line {
+                        break;
                     }
+                    state = end_state;
                 }
-                '\0' => {
-                    break (State::RawDoubleQuotedStringTail(self), TokenKind::String);
+                _ => {
+                    let start_state = state;
+                    let mut new_tokens = Vec::new();
+                    let mut cursor = Cursor::new(&text.as_lines()[line]);
+                    while cursor.peek(0) != '\0' {
+                        let start = cursor.index;
+                        let (next_state, kind) = self.language.next(state, &mut cursor);
+                        let end = cursor.index;
+                        assert!(start < end);
+                        state = next_state;
+                        new_tokens.push(Token {
+                            len: end - start,
+                            kind,
+                        });
+                    }
+                    self.state[line] = Some((start_state, state));
+                    *line_tokens = new_tokens;
+                    tokenized_line_count += 1;
                 }
-                _ => cursor.skip(1),
             }
         }
+        self.dirty_lines.clear();
+        tokenized_line_count
     }
 }
 
@@ -481,18 +208,30 @@ impl<'a> Cursor<'a> {
         Cursor { string, index: 0 }
     }
 
-    fn peek(&self, index: usize) -> char {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn slice(&self, start: usize, end: usize) -> &'a str {
+        &self.string[start..end]
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.string[self.index..]
+    }
+
+    pub fn peek(&self, index: usize) -> char {
         self.string[self.index..].chars().nth(index).unwrap_or('\0')
     }
 
-    fn skip(&mut self, count: usize) {
+    pub fn skip(&mut self, count: usize) {
         self.index = self.string[self.index..]
             .char_indices()
             .nth(count)
             .map_or(self.string.len(), |(index, _)| self.index + index);
     }
 
-    fn skip_if<P>(&mut self, predicate: P) -> bool
+    pub fn skip_if<P>(&mut self, predicate: P) -> bool
     where
         P: FnOnce(char) -> bool,
     {
@@ -504,7 +243,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn skip_exponent(&mut self) -> bool {
+    pub fn skip_exponent(&mut self) -> bool {
         debug_assert!(self.peek(0) == 'E' || self.peek(0) == 'e');
         self.skip(1);
         if self.peek(0) == '+' || self.peek(0) == '-' {
@@ -513,7 +252,7 @@ impl<'a> Cursor<'a> {
         self.skip_digits(10)
     }
 
-    fn skip_digits(&mut self, radix: u32) -> bool {
+    pub fn skip_digits(&mut self, radix: u32) -> bool {
         let mut has_skip_digits = false;
         loop {
             match self.peek(0) {
@@ -530,7 +269,7 @@ impl<'a> Cursor<'a> {
         has_skip_digits
     }
 
-    fn skip_suffix(&mut self) -> bool {
+    pub fn skip_suffix(&mut self) -> bool {
         if self.peek(0).is_identifier_start() {
             self.skip(1);
             while self.skip_if(|char| char.is_identifier_continue()) {}
@@ -0,0 +1,121 @@
+use makepad_code_editor::{
+    completion::{Completion, Hover},
+    decoration::DecorationSet,
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+fn session(text: &str) -> Session {
+    Session::new(Document::new(Text::from(text), DecorationSet::new()))
+}
+
+fn text(session: &Session) -> String {
+    session.document().as_text().to_string()
+}
+
+fn position(line_index: usize, byte_index: usize) -> Position {
+    Position {
+        line_index,
+        byte_index,
+    }
+}
+
+fn completion(label: &str) -> Completion {
+    Completion {
+        label: label.into(),
+        detail: None,
+        text: label.into(),
+    }
+}
+
+fn labels(session: &Session) -> Vec<String> {
+    session
+        .completions()
+        .into_iter()
+        .map(|completion| completion.label)
+        .collect()
+}
+
+#[test]
+fn test_completions_are_filtered_by_typed_prefix() {
+    let mut session = session("x.");
+    session.set_selection(position(0, 2), Affinity::Before, SelectionMode::Simple);
+    session.show_completions(
+        position(0, 2),
+        vec![completion("len"), completion("push"), completion("Pop")],
+    );
+    assert_eq!(labels(&session), ["len", "push", "Pop"]);
+    session.insert(Text::from("p"));
+    session.handle_changes();
+    assert_eq!(labels(&session), ["push", "Pop"]);
+    session.select_next_completion();
+    assert_eq!(session.selected_completion_index(), 1);
+    session.select_next_completion();
+    assert_eq!(session.selected_completion_index(), 0);
+    session.select_prev_completion();
+    assert_eq!(session.selected_completion_index(), 1);
+
+    // Leaving the word hides the completions.
+    session.insert(Text::from(" "));
+    session.handle_changes();
+    assert!(session.completions().is_empty());
+}
+
+#[test]
+fn test_accept_completion_replaces_word() {
+    let mut session = session("x.pu;");
+    session.set_selection(position(0, 4), Affinity::Before, SelectionMode::Simple);
+    session.show_completions(
+        position(0, 2),
+        vec![
+            completion("pop"),
+            Completion {
+                label: "push".into(),
+                detail: Some("fn(&mut self, T)".into()),
+                text: "push()".into(),
+            },
+        ],
+    );
+    assert!(session.accept_completion());
+    assert_eq!(text(&session), "x.push();");
+    assert_eq!(session.selections()[0].cursor.position, position(0, 8));
+    assert!(session.completions().is_empty());
+    assert!(!session.accept_completion());
+
+    // Accepting a completion can be undone on its own.
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "x.pu;");
+}
+
+#[test]
+fn test_hover() {
+    let session = session("fn main() {}");
+    assert!(!session.clear_hover());
+    session.set_hover(Hover {
+        position: position(0, 3),
+        text: "fn main()".into(),
+    });
+    assert_eq!(session.hover().unwrap().text, "fn main()");
+    assert!(session.clear_hover());
+    assert!(session.hover().is_none());
+}
+
+#[test]
+fn test_changed_inlays_are_laid_out() {
+    use makepad_code_editor::inlays::InlineInlay;
+
+    let mut session = session("let x = 1;");
+    session.set_wrap_column(Some(12));
+    session.handle_changes();
+    session.layout_until(1);
+    assert_eq!(session.layout().line(0).row_count(), 1);
+    session
+        .document()
+        .replace_inline_inlays(vec![(position(0, 5), InlineInlay::Text(": i32".into()))]);
+    session.handle_changes();
+    session.layout_until(1);
+    assert_eq!(session.layout().line(0).row_count(), 2);
+}
@@ -0,0 +1,121 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+fn session(text: &str) -> Session {
+    Session::new(Document::new(Text::from(text), DecorationSet::new()))
+}
+
+fn text(session: &Session) -> String {
+    session.document().as_text().to_string()
+}
+
+fn position(line_index: usize, byte_index: usize) -> Position {
+    Position {
+        line_index,
+        byte_index,
+    }
+}
+
+fn selected_ranges(session: &Session) -> Vec<(usize, usize)> {
+    session
+        .selections()
+        .iter()
+        .map(|selection| (selection.start().byte_index, selection.end().byte_index))
+        .collect()
+}
+
+fn composition_ranges(session: &Session) -> Vec<(usize, usize)> {
+    session
+        .composition()
+        .iter()
+        .map(|decoration| (decoration.start().byte_index, decoration.end().byte_index))
+        .collect()
+}
+
+#[test]
+fn test_update_replaces_preedit() {
+    let mut session = session("ab");
+    session.set_selection(position(0, 1), Affinity::Before, SelectionMode::Simple);
+    session.start_composition();
+    assert!(session.is_composing());
+    session.update_composition("n", 1..1);
+    assert_eq!(text(&session), "anb");
+    session.update_composition("ni", 2..2);
+    assert_eq!(text(&session), "anib");
+    assert_eq!(selected_ranges(&session), [(3, 3)]);
+    assert_eq!(composition_ranges(&session), [(1, 3)]);
+
+    // The IME cursor can select the segment being converted.
+    session.update_composition("你好", 0..3);
+    assert_eq!(text(&session), "a你好b");
+    assert_eq!(selected_ranges(&session), [(1, 4)]);
+    assert_eq!(composition_ranges(&session), [(1, 7)]);
+
+    session.commit_composition("你好");
+    assert!(!session.is_composing());
+    assert_eq!(text(&session), "a你好b");
+    assert_eq!(selected_ranges(&session), [(7, 7)]);
+    assert!(session.composition().is_empty());
+}
+
+#[test]
+fn test_commit_is_a_single_undo() {
+    let mut session = session("x");
+    session.set_selection(position(0, 1), Affinity::Before, SelectionMode::Simple);
+    session.insert(Text::from("y"));
+    session.handle_changes();
+    session.start_composition();
+    session.update_composition("k", 1..1);
+    session.update_composition("ka", 2..2);
+    session.update_composition("か", 3..3);
+    session.commit_composition("か");
+    // Typing after a commit does not merge into it.
+    session.insert(Text::from("z"));
+    session.handle_changes();
+    assert_eq!(text(&session), "xyかz");
+
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "xyか");
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "xy");
+    assert_eq!(selected_ranges(&session), [(2, 2)]);
+    assert!(session.redo());
+    session.handle_changes();
+    assert_eq!(text(&session), "xyか");
+}
+
+#[test]
+fn test_cancel_restores_text() {
+    let mut session = session("abc");
+    session.set_selection(position(0, 1), Affinity::Before, SelectionMode::Simple);
+    session.add_selection(position(0, 2), Affinity::Before, SelectionMode::Simple);
+    session.update_composition("xy", 2..2);
+    assert_eq!(text(&session), "axybxyc");
+    assert_eq!(composition_ranges(&session), [(1, 3), (4, 6)]);
+    session.cancel_composition();
+    assert!(!session.is_composing());
+    assert_eq!(text(&session), "abc");
+    assert_eq!(selected_ranges(&session), [(1, 1), (2, 2)]);
+}
+
+#[test]
+fn test_moving_selections_ends_composition() {
+    let mut session = session("ab");
+    session.set_selection(position(0, 2), Affinity::Before, SelectionMode::Simple);
+    session.update_composition("x\ny", 2..2);
+    // The preedit text stays on one line.
+    assert_eq!(text(&session), "abxy");
+    session.move_left(true);
+    assert!(!session.is_composing());
+    assert!(session.composition().is_empty());
+    // The preedit text is kept, and a new composition starts at the moved selection.
+    session.update_composition("z", 1..1);
+    assert_eq!(text(&session), "abxzy");
+}
@@ -0,0 +1,168 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    find::{self, FindQuery},
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+fn session(text: &str) -> Session {
+    Session::new(Document::new(Text::from(text), DecorationSet::new()))
+}
+
+fn selected_strings(session: &Session) -> Vec<String> {
+    let text = session.document().as_text();
+    session
+        .selections()
+        .iter()
+        .map(|selection| {
+            text.slice(selection.start(), selection.length())
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_find_next_and_prev_wrap_around() {
+    let session = session("foo bar\nbar foo\nfoo");
+    assert_eq!(session.find(&FindQuery::new("foo")).unwrap(), 3);
+    assert!(session.find_next());
+    assert_eq!(session.selections()[0].start().line_index, 0);
+    assert!(session.find_next());
+    assert_eq!(session.selections()[0].start().line_index, 1);
+    assert!(session.find_next());
+    assert!(session.find_next());
+    assert_eq!(session.selections()[0].start().line_index, 0);
+    assert!(session.find_prev());
+    assert_eq!(session.selections()[0].start().line_index, 2);
+    assert_eq!(selected_strings(&session), ["foo"]);
+}
+
+#[test]
+fn test_find_options() {
+    let session = session("Foo foo food");
+    assert_eq!(session.find(&FindQuery::new("foo")).unwrap(), 2);
+    let query = FindQuery {
+        case_insensitive: true,
+        ..FindQuery::new("foo")
+    };
+    assert_eq!(session.find(&query).unwrap(), 3);
+    let query = FindQuery {
+        whole_word: true,
+        ..query
+    };
+    assert_eq!(session.find(&query).unwrap(), 2);
+    let query = FindQuery {
+        is_regex: true,
+        ..FindQuery::new("fo+d?")
+    };
+    assert_eq!(session.find(&query).unwrap(), 2);
+    assert!(session
+        .find(&FindQuery {
+            is_regex: true,
+            ..FindQuery::new("(")
+        })
+        .is_err());
+}
+
+#[test]
+fn test_select_all_matches() {
+    let session = session("a1 b2\nc3");
+    let query = FindQuery {
+        is_regex: true,
+        ..FindQuery::new("[a-z]\\d")
+    };
+    session.find(&query).unwrap();
+    assert!(session.select_all_matches());
+    assert_eq!(selected_strings(&session), ["a1", "b2", "c3"]);
+}
+
+#[test]
+fn test_replace_and_undo() {
+    let mut session = session("let a = 1;\nlet b = a;");
+    let query = FindQuery {
+        is_regex: true,
+        ..FindQuery::new("let (\\w+)")
+    };
+    session.find(&query).unwrap();
+    session.find_next();
+    session.replace("const ${1}_");
+    assert_eq!(
+        session.document().as_text().to_string(),
+        "const a_ = 1;\nlet b = a;"
+    );
+    // The next match is selected after replacing.
+    assert_eq!(selected_strings(&session), ["let b"]);
+    assert_eq!(session.find_matches().len(), 1);
+
+    assert_eq!(session.replace_all("var $1"), 1);
+    assert_eq!(
+        session.document().as_text().to_string(),
+        "const a_ = 1;\nvar b = a;"
+    );
+    assert!(session.find_matches().is_empty());
+    session.undo();
+    session.handle_changes();
+    session.undo();
+    session.handle_changes();
+    assert_eq!(
+        session.document().as_text().to_string(),
+        "let a = 1;\nlet b = a;"
+    );
+}
+
+#[test]
+fn test_replace_all_is_single_undo_step() {
+    let mut session = session("x x\nx");
+    session.find(&FindQuery::new("x")).unwrap();
+    assert_eq!(session.replace_all("yy"), 3);
+    assert_eq!(session.document().as_text().to_string(), "yy yy\nyy");
+    session.undo();
+    session.handle_changes();
+    assert_eq!(session.document().as_text().to_string(), "x x\nx");
+    assert_eq!(session.find_matches().len(), 3);
+}
+
+#[test]
+fn test_replace_all_literal_leaves_start_alone() {
+    let mut session = session("a x\nx");
+    session.find(&FindQuery::new("x")).unwrap();
+    assert_eq!(session.replace_all("yy"), 2);
+    assert_eq!(session.document().as_text().to_string(), "a yy\nyy");
+}
+
+#[test]
+fn test_matches_are_updated_after_edits() {
+    let mut session = session("foo bar\nbar\nfo bar foo\nfoo");
+    let query = FindQuery::new("foo");
+    session.find(&query).unwrap();
+    // Completes a match on the third line and adds a new line with another one.
+    session.set_selection(
+        Position {
+            line_index: 2,
+            byte_index: 2,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+    );
+    session.insert(Text::from("o\nfoo"));
+    session.handle_changes();
+    // Breaks up the match on the first line.
+    session.set_selection(
+        Position {
+            line_index: 0,
+            byte_index: 1,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+    );
+    session.insert(Text::from(" "));
+    session.handle_changes();
+
+    let text = session.document().as_text();
+    assert_eq!(text.to_string(), "f oo bar\nbar\nfoo\nfoo bar foo\nfoo");
+    let expected = find::find_matches(text.as_lines(), &query.to_regex().unwrap());
+    assert_eq!(session.find_matches().len(), 4);
+    assert_eq!(&*session.find_matches(), expected.as_decorations());
+}
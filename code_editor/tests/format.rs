@@ -0,0 +1,258 @@
+use {
+    makepad_code_editor::{
+        decoration::DecorationSet,
+        format::{self, FormatError, Hunk},
+        selection::Affinity,
+        session::SelectionMode,
+        text::{Position, Text},
+        Document, Session, Settings,
+    },
+    std::{rc::Rc, thread, time::Duration},
+};
+
+fn session_with_settings(text: &str, settings: Settings) -> Session {
+    let session = Session::with_settings(
+        Document::new(Text::from(text), DecorationSet::new()),
+        Rc::new(settings),
+    );
+    let end = Position::zero() + session.document().as_text().length();
+    session.set_selection(end, Affinity::Before, SelectionMode::Simple);
+    session
+}
+
+fn session_at_end(text: &str) -> Session {
+    session_with_settings(text, Settings::default())
+}
+
+fn text(session: &Session) -> String {
+    session.document().as_text().to_string()
+}
+
+fn lines(strings: &[&str]) -> Vec<String> {
+    strings.iter().map(|string| string.to_string()).collect()
+}
+
+#[test]
+fn test_diff_lines_keeps_common_lines() {
+    let hunks = format::diff_lines(
+        &lines(&["a", "b", "c", "d", "e"]),
+        &lines(&["a", "x", "c", "d", "y", "z"]),
+    );
+    assert_eq!(
+        hunks,
+        [
+            Hunk {
+                old_lines: 1..2,
+                new_lines: 1..2,
+            },
+            Hunk {
+                old_lines: 4..5,
+                new_lines: 4..6,
+            },
+        ]
+    );
+    assert!(format::diff_lines(&lines(&["a"]), &lines(&["a"])).is_empty());
+}
+
+#[test]
+fn test_apply_formatted_text() {
+    let mut session = session_at_end("fn main() {\nlet x=1;\n    foo();\n}\n\n");
+    session.set_selection(
+        Position {
+            line_index: 2,
+            byte_index: 7,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+    );
+    assert!(
+        session.apply_formatted_text(Text::from("fn main() {\n    let x = 1;\n    foo();\n}\n"))
+    );
+    assert_eq!(
+        text(&session),
+        "fn main() {\n    let x = 1;\n    foo();\n}\n"
+    );
+    // The cursor is on a line that did not change, so it stays where it was.
+    assert_eq!(
+        session.selections()[0].cursor.position,
+        Position {
+            line_index: 2,
+            byte_index: 7,
+        }
+    );
+    assert!(
+        !session.apply_formatted_text(Text::from("fn main() {\n    let x = 1;\n    foo();\n}\n"))
+    );
+
+    // Formatting is undone in a single step.
+    session.undo();
+    session.handle_changes();
+    assert_eq!(text(&session), "fn main() {\nlet x=1;\n    foo();\n}\n\n");
+}
+
+#[test]
+fn test_apply_formatted_text_at_end() {
+    let mut session = session_at_end("a\nb\n");
+    assert!(session.apply_formatted_text(Text::from("a\nb\nc")));
+    assert_eq!(text(&session), "a\nb\nc");
+    assert!(session.apply_formatted_text(Text::from("a")));
+    assert_eq!(text(&session), "a");
+    assert!(session.apply_formatted_text(Text::from("")));
+    assert_eq!(text(&session), "");
+}
+
+fn wait_for_format_result(session: &mut Session) -> Result<bool, FormatError> {
+    loop {
+        if let Some(result) = session.handle_format_result() {
+            return result;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn session_with_formatter(text: &str, command: &str, args: &[&str]) -> Session {
+    session_with_settings(
+        text,
+        Settings {
+            formatter_command: command.into(),
+            formatter_args: lines(args),
+            ..Settings::default()
+        },
+    )
+}
+
+#[test]
+fn test_format_document_without_formatter() {
+    let mut session = session_with_formatter("fn main() {}", "makepad-nonexistent-formatter", &[]);
+    assert!(session.handle_format_result().is_none());
+    session.format_document();
+    assert!(matches!(
+        wait_for_format_result(&mut session),
+        Err(FormatError::Io(_))
+    ));
+    assert_eq!(text(&session), "fn main() {}");
+    assert!(session.handle_format_result().is_none());
+}
+
+#[test]
+fn test_format_document() {
+    let mut session = session_with_formatter("a\nx\nb", "tr", &["x", "y"]);
+    session.format_document();
+    assert!(wait_for_format_result(&mut session).unwrap());
+    assert_eq!(text(&session), "a\ny\nb");
+
+    // The output of a formatter that was started before the last edit is dropped.
+    session.format_document();
+    session.insert(Text::from("x"));
+    session.handle_changes();
+    assert!(!wait_for_format_result(&mut session).unwrap());
+    assert_eq!(text(&session), "a\ny\nbx");
+}
+
+#[test]
+fn test_diff_lines_of_long_texts() {
+    let old_lines: Vec<usize> = (0..3000).collect();
+    let new_lines: Vec<usize> = (0..3000)
+        .filter(|line| line % 1000 != 500)
+        .map(|line| {
+            if line % 1000 == 0 {
+                line + 10_000
+            } else {
+                line
+            }
+        })
+        .collect();
+    let hunks = format::diff_lines(&old_lines, &new_lines);
+    assert_eq!(hunks.len(), 6);
+    assert_eq!(
+        hunks[1],
+        Hunk {
+            old_lines: 500..501,
+            new_lines: 500..500,
+        }
+    );
+    assert_eq!(
+        hunks[5],
+        Hunk {
+            old_lines: 2500..2501,
+            new_lines: 2498..2498,
+        }
+    );
+}
+
+#[test]
+fn test_closing_brace_is_outdented() {
+    let mut session = session_at_end("blabla{");
+    session.enter();
+    session.handle_changes();
+    assert_eq!(text(&session), "blabla{\n    ");
+    session.insert(Text::from("}"));
+    session.handle_changes();
+    assert_eq!(text(&session), "blabla{\n}");
+
+    let mut session = session_with_settings(
+        "blabla{",
+        Settings {
+            outdent_closing_brace: false,
+            ..Settings::default()
+        },
+    );
+    session.enter();
+    session.handle_changes();
+    session.insert(Text::from("}"));
+    session.handle_changes();
+    assert_eq!(text(&session), "blabla{\n    }");
+}
+
+#[test]
+fn test_auto_close_pairs() {
+    let mut session = session_at_end("");
+    session.insert(Text::from("("));
+    session.handle_changes();
+    assert_eq!(text(&session), "()");
+    session.insert(Text::from(")"));
+    session.handle_changes();
+    assert_eq!(text(&session), "()");
+
+    let mut session = session_with_settings(
+        "",
+        Settings {
+            auto_close_pairs: false,
+            ..Settings::default()
+        },
+    );
+    session.insert(Text::from("("));
+    session.handle_changes();
+    assert_eq!(text(&session), "(");
+}
+
+#[test]
+fn test_comments_are_continued() {
+    let mut session = session_at_end("fn f() {\n    // foo");
+    session.enter();
+    session.handle_changes();
+    assert_eq!(text(&session), "fn f() {\n    // foo\n    // ");
+
+    let mut session = session_at_end("//! crate\n/// doc");
+    session.enter();
+    session.handle_changes();
+    assert_eq!(text(&session), "//! crate\n/// doc\n/// ");
+
+    // A cursor before the comment marker does not continue the comment.
+    let mut session = session_at_end("// foo");
+    session.set_selection(Position::zero(), Affinity::Before, SelectionMode::Simple);
+    session.enter();
+    session.handle_changes();
+    assert_eq!(text(&session), "\n// foo");
+
+    let mut session = session_with_settings(
+        "// foo",
+        Settings {
+            continue_comments: false,
+            ..Settings::default()
+        },
+    );
+    session.enter();
+    session.handle_changes();
+    assert_eq!(text(&session), "// foo\n");
+}
@@ -0,0 +1,163 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    makepad_widgets::makepad_micro_serde::*,
+    text::{Change, Drift, Edit, Length, Position, Text},
+    Document, History, Session,
+};
+
+fn type_text(session: &mut Session, text: &str) {
+    session.insert(Text::from(text));
+    session.handle_changes();
+    session.document().force_new_group();
+}
+
+fn text(session: &Session) -> String {
+    session.document().as_text().to_string()
+}
+
+#[test]
+fn test_undo_then_edit_keeps_branch() {
+    let mut session = Session::new(Document::new(Text::from(""), DecorationSet::new()));
+    type_text(&mut session, "a");
+    type_text(&mut session, "b");
+    let ab_revision = session.document().history().current_revision();
+    session.undo();
+    session.handle_changes();
+    type_text(&mut session, "c");
+    assert_eq!(text(&session), "ac");
+
+    // Redo follows the newest branch, which has nothing left to redo.
+    assert!(!session.redo());
+
+    // The undone edit is still reachable.
+    assert!(session.jump_to_revision(ab_revision));
+    session.handle_changes();
+    assert_eq!(text(&session), "ab");
+    assert!(session.jump_to_revision(0));
+    session.handle_changes();
+    assert_eq!(text(&session), "");
+
+    // After jumping, redo follows the branch that was jumped to last.
+    assert!(session.redo());
+    session.handle_changes();
+    assert!(session.redo());
+    session.handle_changes();
+    assert_eq!(text(&session), "ab");
+}
+
+#[test]
+fn test_revision_at() {
+    let mut session = Session::new(Document::new(Text::from(""), DecorationSet::new()));
+    type_text(&mut session, "a");
+    let history = session.document().history();
+    let time = history.revisions()[1].time();
+    assert_eq!(history.revision_at(time), 1);
+    assert_eq!(history.revision_at(std::time::UNIX_EPOCH), 0);
+}
+
+#[test]
+fn test_serialize_history() {
+    let mut session = Session::new(Document::new(Text::from("x"), DecorationSet::new()));
+    type_text(&mut session, "1");
+    type_text(&mut session, "2");
+    session.undo();
+    session.handle_changes();
+    type_text(&mut session, "3");
+    let bytes = session.document().history().serialize_bin();
+    drop(session);
+
+    let history = History::deserialize_bin(&bytes).unwrap();
+    assert_eq!(history.as_text().to_string(), "13x");
+    assert_eq!(history.revisions().len(), 4);
+    let mut session = Session::new(Document::with_history(history, DecorationSet::new(), "rs"));
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "1x");
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "x");
+    assert_eq!(session.selections()[0].cursor.position, Position::zero());
+    assert!(!session.undo());
+
+    assert!(History::deserialize_bin(&bytes[..bytes.len() - 1]).is_err());
+}
+
+fn apply_remote_edit(session: &mut Session, change: Change) {
+    session.document().apply_remote_edits(vec![Edit {
+        change,
+        drift: Drift::Before,
+    }]);
+    session.handle_changes();
+}
+
+fn position(byte_index: usize) -> Position {
+    Position {
+        line_index: 0,
+        byte_index,
+    }
+}
+
+#[test]
+fn test_remote_edits_are_not_undone() {
+    let mut session = Session::new(Document::new(Text::from("ac"), DecorationSet::new()));
+    type_text(&mut session, "1");
+    type_text(&mut session, "2");
+    apply_remote_edit(&mut session, Change::Insert(position(0), Text::from("x")));
+    apply_remote_edit(&mut session, Change::Insert(position(4), Text::from("b")));
+    assert_eq!(text(&session), "x12abc");
+
+    // The local edits are undone and redone around the remote ones.
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "x1abc");
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "xabc");
+    assert!(!session.undo());
+    assert!(session.redo());
+    session.handle_changes();
+    assert_eq!(text(&session), "x1abc");
+
+    // A remote edit is applied to a branch that was undone as well.
+    apply_remote_edit(
+        &mut session,
+        Change::Delete(
+            position(2),
+            Length {
+                line_count: 0,
+                byte_count: 1,
+            },
+        ),
+    );
+    assert_eq!(text(&session), "x1bc");
+    assert!(session.redo());
+    session.handle_changes();
+    assert_eq!(text(&session), "x12bc");
+}
+
+#[test]
+fn test_remote_edit_that_overlaps_a_revision_drops_it() {
+    let mut session = Session::new(Document::new(Text::from(""), DecorationSet::new()));
+    type_text(&mut session, "a");
+    type_text(&mut session, "bc");
+    apply_remote_edit(
+        &mut session,
+        Change::Delete(
+            position(2),
+            Length {
+                line_count: 0,
+                byte_count: 1,
+            },
+        ),
+    );
+    assert_eq!(text(&session), "ab");
+
+    // Undoing the last revision would have to delete text that is gone, so it can't be undone,
+    // and neither can the ones before it.
+    assert!(!session.undo());
+    assert_eq!(session.document().history().revisions().len(), 1);
+    type_text(&mut session, "d");
+    assert!(session.undo());
+    session.handle_changes();
+    assert_eq!(text(&session), "ab");
+}
@@ -0,0 +1,83 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+fn session_with_lines(line_count: usize) -> Session {
+    let text: String = (0..line_count)
+        .map(|line_index| format!("    let x{} = {{ \"{}\" }};\n", line_index, line_index))
+        .collect();
+    let mut session = Session::new(Document::new(Text::from(text), DecorationSet::new()));
+    session.set_selection(
+        Position {
+            line_index: line_count,
+            byte_index: 0,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+    );
+    session.handle_changes();
+    session
+}
+
+#[test]
+fn test_edits_at_end_of_large_file_only_lay_out_and_tokenize_edited_lines() {
+    let mut session = session_with_lines(100_000);
+    assert!(session.document().is_large_file());
+    assert_eq!(session.laid_out_line_count(), 0);
+
+    // Looking at the end of the document lays out and tokenizes everything before it, once.
+    session.layout_until(usize::MAX);
+    session.document().tokenize_until(usize::MAX);
+    let laid_out_line_count = session.laid_out_line_count();
+    let tokenized_line_count = session.document().tokenized_line_count();
+    assert_eq!(laid_out_line_count, 100_001);
+    assert_eq!(tokenized_line_count, 100_001);
+
+    for _ in 0..20 {
+        session.insert(Text::from("fn f() {}"));
+        session.handle_changes();
+        session.enter();
+        session.handle_changes();
+        session.layout_until(usize::MAX);
+        session.document().tokenize_until(usize::MAX);
+    }
+    // Each iteration changes the last line and adds a new one. With work proportional to the
+    // size of the document, millions of lines would be laid out and tokenized again.
+    assert_eq!(session.laid_out_line_count() - laid_out_line_count, 40);
+    assert_eq!(
+        session.document().tokenized_line_count() - tokenized_line_count,
+        40
+    );
+    assert_eq!(session.document().as_text().as_lines().len(), 100_021);
+}
+
+#[test]
+fn test_large_file_is_tokenized_lazily() {
+    let session = session_with_lines(100_000);
+    let document = session.document();
+    let first_token_kinds = |line_index: usize| -> Vec<_> {
+        document.layout().tokens[line_index]
+            .iter()
+            .map(|token| token.kind)
+            .collect()
+    };
+    let untokenized = first_token_kinds(50_000);
+    document.tokenize_until(50_001);
+    assert_ne!(first_token_kinds(50_000), untokenized);
+    assert_eq!(first_token_kinds(60_000), untokenized);
+}
+
+#[test]
+fn test_large_file_is_laid_out_lazily() {
+    let session = session_with_lines(100_000);
+    session.layout_until_y(50.0);
+    let layout = session.layout();
+    assert_eq!(layout.find_first_line_starting_after_y(50.0), 51);
+    assert!(session.laid_out_line_count() < 100);
+    // The lines that aren't laid out yet count as a single row each.
+    assert_eq!(layout.height(), 100_001.0);
+}
@@ -0,0 +1,435 @@
+use {
+    makepad_code_editor::{
+        decoration::{Decoration, DecorationSet, DecorationSource, DecorationType},
+        inlays::InlineInlay,
+        lsp::{self, LspClient, LspEvent, LspPosition},
+        makepad_widgets::makepad_micro_serde::*,
+        text::{Position, Text},
+        Document,
+    },
+    std::{
+        io,
+        io::{BufRead, BufReader, Read, Write},
+        path::Path,
+        sync::mpsc,
+        sync::mpsc::{Receiver, Sender},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// One end of an in-memory byte pipe.
+struct PipeReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.buffer.is_empty() {
+            match self.receiver.recv() {
+                Ok(bytes) => self.buffer = bytes,
+                Err(_) => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.buffer.len());
+        buf[..len].copy_from_slice(&self.buffer[..len]);
+        self.buffer.drain(..len);
+        Ok(len)
+    }
+}
+
+struct PipeWriter {
+    sender: Sender<Vec<u8>>,
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn pipe() -> (PipeWriter, PipeReader) {
+    let (sender, receiver) = mpsc::channel();
+    (
+        PipeWriter { sender },
+        PipeReader {
+            receiver,
+            buffer: Vec::new(),
+        },
+    )
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<JsonValue> {
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse().unwrap();
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+    Some(JsonValue::deserialize_json(std::str::from_utf8(&body).unwrap()).unwrap())
+}
+
+fn write_message(writer: &mut impl Write, message: JsonValue) {
+    let body = message.serialize_json();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+}
+
+/// A stand-in language server that understands just enough of the protocol to exercise the
+/// client. It reports an error on every line containing `bad`, and resolves every definition
+/// request to the start of the document.
+fn run_server(reader: PipeReader, mut writer: PipeWriter, log: Sender<String>) {
+    let mut reader = BufReader::new(reader);
+    while let Some(message) = read_message(&mut reader) {
+        let method = message.get("method").and_then(|method| method.as_str()).unwrap_or("");
+        log.send(method.to_string()).unwrap();
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or_default();
+        let respond = |writer: &mut PipeWriter, result: JsonValue| {
+            write_message(
+                writer,
+                JsonValue::object()
+                    .with("jsonrpc", "2.0")
+                    .with("id", id.clone().unwrap())
+                    .with("result", result),
+            );
+        };
+        match method {
+            "initialize" => respond(
+                &mut writer,
+                JsonValue::object().with("capabilities", JsonValue::object()),
+            ),
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument").unwrap();
+                let text = document.get("text").unwrap().as_str().unwrap();
+                let diagnostics = text
+                    .lines()
+                    .enumerate()
+                    .filter_map(|(line, text)| {
+                        let character = text.encode_utf16().count()
+                            - text[text.find("bad")?..].encode_utf16().count();
+                        Some(
+                            JsonValue::object()
+                                .with(
+                                    "range",
+                                    JsonValue::object()
+                                        .with(
+                                            "start",
+                                            JsonValue::object()
+                                                .with("line", line)
+                                                .with("character", character),
+                                        )
+                                        .with(
+                                            "end",
+                                            JsonValue::object()
+                                                .with("line", line)
+                                                .with("character", character + 3),
+                                        ),
+                                )
+                                .with("severity", 1u64)
+                                .with("message", "bad"),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                write_message(
+                    &mut writer,
+                    JsonValue::object()
+                        .with("jsonrpc", "2.0")
+                        .with("method", "textDocument/publishDiagnostics")
+                        .with(
+                            "params",
+                            JsonValue::object()
+                                .with("uri", document.get("uri").unwrap().clone())
+                                .with("diagnostics", diagnostics),
+                        ),
+                );
+            }
+            "textDocument/hover" => respond(
+                &mut writer,
+                JsonValue::object().with(
+                    "contents",
+                    JsonValue::object()
+                        .with("kind", "plaintext")
+                        .with("value", "fn main()"),
+                ),
+            ),
+            "textDocument/definition" => {
+                let uri = params.get("textDocument").unwrap().get("uri").unwrap().clone();
+                let position = params.get("position").unwrap().clone();
+                respond(
+                    &mut writer,
+                    vec![JsonValue::object().with("uri", uri).with(
+                        "range",
+                        JsonValue::object()
+                            .with("start", position.clone())
+                            .with("end", position),
+                    )]
+                    .into(),
+                )
+            }
+            "textDocument/completion" => respond(
+                &mut writer,
+                vec![
+                    JsonValue::object().with("label", "println!"),
+                    JsonValue::object()
+                        .with("label", "print!")
+                        .with("insertText", "print!()"),
+                ]
+                .into(),
+            ),
+            "textDocument/inlayHint" => respond(
+                &mut writer,
+                vec![JsonValue::object()
+                    .with(
+                        "position",
+                        JsonValue::object()
+                            .with("line", 0u64)
+                            .with("character", 6u64),
+                    )
+                    .with(
+                        "label",
+                        vec![
+                            JsonValue::object().with("value", ": "),
+                            JsonValue::object().with("value", "i32"),
+                        ],
+                    )
+                    .with("paddingRight", true)]
+                .into(),
+            ),
+            "shutdown" => respond(&mut writer, JsonValue::Null),
+            "exit" => break,
+            _ => {}
+        }
+    }
+}
+
+fn start() -> (LspClient, Receiver<String>) {
+    let (client_writer, server_reader) = pipe();
+    let (server_writer, client_reader) = pipe();
+    let (log_sender, log_receiver) = mpsc::channel();
+    thread::spawn(move || run_server(server_reader, server_writer, log_sender));
+    (
+        LspClient::with_transport(client_reader, client_writer),
+        log_receiver,
+    )
+}
+
+fn wait_for_events(client: &mut LspClient, count: usize) -> Vec<LspEvent> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut events = Vec::new();
+    while events.len() < count {
+        assert!(Instant::now() < deadline, "timed out waiting for {:?}", events);
+        events.extend(client.poll_events());
+        thread::sleep(Duration::from_millis(1));
+    }
+    events
+}
+
+#[test]
+fn test_position_conversion() {
+    let text = Text::from("aé😀b\nx");
+    let position = Position {
+        line_index: 0,
+        byte_index: 7,
+    };
+    let lsp_position = LspPosition::from_position(text.as_lines(), position);
+    assert_eq!(
+        lsp_position,
+        LspPosition {
+            line: 0,
+            character: 4
+        }
+    );
+    assert_eq!(lsp_position.to_position(text.as_lines()), position);
+}
+
+#[test]
+fn test_uri_round_trip() {
+    let path = Path::new("/home/user/my project/src/main.rs");
+    let uri = lsp::path_to_uri(path);
+    assert_eq!(uri, "file:///home/user/my%20project/src/main.rs");
+    assert_eq!(lsp::uri_to_path(&uri).unwrap(), path);
+}
+
+#[test]
+fn test_diagnostics() {
+    let (mut client, log) = start();
+    let text = Text::from("fn main() {\n    let é = bad;\n}");
+    client.did_open("file:///main.rs", "rust", 0, &text);
+    client.initialize(Path::new("/"));
+    let events = wait_for_events(&mut client, 2);
+    assert_eq!(events[0], LspEvent::Initialized);
+    let LspEvent::Diagnostics { uri, diagnostics } = &events[1] else {
+        panic!("expected diagnostics, got {:?}", events[1]);
+    };
+    assert_eq!(uri, "file:///main.rs");
+    let decorations = lsp::diagnostics_to_decorations(diagnostics, &text);
+    assert_eq!(decorations.len(), 1);
+    assert_eq!(decorations[0].ty, DecorationType::Error);
+    assert_eq!(
+        decorations[0].start(),
+        Position {
+            line_index: 1,
+            byte_index: 13
+        }
+    );
+    assert_eq!(
+        decorations[0].end(),
+        Position {
+            line_index: 1,
+            byte_index: 16
+        }
+    );
+    // Notifications sent before the handshake completed must have been held back until after it.
+    assert_eq!(log.recv().unwrap(), "initialize");
+    assert_eq!(log.recv().unwrap(), "initialized");
+    assert_eq!(log.recv().unwrap(), "textDocument/didOpen");
+}
+
+#[test]
+fn test_requests() {
+    let (mut client, _log) = start();
+    let text = Text::from("fn main() {}");
+    let position = Position {
+        line_index: 0,
+        byte_index: 3,
+    };
+    client.initialize(Path::new("/"));
+    client.did_open("file:///main.rs", "rust", 0, &text);
+    let hover = client.hover("file:///main.rs", position, &text);
+    let definition = client.definition("file:///main.rs", position, &text);
+    let completion = client.completion("file:///main.rs", position, &text);
+    let events = wait_for_events(&mut client, 5);
+    assert!(events.contains(&LspEvent::Hover {
+        request_id: hover,
+        contents: Some("fn main()".into()),
+        range: None,
+    }));
+    assert!(events.iter().any(|event| match event {
+        LspEvent::Definition {
+            request_id,
+            locations,
+        } =>
+            *request_id == definition
+                && locations[0].uri == "file:///main.rs"
+                && locations[0].range.start.to_position(text.as_lines()) == position,
+        _ => false,
+    }));
+    assert!(events.iter().any(|event| match event {
+        LspEvent::Completion { request_id, items } =>
+            *request_id == completion
+                && items.len() == 2
+                && items[0].insert_text == "println!"
+                && items[1].insert_text == "print!()",
+        _ => false,
+    }));
+    client.shutdown();
+    assert_eq!(wait_for_events(&mut client, 1), vec![LspEvent::Exited]);
+}
+
+#[test]
+fn test_inlay_hints() {
+    let (mut client, _log) = start();
+    let text = Text::from("let é = 1;");
+    client.initialize(Path::new("/"));
+    client.did_open("file:///main.rs", "rust", 0, &text);
+    let inlay_hints = client.inlay_hints("file:///main.rs", &text);
+    let events = wait_for_events(&mut client, 2);
+    let Some(LspEvent::InlayHints { request_id, hints }) = events.last() else {
+        panic!("expected inlay hints, got {:?}", events);
+    };
+    assert_eq!(*request_id, inlay_hints);
+    let (position, inlay) = hints[0].to_inlay(text.as_lines());
+    // The UTF-16 column after `é` is a byte index one further along.
+    assert_eq!(
+        position,
+        Position {
+            line_index: 0,
+            byte_index: 7
+        }
+    );
+    assert_eq!(inlay, InlineInlay::Text(": i32 ".into()));
+
+    let document = Document::new(text, DecorationSet::new());
+    document.replace_inline_inlays(vec![(position, inlay.clone())]);
+    assert_eq!(document.layout().inline_inlays[0], [(7, inlay)]);
+}
+
+#[test]
+fn test_completion_start() {
+    let text = Text::from("foo.ba");
+    let position = Position {
+        line_index: 0,
+        byte_index: 6,
+    };
+    assert_eq!(
+        lsp::completion_start(&[], text.as_lines(), position),
+        Position {
+            line_index: 0,
+            byte_index: 4
+        }
+    );
+}
+
+#[test]
+fn test_diagnostics_keep_build_decorations() {
+    let position = |line_index, byte_index| Position {
+        line_index,
+        byte_index,
+    };
+    let mut build_decorations = DecorationSet::new();
+    build_decorations.add_decoration(Decoration::new(
+        0,
+        position(0, 0),
+        position(0, 2),
+        DecorationType::Error,
+    ));
+    let mut document = Document::new(Text::from("ab cd\nef"), build_decorations);
+    let mut lsp_decorations = DecorationSet::new();
+    // This one overlaps with the build error, which is kept instead.
+    lsp_decorations.add_decoration(Decoration::new(
+        0,
+        position(0, 1),
+        position(0, 3),
+        DecorationType::Warning,
+    ));
+    lsp_decorations.add_decoration(Decoration::new(
+        1,
+        position(1, 0),
+        position(1, 2),
+        DecorationType::Warning,
+    ));
+    document.replace_decorations(DecorationSource::LanguageServer, lsp_decorations.clone());
+    let starts = |document: &Document| {
+        document
+            .decorations()
+            .iter()
+            .map(|decoration| decoration.start())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(starts(&document), [position(0, 0), position(1, 0)]);
+
+    // New diagnostics from the server leave the build errors alone, and the other way around.
+    document.replace_decorations(DecorationSource::LanguageServer, DecorationSet::new());
+    assert_eq!(starts(&document), [position(0, 0)]);
+    document.replace_decorations(DecorationSource::LanguageServer, lsp_decorations);
+    document.clear_decorations(DecorationSource::Build);
+    assert_eq!(starts(&document), [position(0, 1), position(1, 0)]);
+}
@@ -0,0 +1,60 @@
+use makepad_code_editor::rope::Rope;
+
+fn lines(range: std::ops::Range<usize>) -> Vec<String> {
+    range.map(|index| index.to_string()).collect()
+}
+
+fn assert_same(rope: &Rope, vec: &[String]) {
+    assert_eq!(rope.len(), vec.len());
+    assert!(rope.iter().eq(vec.iter()));
+    assert!(rope.iter().rev().eq(vec.iter().rev()));
+    for index in [0, vec.len() / 3, vec.len() / 2, vec.len().saturating_sub(1)] {
+        assert_eq!(rope.get(index), vec.get(index));
+    }
+    let (start, end) = (vec.len() / 4, vec.len() / 2);
+    assert!(rope.range(start..end).eq(vec[start..end].iter()));
+    assert_eq!(rope.range(start..end).len(), end - start);
+}
+
+#[test]
+fn test_from_lines() {
+    for line_count in [0, 1, 63, 64, 65, 1_000, 10_000] {
+        let vec = lines(0..line_count);
+        assert_same(&Rope::from(vec.clone()), &vec);
+    }
+}
+
+#[test]
+fn test_splice() {
+    let mut rope = Rope::from(lines(0..10_000));
+    let mut vec = lines(0..10_000);
+    // A simple linear congruential generator, so that the test is deterministic.
+    let mut seed: usize = 12345;
+    let mut next = |bound: usize| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) % (bound + 1)
+    };
+    for step in 0..500 {
+        let start = next(vec.len());
+        let end = start + next((vec.len() - start).min(200));
+        let new_lines = lines(step * 1_000..step * 1_000 + next(200));
+        rope.splice(start..end, new_lines.clone());
+        vec.splice(start..end, new_lines);
+        assert_same(&rope, &vec);
+    }
+    rope.splice(.., Vec::new());
+    assert!(rope.is_empty());
+}
+
+#[test]
+fn test_clones_are_independent() {
+    let mut rope = Rope::from(lines(0..1_000));
+    let clone = rope.clone();
+    rope[500].push('!');
+    rope.splice(0..10, Vec::new());
+    assert_eq!(rope[490], "500!");
+    assert_eq!(clone[500], "500");
+    assert_eq!(clone.len(), 1_000);
+}
@@ -0,0 +1,100 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+const TEXT: &str =
+    "fn main() {\n    let x = foo(\"ab cd\", 1);\n    if x {\n        bar();\n    }\n}";
+
+fn session_at(line_index: usize, byte_index: usize) -> Session {
+    let session = Session::new(Document::new(Text::from(TEXT), DecorationSet::new()));
+    session.set_selection(
+        Position {
+            line_index,
+            byte_index,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+    );
+    session
+}
+
+fn selected_string(session: &Session) -> String {
+    let selection = session.selections()[0];
+    session
+        .document()
+        .as_text()
+        .slice(selection.start(), selection.length())
+        .to_string()
+}
+
+#[test]
+fn test_expand_and_shrink_selection() {
+    // Inside the word "ab" in the string.
+    let session = session_at(1, 18);
+    let expected = [
+        "ab",
+        "ab cd",
+        "\"ab cd\"",
+        "\"ab cd\", 1",
+        "(\"ab cd\", 1)",
+        "\n    let x = foo(\"ab cd\", 1);\n    if x {\n        bar();\n    }\n",
+        "{\n    let x = foo(\"ab cd\", 1);\n    if x {\n        bar();\n    }\n}",
+        TEXT,
+    ];
+    for string in expected {
+        assert!(session.expand_selection());
+        assert_eq!(selected_string(&session), string);
+    }
+    assert!(!session.expand_selection());
+    for string in expected.iter().rev().skip(1) {
+        assert!(session.shrink_selection());
+        assert_eq!(selected_string(&session), *string);
+    }
+    assert!(session.shrink_selection());
+    assert!(session.selections()[0].is_empty());
+    assert!(!session.shrink_selection());
+}
+
+#[test]
+fn test_expand_block_to_item() {
+    // Inside "if x { ... }".
+    let session = session_at(3, 8);
+    assert!(session.expand_selection());
+    assert_eq!(selected_string(&session), "bar");
+    assert!(session.expand_selection());
+    assert_eq!(selected_string(&session), "\n        bar();\n    ");
+    assert!(session.expand_selection());
+    assert_eq!(selected_string(&session), "{\n        bar();\n    }");
+    assert!(session.expand_selection());
+    assert_eq!(selected_string(&session), "if x {\n        bar();\n    }");
+}
+
+#[test]
+fn test_toggle_block_fold() {
+    let session = session_at(0, 0);
+    assert!(session.toggle_block_fold());
+    while session.update_folds() {}
+    let scales: Vec<_> = (0..6)
+        .map(|line_index| session.layout().line(line_index).scale())
+        .collect();
+    assert_eq!(scales, [1.0, 0.1, 0.1, 0.1, 0.1, 1.0]);
+
+    assert!(session.toggle_block_fold());
+    while session.update_folds() {}
+    assert!((0..6).all(|line_index| session.layout().line(line_index).scale() == 1.0));
+
+    // The enclosing block of "bar();" has no lines to fold other than its own, so the block
+    // around it is folded instead.
+    let session = session_at(3, 8);
+    assert!(session.toggle_block_fold());
+    while session.update_folds() {}
+    assert_eq!(session.layout().line(3).scale(), 0.1);
+    assert_eq!(session.layout().line(1).scale(), 1.0);
+
+    let session = Session::new(Document::new(Text::from("foo(1)"), DecorationSet::new()));
+    assert!(!session.toggle_block_fold());
+}
@@ -0,0 +1,117 @@
+use makepad_code_editor::{
+    text::{Change, Position, Text},
+    token::TokenKind,
+    Token, Tokenizer,
+};
+
+fn tokenize(extension: &str, text: &Text) -> Vec<Vec<(String, TokenKind)>> {
+    let mut tokenizer = Tokenizer::for_extension(extension, text.as_lines().len());
+    let mut tokens = vec![Vec::new(); text.as_lines().len()];
+    tokenizer.update(text, &mut tokens);
+    collect(text, &tokens)
+}
+
+fn collect(text: &Text, tokens: &[Vec<Token>]) -> Vec<Vec<(String, TokenKind)>> {
+    text.as_lines()
+        .iter()
+        .zip(tokens)
+        .map(|(line, tokens)| {
+            let mut start = 0;
+            tokens
+                .iter()
+                .map(|token| {
+                    let string = line[start..start + token.len].to_string();
+                    start += token.len;
+                    (string, token.kind)
+                })
+                .filter(|(_, kind)| *kind != TokenKind::Whitespace)
+                .collect()
+        })
+        .collect()
+}
+
+fn kinds_of(tokens: &[Vec<(String, TokenKind)>], string: &str) -> Vec<TokenKind> {
+    tokens
+        .iter()
+        .flatten()
+        .filter(|(other, _)| other == string)
+        .map(|(_, kind)| *kind)
+        .collect()
+}
+
+#[test]
+fn test_toml() {
+    let tokens = tokenize(
+        "toml",
+        &Text::from(
+            "[package]\nname = \"makepad\" # the name\nversion = 1.0\ndescription = \"\"\"\nmulti = 1\n\"\"\"\nok = true",
+        ),
+    );
+    assert_eq!(kinds_of(&tokens, "[package]"), [TokenKind::Typename]);
+    assert_eq!(kinds_of(&tokens, "name"), [TokenKind::Identifier]);
+    assert_eq!(kinds_of(&tokens, "\"makepad\""), [TokenKind::String]);
+    assert_eq!(kinds_of(&tokens, "# the name"), [TokenKind::Comment]);
+    assert_eq!(kinds_of(&tokens, "1.0"), [TokenKind::Number]);
+    assert_eq!(kinds_of(&tokens, "multi = 1"), [TokenKind::String]);
+    assert_eq!(kinds_of(&tokens, "true"), [TokenKind::OtherKeyword]);
+}
+
+#[test]
+fn test_json() {
+    let tokens = tokenize("json", &Text::from("{\"key\": [\"value\", -1.5e3, null]}"));
+    assert_eq!(kinds_of(&tokens, "\"key\""), [TokenKind::Identifier]);
+    assert_eq!(kinds_of(&tokens, "\"value\""), [TokenKind::String]);
+    assert_eq!(kinds_of(&tokens, "-1.5e3"), [TokenKind::Number]);
+    assert_eq!(kinds_of(&tokens, "null"), [TokenKind::OtherKeyword]);
+}
+
+#[test]
+fn test_markdown() {
+    let tokens = tokenize(
+        "md",
+        &Text::from("# Title\nsome `code` and a [link](url)\n```rust\nlet x = 1;\n```\nsnake_case"),
+    );
+    assert_eq!(kinds_of(&tokens, "# Title"), [TokenKind::Typename]);
+    assert_eq!(kinds_of(&tokens, "`code`"), [TokenKind::String]);
+    assert_eq!(kinds_of(&tokens, "[link](url)"), [TokenKind::Function]);
+    assert_eq!(kinds_of(&tokens, "let x = 1;"), [TokenKind::String]);
+    assert_eq!(kinds_of(&tokens, "```"), [TokenKind::Delimiter]);
+    assert!(kinds_of(&tokens, "_case").is_empty());
+}
+
+#[test]
+fn test_live_design() {
+    let tokens = tokenize(
+        "live",
+        &Text::from("App = {{App}} {\n    /* a\n    comment */ draw_bg: {color: #f00}\n}"),
+    );
+    assert_eq!(kinds_of(&tokens, "{{"), [TokenKind::Delimiter]);
+    assert_eq!(kinds_of(&tokens, "App"), [TokenKind::Typename, TokenKind::Typename]);
+    assert_eq!(kinds_of(&tokens, "/* a"), [TokenKind::Comment]);
+    assert_eq!(kinds_of(&tokens, "    comment */"), [TokenKind::Comment]);
+    assert_eq!(kinds_of(&tokens, "#f00"), [TokenKind::Number]);
+}
+
+#[test]
+fn test_incremental_update() {
+    let mut text = Text::from("a = 1\nb = 2\nc = 3");
+    let mut tokenizer = Tokenizer::for_extension("toml", text.as_lines().len());
+    let mut tokens = vec![Vec::new(); text.as_lines().len()];
+    tokenizer.update(&text, &mut tokens);
+
+    // Opening a multi-line string on the first line turns all following lines into string
+    // contents.
+    let change = Change::Insert(
+        Position {
+            line_index: 0,
+            byte_index: 4,
+        },
+        Text::from("\"\"\""),
+    );
+    text.apply_change(change.clone());
+    tokenizer.apply_change(&change);
+    tokenizer.update(&text, &mut tokens);
+    let collected = collect(&text, &tokens);
+    assert_eq!(kinds_of(&collected, "b = 2"), [TokenKind::String]);
+    assert_eq!(kinds_of(&collected, "c = 3"), [TokenKind::String]);
+}
@@ -13,10 +13,11 @@ pub use {
         turtle::{Walk, Layout},
         draw_list_2d::{ManyInstances, DrawList2d, RedrawingApi},
         geometry::GeometryQuad2D,
-        makepad_vector::trapezoidator::Trapezoidator,
-        makepad_vector::geometry::{AffineTransformation, Transform, Vector, Point},
+        makepad_vector::trapezoidator::{Trapezoidator, FillRule},
+        makepad_vector::geometry::{AffineTransformation, Vector},
         makepad_vector::internal_iter::*,
-        makepad_vector::path::{PathIterator, PathCommand},
+        makepad_vector::path::LinePathCommand,
+        makepad_vector::svg::{parse_svg, parse_svg_path, SvgDocument, SvgShape, SvgPaint},
     }
 };
 
@@ -30,29 +31,17 @@ pub struct CxIconSlot {
 #[derive(Clone)]
 pub struct CxIconEntry {
     path_hash: CxIconPathHash,
-    pos: DVec2,
-    slot: CxIconSlot,
+    // the atlas position of the region of every 3 layers
+    pos: Vec<DVec2>,
+    slots: Vec<CxIconSlot>,
     args: CxIconArgs,
 }
 
-struct CxIconPathCommands {
-    bounds: Rect,
-    path: Vec<PathCommand>
-}
-
-impl<'a> InternalIterator for &CxIconPathCommands {
-    type Item = PathCommand;
-    fn for_each<F>(self, f: &mut F) -> bool
-    where
-    F: FnMut(PathCommand) -> bool,
-    {
-        for item in &self.path {
-            if !f(item.clone()) {
-                return false
-            }
-        }
-        true
-    }
+/// A parsed icon. Every shape is a layer with its own coverage in the atlas, 3 layers share
+/// an atlas region by using the red, green and blue channel.
+pub struct CxIcon {
+    pub bounds: Rect,
+    pub shapes: Vec<SvgShape>,
 }
 
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
@@ -65,7 +54,7 @@ pub struct CxIconAtlas {
     pub texture: Texture,
     pub clear_buffer: bool,
     svg_deps: HashMap<String, CxIconPathHash>,
-    paths: HashMap<CxIconPathHash, CxIconPathCommands>,
+    paths: HashMap<CxIconPathHash, CxIcon>,
     entries: HashMap<CxIconEntryHash, CxIconEntry>,
     alloc: CxIconAtlasAlloc
 }
@@ -100,6 +89,14 @@ impl CxIconArgs {
             .bytes_append(&self.size.x.to_be_bytes())
             .bytes_append(&self.size.y.to_be_bytes())
     }
+    
+    /// Maps icon coordinates to pixels in the atlas region of the icon.
+    pub fn transform(&self) -> AffineTransformation {
+        AffineTransformation::identity()
+            .translate(Vector::new(self.translate.x, self.translate.y))
+            .uniform_scale(self.scale)
+            .translate(Vector::new(self.subpixel.x, self.subpixel.y))
+    }
 }
 
 impl CxIconAtlas {
@@ -123,49 +120,40 @@ impl CxIconAtlas {
     pub fn parse_and_cache_path(&mut self, path_hash: CxIconPathHash, path: &[u8]) -> Option<(CxIconPathHash, Rect)> {
         match parse_svg_path(path) {
             Ok(path) => {
-                let mut min = dvec2(f64::INFINITY, f64::INFINITY);
-                let mut max = dvec2(-f64::INFINITY, -f64::INFINITY);
-                fn bound(p: &Point, min: &mut DVec2, max: &mut DVec2) {
-                    if p.x < min.x {min.x = p.x}
-                    if p.y < min.y {min.y = p.y}
-                    if p.x > max.x {max.x = p.x}
-                    if p.y > max.y {max.y = p.y}
-                }
-                for cmd in &path {
-                    match cmd {
-                        PathCommand::MoveTo(p) => {bound(p, &mut min, &mut max)},
-                        PathCommand::LineTo(p) => {bound(p, &mut min, &mut max)},
-                        PathCommand::ArcTo(e, r, _, _, _) => {
-                            // TODO: this is pretty rough
-                            bound(&Point{x: e.x + r.x, y: e.y + r.y}, &mut min, &mut max);
-                            bound(&Point{x: e.x - r.x, y: e.y - r.y}, &mut min, &mut max);
-                        },
-                        PathCommand::QuadraticTo(p1, p) => {
-                            bound(p1, &mut min, &mut max);
-                            bound(p, &mut min, &mut max);
-                        },
-                        PathCommand::CubicTo(p1, p2, p) => {
-                            bound(p1, &mut min, &mut max);
-                            bound(p2, &mut min, &mut max);
-                            bound(p, &mut min, &mut max);
-                        },
-                        PathCommand::Close => ()
-                    }
-                }
-                let bounds = Rect {pos: min, size: max - min};
-                self.paths.insert(path_hash, CxIconPathCommands {
-                    bounds,
-                    path
-                });
-                return Some((path_hash, bounds));
+                // a bare path is a single layer in the colour of the icon
+                let doc = SvgDocument {
+                    view_box: None,
+                    shapes: vec![SvgShape {
+                        path,
+                        transform: AffineTransformation::identity(),
+                        fill_rule: FillRule::NonZero,
+                        stroke: None,
+                        paint: SvgPaint::CurrentColor,
+                        opacity: 1.0,
+                    }]
+                };
+                self.cache_document(path_hash, doc)
             }
             Err(e) => {
                 log!("Error in SVG Path {}", e);
-                return None
+                None
             }
         }
     }
     
+    fn cache_document(&mut self, path_hash: CxIconPathHash, doc: SvgDocument) -> Option<(CxIconPathHash, Rect)> {
+        let bounds = doc.bounds(0.001)?;
+        let bounds = Rect {
+            pos: dvec2(bounds.p_min.x, bounds.p_min.y),
+            size: dvec2(bounds.p_max.x - bounds.p_min.x, bounds.p_max.y - bounds.p_min.y)
+        };
+        self.paths.insert(path_hash, CxIcon {
+            bounds,
+            shapes: doc.shapes
+        });
+        Some((path_hash, bounds))
+    }
+    
     pub fn get_icon_bounds(&mut self, cx: &Cx, path_str: &Rc<String>, svg_dep: &Rc<String>) -> Option<(CxIconPathHash, Rect)> {
         if svg_dep.len() != 0 {
             // alright so. lets see if we have a path hash
@@ -180,29 +168,19 @@ impl CxIconAtlas {
             // lets parse the path range out of the svg file
             match cx.get_dependency(svg_dep.as_str()) {
                 Ok(data)=>{
-                    fn find_path_str(data:&[u8])->Option<&[u8]>{
-                        let pat = "path d=\"".as_bytes();
-                        'outer:for i in 0..data.len(){
-                            for j in 0..pat.len(){
-                                if data[i+j] != pat[j]{
-                                    continue 'outer;
-                                }
-                            }
-                            for k in i+pat.len()..data.len(){
-                                if data[k] == '\"' as u8{
-                                    return Some(&data[i+pat.len()..k])
-                                }
+                    match parse_svg(&String::from_utf8_lossy(&data)) {
+                        Ok(doc) => {
+                            if doc.shapes.is_empty() {
+                                println!("Nothing to draw in svg file {}", svg_dep);
+                                return None
                             }
+                            return self.cache_document(path_hash, doc)
+                        }
+                        Err(err) => {
+                            println!("Error in SVG file {}: {}", svg_dep, err);
                             return None
                         }
-                        None
                     }
-                    if let Some(data) = find_path_str(&data){
-                        return self.parse_and_cache_path(path_hash, data)
-                    }
-                    println!("No SVG path tag found in svg file {}",path_str);
-                    return None
-                    
                 }
                 Err(_err)=>{
                     println!("Error in SVG file {}: {}",path_str, _err);
@@ -220,26 +198,40 @@ impl CxIconAtlas {
         self.parse_and_cache_path(path_hash, path_str.as_str().as_bytes())
     }
     
-    pub fn get_icon_slot(&mut self, args: CxIconArgs, path_hash: CxIconPathHash) -> CxIconSlot {
+    /// Returns the atlas slots of the layers of an icon drawn with `args`, rendering them
+    /// with the next atlas pass if needed, together with the icon.
+    pub fn get_icon_slots(&mut self, args: CxIconArgs, path_hash: CxIconPathHash) -> (&[CxIconSlot], &CxIcon) {
         let entry_hash = CxIconEntryHash(path_hash.0.id_append(args.hash()));
+        let icon = self.paths.get(&path_hash).unwrap();
         
-        if let Some(entry) = self.entries.get(&entry_hash) {
-            return entry.slot
-        }
-        
-        let (slot,pos) = self.alloc.alloc_icon_slot(args.size.x as f64, args.size.y as f64);
-        self.entries.insert(
-            entry_hash,
-            CxIconEntry {
-                path_hash,
-                slot,
-                pos,
-                args
+        if !self.entries.contains_key(&entry_hash) {
+            let mut slots = Vec::new();
+            let mut pos = Vec::new();
+            for layer in 0..icon.shapes.len() {
+                if layer % 3 == 0 {
+                    let (slot, region_pos) = self.alloc.alloc_icon_slot(args.size.x, args.size.y);
+                    slots.push(slot);
+                    pos.push(region_pos);
+                }
+                else {
+                    slots.push(CxIconSlot {
+                        chan: (layer % 3) as f32,
+                        ..slots[layer - layer % 3]
+                    });
+                }
             }
-        );
-        self.alloc.todo.push(entry_hash);
-        
-        return slot
+            self.entries.insert(
+                entry_hash,
+                CxIconEntry {
+                    path_hash,
+                    slots,
+                    pos,
+                    args
+                }
+            );
+            self.alloc.todo.push(entry_hash);
+        }
+        (&self.entries.get(&entry_hash).unwrap().slots, icon)
     }
     
 }
@@ -293,37 +285,40 @@ impl CxIconAtlas {
 
 impl DrawTrapezoidVector {
     // atlas drawing function used by CxAfterDraw
-    fn draw_vector(&mut self, entry: &CxIconEntry, path: &CxIconPathCommands, many: &mut ManyInstances) {
-        let trapezoids = {
-            let mut trapezoids = Vec::new();
-            //log_str(&format!("Serializing char {} {} {} {}", glyphtc.tx1 , cx.fonts_atlas.texture_size.x ,todo.subpixel_x_fract ,atlas_page.dpi_factor));
-            let trapezoidate = self.trapezoidator.trapezoidate(
-                path.map({
-                    //log!("{:?} {:?}", entry.args, entry.pos);
-                    move | cmd | {
-                        let cmd = cmd.transform(
-                            &AffineTransformation::identity()
-                                .translate(Vector::new(entry.args.translate.x, entry.args.translate.y))
-                                .uniform_scale(entry.args.scale)
-                                .translate(Vector::new(entry.pos.x + entry.args.subpixel.x, entry.pos.y + entry.args.subpixel.y))
-                        );
-                        cmd
+    fn draw_vector(&mut self, entry: &CxIconEntry, icon: &CxIcon, many: &mut ManyInstances) {
+        let mut trapezoids = Vec::new();
+        let mut contour = Vec::new();
+        for (layer, shape) in icon.shapes.iter().enumerate() {
+            let pos = entry.pos[layer / 3];
+            let transform = entry.args.transform().translate(Vector::new(pos.x, pos.y));
+            let outline = shape.outline(&transform, entry.args.linearize, &mut self.stroker);
+            
+            trapezoids.clear();
+            self.trapezoidator.set_fill_rule(shape.fill_rule);
+            if shape.stroke.is_some() {
+                // the pieces of a stroke overlap, so they are filled one by one and add up
+                let mut commands = outline.commands().peekable();
+                while let Some(command) = commands.next() {
+                    contour.push(command);
+                    if !matches!(commands.peek(), None | Some(LinePathCommand::MoveTo(_))) {
+                        continue
                     }
-                }).linearize(entry.args.linearize)
-            );
-            if let Some(trapezoidate) = trapezoidate {
-                trapezoids.extend_from_internal_iter(
-                    trapezoidate
-                );
+                    if let Some(trapezoidate) = self.trapezoidator.trapezoidate(contour.iter().cloned()) {
+                        trapezoids.extend_from_internal_iter(trapezoidate);
+                    }
+                    contour.clear();
+                }
+            }
+            else if let Some(trapezoidate) = self.trapezoidator.trapezoidate(outline.commands()) {
+                trapezoids.extend_from_internal_iter(trapezoidate);
+            }
+            
+            for trapezoid in &trapezoids {
+                self.a_xs = Vec2 {x: trapezoid.xs[0], y: trapezoid.xs[1]};
+                self.a_ys = Vec4 {x: trapezoid.ys[0], y: trapezoid.ys[1], z: trapezoid.ys[2], w: trapezoid.ys[3]};
+                self.chan = entry.slots[layer].chan;
+                many.instances.extend_from_slice(self.draw_vars.as_slice());
             }
-            trapezoids
-        };
-        
-        for trapezoid in trapezoids {
-            self.a_xs = Vec2 {x: trapezoid.xs[0], y: trapezoid.xs[1]};
-            self.a_ys = Vec4 {x: trapezoid.ys[0], y: trapezoid.ys[1], z: trapezoid.ys[2], w: trapezoid.ys[3]};
-            self.chan = 0.0 as f32;
-            many.instances.extend_from_slice(self.draw_vars.as_slice());
         }
     }
 }
@@ -411,8 +406,8 @@ impl<'a> Cx2d<'a> {
             if let Some(mut many) = self.begin_many_instances(&draw_atlas.draw_trapezoid.draw_vars) {
                 for todo in atlas_todo {
                     let entry = atlas.entries.get(&todo).unwrap();
-                    let path = atlas.paths.get(&entry.path_hash).unwrap();
-                    draw_atlas.draw_trapezoid.draw_vector(entry, path, &mut many);
+                    let icon = atlas.paths.get(&entry.path_hash).unwrap();
+                    draw_atlas.draw_trapezoid.draw_vector(entry, icon, &mut many);
                 }
                 
                 self.end_many_instances(many);
@@ -424,274 +419,3 @@ impl<'a> Cx2d<'a> {
     
     
 }
-
-fn parse_svg_path(path: &[u8]) -> Result<Vec<PathCommand>, String> {
-    #[derive(Debug)]
-    enum Cmd {
-        Unknown,
-        Move(bool),
-        Hor(bool),
-        Vert(bool),
-        Line(bool),
-        Arc(bool),
-        Cubic(bool),
-        Quadratic(bool),
-        Close
-    }
-    impl Default for Cmd {fn default() -> Self {Self::Unknown}}
-    
-    #[derive(Default)]
-    struct ParseState {
-        cmd: Cmd,
-        expect_nums: usize,
-        chain: bool,
-        nums: [f64; 7],
-        num_count: usize,
-        last_pt: Point,
-        out: Vec<PathCommand>,
-        num_state: Option<NumState>
-    }
-    
-    #[derive(Debug)]
-    struct NumState {
-        num: f64,
-        mul: f64,
-        has_dot: bool,
-    }
-    
-    impl NumState {
-        fn new_pos(v: f64) -> Self {Self {num: v, mul: 1.0, has_dot: false}}
-        fn new_min() -> Self {Self {num: 0.0, mul: -1.0, has_dot: false}}
-        fn finalize(self) -> f64 {self.num * self.mul}
-        fn add_digit(&mut self, digit: f64) {
-            self.num *= 10.0;
-            self.num += digit;
-            if self.has_dot {
-                self.mul *= 0.1;
-            }
-        }
-    }
-    
-    impl ParseState {
-        fn next_cmd(&mut self, cmd: Cmd) -> Result<(), String> {
-            self.finalize_cmd() ?;
-            self.chain = false;
-            self.expect_nums = match cmd {
-                Cmd::Unknown => panic!(),
-                Cmd::Move(_) => 2,
-                Cmd::Hor(_) => 1,
-                Cmd::Vert(_) => 1,
-                Cmd::Line(_) => 2,
-                Cmd::Cubic(_) => 6,
-                Cmd::Arc(_) => 7,
-                Cmd::Quadratic(_) => 4,
-                Cmd::Close => 0
-            };
-            self.cmd = cmd;
-            Ok(())
-        }
-        
-        fn add_min(&mut self) -> Result<(), String> {
-            if self.num_state.is_some() {
-                self.finalize_num();
-            }
-            if self.expect_nums == self.num_count {
-                self.finalize_cmd() ?;
-            }
-            if self.expect_nums == 0 {
-                return Err(format!("Unexpected minus"));
-            }
-            self.num_state = Some(NumState::new_min());
-            Ok(())
-        }
-        
-        fn add_digit(&mut self, digit: f64) -> Result<(), String> {
-            if let Some(num_state) = &mut self.num_state {
-                num_state.add_digit(digit);
-            }
-            else {
-                if self.expect_nums == self.num_count {
-                    self.finalize_cmd() ?;
-                }
-                if self.expect_nums == 0 {
-                    return Err(format!("Unexpected digit"));
-                }
-                self.num_state = Some(NumState::new_pos(digit))
-            }
-            Ok(())
-        }
-        
-        fn add_dot(&mut self) -> Result<(), String> {
-            if let Some(num_state) = &mut self.num_state {
-                if num_state.has_dot {
-                    self.finalize_num();
-                    self.add_digit(0.0) ?;
-                    self.add_dot() ?;
-                    return Ok(());
-                }
-                num_state.has_dot = true;
-            }
-            else {
-                self.add_digit(0.0) ?;
-                self.add_dot() ?;
-            }
-            Ok(())
-        }
-        
-        fn finalize_num(&mut self) {
-            if let Some(num_state) = self.num_state.take() {
-                self.nums[self.num_count] = num_state.finalize();
-                self.num_count += 1;
-            }
-        }
-        
-        fn whitespace(&mut self) -> Result<(), String> {
-            self.finalize_num();
-            if self.expect_nums == self.num_count {
-                self.finalize_cmd() ?;
-            }
-            Ok(())
-        }
-        
-        fn finalize_cmd(&mut self) -> Result<(), String> {
-            self.finalize_num();
-            if self.chain && self.num_count == 0 {
-                return Ok(())
-            }
-            if self.expect_nums != self.num_count {
-                return Err(format!("SVG Path command {:?} expected {} points, got {}", self.cmd, self.expect_nums, self.num_count));
-            }
-            match self.cmd {
-                Cmd::Unknown => (),
-                Cmd::Move(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.nums[0], y: self.nums[1]};
-                    }
-                    else {
-                        self.last_pt += Vector {x: self.nums[0], y: self.nums[1]};
-                    }
-                    self.out.push(PathCommand::MoveTo(self.last_pt));
-                },
-                Cmd::Hor(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.nums[0], y: self.last_pt.y};
-                    }
-                    else {
-                        self.last_pt += Vector {x: self.nums[0], y: 0.0};
-                    }
-                    self.out.push(PathCommand::LineTo(self.last_pt));
-                }
-                Cmd::Vert(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.last_pt.x, y: self.nums[0]};
-                    }
-                    else {
-                        self.last_pt += Vector {x: 0.0, y: self.nums[0]};
-                    }
-                    self.out.push(PathCommand::LineTo(self.last_pt));
-                }
-                Cmd::Line(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.nums[0], y: self.nums[1]};
-                    }
-                    else {
-                        self.last_pt += Vector {x: self.nums[0], y: self.nums[1]};
-                    }
-                    self.out.push(PathCommand::LineTo(self.last_pt));
-                },
-                Cmd::Cubic(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.nums[4], y: self.nums[5]};
-                        self.out.push(PathCommand::CubicTo(
-                            Point {x: self.nums[0], y: self.nums[1]},
-                            Point {x: self.nums[2], y: self.nums[3]},
-                            self.last_pt,
-                        ));
-                    } else {
-                        self.out.push(PathCommand::CubicTo(
-                            self.last_pt + Vector {x: self.nums[0], y: self.nums[1]},
-                            self.last_pt + Vector {x: self.nums[2], y: self.nums[3]},
-                            self.last_pt + Vector {x: self.nums[4], y: self.nums[5]},
-                        ));
-                        self.last_pt += Vector {x: self.nums[4], y: self.nums[5]};
-                    }
-                },
-                Cmd::Arc(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.nums[5], y: self.nums[6]};
-                        self.out.push(PathCommand::ArcTo(
-                            self.last_pt,
-                            Point {x: self.nums[0], y: self.nums[1]},
-                            self.nums[2],
-                            self.nums[3] != 0.0,
-                            self.nums[4] != 0.0,
-                        ));
-                    }
-                    else {
-                        self.out.push(PathCommand::ArcTo(
-                            self.last_pt + Vector {x: self.nums[5], y: self.nums[6]},
-                            Point {x: self.nums[0], y: self.nums[1]},
-                            self.nums[2],
-                            self.nums[3] != 0.0,
-                            self.nums[4] != 0.0,
-                        ));
-                        self.last_pt += Vector {x: self.nums[5], y: self.nums[6]};
-                    }
-                },
-                Cmd::Quadratic(abs) => {
-                    if abs {
-                        self.last_pt = Point {x: self.nums[2], y: self.nums[3]};
-                        self.out.push(PathCommand::QuadraticTo(
-                            Point {x: self.nums[0], y: self.nums[1]},
-                            self.last_pt
-                        ));
-                    }
-                    else {
-                        self.out.push(PathCommand::QuadraticTo(
-                            self.last_pt + Vector {x: self.nums[0], y: self.nums[1]},
-                            self.last_pt + Vector {x: self.nums[2], y: self.nums[3]},
-                        ));
-                        self.last_pt += Vector {x: self.nums[2], y: self.nums[3]};
-                    }
-                }
-                Cmd::Close => {
-                    self.out.push(PathCommand::Close);
-                }
-            }
-            self.num_count = 0;
-            self.chain = true;
-            Ok(())
-        }
-    }
-    
-    let mut state = ParseState::default();
-    for i in 0..path.len() {
-        match path[i] {
-            b'M' => state.next_cmd(Cmd::Move(true)) ?,
-            b'm' => state.next_cmd(Cmd::Move(false)) ?,
-            b'Q' => state.next_cmd(Cmd::Quadratic(true)) ?,
-            b'q' => state.next_cmd(Cmd::Quadratic(false)) ?,
-            b'C' => state.next_cmd(Cmd::Cubic(true)) ?,
-            b'c' => state.next_cmd(Cmd::Cubic(false)) ?,
-            b'H' => state.next_cmd(Cmd::Hor(true)) ?,
-            b'h' => state.next_cmd(Cmd::Hor(false)) ?,
-            b'V' => state.next_cmd(Cmd::Vert(true)) ?,
-            b'v' => state.next_cmd(Cmd::Vert(false)) ?,
-            b'L' => state.next_cmd(Cmd::Line(true)) ?,
-            b'l' => state.next_cmd(Cmd::Line(false)) ?,
-            b'A' => state.next_cmd(Cmd::Arc(true)) ?,
-            b'a' => state.next_cmd(Cmd::Arc(false)) ?,
-            b'Z' | b'z' => state.next_cmd(Cmd::Close) ?,
-            b'-' => state.add_min() ?,
-            b'0'..=b'9' => state.add_digit((path[i] - b'0') as f64) ?,
-            b'.' => state.add_dot() ?,
-            b',' | b' ' | b'\r' | b'\n' | b'\t' => state.whitespace() ?,
-            x => {
-                return Err(format!("Unexpected character {} - {}", x, x as char))
-            }
-        }
-    }
-    state.finalize_cmd() ?;
-    
-    Ok(state.out)
-}
@@ -24,6 +24,8 @@ pub use crate::{
         Padding,
         Flow,
         Size,
+        GridTrack,
+        GridTemplate,
         TurtleAlignRange,
         DeferWalk
     },
@@ -67,6 +69,8 @@ pub fn live_design(cx: &mut Cx) {
     crate::shader::draw_text::live_design(cx);
     crate::shader::draw_line::live_design(cx);
     crate::geometry::geometry_gen::live_design(cx);
+    // lets shaders drawn with it be checked before anything is drawn
+    cx.draw_shaders.register_geometry_fields(&GeometryQuad2D {geometry_ref: None, x1: 0.0, y1: 0.0, x2: 1.0, y2: 1.0});
     crate::shader::std::live_design(cx);
     crate::shader::draw_trapezoid::live_design(cx);
 }
\ No newline at end of file
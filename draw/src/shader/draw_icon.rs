@@ -4,7 +4,9 @@ use {
         makepad_platform::*,
         draw_list_2d::ManyInstances,
         geometry::GeometryQuad2D,
-        icon_atlas::{CxIconAtlas, CxIconArgs},
+        icon_atlas::{CxIconAtlas, CxIconArgs, CxIconSlot},
+        makepad_vector::geometry::{AffineTransformation, Transformation, Vector},
+        makepad_vector::svg::{SvgShape, SvgPaint, SvgColor, SvgGradient},
         cx_2d::Cx2d,
         turtle::{Walk, Size}
     },
@@ -55,16 +57,55 @@ live_design!{
             return self.color;
         }
         
+        fn get_gradient_color(self, pos: float) -> vec4 {
+            let t = clamp(pos, 0.0, 1.0);
+            let o = self.stop_offsets;
+            if t <= o.x {
+                return self.stop_color0
+            }
+            if t <= o.y {
+                return mix(self.stop_color0, self.stop_color1, (t - o.x) / max(o.y - o.x, 0.0001))
+            }
+            if t <= o.z {
+                return mix(self.stop_color1, self.stop_color2, (t - o.y) / max(o.z - o.y, 0.0001))
+            }
+            if t <= o.w {
+                return mix(self.stop_color2, self.stop_color3, (t - o.z) / max(o.w - o.z, 0.0001))
+            }
+            return self.stop_color3
+        }
+        
+        // the paint of the layer, the color of the icon for layers that don't pick one
+        fn get_paint(self) -> vec4 {
+            if self.paint_kind < 0.5 {
+                return self.get_color()
+            }
+            if self.paint_kind < 1.5 {
+                return self.paint_color
+            }
+            let u = self.paint_matrix.xy * self.pos.x + self.paint_matrix.zw * self.pos.y + self.paint_offset;
+            if self.paint_kind < 2.5 {
+                return self.get_gradient_color(u.x)
+            }
+            return self.get_gradient_color(length(u))
+        }
+        
         fn pixel(self) -> vec4 {
             let dx = dFdx(vec2(self.tex_coord1.x * 2048.0, 0.)).x;
             let dp = 1.0 / 2048.0;
             
-            // basic hardcoded mipmapping so it stops 'swimming' in VR
-            // mipmaps are stored in red/green/blue channel
-            let s = sample2d_rt(self.tex, self.tex_coord1.xy).x;
+            // the layers of an icon share an atlas region, one per channel
+            let c = sample2d_rt(self.tex, self.tex_coord1.xy);
+            let s = c.x;
+            if self.icon_chan > 0.5 {
+                s = c.y
+            }
+            if self.icon_chan > 1.5 {
+                s = c.z
+            }
             s = pow(s, self.u_curve);
-            let col = self.get_color(); //color!(white);//get_color();
-            return vec4(s * col.rgb * self.u_brightness * col.a, s * col.a);
+            let col = self.get_paint();
+            return vec4(s * col.rgb * self.u_brightness * col.a, s * col.a) * self.paint_opacity;
         }
     }
 }
@@ -92,6 +133,19 @@ pub struct DrawIcon {
     #[live] pub color: Vec4,
     #[calc] pub icon_t1: Vec2,
     #[calc] pub icon_t2: Vec2,
+    #[calc] pub icon_chan: f32,
+    
+    // the paint of the layer being drawn, set from the svg
+    #[calc] pub paint_kind: f32,
+    #[calc] pub paint_opacity: f32,
+    #[calc] pub paint_color: Vec4,
+    #[calc] pub paint_matrix: Vec4,
+    #[calc] pub paint_offset: Vec2,
+    #[calc] pub stop_offsets: Vec4,
+    #[calc] pub stop_color0: Vec4,
+    #[calc] pub stop_color1: Vec4,
+    #[calc] pub stop_color2: Vec4,
+    #[calc] pub stop_color3: Vec4,
 }
 
 impl LiveHook for DrawIcon{
@@ -171,33 +225,106 @@ impl DrawIcon {
                 ((dpi_pos.y - snapped_pos.y) * 8.0).floor() / 8.0
             );
             
-            // ok now we need to snap our rect to real pixels
-            let slot = icon_atlas.get_icon_slot(CxIconArgs {
+            // lets snap the pos/size to actual pixels
+            self.rect_pos = (snapped_pos / dpi_factor).into();
+            self.rect_size = (snapped_size / dpi_factor).into();
+            
+            let args = CxIconArgs {
                 linearize: self.linearize as f64,
                 size: snapped_size,
                 scale: self.scale * scale * dpi_factor,
                 translate: self.translate - bounds.pos,
-                subpixel: subpixel 
-            }, path_hash);
-            
-            // lets snap the pos/size to actual pixels
-            self.rect_pos = (snapped_pos / dpi_factor).into();
-            self.rect_size = (snapped_size / dpi_factor).into();
+                subpixel
+            };
+            // maps the unit rect of the instance to the pixels of the atlas region
+            let rect_transform = args.transform().invert().unwrap_or(AffineTransformation::identity())
+                .compose(AffineTransformation::scaling(Vector::new(snapped_size.x, snapped_size.y)));
             
-            self.icon_t1 = slot.t1;
-            self.icon_t2 = slot.t2;
+            if self.many_instances.is_none() && self.draw_vars.can_instance() {
+                self.update_draw_call_vars(icon_atlas);
+            }
+            let (slots, icon) = icon_atlas.get_icon_slots(args, path_hash);
             
-            if let Some(mi) = &mut self.many_instances {
-                mi.instances.extend_from_slice(self.draw_vars.as_slice());
+            if self.many_instances.is_some() {
+                for (slot, shape) in slots.iter().zip(&icon.shapes) {
+                    self.set_layer(slot, shape, &rect_transform);
+                    if let Some(mi) = &mut self.many_instances {
+                        mi.instances.extend_from_slice(self.draw_vars.as_slice());
+                    }
+                }
             }
             else if self.draw_vars.can_instance() {
-                self.update_draw_call_vars(icon_atlas);
-                let new_area = cx.add_aligned_instance(&self.draw_vars);
-                self.draw_vars.area = cx.update_area_refs(self.draw_vars.area, new_area);
+                if slots.len() == 1 {
+                    self.set_layer(&slots[0], &icon.shapes[0], &rect_transform);
+                    let new_area = cx.add_aligned_instance(&self.draw_vars);
+                    self.draw_vars.area = cx.update_area_refs(self.draw_vars.area, new_area);
+                }
+                else if let Some(mut mi) = cx.begin_many_aligned_instances(&self.draw_vars) {
+                    for (slot, shape) in slots.iter().zip(&icon.shapes) {
+                        self.set_layer(slot, shape, &rect_transform);
+                        mi.instances.extend_from_slice(self.draw_vars.as_slice());
+                    }
+                    let new_area = cx.end_many_instances(mi);
+                    self.draw_vars.area = cx.update_area_refs(self.draw_vars.area, new_area);
+                }
             }
         }
     }
     
+    fn set_layer(&mut self, slot: &CxIconSlot, shape: &SvgShape, rect_transform: &AffineTransformation) {
+        self.icon_t1 = slot.t1;
+        self.icon_t2 = slot.t2;
+        self.icon_chan = slot.chan;
+        self.paint_opacity = shape.opacity as f32;
+        match &shape.paint {
+            SvgPaint::CurrentColor => {
+                self.paint_kind = 0.0;
+            }
+            SvgPaint::Color(color) => {
+                self.paint_kind = 1.0;
+                self.paint_color = svg_color(color);
+            }
+            SvgPaint::LinearGradient(gradient) => {
+                self.paint_kind = 2.0;
+                self.set_gradient(gradient, rect_transform);
+            }
+            SvgPaint::RadialGradient(gradient) => {
+                self.paint_kind = 3.0;
+                self.set_gradient(gradient, rect_transform);
+            }
+        }
+    }
+    
+    fn set_gradient(&mut self, gradient: &SvgGradient, rect_transform: &AffineTransformation) {
+        // maps the unit rect of the instance to gradient space
+        let transform = gradient.transform.invert().unwrap_or(AffineTransformation::identity())
+            .compose(*rect_transform);
+        let x = transform.transform_vector(Vector::new(1.0, 0.0));
+        let y = transform.transform_vector(Vector::new(0.0, 1.0));
+        self.paint_matrix = vec4(x.x as f32, x.y as f32, y.x as f32, y.y as f32);
+        self.paint_offset = vec2(transform.z.x as f32, transform.z.y as f32);
+        // the shader has room for 4 stops, longer gradients are sampled at 4 of theirs
+        let stops = &gradient.stops;
+        let stop = | index: usize | {
+            if stops.len() <= 4 {
+                &stops[index.min(stops.len() - 1)]
+            }
+            else {
+                &stops[(index * (stops.len() - 1) + 1) / 3]
+            }
+        };
+        self.stop_offsets = vec4(
+            stop(0).offset as f32,
+            stop(1).offset as f32,
+            stop(2).offset as f32,
+            stop(3).offset as f32
+        );
+        self.stop_color0 = svg_color(&stop(0).color);
+        self.stop_color1 = svg_color(&stop(1).color);
+        self.stop_color2 = svg_color(&stop(2).color);
+        self.stop_color3 = svg_color(&stop(3).color);
+    }
+    
     pub fn update_draw_call_vars(&mut self, atlas: &CxIconAtlas) {
         self.draw_vars.texture_slots[0] = Some(atlas.texture.clone());
         self.draw_vars.user_uniforms[0] = self.brightness;
@@ -205,3 +332,7 @@ impl DrawIcon {
     }
     
 }
+
+fn svg_color(color: &SvgColor) -> Vec4 {
+    vec4(color.r, color.g, color.b, color.a)
+}
@@ -235,13 +235,16 @@ impl DrawText {
         if self.many_instances.is_some() {
             self.end_many_instances(cx)
         }
+        self.headless_text_at(cx, pos, val);
     }
     
     pub fn draw_rel(&mut self, cx: &mut Cx2d, pos: DVec2, val: &str) {
-        self.draw_inner(cx, pos + cx.turtle().origin(), val, &mut *cx.fonts_atlas_rc.clone().0.borrow_mut());
+        let pos = pos + cx.turtle().origin();
+        self.draw_inner(cx, pos, val, &mut cx.fonts_atlas_rc.clone().0.borrow_mut());
         if self.many_instances.is_some() {
             self.end_many_instances(cx)
         }
+        self.headless_text_at(cx, pos, val);
     }
     
     pub fn draw_abs(&mut self, cx: &mut Cx2d, pos: DVec2, val: &str) {
@@ -249,6 +252,17 @@ impl DrawText {
         if self.many_instances.is_some() {
             self.end_many_instances(cx)
         }
+        self.headless_text_at(cx, pos, val);
+    }
+    
+    // text drawn at a position is measured on one line for the headless text log
+    fn headless_text_at(&self, cx: &mut Cx2d, pos: DVec2, val: &str) {
+        if val.is_empty() || !cx.is_headless() || self.text_style.font.font_id.is_none() {
+            return
+        }
+        if let Some(geom) = self.compute_geom(cx, Walk::fit(), val) {
+            cx.headless_text(Rect {pos, size: dvec2(geom.measured_width, geom.measured_height)}, val);
+        }
     }
     
     pub fn begin_many_instances(&mut self, cx: &mut Cx2d) {
@@ -450,8 +464,8 @@ impl DrawText {
         
         let font_size_logical = self.text_style.font_size * 96.0 / (72.0 * fonts_atlas.fonts[font_id].as_ref().unwrap().ttf_font.units_per_em);
         let line_height = self.text_style.font_size * self.text_style.height_factor * self.font_scale;
-        let eval_width = cx.turtle().eval_width(walk.width, walk.margin, cx.turtle().layout().flow);
-        let eval_height = cx.turtle().eval_height(walk.height, walk.margin, cx.turtle().layout().flow);
+        let eval_size = cx.turtle().eval_walk(&walk);
+        let (eval_width, eval_height) = (eval_size.x, eval_size.y);
         
         match if walk.width.is_fit() {&TextWrap::Line}else {&self.wrap} {
             TextWrap::Ellipsis => {
@@ -584,7 +598,7 @@ impl DrawText {
             };
             let y_align = (height - geom.measured_height) * align.y;
             
-            match if walk.width.is_fit() {&TextWrap::Line}else {&self.wrap} {
+            let rect = match if walk.width.is_fit() {&TextWrap::Line}else {&self.wrap} {
                 TextWrap::Ellipsis => {
                     // otherwise we should check the ellipsis
                     if let Some((ellip, at_x, dots)) = geom.ellip_pt {
@@ -593,11 +607,13 @@ impl DrawText {
                             abs_pos: walk.abs_pos,
                             margin: walk.margin,
                             width: Size::Fixed(geom.eval_width),
-                            height: Size::Fixed(height)
+                            height: Size::Fixed(height),
+                            ..walk
                         });
                         
                         self.draw_inner(cx, rect.pos + dvec2(0.0, y_align), &text[0..ellip], fonts_atlas);
                         self.draw_inner(cx, rect.pos + dvec2(at_x, y_align), &"..."[0..dots], fonts_atlas);
+                        rect
                     }
                     else { // we might have space to h-align
                         let rect = cx.walk_turtle(Walk {
@@ -610,10 +626,12 @@ impl DrawText {
                                 } else {
                                     geom.eval_height
                                 }
-                            )
+                            ),
+                            ..walk
                         });
                         let x_align = (geom.eval_width - geom.measured_width) * align.x;
                         self.draw_inner(cx, rect.pos + dvec2(x_align, y_align), text, fonts_atlas);
+                        rect
                     }
                 }
                 TextWrap::Word => {
@@ -624,7 +642,8 @@ impl DrawText {
                         abs_pos: walk.abs_pos,
                         margin: walk.margin,
                         width: Size::Fixed(geom.eval_width),
-                        height: Size::Fixed(geom.measured_height)
+                        height: Size::Fixed(geom.measured_height),
+                        ..walk
                     });
                     let mut pos = dvec2(0.0, 0.0);
                     
@@ -642,6 +661,7 @@ impl DrawText {
                             pos.x = 0.0;
                         }
                     }
+                    rect
                 }
                 TextWrap::Line => {
                     let line_height = self.text_style.font_size * self.text_style.height_factor * self.font_scale;
@@ -650,7 +670,8 @@ impl DrawText {
                         abs_pos: walk.abs_pos,
                         margin: walk.margin,
                         width: Size::Fixed(geom.measured_width),
-                        height: Size::Fixed(height)
+                        height: Size::Fixed(height),
+                        ..walk
                     });
                     // lets do our y alignment
                     let mut ypos = 0.0;
@@ -658,9 +679,10 @@ impl DrawText {
                         self.draw_inner(cx, rect.pos + dvec2(0.0, y_align + ypos), line, fonts_atlas);
                         ypos += line_height * self.text_style.line_spacing;
                     }
-                    
+                    rect
                 }
-            }
+            };
+            cx.headless_text(rect, text);
         }
         if self.many_instances.is_some() {
             self.end_many_instances(cx)
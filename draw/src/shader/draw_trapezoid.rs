@@ -14,6 +14,7 @@ pub use {
         geometry::GeometryQuad2D,
         makepad_vector::font::Glyph,
         makepad_vector::trapezoidator::Trapezoidator,
+        makepad_vector::stroker::Stroker,
         makepad_vector::geometry::{AffineTransformation, Transform, Vector},
         makepad_vector::internal_iter::*,
         makepad_vector::path::PathIterator,
@@ -117,6 +118,7 @@ live_design!{
 #[repr(C)]
 pub struct DrawTrapezoidVector {
     #[rust] pub trapezoidator: Trapezoidator,
+    #[rust] pub stroker: Stroker,
     #[live] pub geometry: GeometryQuad2D,
     #[deref] pub draw_vars: DrawVars,
     #[calc] pub a_xs: Vec2,
@@ -31,13 +31,35 @@ impl Default for Layout{
     }
 }
 
-#[derive(Copy, Clone, Default, Debug, Live, LiveHook, LiveRegister)]
+#[derive(Copy, Clone, Debug, Live, LiveHook, LiveRegister)]
 #[live_ignore]
 pub struct Walk {
     #[live] pub abs_pos: Option<DVec2>,
     #[live] pub margin: Margin,
     #[live] pub width: Size,
     #[live] pub height: Size,
+    #[live] pub min_width: Option<f64>,
+    #[live] pub max_width: Option<f64>,
+    #[live] pub min_height: Option<f64>,
+    #[live] pub max_height: Option<f64>,
+    // the share of the space left that a Fill walk gets next to its Fill siblings
+    #[live(1.0)] pub flex: f64,
+}
+
+impl Default for Walk{
+    fn default()->Self{
+        Self{
+            abs_pos: None,
+            margin: Margin::default(),
+            width: Size::Fill,
+            height: Size::Fill,
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+            flex: 1.0
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug, Live, LiveHook, LiveRegister)]
@@ -76,7 +98,37 @@ pub enum Flow {
     Down,
     //Left,
     //Up,
-    Overlay
+    Overlay,
+    RightWrap,
+    DownWrap,
+    #[live {columns: GridTemplate::default(), rows: GridTemplate::default()}]
+    Grid {columns: GridTemplate, rows: GridTemplate}
+}
+
+// the sizes are f32 to keep Flow small, it holds two templates of these
+#[derive(Copy, Clone, Debug, Default, PartialEq, Live)]
+#[live_ignore]
+pub enum GridTrack {
+    #[pick] #[default] Fill,
+    #[live(1.0)] Flex(f32),
+    #[live(100.0)] Fixed(f32)
+}
+
+pub const GRID_MAX_TRACKS: usize = 8;
+
+// the column or row tracks of a grid, rows past the template size to their content.
+// it has a fixed capacity so Layout stays Copy
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GridTemplate {
+    len: usize,
+    tracks: [GridTrack; GRID_MAX_TRACKS]
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FlexItem {
+    pub flex: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>
 }
 
 #[derive(Copy, Clone, Debug, Live)]
@@ -92,8 +144,7 @@ pub enum Size {
 pub enum DeferWalk{
     Unresolved{
         defer_index: usize,
-        margin: Margin,
-        other_axis: Size,
+        walk: Walk,
         pos: DVec2
     },
     Resolved(Walk)
@@ -113,6 +164,10 @@ pub enum AlignEntry{
 pub struct TurtleWalk {
     align_start: usize,
     defer_index: usize,
+    // the wrapped line or grid row the walk is on
+    line: usize,
+    // the grid cell, a NaN height is a row sized by its content
+    cell: DVec2,
     rect: Rect,
 }
 
@@ -122,7 +177,10 @@ pub struct Turtle {
     layout: Layout,
     align_start: usize,
     turtle_walks_start: usize,
+    defer_start: usize,
     defer_count: usize,
+    line: usize,
+    cell: usize,
     shift: DVec2,
     pos: DVec2,
     origin: DVec2,
@@ -153,10 +211,7 @@ impl<'a> Cx2d<'a> {
         let turtle = self.turtles.last_mut().unwrap();
         let defer_index = turtle.defer_count;
         let pos = turtle.pos;
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk(&walk);
         let margin_size = walk.margin.size();
         match turtle.layout.flow {
             Flow::Right if walk.width.is_fill() => {
@@ -165,10 +220,10 @@ impl<'a> Cx2d<'a> {
                 turtle.update_width_max(turtle.pos.x, 0.0);
                 turtle.update_height_max(turtle.pos.y, size.y + margin_size.y);
                 turtle.defer_count += 1;
+                self.turtle_defers.push(FlexItem {flex: walk.flex, min: walk.min_width, max: walk.max_width});
                 Some(DeferWalk::Unresolved{
                     defer_index,
-                    margin: walk.margin,
-                    other_axis: walk.height,
+                    walk,
                     pos: pos + spacing
                 })
             },
@@ -178,10 +233,10 @@ impl<'a> Cx2d<'a> {
                 turtle.update_width_max(turtle.pos.x, size.x + margin_size.x);
                 turtle.update_height_max(turtle.pos.y, 0.0);
                 turtle.defer_count += 1;
+                self.turtle_defers.push(FlexItem {flex: walk.flex, min: walk.min_height, max: walk.max_height});
                 Some(DeferWalk::Unresolved {
                     defer_index,
-                    margin: walk.margin,
-                    other_axis: walk.width,
+                    walk,
                     pos: pos + spacing
                 })
             },
@@ -191,6 +246,14 @@ impl<'a> Cx2d<'a> {
         }
     }
     
+    // the sizes of the deferred Fill walks of a turtle sharing `space`
+    fn turtle_defer_sizes(&self, turtle: &Turtle, space: f64) -> Vec<f64> {
+        let items = &self.turtle_defers[turtle.defer_start..turtle.defer_start + turtle.defer_count];
+        let mut sizes = vec![0.0; items.len()];
+        flex_distribute(space, items, &mut sizes);
+        sizes
+    }
+    
     pub fn begin_pass_sized_turtle(&mut self, layout: Layout) {
         let pass_size = self.current_pass_size();
         self.align_list.push(AlignEntry::BeginTurtle(dvec2(0.0,0.0),pass_size));
@@ -199,7 +262,10 @@ impl<'a> Cx2d<'a> {
             layout,
             align_start: self.align_list.len() - 1,
             turtle_walks_start: self.turtle_walks.len(),
+            defer_start: self.turtle_defers.len(),
             defer_count: 0,
+            line: 0,
+            cell: 0,
             pos: DVec2 {
                 x: layout.padding.left,
                 y: layout.padding.top
@@ -223,6 +289,7 @@ impl<'a> Cx2d<'a> {
         //log!("{:?}", self.align_list[turtle.align_start]);
         self.align_list[turtle.align_start] = AlignEntry::SkipTurtle{skip:self.align_list.len()};
         self.turtle_walks.truncate(turtle.turtle_walks_start);
+        self.turtle_defers.truncate(turtle.defer_start);
     }
 
     pub fn end_pass_sized_turtle_with_shift(&mut self, area:Area, shift:DVec2){
@@ -237,6 +304,7 @@ impl<'a> Cx2d<'a> {
             skip: self.align_list.len()
         };
         self.turtle_walks.truncate(turtle.turtle_walks_start);
+        self.turtle_defers.truncate(turtle.defer_start);
     }
     
     pub fn begin_turtle_with_guard(&mut self, walk: Walk, layout: Layout, guard_area: Area) {
@@ -246,8 +314,8 @@ impl<'a> Cx2d<'a> {
                 parent.pos + parent.child_spacing(self.turtle_walks.len()) 
             };
             
-            let w = parent.eval_width(walk.width, walk.margin, parent.layout.flow);
-            let h = parent.eval_height(walk.height, walk.margin, parent.layout.flow);
+            let size = parent.eval_walk(&walk);
+            let (w, h) = (size.x, size.y);
             
             // figure out new clipping rect
             let (x0, x1) = if layout.clip_x {
@@ -273,8 +341,8 @@ impl<'a> Cx2d<'a> {
         }
         else {
             let o = DVec2 {x: walk.margin.left, y: walk.margin.top};
-            let w = walk.width.fixed_or_nan();
-            let h = walk.height.fixed_or_nan();
+            let w = walk.clamp_width(walk.width.fixed_or_nan());
+            let h = walk.clamp_height(walk.height.fixed_or_nan());
             
             (o, w, h, (dvec2(o.x, o.y), dvec2(o.x + w, o.y + h)))
         };
@@ -284,7 +352,10 @@ impl<'a> Cx2d<'a> {
             layout,
             align_start: self.align_list.len()-1,
             turtle_walks_start: self.turtle_walks.len(),
+            defer_start: self.turtle_defers.len(),
             defer_count: 0,
+            line: 0,
+            cell: 0,
             pos: DVec2 {
                 x: origin.x + layout.padding.left,
                 y: origin.y + layout.padding.top
@@ -322,14 +393,14 @@ impl<'a> Cx2d<'a> {
         
         // computed height
         let w = if turtle.width.is_nan() {
-            Size::Fixed(turtle.width_used + turtle.layout.padding.right - turtle.layout.scroll.x)
+            Size::Fixed(turtle.walk.clamp_width(turtle.width_used + turtle.layout.padding.right - turtle.layout.scroll.x))
         }
         else {
             Size::Fixed(turtle.width)
         };
         
         let h = if turtle.height.is_nan() {
-            Size::Fixed(turtle.height_used + turtle.layout.padding.bottom - turtle.layout.scroll.y)
+            Size::Fixed(turtle.walk.clamp_height(turtle.height_used + turtle.layout.padding.bottom - turtle.layout.scroll.y))
         }
         else {
             Size::Fixed(turtle.height)
@@ -338,11 +409,10 @@ impl<'a> Cx2d<'a> {
         match turtle.layout.flow {
             Flow::Right => {
                 if turtle.defer_count > 0 {
-                    let left = turtle.width_left();
-                    let part = left / turtle.defer_count as f64;
+                    let sizes = self.turtle_defer_sizes(&turtle, turtle.width_left());
                     for i in turtle.turtle_walks_start..self.turtle_walks.len() {
                         let walk = &self.turtle_walks[i];
-                        let shift_x = walk.defer_index as f64 * part;
+                        let shift_x = sizes[0..walk.defer_index].iter().sum();
                         let shift_y = turtle.layout.align.y * (turtle.padded_height_or_used() - walk.rect.size.y);
                         let align_start = walk.align_start;
                         let align_end = self.get_turtle_walk_align_end(i);
@@ -362,12 +432,11 @@ impl<'a> Cx2d<'a> {
             },
             Flow::Down => {
                 if turtle.defer_count > 0 {
-                    let left = turtle.height_left();
-                    let part = left / turtle.defer_count as f64;
+                    let sizes = self.turtle_defer_sizes(&turtle, turtle.height_left());
                     for i in turtle.turtle_walks_start..self.turtle_walks.len() {
                         let walk = &self.turtle_walks[i];
                         let shift_x = turtle.layout.align.x * (turtle.padded_width_or_used() - walk.rect.size.x);
-                        let shift_y = walk.defer_index as f64 * part;
+                        let shift_y = sizes[0..walk.defer_index].iter().sum();
                         let align_start = walk.align_start;
                         let align_end = self.get_turtle_walk_align_end(i);
                         self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
@@ -394,9 +463,50 @@ impl<'a> Cx2d<'a> {
                     self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
                 }
             }
+            Flow::RightWrap => {
+                // lines are aligned on their own, walks within the height of their line
+                let lines = self.turtle_line_rects(&turtle);
+                let line_start = turtle.origin.x + turtle.layout.padding.left;
+                for i in turtle.turtle_walks_start..self.turtle_walks.len() {
+                    let walk = &self.turtle_walks[i];
+                    let line = lines[walk.line];
+                    let shift_x = turtle.layout.align.x * (turtle.padded_width_or_used() - (line.pos.x + line.size.x - line_start));
+                    let shift_y = turtle.layout.align.y * (line.size.y - walk.rect.size.y);
+                    let align_start = walk.align_start;
+                    let align_end = self.get_turtle_walk_align_end(i);
+                    self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
+                }
+            }
+            Flow::DownWrap => {
+                let lines = self.turtle_line_rects(&turtle);
+                let line_start = turtle.origin.y + turtle.layout.padding.top;
+                for i in turtle.turtle_walks_start..self.turtle_walks.len() {
+                    let walk = &self.turtle_walks[i];
+                    let line = lines[walk.line];
+                    let shift_x = turtle.layout.align.x * (line.size.x - walk.rect.size.x);
+                    let shift_y = turtle.layout.align.y * (turtle.padded_height_or_used() - (line.pos.y + line.size.y - line_start));
+                    let align_start = walk.align_start;
+                    let align_end = self.get_turtle_walk_align_end(i);
+                    self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
+                }
+            }
+            Flow::Grid {..} => {
+                // walks are aligned in their cell, rows without a template track are as high as their content
+                let rows = self.turtle_line_rects(&turtle);
+                for i in turtle.turtle_walks_start..self.turtle_walks.len() {
+                    let walk = &self.turtle_walks[i];
+                    let row_height = if walk.cell.y.is_nan() {rows[walk.line].size.y} else {walk.cell.y};
+                    let shift_x = turtle.layout.align.x * (walk.cell.x - walk.rect.size.x);
+                    let shift_y = turtle.layout.align.y * (row_height - walk.rect.size.y);
+                    let align_start = walk.align_start;
+                    let align_end = self.get_turtle_walk_align_end(i);
+                    self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
+                }
+            }
         }
 
         self.turtle_walks.truncate(turtle.turtle_walks_start);
+        self.turtle_defers.truncate(turtle.defer_start);
         self.align_list.push(AlignEntry::EndTurtle);
         if self.turtles.len() == 0 {
             return Rect {
@@ -444,22 +554,21 @@ impl<'a> Cx2d<'a> {
      fn walk_turtle_move(&mut self, walk: Walk, align_start: usize) -> Rect {
         
         let turtle = self.turtles.last_mut().unwrap();
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk(&walk);
         
         if let Some(pos) = walk.abs_pos {
             self.turtle_walks.push(TurtleWalk {
                 align_start,
                 defer_index: 0,
+                line: turtle.line,
+                cell: size + walk.margin.size(),
                 rect: Rect {pos, size: size + walk.margin.size()}
             });
             
             match turtle.layout.flow {
-                Flow::Right=>turtle.update_height_max(pos.y, size.y + walk.margin.size().y),
-                Flow::Down=>turtle.update_width_max(pos.x, size.x + walk.margin.size().x),
-                Flow::Overlay => { // do not walk
+                Flow::Right | Flow::RightWrap=>turtle.update_height_max(pos.y, size.y + walk.margin.size().y),
+                Flow::Down | Flow::DownWrap=>turtle.update_width_max(pos.x, size.x + walk.margin.size().x),
+                Flow::Overlay | Flow::Grid {..} => { // do not walk
                     turtle.update_width_max(pos.x, size.x);
                     turtle.update_height_max(pos.y,size.y);
                 }
@@ -468,9 +577,15 @@ impl<'a> Cx2d<'a> {
         }
         else {
             let spacing = turtle.child_spacing(self.turtle_walks.len());
-            let pos = turtle.pos;
-        
             let margin_size = walk.margin.size();
+            // a walk that doesnt fit goes on the next line, a turtle already drawn moves along
+            let begin = turtle.pos + spacing;
+            let spacing = turtle.wrap_line(size + margin_size, spacing);
+            let pos = turtle.pos;
+            let wrap_shift = pos + spacing - begin;
+            let line = turtle.line;
+            let mut cell = dvec2(0.0, 0.0);
+            
             match turtle.layout.flow {
                 Flow::Right => {
                     turtle.pos.x = pos.x + size.x + margin_size.x + spacing.x;
@@ -494,6 +609,22 @@ impl<'a> Cx2d<'a> {
                         turtle.update_height_max(turtle.pos.y,0.0);
                     }
                 },
+                Flow::RightWrap => {
+                    turtle.pos.x = pos.x + size.x + margin_size.x + spacing.x;
+                    turtle.update_width_max(turtle.pos.x, 0.0);
+                    turtle.update_height_max(turtle.pos.y, size.y + margin_size.y);
+                },
+                Flow::DownWrap => {
+                    turtle.pos.y = pos.y + size.y + margin_size.y + spacing.y;
+                    turtle.update_width_max(turtle.pos.x, size.x + margin_size.x);
+                    turtle.update_height_max(turtle.pos.y, 0.0);
+                },
+                Flow::Grid {..} => {
+                    cell = turtle.grid_cell_size();
+                    turtle.update_width_max(pos.x, (size.x + margin_size.x).max(cell.x));
+                    turtle.update_height_max(pos.y, (size.y + margin_size.y).max(cell.y));
+                    turtle.next_grid_cell();
+                },
                 Flow::Overlay => { // do not walk
                     turtle.update_width_max(turtle.pos.x, size.x);
                     turtle.update_height_max(turtle.pos.y,size.y);
@@ -503,21 +634,34 @@ impl<'a> Cx2d<'a> {
             self.turtle_walks.push(TurtleWalk {
                 align_start,
                 defer_index: turtle.defer_count,
-                rect: Rect {pos, size: size + margin_size}
+                line,
+                cell,
+                rect: Rect {pos: pos + spacing, size: size + margin_size}
             });
+            if align_start < self.align_list.len() {
+                self.move_align_list(wrap_shift.x, wrap_shift.y, align_start, self.align_list.len(), false, dvec2(0.0, 0.0));
+            }
             Rect {pos: pos + walk.margin.left_top() + spacing, size}
         }
     }
     
+    // the bounding rect of the walks on each wrapped line or grid row of a turtle
+    fn turtle_line_rects(&self, turtle: &Turtle) -> Vec<Rect> {
+        let mut lines = vec![(dvec2(f64::INFINITY, f64::INFINITY), dvec2(f64::NEG_INFINITY, f64::NEG_INFINITY)); turtle.line + 1];
+        for walk in &self.turtle_walks[turtle.turtle_walks_start..] {
+            let (min, max) = &mut lines[walk.line];
+            *min = dvec2(min.x.min(walk.rect.pos.x), min.y.min(walk.rect.pos.y));
+            *max = dvec2(max.x.max(walk.rect.pos.x + walk.rect.size.x), max.y.max(walk.rect.pos.y + walk.rect.size.y));
+        }
+        lines.iter().map( | (min, max) | if min.x > max.x {Rect::default()} else {Rect {pos: *min, size: *max - *min}}).collect()
+    }
+    
     fn walk_turtle_peek(&self, walk: Walk) -> Rect {
         if self.turtles.len() == 0{
             return Rect::default()
         }
         let turtle = self.turtles.last().unwrap();
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk(&walk);
         
         if let Some(pos) = walk.abs_pos {
             Rect {pos: pos + walk.margin.left_top(), size}
@@ -732,7 +876,15 @@ impl Turtle {
                 Flow::Down => {
                     dvec2(0.0, self.layout.spacing)
                 }
-                Flow::Overlay => {
+                // the first walk on a line has no spacing in front of it
+                Flow::RightWrap if self.pos.x > self.origin.x + self.layout.padding.left => {
+                    dvec2(self.layout.spacing, 0.0)
+                }
+                Flow::DownWrap if self.pos.y > self.origin.y + self.layout.padding.top => {
+                    dvec2(0.0, self.layout.spacing)
+                }
+                // the grid keeps its spacing in the cell positions
+                Flow::RightWrap | Flow::DownWrap | Flow::Grid {..} | Flow::Overlay => {
                     dvec2(0.0, 0.0)
                 }
             }
@@ -742,6 +894,73 @@ impl Turtle {
         }
     }
     
+    // moves to the next line when `size` doesnt fit on the current one and returns the spacing to use
+    fn wrap_line(&mut self, size: DVec2, spacing: DVec2) -> DVec2 {
+        match self.layout.flow {
+            Flow::RightWrap => {
+                let line_start = self.origin.x + self.layout.padding.left;
+                if self.pos.x > line_start && self.pos.x + spacing.x + size.x > self.origin.x + self.width - self.layout.padding.right {
+                    self.pos = dvec2(line_start, self.origin.y + self.height_used + self.layout.spacing);
+                    self.line += 1;
+                    return dvec2(0.0, 0.0)
+                }
+            }
+            Flow::DownWrap => {
+                let line_start = self.origin.y + self.layout.padding.top;
+                if self.pos.y > line_start && self.pos.y + spacing.y + size.y > self.origin.y + self.height - self.layout.padding.bottom {
+                    self.pos = dvec2(self.origin.x + self.width_used + self.layout.spacing, line_start);
+                    self.line += 1;
+                    return dvec2(0.0, 0.0)
+                }
+            }
+            _ => ()
+        }
+        spacing
+    }
+    
+    // the x offset and width of the grid column the next walk goes in
+    fn grid_column(&self) -> (f64, f64) {
+        if let Flow::Grid {columns, ..} = &self.layout.flow {
+            if !columns.is_empty() {
+                let sizes = columns.resolve(self.width - self.layout.padding.width(), self.layout.spacing);
+                let x = sizes[0..self.cell].iter().filter( | v | !v.is_nan()).sum::<f64>() + self.cell as f64 * self.layout.spacing;
+                return (x, sizes[self.cell])
+            }
+        }
+        (0.0, max_zero_keep_nan(self.width - self.layout.padding.width()))
+    }
+    
+    // the height of the grid row the next walk goes in, NaN when the row is as high as its content
+    fn grid_row_height(&self) -> f64 {
+        if let Flow::Grid {rows, ..} = &self.layout.flow {
+            if self.line < rows.len() {
+                return rows.resolve(self.height - self.layout.padding.height(), self.layout.spacing)[self.line]
+            }
+        }
+        f64::NAN
+    }
+    
+    fn grid_cell_size(&self) -> DVec2 {
+        dvec2(self.grid_column().1, self.grid_row_height())
+    }
+    
+    fn next_grid_cell(&mut self) {
+        let columns = if let Flow::Grid {columns, ..} = &self.layout.flow {columns.len().max(1)} else {1};
+        self.cell += 1;
+        if self.cell >= columns {
+            let row_height = self.grid_row_height();
+            self.pos.y = if row_height.is_nan() {
+                self.origin.y + self.height_used + self.layout.spacing
+            }
+            else {
+                self.pos.y + row_height + self.layout.spacing
+            };
+            self.cell = 0;
+            self.line += 1;
+        }
+        self.pos.x = self.origin.x + self.layout.padding.left + self.grid_column().0;
+    }
+    
     pub fn rect_is_visible(&self, geom: Rect) -> bool {
         let view = Rect {pos: self.origin + self.layout.scroll, size: dvec2(self.width, self.height)};
         return view.intersects(geom)
@@ -766,6 +985,13 @@ impl Turtle {
         self.layout.scroll
     }
     
+    pub fn eval_walk(&self, walk: &Walk) -> DVec2 {
+        dvec2(
+            walk.clamp_width(self.eval_width(walk.width, walk.margin, self.layout.flow)),
+            walk.clamp_height(self.eval_height(walk.height, walk.margin, self.layout.flow))
+        )
+    }
+    
     pub fn eval_width(&self, width: Size, margin: Margin, flow: Flow) -> f64 {
         return match width {
             Size::Fit => std::f64::NAN,
@@ -775,7 +1001,14 @@ impl Turtle {
                     Flow::Right => {
                         max_zero_keep_nan(self.width_left() - margin.width())
                     },
-                    Flow::Down | Flow::Overlay => {
+                    Flow::RightWrap => {
+                        let spacing = if self.pos.x > self.origin.x + self.layout.padding.left {self.layout.spacing} else {0.0};
+                        max_zero_keep_nan(self.origin.x + self.width - self.layout.padding.right - self.pos.x - spacing - margin.width())
+                    },
+                    Flow::Grid {..} => {
+                        max_zero_keep_nan(self.grid_column().1 - margin.width())
+                    },
+                    Flow::Down | Flow::DownWrap | Flow::Overlay => {
                         let r = max_zero_keep_nan(self.width - self.layout.padding.width() - margin.width());
                         if r.is_nan() {
                             return self.width_used - margin.width() - self.layout.padding.right
@@ -794,7 +1027,7 @@ impl Turtle {
             Size::Fixed(v) => max_zero_keep_nan(v),
             Size::Fill => {
                 match flow {
-                    Flow::Right | Flow::Overlay => {
+                    Flow::Right | Flow::RightWrap | Flow::Overlay => {
                         let r = max_zero_keep_nan(self.height - self.layout.padding.height() - margin.height());
                         if r.is_nan() {
                             return self.height_used - margin.height() - self.layout.padding.bottom
@@ -804,6 +1037,13 @@ impl Turtle {
                     Flow::Down => {
                         max_zero_keep_nan(self.height_left() - margin.height())
                     }
+                    Flow::DownWrap => {
+                        let spacing = if self.pos.y > self.origin.y + self.layout.padding.top {self.layout.spacing} else {0.0};
+                        max_zero_keep_nan(self.origin.y + self.height - self.layout.padding.bottom - self.pos.y - spacing - margin.height())
+                    }
+                    Flow::Grid {..} => {
+                        max_zero_keep_nan(self.grid_row_height() - margin.height())
+                    }
                 }
             }
             Size::All=>self.height
@@ -882,30 +1122,28 @@ impl DeferWalk {
     pub fn resolve(&mut self, cx: &Cx2d) -> Walk {
         match self{
             Self::Resolved(walk)=>{*walk},
-            Self::Unresolved{pos, defer_index, margin, other_axis}=>{
+            Self::Unresolved{pos, defer_index, walk}=>{
                 let turtle = cx.turtles.last().unwrap();
                 let walk = match turtle.layout.flow {
                     Flow::Right => {
-                        let left = turtle.width_left();
-                        let part = left / turtle.defer_count as f64;
+                        let sizes = cx.turtle_defer_sizes(turtle, turtle.width_left());
+                        let offset: f64 = sizes[0..*defer_index].iter().sum();
                         Walk {
-                            abs_pos: Some(*pos + dvec2(part * *defer_index as f64, 0.)),
-                            margin: *margin,
-                            width: Size::Fixed(part),
-                            height: *other_axis
+                            abs_pos: Some(*pos + dvec2(offset, 0.)),
+                            width: Size::Fixed(sizes[*defer_index]),
+                            ..*walk
                         }
                     },
                     Flow::Down => {
-                        let left = turtle.height_left();
-                        let part = left / turtle.defer_count as f64;
+                        let sizes = cx.turtle_defer_sizes(turtle, turtle.height_left());
+                        let offset: f64 = sizes[0..*defer_index].iter().sum();
                         Walk {
-                            abs_pos: Some(*pos + dvec2(0., part * *defer_index as f64)),
-                            margin: *margin,
-                            height: Size::Fixed(part),
-                            width: *other_axis
+                            abs_pos: Some(*pos + dvec2(0., offset)),
+                            height: Size::Fixed(sizes[*defer_index]),
+                            ..*walk
                         }
                     }
-                    Flow::Overlay => panic!()
+                    _ => panic!()
                 };
                 *self = DeferWalk::Resolved(walk);
                 walk
@@ -936,6 +1174,32 @@ impl Layout {
             ..Self::default()
         }
     }
+    
+    pub fn flow_right_wrap() -> Self {
+        Self {
+            flow: Flow::RightWrap,
+            ..Self::default()
+        }
+    }
+    
+    pub fn flow_down_wrap() -> Self {
+        Self {
+            flow: Flow::DownWrap,
+            ..Self::default()
+        }
+    }
+    
+    pub fn flow_grid(columns: &[GridTrack], rows: &[GridTrack]) -> Self {
+        Self {
+            flow: Flow::Grid {columns: GridTemplate::new(columns), rows: GridTemplate::new(rows)},
+            ..Self::default()
+        }
+    }
+    
+    pub fn with_spacing(mut self, v: f64) -> Self {
+        self.spacing = v;
+        self
+    }
 
     pub fn with_scroll(mut self, v: DVec2) -> Self {
         self.scroll = v;
@@ -996,6 +1260,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(0.0),
             height: Size::Fixed(0.0),
+            ..Self::default()
         }
     }
     
@@ -1005,6 +1270,7 @@ impl Walk {
             margin: Margin::default(),
             width: w,
             height: h,
+            ..Self::default()
         }
     }
 
@@ -1014,6 +1280,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(rect.size.x),
             height: Size::Fixed(rect.size.y),
+            ..Self::default()
         }
     }
     
@@ -1023,6 +1290,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(w),
             height: Size::Fixed(h),
+            ..Self::default()
         }
     }
         
@@ -1032,6 +1300,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(size.x),
             height: Size::Fixed(size.y),
+            ..Self::default()
         }
     }
     
@@ -1041,6 +1310,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fit,
             height: Size::Fit,
+            ..Self::default()
         }
     }
     
@@ -1050,6 +1320,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fill,
             height: Size::Fill,
+            ..Self::default()
         }
     }
     
@@ -1059,9 +1330,43 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fill,
             height: Size::Fit,
+            ..Self::default()
         }
     }
     
+    pub fn with_min_width(mut self, v: f64) -> Self {
+        self.min_width = Some(v);
+        self
+    }
+    
+    pub fn with_max_width(mut self, v: f64) -> Self {
+        self.max_width = Some(v);
+        self
+    }
+    
+    pub fn with_min_height(mut self, v: f64) -> Self {
+        self.min_height = Some(v);
+        self
+    }
+    
+    pub fn with_max_height(mut self, v: f64) -> Self {
+        self.max_height = Some(v);
+        self
+    }
+    
+    pub fn with_flex(mut self, v: f64) -> Self {
+        self.flex = v;
+        self
+    }
+    
+    pub fn clamp_width(&self, v: f64) -> f64 {
+        clamp_keep_nan(v, self.min_width, self.max_width)
+    }
+    
+    pub fn clamp_height(&self, v: f64) -> f64 {
+        clamp_keep_nan(v, self.min_height, self.max_height)
+    }
+    
     pub fn with_abs_pos(mut self, v: DVec2) -> Self {
         self.abs_pos = Some(v);
         self
@@ -1220,6 +1525,165 @@ impl Size {
     }
 }
 
+impl LiveHook for GridTrack {
+    fn skip_apply(&mut self, _cx: &mut Cx, _apply_from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> Option<usize> {
+        match &nodes[index].value {
+            LiveValue::Float32(v) => {
+                *self = Self::Fixed(*v);
+                Some(index + 1)
+            }
+            LiveValue::Float64(v) => {
+                *self = Self::Fixed(*v as f32);
+                Some(index + 1)
+            }
+            LiveValue::Int64(v) => {
+                *self = Self::Fixed(*v as f32);
+                Some(index + 1)
+            }
+            _ => None
+        }
+    }
+}
+
+impl GridTemplate {
+    pub fn new(tracks: &[GridTrack]) -> Self {
+        let mut template = Self::default();
+        for track in tracks.iter().take(GRID_MAX_TRACKS) {
+            template.push(*track);
+        }
+        template
+    }
+    
+    pub fn push(&mut self, track: GridTrack) {
+        if self.len < GRID_MAX_TRACKS {
+            self.tracks[self.len] = track;
+            self.len += 1;
+        }
+    }
+    
+    pub fn tracks(&self) -> &[GridTrack] {
+        &self.tracks[0..self.len]
+    }
+    
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Sizes the tracks to `space` with `spacing` between them. Fixed tracks
+    /// get their size, the rest is shared by the Fill and Flex tracks.
+    pub fn resolve(&self, space: f64, spacing: f64) -> [f64; GRID_MAX_TRACKS] {
+        let mut sizes = [0.0; GRID_MAX_TRACKS];
+        let mut items = [FlexItem {flex: 0.0, min: None, max: None}; GRID_MAX_TRACKS];
+        let mut left = space - spacing * (self.len.max(1) - 1) as f64;
+        for (i, track) in self.tracks().iter().enumerate() {
+            match track {
+                GridTrack::Fixed(v) => left -= *v as f64,
+                GridTrack::Fill => items[i].flex = 1.0,
+                GridTrack::Flex(v) => items[i].flex = *v as f64,
+            }
+        }
+        flex_distribute(left, &items[0..self.len], &mut sizes[0..self.len]);
+        for (i, track) in self.tracks().iter().enumerate() {
+            if let GridTrack::Fixed(v) = track {
+                sizes[i] = *v as f64;
+            }
+        }
+        sizes
+    }
+}
+
+impl LiveHook for GridTemplate {}
+
+impl LiveApply for GridTemplate {
+    fn apply(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
+        *self = Self::default();
+        if let LiveValue::Array = nodes[index].value {
+            let mut node_iter = nodes.first_child(index);
+            while let Some(track_index) = node_iter {
+                let mut track = GridTrack::default();
+                track.apply(cx, from, track_index, nodes);
+                self.push(track);
+                node_iter = nodes.next_child(track_index);
+            }
+            return nodes.skip_node(index)
+        }
+        // a single track
+        let mut track = GridTrack::default();
+        let index = track.apply(cx, from, index, nodes);
+        self.push(track);
+        index
+    }
+}
+
+impl LiveNew for GridTemplate {
+    fn new(_cx: &mut Cx) -> Self {
+        Self::default()
+    }
+    
+    fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
+        LiveTypeInfo {
+            module_id: LiveModuleId::from_str(module_path!()).unwrap(),
+            live_type: LiveType::of::<Self>(),
+            fields: Vec::new(),
+            live_ignore: true,
+            type_name: LiveId::from_str_with_lut("GridTemplate").unwrap(),
+        }
+    }
+}
+
+/// Shares `space` between the items by their flex weight. Items that end up below
+/// their min or above their max are fixed at that size and the rest of the space is
+/// shared again between the others, until no item violates its bounds.
+pub fn flex_distribute(space: f64, items: &[FlexItem], sizes: &mut [f64]) {
+    let mut frozen = vec![false; items.len()];
+    loop {
+        let mut left = space;
+        let mut flex = 0.0;
+        for (i, item) in items.iter().enumerate() {
+            if frozen[i] {
+                left -= sizes[i];
+            }
+            else {
+                flex += item.flex.max(0.0);
+            }
+        }
+        let mut violation = 0.0;
+        for (i, item) in items.iter().enumerate() {
+            if !frozen[i] {
+                let size = if flex > 0.0 {max_zero_keep_nan(left) * item.flex.max(0.0) / flex} else {0.0};
+                sizes[i] = clamp_keep_nan(size, item.min, item.max);
+                if !size.is_nan() {
+                    violation += sizes[i] - size;
+                }
+            }
+        }
+        if violation.abs() < 0.000000001 {
+            return
+        }
+        // freeze the items that were clamped in the direction of the total violation
+        for (i, item) in items.iter().enumerate() {
+            if !frozen[i] {
+                let size = sizes[i];
+                if violation > 0.0 && item.min.is_some_and( | min | size <= min) || violation < 0.0 && item.max.is_some_and( | max | size >= max) {
+                    frozen[i] = true;
+                }
+            }
+        }
+    }
+}
+
+fn clamp_keep_nan(v: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+    if v.is_nan() {
+        return v
+    }
+    let v = if let Some(max) = max {v.min(max)} else {v};
+    if let Some(min) = min {v.max(min)} else {v}
+}
+
 fn max_zero_keep_nan(v: f64) -> f64 {
     if v.is_nan() {
         v
@@ -0,0 +1,169 @@
+use makepad_draw::*;
+use makepad_draw::turtle::{flex_distribute, FlexItem};
+
+live_design!{
+    CardGrid = {{CardGrid}} {
+        layout: {
+            flow: Grid {columns: [100, Fill, Flex(2.0)], rows: [40]},
+            spacing: 10.0
+        }
+        walk: {width: Fill, height: Fit, min_width: 50, max_width: 80, flex: 2.0}
+    }
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+pub struct CardGrid {
+    #[live] layout: Layout,
+    #[live] walk: Walk
+}
+
+fn new_cx() -> Cx {
+    let mut cx = Cx::new(Box::new( | _, _ | {}));
+    makepad_draw::live_design(&mut cx);
+    crate::live_design(&mut cx);
+    let mut errs = Vec::new();
+    cx.live_registry.borrow_mut().expand_all_documents(&mut errs);
+    assert!(errs.is_empty(), "{:?}", errs);
+    cx
+}
+
+fn layout(f: impl FnOnce(&mut Cx2d)) {
+    let mut cx = new_cx();
+    let draw_event = DrawEvent::default();
+    f(&mut Cx2d::new(&mut cx, &draw_event));
+}
+
+fn rect(x: f64, y: f64, w: f64, h: f64) -> Rect {
+    Rect {pos: dvec2(x, y), size: dvec2(w, h)}
+}
+
+#[test]
+fn test_right_wrap() {
+    layout( | cx | {
+        cx.begin_turtle(Walk::size(Size::Fixed(100.0), Size::Fit), Layout::flow_right_wrap().with_padding_all(5.0).with_spacing(10.0));
+        assert_eq!(cx.walk_turtle(Walk::fixed(40.0, 10.0)), rect(5.0, 5.0, 40.0, 10.0));
+        assert_eq!(cx.walk_turtle(Walk::fixed(40.0, 20.0)), rect(55.0, 5.0, 40.0, 20.0));
+        // the next line starts below the highest walk of this one, without spacing in front
+        assert_eq!(cx.walk_turtle(Walk::fixed(40.0, 10.0)), rect(5.0, 35.0, 40.0, 10.0));
+        // Fill takes what is left of the line
+        assert_eq!(cx.walk_turtle(Walk::size(Size::Fill, Size::Fixed(10.0))), rect(55.0, 35.0, 40.0, 10.0));
+        assert_eq!(cx.end_turtle(), rect(0.0, 0.0, 100.0, 50.0));
+    });
+}
+
+#[test]
+fn test_down_wrap() {
+    layout( | cx | {
+        cx.begin_turtle(Walk::size(Size::Fit, Size::Fixed(50.0)), Layout::flow_down_wrap().with_spacing(10.0));
+        assert_eq!(cx.walk_turtle(Walk::fixed(10.0, 20.0)), rect(0.0, 0.0, 10.0, 20.0));
+        assert_eq!(cx.walk_turtle(Walk::fixed(30.0, 20.0)), rect(0.0, 30.0, 30.0, 20.0));
+        assert_eq!(cx.walk_turtle(Walk::fixed(10.0, 20.0)), rect(40.0, 0.0, 10.0, 20.0));
+        assert_eq!(cx.end_turtle(), rect(0.0, 0.0, 50.0, 50.0));
+    });
+}
+
+#[test]
+fn test_wrap_moves_turtle() {
+    layout( | cx | {
+        cx.begin_turtle(Walk::size(Size::Fixed(100.0), Size::Fit), Layout::flow_right_wrap());
+        cx.walk_turtle(Walk::fixed(60.0, 10.0));
+        // the size of a turtle is only known at its end, that is where it wraps
+        cx.begin_turtle(Walk::fit(), Layout::flow_right());
+        cx.walk_turtle(Walk::fixed(60.0, 10.0));
+        assert_eq!(cx.end_turtle(), rect(0.0, 10.0, 60.0, 10.0));
+        cx.end_turtle();
+    });
+}
+
+#[test]
+fn test_min_max() {
+    layout( | cx | {
+        cx.begin_turtle(Walk::fixed(200.0, 100.0), Layout::flow_right());
+        assert_eq!(cx.walk_turtle(Walk::fixed(10.0, 10.0).with_min_width(20.0)), rect(0.0, 0.0, 20.0, 10.0));
+        assert_eq!(cx.walk_turtle(Walk::fill().with_max_width(30.0).with_max_height(40.0)), rect(20.0, 0.0, 30.0, 40.0));
+        cx.begin_turtle(Walk::fit().with_min_width(50.0).with_max_height(5.0), Layout::flow_down());
+        cx.walk_turtle(Walk::fixed(10.0, 10.0));
+        assert_eq!(cx.end_turtle(), rect(50.0, 0.0, 50.0, 5.0));
+        cx.end_turtle();
+    });
+}
+
+#[test]
+fn test_flex_weights() {
+    layout( | cx | {
+        cx.begin_turtle(Walk::fixed(200.0, 100.0), Layout::flow_right());
+        let mut a = cx.defer_walk(Walk::fill()).unwrap();
+        let mut b = cx.defer_walk(Walk::fill().with_flex(3.0)).unwrap();
+        assert_eq!(cx.walk_turtle(a.resolve(cx)), rect(0.0, 0.0, 50.0, 100.0));
+        assert_eq!(cx.walk_turtle(b.resolve(cx)), rect(50.0, 0.0, 150.0, 100.0));
+        cx.end_turtle();
+
+        // a sibling stopped by its max leaves the rest to the others
+        cx.begin_turtle(Walk::fixed(100.0, 200.0), Layout::flow_down());
+        cx.walk_turtle(Walk::fixed(10.0, 20.0));
+        let mut a = cx.defer_walk(Walk::fill().with_max_height(30.0)).unwrap();
+        let mut b = cx.defer_walk(Walk::fill()).unwrap();
+        assert_eq!(cx.walk_turtle(a.resolve(cx)), rect(0.0, 20.0, 100.0, 30.0));
+        assert_eq!(cx.walk_turtle(b.resolve(cx)), rect(0.0, 50.0, 100.0, 150.0));
+        cx.end_turtle();
+    });
+}
+
+#[test]
+fn test_flex_distribute() {
+    let item = | flex, min, max | FlexItem {flex, min, max};
+    let mut sizes = [0.0; 3];
+    flex_distribute(300.0, &[item(1.0, None, None), item(1.0, None, None), item(1.0, None, None)], &mut sizes);
+    assert_eq!(sizes, [100.0, 100.0, 100.0]);
+    // a min takes space from the others, a max gives it to them
+    flex_distribute(300.0, &[item(1.0, Some(200.0), None), item(1.0, None, None), item(2.0, None, None)], &mut sizes);
+    assert_eq!(sizes, [200.0, 100.0 / 3.0, 200.0 / 3.0]);
+    flex_distribute(300.0, &[item(1.0, None, Some(50.0)), item(1.0, None, None), item(3.0, None, Some(150.0))], &mut sizes);
+    assert_eq!(sizes, [50.0, 100.0, 150.0]);
+    // without space to share the sizes stay NaN, sized by content
+    flex_distribute(f64::NAN, &[item(1.0, None, None)], &mut sizes[0..1]);
+    assert!(sizes[0].is_nan());
+}
+
+#[test]
+fn test_grid() {
+    layout( | cx | {
+        let layout = Layout::flow_grid(&[GridTrack::Fixed(100.0), GridTrack::Fill, GridTrack::Flex(2.0)], &[GridTrack::Fixed(40.0)]).with_spacing(10.0);
+        cx.begin_turtle(Walk::size(Size::Fixed(420.0), Size::Fit), layout);
+        assert_eq!(cx.walk_turtle(Walk::fill()), rect(0.0, 0.0, 100.0, 40.0));
+        assert_eq!(cx.walk_turtle(Walk::fill()), rect(110.0, 0.0, 100.0, 40.0));
+        assert_eq!(cx.walk_turtle(Walk::fill().with_margin_all(5.0)), rect(225.0, 5.0, 190.0, 30.0));
+        // rows past the template are as high as their content
+        assert_eq!(cx.walk_turtle(Walk::size(Size::Fill, Size::Fixed(25.0))), rect(0.0, 50.0, 100.0, 25.0));
+        cx.begin_turtle(Walk::size(Size::Fill, Size::Fit), Layout::flow_down());
+        cx.walk_turtle(Walk::fixed(10.0, 30.0));
+        assert_eq!(cx.end_turtle(), rect(110.0, 50.0, 100.0, 30.0));
+        cx.walk_turtle(Walk::fixed(10.0, 10.0));
+        assert_eq!(cx.walk_turtle(Walk::fixed(10.0, 10.0)), rect(0.0, 90.0, 10.0, 10.0));
+        assert_eq!(cx.end_turtle(), rect(0.0, 0.0, 420.0, 100.0));
+    });
+}
+
+#[test]
+fn test_grid_template() {
+    let template = GridTemplate::new(&[GridTrack::Fixed(50.0), GridTrack::Fill, GridTrack::Flex(3.0)]);
+    assert_eq!(&template.resolve(270.0, 10.0)[0..3], &[50.0, 50.0, 150.0]);
+    assert_eq!(GridTemplate::new(&[GridTrack::Fill; 12]).len(), 8);
+}
+
+#[test]
+fn test_live_design() {
+    let mut cx = new_cx();
+    let grid = CardGrid::new_from_module(&mut cx, LiveModuleId::from_str(module_path!()).unwrap(), live_id!(CardGrid)).unwrap();
+    if let Flow::Grid {columns, rows} = grid.layout.flow {
+        assert_eq!(columns.tracks(), &[GridTrack::Fixed(100.0), GridTrack::Fill, GridTrack::Flex(2.0)]);
+        assert_eq!(rows.tracks(), &[GridTrack::Fixed(40.0)]);
+    }
+    else {
+        panic!("{:?}", grid.layout.flow)
+    }
+    assert_eq!(grid.walk.min_width, Some(50.0));
+    assert_eq!(grid.walk.max_width, Some(80.0));
+    assert_eq!(grid.walk.max_height, None);
+    assert_eq!(grid.walk.flex, 2.0);
+}
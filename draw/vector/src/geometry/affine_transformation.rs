@@ -39,6 +39,27 @@ impl AffineTransformation {
     pub fn translate(self, v: Vector) -> AffineTransformation {
         AffineTransformation::new(self.xy, self.z + v)
     }
+
+    /// Returns the transformation that applies `other` first and `self` second.
+    pub fn compose(self, other: AffineTransformation) -> AffineTransformation {
+        AffineTransformation::new(
+            self.xy.compose(other.xy),
+            self.transform_vector(other.z) + self.z,
+        )
+    }
+
+    /// Returns the inverse of `self`, or `None` if `self` is not invertible.
+    pub fn invert(self) -> Option<AffineTransformation> {
+        let det = self.xy.x.cross(self.xy.y);
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let xy = LinearTransformation::new(
+            Vector::new(self.xy.y.y, -self.xy.x.y) / det,
+            Vector::new(-self.xy.y.x, self.xy.x.x) / det,
+        );
+        Some(AffineTransformation::new(xy, -xy.transform_vector(self.z)))
+    }
 }
 
 impl Transformation for AffineTransformation {
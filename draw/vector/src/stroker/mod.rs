@@ -0,0 +1,320 @@
+use crate::geometry::{Point, Vector};
+use crate::path::{LinePath, LinePathCommand, LinePathIterator};
+use std::f64::consts::{PI, TAU};
+use std::mem;
+
+/// The shape at the ends of open subpaths.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// The shape at the corners of subpaths.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// The parameters of a stroke, with the same meaning as the corresponding SVG properties.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f64,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f64,
+    pub dashes: Vec<f64>,
+    pub dash_offset: f64,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+}
+
+/// Converts a sequence of line path commands to the outline of its stroke.
+#[derive(Clone, Debug, Default)]
+pub struct Stroker {
+    polylines: Vec<(Vec<Point>, bool)>,
+}
+
+impl Stroker {
+    /// Creates a new stroker.
+    pub fn new() -> Stroker {
+        Stroker::default()
+    }
+
+    /// Returns the outline of the stroke of the given line path commands. Circles are approximated
+    /// with tolerance `epsilon`.
+    ///
+    /// The outline is a set of closed convex contours: one for each segment, join and cap. These
+    /// overlap, and the trapezoidator does not split crossing segments, so the contours should be
+    /// filled one at a time, adding up the coverage.
+    pub fn stroke<P: LinePathIterator>(&mut self, path: P, style: &StrokeStyle, epsilon: f64) -> LinePath {
+        self.collect_polylines(path);
+        let mut outline = LinePath::new();
+        let half_width = style.width * 0.5;
+        if half_width <= 0.0 || !half_width.is_finite() {
+            return outline;
+        }
+        let stroke = StrokeParams {
+            half_width,
+            style,
+            epsilon: epsilon.max(1e-6),
+        };
+        for (points, is_closed) in mem::take(&mut self.polylines) {
+            if let Some(dashes) = dash_pattern(&style.dashes) {
+                for mut dash in dash_polyline(&points, is_closed, &dashes, style.dash_offset) {
+                    dash.dedup();
+                    stroke.stroke_polyline(&dash, false, &mut outline);
+                }
+            } else {
+                stroke.stroke_polyline(&points, is_closed, &mut outline);
+            }
+        }
+        outline
+    }
+
+    fn collect_polylines<P: LinePathIterator>(&mut self, path: P) {
+        let mut polyline: Vec<Point> = Vec::new();
+        let mut initial_point = None;
+        let polylines = &mut self.polylines;
+        polylines.clear();
+        path.for_each(&mut |command| {
+            match command {
+                LinePathCommand::MoveTo(p) => {
+                    if !polyline.is_empty() {
+                        polylines.push((mem::take(&mut polyline), false));
+                    }
+                    initial_point = Some(p);
+                    polyline.push(p);
+                }
+                LinePathCommand::LineTo(p) => {
+                    if polyline.is_empty() {
+                        polyline.push(initial_point.unwrap_or(p));
+                    }
+                    if polyline.last() != Some(&p) {
+                        polyline.push(p);
+                    }
+                }
+                LinePathCommand::Close => {
+                    if polyline.len() > 1 && polyline.first() == polyline.last() {
+                        polyline.pop();
+                    }
+                    if !polyline.is_empty() {
+                        polylines.push((mem::take(&mut polyline), true));
+                    }
+                }
+            }
+            true
+        });
+        if !polyline.is_empty() {
+            polylines.push((polyline, false));
+        }
+    }
+}
+
+struct StrokeParams<'a> {
+    half_width: f64,
+    style: &'a StrokeStyle,
+    epsilon: f64,
+}
+
+impl<'a> StrokeParams<'a> {
+    fn stroke_polyline(&self, points: &[Point], is_closed: bool, outline: &mut LinePath) {
+        let hw = self.half_width;
+        if points.len() == 1 {
+            // a zero length subpath only shows its caps
+            let p = points[0];
+            match self.style.line_cap {
+                LineCap::Butt => (),
+                LineCap::Round => self.push_circle(p, outline),
+                LineCap::Square => push_polygon(outline, &[
+                    p + Vector::new(-hw, -hw),
+                    p + Vector::new(hw, -hw),
+                    p + Vector::new(hw, hw),
+                    p + Vector::new(-hw, hw),
+                ]),
+            }
+            return;
+        }
+        let segment_count = if is_closed && points.len() > 2 {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        let segment = |index: usize| (points[index], points[(index + 1) % points.len()]);
+        for index in 0..segment_count {
+            let (p0, p1) = segment(index);
+            let n = normal(p1 - p0) * hw;
+            push_polygon(outline, &[p0 + n, p1 + n, p1 - n, p0 - n]);
+        }
+        let joins = if is_closed && points.len() > 2 {
+            0..segment_count
+        } else {
+            1..segment_count
+        };
+        for index in joins {
+            let (p0, p) = segment((index + segment_count - 1) % segment_count);
+            let (_, p1) = segment(index);
+            self.push_join(p, p - p0, p1 - p, outline);
+        }
+        if !is_closed || points.len() == 2 {
+            let (p0, p1) = segment(0);
+            self.push_cap(p0, p0 - p1, outline);
+            let (p0, p1) = segment(segment_count - 1);
+            self.push_cap(p1, p1 - p0, outline);
+        }
+    }
+
+    fn push_join(&self, p: Point, d0: Vector, d1: Vector, outline: &mut LinePath) {
+        let hw = self.half_width;
+        let (d0, d1) = match (d0.normalize(), d1.normalize()) {
+            (Some(d0), Some(d1)) => (d0, d1),
+            _ => return,
+        };
+        let cross = d0.cross(d1);
+        if cross.abs() < 1e-9 && d0.dot(d1) > 0.0 {
+            return;
+        }
+        if self.style.line_join == LineJoin::Round {
+            self.push_circle(p, outline);
+            return;
+        }
+        // the offsets on the outside of the turn
+        let side = if cross > 0.0 { -hw } else { hw };
+        let o0 = Vector::new(-d0.y, d0.x) * side;
+        let o1 = Vector::new(-d1.y, d1.x) * side;
+        if self.style.line_join == LineJoin::Miter {
+            let cos_half = ((1.0 + d0.dot(d1)) * 0.5).max(0.0).sqrt();
+            if cos_half > 1e-9 && 1.0 / cos_half <= self.style.miter_limit {
+                if let Some(direction) = (o0 + o1).normalize() {
+                    let tip = p + direction * (hw / cos_half);
+                    push_polygon(outline, &[p, p + o0, tip, p + o1]);
+                    return;
+                }
+            }
+        }
+        push_polygon(outline, &[p, p + o0, p + o1]);
+    }
+
+    fn push_cap(&self, p: Point, outward: Vector, outline: &mut LinePath) {
+        let hw = self.half_width;
+        match self.style.line_cap {
+            LineCap::Butt => (),
+            LineCap::Round => self.push_circle(p, outline),
+            LineCap::Square => {
+                let d = match outward.normalize() {
+                    Some(d) => d * hw,
+                    None => return,
+                };
+                let n = Vector::new(-d.y, d.x);
+                push_polygon(outline, &[p + n, p + n + d, p - n + d, p - n]);
+            }
+        }
+    }
+
+    fn push_circle(&self, center: Point, outline: &mut LinePath) {
+        let r = self.half_width;
+        let step = if self.epsilon < r {
+            2.0 * (1.0 - self.epsilon / r).acos()
+        } else {
+            PI / 4.0
+        };
+        let count = ((TAU / step).ceil() as usize).clamp(8, 256);
+        for index in 0..count {
+            let angle = TAU * index as f64 / count as f64;
+            let p = center + Vector::new(angle.cos(), angle.sin()) * r;
+            if index == 0 {
+                outline.move_to(p);
+            } else {
+                outline.line_to(p);
+            }
+        }
+        outline.close();
+    }
+}
+
+fn normal(d: Vector) -> Vector {
+    d.normalize().map_or(Vector::zero(), |d| Vector::new(-d.y, d.x))
+}
+
+fn push_polygon(outline: &mut LinePath, points: &[Point]) {
+    outline.move_to(points[0]);
+    for p in &points[1..] {
+        outline.line_to(*p);
+    }
+    outline.close();
+}
+
+/// Returns the dash pattern to use, with an even number of entries, or `None` if the stroke is
+/// solid.
+fn dash_pattern(dashes: &[f64]) -> Option<Vec<f64>> {
+    let total: f64 = dashes.iter().sum();
+    if dashes.is_empty() || total <= 0.0 || dashes.iter().any(|d| *d < 0.0 || !d.is_finite()) {
+        return None;
+    }
+    let mut pattern = dashes.to_vec();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_slice(dashes);
+    }
+    Some(pattern)
+}
+
+/// Splits a polyline into the open polylines of its dashes.
+fn dash_polyline(points: &[Point], is_closed: bool, pattern: &[f64], offset: f64) -> Vec<Vec<Point>> {
+    let total: f64 = pattern.iter().sum();
+    let mut index = 0;
+    let mut remaining = offset.rem_euclid(total);
+    while remaining >= pattern[index] {
+        remaining -= pattern[index];
+        index = (index + 1) % pattern.len();
+    }
+    remaining = pattern[index] - remaining;
+
+    let mut dashes = Vec::new();
+    let mut dash = Vec::new();
+    if index % 2 == 0 {
+        dash.push(points[0]);
+    }
+    let segment_count = if is_closed { points.len() } else { points.len() - 1 };
+    for segment in 0..segment_count {
+        let p0 = points[segment];
+        let p1 = points[(segment + 1) % points.len()];
+        let length = (p1 - p0).length();
+        let mut t = 0.0;
+        while length - t > remaining {
+            t += remaining;
+            let p = p0.lerp(p1, t / length);
+            if index % 2 == 0 {
+                dash.push(p);
+                dashes.push(mem::take(&mut dash));
+            } else {
+                dash.push(p);
+            }
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+        remaining -= length - t;
+        if index % 2 == 0 {
+            dash.push(p1);
+        }
+    }
+    if index % 2 == 0 && dash.len() > 1 {
+        dashes.push(dash);
+    }
+    dashes
+}
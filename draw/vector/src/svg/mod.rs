@@ -0,0 +1,751 @@
+//! A renderer-agnostic model of SVG documents, covering the static subset of SVG 1.1 Tiny that
+//! icons and illustrations use: groups, transforms, the basic shapes, fills and strokes, opacity
+//! and gradients.
+//!
+//! Not supported are text, images, clipping, masking, filters, patterns and style sheets. Group
+//! opacity is folded into the opacity of the shapes of the group, rather than compositing the
+//! group on its own.
+
+mod path_data;
+mod xml;
+
+pub use self::path_data::parse_svg_path;
+pub use self::xml::{parse_xml, XmlElement};
+
+use crate::geometry::{AffineTransformation, LinearTransformation, Point, Rectangle, Transform, Vector};
+use crate::internal_iter::FromInternalIterator;
+use crate::path::{LinePath, PathCommand, PathIterator};
+use crate::stroker::{LineCap, LineJoin, StrokeStyle, Stroker};
+use crate::trapezoidator::FillRule;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// A colour with components in the range 0 to 1, not premultiplied.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SvgColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl SvgColor {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> SvgColor {
+        SvgColor { r, g, b, a }
+    }
+
+    fn from_rgb8(r: u8, g: u8, b: u8) -> SvgColor {
+        SvgColor::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgGradientStop {
+    pub offset: f64,
+    pub color: SvgColor,
+}
+
+/// A gradient, defined in its own space: a linear gradient runs from x = 0 to x = 1, a radial
+/// gradient from the origin to the unit circle. Outside that range the end colours are used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgGradient {
+    /// Maps gradient space to the coordinates of the document.
+    pub transform: AffineTransformation,
+    /// The stops, at least two and in order of offset.
+    pub stops: Vec<SvgGradientStop>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SvgPaint {
+    /// The colour of whoever draws the document, for shapes that do not pick a colour.
+    CurrentColor,
+    Color(SvgColor),
+    LinearGradient(SvgGradient),
+    RadialGradient(SvgGradient),
+}
+
+/// A filled or stroked path of a document, in painting order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgShape {
+    /// The path in the coordinates of its element.
+    pub path: Vec<PathCommand>,
+    /// Maps the coordinates of the element to those of the document.
+    pub transform: AffineTransformation,
+    pub fill_rule: FillRule,
+    /// The style of the stroke if the shape is the stroke of the path, rather than its fill.
+    pub stroke: Option<StrokeStyle>,
+    pub paint: SvgPaint,
+    pub opacity: f64,
+}
+
+impl SvgShape {
+    /// Returns the outline to fill for this shape, in document coordinates mapped by `transform`,
+    /// approximated with tolerance `epsilon` in those coordinates.
+    ///
+    /// The outline of a stroke is made of overlapping contours that should be filled one at a
+    /// time, see `Stroker::stroke`.
+    pub fn outline(&self, transform: &AffineTransformation, epsilon: f64, stroker: &mut Stroker) -> LinePath {
+        let transform = transform.compose(self.transform);
+        let scale = transform.xy.x.length().max(transform.xy.y.length()).max(1e-9);
+        // the path is linearized and stroked before it is transformed, which keeps arcs and
+        // stroke widths right under any transform
+        let epsilon = epsilon / scale;
+        let path = self.path.iter().cloned().linearize(epsilon);
+        let outline = match &self.stroke {
+            Some(style) => stroker.stroke(path, style, epsilon),
+            None => LinePath::from_internal_iter(path),
+        };
+        outline.transform(&transform)
+    }
+}
+
+/// A parsed SVG document, reduced to the shapes it paints.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SvgDocument {
+    pub view_box: Option<Rectangle>,
+    pub shapes: Vec<SvgShape>,
+}
+
+impl SvgDocument {
+    /// Returns the bounds of everything the document paints, approximated with tolerance
+    /// `epsilon`, or `None` if it paints nothing.
+    pub fn bounds(&self, epsilon: f64) -> Option<Rectangle> {
+        let mut stroker = Stroker::new();
+        let mut bounds = None;
+        for shape in &self.shapes {
+            let outline = shape.outline(&AffineTransformation::identity(), epsilon, &mut stroker);
+            for p in outline.points() {
+                bounds = Some(union(bounds, *p));
+            }
+        }
+        bounds
+    }
+}
+
+fn union(bounds: Option<Rectangle>, p: Point) -> Rectangle {
+    match bounds {
+        Some(r) => Rectangle::new(
+            Point::new(r.p_min.x.min(p.x), r.p_min.y.min(p.y)),
+            Point::new(r.p_max.x.max(p.x), r.p_max.y.max(p.y)),
+        ),
+        None => Rectangle::new(p, p),
+    }
+}
+
+/// Parses an SVG document.
+pub fn parse_svg(data: &str) -> Result<SvgDocument, String> {
+    let root = parse_xml(data)?;
+    if root.name != "svg" {
+        return Err(format!("Expected an svg root element, found {}", root.name));
+    }
+    let mut ids = HashMap::new();
+    collect_ids(&root, &mut ids);
+    let view_box = root.attribute("viewBox").and_then(|value| {
+        let nums = parse_numbers(value);
+        if nums.len() == 4 {
+            Some(Rectangle::new(
+                Point::new(nums[0], nums[1]),
+                Point::new(nums[0] + nums[2], nums[1] + nums[3]),
+            ))
+        } else {
+            None
+        }
+    });
+    let mut builder = Builder {
+        ids,
+        shapes: Vec::new(),
+        depth: 0,
+    };
+    let style = Style::default().apply(&root);
+    for child in &root.children {
+        builder.element(child, &style, AffineTransformation::identity(), 1.0);
+    }
+    Ok(SvgDocument {
+        view_box,
+        shapes: builder.shapes,
+    })
+}
+
+fn collect_ids<'a>(element: &'a XmlElement, ids: &mut HashMap<&'a str, &'a XmlElement>) {
+    if let Some(id) = element.attribute("id") {
+        ids.entry(id).or_insert(element);
+    }
+    for child in &element.children {
+        collect_ids(child, ids);
+    }
+}
+
+/// A paint as specified, before references and `currentColor` are resolved.
+#[derive(Clone, Debug, PartialEq)]
+enum PaintSpec {
+    None,
+    CurrentColor,
+    Color(SvgColor),
+    Url(String, Box<PaintSpec>),
+}
+
+/// The inherited properties in effect for an element.
+#[derive(Clone, Debug)]
+struct Style {
+    fill: PaintSpec,
+    fill_opacity: f64,
+    fill_rule: FillRule,
+    stroke: PaintSpec,
+    stroke_opacity: f64,
+    stroke_style: StrokeStyle,
+    color: Option<SvgColor>,
+    is_visible: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            // unlike SVG, which fills with black, unspecified fills use the colour of the drawer,
+            // so single colour icons can be tinted
+            fill: PaintSpec::CurrentColor,
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            stroke: PaintSpec::None,
+            stroke_opacity: 1.0,
+            stroke_style: StrokeStyle::default(),
+            color: None,
+            is_visible: true,
+        }
+    }
+}
+
+impl Style {
+    /// Returns the style for `element`, inheriting from `self`.
+    fn apply(&self, element: &XmlElement) -> Style {
+        let mut style = self.clone();
+        for (name, value) in &element.attributes {
+            style.set(name, value);
+        }
+        if let Some(declarations) = element.attribute("style") {
+            for declaration in declarations.split(';') {
+                if let Some((name, value)) = declaration.split_once(':') {
+                    style.set(name.trim(), value.trim());
+                }
+            }
+        }
+        style
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        let value = value.trim();
+        if value == "inherit" {
+            return;
+        }
+        match name {
+            "fill" => if let Some(paint) = parse_paint(value) {
+                self.fill = paint;
+            },
+            "fill-opacity" => self.fill_opacity = parse_opacity(value),
+            "fill-rule" => self.fill_rule = match value {
+                "evenodd" => FillRule::EvenOdd,
+                _ => FillRule::NonZero,
+            },
+            "stroke" => if let Some(paint) = parse_paint(value) {
+                self.stroke = paint;
+            },
+            "stroke-opacity" => self.stroke_opacity = parse_opacity(value),
+            "stroke-width" => if let Some(width) = parse_length(value) {
+                self.stroke_style.width = width;
+            },
+            "stroke-linecap" => self.stroke_style.line_cap = match value {
+                "round" => LineCap::Round,
+                "square" => LineCap::Square,
+                _ => LineCap::Butt,
+            },
+            "stroke-linejoin" => self.stroke_style.line_join = match value {
+                "round" => LineJoin::Round,
+                "bevel" => LineJoin::Bevel,
+                _ => LineJoin::Miter,
+            },
+            "stroke-miterlimit" => if let Ok(limit) = value.parse::<f64>() {
+                self.stroke_style.miter_limit = limit.max(1.0);
+            },
+            "stroke-dasharray" => self.stroke_style.dashes = if value == "none" {
+                Vec::new()
+            } else {
+                value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|s| !s.is_empty())
+                    .filter_map(parse_length)
+                    .collect()
+            },
+            "stroke-dashoffset" => if let Some(offset) = parse_length(value) {
+                self.stroke_style.dash_offset = offset;
+            },
+            "color" => if let Some(PaintSpec::Color(color)) = parse_paint(value) {
+                self.color = Some(color);
+            },
+            "visibility" => self.is_visible = value == "visible",
+            _ => (),
+        }
+    }
+}
+
+struct Builder<'a> {
+    ids: HashMap<&'a str, &'a XmlElement>,
+    shapes: Vec<SvgShape>,
+    depth: usize,
+}
+
+impl<'a> Builder<'a> {
+    fn element(&mut self, element: &'a XmlElement, parent_style: &Style, parent_transform: AffineTransformation, parent_opacity: f64) {
+        if element.attribute("display") == Some("none") || style_declaration(element, "display") == Some("none") {
+            return;
+        }
+        let style = parent_style.apply(element);
+        let mut transform = match element.attribute("transform") {
+            Some(value) => parent_transform.compose(parse_transform(value)),
+            None => parent_transform,
+        };
+        let opacity = parent_opacity
+            * element.attribute("opacity").or_else(|| style_declaration(element, "opacity")).map_or(1.0, parse_opacity);
+        let path = match element.name.as_str() {
+            "g" | "a" | "switch" | "svg" => {
+                if element.name == "svg" {
+                    transform = transform.compose(AffineTransformation::translation(Vector::new(
+                        number_attribute(element, "x"),
+                        number_attribute(element, "y"),
+                    )));
+                }
+                for child in &element.children {
+                    self.element(child, &style, transform, opacity);
+                }
+                return;
+            }
+            "use" => {
+                let href = element.attribute("href").or_else(|| element.attribute("xlink:href"));
+                let target = href.and_then(|href| href.strip_prefix('#')).and_then(|id| self.ids.get(id).cloned());
+                if let Some(target) = target {
+                    // guards against references to an ancestor
+                    if self.depth < 16 {
+                        self.depth += 1;
+                        let transform = transform.compose(AffineTransformation::translation(Vector::new(
+                            number_attribute(element, "x"),
+                            number_attribute(element, "y"),
+                        )));
+                        self.element(target, &style, transform, opacity);
+                        self.depth -= 1;
+                    }
+                }
+                return;
+            }
+            "path" => match element.attribute("d").map(|d| parse_svg_path(d.as_bytes())) {
+                Some(Ok(path)) => path,
+                // like browsers, we render nothing for a path with errors
+                _ => return,
+            },
+            "rect" => rect_path(element),
+            "circle" => {
+                let r = number_attribute(element, "r");
+                ellipse_path(number_attribute(element, "cx"), number_attribute(element, "cy"), r, r)
+            }
+            "ellipse" => ellipse_path(
+                number_attribute(element, "cx"),
+                number_attribute(element, "cy"),
+                number_attribute(element, "rx"),
+                number_attribute(element, "ry"),
+            ),
+            "line" => vec![
+                PathCommand::MoveTo(Point::new(number_attribute(element, "x1"), number_attribute(element, "y1"))),
+                PathCommand::LineTo(Point::new(number_attribute(element, "x2"), number_attribute(element, "y2"))),
+            ],
+            "polyline" | "polygon" => {
+                let nums = parse_numbers(element.attribute("points").unwrap_or(""));
+                let mut path: Vec<PathCommand> = nums
+                    .chunks_exact(2)
+                    .enumerate()
+                    .map(|(index, p)| {
+                        let p = Point::new(p[0], p[1]);
+                        if index == 0 { PathCommand::MoveTo(p) } else { PathCommand::LineTo(p) }
+                    })
+                    .collect();
+                if element.name == "polygon" && !path.is_empty() {
+                    path.push(PathCommand::Close);
+                }
+                path
+            }
+            // defs, gradients, clip paths, text and the like paint nothing themselves
+            _ => return,
+        };
+        if path.is_empty() || !style.is_visible {
+            return;
+        }
+        if let Some(paint) = self.resolve_paint(&style.fill, &style, &path, transform) {
+            self.shapes.push(SvgShape {
+                path: path.clone(),
+                transform,
+                fill_rule: style.fill_rule,
+                stroke: None,
+                paint,
+                opacity: opacity * style.fill_opacity,
+            });
+        }
+        if style.stroke_style.width > 0.0 {
+            if let Some(paint) = self.resolve_paint(&style.stroke, &style, &path, transform) {
+                self.shapes.push(SvgShape {
+                    path,
+                    transform,
+                    fill_rule: FillRule::NonZero,
+                    stroke: Some(style.stroke_style.clone()),
+                    paint,
+                    opacity: opacity * style.stroke_opacity,
+                });
+            }
+        }
+    }
+
+    fn resolve_paint(&self, paint: &PaintSpec, style: &Style, path: &[PathCommand], transform: AffineTransformation) -> Option<SvgPaint> {
+        match paint {
+            PaintSpec::None => None,
+            PaintSpec::CurrentColor => Some(match style.color {
+                Some(color) => SvgPaint::Color(color),
+                None => SvgPaint::CurrentColor,
+            }),
+            PaintSpec::Color(color) => Some(SvgPaint::Color(*color)),
+            PaintSpec::Url(id, fallback) => match self.ids.get(id.as_str()) {
+                Some(gradient) => self.resolve_gradient(gradient, path, transform),
+                None => self.resolve_paint(fallback, style, path, transform),
+            },
+        }
+    }
+
+    fn resolve_gradient(&self, element: &XmlElement, path: &[PathCommand], transform: AffineTransformation) -> Option<SvgPaint> {
+        let is_radial = match element.name.as_str() {
+            "linearGradient" => false,
+            "radialGradient" => true,
+            _ => return None,
+        };
+        // gradients can take their attributes and stops from the gradient they reference
+        let mut chain = vec![element];
+        while chain.len() < 16 {
+            let href = chain.last().unwrap().attribute("href").or_else(|| chain.last().unwrap().attribute("xlink:href"));
+            match href.and_then(|href| href.strip_prefix('#')).and_then(|id| self.ids.get(id)) {
+                Some(next) => chain.push(next),
+                None => break,
+            }
+        }
+        let attribute = |name: &str| chain.iter().find_map(|element| element.attribute(name));
+        let stops = chain
+            .iter()
+            .map(|element| parse_stops(element))
+            .find(|stops| !stops.is_empty())
+            .unwrap_or_default();
+        match stops.len() {
+            0 => return None,
+            1 => return Some(SvgPaint::Color(stops[0].color)),
+            _ => (),
+        }
+
+        let user_space = attribute("gradientUnits") == Some("userSpaceOnUse");
+        let mut units = AffineTransformation::identity();
+        if !user_space {
+            let bounds = LinePath::from_internal_iter(path.iter().cloned().linearize(0.01))
+                .points()
+                .iter()
+                .fold(None, |bounds, p| Some(union(bounds, *p)))?;
+            let size = bounds.p_max - bounds.p_min;
+            if size.x <= 0.0 || size.y <= 0.0 {
+                return None;
+            }
+            units = AffineTransformation::scaling(size).translate(bounds.p_min.to_vector());
+        }
+        let coordinate = |name: &str, default: f64| {
+            attribute(name).and_then(|value| parse_coordinate(value, user_space)).unwrap_or(default)
+        };
+        let gradient = if is_radial {
+            let r = coordinate("r", 0.5);
+            if r <= 0.0 {
+                return Some(SvgPaint::Color(stops.last().unwrap().color));
+            }
+            AffineTransformation::uniform_scaling(r).translate(Vector::new(coordinate("cx", 0.5), coordinate("cy", 0.5)))
+        } else {
+            let p1 = Point::new(coordinate("x1", 0.0), coordinate("y1", 0.0));
+            let p2 = Point::new(coordinate("x2", 1.0), coordinate("y2", 0.0));
+            let d = p2 - p1;
+            if d.length() == 0.0 {
+                return Some(SvgPaint::Color(stops.last().unwrap().color));
+            }
+            AffineTransformation::new(LinearTransformation::new(d, Vector::new(-d.y, d.x)), p1.to_vector())
+        };
+        let gradient_transform = attribute("gradientTransform").map_or(AffineTransformation::identity(), parse_transform);
+        let gradient = SvgGradient {
+            transform: transform.compose(units).compose(gradient_transform).compose(gradient),
+            stops,
+        };
+        Some(if is_radial {
+            SvgPaint::RadialGradient(gradient)
+        } else {
+            SvgPaint::LinearGradient(gradient)
+        })
+    }
+}
+
+fn parse_stops(element: &XmlElement) -> Vec<SvgGradientStop> {
+    let mut stops: Vec<SvgGradientStop> = Vec::new();
+    for stop in element.children.iter().filter(|child| child.name == "stop") {
+        let property = |name: &str| style_declaration(stop, name).or_else(|| stop.attribute(name));
+        let offset = stop.attribute("offset").and_then(|value| parse_coordinate(value, false)).unwrap_or(0.0).clamp(0.0, 1.0);
+        // offsets never decrease
+        let offset = stops.last().map_or(offset, |last| offset.max(last.offset));
+        let mut color = match property("stop-color").and_then(parse_paint) {
+            Some(PaintSpec::Color(color)) => color,
+            _ => SvgColor::new(0.0, 0.0, 0.0, 1.0),
+        };
+        color.a *= property("stop-opacity").map_or(1.0, parse_opacity) as f32;
+        stops.push(SvgGradientStop { offset, color });
+    }
+    stops
+}
+
+fn style_declaration<'a>(element: &'a XmlElement, name: &str) -> Option<&'a str> {
+    element.attribute("style")?.split(';').find_map(|declaration| {
+        let (key, value) = declaration.split_once(':')?;
+        if key.trim() == name {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+fn number_attribute(element: &XmlElement, name: &str) -> f64 {
+    element.attribute(name).and_then(parse_length).unwrap_or(0.0)
+}
+
+fn rect_path(element: &XmlElement) -> Vec<PathCommand> {
+    let x = number_attribute(element, "x");
+    let y = number_attribute(element, "y");
+    let w = number_attribute(element, "width");
+    let h = number_attribute(element, "height");
+    if w <= 0.0 || h <= 0.0 {
+        return Vec::new();
+    }
+    let rx = element.attribute("rx").and_then(parse_length);
+    let ry = element.attribute("ry").and_then(parse_length);
+    // a missing radius takes the value of the other one
+    let (rx, ry) = match (rx, ry) {
+        (Some(rx), Some(ry)) => (rx, ry),
+        (Some(r), None) | (None, Some(r)) => (r, r),
+        (None, None) => (0.0, 0.0),
+    };
+    let rx = rx.clamp(0.0, w * 0.5);
+    let ry = ry.clamp(0.0, h * 0.5);
+    if rx == 0.0 || ry == 0.0 {
+        return vec![
+            PathCommand::MoveTo(Point::new(x, y)),
+            PathCommand::LineTo(Point::new(x + w, y)),
+            PathCommand::LineTo(Point::new(x + w, y + h)),
+            PathCommand::LineTo(Point::new(x, y + h)),
+            PathCommand::Close,
+        ];
+    }
+    let r = Point::new(rx, ry);
+    vec![
+        PathCommand::MoveTo(Point::new(x + rx, y)),
+        PathCommand::LineTo(Point::new(x + w - rx, y)),
+        PathCommand::ArcTo(Point::new(x + w, y + ry), r, 0.0, false, true),
+        PathCommand::LineTo(Point::new(x + w, y + h - ry)),
+        PathCommand::ArcTo(Point::new(x + w - rx, y + h), r, 0.0, false, true),
+        PathCommand::LineTo(Point::new(x + rx, y + h)),
+        PathCommand::ArcTo(Point::new(x, y + h - ry), r, 0.0, false, true),
+        PathCommand::LineTo(Point::new(x, y + ry)),
+        PathCommand::ArcTo(Point::new(x + rx, y), r, 0.0, false, true),
+        PathCommand::Close,
+    ]
+}
+
+fn ellipse_path(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<PathCommand> {
+    if rx <= 0.0 || ry <= 0.0 {
+        return Vec::new();
+    }
+    let r = Point::new(rx, ry);
+    vec![
+        PathCommand::MoveTo(Point::new(cx + rx, cy)),
+        PathCommand::ArcTo(Point::new(cx, cy + ry), r, 0.0, false, true),
+        PathCommand::ArcTo(Point::new(cx - rx, cy), r, 0.0, false, true),
+        PathCommand::ArcTo(Point::new(cx, cy - ry), r, 0.0, false, true),
+        PathCommand::ArcTo(Point::new(cx + rx, cy), r, 0.0, false, true),
+        PathCommand::Close,
+    ]
+}
+
+fn parse_numbers(value: &str) -> Vec<f64> {
+    let mut nums = Vec::new();
+    let mut rest = value.as_bytes();
+    while !rest.is_empty() {
+        match scan_number(rest) {
+            Some((num, len)) => {
+                nums.push(num);
+                rest = &rest[len..];
+            }
+            None => rest = &rest[1..],
+        }
+    }
+    nums
+}
+
+/// Scans a number at the start of `data`, skipping leading separators, and returns it with the
+/// number of bytes consumed.
+fn scan_number(data: &[u8]) -> Option<(f64, usize)> {
+    let mut start = 0;
+    while start < data.len() && matches!(data[start], b' ' | b'\t' | b'\r' | b'\n' | b',') {
+        start += 1;
+    }
+    let mut end = start;
+    if end < data.len() && matches!(data[end], b'-' | b'+') {
+        end += 1;
+    }
+    let mut has_dot = false;
+    while end < data.len() && (data[end].is_ascii_digit() || (data[end] == b'.' && !has_dot)) {
+        has_dot |= data[end] == b'.';
+        end += 1;
+    }
+    if end + 1 < data.len() && matches!(data[end], b'e' | b'E') {
+        let mut exp_end = end + 1;
+        if matches!(data[exp_end], b'-' | b'+') {
+            exp_end += 1;
+        }
+        if exp_end < data.len() && data[exp_end].is_ascii_digit() {
+            while exp_end < data.len() && data[exp_end].is_ascii_digit() {
+                exp_end += 1;
+            }
+            end = exp_end;
+        }
+    }
+    let num = std::str::from_utf8(&data[start..end]).ok()?.parse().ok()?;
+    Some((num, end))
+}
+
+/// Parses a length, ignoring its unit. Lengths in SVG icons are nearly always in user units.
+fn parse_length(value: &str) -> Option<f64> {
+    scan_number(value.trim().as_bytes()).map(|(num, _)| num)
+}
+
+/// Parses a gradient coordinate, in which percentages are fractions of the bounding box.
+fn parse_coordinate(value: &str, user_space: bool) -> Option<f64> {
+    let num = parse_length(value)?;
+    if value.trim_end().ends_with('%') && !user_space {
+        return Some(num / 100.0);
+    }
+    Some(num)
+}
+
+fn parse_opacity(value: &str) -> f64 {
+    let opacity = parse_length(value).unwrap_or(1.0);
+    if value.trim_end().ends_with('%') {
+        return (opacity / 100.0).clamp(0.0, 1.0);
+    }
+    opacity.clamp(0.0, 1.0)
+}
+
+fn parse_paint(value: &str) -> Option<PaintSpec> {
+    let value = value.trim();
+    if let Some(rest) = value.strip_prefix("url(") {
+        let end = rest.find(')')?;
+        let id = rest[..end].trim().trim_matches(|c| c == '\'' || c == '"').strip_prefix('#')?;
+        let fallback = parse_paint(&rest[end + 1..]).unwrap_or(PaintSpec::None);
+        return Some(PaintSpec::Url(id.to_string(), Box::new(fallback)));
+    }
+    match value {
+        "" => return None,
+        "none" => return Some(PaintSpec::None),
+        "currentColor" => return Some(PaintSpec::CurrentColor),
+        "transparent" => return Some(PaintSpec::Color(SvgColor::default())),
+        _ => (),
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        let digit = |index: usize| u8::from_str_radix(hex.get(index..index + 1)?, 16).ok();
+        return match hex.len() {
+            3 => Some(PaintSpec::Color(SvgColor::from_rgb8(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17))),
+            6 => {
+                let byte = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+                Some(PaintSpec::Color(SvgColor::from_rgb8(byte(0)?, byte(2)?, byte(4)?)))
+            }
+            _ => None,
+        };
+    }
+    if let Some(args) = value.strip_prefix("rgb(").or_else(|| value.strip_prefix("rgba(")) {
+        let args: Vec<&str> = args.trim_end_matches(')').split(',').map(|arg| arg.trim()).collect();
+        if args.len() < 3 {
+            return None;
+        }
+        let component = |arg: &str| -> Option<f32> {
+            let num = parse_length(arg)? as f32;
+            Some(if arg.ends_with('%') { num / 100.0 } else { num / 255.0 }.clamp(0.0, 1.0))
+        };
+        let alpha = args.get(3).map_or(1.0, |arg| parse_opacity(arg) as f32);
+        return Some(PaintSpec::Color(SvgColor::new(component(args[0])?, component(args[1])?, component(args[2])?, alpha)));
+    }
+    let (r, g, b) = match value.to_ascii_lowercase().as_str() {
+        "black" => (0, 0, 0),
+        "silver" => (192, 192, 192),
+        "gray" | "grey" => (128, 128, 128),
+        "white" => (255, 255, 255),
+        "maroon" => (128, 0, 0),
+        "red" => (255, 0, 0),
+        "purple" => (128, 0, 128),
+        "fuchsia" | "magenta" => (255, 0, 255),
+        "green" => (0, 128, 0),
+        "lime" => (0, 255, 0),
+        "olive" => (128, 128, 0),
+        "yellow" => (255, 255, 0),
+        "navy" => (0, 0, 128),
+        "blue" => (0, 0, 255),
+        "teal" => (0, 128, 128),
+        "aqua" | "cyan" => (0, 255, 255),
+        "orange" => (255, 165, 0),
+        _ => return None,
+    };
+    Some(PaintSpec::Color(SvgColor::from_rgb8(r, g, b)))
+}
+
+/// Parses a transform list, as found in the `transform` attribute.
+fn parse_transform(value: &str) -> AffineTransformation {
+    let mut transform = AffineTransformation::identity();
+    let mut rest = value;
+    while let Some(open) = rest.find('(') {
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let close = match rest[open..].find(')') {
+            Some(close) => open + close,
+            None => break,
+        };
+        let args = parse_numbers(&rest[open + 1..close]);
+        let arg = |index: usize, default: f64| args.get(index).cloned().unwrap_or(default);
+        let next = match name {
+            "matrix" if args.len() == 6 => AffineTransformation::new(
+                LinearTransformation::new(Vector::new(args[0], args[1]), Vector::new(args[2], args[3])),
+                Vector::new(args[4], args[5]),
+            ),
+            "translate" => AffineTransformation::translation(Vector::new(arg(0, 0.0), arg(1, 0.0))),
+            "scale" => AffineTransformation::scaling(Vector::new(arg(0, 1.0), arg(1, arg(0, 1.0)))),
+            "rotate" => {
+                let angle = arg(0, 0.0) * PI / 180.0;
+                let center = Vector::new(arg(1, 0.0), arg(2, 0.0));
+                let rotation = LinearTransformation::new(
+                    Vector::new(angle.cos(), angle.sin()),
+                    Vector::new(-angle.sin(), angle.cos()),
+                );
+                AffineTransformation::translation(center)
+                    .compose(AffineTransformation::new(rotation, Vector::zero()))
+                    .compose(AffineTransformation::translation(-center))
+            }
+            "skewX" => AffineTransformation::new(
+                LinearTransformation::new(Vector::new(1.0, 0.0), Vector::new((arg(0, 0.0) * PI / 180.0).tan(), 1.0)),
+                Vector::zero(),
+            ),
+            "skewY" => AffineTransformation::new(
+                LinearTransformation::new(Vector::new(1.0, (arg(0, 0.0) * PI / 180.0).tan()), Vector::new(0.0, 1.0)),
+                Vector::zero(),
+            ),
+            _ => AffineTransformation::identity(),
+        };
+        transform = transform.compose(next);
+        rest = &rest[close + 1..];
+    }
+    transform
+}
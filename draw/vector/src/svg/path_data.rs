@@ -0,0 +1,182 @@
+use crate::geometry::{Point, Vector};
+use crate::path::PathCommand;
+
+/// Parses the data of an SVG path, as found in the `d` attribute of a `path` element.
+pub fn parse_svg_path(data: &[u8]) -> Result<Vec<PathCommand>, String> {
+    let mut lexer = Lexer { data, pos: 0 };
+    let mut out = Vec::new();
+    let mut cmd = None;
+    let mut start = Point::origin();
+    let mut current = Point::origin();
+    // the last control point of the previous segment, for the smooth curve commands
+    let mut last_cubic = None;
+    let mut last_quadratic = None;
+    loop {
+        lexer.skip_separators();
+        let c = match lexer.peek() {
+            Some(c) => c,
+            None => break,
+        };
+        if c.is_ascii_alphabetic() {
+            lexer.pos += 1;
+            if c == b'Z' || c == b'z' {
+                out.push(PathCommand::Close);
+                current = start;
+                cmd = None;
+                last_cubic = None;
+                last_quadratic = None;
+                continue;
+            }
+            if !b"MmLlHhVvCcSsQqTtAa".contains(&c) {
+                return Err(format!("Unexpected character {} - {}", c, c as char));
+            }
+            cmd = Some(c);
+        }
+        let c = match cmd {
+            Some(c) => c,
+            None => return Err(format!("Expected a command at {}", lexer.pos)),
+        };
+        let relative = c.is_ascii_lowercase();
+        let base = if relative { current.to_vector() } else { Vector::zero() };
+        let point = |lexer: &mut Lexer| -> Result<Point, String> {
+            let x = lexer.number()?;
+            let y = lexer.number()?;
+            Ok(Point::new(x, y) + base)
+        };
+        let mut next_cubic = None;
+        let mut next_quadratic = None;
+        match c.to_ascii_uppercase() {
+            b'M' => {
+                current = point(&mut lexer)?;
+                start = current;
+                out.push(PathCommand::MoveTo(current));
+                // subsequent pairs are implicit line commands
+                cmd = Some(if relative { b'l' } else { b'L' });
+            }
+            b'L' => {
+                current = point(&mut lexer)?;
+                out.push(PathCommand::LineTo(current));
+            }
+            b'H' => {
+                current = Point::new(lexer.number()? + base.x, current.y);
+                out.push(PathCommand::LineTo(current));
+            }
+            b'V' => {
+                current = Point::new(current.x, lexer.number()? + base.y);
+                out.push(PathCommand::LineTo(current));
+            }
+            b'C' | b'S' => {
+                let p1 = if c.eq_ignore_ascii_case(&b'C') {
+                    point(&mut lexer)?
+                } else {
+                    reflect(last_cubic, current)
+                };
+                let p2 = point(&mut lexer)?;
+                current = point(&mut lexer)?;
+                out.push(PathCommand::CubicTo(p1, p2, current));
+                next_cubic = Some(p2);
+            }
+            b'Q' | b'T' => {
+                let p1 = if c.eq_ignore_ascii_case(&b'Q') {
+                    point(&mut lexer)?
+                } else {
+                    reflect(last_quadratic, current)
+                };
+                current = point(&mut lexer)?;
+                out.push(PathCommand::QuadraticTo(p1, current));
+                next_quadratic = Some(p1);
+            }
+            b'A' => {
+                let rx = lexer.number()?;
+                let ry = lexer.number()?;
+                let x_axis_rotation = lexer.number()?;
+                let large_arc = lexer.flag()?;
+                let sweep = lexer.flag()?;
+                current = point(&mut lexer)?;
+                out.push(PathCommand::ArcTo(current, Point::new(rx, ry), x_axis_rotation, large_arc, sweep));
+            }
+            _ => unreachable!(),
+        }
+        last_cubic = next_cubic;
+        last_quadratic = next_quadratic;
+    }
+    Ok(out)
+}
+
+fn reflect(control: Option<Point>, current: Point) -> Point {
+    match control {
+        Some(control) => current + (current - control),
+        None => current,
+    }
+}
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).cloned()
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(b' ' | b'\t' | b'\r' | b'\n' | b',') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.skip_separators();
+        let start = self.pos;
+        if let Some(b'-' | b'+') = self.peek() {
+            self.pos += 1;
+        }
+        let mut has_digits = false;
+        let mut has_dot = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' => has_digits = true,
+                // a second dot starts the next number, as in "0.5.5"
+                b'.' if !has_dot => has_dot = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        if !has_digits {
+            return Err(format!("Expected a number at {}", start));
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if let Some(b'-' | b'+') = self.peek() {
+                self.pos += 1;
+            }
+            if let Some(b'0'..=b'9') = self.peek() {
+                while let Some(b'0'..=b'9') = self.peek() {
+                    self.pos += 1;
+                }
+            } else {
+                self.pos = mantissa_end;
+            }
+        }
+        let text = std::str::from_utf8(&self.data[start..self.pos]).unwrap();
+        text.parse().map_err(|_| format!("Invalid number {}", text))
+    }
+
+    /// Arc flags are a single digit, and need no separator before the next number.
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        match self.peek() {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(format!("Expected a flag at {}", self.pos)),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+/// An element of an XML document. Text content is not kept, only elements and their attributes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XmlElement {
+    /// The name of the element, without its namespace prefix.
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    /// Returns the value of the attribute with the given name, if any.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parses an XML document and returns its root element.
+pub fn parse_xml(data: &str) -> Result<XmlElement, String> {
+    let mut parser = Parser { data: data.as_bytes(), pos: 0 };
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut root = None;
+    while parser.pos < parser.data.len() {
+        if parser.peek() != Some(b'<') {
+            parser.pos += 1;
+            continue;
+        }
+        if parser.skip_over(b"<?", b"?>")
+            || parser.skip_over(b"<!--", b"-->")
+            || parser.skip_over(b"<![CDATA[", b"]]>")
+            || parser.skip_doctype()
+        {
+            continue;
+        }
+        if parser.starts_with(b"</") {
+            parser.pos += 2;
+            let name = parser.name();
+            parser.skip_whitespace();
+            parser.expect(b'>')?;
+            let element = stack.pop().ok_or_else(|| format!("Unexpected closing tag {}", name))?;
+            if element.name != local_name(&name) {
+                return Err(format!("Closing tag {} does not match {}", name, element.name));
+            }
+            push_element(&mut stack, &mut root, element);
+            continue;
+        }
+        parser.pos += 1;
+        let name = parser.name();
+        if name.is_empty() {
+            return Err(format!("Expected a tag name at {}", parser.pos));
+        }
+        let mut element = XmlElement {
+            name: local_name(&name).to_string(),
+            ..XmlElement::default()
+        };
+        loop {
+            parser.skip_whitespace();
+            match parser.peek() {
+                Some(b'/') => {
+                    parser.pos += 1;
+                    parser.expect(b'>')?;
+                    push_element(&mut stack, &mut root, element);
+                    break;
+                }
+                Some(b'>') => {
+                    parser.pos += 1;
+                    stack.push(element);
+                    break;
+                }
+                Some(_) => {
+                    let key = parser.name();
+                    if key.is_empty() {
+                        return Err(format!("Expected an attribute name at {}", parser.pos));
+                    }
+                    parser.skip_whitespace();
+                    parser.expect(b'=')?;
+                    parser.skip_whitespace();
+                    let value = parser.quoted()?;
+                    element.attributes.push((key, value));
+                }
+                None => return Err(format!("Unexpected end of document in tag {}", element.name)),
+            }
+        }
+    }
+    if let Some(element) = stack.last() {
+        return Err(format!("Tag {} is not closed", element.name));
+    }
+    root.ok_or_else(|| "No root element found".to_string())
+}
+
+fn push_element(stack: &mut [XmlElement], root: &mut Option<XmlElement>, element: XmlElement) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(element);
+    } else if root.is_none() {
+        *root = Some(element);
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).cloned()
+    }
+
+    fn starts_with(&self, pattern: &[u8]) -> bool {
+        self.data[self.pos..].starts_with(pattern)
+    }
+
+    fn skip_over(&mut self, start: &[u8], end: &[u8]) -> bool {
+        if !self.starts_with(start) {
+            return false;
+        }
+        self.pos += start.len();
+        while self.pos < self.data.len() && !self.starts_with(end) {
+            self.pos += 1;
+        }
+        self.pos = (self.pos + end.len()).min(self.data.len());
+        true
+    }
+
+    fn skip_doctype(&mut self) -> bool {
+        if !self.starts_with(b"<!") {
+            return false;
+        }
+        // a doctype can hold an internal subset in brackets
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'[' => depth += 1,
+                b']' => depth -= 1,
+                b'>' if depth == 0 => break,
+                _ => (),
+            }
+        }
+        true
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\r' | b'\n') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        if self.peek() != Some(c) {
+            return Err(format!("Expected {} at {}", c as char, self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b':' | b'.') || c >= 0x80 {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        String::from_utf8_lossy(&self.data[start..self.pos]).into_owned()
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let quote = match self.peek() {
+            Some(c @ (b'"' | b'\'')) => c,
+            _ => return Err(format!("Expected a quoted value at {}", self.pos)),
+        };
+        self.pos += 1;
+        let start = self.pos;
+        while self.peek().ok_or("Unexpected end of document in attribute value")? != quote {
+            self.pos += 1;
+        }
+        let value = String::from_utf8_lossy(&self.data[start..self.pos]);
+        self.pos += 1;
+        Ok(unescape(&value))
+    }
+}
+
+fn unescape(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,175 @@
+use makepad_vector::geometry::{AffineTransformation, Point, Rectangle, Transformation, Vector};
+use makepad_vector::internal_iter::InternalIterator;
+use makepad_vector::path::{LinePathCommand, PathCommand};
+use makepad_vector::stroker::{LineCap, StrokeStyle, Stroker};
+use makepad_vector::svg::{parse_svg, parse_svg_path, SvgColor, SvgPaint};
+use makepad_vector::trapezoidator::{FillRule, Trapezoidator};
+
+fn assert_near(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+}
+
+fn assert_rect_near(r: Rectangle, x0: f64, y0: f64, x1: f64, y1: f64) {
+    assert_near(r.p_min.x, x0);
+    assert_near(r.p_min.y, y0);
+    assert_near(r.p_max.x, x1);
+    assert_near(r.p_max.y, y1);
+}
+
+fn area(trapezoidator: &mut Trapezoidator, path: &[LinePathCommand]) -> f64 {
+    let mut area = 0.0;
+    trapezoidator.trapezoidate(path.iter().cloned()).unwrap().for_each(&mut |t| {
+        area += ((t.xs[1] - t.xs[0]) * ((t.ys[2] - t.ys[0]) + (t.ys[3] - t.ys[1])) * 0.5) as f64;
+        true
+    });
+    area
+}
+
+#[test]
+fn test_path_data() {
+    let path = parse_svg_path(b"M10 20l5-5h-5v5zm1e1,0a5 5 0 01 10 0S1 2 3 4T5 6").unwrap();
+    assert_eq!(path, vec![
+        PathCommand::MoveTo(Point::new(10.0, 20.0)),
+        PathCommand::LineTo(Point::new(15.0, 15.0)),
+        PathCommand::LineTo(Point::new(10.0, 15.0)),
+        PathCommand::LineTo(Point::new(10.0, 20.0)),
+        PathCommand::Close,
+        // relative to the start of the closed subpath
+        PathCommand::MoveTo(Point::new(20.0, 20.0)),
+        PathCommand::ArcTo(Point::new(30.0, 20.0), Point::new(5.0, 5.0), 0.0, false, true),
+        // no previous cubic, so the first control point is the current point
+        PathCommand::CubicTo(Point::new(30.0, 20.0), Point::new(1.0, 2.0), Point::new(3.0, 4.0)),
+        PathCommand::QuadraticTo(Point::new(3.0, 4.0), Point::new(5.0, 6.0)),
+    ]);
+    assert!(parse_svg_path(b"M 0 0 L 1").is_err());
+    assert!(parse_svg_path(b"M 0 0 X 1 1").is_err());
+}
+
+#[test]
+fn test_fill_rule() {
+    let square = |x: f64, size: f64| vec![
+        LinePathCommand::MoveTo(Point::new(x, x)),
+        LinePathCommand::LineTo(Point::new(x + size, x)),
+        LinePathCommand::LineTo(Point::new(x + size, x + size)),
+        LinePathCommand::LineTo(Point::new(x, x + size)),
+        LinePathCommand::Close,
+    ];
+    let mut path = square(0.0, 10.0);
+    path.extend(square(2.0, 6.0));
+    let mut trapezoidator = Trapezoidator::new();
+    assert_near(area(&mut trapezoidator, &path), 100.0);
+    trapezoidator.set_fill_rule(FillRule::EvenOdd);
+    assert_near(area(&mut trapezoidator, &path), 64.0);
+}
+
+#[test]
+fn test_stroke() {
+    let line = [
+        LinePathCommand::MoveTo(Point::new(0.0, 0.0)),
+        LinePathCommand::LineTo(Point::new(10.0, 0.0)),
+        LinePathCommand::LineTo(Point::new(10.0, 10.0)),
+    ];
+    let mut stroker = Stroker::new();
+    let mut style = StrokeStyle {
+        width: 2.0,
+        ..StrokeStyle::default()
+    };
+    let mut trapezoidator = Trapezoidator::new();
+    let outline = stroker.stroke(line.iter().cloned(), &style, 0.01);
+    // each contour of the outline is filled on its own: two segments and a miter join
+    let mut contours = Vec::new();
+    for command in outline.commands() {
+        if let LinePathCommand::MoveTo(_) = command {
+            contours.push(Vec::new());
+        }
+        contours.last_mut().unwrap().push(command);
+    }
+    let mut areas = Vec::new();
+    for contour in &contours {
+        areas.push(area(&mut trapezoidator, contour));
+    }
+    assert_eq!(areas.len(), 3);
+    assert_near(areas[0], 20.0);
+    assert_near(areas[1], 20.0);
+    assert_near(areas[2], 1.0);
+
+    style.line_cap = LineCap::Square;
+    style.dashes = vec![5.0, 2.0];
+    let outline = stroker.stroke(line.iter().cloned(), &style, 0.01);
+    let contours = outline.commands().filter(|command| matches!(command, LinePathCommand::MoveTo(_))).count();
+    // dashes at 0..5, 7..12 and 14..19 of the length, each with a segment and two caps, and the
+    // one around the corner with another segment and a join
+    assert_eq!(contours, 3 * 3 + 2);
+}
+
+#[test]
+fn test_document() {
+    let doc = parse_svg(r##"<?xml version="1.0"?>
+        <!DOCTYPE svg>
+        <svg viewBox="0 0 100 50" xmlns="http://www.w3.org/2000/svg" style="fill-rule:evenodd">
+            <!-- a comment -->
+            <defs>
+                <linearGradient id="base">
+                    <stop offset="0" stop-color="#f00"/>
+                    <stop offset="100%" style="stop-color:blue;stop-opacity:0.5"/>
+                </linearGradient>
+                <linearGradient id="fade" href="#base" x2="0" y2="1"/>
+                <circle id="dot" r="5"/>
+            </defs>
+            <rect width="100" height="50" style="fill:none;"/>
+            <g transform="translate(10, 0) scale(2)" opacity="0.5">
+                <path d="M0 0 H10 V10 H0 Z" fill="rgb(0, 255, 0)" stroke="currentColor" stroke-width="2"/>
+                <rect x="0" y="0" width="10" height="20" fill="url(#fade)"/>
+            </g>
+            <use href="#dot" x="50" y="25" fill-opacity="0.25"/>
+            <circle cx="80" cy="25" r="10" display="none"/>
+        </svg>
+    "##).unwrap();
+    assert_rect_near(doc.view_box.unwrap(), 0.0, 0.0, 100.0, 50.0);
+    assert_eq!(doc.shapes.len(), 4);
+
+    let fill = &doc.shapes[0];
+    assert_eq!(fill.paint, SvgPaint::Color(SvgColor::new(0.0, 1.0, 0.0, 1.0)));
+    assert_eq!(fill.fill_rule, FillRule::EvenOdd);
+    assert_near(fill.opacity, 0.5);
+    assert!(fill.stroke.is_none());
+    let p = fill.transform.transform_point(Point::new(10.0, 10.0));
+    assert_eq!(p, Point::new(30.0, 20.0));
+
+    let stroke = &doc.shapes[1];
+    assert_eq!(stroke.paint, SvgPaint::CurrentColor);
+    assert_near(stroke.stroke.as_ref().unwrap().width, 2.0);
+
+    match &doc.shapes[2].paint {
+        SvgPaint::LinearGradient(gradient) => {
+            assert_eq!(gradient.stops.len(), 2);
+            assert_eq!(gradient.stops[1].color, SvgColor::new(0.0, 0.0, 1.0, 0.5));
+            // the gradient runs down the bounding box of the rect, in document coordinates
+            let p0 = gradient.transform.transform_point(Point::new(0.0, 0.0));
+            let p1 = gradient.transform.transform_point(Point::new(1.0, 0.0));
+            assert_eq!((p0, p1), (Point::new(10.0, 0.0), Point::new(10.0, 40.0)));
+        }
+        paint => panic!("expected a linear gradient, got {:?}", paint),
+    }
+
+    let dot = &doc.shapes[3];
+    assert_eq!(dot.paint, SvgPaint::CurrentColor);
+    assert_near(dot.opacity, 0.25);
+
+    // bounds follow the curves and include the stroke, which sticks out 2 units at the scale
+    assert_rect_near(doc.bounds(0.001).unwrap(), 8.0, -2.0, 55.0, 40.0);
+}
+
+#[test]
+fn test_outline_transform() {
+    let doc = parse_svg(r#"<svg><circle cx="5" cy="5" r="5" transform="rotate(90, 5, 5)"/></svg>"#).unwrap();
+    let shape = &doc.shapes[0];
+    let outline = shape.outline(
+        &AffineTransformation::uniform_scaling(10.0).translate(Vector::new(1.0, 1.0)),
+        0.01,
+        &mut Stroker::new(),
+    );
+    for p in outline.points() {
+        assert_near((*p - Point::new(51.0, 51.0)).length(), 50.0);
+    }
+}
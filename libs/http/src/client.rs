@@ -0,0 +1,396 @@
+// a small blocking HTTP/1.1 client, used by the platforms that have no http stack of their own.
+
+use std::io::{self, prelude::*, BufReader};
+use std::fmt;
+use std::time::Duration;
+
+use makepad_zune_inflate::DeflateDecoder;
+
+use crate::utils::write_bytes_to_tcp_stream_no_error;
+use crate::stream::HttpStream;
+
+pub const MAX_REDIRECTS: usize = 10;
+
+/// The headers that are not sent along once a redirect leaves the origin of the request.
+pub const CREDENTIAL_HEADERS: [&str; 3] = ["Authorization", "Cookie", "Proxy-Authorization"];
+
+#[derive(Clone, Debug, Default)]
+pub struct HttpClientRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HttpClientResponse {
+    /// The url the response came from, which differs from the requested one after a redirect.
+    pub url: String,
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    /// The body, with any gzip or deflate content encoding already undone.
+    pub body: Vec<u8>,
+}
+
+impl HttpClientResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header_value(&self.headers, name)
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpClientError {
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    Io(io::Error),
+    InvalidResponse(String),
+    TooManyRedirects,
+    /// A redirect from https to a url that isn't, which we don't follow.
+    InsecureRedirect(String),
+    Decode(String),
+    WebSocket(String),
+}
+
+impl fmt::Display for HttpClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(url) => write!(f, "invalid url: {}", url),
+            Self::UnsupportedScheme(scheme) => write!(f, "unsupported url scheme: {}", scheme),
+            Self::Io(error) => write!(f, "{}", error),
+            Self::InvalidResponse(what) => write!(f, "invalid http response: {}", what),
+            Self::TooManyRedirects => write!(f, "too many redirects"),
+            Self::InsecureRedirect(url) => write!(f, "insecure redirect to {}", url),
+            Self::Decode(what) => write!(f, "cannot decode response body: {}", what),
+            Self::WebSocket(what) => write!(f, "websocket error: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for HttpClientError {}
+
+impl From<io::Error> for HttpClientError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpUrl {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    /// The path including the query, always starting with a `/`.
+    pub path: String,
+}
+
+impl HttpUrl {
+    pub fn parse(url: &str) -> Result<HttpUrl, HttpClientError> {
+        let invalid = || HttpClientError::InvalidUrl(url.to_string());
+        let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+        let scheme = scheme.to_ascii_lowercase();
+        let default_port = match scheme.as_str() {
+            "http" | "ws" => 80,
+            "https" | "wss" => 443,
+            _ => return Err(HttpClientError::UnsupportedScheme(scheme))
+        };
+        let rest = rest.split('#').next().unwrap();
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        let path = if path.starts_with('?') {format!("/{}", path)} else {path.to_string()};
+        // user info is not supported, but shouldn't end up in the host either
+        let authority = authority.rsplit('@').next().unwrap();
+        let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+            // an ipv6 address like [::1]:8080
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            (host, rest.strip_prefix(':'))
+        }
+        else {
+            match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None)
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid())
+        }
+        let port = match port {
+            Some(port) => port.parse().map_err( | _ | invalid())?,
+            None => default_port
+        };
+        Ok(HttpUrl {
+            scheme,
+            host: host.to_string(),
+            port,
+            path
+        })
+    }
+
+    pub fn default_port(&self) -> u16 {
+        if self.scheme == "https" || self.scheme == "wss" {443} else {80}
+    }
+
+    /// The host as it goes in a `Host` header.
+    pub fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {format!("[{}]", self.host)} else {self.host.clone()};
+        if self.port == self.default_port() {host} else {format!("{}:{}", host, self.port)}
+    }
+
+    /// Resolves the value of a `Location` header against this url.
+    pub fn join(&self, location: &str) -> String {
+        if location.contains("://") {
+            location.to_string()
+        }
+        else if let Some(rest) = location.strip_prefix("//") {
+            format!("{}://{}", self.scheme, rest)
+        }
+        else if location.starts_with('/') {
+            format!("{}://{}{}", self.scheme, self.host_header(), location)
+        }
+        else {
+            let path = self.path.split('?').next().unwrap();
+            let dir = &path[..path.rfind('/').map_or(0, | index | index + 1)];
+            format!("{}://{}{}{}", self.scheme, self.host_header(), dir, location)
+        }
+    }
+}
+
+pub fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find( | (key, _) | key.eq_ignore_ascii_case(name)).map( | (_, value) | value.as_str())
+}
+
+impl HttpClientRequest {
+    pub fn new(method: &str, url: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Sends the request, following redirects, and calls `progress` with the number of body bytes
+    /// received so far and the total if the server told us. Once a redirect goes to another
+    /// scheme, host or port, the `CREDENTIAL_HEADERS` are left out from then on. Redirects from
+    /// https to http are not followed.
+    pub fn send(&self, progress: &mut dyn FnMut(u64, Option<u64>)) -> Result<HttpClientResponse, HttpClientError> {
+        let mut method = self.method.clone();
+        let mut url = self.url.clone();
+        let mut body = self.body.as_deref();
+        let mut origin = None;
+        let mut with_credentials = true;
+        for _ in 0..=MAX_REDIRECTS {
+            let http_url = HttpUrl::parse(&url)?;
+            let url_origin = (http_url.scheme.clone(), http_url.host.to_ascii_lowercase(), http_url.port);
+            if origin.get_or_insert_with( | | url_origin.clone()) != &url_origin {
+                with_credentials = false;
+            }
+            let response = self.send_once(&method, &http_url, body, with_credentials, progress)?;
+            let Some(location) = redirect_location(response.status_code, &response.headers) else {
+                return Ok(HttpClientResponse {url, ..response})
+            };
+            // 307 and 308 repeat the request as is, the others turn it into a GET
+            if response.status_code != 307 && response.status_code != 308 && method != "HEAD" {
+                method = "GET".to_string();
+                body = None;
+            }
+            url = http_url.join(location);
+            if http_url.scheme == "https" && HttpUrl::parse(&url)?.scheme != "https" {
+                return Err(HttpClientError::InsecureRedirect(url))
+            }
+        }
+        Err(HttpClientError::TooManyRedirects)
+    }
+
+    fn send_once(&self, method: &str, url: &HttpUrl, body: Option<&[u8]>, with_credentials: bool, progress: &mut dyn FnMut(u64, Option<u64>)) -> Result<HttpClientResponse, HttpClientError> {
+        let mut stream = HttpStream::connect(url)?;
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        let request = self.request_header(method, url, body, with_credentials);
+        if write_bytes_to_tcp_stream_no_error(&mut stream, request.as_bytes()) ||
+        write_bytes_to_tcp_stream_no_error(&mut stream, body.unwrap_or(&[])) {
+            return Err(HttpClientError::Io(io::Error::new(io::ErrorKind::WriteZero, "cannot write request")))
+        }
+        read_response(&mut BufReader::new(stream), method, progress)
+    }
+
+    fn request_header(&self, method: &str, url: &HttpUrl, body: Option<&[u8]>, with_credentials: bool) -> String {
+        let mut header = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, url.path, url.host_header());
+        for (key, value) in &self.headers {
+            // we decide these ourselves
+            if key.eq_ignore_ascii_case("Host") || key.eq_ignore_ascii_case("Content-Length") {
+                continue
+            }
+            if !with_credentials && CREDENTIAL_HEADERS.iter().any( | name | key.eq_ignore_ascii_case(name)) {
+                continue
+            }
+            header.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        for (key, value) in [("Accept-Encoding", "gzip, deflate"), ("Connection", "close"), ("User-Agent", "makepad")] {
+            if header_value(&self.headers, key).is_none() {
+                header.push_str(&format!("{}: {}\r\n", key, value));
+            }
+        }
+        if let Some(body) = body {
+            header.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        header.push_str("\r\n");
+        header
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, HttpClientError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(HttpClientError::InvalidResponse("connection closed".into()))
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Reads the status line and headers of a single response, including interim ones like
+/// `100 Continue` or `101 Switching Protocols`.
+pub fn read_response_head(reader: &mut impl BufRead) -> Result<(u16, Vec<(String, String)>), HttpClientError> {
+    let status_line = read_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let version = parts.next().unwrap_or("");
+    let status_code: u16 = match parts.next().map( | code | code.parse()) {
+        Some(Ok(status_code)) if version.starts_with("HTTP/") => status_code,
+        _ => return Err(HttpClientError::InvalidResponse(status_line))
+    };
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(HttpClientError::InvalidResponse(line))
+        };
+        headers.push((key.trim().to_string(), value.trim().to_string()));
+    }
+    Ok((status_code, headers))
+}
+
+/// The `Location` of a response that redirects.
+pub fn redirect_location(status_code: u16, headers: &[(String, String)]) -> Option<&str> {
+    match status_code {
+        301 | 302 | 303 | 307 | 308 => header_value(headers, "Location"),
+        _ => None
+    }
+}
+
+/// Reads a response to a request with the given method from `reader`. The body of a redirect is
+/// read without calling `progress`, as it isn't the body the caller is waiting for.
+pub fn read_response(reader: &mut impl BufRead, method: &str, progress: &mut dyn FnMut(u64, Option<u64>)) -> Result<HttpClientResponse, HttpClientError> {
+    let (status_code, mut headers) = loop {
+        let (status_code, headers) = read_response_head(reader)?;
+        // interim responses like 100 Continue are followed by the real one
+        if !(100..200).contains(&status_code) {
+            break (status_code, headers)
+        }
+    };
+    let mut no_progress = | _: u64, _: Option<u64> | {};
+    let progress: &mut dyn FnMut(u64, Option<u64>) = if redirect_location(status_code, &headers).is_some() {
+        &mut no_progress
+    }
+    else {
+        progress
+    };
+
+    let mut body = Vec::new();
+    if method != "HEAD" && status_code != 204 && status_code != 304 {
+        let is_chunked = header_value(&headers, "Transfer-Encoding")
+            .is_some_and( | value | value.to_ascii_lowercase().contains("chunked"));
+        if is_chunked {
+            read_chunked_body(reader, &mut body, progress)?;
+        }
+        else if let Some(length) = header_value(&headers, "Content-Length") {
+            let length: u64 = length.parse().map_err( | _ | HttpClientError::InvalidResponse(format!("Content-Length: {}", length)))?;
+            read_body(reader, &mut body, Some(length), progress)?;
+        }
+        else {
+            read_body(reader, &mut body, None, progress)?;
+        }
+    }
+
+    let decoded = match header_value(&headers, "Content-Encoding").map( | value | value.to_ascii_lowercase()) {
+        Some(encoding) if encoding == "gzip" || encoding == "x-gzip" => {
+            Some(DeflateDecoder::new(&body).decode_gzip()
+                .map_err( | error | HttpClientError::Decode(format!("{:?}", error)))?)
+        }
+        Some(encoding) if encoding == "deflate" => {
+            // deflate is meant to be zlib wrapped, but some servers send it raw
+            Some(DeflateDecoder::new(&body).decode_zlib()
+                .or_else( | _ | DeflateDecoder::new(&body).decode_deflate())
+                .map_err( | error | HttpClientError::Decode(format!("{:?}", error)))?)
+        }
+        _ => None
+    };
+    let body = match decoded {
+        Some(decoded) => {
+            // these describe the body as it was sent, not as we return it
+            headers.retain( | (key, _) | {
+                !key.eq_ignore_ascii_case("Content-Encoding") && !key.eq_ignore_ascii_case("Content-Length")
+            });
+            decoded
+        }
+        None => body
+    };
+    Ok(HttpClientResponse {
+        url: String::new(),
+        status_code,
+        headers,
+        body
+    })
+}
+
+fn read_body(reader: &mut impl Read, body: &mut Vec<u8>, length: Option<u64>, progress: &mut dyn FnMut(u64, Option<u64>)) -> Result<(), HttpClientError> {
+    let mut buffer = [0u8; 65536];
+    loop {
+        let want = match length {
+            Some(length) => (length - body.len() as u64).min(buffer.len() as u64) as usize,
+            None => buffer.len()
+        };
+        if want == 0 {
+            return Ok(())
+        }
+        let read = reader.read(&mut buffer[..want])?;
+        if read == 0 {
+            if length.is_some() {
+                return Err(HttpClientError::InvalidResponse("body is shorter than its Content-Length".into()))
+            }
+            return Ok(())
+        }
+        body.extend_from_slice(&buffer[..read]);
+        progress(body.len() as u64, length);
+    }
+}
+
+/// Reads a body sent with `Transfer-Encoding: chunked`.
+pub fn read_chunked_body(reader: &mut impl BufRead, body: &mut Vec<u8>, progress: &mut dyn FnMut(u64, Option<u64>)) -> Result<(), HttpClientError> {
+    loop {
+        let line = read_line(reader)?;
+        // chunk extensions after a ; are ignored
+        let size = line.split(';').next().unwrap().trim();
+        let size = u64::from_str_radix(size, 16).map_err( | _ | HttpClientError::InvalidResponse(format!("chunk size {}", line)))?;
+        if size == 0 {
+            break
+        }
+        let mut chunk = Vec::new();
+        read_body(reader, &mut chunk, Some(size), &mut | _, _ | {})?;
+        body.extend_from_slice(&chunk);
+        progress(body.len() as u64, None);
+        if !read_line(reader)?.is_empty() {
+            return Err(HttpClientError::InvalidResponse("chunk is longer than its size".into()))
+        }
+    }
+    // skip the trailers, tolerating servers that close the connection instead
+    while let Ok(line) = read_line(reader) {
+        if line.is_empty() {
+            break
+        }
+    }
+    Ok(())
+}
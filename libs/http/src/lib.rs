@@ -2,3 +2,9 @@
  pub mod utils;
  pub mod server;
  pub mod websocket;
+ pub mod client;
+ pub mod websocket_client;
+ pub mod stream;
+ pub mod router;
+ #[cfg(feature = "tls")]
+ pub mod tls;
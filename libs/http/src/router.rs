@@ -0,0 +1,414 @@
+// routes the requests coming out of start_http_server to handlers, and serves static files.
+
+use std::fs::{self, File};
+use std::io::{prelude::*, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::server::{HttpServerRequest, HttpServerResponse};
+use crate::utils::HttpServerHeaders;
+
+impl HttpServerResponse {
+    /// A response with the given status, headers and body. `Content-Length` and
+    /// `Connection: close` are added.
+    pub fn new(status_code: u16, headers: &[(&str, &str)], body: Vec<u8>) -> Self {
+        let mut header = format!("HTTP/1.1 {} {}\r\n", status_code, status_text(status_code));
+        for (key, value) in headers {
+            header.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        header.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+        Self {header, body}
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(404, &[("Content-Type", "text/plain")], b"Not Found".to_vec())
+    }
+}
+
+pub fn status_text(status_code: u16) -> &'static str {
+    match status_code {
+        200 => "OK",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        500 => "Internal Server Error",
+        _ => ""
+    }
+}
+
+/// The parameters a route pattern captured, already percent-decoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteParams(pub Vec<(String, String)>);
+
+impl RouteParams {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter().find( | (key, _) | key == name).map( | (_, value) | value.as_str())
+    }
+}
+
+/// A request as a handler sees it.
+pub struct RouteRequest<'a> {
+    pub headers: &'a HttpServerHeaders,
+    pub params: RouteParams,
+    /// The body of a POST, empty for a GET.
+    pub body: &'a [u8],
+}
+
+type Handler = Box<dyn Fn(&RouteRequest) -> HttpServerResponse + Send + Sync>;
+
+#[derive(Debug)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+struct Route {
+    method: &'static str,
+    segments: Vec<Segment>,
+    handler: Handler,
+}
+
+/// Maps method and path patterns to handlers. A pattern is a path whose segments can be a
+/// `:name` parameter, matching one segment, or end in a `*name` parameter that matches the rest
+/// of the path, for instance `/users/:id` or `/files/*path`. Routes are tried in the order they
+/// were added.
+#[derive(Default)]
+pub struct HttpRouter {
+    routes: Vec<Route>,
+}
+
+impl HttpRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&mut self, pattern: &str, handler: impl Fn(&RouteRequest) -> HttpServerResponse + Send + Sync + 'static) -> &mut Self {
+        self.add("GET", pattern, Box::new(handler))
+    }
+
+    pub fn post(&mut self, pattern: &str, handler: impl Fn(&RouteRequest) -> HttpServerResponse + Send + Sync + 'static) -> &mut Self {
+        self.add("POST", pattern, Box::new(handler))
+    }
+
+    /// Serves the files in `static_files` below `prefix`, so `/prefix/a/b.js` serves `a/b.js`.
+    pub fn static_files(&mut self, prefix: &str, static_files: StaticFiles) -> &mut Self {
+        let pattern = format!("{}/*path", prefix.trim_end_matches('/'));
+        self.get(&pattern, move | request | {
+            static_files.respond(request.params.get("path").unwrap_or(""), request.headers)
+        })
+    }
+
+    fn add(&mut self, method: &'static str, pattern: &str, handler: Handler) -> &mut Self {
+        let segments = path_segments(pattern).map( | segment | {
+            if let Some(name) = segment.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            }
+            else if let Some(name) = segment.strip_prefix('*') {
+                Segment::Rest(name.to_string())
+            }
+            else {
+                Segment::Literal(segment.to_string())
+            }
+        }).collect();
+        self.routes.push(Route {method, segments, handler});
+        self
+    }
+
+    /// Finds the route for a request, returning its index and the parameters it captured.
+    fn find(&self, method: &str, path: &str) -> Option<(usize, RouteParams)> {
+        'routes: for (index, route) in self.routes.iter().enumerate() {
+            if route.method != method {
+                continue
+            }
+            let mut params = RouteParams::default();
+            let mut segments = path_segments(path);
+            for segment in &route.segments {
+                match segment {
+                    Segment::Rest(name) => {
+                        let rest: Vec<&str> = segments.by_ref().collect();
+                        params.0.push((name.clone(), percent_decode(&rest.join("/"))));
+                    }
+                    Segment::Literal(literal) => {
+                        if segments.next() != Some(literal.as_str()) {
+                            continue 'routes
+                        }
+                    }
+                    Segment::Param(name) => {
+                        let Some(value) = segments.next() else {
+                            continue 'routes
+                        };
+                        params.0.push((name.clone(), percent_decode(value)));
+                    }
+                }
+            }
+            if segments.next().is_none() {
+                return Some((index, params))
+            }
+        }
+        None
+    }
+
+    /// The response to a GET or POST to `path`, or `None` if no route matches.
+    pub fn respond(&self, method: &str, headers: &HttpServerHeaders, body: &[u8]) -> Option<HttpServerResponse> {
+        // the server turns a trailing / into /index.html, so we also try what was asked for
+        let (index, params) = self.find(method, &headers.path).or_else( || {
+            let path = headers.path.strip_suffix("index.html").filter( | path | path.ends_with('/')) ?;
+            self.find(method, path)
+        }) ?;
+        Some((self.routes[index].handler)(&RouteRequest {headers, params, body}))
+    }
+
+    /// Answers a GET or POST from the server, with a 404 if no route matches. Other requests,
+    /// which are about websockets, are handed back.
+    pub fn handle(&self, request: HttpServerRequest) -> Option<HttpServerRequest> {
+        match request {
+            HttpServerRequest::Get {headers, response_sender} => {
+                let response = self.respond("GET", &headers, &[]).unwrap_or_else(HttpServerResponse::not_found);
+                let _ = response_sender.send(response);
+                None
+            }
+            HttpServerRequest::Post {headers, body, response} => {
+                let reply = self.respond("POST", &headers, &body).unwrap_or_else(HttpServerResponse::not_found);
+                let _ = response.send(reply);
+                None
+            }
+            other => Some(other)
+        }
+    }
+}
+
+// the segments of a path without its query, ignoring empty ones
+fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('?').next().unwrap().split('/').filter( | segment | !segment.is_empty())
+}
+
+pub fn percent_decode(input: &str) -> String {
+    fn hex(byte: u8) -> Option<u8> {
+        (byte as char).to_digit(16).map( | digit | digit as u8)
+    }
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push(high << 4 | low);
+                i += 3;
+                continue
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The content type for a file name, going by its extension.
+pub fn mime_type(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map_or("", | (_, extension) | extension).to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" | "rs" | "toml" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream"
+    }
+}
+
+/// A byte range of a `Range` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// The inclusive range to send.
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parses a `Range` header for a body of `len` bytes. Headers we don't support, like ones with
+/// several ranges, give `None` so the whole body is sent, which is allowed.
+pub fn parse_range(value: &str, len: u64) -> Option<ByteRange> {
+    let range = value.trim().strip_prefix("bytes=")?.trim();
+    if range.contains(',') {
+        return None
+    }
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let (start, end) = if start.is_empty() {
+        // the last `end` bytes
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(ByteRange::Unsatisfiable)
+        }
+        (len.saturating_sub(suffix), len - 1)
+    }
+    else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {u64::MAX} else {end.parse().ok()?};
+        if end < start {
+            return None
+        }
+        if start >= len {
+            return Some(ByteRange::Unsatisfiable)
+        }
+        (start, end.min(len - 1))
+    };
+    Some(ByteRange::Satisfiable(start, end))
+}
+
+/// Serves the files in a directory, with content types, `ETag`s, `Range` requests and the
+/// precompressed `.br` or `.gz` variant of a file when there is one the client accepts.
+#[derive(Clone, Debug)]
+pub struct StaticFiles {
+    pub root: PathBuf,
+    /// Headers added to every response, like `Cache-Control` or the ones cross-origin isolation needs.
+    pub headers: Vec<(String, String)>,
+    /// When not empty, only files with these extensions are served.
+    pub extensions: Vec<String>,
+}
+
+impl StaticFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            headers: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map( | extension | extension.to_string()).collect();
+        self
+    }
+
+    /// The file for a path relative to the root, if it is one we may serve.
+    pub fn file_path(&self, path: &str) -> Option<PathBuf> {
+        let path = if path.is_empty() || path.ends_with('/') {format!("{}index.html", path)} else {path.to_string()};
+        if path.contains('\\') || path.contains('\0') || path.split('/').any( | segment | segment == ".." || segment == ".") {
+            return None
+        }
+        if !self.extensions.is_empty() {
+            let extension = Path::new(&path).extension()?.to_str()?;
+            if !self.extensions.iter().any( | allowed | allowed.eq_ignore_ascii_case(extension)) {
+                return None
+            }
+        }
+        Some(self.root.join(path.trim_start_matches('/')))
+    }
+
+    /// Responds to a GET of `path`, relative to the root.
+    pub fn respond(&self, path: &str, headers: &HttpServerHeaders) -> HttpServerResponse {
+        let Some(file_path) = self.file_path(path) else {
+            return HttpServerResponse::not_found()
+        };
+        let accept_encoding = headers.header("Accept-Encoding").unwrap_or("");
+        let accepts = | encoding: &str | accept_encoding.split(',').any( | item | {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap().trim();
+            // q=0 means not acceptable
+            name.eq_ignore_ascii_case(encoding) && !parts.any( | param | param.trim().replace(' ', "") == "q=0")
+        });
+
+        // ranges are served from the file itself, so they mean the same with or without compression
+        let is_range = headers.header("Range").is_some();
+        let mut variants = Vec::new();
+        if !is_range {
+            for (encoding, suffix) in [("br", "br"), ("gzip", "gz")] {
+                if accepts(encoding) {
+                    let mut name = file_path.clone().into_os_string();
+                    name.push(".");
+                    name.push(suffix);
+                    variants.push((Some(encoding), PathBuf::from(name)));
+                }
+            }
+        }
+        variants.push((None, file_path.clone()));
+        let Some((encoding, variant_path, metadata)) = variants.into_iter().find_map( | (encoding, path) | {
+            let metadata = fs::metadata(&path).ok().filter( | metadata | metadata.is_file())?;
+            Some((encoding, path, metadata))
+        }) else {
+            return HttpServerResponse::not_found()
+        };
+
+        let len = metadata.len();
+        let modified = metadata.modified().ok()
+            .and_then( | modified | modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, | modified | modified.as_nanos());
+        let etag = format!("\"{:x}-{:x}{}\"", len, modified, encoding.map_or(String::new(), | encoding | format!("-{}", encoding)));
+
+        let content_type = mime_type(&file_path.to_string_lossy());
+        let mut response_headers: Vec<(&str, &str)> = self.headers.iter().map( | (key, value) | (key.as_str(), value.as_str())).collect();
+        response_headers.push(("ETag", &etag));
+        response_headers.push(("Vary", "Accept-Encoding"));
+        response_headers.push(("Accept-Ranges", "bytes"));
+
+        if let Some(if_none_match) = headers.header("If-None-Match") {
+            let matches = if_none_match.split(',').any( | tag | {
+                let tag = tag.trim();
+                tag == "*" || tag.trim_start_matches("W/") == etag
+            });
+            if matches {
+                return HttpServerResponse::new(304, &response_headers, Vec::new())
+            }
+        }
+
+        response_headers.push(("Content-Type", content_type));
+        if let Some(encoding) = encoding {
+            response_headers.push(("Content-Encoding", encoding));
+        }
+
+        let range = headers.header("Range").and_then( | range | parse_range(range, len));
+        let (start, end) = match range {
+            Some(ByteRange::Unsatisfiable) => {
+                let content_range = format!("bytes */{}", len);
+                response_headers.push(("Content-Range", &content_range));
+                return HttpServerResponse::new(416, &response_headers, Vec::new())
+            }
+            Some(ByteRange::Satisfiable(start, end)) => (start, end),
+            None => (0, len.saturating_sub(1)),
+        };
+        let body_len = if len == 0 {0} else {end - start + 1};
+        let mut body = Vec::with_capacity(body_len as usize);
+        let read = File::open(&variant_path).and_then( | mut file | {
+            file.seek(SeekFrom::Start(start))?;
+            file.take(body_len).read_to_end(&mut body)
+        });
+        if read.is_err() || body.len() as u64 != body_len {
+            return HttpServerResponse::new(500, &[], Vec::new())
+        }
+        if range.is_some() {
+            let content_range = format!("bytes {}-{}/{}", start, end, len);
+            response_headers.push(("Content-Range", &content_range));
+            return HttpServerResponse::new(206, &response_headers, body)
+        }
+        HttpServerResponse::new(200, &response_headers, body)
+    }
+}
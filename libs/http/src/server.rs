@@ -7,6 +7,11 @@ use std::time::Duration;
 
 use crate::websocket::{PONG_MESSAGE, WebSocket, WebSocketMessage, MessageFormat, MessageHeader, PING_MESSAGE};
 use crate::utils::*;
+use crate::stream::HttpStream;
+#[cfg(feature = "tls")]
+use crate::tls::{TlsStream, server_config_from_pem_files};
+#[cfg(feature = "tls")]
+use std::path::Path;
 
 #[derive(Clone)]
 pub struct HttpServer {
@@ -48,6 +53,31 @@ pub enum HttpServerRequest {
 pub fn start_http_server(
     http_server: HttpServer,
 ) -> Option<std::thread::JoinHandle<() >> {
+    start_server(http_server, | tcp_stream | Ok(HttpStream::Tcp(tcp_stream)))
+}
+
+/// Like `start_http_server`, but serving https with the certificate chain and private key from
+/// the given PEM files. Requests arrive on the same channel as they would for plain http.
+#[cfg(feature = "tls")]
+pub fn start_https_server(
+    http_server: HttpServer,
+    cert_path: &Path,
+    key_path: &Path,
+) -> Option<std::thread::JoinHandle<() >> {
+    let config = match server_config_from_pem_files(cert_path, key_path) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Cannot load tls certificate or key: {}", e);
+            return None
+        }
+    };
+    start_server(http_server, move | tcp_stream | Ok(HttpStream::Tls(TlsStream::accept(tcp_stream, config.clone()) ?)))
+}
+
+fn start_server(
+    http_server: HttpServer,
+    accept: impl Fn(TcpStream) -> std::io::Result<HttpStream> + Send + 'static,
+) -> Option<std::thread::JoinHandle<() >> {
     
     let listener = if let Ok(listener) = TcpListener::bind(http_server.listen_address) {listener} else {println!("Cannot bind http server port"); return None};
     
@@ -55,7 +85,7 @@ pub fn start_http_server(
         std::thread::spawn(move || {
             let mut connection_counter = 0u64;
             for tcp_stream in listener.incoming() {
-                let mut tcp_stream = if let Ok(tcp_stream) = tcp_stream {
+                let mut tcp_stream = if let Ok(tcp_stream) = tcp_stream.and_then(&accept) {
                     tcp_stream
                 }
                 else {
@@ -66,17 +96,17 @@ pub fn start_http_server(
                 connection_counter += 1;
                 let _read_thread = std::thread::spawn(move || {
                     
-                    let headers = HttpServerHeaders::from_tcp_stream(&mut tcp_stream);
+                    let headers = HttpServerHeaders::from_stream(&mut tcp_stream);
                     if headers.is_none() {
                         return http_error_out(tcp_stream, 500);
                     }
-                    let headers = headers.unwrap();
+                    let (headers, read_ahead) = headers.unwrap();
                     
                     if headers.sec_websocket_key.is_some() {
-                        return handle_web_socket(http_server, tcp_stream, headers, connection_counter);
+                        return handle_web_socket(http_server, tcp_stream, headers, read_ahead, connection_counter);
                     }
                     if headers.verb == "POST" {
-                        return handle_post(http_server, tcp_stream, headers);
+                        return handle_post(http_server, tcp_stream, headers, read_ahead);
                     }
                     if headers.verb == "GET" {
                         return handle_get(http_server, tcp_stream, headers);
@@ -89,7 +119,7 @@ pub fn start_http_server(
     Some(listen_thread)
 }
 
-fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders) {
+fn handle_post(http_server: HttpServer, mut tcp_stream: HttpStream, headers: HttpServerHeaders, read_ahead: Vec<u8>) {
     // we have to have a content-length or bust
     if headers.content_length.is_none() {
         return http_error_out(tcp_stream, 500);
@@ -102,7 +132,10 @@ fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: Http
     let mut body = Vec::new();
     body.resize(bytes_total, 0u8);
     
-    let mut bytes_left = bytes_total;
+    // the start of the body may have been read along with the headers
+    let read_ahead = &read_ahead[0..read_ahead.len().min(bytes_total)];
+    body[0..read_ahead.len()].copy_from_slice(read_ahead);
+    let mut bytes_left = bytes_total - read_ahead.len();
     while bytes_left > 0 {
         let buf = &mut body[(bytes_total - bytes_left)..bytes_total];
         let bytes_read = tcp_stream.read(buf);
@@ -132,7 +165,7 @@ fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: Http
     let _ = tcp_stream.shutdown(Shutdown::Both);
 }
 
-fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders, web_socket_id: u64) {
+fn handle_web_socket(http_server: HttpServer, mut tcp_stream: HttpStream, headers: HttpServerHeaders, mut read_ahead: Vec<u8>, web_socket_id: u64) {
     let upgrade_response = WebSocket::create_upgrade_response(headers.sec_websocket_key.as_ref().unwrap());
 
     write_bytes_to_tcp_stream_no_error(&mut tcp_stream, upgrade_response.as_bytes());
@@ -175,7 +208,17 @@ fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers
     let mut web_socket = WebSocket::new();
     loop {
         let mut data = [0u8; 65535];
-        match tcp_stream.read(&mut data) {
+        let read = if !read_ahead.is_empty() {
+            // frames the client sent right after the upgrade request
+            let n = read_ahead.len().min(data.len());
+            data[0..n].copy_from_slice(&read_ahead[0..n]);
+            read_ahead.drain(0..n);
+            Ok(n)
+        }
+        else {
+            tcp_stream.read(&mut data)
+        };
+        match read {
             Ok(n) => {
                 if n == 0 {
                     let _ = tcp_stream.shutdown(Shutdown::Both);
@@ -227,7 +270,7 @@ fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers
     });
 }
 
-fn handle_get(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders) {
+fn handle_get(http_server: HttpServer, mut tcp_stream: HttpStream, headers: HttpServerHeaders) {
     // send our channel the post
     let (tx_socket, rx_socket) = mpsc::channel::<HttpServerResponse> ();
     if http_server.request.send(HttpServerRequest::Get {
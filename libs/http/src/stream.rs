@@ -0,0 +1,104 @@
+// the connection under a request or websocket, which is plain tcp or, with the `tls` feature, tls.
+
+use std::net::{TcpStream, Shutdown, SocketAddr};
+use std::io::{self, prelude::*};
+use std::time::Duration;
+
+use crate::client::{HttpClientError, HttpUrl};
+#[cfg(feature = "tls")]
+use crate::tls::TlsStream;
+
+pub enum HttpStream {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(TlsStream),
+}
+
+impl HttpStream {
+    /// Connects to the host of `url`, using tls for `https` and `wss` urls.
+    pub fn connect(url: &HttpUrl) -> Result<HttpStream, HttpClientError> {
+        let is_tls = match url.scheme.as_str() {
+            "http" | "ws" => false,
+            "https" | "wss" => true,
+            _ => return Err(HttpClientError::UnsupportedScheme(url.scheme.clone()))
+        };
+        if is_tls && !cfg!(feature = "tls") {
+            return Err(HttpClientError::UnsupportedScheme(format!("{} (makepad-http is built without the tls feature)", url.scheme)))
+        }
+        let tcp_stream = TcpStream::connect((url.host.as_str(), url.port))?;
+        #[cfg(feature = "tls")]
+        if is_tls {
+            return Ok(HttpStream::Tls(TlsStream::connect(tcp_stream, &url.host)?))
+        }
+        Ok(HttpStream::Tcp(tcp_stream))
+    }
+
+    /// A second handle to the same connection, for instance to write from another thread.
+    pub fn try_clone(&self) -> io::Result<HttpStream> {
+        match self {
+            Self::Tcp(tcp_stream) => Ok(Self::Tcp(tcp_stream.try_clone()?)),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => Ok(Self::Tls(tls_stream.clone())),
+        }
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.peer_addr(),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.peer_addr(),
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.set_read_timeout(timeout),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.set_read_timeout(timeout),
+        }
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.set_write_timeout(timeout),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.set_write_timeout(timeout),
+        }
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.shutdown(how),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.shutdown(how),
+        }
+    }
+}
+
+impl Read for HttpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.read(buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.read(buf),
+        }
+    }
+}
+
+impl Write for HttpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.write(buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(tcp_stream) => tcp_stream.flush(),
+            #[cfg(feature = "tls")]
+            Self::Tls(tls_stream) => tls_stream.flush(),
+        }
+    }
+}
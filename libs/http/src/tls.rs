@@ -0,0 +1,211 @@
+// TLS on top of a blocking TcpStream, shared between a reading and a writing thread like a cloned
+// TcpStream would be. Only built with the `tls` feature.
+
+use std::net::{TcpStream, Shutdown, SocketAddr};
+use std::io::{self, prelude::*};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rustls::{ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig, ServerConnection};
+use rustls::crypto::{CryptoProvider, ring};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject};
+
+// a blocked read holds the connection for at most this long, so writes from other threads can go
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static EXTRA_ROOT_CERTIFICATES: Mutex<Vec<CertificateDer<'static >>> = Mutex::new(Vec::new());
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn invalid_data(error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Loads the certificate chain and private key of a server from PEM files.
+pub fn server_config_from_pem_files(cert_path: &Path, key_path: &Path) -> io::Result<Arc<ServerConfig >> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .map_err(invalid_data)?
+        .collect::<Result<Vec<_>, _ >> ()
+        .map_err(invalid_data)?;
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(invalid_data)?;
+    let config = ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_data)?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(invalid_data)?;
+    Ok(Arc::new(config))
+}
+
+/// Trusts the certificates in a PEM file for outgoing connections, on top of the usual roots.
+/// Meant for servers with a self-signed certificate, like a studio on the local network.
+pub fn add_root_certificates_from_pem_file(path: &Path) -> io::Result<()> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .map_err(invalid_data)?
+        .collect::<Result<Vec<_>, _ >> ()
+        .map_err(invalid_data)?;
+    EXTRA_ROOT_CERTIFICATES.lock().unwrap().extend(certs);
+    Ok(())
+}
+
+fn client_config() -> io::Result<Arc<ClientConfig >> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for cert in EXTRA_ROOT_CERTIFICATES.lock().unwrap().iter() {
+        roots.add(cert.clone()).map_err(invalid_data)?;
+    }
+    let config = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_data)?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+struct TlsState {
+    connection: Connection,
+    tcp_stream: TcpStream,
+    read_timeout: Option<Duration>,
+}
+
+/// A TLS connection. Clones share the connection, so one thread can block reading while another
+/// one writes. The handshake happens as part of the first reads and writes.
+#[derive(Clone)]
+pub struct TlsStream {
+    state: Arc<Mutex<TlsState >>,
+    socket: Arc<TcpStream>,
+}
+
+impl TlsStream {
+    pub fn connect(tcp_stream: TcpStream, host: &str) -> io::Result<TlsStream> {
+        let server_name = ServerName::try_from(host.to_string()).map_err(invalid_data)?;
+        let connection = ClientConnection::new(client_config()?, server_name).map_err(invalid_data)?;
+        Self::new(tcp_stream, connection.into())
+    }
+
+    pub fn accept(tcp_stream: TcpStream, config: Arc<ServerConfig>) -> io::Result<TlsStream> {
+        let connection = ServerConnection::new(config).map_err(invalid_data)?;
+        Self::new(tcp_stream, connection.into())
+    }
+
+    fn new(tcp_stream: TcpStream, connection: Connection) -> io::Result<TlsStream> {
+        tcp_stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(TlsStream {
+            socket: Arc::new(tcp_stream.try_clone()?),
+            state: Arc::new(Mutex::new(TlsState {
+                connection,
+                tcp_stream,
+                read_timeout: None
+            })),
+        })
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.peer_addr()
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.state.lock().unwrap().read_timeout = timeout;
+        Ok(())
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.socket.set_write_timeout(timeout)
+    }
+
+    /// Sends a close_notify and then shuts down the socket.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let TlsState {connection, tcp_stream, ..} = &mut *state;
+        connection.send_close_notify();
+        while connection.wants_write() {
+            if connection.write_tls(tcp_stream).is_err() {
+                break
+            }
+        }
+        tcp_stream.shutdown(how)
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                let TlsState {connection, tcp_stream, read_timeout} = &mut *state;
+                match connection.reader().read(buf) {
+                    Ok(n) => return Ok(n),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => (),
+                    // a peer closing the socket without a close_notify is common enough to treat as the end
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                    Err(e) => return Err(e)
+                }
+                // send anything pending first, like handshake messages or data written during the handshake
+                while connection.wants_write() {
+                    connection.write_tls(tcp_stream)?;
+                }
+                match connection.read_tls(tcp_stream) {
+                    Ok(0) => return Ok(0),
+                    Ok(_) => {
+                        if let Err(e) = connection.process_new_packets() {
+                            // tell the peer what went wrong
+                            let _ = connection.write_tls(tcp_stream);
+                            return Err(invalid_data(e))
+                        }
+                        continue
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                        if read_timeout.is_some_and( | timeout | started.elapsed() >= timeout) {
+                            return Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out"))
+                        }
+                    }
+                    Err(e) => return Err(e)
+                }
+            }
+            // give writers a chance while nothing arrives
+            std::thread::yield_now();
+        }
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let TlsState {connection, tcp_stream, read_timeout} = &mut *state;
+        // finish the handshake first, as data written during it is buffered only up to a limit
+        while connection.is_handshaking() {
+            while connection.wants_write() {
+                connection.write_tls(tcp_stream)?;
+            }
+            match connection.read_tls(tcp_stream) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed during the tls handshake")),
+                Ok(_) => connection.process_new_packets().map_err(invalid_data).map( | _ | ())?,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                    if read_timeout.is_some_and( | timeout | started.elapsed() >= timeout) {
+                        return Err(io::Error::new(io::ErrorKind::TimedOut, "tls handshake timed out"))
+                    }
+                }
+                Err(e) => return Err(e)
+            }
+        }
+        let n = connection.writer().write(buf)?;
+        while connection.wants_write() {
+            connection.write_tls(tcp_stream)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let TlsState {connection, tcp_stream, ..} = &mut *state;
+        while connection.wants_write() {
+            connection.write_tls(tcp_stream)?;
+        }
+        tcp_stream.flush()
+    }
+}
@@ -1,9 +1,10 @@
-use std::net::{TcpStream, Shutdown, SocketAddr};
+use std::net::{Shutdown, SocketAddr};
+use crate::stream::HttpStream;
 use std::io::Write;
 use std::io::BufReader;
 use std::io::prelude::*;
 
-pub fn write_bytes_to_tcp_stream_no_error(tcp_stream: &mut TcpStream, bytes: &[u8]) -> bool {
+pub fn write_bytes_to_tcp_stream_no_error(tcp_stream: &mut impl Write, bytes: &[u8]) -> bool {
     let bytes_total = bytes.len();
     let mut bytes_left = bytes_total;
     while bytes_left > 0 {
@@ -21,7 +22,7 @@ pub fn write_bytes_to_tcp_stream_no_error(tcp_stream: &mut TcpStream, bytes: &[u
     false
 }
 
-pub fn http_error_out(mut tcp_stream: TcpStream, code: usize) {
+pub fn http_error_out(mut tcp_stream: HttpStream, code: usize) {
     write_bytes_to_tcp_stream_no_error(&mut tcp_stream, format!("HTTP/1.1 {}\r\n\r\n", code).as_bytes());
     let _ = tcp_stream.shutdown(Shutdown::Both);
 }
@@ -72,7 +73,9 @@ pub struct HttpServerHeaders {
 }
 
 impl HttpServerHeaders {
-    pub fn from_tcp_stream(tcp_stream: &mut TcpStream) -> Option<HttpServerHeaders> {
+    /// Reads the request line and headers. Besides the headers it returns what was read past
+    /// them, which is the start of a body or of websocket frames.
+    pub fn from_stream(tcp_stream: &mut HttpStream) -> Option<(HttpServerHeaders, Vec<u8>)> {
         let addr = tcp_stream.peer_addr().unwrap();
         let mut reader = BufReader::new(tcp_stream);
         
@@ -129,8 +132,9 @@ impl HttpServerHeaders {
         }
         path.as_ref() ?;
         let path = path.unwrap();
+        let read_ahead = reader.buffer().to_vec();
         
-        Some(HttpServerHeaders {
+        Some((HttpServerHeaders {
             addr,
             verb: verb.to_string(),
             path_no_slash: path.0[1..].to_string(),
@@ -140,6 +144,14 @@ impl HttpServerHeaders {
             content_length,
             accept_encoding,
             sec_websocket_key
+        }, read_ahead))
+    }
+    
+    /// The value of the header with the given name, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.lines.iter().skip(1).find_map( | line | {
+            let (key, value) = line.split_once(':') ?;
+            if key.trim().eq_ignore_ascii_case(name) {Some(value.trim())} else {None}
         })
     }
 }
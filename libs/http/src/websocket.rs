@@ -60,7 +60,10 @@ pub const PONG_MESSAGE:[u8;2] = [128 | 10,0];
 
 pub enum MessageFormat {
     Binary,
-    Text
+    Text,
+    Close,
+    Ping,
+    Pong
 }
 
 pub struct MessageHeader {
@@ -77,6 +80,9 @@ impl MessageHeader {
         match format {
             MessageFormat::Binary => data[0] = 128 | 2,
             MessageFormat::Text => data[0] = 128 | 1,
+            MessageFormat::Close => data[0] = 128 | 8,
+            MessageFormat::Ping => data[0] = 128 | 9,
+            MessageFormat::Pong => data[0] = 128 | 10,
         }
 
         if masked {
@@ -123,12 +129,8 @@ impl MessageHeader {
 
     pub fn mask(&mut self)->Option<&[u8]> {
         if self.masked {
-            match self.len {
-                6 => Some(&self.data[2..6]),
-                10 => Some(&self.data[6..10]),
-                14 => Some(&self.data[10..14]),
-                _ => None
-            }
+            // the mask is the last 4 bytes of the header
+            Some(&self.data[self.len - 4..self.len])
         } else {
             None
         }
@@ -160,12 +162,17 @@ impl WebSocket {
         }
     }
     
-    pub fn create_upgrade_response(key: &str) -> String {
+    /// The `Sec-WebSocket-Accept` value that answers a `Sec-WebSocket-Key`.
+    pub fn accept_key(key: &str) -> String {
         let to_hash = format!("{}258EAFA5-E914-47DA-95CA-C5AB0DC85B11", key);
         let mut sha1 = Sha1::new();
         sha1.update(to_hash.as_bytes());
         let out_bytes = sha1.finalise();
-        let base64 = base64_encode(&out_bytes);
+        base64_encode(&out_bytes)
+    }
+
+    pub fn create_upgrade_response(key: &str) -> String {
+        let base64 = Self::accept_key(key);
         let response_ack = format!(
             "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
             base64
@@ -0,0 +1,166 @@
+// a small blocking websocket client, doing the handshake over http and the framing with WebSocket.
+
+use std::net::Shutdown;
+use std::io::{self, prelude::*, BufReader};
+use std::sync::{Arc, Mutex};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::client::{HttpClientError, HttpUrl, header_value, read_response_head};
+use crate::digest::base64_encode;
+use crate::utils::write_bytes_to_tcp_stream_no_error;
+use crate::stream::HttpStream;
+use crate::websocket::{WebSocket, WebSocketMessage, MessageFormat, MessageHeader};
+
+/// The writing half of a websocket connection, which can be cloned to send from any thread.
+/// Frames sent by a client are always masked.
+#[derive(Clone)]
+pub struct WebSocketClientSender {
+    tcp_stream: Arc<Mutex<HttpStream>>,
+}
+
+impl WebSocketClientSender {
+    pub fn send_binary(&self, data: &[u8]) -> io::Result<()> {
+        self.send_frame(MessageFormat::Binary, data)
+    }
+
+    pub fn send_text(&self, text: &str) -> io::Result<()> {
+        self.send_frame(MessageFormat::Text, text.as_bytes())
+    }
+
+    pub fn send_ping(&self, data: &[u8]) -> io::Result<()> {
+        self.send_frame(MessageFormat::Ping, data)
+    }
+
+    pub fn send_pong(&self, data: &[u8]) -> io::Result<()> {
+        self.send_frame(MessageFormat::Pong, data)
+    }
+
+    pub fn send_close(&self) -> io::Result<()> {
+        self.send_frame(MessageFormat::Close, &[])
+    }
+
+    /// Closes the connection without a close frame, which also ends `WebSocketClient::read_messages`.
+    pub fn shutdown(&self) {
+        let _ = self.tcp_stream.lock().unwrap().shutdown(Shutdown::Both);
+    }
+
+    fn send_frame(&self, format: MessageFormat, data: &[u8]) -> io::Result<()> {
+        let header = MessageHeader::from_len(data.len(), format, true);
+        let frame = WebSocket::build_message(header, data);
+        let mut tcp_stream = self.tcp_stream.lock().unwrap();
+        if write_bytes_to_tcp_stream_no_error(&mut *tcp_stream, &frame) {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "cannot write websocket frame"))
+        }
+        Ok(())
+    }
+}
+
+/// The reading half of a websocket connection.
+pub struct WebSocketClient {
+    reader: BufReader<HttpStream>,
+    web_socket: WebSocket,
+    sender: WebSocketClientSender,
+}
+
+impl WebSocketClient {
+    /// Connects to a `ws://` or `http://` url, or with the `tls` feature a `wss://` or `https://`
+    /// one, and does the websocket handshake, sending `headers` along with the upgrade request.
+    pub fn connect(url: &str, headers: &[(String, String)]) -> Result<WebSocketClient, HttpClientError> {
+        let url = HttpUrl::parse(url)?;
+        let mut tcp_stream = HttpStream::connect(&url)?;
+
+        let key = random_key();
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n",
+            url.path,
+            url.host_header(),
+            key
+        );
+        for (key, value) in headers {
+            // we decide these ourselves
+            if ["Host", "Upgrade", "Connection"].iter().any( | name | key.eq_ignore_ascii_case(name))
+                || key.to_ascii_lowercase().starts_with("sec-websocket-") {
+                continue
+            }
+            request.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        request.push_str("\r\n");
+        if write_bytes_to_tcp_stream_no_error(&mut tcp_stream, request.as_bytes()) {
+            return Err(HttpClientError::Io(io::Error::new(io::ErrorKind::WriteZero, "cannot write request")))
+        }
+
+        // the reader keeps anything the server sent right after the handshake
+        let mut reader = BufReader::new(tcp_stream.try_clone()?);
+        let (status_code, response_headers) = read_response_head(&mut reader)?;
+        if status_code != 101 {
+            return Err(HttpClientError::WebSocket(format!("server answered the upgrade with status {}", status_code)))
+        }
+        if header_value(&response_headers, "Sec-WebSocket-Accept") != Some(WebSocket::accept_key(&key).as_str()) {
+            return Err(HttpClientError::WebSocket("Sec-WebSocket-Accept does not match the key".into()))
+        }
+
+        Ok(WebSocketClient {
+            reader,
+            web_socket: WebSocket::new(),
+            sender: WebSocketClientSender {tcp_stream: Arc::new(Mutex::new(tcp_stream))},
+        })
+    }
+
+    pub fn sender(&self) -> WebSocketClientSender {
+        self.sender.clone()
+    }
+
+    /// Reads messages until the connection is closed, calling `message` for each of them. Pings
+    /// are answered and a close frame from the server is echoed before returning `Ok`, as is the
+    /// connection being closed without one.
+    pub fn read_messages(&mut self, message: &mut dyn FnMut(WebSocketMessage)) -> Result<(), HttpClientError> {
+        let mut buffer = [0u8; 65536];
+        loop {
+            let n = self.reader.read(&mut buffer)?;
+            if n == 0 {
+                return Ok(())
+            }
+            let sender = &self.sender;
+            let mut is_closed = false;
+            let mut error = None;
+            self.web_socket.parse(&buffer[0..n], | result | {
+                if is_closed || error.is_some() {
+                    return
+                }
+                match result {
+                    Ok(WebSocketMessage::Ping(data)) => {
+                        if let Err(e) = sender.send_pong(data) {
+                            error = Some(HttpClientError::Io(e));
+                        }
+                        message(WebSocketMessage::Ping(data));
+                    }
+                    Ok(WebSocketMessage::Close) => {
+                        let _ = sender.send_close();
+                        is_closed = true;
+                    }
+                    Ok(other) => message(other),
+                    Err(e) => {
+                        error = Some(HttpClientError::WebSocket(format!("{:?}", e)));
+                    }
+                }
+            });
+            if let Some(error) = error {
+                return Err(error)
+            }
+            if is_closed {
+                sender.shutdown();
+                return Ok(())
+            }
+        }
+    }
+}
+
+// 16 random bytes, base64 encoded
+fn random_key() -> String {
+    let mut bytes = Vec::new();
+    for _ in 0..2 {
+        bytes.extend_from_slice(&RandomState::new().build_hasher().finish().to_le_bytes());
+    }
+    base64_encode(&bytes)
+}
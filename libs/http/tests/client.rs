@@ -0,0 +1,188 @@
+use makepad_http::{
+    client::*,
+    server::*,
+    utils::HttpServerHeaders,
+};
+use std::{
+    io::BufReader,
+    net::{SocketAddr, TcpListener},
+    sync::mpsc,
+};
+
+// starts the in-tree server on a free port, answering GET requests with `respond`
+fn start_server(respond: impl Fn(&HttpServerHeaders) -> HttpServerResponse + Send + 'static) -> String {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let (tx_request, rx_request) = mpsc::channel();
+    start_http_server(HttpServer {
+        listen_address: SocketAddr::from(([127, 0, 0, 1], port)),
+        post_max_size: 1024,
+        request: tx_request
+    }).unwrap();
+    std::thread::spawn(move || {
+        while let Ok(request) = rx_request.recv() {
+            if let HttpServerRequest::Get {headers, response_sender} = request {
+                let _ = response_sender.send(respond(&headers));
+            }
+        }
+    });
+    format!("http://127.0.0.1:{}", port)
+}
+
+fn response(header: &str, body: &[u8]) -> HttpServerResponse {
+    HttpServerResponse {
+        header: format!("HTTP/1.1 {}\r\n\r\n", header),
+        body: body.to_vec()
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+// a gzip member with a single stored deflate block
+fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff, 1];
+    out.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+fn routes(headers: &HttpServerHeaders) -> HttpServerResponse {
+    match headers.path.as_str() {
+        "/plain" => response("200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5", b"hello"),
+        "/chunked" => response("200 OK\r\nTransfer-Encoding: chunked", b"5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nX-Trailer: 1\r\n\r\n"),
+        "/gzip" => {
+            let body = gzip_stored(b"zipped hello");
+            response(&format!("200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}", body.len()), &body)
+        }
+        "/redirect" => response("302 Found\r\nLocation: /dir/relative\r\nContent-Length: 0", b""),
+        "/moved" => response("301 Moved Permanently\r\nLocation: /plain\r\nContent-Length: 9", b"see plain"),
+        "/dir/relative" => response("301 Moved Permanently\r\nLocation: plain\r\nContent-Length: 0", b""),
+        "/loop" => response("302 Found\r\nLocation: /loop\r\nContent-Length: 0", b""),
+        "/dir/plain" => response("200 OK\r\nContent-Length: 8", b"dirplain"),
+        "/eof" => response("200 OK", b"until the end"),
+        _ => response("404 Not Found\r\nContent-Length: 0", b""),
+    }
+}
+
+fn get(url: &str) -> Result<HttpClientResponse, HttpClientError> {
+    HttpClientRequest::new("GET", url).send(&mut | _, _ | {})
+}
+
+#[test]
+fn test_client_against_server() {
+    let base = start_server(routes);
+
+    let mut progress = Vec::new();
+    let response = HttpClientRequest::new("GET", &format!("{}/plain", base))
+        .send(&mut | loaded, total | progress.push((loaded, total)))
+        .unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.header("content-type"), Some("text/plain"));
+    assert_eq!(response.body, b"hello");
+    assert_eq!(progress.last(), Some(&(5, Some(5))));
+
+    assert_eq!(get(&format!("{}/chunked", base)).unwrap().body, b"hello, world");
+    let response = get(&format!("{}/gzip", base)).unwrap();
+    assert_eq!(response.body, b"zipped hello");
+    // the body is no longer encoded, nor as long as it was
+    assert_eq!(response.header("Content-Encoding"), None);
+    assert_eq!(response.header("Content-Length"), None);
+    assert_eq!(get(&format!("{}/eof", base)).unwrap().body, b"until the end");
+    assert_eq!(get(&format!("{}/missing", base)).unwrap().status_code, 404);
+
+    let response = get(&format!("{}/redirect", base)).unwrap();
+    assert_eq!(response.body, b"dirplain");
+    assert_eq!(response.url, format!("{}/dir/plain", base));
+
+    // only the body that is returned reports progress, not that of the redirect
+    let mut progress = Vec::new();
+    let response = HttpClientRequest::new("GET", &format!("{}/moved", base))
+        .send(&mut | loaded, total | progress.push((loaded, total)))
+        .unwrap();
+    assert_eq!(response.body, b"hello");
+    assert_eq!(progress, [(5, Some(5))]);
+
+    assert!(matches!(get(&format!("{}/loop", base)), Err(HttpClientError::TooManyRedirects)));
+}
+
+// answers with the credential headers the request came with
+fn credentials(headers: &HttpServerHeaders) -> HttpServerResponse {
+    let body: String = headers.lines.iter().filter( | line | {
+        CREDENTIAL_HEADERS.iter().any( | name | line.to_ascii_lowercase().starts_with(&format!("{}:", name.to_ascii_lowercase())))
+    }).map( | line | line.trim_end()).collect::<Vec<_>>().join("\n");
+    response(&format!("200 OK\r\nContent-Length: {}", body.len()), body.as_bytes())
+}
+
+#[test]
+fn test_redirect_to_other_origin_drops_credentials() {
+    let other_base = start_server(credentials);
+    let base = start_server(move | headers | match headers.path.as_str() {
+        "/same" => response("302 Found\r\nLocation: /credentials\r\nContent-Length: 0", b""),
+        "/other" => response(&format!("307 Temporary Redirect\r\nLocation: {}/same\r\nContent-Length: 0", other_base), b""),
+        _ => credentials(headers)
+    });
+    let get_with_credentials = | url: String | {
+        let mut request = HttpClientRequest::new("GET", &url);
+        request.headers = vec![
+            ("Authorization".to_string(), "Bearer secret".to_string()),
+            ("cookie".to_string(), "session=1".to_string()),
+            ("Proxy-Authorization".to_string(), "Basic cHJveHk=".to_string()),
+        ];
+        String::from_utf8(request.send(&mut | _, _ | {}).unwrap().body).unwrap()
+    };
+    assert_eq!(
+        get_with_credentials(format!("{}/same", base)),
+        "Authorization: Bearer secret\ncookie: session=1\nProxy-Authorization: Basic cHJveHk="
+    );
+    // the other server redirects within its own origin, which doesn't bring the credentials back
+    assert_eq!(get_with_credentials(format!("{}/other", base)), "");
+}
+
+#[test]
+fn test_parse_url() {
+    assert_eq!(HttpUrl::parse("http://example.com").unwrap(), HttpUrl {
+        scheme: "http".into(),
+        host: "example.com".into(),
+        port: 80,
+        path: "/".into()
+    });
+    let url = HttpUrl::parse("HTTP://user@[::1]:8080/a/b?x=1#top").unwrap();
+    assert_eq!((url.host.as_str(), url.port, url.path.as_str()), ("::1", 8080, "/a/b?x=1"));
+    assert_eq!(url.host_header(), "[::1]:8080");
+    assert_eq!(HttpUrl::parse("http://host?q").unwrap().path, "/?q");
+    assert!(matches!(HttpUrl::parse("ftp://host"), Err(HttpClientError::UnsupportedScheme(_))));
+    assert!(matches!(HttpUrl::parse("http://host:port/"), Err(HttpClientError::InvalidUrl(_))));
+
+    let url = HttpUrl::parse("http://host:81/a/b?x").unwrap();
+    assert_eq!(url.join("c"), "http://host:81/a/c");
+    assert_eq!(url.join("/c"), "http://host:81/c");
+    assert_eq!(url.join("//other/c"), "http://other/c");
+    assert_eq!(url.join("https://other/"), "https://other/");
+}
+
+#[test]
+fn test_read_response() {
+    let raw = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 204 No Content\r\nX-A: b\r\n\r\n";
+    let response = read_response(&mut BufReader::new(&raw[..]), "GET", &mut | _, _ | {}).unwrap();
+    assert_eq!(response.status_code, 204);
+    assert_eq!(response.headers, [("X-A".to_string(), "b".to_string())]);
+
+    // a HEAD response has no body even if it has a length
+    let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n";
+    assert!(read_response(&mut BufReader::new(&raw[..]), "HEAD", &mut | _, _ | {}).unwrap().body.is_empty());
+
+    let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+    assert!(matches!(read_response(&mut BufReader::new(&raw[..]), "GET", &mut | _, _ | {}), Err(HttpClientError::InvalidResponse(_))));
+    assert!(read_response(&mut BufReader::new(&b"garbage\r\n\r\n"[..]), "GET", &mut | _, _ | {}).is_err());
+}
@@ -0,0 +1,173 @@
+use makepad_http::{
+    client::*,
+    router::*,
+    server::*,
+};
+use std::{
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::mpsc,
+};
+
+// a directory with some files to serve, unique to the test
+fn make_root(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("makepad_http_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::write(root.join("index.html"), "<html></html>").unwrap();
+    std::fs::write(root.join("app.js"), "0123456789").unwrap();
+    std::fs::write(root.join("app.js.br"), "brotli bytes").unwrap();
+    std::fs::write(root.join("app.js.gz"), gzip_stored(b"gzipped js")).unwrap();
+    std::fs::write(root.join("sub/data.bin"), [1, 2, 3]).unwrap();
+    std::fs::write(root.join("secret.rs"), "fn main() {}").unwrap();
+    root
+}
+
+// starts the in-tree server on a free port with `router` answering its requests
+fn start_server(router: HttpRouter) -> String {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let (tx_request, rx_request) = mpsc::channel();
+    start_http_server(HttpServer {
+        listen_address: SocketAddr::from(([127, 0, 0, 1], port)),
+        post_max_size: 1024,
+        request: tx_request
+    }).unwrap();
+    std::thread::spawn(move || {
+        while let Ok(request) = rx_request.recv() {
+            router.handle(request);
+        }
+    });
+    format!("http://127.0.0.1:{}", port)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+// a gzip member with a single stored deflate block
+fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff, 1];
+    out.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+fn get(url: &str, headers: &[(&str, &str)]) -> HttpClientResponse {
+    let mut request = HttpClientRequest::new("GET", url);
+    request.headers = headers.iter().map( | (key, value) | (key.to_string(), value.to_string())).collect();
+    request.send(&mut | _, _ | {}).unwrap()
+}
+
+#[test]
+fn test_router() {
+    let mut router = HttpRouter::new();
+    router
+        .get("/users/:id", | request | {
+            let body = format!("user {}", request.params.get("id").unwrap());
+            HttpServerResponse::new(200, &[("Content-Type", "text/plain")], body.into_bytes())
+        })
+        .get("/users/:id/files/*path", | request | {
+            let body = format!("{} {}", request.params.get("id").unwrap(), request.params.get("path").unwrap());
+            HttpServerResponse::new(200, &[], body.into_bytes())
+        })
+        .post("/echo", | request | HttpServerResponse::new(200, &[], request.body.to_vec()));
+    let base = start_server(router);
+
+    let response = get(&format!("{}/users/42?tab=1", base), &[("Accept-Encoding", "identity")]);
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.body, b"user 42");
+    assert_eq!(get(&format!("{}/users/a%20b/files/x/y.txt", base), &[]).body, b"a b x/y.txt");
+    assert_eq!(get(&format!("{}/users", base), &[]).status_code, 404);
+    assert_eq!(get(&format!("{}/users/1/other", base), &[]).status_code, 404);
+
+    let mut request = HttpClientRequest::new("POST", &format!("{}/echo", base));
+    request.body = Some(b"posted".to_vec());
+    assert_eq!(request.send(&mut | _, _ | {}).unwrap().body, b"posted");
+}
+
+#[test]
+fn test_static_files() {
+    let root = make_root("static");
+    let mut router = HttpRouter::new();
+    router.static_files("/files", StaticFiles::new(&root)
+        .with_header("Cross-Origin-Opener-Policy", "same-origin")
+        .with_extensions(&["html", "js", "bin"]));
+    let base = start_server(router);
+    let identity = [("Accept-Encoding", "identity")];
+
+    let response = get(&format!("{}/files/", base), &identity);
+    assert_eq!(response.body, b"<html></html>");
+    assert_eq!(response.header("Content-Type"), Some("text/html; charset=utf-8"));
+    assert_eq!(response.header("Cross-Origin-Opener-Policy"), Some("same-origin"));
+
+    let response = get(&format!("{}/files/sub/data.bin", base), &identity);
+    assert_eq!((response.status_code, response.body.as_slice()), (200, &[1u8, 2, 3][..]));
+    assert_eq!(response.header("Content-Type"), Some("application/octet-stream"));
+
+    // outside the root, of a type we don't serve, or not there
+    assert_eq!(get(&format!("{}/files/sub/../app.js", base), &identity).status_code, 404);
+    assert_eq!(get(&format!("{}/files/%2e%2e/secret.js", base), &identity).status_code, 404);
+    assert_eq!(get(&format!("{}/files/secret.rs", base), &identity).status_code, 404);
+    assert_eq!(get(&format!("{}/files/missing.js", base), &identity).status_code, 404);
+
+    // etags
+    let response = get(&format!("{}/files/app.js", base), &identity);
+    assert_eq!(response.body, b"0123456789");
+    assert_eq!(response.header("Content-Type"), Some("text/javascript; charset=utf-8"));
+    let etag = response.header("ETag").unwrap().to_string();
+    let response = get(&format!("{}/files/app.js", base), &[("Accept-Encoding", "identity"), ("If-None-Match", &format!("\"other\", {}", etag))]);
+    assert_eq!(response.status_code, 304);
+    assert!(response.body.is_empty());
+    assert_eq!(get(&format!("{}/files/app.js", base), &[("Accept-Encoding", "identity"), ("If-None-Match", "\"other\"")]).status_code, 200);
+
+    // precompressed variants
+    let response = get(&format!("{}/files/app.js", base), &[("Accept-Encoding", "gzip, br")]);
+    assert_eq!(response.header("Content-Encoding"), Some("br"));
+    assert_eq!(response.body, b"brotli bytes");
+    assert_ne!(response.header("ETag"), Some(etag.as_str()));
+    // the client undoes the gzip encoding and drops its header, so the body tells the variant
+    let response = get(&format!("{}/files/app.js", base), &[("Accept-Encoding", "gzip, br;q=0")]);
+    assert_eq!(response.header("Content-Encoding"), None);
+    assert_eq!(response.body, b"gzipped js");
+    assert_eq!(response.header("Vary"), Some("Accept-Encoding"));
+
+    // ranges
+    let response = get(&format!("{}/files/app.js", base), &[("Accept-Encoding", "gzip, br"), ("Range", "bytes=2-4")]);
+    assert_eq!(response.status_code, 206);
+    assert_eq!(response.body, b"234");
+    assert_eq!(response.header("Content-Range"), Some("bytes 2-4/10"));
+    assert_eq!(response.header("Content-Encoding"), None);
+    assert_eq!(get(&format!("{}/files/app.js", base), &[("Range", "bytes=-3")]).body, b"789");
+    assert_eq!(get(&format!("{}/files/app.js", base), &[("Range", "bytes=8-")]).body, b"89");
+    let response = get(&format!("{}/files/app.js", base), &[("Range", "bytes=10-")]);
+    assert_eq!(response.status_code, 416);
+    assert_eq!(response.header("Content-Range"), Some("bytes */10"));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_parse_range() {
+    assert_eq!(parse_range("bytes=0-0", 5), Some(ByteRange::Satisfiable(0, 0)));
+    assert_eq!(parse_range("bytes=3-100", 5), Some(ByteRange::Satisfiable(3, 4)));
+    assert_eq!(parse_range("bytes=-100", 5), Some(ByteRange::Satisfiable(0, 4)));
+    assert_eq!(parse_range("bytes=5-", 5), Some(ByteRange::Unsatisfiable));
+    assert_eq!(parse_range("bytes=-0", 5), Some(ByteRange::Unsatisfiable));
+    assert_eq!(parse_range("bytes=0-1,3-4", 5), None);
+    assert_eq!(parse_range("bytes=4-2", 5), None);
+    assert_eq!(parse_range("items=0-1", 5), None);
+
+    assert_eq!(mime_type("a/b.WASM"), "application/wasm");
+    assert_eq!(mime_type("noextension"), "application/octet-stream");
+    assert_eq!(percent_decode("a%20b%2Fc%zz%"), "a b/c%zz%");
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "tls")]
+
+use makepad_http::{
+    client::*,
+    server::*,
+    tls,
+    websocket::WebSocketMessage,
+    websocket_client::WebSocketClient,
+};
+use std::{
+    net::{SocketAddr, TcpListener},
+    path::Path,
+    sync::mpsc,
+    time::Duration,
+};
+
+// starts the in-tree server with tls on a free port, answering GET requests with the path and
+// echoing websocket messages
+fn start_server() -> u16 {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let (tx_request, rx_request) = mpsc::channel();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tls");
+    start_https_server(HttpServer {
+        listen_address: SocketAddr::from(([127, 0, 0, 1], port)),
+        post_max_size: 1024,
+        request: tx_request
+    }, &dir.join("cert.pem"), &dir.join("key.pem")).unwrap();
+    std::thread::spawn(move || {
+        while let Ok(request) = rx_request.recv() {
+            match request {
+                HttpServerRequest::Get {headers, response_sender} if headers.path == "/downgrade" => {
+                    let _ = response_sender.send(HttpServerResponse {
+                        header: format!("HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:{}/page\r\nContent-Length: 0\r\n\r\n", port),
+                        body: Vec::new()
+                    });
+                }
+                HttpServerRequest::Get {headers, response_sender} => {
+                    let body = format!("secret {}", headers.path).into_bytes();
+                    let _ = response_sender.send(HttpServerResponse {
+                        header: format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()),
+                        body
+                    });
+                }
+                HttpServerRequest::BinaryMessage {response_sender, data, ..} => {
+                    let _ = response_sender.send(data);
+                }
+                _ => ()
+            }
+        }
+    });
+    port
+}
+
+#[test]
+fn test_tls_client_against_server() {
+    let port = start_server();
+    let url = format!("https://127.0.0.1:{}/page", port);
+
+    // the certificate is self-signed, so it isn't trusted until we say so
+    assert!(matches!(HttpClientRequest::new("GET", &url).send(&mut | _, _ | {}), Err(HttpClientError::Io(_))));
+    tls::add_root_certificates_from_pem_file(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tls/cert.pem")).unwrap();
+
+    let response = HttpClientRequest::new("GET", &url).send(&mut | _, _ | {}).unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.body, b"secret /page");
+
+    // a redirect must not leave tls
+    let downgrade = HttpClientRequest::new("GET", &format!("https://127.0.0.1:{}/downgrade", port)).send(&mut | _, _ | {});
+    assert!(matches!(downgrade, Err(HttpClientError::InsecureRedirect(_))));
+
+    let mut client = WebSocketClient::connect(&format!("wss://127.0.0.1:{}/socket", port), &[]).unwrap();
+    let sender = client.sender();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = client.read_messages(&mut | message | {
+            if let WebSocketMessage::Binary(data) = message {
+                let _ = tx.send(data.to_vec());
+            }
+        });
+    });
+    // written while the other thread is blocked reading
+    sender.send_binary(b"over tls").unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), b"over tls");
+    sender.send_binary(&[1; 100000]).unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), vec![1; 100000]);
+}
@@ -0,0 +1,174 @@
+use makepad_http::{
+    client::HttpClientError,
+    server::*,
+    websocket::*,
+    websocket_client::*,
+};
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener},
+    sync::mpsc,
+    time::Duration,
+};
+
+// starts the in-tree server on a free port, echoing binary messages and closing the connection
+// when it gets an empty one
+fn start_echo_server() -> u16 {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let (tx_request, rx_request) = mpsc::channel();
+    start_http_server(HttpServer {
+        listen_address: SocketAddr::from(([127, 0, 0, 1], port)),
+        post_max_size: 1024,
+        request: tx_request
+    }).unwrap();
+    std::thread::spawn(move || {
+        while let Ok(request) = rx_request.recv() {
+            if let HttpServerRequest::BinaryMessage {response_sender, data, ..} = request {
+                let _ = response_sender.send(if data == b"bye" {Vec::new()} else {data});
+            }
+        }
+    });
+    port
+}
+
+#[derive(Debug, PartialEq)]
+enum Received {
+    Binary(Vec<u8>),
+    Text(String),
+}
+
+// reads messages on a thread of its own, forwarding them until the connection ends
+fn read_thread(mut client: WebSocketClient) -> mpsc::Receiver<Result<Received, String >> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let result = client.read_messages(&mut | message | {
+            let received = match message {
+                WebSocketMessage::Binary(data) => Received::Binary(data.to_vec()),
+                WebSocketMessage::Text(text) => Received::Text(text.to_string()),
+                _ => return
+            };
+            let _ = tx.send(Ok(received));
+        });
+        let _ = tx.send(Err(result.map_or_else( | error | error.to_string(), | _ | "closed".to_string())));
+    });
+    rx
+}
+
+#[test]
+fn test_client_against_server() {
+    let port = start_echo_server();
+    let client = WebSocketClient::connect(&format!("ws://127.0.0.1:{}/socket", port), &[]).unwrap();
+    let sender = client.sender();
+    let rx = read_thread(client);
+    let timeout = Duration::from_secs(5);
+
+    sender.send_binary(b"hello").unwrap();
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), Ok(Received::Binary(b"hello".to_vec())));
+
+    // long enough for the 16 bit length
+    let long = vec![7u8; 70000];
+    sender.send_binary(&long).unwrap();
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), Ok(Received::Binary(long)));
+
+    // the server closes the connection on our request
+    sender.send_binary(b"bye").unwrap();
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), Err("closed".to_string()));
+
+    if !cfg!(feature = "tls") {
+        assert!(matches!(
+            WebSocketClient::connect(&format!("wss://127.0.0.1:{}/", port), &[]),
+            Err(HttpClientError::UnsupportedScheme(_))
+        ));
+    }
+}
+
+// accepts a single connection, answering the upgrade with `response` and then writing `frames`.
+// Returns the port and a receiver for everything the client sent after the upgrade request.
+fn start_raw_server(response: fn(&str) -> String, frames: Vec<u8>) -> (u16, mpsc::Receiver<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (mut tcp_stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut byte = [0u8];
+        while !request.ends_with(b"\r\n\r\n") && tcp_stream.read(&mut byte).unwrap() == 1 {
+            request.push(byte[0]);
+        }
+        let request = String::from_utf8(request).unwrap();
+        let key = request.lines().find_map( | line | line.strip_prefix("Sec-WebSocket-Key: ")).unwrap();
+        let mut bytes = response(key).into_bytes();
+        // frames sent along with the handshake shouldn't get lost
+        bytes.extend_from_slice(&frames);
+        tcp_stream.write_all(&bytes).unwrap();
+
+        let mut rest = Vec::new();
+        let _ = tcp_stream.read_to_end(&mut rest);
+        let _ = tx.send(rest);
+    });
+    (port, rx)
+}
+
+#[test]
+fn test_client_handshake_and_frames() {
+    let mut frames = Vec::new();
+    frames.extend(WebSocket::build_message(MessageHeader::from_len(2, MessageFormat::Text, false), b"hi"));
+    frames.extend(WebSocket::build_message(MessageHeader::from_len(1, MessageFormat::Binary, false), &[3]));
+    frames.extend(WebSocket::build_message(MessageHeader::from_len(1, MessageFormat::Ping, false), &[9]));
+    frames.extend(WebSocket::build_message(MessageHeader::from_len(0, MessageFormat::Close, false), &[]));
+    let (port, sent) = start_raw_server(WebSocket::create_upgrade_response, frames);
+    let rx = read_thread(WebSocketClient::connect(&format!("http://127.0.0.1:{}/", port), &[]).unwrap());
+    let received: Vec<_> = rx.iter().collect();
+    assert_eq!(received, [
+        Ok(Received::Text("hi".to_string())),
+        Ok(Received::Binary(vec![3])),
+        Err("closed".to_string())
+    ]);
+    // the ping is answered with a pong and the close with a close, both masked
+    let mut web_socket = WebSocket::new();
+    let sent = sent.recv().unwrap();
+    assert_eq!((sent[0], sent[1]), (128 | 10, 128 | 1));
+    let mut answers = Vec::new();
+    web_socket.parse(&sent, | result | match result {
+        Ok(WebSocketMessage::Pong(data)) => answers.push(format!("pong {:?}", data)),
+        Ok(WebSocketMessage::Close) => answers.push("close".to_string()),
+        _ => answers.push("other".to_string()),
+    });
+    assert_eq!(answers, ["pong [9]", "close"]);
+
+    fn wrong_accept(_key: &str) -> String {
+        WebSocket::create_upgrade_response("some other key")
+    }
+    let (port, _) = start_raw_server(wrong_accept, Vec::new());
+    assert!(matches!(
+        WebSocketClient::connect(&format!("ws://127.0.0.1:{}/", port), &[]),
+        Err(HttpClientError::WebSocket(_))
+    ));
+
+    fn not_found(_key: &str) -> String {
+        "HTTP/1.1 404 Not Found\r\n\r\n".to_string()
+    }
+    let (port, _) = start_raw_server(not_found, Vec::new());
+    assert!(matches!(
+        WebSocketClient::connect(&format!("ws://127.0.0.1:{}/", port), &[]),
+        Err(HttpClientError::WebSocket(_))
+    ));
+}
+
+#[test]
+fn test_masked_frames_round_trip() {
+    let mut web_socket = WebSocket::new();
+    let data: Vec<u8> = (0..300).map( | i | i as u8).collect();
+    let frame = WebSocket::build_message(MessageHeader::from_len(data.len(), MessageFormat::Binary, true), &data);
+    assert_eq!(frame[1] & 128, 128);
+    let mut received = Vec::new();
+    // split the frame to check that the parser picks up where it left off
+    for part in frame.chunks(7) {
+        web_socket.parse(part, | result | {
+            if let Ok(WebSocketMessage::Binary(data)) = result {
+                received.push(data.to_vec());
+            }
+        });
+    }
+    assert_eq!(received, [data]);
+}
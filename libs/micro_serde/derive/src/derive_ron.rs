@@ -195,9 +195,7 @@ pub fn derive_de_ron_impl(input: TokenStream) -> TokenStream {
                 for field in fields {
                     tb.ident(&field.name).add(":");
                     if field.ty.into_iter().next().unwrap().to_string() == "Option" {
-                        tb.add("if let Some ( t ) =")
-                            .ident(&format!("_{}", field.name));
-                        tb.add("{ t } else { None } ,");
+                        tb.ident(&format!("_{}", field.name)).add(". flatten ( ) ,");
                     } else {
                         tb.add("if let Some ( t ) =")
                             .ident(&format!("_{}", field.name));
@@ -266,9 +264,7 @@ pub fn derive_de_ron_impl(input: TokenStream) -> TokenStream {
                         for field in fields {
                             tb.ident(&field.name).add(":");
                             if field.ty.into_iter().next().unwrap().to_string() == "Option" {
-                                tb.add("if let Some ( t ) =")
-                                    .ident(&format!("_{}", field.name));
-                                tb.add("{ t } else { None } ,");
+                                tb.ident(&format!("_{}", field.name)).add(". flatten ( ) ,");
                             } else {
                                 tb.add("if let Some ( t ) =")
                                     .ident(&format!("_{}", field.name));
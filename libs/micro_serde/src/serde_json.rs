@@ -323,6 +323,21 @@ impl DeJsonState {
         Err(self.err_token("string"))
     }
     
+    fn hex_escape(&mut self, i: &mut Chars) -> Result<u32, DeJsonErr> {
+        let mut code = 0;
+        for _ in 0..4 {
+            self.next(i);
+            if let Some(digit) = self.cur.to_digit(16) {
+                code = code * 16 + digit;
+            }
+            else {
+                return Err(self.err_parse("unicode escape"));
+            }
+        }
+        self.next(i);
+        Ok(code)
+    }
+    
     pub fn next_tok(&mut self, i: &mut Chars) -> Result<(), DeJsonErr> {
         while self.cur == '\n' || self.cur == '\r' || self.cur == '\t' || self.cur == ' ' {
             self.next(i);
@@ -376,13 +391,30 @@ impl DeJsonState {
                     self.numbuf.push(self.cur);
                     self.next(i);
                 }
+                let mut is_float = false;
                 if self.cur == '.' {
+                    is_float = true;
+                    self.numbuf.push(self.cur);
+                    self.next(i);
+                    while self.cur >= '0' && self.cur <= '9' {
+                        self.numbuf.push(self.cur);
+                        self.next(i);
+                    }
+                }
+                if self.cur == 'e' || self.cur == 'E' {
+                    is_float = true;
                     self.numbuf.push(self.cur);
                     self.next(i);
+                    if self.cur == '-' || self.cur == '+' {
+                        self.numbuf.push(self.cur);
+                        self.next(i);
+                    }
                     while self.cur >= '0' && self.cur <= '9' {
                         self.numbuf.push(self.cur);
                         self.next(i);
                     }
+                }
+                if is_float {
                     if let Ok(num) = self.numbuf.parse() {
                         self.tok = DeJsonTok::F64(num);
                         Ok(())
@@ -444,6 +476,22 @@ impl DeJsonState {
                             'r'=>self.strbuf.push('\r'),
                             't'=>self.strbuf.push('\t'),
                             '0'=>self.strbuf.push('\0'),
+                            'b'=>self.strbuf.push('\u{8}'),
+                            'f'=>self.strbuf.push('\u{c}'),
+                            'u'=>{
+                                let mut code = self.hex_escape(i) ?;
+                                // utf16 surrogate pairs are encoded as two consecutive escapes
+                                if (0xD800..0xDC00).contains(&code) && self.cur == '\\' {
+                                    self.next(i);
+                                    if self.cur != 'u' {
+                                        return Err(self.err_parse("string"));
+                                    }
+                                    let low = self.hex_escape(i) ?;
+                                    code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3ff);
+                                }
+                                self.strbuf.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                                continue;
+                            }
                             '\0'=>{
                                 return Err(self.err_parse("string"));
                             },
@@ -806,3 +854,191 @@ impl<T> DeJson for Box<T> where T: DeJson {
         Ok(Box::new(DeJson::de_json(s, i) ?))
     }
 }
+
+/// A dynamically typed json value, for documents whose shape is not known up front.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl Default for JsonValue {
+    fn default() -> Self {
+        Self::Null
+    }
+}
+
+impl JsonValue {
+    pub fn object() -> Self {
+        Self::Object(Vec::new())
+    }
+    
+    pub fn with(mut self, key: &str, value: impl Into<JsonValue>) -> Self {
+        self.insert(key, value);
+        self
+    }
+    
+    pub fn insert(&mut self, key: &str, value: impl Into<JsonValue>) {
+        if let Self::Object(fields) = self {
+            let value = value.into();
+            if let Some(field) = fields.iter_mut().find( | (k, _) | k == key) {
+                field.1 = value;
+            }
+            else {
+                fields.push((key.to_string(), value));
+            }
+        }
+    }
+    
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        if let Self::Object(fields) = self {
+            return fields.iter().find( | (k, _) | k == key).map( | (_, v) | v)
+        }
+        None
+    }
+    
+    pub fn index(&self, index: usize) -> Option<&JsonValue> {
+        if let Self::Array(items) = self {
+            return items.get(index)
+        }
+        None
+    }
+    
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+    
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Self::Bool(v) = self {Some(*v)} else {None}
+    }
+    
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::U64(v) => Some(*v),
+            Self::I64(v) if *v >= 0 => Some(*v as u64),
+            _ => None
+        }
+    }
+    
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::U64(v) => Some(*v as i64),
+            Self::I64(v) => Some(*v),
+            _ => None
+        }
+    }
+    
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::U64(v) => Some(*v as f64),
+            Self::I64(v) => Some(*v as f64),
+            Self::F64(v) => Some(*v),
+            _ => None
+        }
+    }
+    
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(v) = self {Some(v)} else {None}
+    }
+    
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        if let Self::Array(v) = self {Some(v)} else {None}
+    }
+    
+    pub fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        if let Self::Object(v) = self {Some(v)} else {None}
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(v: bool) -> Self {Self::Bool(v)}
+}
+
+impl From<u64> for JsonValue {
+    fn from(v: u64) -> Self {Self::U64(v)}
+}
+
+impl From<usize> for JsonValue {
+    fn from(v: usize) -> Self {Self::U64(v as u64)}
+}
+
+impl From<i64> for JsonValue {
+    fn from(v: i64) -> Self {Self::I64(v)}
+}
+
+impl From<f64> for JsonValue {
+    fn from(v: f64) -> Self {Self::F64(v)}
+}
+
+impl From<&str> for JsonValue {
+    fn from(v: &str) -> Self {Self::String(v.to_string())}
+}
+
+impl From<String> for JsonValue {
+    fn from(v: String) -> Self {Self::String(v)}
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(v: Vec<JsonValue>) -> Self {Self::Array(v)}
+}
+
+impl SerJson for JsonValue {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Null => s.out.push_str("null"),
+            Self::Bool(v) => v.ser_json(d, s),
+            Self::U64(v) => v.ser_json(d, s),
+            Self::I64(v) => v.ser_json(d, s),
+            Self::F64(v) => v.ser_json(d, s),
+            Self::String(v) => v.ser_json(d, s),
+            Self::Array(v) => v.ser_json(d, s),
+            Self::Object(fields) => {
+                s.st_pre();
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index != 0 {
+                        s.conl();
+                    }
+                    key.ser_json(d + 1, s);
+                    s.out.push(':');
+                    value.ser_json(d + 1, s);
+                }
+                s.st_post(d);
+            }
+        }
+    }
+}
+
+impl DeJson for JsonValue {
+    fn de_json(s: &mut DeJsonState, i: &mut Chars) -> Result<Self, DeJsonErr> {
+        let value = match s.tok {
+            DeJsonTok::Null => Self::Null,
+            DeJsonTok::Bool(v) => Self::Bool(v),
+            DeJsonTok::U64(v) => Self::U64(v),
+            DeJsonTok::I64(v) => Self::I64(v),
+            DeJsonTok::F64(v) => Self::F64(v),
+            DeJsonTok::Str => Self::String(s.as_string() ?),
+            DeJsonTok::BlockOpen => return Ok(Self::Array(DeJson::de_json(s, i) ?)),
+            DeJsonTok::CurlyOpen => {
+                let mut fields = Vec::new();
+                s.curly_open(i) ?;
+                while s.tok != DeJsonTok::CurlyClose {
+                    let key = s.as_string() ?;
+                    s.next_colon(i) ?;
+                    fields.push((key, DeJson::de_json(s, i) ?));
+                    s.eat_comma_curly(i) ?;
+                }
+                s.curly_close(i) ?;
+                return Ok(Self::Object(fields))
+            }
+            _ => return Err(s.err_token("json value"))
+        };
+        s.next_tok(i) ?;
+        Ok(value)
+    }
+}
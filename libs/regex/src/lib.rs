@@ -0,0 +1,2 @@
+mod regex;
+pub use crate::regex::*;
@@ -0,0 +1,668 @@
+use std::fmt;
+
+/// A small backtracking regular expression engine.
+///
+/// Supports literals, `.`, character classes (`[a-z]`, `[^...]`, `\d`, `\w`, `\s` and their
+/// negations), anchors (`^`, `$`, `\b`, `\B`), capturing and non-capturing groups, alternation,
+/// and greedy and lazy quantifiers (`*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}`). Matching works on a
+/// single string at a time, so `^` and `$` match at the start and end of that string.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    node: Node,
+    group_count: usize,
+    case_insensitive: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RegexOptions {
+    pub case_insensitive: bool,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RegexError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Match {
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn as_str(self, haystack: &str) -> &str {
+        &haystack[self.start..self.end]
+    }
+}
+
+/// The positions of all capture groups of a match. Group 0 is the match as a whole.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Captures {
+    slots: Vec<Option<usize>>,
+}
+
+impl Captures {
+    pub fn get(&self, index: usize) -> Option<Match> {
+        match (self.slots.get(index * 2)?, self.slots.get(index * 2 + 1)?) {
+            (Some(start), Some(end)) => Some(Match {
+                start: *start,
+                end: *end,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Appends `replacement` to `dst`, with `$n` and `${n}` replaced by the text of group `n`,
+    /// and `$$` replaced by a single `$`.
+    pub fn expand(&self, haystack: &str, replacement: &str, dst: &mut String) {
+        let mut chars = replacement.char_indices().peekable();
+        while let Some((_, char)) = chars.next() {
+            if char != '$' {
+                dst.push(char);
+                continue;
+            }
+            match chars.peek().copied() {
+                Some((_, '$')) => {
+                    chars.next();
+                    dst.push('$');
+                }
+                Some((start, '{')) => {
+                    let end = replacement[start..].find('}').map(|index| start + index);
+                    match end.and_then(|end| replacement[start + 1..end].parse::<usize>().ok()) {
+                        Some(index) => {
+                            while chars.next().is_some_and(|(_, char)| char != '}') {}
+                            if let Some(group) = self.get(index) {
+                                dst.push_str(group.as_str(haystack));
+                            }
+                        }
+                        None => dst.push('$'),
+                    }
+                }
+                Some((_, char)) if char.is_ascii_digit() => {
+                    let mut index = 0;
+                    while let Some(digit) = chars.peek().and_then(|(_, char)| char.to_digit(10)) {
+                        index = index * 10 + digit as usize;
+                        chars.next();
+                    }
+                    if let Some(group) = self.get(index) {
+                        dst.push_str(group.as_str(haystack));
+                    }
+                }
+                _ => dst.push('$'),
+            }
+        }
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        Self::with_options(pattern, RegexOptions::default())
+    }
+
+    pub fn with_options(pattern: &str, options: RegexOptions) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.char_indices().collect(),
+            index: 0,
+            group_count: 1,
+            pattern_len: pattern.len(),
+        };
+        let node = parser.parse_alternation()?;
+        if parser.index < parser.chars.len() {
+            return Err(parser.error("unmatched `)`"));
+        }
+        Ok(Self {
+            node,
+            group_count: parser.group_count,
+            case_insensitive: options.case_insensitive,
+        })
+    }
+
+    /// Creates a regex that matches `string` literally.
+    pub fn literal(string: &str, options: RegexOptions) -> Self {
+        Self::with_options(&escape(string), options).unwrap()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.find_at(haystack, 0).is_some()
+    }
+
+    pub fn find_at(&self, haystack: &str, start: usize) -> Option<Match> {
+        self.captures_at(haystack, start)
+            .map(|captures| captures.get(0).unwrap())
+    }
+
+    pub fn captures_at(&self, haystack: &str, start: usize) -> Option<Captures> {
+        let mut slots = vec![None; self.group_count * 2];
+        let matcher = Matcher {
+            haystack,
+            case_insensitive: self.case_insensitive,
+        };
+        let mut start = start;
+        loop {
+            let mut end = None;
+            if matcher.match_node(&self.node, start, &mut slots, &mut |position, _| {
+                end = Some(position);
+                true
+            }) {
+                slots[0] = Some(start);
+                slots[1] = end;
+                return Some(Captures { slots });
+            }
+            start += haystack[start..].chars().next()?.len_utf8();
+        }
+    }
+
+    /// Returns all non-overlapping matches in `haystack`, from left to right.
+    pub fn find_iter<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let mut start = Some(0);
+        let mut last_end = None;
+        std::iter::from_fn(move || loop {
+            let m = self.find_at(haystack, start?)?;
+            start = if m.end > m.start {
+                Some(m.end)
+            } else {
+                haystack[m.end..]
+                    .chars()
+                    .next()
+                    .map(|char| m.end + char.len_utf8())
+            };
+            // An empty match directly after a previous match is not a separate match.
+            if m.is_empty() && last_end == Some(m.end) {
+                continue;
+            }
+            last_end = Some(m.end);
+            return Some(m);
+        })
+    }
+
+    /// Replaces all matches in `haystack` with `replacement`, expanding group references.
+    pub fn replace_all(&self, haystack: &str, replacement: &str) -> String {
+        let mut result = String::new();
+        let mut last_end = 0;
+        for m in self.find_iter(haystack) {
+            let captures = self.captures_at(haystack, m.start).unwrap();
+            result.push_str(&haystack[last_end..m.start]);
+            captures.expand(haystack, replacement, &mut result);
+            last_end = m.end;
+        }
+        result.push_str(&haystack[last_end..]);
+        result
+    }
+}
+
+/// Escapes all characters in `string` that have a special meaning in a pattern.
+pub fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for char in string.chars() {
+        if is_meta_char(char) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+fn is_meta_char(char: char) -> bool {
+    matches!(
+        char,
+        '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$'
+    )
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        is_greedy: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct Class {
+    is_negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn contains(self, char: char) -> bool {
+        match self {
+            ClassItem::Range(start, end) => start <= char && char <= end,
+            ClassItem::Digit(is_negated) => char.is_ascii_digit() != is_negated,
+            ClassItem::Word(is_negated) => is_word_char(char) != is_negated,
+            ClassItem::Space(is_negated) => char.is_whitespace() != is_negated,
+        }
+    }
+}
+
+impl Class {
+    fn contains(&self, char: char, case_insensitive: bool) -> bool {
+        let contains = |char| self.items.iter().any(|item| item.contains(char));
+        let is_contained = contains(char)
+            || case_insensitive
+                && (char.to_lowercase().any(&contains) || char.to_uppercase().any(contains));
+        is_contained != self.is_negated
+    }
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    index: usize,
+    group_count: usize,
+    pattern_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).map(|(_, char)| *char)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.index += 1;
+        Some(char)
+    }
+
+    fn eat(&mut self, char: char) -> bool {
+        if self.peek() == Some(char) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> RegexError {
+        RegexError {
+            message: message.into(),
+            position: self
+                .chars
+                .get(self.index)
+                .map_or(self.pattern_len, |(position, _)| *position),
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = vec![self.parse_concat()?];
+        while self.eat('|') {
+            nodes.push(self.parse_concat()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::Alternation(nodes)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(char) = self.peek() {
+            if char == '|' || char == ')' {
+                break;
+            }
+            let node = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(node)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_quantifier(&mut self, node: Node) -> Result<Node, RegexError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.parse_counted_repetition() {
+                Some(range) => range,
+                None => return Ok(node),
+            },
+            _ => return Ok(node),
+        };
+        self.index += 1;
+        match node {
+            Node::Start | Node::End | Node::WordBoundary(_) | Node::Empty => {
+                return Err(self.error("nothing to repeat"))
+            }
+            _ => {}
+        }
+        let is_greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            is_greedy,
+        })
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`, leaving the parser on the closing brace. A brace that
+    /// doesn't start a valid repetition is treated as a literal, like most engines do.
+    fn parse_counted_repetition(&mut self) -> Option<(usize, Option<usize>)> {
+        let start = self.index;
+        self.index += 1;
+        let min = self.parse_number();
+        let max = if self.eat(',') {
+            self.parse_number()
+        } else {
+            min
+        };
+        match (min, self.peek()) {
+            (Some(min), Some('}')) if max.is_none_or(|max| min <= max) => Some((min, max)),
+            _ => {
+                self.index = start;
+                None
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let mut number = None;
+        while let Some(digit) = self.peek().and_then(|char| char.to_digit(10)) {
+            number = Some(number.unwrap_or(0) * 10 + digit as usize);
+            self.index += 1;
+        }
+        number
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        match self.next().unwrap() {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => {
+                let index = if self.peek() == Some('?') {
+                    self.index += 1;
+                    if !self.eat(':') {
+                        return Err(self.error("unsupported group syntax"));
+                    }
+                    None
+                } else {
+                    let index = self.group_count;
+                    self.group_count += 1;
+                    Some(index)
+                };
+                let node = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                Ok(Node::Group(Box::new(node), index))
+            }
+            '[' => self.parse_class(),
+            '\\' => self.parse_escape(),
+            '*' | '+' | '?' => {
+                self.index -= 1;
+                Err(self.error("nothing to repeat"))
+            }
+            char => Ok(Node::Char(char)),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, RegexError> {
+        let Some(char) = self.next() else {
+            return Err(self.error("trailing backslash"));
+        };
+        Ok(match char {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            char => match self.escape_class_item(char) {
+                Some(item) => Node::Class(Class {
+                    is_negated: false,
+                    items: vec![item],
+                }),
+                None => Node::Char(self.escape_char(char)?),
+            },
+        })
+    }
+
+    fn escape_class_item(&self, char: char) -> Option<ClassItem> {
+        Some(match char {
+            'd' => ClassItem::Digit(false),
+            'D' => ClassItem::Digit(true),
+            'w' => ClassItem::Word(false),
+            'W' => ClassItem::Word(true),
+            's' => ClassItem::Space(false),
+            'S' => ClassItem::Space(true),
+            _ => return None,
+        })
+    }
+
+    fn escape_char(&self, char: char) -> Result<char, RegexError> {
+        Ok(match char {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            char if char.is_alphanumeric() => {
+                return Err(RegexError {
+                    message: format!("unknown escape `\\{}`", char),
+                    position: self.chars[self.index - 2].0,
+                })
+            }
+            char => char,
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node, RegexError> {
+        let is_negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut is_first = true;
+        loop {
+            let Some(char) = self.next() else {
+                return Err(self.error("unclosed character class"));
+            };
+            let start = match char {
+                ']' if !is_first => break,
+                '\\' => {
+                    let Some(char) = self.next() else {
+                        return Err(self.error("trailing backslash"));
+                    };
+                    if let Some(item) = self.escape_class_item(char) {
+                        items.push(item);
+                        is_first = false;
+                        continue;
+                    }
+                    self.escape_char(char)?
+                }
+                char => char,
+            };
+            is_first = false;
+            if self.peek() == Some('-')
+                && self
+                    .chars
+                    .get(self.index + 1)
+                    .is_some_and(|(_, char)| *char != ']')
+            {
+                self.index += 1;
+                let end = match self.next().unwrap() {
+                    '\\' => {
+                        let Some(char) = self.next() else {
+                            return Err(self.error("trailing backslash"));
+                        };
+                        self.escape_char(char)?
+                    }
+                    char => char,
+                };
+                if end < start {
+                    return Err(self.error("invalid character class range"));
+                }
+                items.push(ClassItem::Range(start, end));
+            } else {
+                items.push(ClassItem::Range(start, start));
+            }
+        }
+        Ok(Node::Class(Class { is_negated, items }))
+    }
+}
+
+struct Matcher<'a> {
+    haystack: &'a str,
+    case_insensitive: bool,
+}
+
+type Continuation<'k> = dyn FnMut(usize, &mut Vec<Option<usize>>) -> bool + 'k;
+
+impl<'a> Matcher<'a> {
+    fn char_at(&self, position: usize) -> Option<char> {
+        self.haystack[position..].chars().next()
+    }
+
+    fn char_before(&self, position: usize) -> Option<char> {
+        self.haystack[..position].chars().next_back()
+    }
+
+    fn chars_eq(&self, a: char, b: char) -> bool {
+        a == b || self.case_insensitive && a.to_lowercase().eq(b.to_lowercase())
+    }
+
+    /// Tries to match `node` at `position`, calling `k` with the end position of each way the
+    /// node can match until `k` accepts one.
+    fn match_node(
+        &self,
+        node: &Node,
+        position: usize,
+        slots: &mut Vec<Option<usize>>,
+        k: &mut Continuation<'_>,
+    ) -> bool {
+        match node {
+            Node::Empty => k(position, slots),
+            Node::Char(expected) => match self.char_at(position) {
+                Some(char) if self.chars_eq(char, *expected) => {
+                    k(position + char.len_utf8(), slots)
+                }
+                _ => false,
+            },
+            Node::Any => match self.char_at(position) {
+                Some(char) if char != '\n' => k(position + char.len_utf8(), slots),
+                _ => false,
+            },
+            Node::Class(class) => match self.char_at(position) {
+                Some(char) if class.contains(char, self.case_insensitive) => {
+                    k(position + char.len_utf8(), slots)
+                }
+                _ => false,
+            },
+            Node::Start => position == 0 && k(position, slots),
+            Node::End => position == self.haystack.len() && k(position, slots),
+            Node::WordBoundary(is_boundary) => {
+                let before = self.char_before(position).is_some_and(is_word_char);
+                let after = self.char_at(position).is_some_and(is_word_char);
+                (before != after) == *is_boundary && k(position, slots)
+            }
+            Node::Group(node, None) => self.match_node(node, position, slots, k),
+            Node::Group(node, Some(index)) => {
+                let old_start = slots[index * 2];
+                let old_end = slots[index * 2 + 1];
+                slots[index * 2] = Some(position);
+                if self.match_node(node, position, slots, &mut |end, slots| {
+                    let prev_end = slots[index * 2 + 1];
+                    slots[index * 2 + 1] = Some(end);
+                    if k(end, slots) {
+                        return true;
+                    }
+                    slots[index * 2 + 1] = prev_end;
+                    false
+                }) {
+                    return true;
+                }
+                slots[index * 2] = old_start;
+                slots[index * 2 + 1] = old_end;
+                false
+            }
+            Node::Concat(nodes) => self.match_concat(nodes, position, slots, k),
+            Node::Alternation(nodes) => nodes
+                .iter()
+                .any(|node| self.match_node(node, position, slots, k)),
+            Node::Repeat {
+                node,
+                min,
+                max,
+                is_greedy,
+            } => self.match_repeat(node, *min, *max, *is_greedy, 0, position, slots, k),
+        }
+    }
+
+    fn match_concat(
+        &self,
+        nodes: &[Node],
+        position: usize,
+        slots: &mut Vec<Option<usize>>,
+        k: &mut Continuation<'_>,
+    ) -> bool {
+        match nodes.split_first() {
+            None => k(position, slots),
+            Some((first, rest)) => self.match_node(first, position, slots, &mut |end, slots| {
+                self.match_concat(rest, end, slots, k)
+            }),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn match_repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        is_greedy: bool,
+        count: usize,
+        position: usize,
+        slots: &mut Vec<Option<usize>>,
+        k: &mut Continuation<'_>,
+    ) -> bool {
+        let can_repeat = max.is_none_or(|max| count < max);
+        let repeat = |slots: &mut Vec<Option<usize>>, k: &mut Continuation<'_>| {
+            can_repeat
+                && self.match_node(node, position, slots, &mut |end, slots| {
+                    // Stop repeating once an iteration no longer makes progress, or we would
+                    // loop forever.
+                    (end != position || count < min)
+                        && self.match_repeat(node, min, max, is_greedy, count + 1, end, slots, k)
+                })
+        };
+        // A greedy repetition tries another iteration before the continuation, a lazy one after.
+        (is_greedy && repeat(slots, k))
+            || (count >= min && k(position, slots))
+            || (!is_greedy && repeat(slots, k))
+    }
+}
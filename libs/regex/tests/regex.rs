@@ -0,0 +1,75 @@
+use makepad_regex::{Regex, RegexOptions};
+
+fn find_all(pattern: &str, haystack: &str) -> Vec<String> {
+    let regex = Regex::new(pattern).unwrap();
+    regex
+        .find_iter(haystack)
+        .map(|m| m.as_str(haystack).to_string())
+        .collect()
+}
+
+#[test]
+fn test_literals_and_classes() {
+    assert_eq!(find_all("ab", "xabyab"), ["ab", "ab"]);
+    assert_eq!(find_all("[a-c]+", "xabcxcb"), ["abc", "cb"]);
+    assert_eq!(find_all("[^a-c ]+", "abxy cz"), ["xy", "z"]);
+    assert_eq!(find_all(r"\d+", "a12b345"), ["12", "345"]);
+    assert_eq!(find_all(r"\w+", "foo_bar, baz"), ["foo_bar", "baz"]);
+    assert_eq!(find_all(r"a\.b", "axb a.b"), ["a.b"]);
+    assert_eq!(find_all("é.", "éa ü"), ["éa"]);
+}
+
+#[test]
+fn test_quantifiers() {
+    assert_eq!(find_all("a*b", "aaab b"), ["aaab", "b"]);
+    assert_eq!(find_all("a{2,3}", "aaaaa"), ["aaa", "aa"]);
+    assert_eq!(find_all("a{2}", "aaaaa"), ["aa", "aa"]);
+    assert_eq!(find_all("<.+>", "<a><b>"), ["<a><b>"]);
+    assert_eq!(find_all("<.+?>", "<a><b>"), ["<a>", "<b>"]);
+    assert_eq!(find_all("colou?r", "color colour"), ["color", "colour"]);
+    assert_eq!(find_all("x{a}", "x{a}"), ["x{a}"]);
+    assert_eq!(find_all("(a*)*b", "aab"), ["aab"]);
+}
+
+#[test]
+fn test_anchors_and_alternation() {
+    assert_eq!(find_all("^fn", "fn fn"), ["fn"]);
+    assert_eq!(find_all("fn$", "fn fn"), ["fn"]);
+    assert_eq!(find_all(r"\bin\b", "in inner in"), ["in", "in"]);
+    assert_eq!(find_all("cat|dog", "a cat and a dog"), ["cat", "dog"]);
+    assert_eq!(find_all("x*", "ab").len(), 3);
+}
+
+#[test]
+fn test_captures_and_replace() {
+    let regex = Regex::new(r"(\w+)=(\w+)").unwrap();
+    let captures = regex.captures_at("let a=b;", 0).unwrap();
+    assert_eq!(captures.get(1).unwrap().as_str("let a=b;"), "a");
+    assert_eq!(captures.get(2).unwrap().as_str("let a=b;"), "b");
+    assert_eq!(regex.replace_all("a=b, c=d", "$2=$1"), "b=a, d=c");
+    assert_eq!(regex.replace_all("a=b", "${1}x $$"), "ax $");
+}
+
+#[test]
+fn test_options() {
+    let regex = Regex::with_options(
+        "hello",
+        RegexOptions {
+            case_insensitive: true,
+        },
+    )
+    .unwrap();
+    assert!(regex.is_match("Say HeLLo"));
+    assert!(Regex::literal("a+b", RegexOptions::default()).is_match("a+b"));
+}
+
+// Clippy checks the patterns passed to `Regex::new`, and these are meant to be invalid.
+#[test]
+#[allow(clippy::invalid_regex)]
+fn test_invalid_patterns() {
+    assert!(Regex::new("(ab").is_err());
+    assert!(Regex::new("ab)").is_err());
+    assert!(Regex::new("*a").is_err());
+    assert!(Regex::new("[a-").is_err());
+    assert_eq!(Regex::new(r"\q").unwrap_err().position, 0);
+}
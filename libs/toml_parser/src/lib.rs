@@ -1,2 +1,3 @@
 mod toml;
-pub use crate::toml::*;
\ No newline at end of file
+mod toml_ser;
+pub use crate::toml::*;
@@ -1,9 +1,12 @@
-use std::collections::{HashMap};
-use std::str::Chars;
+use std::collections::HashMap;
 
-#[derive(Default)]
+/// A TOML 1.0 parser.
+///
+/// `parse_toml` returns the root table of the document, tables keep their keys
+/// in the order they appear in the source. Spans are in chars, the span of a
+/// string includes its quotes.
 pub struct TomlParser {
-    pub cur: char,
+    chars: Vec<char>,
     pub pos: usize,
 }
 
@@ -13,41 +16,178 @@ pub struct TomlSpan {
     pub len: usize
 }
 
-#[derive(PartialEq, Debug)]
-pub struct TomlTokWithSpan {
-    pub span: TomlSpan,
-    pub tok: TomlTok
-}
-
-#[derive(PartialEq, Debug)]
-pub enum TomlTok {
-    Ident(String),
-    Str(String),
-    U64(u64),
-    I64(i64),
-    F64(f64),
-    Bool(bool),
-    Nan(bool),
-    Inf(bool),
-    Date(String),
-    Equals,
-    BlockOpen,
-    BlockClose,
-    ObjectOpen,
-    ObjectClose,
-    Comma,
-    Bof,
-    Eof
-}
-
-
 #[derive(PartialEq, Debug, Clone)]
 pub enum Toml {
     Str(String, TomlSpan),
     Bool(bool, TomlSpan),
-    Num(f64, TomlSpan),
-    Date(String, TomlSpan),
+    Int(i64, TomlSpan),
+    Float(f64, TomlSpan),
+    Date(TomlDateTime, TomlSpan),
     Array(Vec<Toml>),
+    Table(TomlTable),
+    InlineTable(TomlTable),
+    ArrayOfTables(Vec<TomlTable>),
+}
+
+/// An offset datetime, local datetime, local date or local time, depending on
+/// which parts are present.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct TomlDateTime {
+    pub date: Option<TomlDate>,
+    pub time: Option<TomlTime>,
+    pub offset: Option<TomlOffset>,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct TomlDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct TomlTime {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TomlOffset {
+    Z,
+    Minutes(i16),
+}
+
+// how a table came to exist, tables can only be defined once
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+enum TomlTableKind {
+    #[default]
+    Implicit,
+    Header,
+    Dotted,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TomlTable {
+    entries: Vec<(String, Toml)>,
+    kind: TomlTableKind,
+}
+
+impl PartialEq for TomlTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl TomlTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_kind(kind: TomlTableKind) -> Self {
+        Self {entries: Vec::new(), kind}
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Toml> {
+        self.entries.iter().find( | (k, _) | k == key).map( | (_, v) | v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Toml> {
+        self.entries.iter_mut().find( | (k, _) | k == key).map( | (_, v) | v)
+    }
+
+    /// Looks up a value through nested tables, `get_path(&["package", "name"])`.
+    pub fn get_path(&self, path: &[&str]) -> Option<&Toml> {
+        let (last, path) = path.split_last() ?;
+        let mut table = self;
+        for key in path {
+            table = table.get(key) ?.as_table() ?;
+        }
+        table.get(last)
+    }
+
+    /// Inserts or replaces a value, returns the old value.
+    pub fn insert(&mut self, key: impl Into<String>, value: Toml) -> Option<Toml> {
+        let key = key.into();
+        if let Some(old) = self.get_mut(&key) {
+            return Some(std::mem::replace(old, value))
+        }
+        self.entries.push((key, value));
+        None
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Toml> {
+        let index = self.entries.iter().position( | (k, _) | k == key) ?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Toml)> {
+        self.entries.iter().map( | (k, v) | (k.as_str(), v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map( | (k, _) | k.as_str())
+    }
+
+    /// All values that aren't tables by their dotted key path, like
+    /// `dependencies.makepad-widgets.version`. Keys that aren't bare keys are
+    /// quoted like `target.'cfg(windows)'.dependencies`, the elements of
+    /// arrays of tables are numbered like `bin.0.name`.
+    pub fn flatten(&self) -> HashMap<String, Toml> {
+        fn flatten(prefix: &str, table: &TomlTable, out: &mut HashMap<String, Toml>) {
+            for (key, value) in &table.entries {
+                let path = format!("{}{}", prefix, flat_key(key));
+                match value {
+                    Toml::Table(table) | Toml::InlineTable(table) => flatten(&format!("{}.", path), table, out),
+                    Toml::ArrayOfTables(tables) => for (index, table) in tables.iter().enumerate() {
+                        flatten(&format!("{}.{}.", path, index), table, out);
+                    }
+                    value => {
+                        out.insert(path, value.clone());
+                    }
+                }
+            }
+        }
+        let mut out = HashMap::new();
+        flatten("", self, &mut out);
+        out
+    }
+
+    fn get_or_insert(&mut self, key: &str, value: Toml) -> &mut Toml {
+        let index = match self.entries.iter().position( | (k, _) | k == key) {
+            Some(index) => index,
+            None => {
+                self.entries.push((key.to_string(), value));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index].1
+    }
+}
+
+fn flat_key(key: &str) -> String {
+    if is_bare_key(key) {
+        key.to_string()
+    }
+    else if !key.contains('\'') {
+        format!("'{}'", key)
+    }
+    else {
+        format!("{:?}", key)
+    }
+}
+
+pub(crate) fn is_bare_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all( | c | c.is_ascii_alphanumeric() || c == '_' || c == '-')
 }
 
 impl Toml {
@@ -57,6 +197,42 @@ impl Toml {
             _ => None
         }
     }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::Str(v, _) = self {Some(v)} else {None}
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Self::Bool(v, _) = self {Some(*v)} else {None}
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        if let Self::Int(v, _) = self {Some(*v)} else {None}
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        if let Self::Float(v, _) = self {Some(*v)} else {None}
+    }
+
+    pub fn as_date(&self) -> Option<&TomlDateTime> {
+        if let Self::Date(v, _) = self {Some(v)} else {None}
+    }
+
+    pub fn as_array(&self) -> Option<&[Toml]> {
+        if let Self::Array(v) = self {Some(v)} else {None}
+    }
+
+    /// Both tables and inline tables.
+    pub fn as_table(&self) -> Option<&TomlTable> {
+        match self {
+            Self::Table(v) | Self::InlineTable(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_array_of_tables(&self) -> Option<&[TomlTable]> {
+        if let Self::ArrayOfTables(v) = self {Some(v)} else {None}
+    }
 }
 
 pub struct TomlErr {
@@ -70,328 +246,644 @@ impl std::fmt::Debug for TomlErr {
     }
 }
 
-pub fn parse_toml(data: &str) -> Result<HashMap<String, Toml>, TomlErr> {
-    let i = &mut data.chars();
-    let mut t = TomlParser::default();
-    t.next(i);
-    let mut out = HashMap::new();
-    let mut local_scope = String::new();
-    loop {
-        let tok = t.next_tok(i) ?;
-        match tok.tok {
-            TomlTok::Eof => { // at eof.
-                return Ok(out);
-            },
-            TomlTok::BlockOpen => { // its a scope
-                // we should expect an ident or a string
-                let tok = t.next_tok(i) ?;
-                let (tok, double_block) = if let TomlTok::BlockOpen = tok.tok {
-                    (t.next_tok(i) ?, true)
-                }
-                else {(tok, false)};
-                
-                match tok.tok {
-                    TomlTok::Str(key) => { // a key
-                        local_scope = key;
-                    },
-                    TomlTok::Ident(key) => { // also a key
-                        local_scope = key;
-                    },
-                    _ => return Err(t.err_token(tok))
-                }
-                let tok = t.next_tok(i) ?;
-                
-                if tok.tok != TomlTok::BlockClose {
-                    return Err(t.err_token(tok))
-                }
-                if double_block {
-                    let tok = t.next_tok(i) ?;
-                    if tok.tok != TomlTok::BlockClose {
-                        return Err(t.err_token(tok))
-                    }
-                }
-            },
-            TomlTok::Str(key) => { // a key
-                t.parse_key_value(&local_scope, key, i, &mut out) ?;
-            },
-            TomlTok::Ident(key) => { // also a key
-                t.parse_key_value(&local_scope, key, i, &mut out) ?;
-            },
-            _ => return Err(t.err_token(tok))
-        }
-    }
+pub fn parse_toml(data: &str) -> Result<TomlTable, TomlErr> {
+    let mut t = TomlParser {chars: data.chars().collect(), pos: 0};
+    t.parse_document()
 }
 
 impl TomlParser {
-    pub fn to_val(&mut self, tok: TomlTokWithSpan, i: &mut Chars) -> Result<Toml, TomlErr> {
-        match tok.tok {
-            TomlTok::BlockOpen => {
-                let mut vals = Vec::new();
-                loop {
-                    let tok = self.next_tok(i) ?;
-                    match tok.tok {
-                        TomlTok::BlockClose | TomlTok::Eof => {
-                            break;
-                        }
-                        TomlTok::Comma => {}
-                        _ => {
-                            vals.push(self.to_val(tok, i) ?);
-                        }
-                    }
-                }
-                Ok(Toml::Array(vals))
-            },
-            TomlTok::Str(v) => Ok(Toml::Str(v, tok.span)),
-            TomlTok::U64(v) => Ok(Toml::Num(v as f64, tok.span)),
-            TomlTok::I64(v) => Ok(Toml::Num(v as f64, tok.span)),
-            TomlTok::F64(v) => Ok(Toml::Num(v, tok.span)),
-            TomlTok::Bool(v) => Ok(Toml::Bool(v, tok.span)),
-            TomlTok::Nan(v) => Ok(Toml::Num(if v {-std::f64::NAN}else {std::f64::NAN}, tok.span)),
-            TomlTok::Inf(v) => Ok(Toml::Num(if v {-std::f64::INFINITY}else {std::f64::INFINITY}, tok.span)),
-            TomlTok::Date(v) => Ok(Toml::Date(v, tok.span)),
-            _ => Err(self.err_token(tok))
-        }
-    }
-    
-    pub fn parse_key_value(&mut self, local_scope: &str, key: String, i: &mut Chars, out: &mut HashMap<String, Toml>) -> Result<(), TomlErr> {
-        let tok = self.next_tok(i) ?;
-        if tok.tok != TomlTok::Equals {
-            return Err(self.err_token(tok));
-        }
-        let tok = self.next_tok(i) ?;
-        // if we are an ObjectOpen we do a subscope
-        if let TomlTok::ObjectOpen = tok.tok {
-            let local_scope = if !local_scope.is_empty() {
-                format!("{}.{}", local_scope, key)
+    fn parse_document(&mut self) -> Result<TomlTable, TomlErr> {
+        let mut root = TomlTable::with_kind(TomlTableKind::Header);
+        // the path of the table the key/values go into
+        let mut current = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.at_end() {
+                return Ok(root)
             }
-            else {
-                key
-            };
-            loop {
-                let tok = self.next_tok(i) ?;
-                match tok.tok {
-                    TomlTok::ObjectClose | TomlTok::Eof => {
-                        break;
+            match self.cur() {
+                '#' => self.skip_comment() ?,
+                '\n' => self.next(),
+                '\r' if self.peek(1) == '\n' => self.pos += 2,
+                '[' => {
+                    let start = self.pos;
+                    self.next();
+                    let is_array = self.cur() == '[';
+                    if is_array {
+                        self.next();
                     }
-                    TomlTok::Comma => {}
-                    TomlTok::Str(key) => { // a key
-                        self.parse_key_value(&local_scope, key, i, out) ?;
-                    },
-                    TomlTok::Ident(key) => { // also a key
-                        self.parse_key_value(&local_scope, key, i, out) ?;
-                    },
-                    _ => return Err(self.err_token(tok))
+                    self.skip_whitespace();
+                    let keys = self.parse_key() ?;
+                    self.skip_whitespace();
+                    self.expect(']') ?;
+                    if is_array {
+                        self.expect(']') ?;
+                    }
+                    define_table(&mut root, &keys, is_array).map_err( | msg | self.err_span(msg, start)) ?;
+                    current = keys;
+                    self.expect_line_end() ?;
+                }
+                _ => {
+                    let start = self.pos;
+                    let keys = self.parse_key() ?;
+                    self.skip_whitespace();
+                    self.expect('=') ?;
+                    self.skip_whitespace();
+                    let value = self.parse_value() ?;
+                    let table = table_at_path(&mut root, &current);
+                    insert_dotted(table, &keys, value).map_err( | msg | self.err_span(msg, start)) ?;
+                    self.expect_line_end() ?;
                 }
             }
         }
-        else {
-            let val = self.to_val(tok, i) ?;
-            let key = if !local_scope.is_empty() {
-                format!("{}.{}", local_scope, key)
-            }
-            else {
-                key
-            };
-            out.insert(key, val);
+    }
+
+    fn cur(&self) -> char {
+        self.peek(0)
+    }
+
+    fn peek(&self, offset: usize) -> char {
+        *self.chars.get(self.pos + offset).unwrap_or(&'\0')
+    }
+
+    fn next(&mut self) {
+        self.pos += 1;
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn err(&self, msg: &str) -> TomlErr {
+        TomlErr {msg: format!("Cannot parse toml {}", msg), span: TomlSpan {start: self.pos, len: 1}}
+    }
+
+    fn err_span(&self, msg: String, start: usize) -> TomlErr {
+        TomlErr {msg, span: TomlSpan {start, len: self.pos - start}}
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), TomlErr> {
+        if self.at_end() || self.cur() != c {
+            return Err(self.err(&format!("expected {:?}", c)))
         }
+        self.next();
         Ok(())
     }
-    
-    pub fn next(&mut self, i: &mut Chars) {
-        if let Some(c) = i.next() {
-            self.cur = c;
-            self.pos += 1;
+
+    fn skip_whitespace(&mut self) {
+        while self.cur() == ' ' || self.cur() == '\t' {
+            self.next();
         }
-        else {
-            self.cur = '\0';
+    }
+
+    fn skip_comment(&mut self) -> Result<(), TomlErr> {
+        while !self.at_end() && self.cur() != '\n' {
+            if self.cur() == '\r' && self.peek(1) == '\n' {
+                break;
+            }
+            if is_control(self.cur()) {
+                return Err(self.err("control character in comment"))
+            }
+            self.next();
         }
+        Ok(())
     }
-    
-    pub fn err_token(&self, tok: TomlTokWithSpan) -> TomlErr {
-        TomlErr {msg: format!("Unexpected token {:?} ", tok), span: tok.span}
+
+    // whitespace, newlines and comments, inside arrays
+    fn skip_whitespace_newlines(&mut self) -> Result<(), TomlErr> {
+        loop {
+            match self.cur() {
+                ' ' | '\t' | '\n' => self.next(),
+                '\r' if self.peek(1) == '\n' => self.pos += 2,
+                '#' => self.skip_comment() ?,
+                _ => return Ok(())
+            }
+        }
     }
-    
-    pub fn err_parse(&self, what: &str) -> TomlErr {
-        TomlErr {msg: format!("Cannot parse toml {} ", what), span: TomlSpan {start: self.pos, len: 0}}
+
+    fn expect_line_end(&mut self) -> Result<(), TomlErr> {
+        self.skip_whitespace();
+        if self.cur() == '#' {
+            self.skip_comment() ?;
+        }
+        if self.at_end() || self.cur() == '\n' {
+            self.next();
+            return Ok(())
+        }
+        if self.cur() == '\r' && self.peek(1) == '\n' {
+            self.pos += 2;
+            return Ok(())
+        }
+        Err(self.err("expected the end of the line"))
     }
-    
-    pub fn next_tok(&mut self, i: &mut Chars) -> Result<TomlTokWithSpan, TomlErr> {
-        
-        while self.cur == '\n' || self.cur == '\r' || self.cur == '\t' || self.cur == ' ' || self.cur == '#' {
-            if self.cur == '#' {
-                while self.cur != '\n' && self.cur != '\0' {
-                    self.next(i);
+
+    fn parse_key(&mut self) -> Result<Vec<String>, TomlErr> {
+        let mut keys = Vec::new();
+        loop {
+            let key = match self.cur() {
+                '"' if !(self.peek(1) == '"' && self.peek(2) == '"') => self.parse_basic_string() ?,
+                '\'' if !(self.peek(1) == '\'' && self.peek(2) == '\'') => self.parse_literal_string() ?,
+                _ => {
+                    let mut key = String::new();
+                    while self.cur().is_ascii_alphanumeric() || self.cur() == '_' || self.cur() == '-' {
+                        key.push(self.cur());
+                        self.next();
+                    }
+                    if key.is_empty() {
+                        return Err(self.err("key"))
+                    }
+                    key
                 }
-                continue
+            };
+            keys.push(key);
+            self.skip_whitespace();
+            if self.cur() != '.' {
+                return Ok(keys)
             }
-            self.next(i);
+            self.next();
+            self.skip_whitespace();
         }
+    }
+
+    fn parse_value(&mut self) -> Result<Toml, TomlErr> {
         let start = self.pos;
-        match self.cur {
-            '\0' => {
-                Ok(TomlTokWithSpan {tok: TomlTok::Eof, span: TomlSpan {start, len: 0}})
+        match self.cur() {
+            '"' => {
+                let value = if self.peek(1) == '"' && self.peek(2) == '"' {
+                    self.parse_multiline_basic_string() ?
+                }
+                else {
+                    self.parse_basic_string() ?
+                };
+                Ok(Toml::Str(value, self.span(start)))
             }
-            ',' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::Comma, span: TomlSpan {start, len: 1}})
+            '\'' => {
+                let value = if self.peek(1) == '\'' && self.peek(2) == '\'' {
+                    self.parse_multiline_literal_string() ?
+                }
+                else {
+                    self.parse_literal_string() ?
+                };
+                Ok(Toml::Str(value, self.span(start)))
             }
-            '[' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::BlockOpen, span: TomlSpan {start, len: 1}})
+            '[' => self.parse_array(),
+            '{' => self.parse_inline_table(),
+            't' | 'f' => {
+                let word = self.take_while( | c | c.is_ascii_alphabetic());
+                match word.as_str() {
+                    "true" => Ok(Toml::Bool(true, self.span(start))),
+                    "false" => Ok(Toml::Bool(false, self.span(start))),
+                    _ => Err(self.err_span(format!("Cannot parse toml value {}", word), start))
+                }
             }
-            ']' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::BlockClose, span: TomlSpan {start, len: 1}})
+            '0'..='9' if self.peek(2) == ':' || (self.peek(4) == '-' && (0..4).all( | i | self.peek(i).is_ascii_digit())) => {
+                let value = self.parse_datetime() ?;
+                Ok(Toml::Date(value, self.span(start)))
             }
-            '{' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::ObjectOpen, span: TomlSpan {start, len: 1}})
+            '+' | '-' | '0'..='9' | 'i' | 'n' => self.parse_number(),
+            _ => Err(self.err("value"))
+        }
+    }
+
+    fn span(&self, start: usize) -> TomlSpan {
+        TomlSpan {start, len: self.pos - start}
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut out = String::new();
+        while !self.at_end() && f(self.cur()) {
+            out.push(self.cur());
+            self.next();
+        }
+        out
+    }
+
+    fn parse_array(&mut self) -> Result<Toml, TomlErr> {
+        self.expect('[') ?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace_newlines() ?;
+            if self.cur() == ']' {
+                break;
+            }
+            values.push(self.parse_value() ?);
+            self.skip_whitespace_newlines() ?;
+            match self.cur() {
+                ',' => self.next(),
+                ']' => break,
+                _ => return Err(self.err("array, expected , or ]"))
             }
-            '}' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::ObjectClose, span: TomlSpan {start, len: 1}})
+        }
+        self.next();
+        Ok(Toml::Array(values))
+    }
+
+    fn parse_inline_table(&mut self) -> Result<Toml, TomlErr> {
+        self.expect('{') ?;
+        let mut table = TomlTable::new();
+        self.skip_whitespace();
+        if self.cur() == '}' {
+            self.next();
+            return Ok(Toml::InlineTable(table))
+        }
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let keys = self.parse_key() ?;
+            self.skip_whitespace();
+            self.expect('=') ?;
+            self.skip_whitespace();
+            let value = self.parse_value() ?;
+            insert_dotted(&mut table, &keys, value).map_err( | msg | self.err_span(msg, start)) ?;
+            self.skip_whitespace();
+            match self.cur() {
+                ',' => self.next(),
+                '}' => {
+                    self.next();
+                    return Ok(Toml::InlineTable(table))
+                }
+                _ => return Err(self.err("inline table, expected , or }"))
             }
-            '=' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::Equals, span: TomlSpan {start, len: 1}})
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, TomlErr> {
+        self.expect('"') ?;
+        let mut out = String::new();
+        loop {
+            match self.cur() {
+                '"' => {
+                    self.next();
+                    return Ok(out)
+                }
+                '\\' => out.push(self.parse_escape() ?),
+                c if self.at_end() || (is_control(c) && c != '\t') => return Err(self.err("string")),
+                c => {
+                    out.push(c);
+                    self.next();
+                }
             }
-            '+' | '-' | '0'..='9' => {
-                let mut num = String::new();
-                let is_neg = if self.cur == '-' {
-                    num.push(self.cur);
-                    self.next(i);
-                    true
+        }
+    }
+
+    fn parse_multiline_basic_string(&mut self) -> Result<String, TomlErr> {
+        self.pos += 3;
+        self.skip_first_newline();
+        let mut out = String::new();
+        loop {
+            match self.cur() {
+                '"' if self.peek(1) == '"' && self.peek(2) == '"' => {
+                    self.close_multiline('"', &mut out) ?;
+                    return Ok(out)
                 }
-                else {
-                    if self.cur == '+' {
-                        self.next(i);
+                '\\' => {
+                    // a backslash at the end of a line trims the whitespace after it
+                    let mut offset = 1;
+                    while self.peek(offset) == ' ' || self.peek(offset) == '\t' {
+                        offset += 1;
                     }
-                    false
-                };
-                if self.cur == 'n' {
-                    self.next(i);
-                    if self.cur == 'a' {
-                        self.next(i);
-                        if self.cur == 'n' {
-                            self.next(i);
-                            return Ok(TomlTokWithSpan {tok: TomlTok::Nan(is_neg), span: TomlSpan {start, len: self.pos - start}})
-                        }
-                        else {
-                            return Err(self.err_parse("nan"))
+                    if self.peek(offset) == '\n' || (self.peek(offset) == '\r' && self.peek(offset + 1) == '\n') {
+                        self.pos += offset;
+                        while matches!(self.cur(), ' ' | '\t' | '\n') || (self.cur() == '\r' && self.peek(1) == '\n') {
+                            self.next();
                         }
                     }
                     else {
-                        return Err(self.err_parse("nan"))
+                        out.push(self.parse_escape() ?);
                     }
                 }
-                if self.cur == 'i' {
-                    self.next(i);
-                    if self.cur == 'n' {
-                        self.next(i);
-                        if self.cur == 'f' {
-                            self.next(i);
-                            return Ok(TomlTokWithSpan {tok: TomlTok::Inf(is_neg), span: TomlSpan {start, len: self.pos - start}})
-                        }
-                        else {
-                            return Err(self.err_parse("inf"))
-                        }
-                    }
-                    else {
-                        return Err(self.err_parse("nan"))
-                    }
+                '\r' if self.peek(1) == '\n' => {
+                    out.push('\n');
+                    self.pos += 2;
                 }
-                while self.cur >= '0' && self.cur <= '9' || self.cur == '_' {
-                    if self.cur != '_' {
-                        num.push(self.cur);
-                    }
-                    self.next(i);
+                c if self.at_end() || (is_control(c) && c != '\t' && c != '\n') => return Err(self.err("string")),
+                c => {
+                    out.push(c);
+                    self.next();
                 }
-                if self.cur == '.' {
-                    num.push(self.cur);
-                    self.next(i);
-                    while self.cur >= '0' && self.cur <= '9' || self.cur == '_' {
-                        if self.cur != '_' {
-                            num.push(self.cur);
-                        }
-                        self.next(i);
-                    }
-                    if let Ok(num) = num.parse() {
-                        Ok(TomlTokWithSpan {tok: TomlTok::F64(num), span: TomlSpan {start, len: self.pos - start}})
-                    }
-                    else {
-                        Err(self.err_parse("number"))
-                    }
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, TomlErr> {
+        self.expect('\'') ?;
+        let mut out = String::new();
+        loop {
+            match self.cur() {
+                '\'' => {
+                    self.next();
+                    return Ok(out)
                 }
-                else if self.cur == '-' { // lets assume its a date. whatever. i don't feel like more parsing today
-                    num.push(self.cur);
-                    self.next(i);
-                    while self.cur >= '0' && self.cur <= '9' || self.cur == ':' || self.cur == '-' || self.cur == 'T' {
-                        num.push(self.cur);
-                        self.next(i);
-                    }
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Date(num), span: TomlSpan {start, len: self.pos - start}})
+                c if self.at_end() || (is_control(c) && c != '\t') => return Err(self.err("string")),
+                c => {
+                    out.push(c);
+                    self.next();
                 }
-                else {
-                    if is_neg {
-                        if let Ok(num) = num.parse() {
-                            return Ok(TomlTokWithSpan {tok: TomlTok::I64(num), span: TomlSpan {start, len: self.pos - start}})
-                        }
-                        else {
-                            return Err(self.err_parse("number"));
-                        }
-                    }
-                    if let Ok(num) = num.parse() {
-                        return Ok(TomlTokWithSpan {tok: TomlTok::U64(num), span: TomlSpan {start, len: self.pos - start}})
-                    }
-                    else {
-                        return Err(self.err_parse("number"));
-                    }
+            }
+        }
+    }
+
+    fn parse_multiline_literal_string(&mut self) -> Result<String, TomlErr> {
+        self.pos += 3;
+        self.skip_first_newline();
+        let mut out = String::new();
+        loop {
+            match self.cur() {
+                '\'' if self.peek(1) == '\'' && self.peek(2) == '\'' => {
+                    self.close_multiline('\'', &mut out) ?;
+                    return Ok(out)
+                }
+                '\r' if self.peek(1) == '\n' => {
+                    out.push('\n');
+                    self.pos += 2;
                 }
-            },
-            'a'..='z' | 'A'..='Z' | '_' => {
-                let mut ident = String::new();
-                while self.cur >= 'a' && self.cur <= 'z'
-                    || self.cur >= 'A' && self.cur <= 'Z'
-                    || self.cur >= '0' && self.cur <= '9'
-                    || self.cur == '_' || self.cur == '-' || self.cur == '.'
-                    || self.cur == '\'' || self.cur == '(' || self.cur == ')' {
-                    ident.push(self.cur);
-                    self.next(i);
+                c if self.at_end() || (is_control(c) && c != '\t' && c != '\n') => return Err(self.err("string")),
+                c => {
+                    out.push(c);
+                    self.next();
                 }
-                if ident == "true" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Bool(true), span: TomlSpan {start, len: self.pos - start}})
+            }
+        }
+    }
+
+    fn skip_first_newline(&mut self) {
+        if self.cur() == '\n' {
+            self.next();
+        }
+        else if self.cur() == '\r' && self.peek(1) == '\n' {
+            self.pos += 2;
+        }
+    }
+
+    // up to two quotes right before the closing delimiter belong to the string
+    fn close_multiline(&mut self, quote: char, out: &mut String) -> Result<(), TomlErr> {
+        let mut count = 0;
+        while self.peek(count) == quote {
+            count += 1;
+        }
+        if count > 5 {
+            return Err(self.err("string, too many quotes"))
+        }
+        for _ in 3..count {
+            out.push(quote);
+        }
+        self.pos += count;
+        Ok(())
+    }
+
+    fn parse_escape(&mut self) -> Result<char, TomlErr> {
+        self.expect('\\') ?;
+        let c = match self.cur() {
+            'b' => '\u{8}',
+            't' => '\t',
+            'n' => '\n',
+            'f' => '\u{c}',
+            'r' => '\r',
+            '"' => '"',
+            '\\' => '\\',
+            'u' | 'U' => {
+                let len = if self.cur() == 'u' {4} else {8};
+                let start = self.pos;
+                self.next();
+                let mut value = 0;
+                for _ in 0..len {
+                    let digit = self.cur().to_digit(16).ok_or_else( | | self.err("unicode escape")) ?;
+                    value = value * 16 + digit;
+                    self.next();
                 }
-                if ident == "false" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Bool(false), span: TomlSpan {start, len: self.pos - start}})
+                return char::from_u32(value).ok_or_else( | | self.err_span("Cannot parse toml unicode escape, not a scalar value".to_string(), start))
+            }
+            _ => return Err(self.err("escape"))
+        };
+        self.next();
+        Ok(c)
+    }
+
+    fn parse_number(&mut self) -> Result<Toml, TomlErr> {
+        let start = self.pos;
+        let text = self.take_while( | c | c.is_ascii_alphanumeric() || c == '_' || c == '+' || c == '-' || c == '.');
+        let err = | | TomlErr {msg: format!("Cannot parse toml number {}", text), span: TomlSpan {start, len: text.chars().count()}};
+        let (sign, body) = match text.strip_prefix('-') {
+            Some(body) => ("-", body),
+            None => ("", text.strip_prefix('+').unwrap_or(&text))
+        };
+        let is_neg = sign == "-";
+        match body {
+            "inf" => return Ok(Toml::Float(if is_neg {f64::NEG_INFINITY} else {f64::INFINITY}, self.span(start))),
+            "nan" => return Ok(Toml::Float(if is_neg {-f64::NAN} else {f64::NAN}, self.span(start))),
+            _ => ()
+        }
+        let radix = match body.get(0..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => 10
+        };
+        if radix != 10 {
+            if text.len() != body.len() || !valid_digits(&body[2..], radix) {
+                return Err(err())
+            }
+            let value = i64::from_str_radix(&body[2..].replace('_', ""), radix).map_err( | _ | err()) ?;
+            return Ok(Toml::Int(value, self.span(start)))
+        }
+        let (mantissa, exponent) = match body.find(['e', 'E']) {
+            Some(index) => (&body[..index], Some(&body[index + 1..])),
+            None => (body, None)
+        };
+        let (int, frac) = match mantissa.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (mantissa, None)
+        };
+        if !valid_digits(int, 10) || (int.len() > 1 && int.starts_with('0')) {
+            return Err(err())
+        }
+        if let Some(frac) = frac {
+            if !valid_digits(frac, 10) {
+                return Err(err())
+            }
+        }
+        if let Some(exponent) = exponent {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if !valid_digits(exponent, 10) {
+                return Err(err())
+            }
+        }
+        if frac.is_none() && exponent.is_none() {
+            let value = format!("{}{}", sign, int.replace('_', "")).parse().map_err( | _ | err()) ?;
+            return Ok(Toml::Int(value, self.span(start)))
+        }
+        let value = format!("{}{}", sign, body.replace('_', "")).parse().map_err( | _ | err()) ?;
+        Ok(Toml::Float(value, self.span(start)))
+    }
+
+    fn parse_datetime(&mut self) -> Result<TomlDateTime, TomlErr> {
+        let start = self.pos;
+        let date = if self.peek(2) == ':' {
+            None
+        }
+        else {
+            let year = self.parse_digits(4) ?;
+            self.expect('-') ?;
+            let month = self.parse_digits(2) ?;
+            self.expect('-') ?;
+            let day = self.parse_digits(2) ?;
+            if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+                return Err(self.err_span("Cannot parse toml date, out of range".to_string(), start))
+            }
+            Some(TomlDate {year: year as u16, month: month as u8, day: day as u8})
+        };
+        let has_time = date.is_none() || matches!(self.cur(), 'T' | 't') || (self.cur() == ' ' && self.peek(1).is_ascii_digit());
+        let time = if has_time {
+            if date.is_some() {
+                self.next();
+            }
+            let hour = self.parse_digits(2) ?;
+            self.expect(':') ?;
+            let minute = self.parse_digits(2) ?;
+            self.expect(':') ?;
+            let second = self.parse_digits(2) ?;
+            let mut nanosecond = 0;
+            if self.cur() == '.' {
+                self.next();
+                let digits = self.take_while( | c | c.is_ascii_digit());
+                if digits.is_empty() {
+                    return Err(self.err("time fraction"))
                 }
-                if ident == "inf" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Inf(false), span: TomlSpan {start, len: self.pos - start}})
+                // beyond nanoseconds the digits are dropped
+                for (index, digit) in digits.chars().chain(std::iter::repeat('0')).take(9).enumerate() {
+                    nanosecond += digit.to_digit(10).unwrap() * 10u32.pow(8 - index as u32);
                 }
-                if ident == "nan" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Nan(false), span: TomlSpan {start, len: self.pos - start}})
+            }
+            if hour > 23 || minute > 59 || second > 60 {
+                return Err(self.err_span("Cannot parse toml time, out of range".to_string(), start))
+            }
+            Some(TomlTime {hour: hour as u8, minute: minute as u8, second: second as u8, nanosecond})
+        }
+        else {
+            None
+        };
+        let offset = if date.is_some() && time.is_some() {
+            match self.cur() {
+                'Z' | 'z' => {
+                    self.next();
+                    Some(TomlOffset::Z)
                 }
-                Ok(TomlTokWithSpan {tok: TomlTok::Ident(ident), span: TomlSpan {start, len: self.pos - start}})
-            },
-            
-            '"' => {
-                let mut val = String::new();
-                self.next(i);
-                while self.cur != '"' {
-                    if self.cur == '\\' {
-                        self.next(i);
-                    }
-                    if self.cur == '\0' {
-                        return Err(self.err_parse("string"));
+                '+' | '-' => {
+                    let is_neg = self.cur() == '-';
+                    self.next();
+                    let hours = self.parse_digits(2) ?;
+                    self.expect(':') ?;
+                    let minutes = self.parse_digits(2) ?;
+                    if hours > 23 || minutes > 59 {
+                        return Err(self.err_span("Cannot parse toml offset, out of range".to_string(), start))
                     }
-                    val.push(self.cur);
-                    self.next(i);
+                    let minutes = (hours * 60 + minutes) as i16;
+                    Some(TomlOffset::Minutes(if is_neg {-minutes} else {minutes}))
                 }
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::Str(val), span: TomlSpan {start, len: self.pos - start}})
-            },
-            _ => {
-                Err(self.err_parse("tokenizer"))
+                _ => None
             }
         }
+        else {
+            None
+        };
+        Ok(TomlDateTime {date, time, offset})
     }
-}
\ No newline at end of file
+
+    fn parse_digits(&mut self, count: usize) -> Result<u32, TomlErr> {
+        let mut value = 0;
+        for _ in 0..count {
+            let digit = self.cur().to_digit(10).ok_or_else( | | self.err("date, expected a digit")) ?;
+            value = value * 10 + digit;
+            self.next();
+        }
+        Ok(value)
+    }
+}
+
+fn is_control(c: char) -> bool {
+    c < ' ' || c == '\u{7f}'
+}
+
+// digits in `radix` where every underscore sits between two digits
+fn valid_digits(digits: &str, radix: u32) -> bool {
+    let mut last_is_digit = false;
+    for c in digits.chars() {
+        if c == '_' {
+            if !last_is_digit {
+                return false
+            }
+            last_is_digit = false;
+        }
+        else if c.is_digit(radix) {
+            last_is_digit = true;
+        }
+        else {
+            return false
+        }
+    }
+    last_is_digit
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+// the table key/values go into after a `[table]` or `[[array]]` header
+fn table_at_path<'a>(root: &'a mut TomlTable, path: &[String]) -> &'a mut TomlTable {
+    let mut table = root;
+    for key in path {
+        table = match table.get_mut(key) {
+            Some(Toml::Table(table)) => table,
+            Some(Toml::ArrayOfTables(tables)) => tables.last_mut().unwrap(),
+            _ => unreachable!()
+        }
+    }
+    table
+}
+
+fn define_table(root: &mut TomlTable, keys: &[String], is_array: bool) -> Result<(), String> {
+    let (last, path) = keys.split_last().unwrap();
+    let mut table = root;
+    for key in path {
+        table = match table.get_or_insert(key, Toml::Table(TomlTable::with_kind(TomlTableKind::Implicit))) {
+            Toml::Table(table) => table,
+            Toml::ArrayOfTables(tables) => tables.last_mut().unwrap(),
+            _ => return Err(format!("Key {} is not a table", key))
+        }
+    }
+    match table.get_mut(last) {
+        None => {
+            let new_table = TomlTable::with_kind(TomlTableKind::Header);
+            table.insert(last.clone(), if is_array {Toml::ArrayOfTables(vec![new_table])} else {Toml::Table(new_table)});
+        }
+        Some(Toml::Table(table)) if !is_array && table.kind == TomlTableKind::Implicit => {
+            table.kind = TomlTableKind::Header;
+        }
+        Some(Toml::ArrayOfTables(tables)) if is_array => {
+            tables.push(TomlTable::with_kind(TomlTableKind::Header));
+        }
+        Some(_) => return Err(format!("Table {} is defined twice", keys.join(".")))
+    }
+    Ok(())
+}
+
+fn insert_dotted(table: &mut TomlTable, keys: &[String], value: Toml) -> Result<(), String> {
+    let (last, path) = keys.split_last().unwrap();
+    let mut table = table;
+    for key in path {
+        table = match table.get_or_insert(key, Toml::Table(TomlTable::with_kind(TomlTableKind::Dotted))) {
+            Toml::Table(table) if table.kind != TomlTableKind::Header => table,
+            _ => return Err(format!("Cannot add keys to {} with dotted keys", key))
+        }
+    }
+    if table.get(last).is_some() {
+        return Err(format!("Duplicate key {}", last))
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
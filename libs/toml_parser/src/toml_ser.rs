@@ -0,0 +1,175 @@
+use std::fmt;
+use crate::toml::*;
+
+impl TomlTable {
+    /// Writes the table as a TOML document. Values come before the sub tables
+    /// of a table, so the keys of a document can come out in another order.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        write_table(&mut out, &mut Vec::new(), self);
+        out
+    }
+}
+
+impl Toml {
+    /// Writes the value the way it appears on the right of a `key = value`,
+    /// tables are written as inline tables.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self);
+        out
+    }
+}
+
+fn write_table(out: &mut String, path: &mut Vec<String>, table: &TomlTable) {
+    for (key, value) in table.iter() {
+        if !matches!(value, Toml::Table(_) | Toml::ArrayOfTables(_)) {
+            write_key(out, key);
+            out.push_str(" = ");
+            write_value(out, value);
+            out.push('\n');
+        }
+    }
+    for (key, value) in table.iter() {
+        match value {
+            Toml::Table(table) => {
+                path.push(key.to_string());
+                // tables with only sub tables are defined by the headers of those
+                let has_values = table.iter().any( | (_, v) | !matches!(v, Toml::Table(_) | Toml::ArrayOfTables(_)));
+                if has_values || table.is_empty() {
+                    write_header(out, path, "[", "]");
+                }
+                write_table(out, path, table);
+                path.pop();
+            }
+            Toml::ArrayOfTables(tables) => {
+                path.push(key.to_string());
+                for table in tables {
+                    write_header(out, path, "[[", "]]");
+                    write_table(out, path, table);
+                }
+                path.pop();
+            }
+            _ => ()
+        }
+    }
+}
+
+fn write_header(out: &mut String, path: &[String], open: &str, close: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(open);
+    for (index, key) in path.iter().enumerate() {
+        if index != 0 {
+            out.push('.');
+        }
+        write_key(out, key);
+    }
+    out.push_str(close);
+    out.push('\n');
+}
+
+fn write_key(out: &mut String, key: &str) {
+    if is_bare_key(key) {
+        out.push_str(key);
+    }
+    else {
+        write_string(out, key);
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' || c == '\u{7f}' => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+}
+
+fn write_inline_table(out: &mut String, table: &TomlTable) {
+    out.push('{');
+    for (index, (key, value)) in table.iter().enumerate() {
+        out.push_str(if index == 0 {" "} else {", "});
+        write_key(out, key);
+        out.push_str(" = ");
+        write_value(out, value);
+    }
+    out.push_str(if table.is_empty() {"}"} else {" }"});
+}
+
+fn write_value(out: &mut String, value: &Toml) {
+    match value {
+        Toml::Str(v, _) => write_string(out, v),
+        Toml::Bool(v, _) => out.push_str(if *v {"true"} else {"false"}),
+        Toml::Int(v, _) => out.push_str(&v.to_string()),
+        Toml::Float(v, _) => {
+            if v.is_nan() {
+                out.push_str("nan")
+            }
+            else if v.is_infinite() {
+                out.push_str(if *v < 0.0 {"-inf"} else {"inf"})
+            }
+            else {
+                // debug formatting always has a fraction or an exponent
+                out.push_str(&format!("{:?}", v))
+            }
+        }
+        Toml::Date(v, _) => out.push_str(&v.to_string()),
+        Toml::Array(values) => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index != 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, value);
+            }
+            out.push(']');
+        }
+        Toml::Table(table) | Toml::InlineTable(table) => write_inline_table(out, table),
+        Toml::ArrayOfTables(tables) => {
+            out.push('[');
+            for (index, table) in tables.iter().enumerate() {
+                if index != 0 {
+                    out.push_str(", ");
+                }
+                write_inline_table(out, table);
+            }
+            out.push(']');
+        }
+    }
+}
+
+impl fmt::Display for TomlDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(date) = &self.date {
+            write!(f, "{:04}-{:02}-{:02}", date.year, date.month, date.day) ?;
+            if self.time.is_some() {
+                write!(f, "T") ?;
+            }
+        }
+        if let Some(time) = &self.time {
+            write!(f, "{:02}:{:02}:{:02}", time.hour, time.minute, time.second) ?;
+            if time.nanosecond != 0 {
+                let fraction = format!("{:09}", time.nanosecond);
+                write!(f, ".{}", fraction.trim_end_matches('0')) ?;
+            }
+        }
+        match self.offset {
+            Some(TomlOffset::Z) => write!(f, "Z"),
+            Some(TomlOffset::Minutes(minutes)) => {
+                let sign = if minutes < 0 {'-'} else {'+'};
+                write!(f, "{}{:02}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+            }
+            None => Ok(())
+        }
+    }
+}
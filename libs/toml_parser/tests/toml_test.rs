@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+use makepad_micro_serde::*;
+use makepad_toml_parser::*;
+
+// runs the cases in tests/toml-test, laid out like the toml-test suite: every
+// `valid/*.toml` has a `.json` next to it with the expected values in tagged form
+// (`{"type": "integer", "value": "1"}`), every `invalid/*.toml` has to fail.
+
+fn toml_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            toml_files(&path, out);
+        }
+        else if path.extension().is_some_and( | ext | ext == "toml") {
+            out.push(path);
+        }
+    }
+}
+
+fn suite_files(kind: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    toml_files(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/toml-test").join(kind), &mut files);
+    files.sort();
+    assert!(!files.is_empty());
+    files
+}
+
+fn tagged(value: &str, ty: &str) -> JsonValue {
+    JsonValue::object().with("type", ty).with("value", value)
+}
+
+fn table_to_json(table: &TomlTable) -> JsonValue {
+    let mut object = JsonValue::object();
+    for (key, value) in table.iter() {
+        object.insert(key, toml_to_json(value));
+    }
+    object
+}
+
+fn toml_to_json(value: &Toml) -> JsonValue {
+    match value {
+        Toml::Str(v, _) => tagged(v, "string"),
+        Toml::Bool(v, _) => tagged(&v.to_string(), "bool"),
+        Toml::Int(v, _) => tagged(&v.to_string(), "integer"),
+        Toml::Float(_, _) => tagged(&value.to_toml(), "float"),
+        Toml::Date(v, _) => {
+            let ty = match (v.date.is_some(), v.time.is_some(), v.offset.is_some()) {
+                (_, _, true) => "datetime",
+                (true, true, false) => "datetime-local",
+                (true, false, _) => "date-local",
+                (false, _, _) => "time-local",
+            };
+            tagged(&v.to_string(), ty)
+        }
+        Toml::Array(values) => JsonValue::Array(values.iter().map(toml_to_json).collect()),
+        Toml::Table(table) | Toml::InlineTable(table) => table_to_json(table),
+        Toml::ArrayOfTables(tables) => JsonValue::Array(tables.iter().map(table_to_json).collect()),
+    }
+}
+
+// floats are compared by value, the suite writes them the way the toml source does
+fn json_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            if a.first().map( | (k, _) | k.as_str()) == Some("type") && b.iter().any( | (k, v) | k == "type" && v.as_str() == Some("float")) {
+                let a_value = a.iter().find( | (k, _) | k == "value").and_then( | (_, v) | v.as_str()).unwrap_or("");
+                let b_value = b.iter().find( | (k, _) | k == "value").and_then( | (_, v) | v.as_str()).unwrap_or("");
+                let (a_value, b_value) = (parse_float(a_value), parse_float(b_value));
+                return a_value == b_value || (a_value.is_nan() && b_value.is_nan())
+            }
+            a.len() == b.len() && a.iter().all( | (k, v) | b.iter().any( | (bk, bv) | bk == k && json_eq(v, bv)))
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => a.len() == b.len() && a.iter().zip(b).all( | (a, b) | json_eq(a, b)),
+        (a, b) => a == b
+    }
+}
+
+fn parse_float(value: &str) -> f64 {
+    match value.trim_start_matches('+') {
+        "inf" => f64::INFINITY,
+        "-inf" => f64::NEG_INFINITY,
+        "nan" | "-nan" => f64::NAN,
+        value => value.parse().unwrap()
+    }
+}
+
+#[test]
+fn test_valid() {
+    for path in suite_files("valid") {
+        let source = std::fs::read_to_string(&path).unwrap();
+        let toml = parse_toml(&source).unwrap_or_else( | err | panic!("{}: {:?}", path.display(), err));
+        let expected = JsonValue::deserialize_json(&std::fs::read_to_string(path.with_extension("json")).unwrap()).unwrap();
+        let json = table_to_json(&toml);
+        assert!(json_eq(&json, &expected), "{}:\n{}", path.display(), json.serialize_json());
+    }
+}
+
+#[test]
+fn test_invalid() {
+    for path in suite_files("invalid") {
+        let source = std::fs::read_to_string(&path).unwrap();
+        assert!(parse_toml(&source).is_err(), "{} should not parse", path.display());
+    }
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    for path in suite_files("valid") {
+        let toml = parse_toml(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let source = toml.to_toml();
+        let again = parse_toml(&source).unwrap_or_else( | err | panic!("{}: {:?}\n{}", path.display(), err, source));
+        assert!(json_eq(&table_to_json(&toml), &table_to_json(&again)), "{}:\n{}", path.display(), source);
+    }
+}
+
+#[test]
+fn test_cargo_manifest() {
+    let source = std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/toml-test/valid/spec/cargo-manifest.toml")).unwrap();
+    let toml = parse_toml(&source).unwrap();
+    let features = toml.get_path(&["dependencies", "serde", "features"]).unwrap().as_array().unwrap();
+    assert_eq!(features.iter().filter_map( | v | v.as_str()).collect::<Vec<_>>(), ["derive", "std"]);
+    assert_eq!(toml.get_path(&["profile", "release", "opt-level"]).unwrap().as_int(), Some(3));
+    assert_eq!(toml.get("bin").unwrap().as_array_of_tables().unwrap().len(), 1);
+
+    let flat = toml.flatten();
+    assert_eq!(flat.get("package.metadata.makepad-auto-version").unwrap().as_str(), Some("abc="));
+    assert_eq!(flat.get("target.'cfg(windows)'.dependencies.windows.version").unwrap().as_str(), Some("0.48"));
+    assert_eq!(flat.get("bin.0.name").unwrap().as_str(), Some("example"));
+
+    // spans point at the source, quotes included
+    if let Some(Toml::Str(_, span)) = flat.get("dependencies.makepad-widgets.version") {
+        let text: String = source.chars().skip(span.start).take(span.len).collect();
+        assert_eq!(text, "\"0.4.0\"");
+    }
+    else {
+        panic!("no version")
+    }
+}
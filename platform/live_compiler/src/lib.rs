@@ -16,6 +16,7 @@ pub mod live_component;
 pub mod live_node_cbor;
 //pub mod live_node_cbor;
 pub mod live_node_reader;
+pub mod live_language_server;
 
 pub use makepad_math;
 pub use makepad_derive_live;
@@ -68,6 +69,7 @@ pub use {
         live_node_reader::{
             LiveNodeReader,
         },
+        live_language_server::LiveLanguageServer,
         live_node::{
             LiveProp,
             LiveIdAsProp,
@@ -96,7 +98,10 @@ pub use {
         makepad_live_tokenizer::{LiveErrorOrigin, live_error_origin},
         live_error::{
             LiveError,
-            LiveFileError
+            LiveErrorNote,
+            LiveErrorSpan,
+            LiveFileError,
+            LiveFileErrorNote
         },
         live_document::{LiveOriginal, LiveExpanded}
     }
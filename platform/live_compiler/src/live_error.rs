@@ -43,6 +43,15 @@ pub struct LiveError {
     pub origin: LiveErrorOrigin,
     pub span: LiveErrorSpan,
     pub message: String,
+    pub notes: Vec<LiveErrorNote>,
+}
+
+/// A secondary location that explains an error, like the declaration
+/// of the type an expression was expected to have.
+#[derive(Clone)]
+pub struct LiveErrorNote {
+    pub span: LiveErrorSpan,
+    pub message: String,
 }
 
 #[derive(Clone, PartialEq)]
@@ -51,6 +60,14 @@ pub struct LiveFileError {
     pub file: String,
     pub span: TextSpan,
     pub message: String,
+    pub notes: Vec<LiveFileErrorNote>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct LiveFileErrorNote {
+    pub file: String,
+    pub span: TextSpan,
+    pub message: String,
 }
 
 impl fmt::Display for LiveFileError {
@@ -64,7 +81,18 @@ impl fmt::Display for LiveFileError {
             self.span.start.column+1,
             self.message,
             self.origin
-        )
+        ) ?;
+        for note in &self.notes {
+            write!(
+                f,
+                "\n   note: {}: {}:{} - {}",
+                note.file,
+                note.span.start.line+1,
+                note.span.start.column+1,
+                note.message
+            ) ?;
+        }
+        Ok(())
     }
 }
 
@@ -72,12 +100,22 @@ impl fmt::Display for LiveFileError {
 
 impl LiveError{
     
+    pub fn with_note(mut self, span: impl Into<LiveErrorSpan>, message: String) -> Self {
+        self.notes.push(LiveErrorNote {span: span.into(), message});
+        self
+    }
+    
     pub fn into_live_file_error(self, file:&str)->LiveFileError{
         LiveFileError {
             origin: self.origin.clone(),
             file: file.to_string(),
             span: self.span.into_text_span().unwrap(),
             message: self.message,
+            notes: self.notes.into_iter().filter_map( | note | Some(LiveFileErrorNote {
+                file: file.to_string(),
+                span: note.span.into_text_span() ?,
+                message: note.message
+            })).collect()
         }
     }
 }
@@ -51,7 +51,8 @@ impl LiveError {
         LiveError {
             origin,
             message,
-            span: nodes[index].origin.token_id().unwrap().into()
+            span: nodes[index].origin.token_id().unwrap().into(),
+            notes: Vec::new(),
         }
     }
 }
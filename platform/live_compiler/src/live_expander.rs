@@ -108,7 +108,8 @@ impl<'a> LiveExpander<'a> {
                         self.errors.push(LiveError {
                             origin: live_error_origin!(),
                             span: in_node.origin.token_id().unwrap().into(),
-                            message: format!("Import statement nothing found {}::{} as {}", live_import.module_id, live_import.import_id, in_node.id)
+                            message: format!("Import statement nothing found {}::{} as {}", live_import.module_id, live_import.import_id, in_node.id),
+                            notes: Vec::new(),
                         });
                     }
                     in_index += 1;
@@ -130,7 +131,8 @@ impl<'a> LiveExpander<'a> {
                         self.errors.push(LiveError {
                             origin: live_error_origin!(),
                             span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
-                            message: format!("Cannot define edit info after first prop def of {}", in_node.id)
+                            message: format!("Cannot define edit info after first prop def of {}", in_node.id),
+                            notes: Vec::new(),
                         });
                     }
                     // object override
@@ -248,7 +250,8 @@ impl<'a> LiveExpander<'a> {
                                             self.errors.push(LiveError {
                                                 origin: live_error_origin!(),
                                                 span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
-                                                message: format!("Class override with wrong type {}", in_node.id)
+                                                message: format!("Class override with wrong type {}", in_node.id),
+                                                notes: Vec::new(),
                                             });
                                         }
                                     }
@@ -280,7 +283,8 @@ impl<'a> LiveExpander<'a> {
                         self.errors.push(LiveError {
                             origin: live_error_origin!(),
                             span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
-                            message: format!("Can't find live definition of {} did you forget to call live_design for it?", clone)
+                            message: format!("Can't find live definition of {} did you forget to call live_design for it?", clone),
+                            notes: Vec::new(),
                         });
                     }
                     current_parent.push((out_doc.nodes[out_index].id, out_index));
@@ -354,7 +358,8 @@ impl<'a> LiveExpander<'a> {
                                     self.errors.push(LiveError {
                                         origin: live_error_origin!(),
                                         span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
-                                        message: format!("Can't find live definition of {} did you forget to call live_design for it?", lti.type_name)
+                                        message: format!("Can't find live definition of {} did you forget to call live_design for it?", lti.type_name),
+                                        notes: Vec::new(),
                                     });
                                 }
                             }
@@ -393,7 +398,8 @@ impl<'a> LiveExpander<'a> {
                                     self.errors.push(LiveError {
                                         origin: live_error_origin!(),
                                         span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
-                                        message: format!("Typename {}, not defined in file where it was expected", lti.type_name)
+                                        message: format!("Typename {}, not defined in file where it was expected", lti.type_name),
+                                        notes: Vec::new(),
                                     });
                                 }
                             }
@@ -402,7 +408,8 @@ impl<'a> LiveExpander<'a> {
                             self.errors.push(LiveError {
                                 origin: live_error_origin!(),
                                 span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
-                                message: format!("Can't find live definition of {} did you forget to call live_design for it?", lti.type_name)
+                                message: format!("Can't find live definition of {} did you forget to call live_design for it?", lti.type_name),
+                                notes: Vec::new(),
                             });
                         }
                     }
@@ -0,0 +1,589 @@
+use {
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io,
+        io::{BufRead, BufReader, Read, Write},
+    },
+    crate::{
+        makepad_micro_serde::*,
+        makepad_live_id::*,
+        live_error::LiveFileError,
+        live_node::{LiveNode, LiveValue, LiveFieldKind, LiveTypeInfo},
+        live_node_vec::LiveNodeSliceApi,
+        live_ptr::LiveFileId,
+        live_registry::{LiveFile, LiveRegistry, LiveScopeTarget},
+        live_token::{LiveToken, LiveTokenId},
+        span::{TextPos, TextSpan},
+    }
+};
+
+/// A language server for the `live_design!` blocks of the files in a `LiveRegistry`.
+///
+/// The registry has to be filled by the app itself, since the field metadata of the
+/// `LiveType`s used in the documents only exists inside a compiled app. `app_main!`
+/// runs it over stdio when the app is started with `--live-language-server`.
+///
+/// Open documents replace the sources of the registry files they match, the server
+/// re-expands every document on each change and publishes the errors as diagnostics.
+#[derive(Default)]
+pub struct LiveLanguageServer {
+    is_shutdown: bool,
+    is_exit: bool,
+    files_with_errors: BTreeSet<LiveFileId>,
+    // the lines of the documents as the client sees them, to convert between lsp
+    // characters and our columns
+    file_lines: BTreeMap<LiveFileId, Vec<String>>,
+}
+
+// LSP error code for requests the server doesn't know
+const METHOD_NOT_FOUND: i64 = -32601;
+// LSP completion item kind
+const COMPLETION_KIND_PROPERTY: u64 = 10;
+// LSP diagnostic severity
+const SEVERITY_ERROR: u64 = 1;
+
+impl LiveLanguageServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True once the client sent `shutdown` before `exit`.
+    pub fn is_shutdown(&self) -> bool {
+        self.is_shutdown
+    }
+
+    /// Serves the language server protocol on `reader` and `writer` until the client
+    /// sends `exit` or closes the stream.
+    pub fn run(&mut self, live_registry: &mut LiveRegistry, reader: impl Read, mut writer: impl Write) -> io::Result<()> {
+        let mut reader = BufReader::new(reader);
+        while let Some(body) = read_message(&mut reader) ? {
+            let message = match JsonValue::deserialize_json(&body) {
+                Ok(message) => message,
+                Err(_) => continue
+            };
+            for response in self.handle_message(live_registry, &message) {
+                write_message(&mut writer, &response) ?;
+            }
+            if self.is_exit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one message from the client and returns the messages to send back,
+    /// the response to a request always comes first.
+    pub fn handle_message(&mut self, live_registry: &mut LiveRegistry, message: &JsonValue) -> Vec<JsonValue> {
+        let method = message.get("method").and_then( | v | v.as_str()).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or_default();
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.handle_notification(live_registry, method, &params)
+        };
+        let mut out = Vec::new();
+        let result = match method {
+            "initialize" => {
+                let result = JsonValue::object()
+                    .with("capabilities", JsonValue::object()
+                        .with("textDocumentSync", 1usize)
+                        .with("completionProvider", JsonValue::object())
+                        .with("hoverProvider", true)
+                        .with("definitionProvider", true))
+                    .with("serverInfo", JsonValue::object().with("name", "makepad-live"));
+                out.push(response_message(id, result));
+                // read the files from disk so the token positions match the editor
+                let changes = live_registry.live_files.iter().filter_map( | file | {
+                    let content = std::fs::read_to_string(file_disk_path(file)).ok() ?;
+                    Some((file.file_name.clone(), content))
+                }).collect();
+                out.extend(self.update_files(live_registry, changes));
+                return out
+            }
+            "shutdown" => {
+                self.is_shutdown = true;
+                JsonValue::Null
+            }
+            "textDocument/completion" => self.completion(live_registry, &params),
+            "textDocument/definition" => self.definition(live_registry, &params),
+            "textDocument/hover" => self.hover(live_registry, &params),
+            _ => {
+                let error = JsonValue::object()
+                    .with("code", METHOD_NOT_FOUND)
+                    .with("message", format!("Unknown method {}", method));
+                out.push(JsonValue::object().with("jsonrpc", "2.0").with("id", id).with("error", error));
+                return out
+            }
+        };
+        out.push(response_message(id, result));
+        out
+    }
+
+    fn handle_notification(&mut self, live_registry: &mut LiveRegistry, method: &str, params: &JsonValue) -> Vec<JsonValue> {
+        let document = params.get("textDocument");
+        let uri = document.and_then( | v | v.get("uri")).and_then( | v | v.as_str()).unwrap_or("");
+        let content = match method {
+            "exit" => {
+                self.is_exit = true;
+                return Vec::new()
+            }
+            "textDocument/didOpen" => document.and_then( | v | v.get("text")),
+            // we ask for full document sync, so the last change holds the whole text
+            "textDocument/didChange" => params.get("contentChanges")
+                .and_then( | v | v.as_array())
+                .and_then( | v | v.last())
+                .and_then( | v | v.get("text")),
+            _ => None
+        };
+        let (file_id, content) = match (uri_to_file_id(live_registry, uri), content.and_then( | v | v.as_str())) {
+            (Some(file_id), Some(content)) => (file_id, content),
+            _ => return Vec::new()
+        };
+        let file_name = live_registry.file_id_to_file_name(file_id).to_string();
+        self.update_files(live_registry, vec![(file_name, content.to_string())])
+    }
+
+    // swaps in the new sources and re-expands every document, so errors in files
+    // that depend on the changed ones show up as well
+    fn update_files(&mut self, live_registry: &mut LiveRegistry, changes: Vec<(String, String)>) -> Vec<JsonValue> {
+        let mut errors = Vec::new();
+        for (file_name, content) in changes {
+            if let Some(file_id) = live_registry.file_name_to_file_id(&file_name) {
+                self.file_lines.insert(file_id, content.lines().map( | line | line.to_string()).collect());
+                if let Err(err) = live_registry.update_file_source(file_id, &content) {
+                    errors.push(err);
+                }
+            }
+        }
+        for file in &mut live_registry.live_files {
+            file.reexpand = true;
+        }
+        live_registry.expand_all_documents(&mut errors);
+
+        let mut diagnostics: Vec<(LiveFileId, Vec<JsonValue>)> = Vec::new();
+        for err in errors {
+            let err = live_registry.live_error_to_live_file_error(err);
+            let file_id = err.span.file_id;
+            let diagnostic = self.error_to_diagnostic(live_registry, &err);
+            if let Some((_, list)) = diagnostics.iter_mut().find( | (id, _) | *id == file_id) {
+                if !list.contains(&diagnostic) {
+                    list.push(diagnostic);
+                }
+            }
+            else {
+                diagnostics.push((file_id, vec![diagnostic]));
+            }
+        }
+        // clear the files that don't have errors anymore
+        let files_with_errors = diagnostics.iter().map( | (file_id, _) | *file_id).collect();
+        for file_id in self.files_with_errors.difference(&files_with_errors) {
+            diagnostics.push((*file_id, Vec::new()));
+        }
+        self.files_with_errors = files_with_errors;
+
+        diagnostics.into_iter().map( | (file_id, list) | {
+            let params = JsonValue::object()
+                .with("uri", file_id_to_uri(live_registry, file_id))
+                .with("diagnostics", list);
+            notification_message("textDocument/publishDiagnostics", params)
+        }).collect()
+    }
+
+    fn completion(&self, live_registry: &LiveRegistry, params: &JsonValue) -> JsonValue {
+        let (file_id, pos) = match self.text_document_position(live_registry, params) {
+            Some(v) => v,
+            None => return JsonValue::Null
+        };
+        let file = live_registry.file_id_to_file(file_id);
+        let nodes = &file.expanded.nodes;
+        let index = match enclosing_object(file, pos).and_then( | token_id | nodes.first_node_with_token_id(token_id, false)) {
+            Some(index) => index,
+            None => return JsonValue::Array(Vec::new())
+        };
+        let mut items = Vec::new();
+        let mut labels = Vec::new();
+        let mut child = nodes.first_child(index);
+        while let Some(child_index) = child {
+            let node = &nodes[child_index];
+            if node.id != LiveId(0) && !labels.contains(&node.id) {
+                labels.push(node.id);
+                items.push(completion_item(node.id, live_value_to_string(live_registry, node)));
+            }
+            child = nodes.next_child(child_index);
+        }
+        if let Some(live_type_info) = node_type_info(live_registry, nodes, index) {
+            let mut fields = Vec::new();
+            collect_fields(live_registry, &live_type_info, &mut fields);
+            for (id, type_name) in fields {
+                if !labels.contains(&id) {
+                    labels.push(id);
+                    items.push(completion_item(id, type_name.to_string()));
+                }
+            }
+        }
+        JsonValue::Array(items)
+    }
+
+    fn definition(&self, live_registry: &LiveRegistry, params: &JsonValue) -> JsonValue {
+        let (file_id, id) = match self.ident_at_position(live_registry, params) {
+            Some((file_id, _, id)) => (file_id, id),
+            None => return JsonValue::Null
+        };
+        match find_definition(live_registry, file_id, id) {
+            Some((nodes, index)) => match nodes[index].origin.token_id() {
+                Some(token_id) => self.location(live_registry, live_registry.token_id_to_span(token_id)),
+                None => JsonValue::Null
+            }
+            None => JsonValue::Null
+        }
+    }
+
+    fn hover(&self, live_registry: &LiveRegistry, params: &JsonValue) -> JsonValue {
+        let (file_id, token_id, id) = match self.ident_at_position(live_registry, params) {
+            Some(v) => v,
+            None => return JsonValue::Null
+        };
+        let nodes = &live_registry.file_id_to_file(file_id).expanded.nodes;
+        let mut text = String::new();
+        // a property, show its value and the one it overrides
+        if let Some(index) = nodes.first_node_with_token_id(token_id, false) {
+            if nodes[index].id == id {
+                text = format!("{}: {}", id, live_value_to_string(live_registry, &nodes[index]));
+                if let Some(default) = class_default(live_registry, nodes, index) {
+                    text.push_str(&format!("\n\ndefault: {}", live_value_to_string(live_registry, default)));
+                }
+            }
+        }
+        // a reference to a definition, show the values it sets
+        if text.is_empty() {
+            if let Some((nodes, index)) = find_definition(live_registry, file_id, id) {
+                text = format!("{}: {}", id, live_value_to_string(live_registry, &nodes[index]));
+                let mut child = nodes.first_child(index);
+                while let Some(child_index) = child {
+                    let node = &nodes[child_index];
+                    text.push_str(&format!("\n    {}: {}", node.id, live_value_to_string(live_registry, node)));
+                    child = nodes.next_child(child_index);
+                }
+            }
+        }
+        if text.is_empty() {
+            return JsonValue::Null
+        }
+        let span = live_registry.token_id_to_span(token_id);
+        JsonValue::object()
+            .with("contents", JsonValue::object().with("kind", "plaintext").with("value", text))
+            .with("range", self.range(span))
+    }
+
+    fn line(&self, file_id: LiveFileId, line: u32) -> Option<&str> {
+        Some(self.file_lines.get(&file_id) ?.get(line as usize) ?.as_str())
+    }
+
+    // lsp lines are 0-based like ours, lsp characters are 0-based utf-16 offsets where
+    // our columns are 1-based chars
+    fn text_document_position(&self, live_registry: &LiveRegistry, params: &JsonValue) -> Option<(LiveFileId, TextPos)> {
+        let uri = params.get("textDocument") ?.get("uri") ?.as_str() ?;
+        let file_id = uri_to_file_id(live_registry, uri) ?;
+        let position = params.get("position") ?;
+        let line = position.get("line") ?.as_u64() ? as u32;
+        let character = position.get("character") ?.as_u64() ? as u32;
+        Some((file_id, TextPos {line, column: utf16_to_column(self.line(file_id, line), character)}))
+    }
+
+    fn ident_at_position(&self, live_registry: &LiveRegistry, params: &JsonValue) -> Option<(LiveFileId, LiveTokenId, LiveId)> {
+        let (file_id, pos) = self.text_document_position(live_registry, params) ?;
+        let original = &live_registry.file_id_to_file(file_id).original;
+        // the cursor can be right behind the identifier as well
+        let token_index = original.find_token_by_pos(pos).or_else( | | {
+            original.find_token_by_pos(TextPos {line: pos.line, column: pos.column.checked_sub(1) ?})
+        }) ?;
+        if let LiveToken::Ident(id) = original.tokens[token_index].token {
+            return Some((file_id, LiveTokenId::new(file_id, token_index), id))
+        }
+        None
+    }
+
+    fn position(&self, file_id: LiveFileId, pos: TextPos) -> JsonValue {
+        JsonValue::object()
+            .with("line", pos.line as usize)
+            .with("character", column_to_utf16(self.line(file_id, pos.line), pos.column) as usize)
+    }
+
+    fn range(&self, span: TextSpan) -> JsonValue {
+        JsonValue::object()
+            .with("start", self.position(span.file_id, span.start))
+            .with("end", self.position(span.file_id, span.end))
+    }
+
+    fn location(&self, live_registry: &LiveRegistry, span: TextSpan) -> JsonValue {
+        JsonValue::object()
+            .with("uri", file_id_to_uri(live_registry, span.file_id))
+            .with("range", self.range(span))
+    }
+
+    fn error_to_diagnostic(&self, live_registry: &LiveRegistry, err: &LiveFileError) -> JsonValue {
+        JsonValue::object()
+            .with("range", self.range(err.span))
+            .with("severity", SEVERITY_ERROR)
+            .with("source", "makepad-live")
+            .with("message", err.message.clone())
+            .with("relatedInformation", err.notes.iter().map( | note | {
+                JsonValue::object()
+                    .with("location", self.location(live_registry, note.span))
+                    .with("message", note.message.clone())
+            }).collect::<Vec<_>>())
+    }
+}
+
+// the source file of a live file, found like `Cx::start_disk_live_file_watcher` does
+fn file_disk_path(file: &LiveFile) -> String {
+    if let Some(start) = file.file_name.find("src/") {
+        format!("{}/{}", file.cargo_manifest_path, &file.file_name[start..])
+    }
+    else {
+        file.file_name.clone()
+    }
+}
+
+fn uri_to_file_id(live_registry: &LiveRegistry, uri: &str) -> Option<LiveFileId> {
+    let path = uri_to_path(uri) ?;
+    let index = live_registry.live_files.iter().position( | file | {
+        file_disk_path(file) == path || path.ends_with(&format!("/{}", file.file_name))
+    }) ?;
+    Some(LiveFileId::new(index))
+}
+
+fn file_id_to_uri(live_registry: &LiveRegistry, file_id: LiveFileId) -> String {
+    path_to_uri(&file_disk_path(live_registry.file_id_to_file(file_id)))
+}
+
+fn path_to_uri(path: &str) -> String {
+    let mut uri = String::from("file://");
+    let path = path.replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    uri
+}
+
+fn uri_to_path(uri: &str) -> Option<String> {
+    let path = uri.strip_prefix("file://") ?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next() ?, iter.next() ?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok() ?, 16).ok() ?);
+        }
+        else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok() ?;
+    // windows paths look like `/C:/...`
+    if path.as_bytes().get(2) == Some(&b':') {
+        return Some(path[1..].to_string())
+    }
+    Some(path)
+}
+
+// without the text of the line every char is taken to be a single utf-16 code unit
+fn utf16_to_column(line: Option<&str>, character: u32) -> u32 {
+    let mut chars = line.unwrap_or("").chars();
+    let mut column = 1;
+    let mut utf16_offset = 0;
+    while utf16_offset < character {
+        utf16_offset += chars.next().map_or(1, | c | c.len_utf16() as u32);
+        column += 1;
+    }
+    column
+}
+
+fn column_to_utf16(line: Option<&str>, column: u32) -> u32 {
+    let mut chars = line.unwrap_or("").chars();
+    (1..column).map( | _ | chars.next().map_or(1, | c | c.len_utf16() as u32)).sum()
+}
+
+fn completion_item(id: LiveId, detail: String) -> JsonValue {
+    JsonValue::object()
+        .with("label", id.to_string())
+        .with("kind", COMPLETION_KIND_PROPERTY)
+        .with("detail", detail)
+}
+
+// the token of the innermost object in the original document around `pos`
+fn enclosing_object(file: &LiveFile, pos: TextPos) -> Option<LiveTokenId> {
+    let nodes = &file.original.nodes;
+    let mut found = None;
+    for (index, node) in nodes.iter().enumerate().skip(1) {
+        if !node.value.is_open() {
+            continue
+        }
+        let close = nodes.skip_node(index) - 1;
+        if let (Some(start), Some(end)) = (node.origin.token_id(), nodes[close].origin.token_id()) {
+            let start_pos = file.original.token_id_to_span(start).end;
+            let end_pos = file.original.token_id_to_span(end).start;
+            // nested objects come after their parents
+            if start_pos <= pos && pos <= end_pos {
+                found = Some(start);
+            }
+        }
+    }
+    found
+}
+
+// the rust type behind a node, either its class or the field type of its parent
+fn node_type_info(live_registry: &LiveRegistry, nodes: &[LiveNode], index: usize) -> Option<LiveTypeInfo> {
+    if let LiveValue::Class {live_type, ..} = &nodes[index].value {
+        return live_registry.live_type_infos.get(live_type).cloned()
+    }
+    let parent_info = node_type_info(live_registry, nodes, nodes.parent(index) ?) ?;
+    let mut fields = Vec::new();
+    collect_field_infos(live_registry, &parent_info, &mut fields);
+    fields.into_iter().find( | info | info.0 == nodes[index].id).map( | info | info.1)
+}
+
+fn collect_field_infos(live_registry: &LiveRegistry, live_type_info: &LiveTypeInfo, out: &mut Vec<(LiveId, LiveTypeInfo)>) {
+    for field in &live_type_info.fields {
+        let field_info = live_registry.live_type_infos.get(&field.live_type_info.live_type)
+            .unwrap_or(&field.live_type_info);
+        match field.live_field_kind {
+            LiveFieldKind::Deref => collect_field_infos(live_registry, field_info, out),
+            LiveFieldKind::Live | LiveFieldKind::LiveOption | LiveFieldKind::Animator => {
+                out.push((field.id, field_info.clone()))
+            }
+            LiveFieldKind::Calc => ()
+        }
+    }
+}
+
+fn collect_fields(live_registry: &LiveRegistry, live_type_info: &LiveTypeInfo, out: &mut Vec<(LiveId, LiveId)>) {
+    let mut infos = Vec::new();
+    collect_field_infos(live_registry, live_type_info, &mut infos);
+    out.extend(infos.into_iter().map( | (id, info) | (id, info.type_name)));
+}
+
+// the value a property has in the class it was inherited from
+fn class_default<'a>(live_registry: &'a LiveRegistry, nodes: &[LiveNode], index: usize) -> Option<&'a LiveNode> {
+    let parent = nodes.parent(index) ?;
+    if let LiveValue::Class {class_parent: Some(class_parent), ..} = &nodes[parent].value {
+        if !live_registry.generation_valid(*class_parent) {
+            return None
+        }
+        let (parent_nodes, parent_index) = live_registry.ptr_to_nodes_index(*class_parent);
+        let mut child = parent_nodes.first_child(parent_index);
+        while let Some(child_index) = child {
+            if parent_nodes[child_index].id == nodes[index].id {
+                return Some(&parent_nodes[child_index])
+            }
+            child = parent_nodes.next_child(child_index);
+        }
+    }
+    None
+}
+
+// resolves `id` like the expander does, imports first and then the definitions of the document
+fn find_definition(live_registry: &LiveRegistry, file_id: LiveFileId, id: LiveId) -> Option<(&[LiveNode], usize)> {
+    let file = live_registry.file_id_to_file(file_id);
+    for node in &file.original.nodes {
+        if let LiveValue::Import(import) = &node.value {
+            if node.id == id {
+                if let Some(ptr) = live_registry.module_id_and_name_to_ptr(import.module_id, import.import_id) {
+                    return Some(live_registry.ptr_to_nodes_index(ptr))
+                }
+            }
+        }
+    }
+    if file.expanded.nodes.is_empty() {
+        return None
+    }
+    match live_registry.find_scope_target(id, &file.expanded.nodes) ? {
+        LiveScopeTarget::LocalPtr(index) => Some((&file.expanded.nodes, index)),
+        LiveScopeTarget::LivePtr(ptr) => Some(live_registry.ptr_to_nodes_index(ptr))
+    }
+}
+
+fn live_value_to_string(live_registry: &LiveRegistry, node: &LiveNode) -> String {
+    match &node.value {
+        LiveValue::Str(v) => format!("{:?}", v),
+        LiveValue::String(v) => format!("{:?}", v.as_str()),
+        LiveValue::InlineString(v) => format!("{:?}", v.as_str()),
+        LiveValue::Dependency(v) => format!("dep({:?})", v.as_str()),
+        LiveValue::Bool(v) => format!("{}", v),
+        LiveValue::Int64(v) => format!("{}", v),
+        LiveValue::Uint64(v) => format!("{}", v),
+        LiveValue::Float32(v) => format!("{}", v),
+        LiveValue::Float64(v) => format!("{}", v),
+        LiveValue::Color(v) => format!("#{:08x}", v),
+        LiveValue::Vec2(v) => format!("{}", v),
+        LiveValue::Vec3(v) => format!("{}", v),
+        LiveValue::Vec4(v) => format!("{}", v),
+        LiveValue::Id(v) => format!("{}", v),
+        LiveValue::BareEnum(v) => format!("{}", v),
+        LiveValue::TupleEnum(v) => format!("{}(..)", v),
+        LiveValue::NamedEnum(v) => format!("{} {{..}}", v),
+        LiveValue::Array => "[..]".to_string(),
+        LiveValue::Object => "{..}".to_string(),
+        LiveValue::Clone(v) => format!("<{}>", v),
+        LiveValue::Class {live_type, ..} => match live_registry.live_type_infos.get(live_type) {
+            Some(info) => format!("<{}>", info.type_name),
+            None => "<class>".to_string()
+        }
+        LiveValue::Expr {..} => "expression".to_string(),
+        LiveValue::DSL {..} => "shader code".to_string(),
+        LiveValue::Import(import) => format!("import {}::{}", import.module_id, import.import_id),
+        _ => String::new()
+    }
+}
+
+fn response_message(id: JsonValue, result: JsonValue) -> JsonValue {
+    JsonValue::object()
+        .with("jsonrpc", "2.0")
+        .with("id", id)
+        .with("result", result)
+}
+
+fn notification_message(method: &str, params: JsonValue) -> JsonValue {
+    JsonValue::object()
+        .with("jsonrpc", "2.0")
+        .with("method", method)
+        .with("params", params)
+}
+
+// reads one `Content-Length` framed message, `None` at the end of the stream
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line) ? == 0 {
+            return Ok(None)
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; content_length.unwrap()];
+    reader.read_exact(&mut body) ?;
+    String::from_utf8(body).map(Some).map_err( | err | io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_message(writer: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let body = message.serialize_json();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body) ?;
+    writer.flush()
+}
@@ -80,6 +80,7 @@ impl<'a> LiveParser<'a> {
             origin,
             span: self.token_with_span.span.into(),
             message,
+            notes: Vec::new(),
         }
     }
     
@@ -5,7 +5,7 @@ use {
         makepad_live_id::*,
        // makepad_error_log::*,
         makepad_live_tokenizer::{TokenWithLen, Delim, FullToken, LiveId, State, Cursor, live_error_origin, LiveErrorOrigin},
-        live_error::{LiveError, LiveErrorSpan, LiveFileError},
+        live_error::{LiveError, LiveErrorSpan, LiveFileError, LiveFileErrorNote},
         live_parser::LiveParser,
         live_document::{LiveOriginal, LiveExpanded},
         live_node::{LiveNodeOrigin, LiveNode, LiveValue, LiveType, LiveTypeInfo, LiveIdAsProp},
@@ -358,29 +358,32 @@ impl LiveRegistry {
     }
     
     pub fn live_error_to_live_file_error(&self, live_error: LiveError) -> LiveFileError {
-        match live_error.span {
+        let (file, span) = self.live_error_span_to_file_span(live_error.span);
+        LiveFileError {
+            origin: live_error.origin,
+            file,
+            span,
+            message: live_error.message,
+            notes: live_error.notes.into_iter().map( | note | {
+                let (file, span) = self.live_error_span_to_file_span(note.span);
+                LiveFileErrorNote {file, span, message: note.message}
+            }).collect()
+        }
+    }
+    
+    fn live_error_span_to_file_span(&self, span: LiveErrorSpan) -> (String, TextSpan) {
+        match span {
             LiveErrorSpan::Text(text_span) => {
                 let live_file = &self.live_files[text_span.file_id.to_index()];
-                LiveFileError {
-                    origin: live_error.origin,
-                    file: live_file.file_name.clone(),
-                    span: text_span,
-                    message: live_error.message
-                }
+                (live_file.file_name.clone(), text_span)
             }
             LiveErrorSpan::Token(token_span) => {
-                let (file_name, span) = if let Some(file_id) = token_span.token_id.file_id() {
+                if let Some(file_id) = token_span.token_id.file_id() {
                     let live_file = &self.live_files[file_id.to_index()];
-                    (live_file.file_name.as_str(), live_file.original.tokens[token_span.token_id.token_index()].span)
+                    (live_file.file_name.clone(), live_file.original.tokens[token_span.token_id.token_index()].span)
                 }
                 else {
-                    ("<file id is not defined>", TextSpan::default())
-                };
-                LiveFileError {
-                    origin: live_error.origin,
-                    file: file_name.to_string(),
-                    span,
-                    message: live_error.message
+                    ("<file id is not defined>".to_string(), TextSpan::default())
                 }
             }
         }
@@ -414,7 +417,8 @@ impl LiveRegistry {
                             return Err(LiveError {
                                 origin: live_error_origin!(),
                                 span: span.into(),
-                                message: "Error tokenizing".to_string()
+                                message: "Error tokenizing".to_string(),
+                                notes: Vec::new(),
                             })
                         },
                         _ => if let Some(live_token) = LiveToken::from_full_token(&full_token.token) {
@@ -532,35 +536,9 @@ impl LiveRegistry {
         let mut any_changes = false;
         for change in changes {
             if let Some(file_id) = self.file_name_to_file_id(&change.file_name){
-                let module_id = self.file_id_to_module_id(file_id).unwrap();
-                let live_file = self.file_id_to_file_mut(file_id);
-                match Self::tokenize_from_str_live_design(&change.content, TextPos::default(), file_id, None) {
+                match self.update_file_source(file_id, &change.content) {
                     Err(msg) => errors.push(msg), //panic!("Lex error {}", msg),
-                    Ok(new_tokens) => {
-                        let mut parser = LiveParser::new(&new_tokens, &live_file.live_type_infos, file_id);
-                        match parser.parse_live_document() {
-                            Err(msg) => {
-                                errors.push(msg);
-                            },
-                            Ok(mut ld) => { // only swap it out when it parses
-                                for node in &mut ld.nodes {
-                                    match &mut node.value {
-                                        LiveValue::Import(live_import) => {
-                                            if live_import.module_id.0 == live_id!(crate) { // patch up crate refs
-                                               live_import.module_id.0 = module_id.0
-                                            };
-                                        }
-                                        _=>()
-                                    }
-                                }
-                                any_changes = true;
-                                ld.tokens = new_tokens;
-                                live_file.original = ld;
-                                live_file.reexpand = true;
-                                live_file.generation.next_gen();
-                            }
-                        };
-                    }
+                    Ok(()) => any_changes = true
                 }
             }
         }
@@ -569,6 +547,28 @@ impl LiveRegistry {
             self.expand_all_documents(errors);
         }
     }
+    
+    /// Re-parses the `live_design!` block in the full source of a file, the new document
+    /// is only swapped in when it parses. Call `expand_all_documents` afterwards.
+    pub fn update_file_source(&mut self, file_id: LiveFileId, content: &str) -> Result<(), LiveError> {
+        let module_id = self.file_id_to_module_id(file_id).unwrap();
+        let live_file = self.file_id_to_file_mut(file_id);
+        let new_tokens = Self::tokenize_from_str_live_design(content, TextPos::default(), file_id, None) ?;
+        let mut parser = LiveParser::new(&new_tokens, &live_file.live_type_infos, file_id);
+        let mut ld = parser.parse_live_document() ?;
+        for node in &mut ld.nodes {
+            if let LiveValue::Import(live_import) = &mut node.value {
+                if live_import.module_id.0 == live_id!(crate) { // patch up crate refs
+                    live_import.module_id.0 = module_id.0
+                }
+            }
+        }
+        ld.tokens = new_tokens;
+        live_file.original = ld;
+        live_file.reexpand = true;
+        live_file.generation.next_gen();
+        Ok(())
+    }
 
     pub fn register_live_file(
         &mut self,
@@ -0,0 +1,201 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    makepad_micro_serde::*,
+    *,
+};
+
+struct Button;
+struct Frame;
+struct App;
+
+const BUTTON_SOURCE: &str = "
+    Button = {{Button}} {
+        width: 100.0,
+        label: \"button\"
+    }
+";
+
+const APP_SOURCE: &str = "use makepad_widgets::*;
+
+live_design!{
+    import widgets::button::Button;
+
+    App = {{App}} {
+        ok = <Button> {
+            width: 50.0,
+
+        }
+    }
+}
+";
+
+fn type_info(live_type: LiveType, type_name: LiveId, module_id: LiveModuleId, fields: Vec<LiveTypeField>) -> LiveTypeInfo {
+    LiveTypeInfo {live_type, type_name, module_id, live_ignore: true, fields}
+}
+
+// field names are registered with the id lookup table, like `#[derive(Live)]` does
+fn field(name: &str, live_type_info: LiveTypeInfo, live_field_kind: LiveFieldKind) -> LiveTypeField {
+    LiveTypeField {id: LiveId::from_str_with_lut(name).unwrap(), live_type_info, live_field_kind}
+}
+
+fn registry() -> LiveRegistry {
+    let widgets = LiveModuleId::from_str("widgets::button").unwrap();
+    let app = LiveModuleId::from_str("app::app").unwrap();
+    let f64_info = type_info(LiveType::of::<f64>(), live_id!(f64), widgets, Vec::new());
+    let string_info = type_info(LiveType::of::<String>(), live_id!(String), widgets, Vec::new());
+    let frame_info = type_info(LiveType::of::<Frame>(), live_id!(Frame), widgets, vec![
+        field("padding", f64_info.clone(), LiveFieldKind::Live),
+        field("area", f64_info.clone(), LiveFieldKind::Calc),
+    ]);
+    let button_info = type_info(LiveType::of::<Button>(), live_id!(Button), widgets, vec![
+        field("frame", frame_info, LiveFieldKind::Deref),
+        field("width", f64_info.clone(), LiveFieldKind::Live),
+        field("height", f64_info, LiveFieldKind::Live),
+        field("label", string_info, LiveFieldKind::Live),
+    ]);
+    let app_info = type_info(LiveType::of::<App>(), live_id!(App), app, vec![
+        field("ok", button_info.clone(), LiveFieldKind::Live),
+    ]);
+
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file("widgets/src/button.rs", "/work/widgets", widgets, BUTTON_SOURCE.to_string(), vec![button_info], TextPos::default())
+        .unwrap_or_else( | err | panic!("{}", err));
+    // registered from the body of the macro, like `live_design!` does
+    let body = &APP_SOURCE[APP_SOURCE.find("import").unwrap()..APP_SOURCE.rfind('}').unwrap()];
+    live_registry.register_live_file("app/src/app.rs", "/work/app", app, body.to_string(), vec![app_info], TextPos::default())
+        .unwrap_or_else( | err | panic!("{}", err));
+    live_registry
+}
+
+const APP_URI: &str = "file:///work/app/src/app.rs";
+const BUTTON_URI: &str = "file:///work/widgets/src/button.rs";
+
+fn request(id: u64, method: &str, params: JsonValue) -> JsonValue {
+    JsonValue::object().with("jsonrpc", "2.0").with("id", id).with("method", method).with("params", params)
+}
+
+fn notification(method: &str, params: JsonValue) -> JsonValue {
+    JsonValue::object().with("jsonrpc", "2.0").with("method", method).with("params", params)
+}
+
+fn text_document_position(line: u64, character: u64) -> JsonValue {
+    JsonValue::object()
+        .with("textDocument", JsonValue::object().with("uri", APP_URI))
+        .with("position", JsonValue::object().with("line", line).with("character", character))
+}
+
+// the line and character of the first occurrence of `text` in the app source
+fn find(text: &str) -> (u64, u64) {
+    for (line, line_str) in APP_SOURCE.lines().enumerate() {
+        if let Some(character) = line_str.find(text) {
+            return (line as u64, character as u64)
+        }
+    }
+    panic!("{} not found", text)
+}
+
+// starts a server and opens the app document with `source`
+fn open(source: &str) -> (LiveLanguageServer, LiveRegistry, Vec<JsonValue>) {
+    let mut server = LiveLanguageServer::new();
+    let mut live_registry = registry();
+    let out = server.handle_message(&mut live_registry, &request(1, "initialize", JsonValue::object()));
+    assert!(out[0].get("result").unwrap().get("capabilities").unwrap().get("hoverProvider").is_some());
+    let document = JsonValue::object().with("uri", APP_URI).with("text", source);
+    let out = server.handle_message(&mut live_registry, &notification("textDocument/didOpen", JsonValue::object().with("textDocument", document)));
+    (server, live_registry, out)
+}
+
+fn result(server: &mut LiveLanguageServer, live_registry: &mut LiveRegistry, method: &str, params: JsonValue) -> JsonValue {
+    let out = server.handle_message(live_registry, &request(2, method, params));
+    assert_eq!(out.len(), 1);
+    out[0].get("result").unwrap().clone()
+}
+
+#[test]
+fn test_diagnostics() {
+    let (mut server, mut live_registry, out) = open(&APP_SOURCE.replace("<Button>", "<Missing>"));
+    assert_eq!(out.len(), 1);
+    let params = out[0].get("params").unwrap();
+    assert_eq!(params.get("uri").unwrap().as_str(), Some(APP_URI));
+    let diagnostics = params.get("diagnostics").unwrap().as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    let start = diagnostics[0].get("range").unwrap().get("start").unwrap();
+    let (line, _) = find("<Button>");
+    assert_eq!(start.get("line").unwrap().as_u64(), Some(line));
+
+    // fixing the error clears the diagnostics of the file
+    let change = JsonValue::object()
+        .with("textDocument", JsonValue::object().with("uri", APP_URI))
+        .with("contentChanges", vec![JsonValue::object().with("text", APP_SOURCE)]);
+    let out = server.handle_message(&mut live_registry, &notification("textDocument/didChange", change));
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].get("params").unwrap().get("diagnostics").unwrap().as_array().unwrap().len(), 0);
+}
+
+#[test]
+fn test_completion() {
+    let (mut server, mut live_registry, out) = open(APP_SOURCE);
+    assert!(out.is_empty());
+    let (line, _) = find("width: 50.0");
+    let items = result(&mut server, &mut live_registry, "textDocument/completion", text_document_position(line + 1, 12));
+    let labels: Vec<_> = items.as_array().unwrap().iter().map( | item | item.get("label").unwrap().as_str().unwrap()).collect();
+    for label in ["width", "label", "height", "padding"] {
+        assert!(labels.contains(&label), "{:?}", labels);
+    }
+    assert!(!labels.contains(&"area"), "{:?}", labels);
+    assert!(!labels.contains(&"frame"), "{:?}", labels);
+    let width = items.as_array().unwrap().iter().find( | item | item.get("label").unwrap().as_str() == Some("width")).unwrap();
+    assert_eq!(width.get("detail").unwrap().as_str(), Some("50"));
+}
+
+#[test]
+fn test_definition_across_imports() {
+    let (mut server, mut live_registry, _) = open(APP_SOURCE);
+    let (line, character) = find("<Button>");
+    let location = result(&mut server, &mut live_registry, "textDocument/definition", text_document_position(line, character + 1));
+    assert_eq!(location.get("uri").unwrap().as_str(), Some(BUTTON_URI));
+    let start = location.get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("line").unwrap().as_u64(), Some(1));
+}
+
+#[test]
+fn test_hover_default_value() {
+    let (mut server, mut live_registry, _) = open(APP_SOURCE);
+    let (line, character) = find("width");
+    let hover = result(&mut server, &mut live_registry, "textDocument/hover", text_document_position(line, character));
+    let text = hover.get("contents").unwrap().get("value").unwrap().as_str().unwrap();
+    assert_eq!(text, "width: 50\n\ndefault: 100");
+
+    let (line, character) = find("<Button>");
+    let hover = result(&mut server, &mut live_registry, "textDocument/hover", text_document_position(line, character + 1));
+    let text = hover.get("contents").unwrap().get("value").unwrap().as_str().unwrap();
+    assert!(text.starts_with("Button: <Button>"), "{}", text);
+    assert!(text.contains("width: 100"), "{}", text);
+}
+
+#[test]
+fn test_hover_behind_non_ascii() {
+    // the emoji is two utf-16 code units, which is what lsp characters count
+    let source = APP_SOURCE.replace("width: 50.0,", "label: \"\u{1F600}\", width: 50.0,");
+    let line_str = source.lines().find( | line | line.contains("width")).unwrap();
+    let line = source.lines().position( | line | line == line_str).unwrap() as u64;
+    let character = line_str[..line_str.find("width").unwrap()].encode_utf16().count() as u64;
+    let (mut server, mut live_registry, out) = open(&source);
+    assert!(out.is_empty());
+    let hover = result(&mut server, &mut live_registry, "textDocument/hover", text_document_position(line, character));
+    let text = hover.get("contents").unwrap().get("value").unwrap().as_str().unwrap();
+    assert_eq!(text, "width: 50\n\ndefault: 100");
+    let range = hover.get("range").unwrap();
+    assert_eq!(range.get("start").unwrap().get("character").unwrap().as_u64(), Some(character));
+    assert_eq!(range.get("end").unwrap().get("character").unwrap().as_u64(), Some(character + 5));
+}
+
+#[test]
+fn test_unknown_request() {
+    let mut server = LiveLanguageServer::new();
+    let mut live_registry = registry();
+    let out = server.handle_message(&mut live_registry, &request(3, "textDocument/rename", JsonValue::object()));
+    assert!(out[0].get("error").is_some());
+    assert!(server.handle_message(&mut live_registry, &request(4, "shutdown", JsonValue::Null))[0].get("result").unwrap().is_null());
+    assert!(server.is_shutdown());
+}
@@ -304,6 +304,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                             message: String::from(
                                 "attribute must be either a floating-point scalar or vector or mat4",
                             ),
+                            notes: Vec::new(),
                         })
                     }
                 }
@@ -320,6 +321,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                             message: String::from(
                                 "attribute must be either a floating-point scalar or vector or mat4",
                             ),
+                            notes: Vec::new(),
                         })
                     }
                 }
@@ -334,6 +336,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                             origin: live_error_origin!(),
                             span: decl.span.into(),
                             message: String::from("texture must be either a texture2D or a textureOES (android)"),
+                            notes: Vec::new(),
                         })
                     }
                 }
@@ -354,6 +357,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                             message: String::from(
                                 "varying must be either a floating-point scalar or vector",
                             ),
+                            notes: Vec::new(),
                         })
                     }
                 }
@@ -385,6 +389,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                         message: String::from(
                             "function `vertex` must return a value of type `vec4`",
                         ),
+                        notes: Vec::new(),
                     })
                 }
             }
@@ -398,6 +403,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                         message: String::from(
                             "function `fragment` must return a value of type `vec4`",
                         ),
+                        notes: Vec::new(),
                     })
                 }
             }
@@ -408,6 +414,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                         origin: live_error_origin!(),
                         span: def.span.into(),
                         message: String::from("functions can't return arrays"),
+                        notes: Vec::new(),
                     })
                 }
                 _ => {}
@@ -447,6 +454,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                     origin: live_error_origin!(),
                     span: sub_decl.span.into(),
                     message: format!("Struct has recursively dependency"),
+                    notes: Vec::new(),
                 });
             }
             
@@ -485,6 +493,7 @@ impl<'a> DrawShaderAnalyser<'a> {
                     origin: live_error_origin!(),
                     span: def.span.into(),
                     message: format!("function `{}` recursively calls `{}`", def.ident, callee_decl.ident),
+                    notes: Vec::new(),
                 });
             }
             
@@ -522,7 +531,7 @@ impl<'a> ConstAnalyser<'a> {
     pub fn analyse_const_decl(&mut self) -> Result<(), LiveError> {
         let expected_ty = self.ty_checker().ty_check_ty_expr(&self.const_def.ty_expr) ?;
         let actual_ty = self.ty_checker().ty_check_expr_with_expected_ty(
-            self.const_def.span,
+            self.const_def.ty_expr.span,
             &self.const_def.expr,
             &expected_ty,
         ) ?;
@@ -531,6 +540,7 @@ impl<'a> ConstAnalyser<'a> {
                 origin: live_error_origin!(),
                 span: self.const_def.span.into(),
                 message: String::from("Declared type and inferred type not the same"),
+                notes: Vec::new(),
             } .into());
         }
         self.const_evaluator().const_eval_expr(&self.const_def.expr) ?;
@@ -649,6 +659,7 @@ impl<'a> FnDefAnalyser<'a> {
                         message: format!(
                             "Function has no return",
                         ),
+                        notes: Vec::new(),
                     });
                 }
             }
@@ -691,6 +702,7 @@ impl<'a> FnDefAnalyser<'a> {
                                 closure_def.params.len(),
                                 params.len()
                             ),
+                            notes: Vec::new(),
                         });
                     }
                     // lets now push the argument idents on the scope
@@ -721,6 +733,7 @@ impl<'a> FnDefAnalyser<'a> {
                                         expr.ty.borrow().as_ref().unwrap(),
                                         return_ty.borrow().as_ref().unwrap()
                                     ),
+                                    notes: Vec::new(),
                                 });
                             }
                         }
@@ -765,6 +778,7 @@ impl<'a> FnDefAnalyser<'a> {
                 origin: live_error_origin!(),
                 span: self.fn_def.span.into(),
                 message: format!("Nesting closures is not supported at the moment"),
+                notes: Vec::new(),
             });
             
         }
@@ -789,7 +803,14 @@ impl<'a> FnDefAnalyser<'a> {
                 ref to_expr,
                 ref step_expr,
                 ref block,
-            } => self.analyse_for_stmt(span, ident, from_expr, to_expr, step_expr, block),
+                ref shadow,
+            } => self.analyse_for_stmt(span, ident, from_expr, to_expr, step_expr, block, shadow),
+            Stmt::While {
+                span,
+                ref expr,
+                ref block,
+            } => self.analyse_while_stmt(span, expr, block),
+            Stmt::Loop {span, ref block} => self.analyse_loop_stmt(span, block),
             Stmt::If {
                 span,
                 ref expr,
@@ -821,6 +842,7 @@ impl<'a> FnDefAnalyser<'a> {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: String::from("break outside loop"),
+                notes: Vec::new(),
             } .into());
         }
         Ok(())
@@ -832,6 +854,7 @@ impl<'a> FnDefAnalyser<'a> {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: String::from("continue outside loop"),
+                notes: Vec::new(),
             } .into());
         }
         Ok(())
@@ -845,6 +868,7 @@ impl<'a> FnDefAnalyser<'a> {
         to_expr: &Expr,
         step_expr: &Option<Expr>,
         block: &Block,
+        shadow: &Cell<Option<ScopeSymShadow >>,
     ) -> Result<(), LiveError> {
         
         self.ty_checker()
@@ -880,6 +904,7 @@ impl<'a> FnDefAnalyser<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: String::from("step must not be zero"),
+                    notes: Vec::new(),
                 } .into());
             }
             if from < to && step < 0 {
@@ -887,6 +912,7 @@ impl<'a> FnDefAnalyser<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: String::from("step must not be positive"),
+                    notes: Vec::new(),
                 } .into());
             }
             if from > to && step > 0 {
@@ -894,17 +920,47 @@ impl<'a> FnDefAnalyser<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: String::from("step must not be negative"),
+                    notes: Vec::new(),
                 } .into());
             }
             self.dep_analyser().dep_analyse_expr(step_expr);
         }
         self.scopes.push_scope();
-        self.scopes.insert_sym(
+        let new_shadow = self.scopes.insert_sym(
             span,
             ident,
             Ty::Int,
             ScopeSymKind::Local,
         );
+        shadow.set(Some(new_shadow));
+        let was_inside_loop = self.is_inside_loop;
+        self.is_inside_loop = true;
+        self.analyse_block(block) ?;
+        self.is_inside_loop = was_inside_loop;
+        self.scopes.pop_scope();
+        Ok(())
+    }
+    
+    fn analyse_while_stmt(
+        &mut self,
+        span: TokenSpan,
+        expr: &Expr,
+        block: &Block,
+    ) -> Result<(), LiveError> {
+        self.ty_checker()
+            .ty_check_expr_with_expected_ty(span, expr, &Ty::Bool) ?;
+        self.const_evaluator().try_const_eval_expr(expr);
+        self.const_gatherer().const_gather_expr(expr);
+        self.dep_analyser().dep_analyse_expr(expr);
+        self.analyse_loop_stmt(span, block)
+    }
+    
+    fn analyse_loop_stmt(
+        &mut self,
+        _span: TokenSpan,
+        block: &Block,
+    ) -> Result<(), LiveError> {
+        self.scopes.push_scope();
         let was_inside_loop = self.is_inside_loop;
         self.is_inside_loop = true;
         self.analyse_block(block) ?;
@@ -959,6 +1015,7 @@ impl<'a> FnDefAnalyser<'a> {
                         origin: live_error_origin!(),
                         span:span.into(),
                         message: format!("Enum name mismatched, expected {} got {}", shader_enum.enum_name, match_item.enum_name.0),
+                        notes: Vec::new(),
                     } .into())
                 } 
                 
@@ -970,6 +1027,7 @@ impl<'a> FnDefAnalyser<'a> {
                         origin: live_error_origin!(),
                         span:span.into(),
                         message: format!("Variant not found on enum {}::{}", match_item.enum_name.0, match_item.enum_variant.0),
+                        notes: Vec::new(),
                     } .into())
                 }
                 
@@ -985,6 +1043,7 @@ impl<'a> FnDefAnalyser<'a> {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: String::from("Can only match on enum types"),
+                notes: Vec::new(),
             } .into())
         }
     }
@@ -1004,13 +1063,14 @@ impl<'a> FnDefAnalyser<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("cannot define an uninitialised variable `{}`", ident),
+                    notes: Vec::new(),
                 });
             }
             let expected_ty = self.ty_checker().ty_check_ty_expr(ty_expr) ?;
             if let Some(expr) = expr {
                 let actual_ty =
                 self.ty_checker()
-                    .ty_check_expr_with_expected_ty(span, expr, &expected_ty) ?;
+                    .ty_check_expr_with_expected_ty(ty_expr.span, expr, &expected_ty) ?;
                 self.dep_analyser().dep_analyse_expr(expr);
                 actual_ty
             } else {
@@ -1023,7 +1083,8 @@ impl<'a> FnDefAnalyser<'a> {
                 return Err(LiveError {
                     origin: live_error_origin!(),
                     span:span.into(),
-                    message: String::from("init expression cannot be void")
+                    message: String::from("init expression cannot be void"),
+                    notes: Vec::new(),
                 });
             }
             self.const_evaluator().try_const_eval_expr(expr);
@@ -1035,6 +1096,7 @@ impl<'a> FnDefAnalyser<'a> {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: format!("can't infer type of variable `{}`", ident),
+                notes: Vec::new(),
             });
         });
         let new_shadow = self.scopes.insert_sym(
@@ -1060,7 +1122,7 @@ impl<'a> FnDefAnalyser<'a> {
             }
             else {
                 self.ty_checker().ty_check_expr_with_expected_ty(
-                    span,
+                    self.fn_def.return_ty_expr.as_ref().map_or(span, | ty_expr | ty_expr.span),
                     expr,
                     self.fn_def.return_ty.borrow().as_ref().unwrap()
                 ) ?;
@@ -1074,6 +1136,7 @@ impl<'a> FnDefAnalyser<'a> {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: String::from("missing return expression"),
+                notes: Vec::new(),
             } .into());
         }
         Ok(())
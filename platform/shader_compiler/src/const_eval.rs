@@ -22,6 +22,7 @@ impl ConstEvaluator {
             origin:live_error_origin!(),
             span: expr.span.into(),
             message: String::from("expression is not const"),
+            notes: Vec::new(),
         })
     }
 
@@ -95,9 +96,12 @@ impl ConstEvaluator {
         expr_if_true: &Expr,
         expr_if_false: &Expr,
     ) -> Option<Val> {
-        let val = self.try_const_eval_expr(expr)?;
-        let val_if_true = self.try_const_eval_expr(expr_if_true)?;
-        let val_if_false = self.try_const_eval_expr(expr_if_false)?;
+        let val = self.try_const_eval_expr(expr);
+        let val_if_true = self.try_const_eval_expr(expr_if_true);
+        let val_if_false = self.try_const_eval_expr(expr_if_false);
+        let val = val?;
+        let val_if_true = val_if_true?;
+        let val_if_false = val_if_false?;
         Some(if val.to_bool().unwrap() {
             val_if_true
         } else {
@@ -240,8 +240,10 @@ impl<'a> DepAnalyser<'a> {
                     Some(Ty::Struct(struct_ptr))=>{
                         self.fn_def.struct_refs.borrow_mut().as_mut().unwrap().insert(*struct_ptr);
                     }
-                    Some(Ty::Array{..})=>{
-                        todo!();
+                    Some(Ty::Array{elem_ty, ..})=>{
+                        if let Ty::Struct(struct_ptr) = **elem_ty{
+                            self.fn_def.struct_refs.borrow_mut().as_mut().unwrap().insert(struct_ptr);
+                        }
                     }
                     _=>()
                 }
@@ -12,7 +12,8 @@ use{
             TokenSpan
         },
         shader_ast::*,
-        shader_registry::ShaderRegistry
+        shader_registry::ShaderRegistry,
+        swizzle::Swizzle
     }
 };
 
@@ -77,6 +78,66 @@ pub trait BackendWriter {
     fn write_ty_lit(&self, string: &mut String, ty_lit: TyLit);
     fn write_builtin_call_ident(&self, string: &mut String, ident: Ident, arg_exprs: &[Expr]);
     
+    // the hooks below have C-like defaults, backends with a different syntax override them
+    
+    fn needs_select_for_cond_expr(&self) -> bool {
+        false
+    }
+    
+    // arrays can only be indexed with constants and loop indices
+    fn needs_select_for_dynamic_index(&self) -> bool {
+        false
+    }
+    
+    // `while` and `loop` are written as for loops with this many iterations at most
+    fn max_loop_iterations(&self) -> Option<usize> {
+        None
+    }
+    
+    // assignments can't be nested in expressions, so they aren't wrapped in parentheses
+    fn assign_is_stmt(&self) -> bool {
+        false
+    }
+    
+    // expression statements that aren't calls or assignments need to discard their value
+    fn needs_phony_assign_for_expr_stmt(&self) -> bool {
+        false
+    }
+    
+    fn write_fn_def_start(&self, string: &mut String, ident: &dyn fmt::Display, return_ty: &Ty) {
+        self.write_var_decl(string, "", false, false, ident, return_ty);
+        write!(string, "(").unwrap();
+    }
+    
+    fn write_fn_def_end(&self, string: &mut String, _return_ty: &Ty) {
+        write!(string, ") ").unwrap();
+    }
+    
+    fn write_param_decl(&self, string: &mut String, sep: &'static str, is_inout: bool, ident: &dyn fmt::Display, ty: &Ty) -> bool {
+        self.write_var_decl(string, sep, is_inout, false, ident, ty)
+    }
+    
+    fn write_let_decl(&self, string: &mut String, ident: &dyn fmt::Display, ty: &Ty) {
+        self.write_var_decl(string, "", false, false, ident, ty);
+    }
+    
+    // the prologue is written as lines at the start of a function body, the epilogue inline before
+    // every return
+    fn write_fn_def_prologue(&self, _string: &mut String, _params: &[ParamDecl]) {
+    }
+    
+    fn write_fn_def_epilogue(&self, _string: &mut String, _params: &[ParamDecl]) {
+    }
+    
+    fn write_inout_arg_prefix(&self, _string: &mut String) {
+    }
+}
+
+/// A parameter as it was written by `write_param_decl`
+pub struct ParamDecl {
+    pub is_inout: bool,
+    pub ident: String,
+    pub ty: Ty
 }
 
 pub struct BlockGenerator<'a> {
@@ -88,6 +149,7 @@ pub struct BlockGenerator<'a> {
     pub const_table_offset: Option<usize>,
     //pub use_generated_cons_fns: bool,
     pub indent_level: usize,
+    pub param_decls: &'a [ParamDecl],
     pub string: &'a mut String,
 }
 
@@ -100,25 +162,29 @@ pub fn generate_cons_fn(backend_writer:&dyn BackendWriter, string: &mut String,
     if !backend_writer.use_cons_fn(&cons_name) {
         return
     }
-    backend_writer.write_ty_lit(string, ty_lit);
-    write!(string, " {}(", cons_name).unwrap();
+    let ty = ty_lit.to_ty();
+    backend_writer.write_fn_def_start(string, &cons_name, &ty);
     
     let mut sep = "";
+    let mut param_decls = Vec::new();
     if param_tys.len() == 1 {
-        backend_writer.write_var_decl(string, sep, false, false, &Ident(live_id!(x)), &param_tys[0]);
+        backend_writer.write_param_decl(string, sep, false, &Ident(live_id!(x)), &param_tys[0]);
+        param_decls.push(ParamDecl {is_inout: false, ident: "x".to_string(), ty: param_tys[0].clone()});
     } else {
         for (index, param_ty) in param_tys.iter().enumerate() {
             //write!(string, "{}", sep).unwrap();
-            backend_writer.write_var_decl(string, sep, false, false,&DisplaConstructorArg(index), param_ty);
+            backend_writer.write_param_decl(string, sep, false, &DisplaConstructorArg(index), param_ty);
+            param_decls.push(ParamDecl {is_inout: false, ident: DisplaConstructorArg(index).to_string(), ty: param_ty.clone()});
             sep = ", ";
         }
     }
     
-    writeln!(string, ") {{").unwrap();
+    backend_writer.write_fn_def_end(string, &ty);
+    writeln!(string, "{{").unwrap();
+    backend_writer.write_fn_def_prologue(string, &param_decls);
     write!(string, "    return ").unwrap();
     backend_writer.write_ty_lit(string, ty_lit);
     write!(string, "(").unwrap();
-    let ty = ty_lit.to_ty();
     if param_tys.len() == 1 {
         let param_ty = &param_tys[0];
         match param_ty {
@@ -129,6 +195,13 @@ pub fn generate_cons_fn(backend_writer:&dyn BackendWriter, string: &mut String,
                     sep = ", ";
                 }
             }
+            Ty::Bvec2 | Ty::Bvec3 | Ty::Bvec4 | Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4 | Ty::Vec2 | Ty::Vec3 | Ty::Vec4 => {
+                let mut sep = "";
+                for index in 0..ty.slots() {
+                    write!(string, "{}x[{}]", sep, index).unwrap();
+                    sep = ", ";
+                }
+            }
             Ty::Mat2 | Ty::Mat3 | Ty::Mat4 => {
                 let dst_size = match ty {
                     Ty::Mat2 => 2,
@@ -197,6 +270,31 @@ impl<'a> BlockGenerator<'a> {
         write!(self.string, "}}").unwrap();
     }
     
+    /// Like `generate_block`, with the prologue and epilogue for `param_decls` around the statements.
+    pub fn generate_fn_body(&mut self, block: &Block, return_ty: &Ty) {
+        write!(self.string, "{{\n").unwrap();
+        self.write_indent();
+        self.backend_writer.write_fn_def_prologue(self.string, self.param_decls);
+        if !block.stmts.is_empty() {
+            self.indent_level += 1;
+            for stmt in &block.stmts {
+                self.generate_stmt(stmt);
+                writeln!(self.string).unwrap();
+            }
+            if let Ty::Void = return_ty {
+                let mut epilogue = String::new();
+                self.backend_writer.write_fn_def_epilogue(&mut epilogue, self.param_decls);
+                if !epilogue.is_empty() {
+                    self.write_indent();
+                    writeln!(self.string, "{}", epilogue).unwrap();
+                }
+            }
+            self.indent_level -= 1;
+            self.write_indent();
+        }
+        write!(self.string, "}}").unwrap();
+    }
+    
     fn generate_stmt(&mut self, stmt: &Stmt) {
         self.write_indent();
         match *stmt {
@@ -209,7 +307,14 @@ impl<'a> BlockGenerator<'a> {
                 ref to_expr,
                 ref step_expr,
                 ref block,
-            } => self.generate_for_stmt(span, ident, from_expr, to_expr, step_expr, block),
+                ref shadow,
+            } => self.generate_for_stmt(span, ident, from_expr, to_expr, step_expr, block, shadow),
+            Stmt::While {
+                span,
+                ref expr,
+                ref block,
+            } => self.generate_while_stmt(span, Some(expr), block),
+            Stmt::Loop {span, ref block} => self.generate_while_stmt(span, None, block),
             Stmt::If {
                 span,
                 ref expr,
@@ -229,7 +334,7 @@ impl<'a> BlockGenerator<'a> {
                 ref expr,
                 ref shadow
             } => self.generate_let_stmt(span, ty, ident, ty_expr, expr, shadow),
-            Stmt::Return {span, ref expr} => self.generate_return_stmt(span, expr),
+            Stmt::Return {span, ref expr} => self.generate_return_stmt(span, expr.as_ref()),
             Stmt::Block {span, ref block} => self.generate_block_stmt(span, block),
             Stmt::Expr {span, ref expr} => self.generate_expr_stmt(span, expr),
         }
@@ -251,6 +356,7 @@ impl<'a> BlockGenerator<'a> {
         to_expr: &Expr,
         step_expr: &Option<Expr>,
         block: &Block,
+        shadow: &Cell<Option<ScopeSymShadow >>
     ) {
         let from = from_expr
             .const_val
@@ -285,10 +391,13 @@ impl<'a> BlockGenerator<'a> {
         } else {
             -1
         };
+        let var_name = DisplayVarName(ident, shadow.get().unwrap());
+        write!(self.string, "for (").unwrap();
+        self.backend_writer.write_let_decl(self.string, &var_name, &Ty::Int);
         write!(
             self.string,
-            "for (int {0} = {1}; {0} {2} {3}; {0} {4} {5}) ",
-            ident,
+            " = {1}; {0} {2} {3}; {0} {4} {5}) ",
+            var_name,
             if from <= to {from} else {from - 1},
             if from <= to {"<"} else {">="},
             to,
@@ -300,6 +409,48 @@ impl<'a> BlockGenerator<'a> {
         writeln!(self.string).unwrap();
     }
     
+    // `loop` is a `while` without a condition
+    fn generate_while_stmt(
+        &mut self,
+        _span: TokenSpan,
+        expr: Option<&Expr>,
+        block: &Block,
+    ) {
+        if let Some(max_iterations) = self.backend_writer.max_loop_iterations() {
+            // only for loops with a constant bound are allowed, so the condition becomes a break
+            let var_name = format!("loop_{}", self.indent_level);
+            write!(self.string, "for (").unwrap();
+            self.backend_writer.write_let_decl(self.string, &var_name, &Ty::Int);
+            write!(self.string, " = 0; {0} < {1}; {0}++) {{\n", var_name, max_iterations).unwrap();
+            self.indent_level += 1;
+            if let Some(expr) = expr {
+                self.write_indent();
+                write!(self.string, "if (!(").unwrap();
+                self.generate_expr(expr);
+                write!(self.string, ")) {{break;}}\n").unwrap();
+            }
+            for stmt in &block.stmts {
+                self.generate_stmt(stmt);
+                writeln!(self.string).unwrap();
+            }
+            self.indent_level -= 1;
+            self.write_indent();
+            write!(self.string, "}}").unwrap();
+            writeln!(self.string).unwrap();
+            return
+        }
+        write!(self.string, "while (").unwrap();
+        if let Some(expr) = expr {
+            self.generate_expr(expr);
+        }
+        else {
+            write!(self.string, "true").unwrap();
+        }
+        write!(self.string, ") ").unwrap();
+        self.generate_block(block);
+        writeln!(self.string).unwrap();
+    }
+    
     fn generate_if_stmt(
         &mut self,
         _span: TokenSpan,
@@ -358,11 +509,8 @@ impl<'a> BlockGenerator<'a> {
         expr: &Option<Expr>,
         shadow: &Cell<Option<ScopeSymShadow >>
     ) {
-        self.backend_writer.write_var_decl(
+        self.backend_writer.write_let_decl(
             &mut self.string,
-            "",
-            false, 
-            false,
             &DisplayVarName(ident, shadow.get().unwrap()),
             ty.borrow().as_ref().unwrap()
         );
@@ -373,7 +521,23 @@ impl<'a> BlockGenerator<'a> {
         writeln!(self.string, ";").unwrap();
     }
     
-    fn generate_return_stmt(&mut self, _span: TokenSpan, expr: &Option<Expr>) {
+    pub fn generate_return_stmt(&mut self, _span: TokenSpan, expr: Option<&Expr>) {
+        let mut epilogue = String::new();
+        self.backend_writer.write_fn_def_epilogue(&mut epilogue, self.param_decls);
+        if !epilogue.is_empty() {
+            if let Some(expr) = expr {
+                // the return value has to be computed before the epilogue runs
+                write!(self.string, "{{").unwrap();
+                self.backend_writer.write_let_decl(self.string, &"ret_val", expr.ty.borrow().as_ref().unwrap());
+                write!(self.string, " = ").unwrap();
+                self.generate_expr(expr);
+                writeln!(self.string, "; {}return ret_val;}}", epilogue).unwrap();
+            }
+            else {
+                writeln!(self.string, "{}return;", epilogue).unwrap();
+            }
+            return
+        }
         write!(self.string, "return").unwrap();
         if let Some(expr) = expr {
             write!(self.string, " ").unwrap();
@@ -388,6 +552,15 @@ impl<'a> BlockGenerator<'a> {
     }
     
     fn generate_expr_stmt(&mut self, _span: TokenSpan, expr: &Expr) {
+        if self.backend_writer.needs_phony_assign_for_expr_stmt() {
+            let is_assign = match &expr.kind {
+                ExprKind::Bin {op, ..} => op.is_assign(),
+                _ => false
+            };
+            if !is_assign && *expr.ty.borrow() != Some(Ty::Void) {
+                write!(self.string, "_ = ").unwrap();
+            }
+        }
         self.generate_expr(expr);
         writeln!(self.string, ";").unwrap();
     }
@@ -566,6 +739,16 @@ impl<'a> ExprGenerator<'a> {
         expr_if_true: &Expr,
         expr_if_false: &Expr,
     ) {
+        if self.backend_writer.needs_select_for_cond_expr() {
+            write!(self.string, "select(").unwrap();
+            self.generate_expr(expr_if_false);
+            write!(self.string, ", ").unwrap();
+            self.generate_expr(expr_if_true);
+            write!(self.string, ", ").unwrap();
+            self.generate_expr(expr);
+            write!(self.string, ")").unwrap();
+            return
+        }
         write!(self.string, "(").unwrap();
         self.generate_expr(expr);
         write!(self.string, " ? ").unwrap();
@@ -577,6 +760,37 @@ impl<'a> ExprGenerator<'a> {
     
     fn generate_bin_expr(&mut self, _span: TokenSpan, op: BinOp, left_expr: &Expr, right_expr: &Expr) {
         
+        if op.is_assign() && self.backend_writer.assign_is_stmt() {
+            // without swizzles on the left, assign to the components one by one
+            if let ExprKind::Field {expr: vec_expr, field_ident, ..} = &left_expr.kind {
+                let is_vec = matches!(
+                    vec_expr.ty.borrow().as_ref().unwrap(),
+                    Ty::Bvec2 | Ty::Bvec3 | Ty::Bvec4 | Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4 | Ty::Vec2 | Ty::Vec3 | Ty::Vec4
+                );
+                if let Some(swizzle) = Swizzle::parse(*field_ident).filter( | swizzle | is_vec && swizzle.len() > 1) {
+                    let is_scalar = right_expr.ty.borrow().as_ref().unwrap().slots() == 1;
+                    write!(self.string, "{{let swizzle_val = ").unwrap();
+                    self.generate_expr(right_expr);
+                    write!(self.string, ";").unwrap();
+                    for (index, component) in swizzle.iter().enumerate() {
+                        write!(self.string, " ").unwrap();
+                        self.generate_expr(vec_expr);
+                        write!(self.string, ".{} {} swizzle_val", &"xyzw"[*component..*component + 1], op).unwrap();
+                        if !is_scalar {
+                            write!(self.string, ".{}", &"xyzw"[index..index + 1]).unwrap();
+                        }
+                        write!(self.string, ";").unwrap();
+                    }
+                    write!(self.string, "}}").unwrap();
+                    return
+                }
+            }
+            self.generate_expr(left_expr);
+            write!(self.string, " {} ", op).unwrap();
+            self.generate_expr(right_expr);
+            return
+        }
+        
         // if left_expr or right_expr is a matrix, HLSL needs to use mul()
         let left_is_mat = match left_expr.ty.borrow().as_ref().unwrap() {
             Ty::Mat2 | Ty::Mat3 | Ty::Mat4 => true,
@@ -710,8 +924,9 @@ impl<'a> ExprGenerator<'a> {
                 fn_def.ident
             )).unwrap();
             
+            let param_offset = fn_def.params.len() - arg_exprs.len();
             let mut sep = "";
-            for arg_expr in arg_exprs {
+            for (index, arg_expr) in arg_exprs.iter().enumerate() {
                 // check if the args is a closure, ifso skip it
                 match arg_expr.ty.borrow().as_ref().unwrap(){
                     Ty::ClosureDef(_)=>{
@@ -721,7 +936,7 @@ impl<'a> ExprGenerator<'a> {
                 }
                 
                 write!(self.string, "{}", sep).unwrap();
-                self.generate_expr(arg_expr);
+                self.generate_arg_expr(&fn_def.params[param_offset + index], arg_expr);
                 sep = ", ";
             }
             // and now the closed over values
@@ -747,10 +962,11 @@ impl<'a> ExprGenerator<'a> {
         }
         else {
             write!(self.string, "{}_{} (", fn_def.fn_ptr, fn_def.ident).unwrap();
+            let param_offset = fn_def.params.len() - arg_exprs.len();
             let mut sep = "";
-            for arg_expr in arg_exprs {
+            for (index, arg_expr) in arg_exprs.iter().enumerate() {
                 write!(self.string, "{}", sep).unwrap();
-                self.generate_expr(arg_expr);
+                self.generate_arg_expr(&fn_def.params[param_offset + index], arg_expr);
                 sep = ", ";
             }
 
@@ -760,6 +976,13 @@ impl<'a> ExprGenerator<'a> {
         }
     }
     
+    fn generate_arg_expr(&mut self, param: &Param, arg_expr: &Expr) {
+        if param.is_inout {
+            self.backend_writer.write_inout_arg_prefix(self.string);
+        }
+        self.generate_expr(arg_expr);
+    }
+    
     fn generate_field_expr(&mut self, _span: TokenSpan, expr: &Expr, field_ident: Ident, ty:&Ty) {
         match expr.ty.borrow().as_ref() {
             Some(Ty::DrawShader(_)) => {
@@ -811,12 +1034,61 @@ impl<'a> ExprGenerator<'a> {
     }
     
     fn generate_index_expr(&mut self, _span: TokenSpan, expr: &Expr, index_expr: &Expr) {
+        let array_len = match expr.ty.borrow().as_ref() {
+            Some(Ty::Array {len, ..}) => Some(*len),
+            _ => None
+        };
+        if let Some(len) = array_len {
+            let const_index = match &index_expr.kind {
+                ExprKind::Lit {lit, ..} => lit.to_val().to_int(),
+                _ => index_expr.const_val.borrow().as_ref().and_then( | val | val.as_ref().and_then( | val | val.to_int()))
+            };
+            if let Some(index) = const_index {
+                self.generate_expr(expr);
+                write!(self.string, "[{}]", index).unwrap();
+                return
+            }
+            if self.backend_writer.needs_select_for_dynamic_index() {
+                // pick the element with constant indices: (i == 0 ? a[0] : (i == 1 ? a[1] : a[2]))
+                for i in 0..len - 1 {
+                    write!(self.string, "(").unwrap();
+                    self.generate_array_index(index_expr);
+                    write!(self.string, " == {} ? ", i).unwrap();
+                    self.generate_expr(expr);
+                    write!(self.string, "[{}] : ", i).unwrap();
+                }
+                self.generate_expr(expr);
+                write!(self.string, "[{}]", len - 1).unwrap();
+                for _ in 0..len - 1 {
+                    write!(self.string, ")").unwrap();
+                }
+                return
+            }
+            self.generate_expr(expr);
+            write!(self.string, "[").unwrap();
+            self.generate_array_index(index_expr);
+            write!(self.string, "]").unwrap();
+            return
+        }
         self.generate_expr(expr);
         write!(self.string, "[").unwrap();
         self.generate_expr(index_expr);
         write!(self.string, "]").unwrap();
     }
     
+    // arrays can be indexed with floats, which are converted to ints
+    fn generate_array_index(&mut self, index_expr: &Expr) {
+        if let Some(Ty::Float) = index_expr.ty.borrow().as_ref() {
+            self.write_ty_lit(TyLit::Int);
+            write!(self.string, "(").unwrap();
+            self.generate_expr(index_expr);
+            write!(self.string, ")").unwrap();
+        }
+        else {
+            self.generate_expr(index_expr);
+        }
+    }
+    
     
     fn generate_builtin_call_expr(&mut self, _span: TokenSpan, ident: Ident, arg_exprs: &[Expr]) {
         // lets create a fn name for this thing.
@@ -862,10 +1134,14 @@ impl<'a> ExprGenerator<'a> {
         write!(self.string, "{}", DisplayClosureName(closure_site_info.call_ptr, closure_def_index)).unwrap();
         
         write!(self.string, "(").unwrap();
+        let closure_params = match &self.fn_def.unwrap().params[param_index].ty_expr.kind {
+            TyExprKind::ClosureDecl {params, ..} => params,
+            _ => panic!()
+        };
         let mut sep = "";
-        for arg_expr in arg_exprs {
+        for (arg_expr, param) in arg_exprs.iter().zip(closure_params) {
             write!(self.string, "{}", sep).unwrap();
-            self.generate_expr(arg_expr);
+            self.generate_arg_expr(param, arg_expr);
             sep = ", ";
         }
         // alright now we have to pass in the closed over syms IN order
@@ -959,39 +1235,16 @@ pub struct FnDefGenerator<'a> {
 
 impl<'a> FnDefGenerator<'a> {
     pub fn generate_fn_def(&mut self) {
-        
-        self.backend_writer.write_var_decl(
+        let return_ty = self.fn_def.return_ty.borrow();
+        let return_ty = return_ty.as_ref().unwrap();
+        self.backend_writer.write_fn_def_start(
             &mut self.string,
-            "",
-            false,
-            false,
             &DisplayFnName(self.fn_def.fn_ptr, self.fn_def.ident), // here we must expand IdentPath to something
-            self.fn_def.return_ty.borrow().as_ref().unwrap()
+            return_ty
         );
-        write!(self.string, "(").unwrap();
-        let mut sep = "";
-        for param in &self.fn_def.params {
-            if !param.shadow.get().is_none() {
-                if self.backend_writer.write_var_decl(
-                    &mut self.string,
-                    sep,
-                    param.is_inout,
-                    false,
-                    &DisplayVarName(param.ident, param.shadow.get().unwrap()),
-                    param.ty_expr.ty.borrow().as_ref().unwrap(),
-                ) {
-                    sep = ", ";
-                }
-            }
-        }
+        let (param_decls, sep) = write_param_decls(self.backend_writer, self.string, self.fn_def);
         self.backend_writer.write_fn_def_hidden_params(self.string, self.fn_def.hidden_args.borrow().as_ref().unwrap(), sep);
-        write!(self.string, ") ").unwrap();
-        self.generate_block(&self.fn_def.block);
-        writeln!(self.string).unwrap();
-        //self.visited.insert(self.decl.ident_path);
-    }
-    
-    fn generate_block(&mut self, block: &Block) {
+        self.backend_writer.write_fn_def_end(self.string, return_ty);
         BlockGenerator {
             shader_registry: self.shader_registry,
             closure_site_info: None,
@@ -1000,12 +1253,32 @@ impl<'a> FnDefGenerator<'a> {
             backend_writer: self.backend_writer,
             const_table_offset: self.const_table_offset,
             indent_level: 0,
+            param_decls: &param_decls,
             string: self.string,
         }
-        .generate_block(block)
+        .generate_fn_body(&self.fn_def.block, return_ty);
+        writeln!(self.string).unwrap();
+        //self.visited.insert(self.decl.ident_path);
     }
 }
 
+fn write_param_decls(backend_writer: &dyn BackendWriter, string: &mut String, fn_def: &FnDef) -> (Vec<ParamDecl>, &'static str) {
+    let mut param_decls = Vec::new();
+    let mut sep = "";
+    for param in &fn_def.params {
+        if param.shadow.get().is_some() {
+            let ident = DisplayVarName(param.ident, param.shadow.get().unwrap());
+            let ty = param.ty_expr.ty.borrow();
+            let ty = ty.as_ref().unwrap();
+            if backend_writer.write_param_decl(string, sep, param.is_inout, &ident, ty) {
+                param_decls.push(ParamDecl {is_inout: param.is_inout, ident: ident.to_string(), ty: ty.clone()});
+                sep = ", ";
+            }
+        }
+    }
+    (param_decls, sep)
+}
+
 pub struct FnDefWithClosureArgsGenerator<'a> {
     pub closure_site_info: ClosureSiteInfo<'a>,
     pub fn_def: &'a FnDef,
@@ -1024,7 +1297,8 @@ impl<'a> FnDefWithClosureArgsGenerator<'a> {
         fn_def: &FnDef,
         call_def: &FnDef,
         backend_writer: &dyn BackendWriter,
-        const_table_offset: Option<usize>
+        const_table_offset: Option<usize>,
+        call_const_table_offset: Option<usize>
     ) {
         // so first we are collecting the closures in defs that are actually used
         for (closure_def_index, closure_def) in call_def.closure_defs.iter().enumerate() {
@@ -1041,7 +1315,8 @@ impl<'a> FnDefWithClosureArgsGenerator<'a> {
                                 call_def,
                                 shader_registry: shader_registry,
                                 //env:self.env,
-                                const_table_offset,
+                                // the closure body lives in the caller, and so do its constants
+                                const_table_offset: call_const_table_offset,
                                 backend_writer,
                                 string: string,
                             }
@@ -1076,35 +1351,18 @@ impl<'a> FnDefWithClosureArgsGenerator<'a> {
     }
     
     pub fn generate_fn_def_with_closure_args(&mut self) {
-        
-        self.backend_writer.write_var_decl(
+        let return_ty = self.fn_def.return_ty.borrow();
+        let return_ty = return_ty.as_ref().unwrap();
+        self.backend_writer.write_fn_def_start(
             &mut self.string,
-            "",
-            false,
-            false,
             &DisplayFnNameWithClosureArgs(
                 self.closure_site_info.site_index,
                 self.call_def.fn_ptr,
                 self.fn_def.ident
             ), // here we must expand IdentPath to something
-            self.fn_def.return_ty.borrow().as_ref().unwrap()
+            return_ty
         );
-        write!(self.string, "(").unwrap();
-        let mut sep = "";
-        for param in &self.fn_def.params {
-            if !param.shadow.get().is_none() {
-                if self.backend_writer.write_var_decl(
-                    &mut self.string,
-                    sep,
-                    param.is_inout,
-                    false,
-                    &DisplayVarName(param.ident, param.shadow.get().unwrap()),
-                    param.ty_expr.ty.borrow().as_ref().unwrap(),
-                ) {
-                    sep = ", ";
-                }
-            }
-        }
+        let (param_decls, mut sep) = write_param_decls(self.backend_writer, self.string, self.fn_def);
         // now we iterate over the closures in our site,
         // and we need to merge the set of closed over args.
         for sym in &self.closure_site_info.closure_site.all_closed_over {
@@ -1125,17 +1383,9 @@ impl<'a> FnDefWithClosureArgsGenerator<'a> {
         merged_hidden_args.extend(self.call_def.hidden_args.borrow().as_ref().unwrap().iter().cloned());
         self.backend_writer.write_fn_def_hidden_params(self.string, &merged_hidden_args, sep);
         
-        write!(self.string, ") ").unwrap();
+        self.backend_writer.write_fn_def_end(self.string, return_ty);
         // alright so here the block is generated.. however
         // we need to know the names and the closed-over-args passthrough
-        self.generate_block(&self.fn_def.block);
-        
-        
-        writeln!(self.string).unwrap();
-        //self.visited.insert(self.decl.ident_path);
-    }
-    
-    fn generate_block(&mut self, block: &Block) {
         BlockGenerator {
             shader_registry: self.shader_registry,
             closure_site_info: Some(self.closure_site_info.clone()),
@@ -1144,9 +1394,13 @@ impl<'a> FnDefWithClosureArgsGenerator<'a> {
             backend_writer: self.backend_writer,
             const_table_offset: self.const_table_offset,
             indent_level: 0,
+            param_decls: &param_decls,
             string: self.string,
         }
-        .generate_block(block)
+        .generate_fn_body(&self.fn_def.block, return_ty);
+        
+        writeln!(self.string).unwrap();
+        //self.visited.insert(self.decl.ident_path);
     }
 }
 
@@ -1167,49 +1421,39 @@ impl<'a> ClosureDefGenerator<'a> {
         let fn_param = &self.fn_def.params[self.closure_site_arg.param_index];
         
         let mut sep = "";
+        let mut param_decls = Vec::new();
         
-        if let TyExprKind::ClosureDecl {params, return_ty, ..} = &fn_param.ty_expr.kind {
-            
-            self.backend_writer.write_var_decl(
+        let return_ty = if let TyExprKind::ClosureDecl {params, return_ty, ..} = &fn_param.ty_expr.kind {
+            let return_ty = return_ty.borrow().as_ref().unwrap().clone();
+            self.backend_writer.write_fn_def_start(
                 &mut self.string,
-                "",
-                false,
-                false,
                 &DisplayClosureName(self.call_def.fn_ptr, self.closure_site_arg.closure_def_index), // here we must expand IdentPath to something
-                return_ty.borrow().as_ref().unwrap(),
+                &return_ty,
             );
-            write!(self.string, "(").unwrap();
             
             // ok we have now params and names
             for (param_index, param) in params.iter().enumerate() {
                 // lets fetch the name of this thing
                 let closure_param = &self.closure_def.params[param_index];
                 let shadow = closure_param.shadow.get().unwrap();
-                if self.backend_writer.write_var_decl(
-                    &mut self.string,
-                    sep,
-                    param.is_inout,
-                    false,
-                    &DisplayVarName(closure_param.ident, shadow),
-                    param.ty_expr.ty.borrow().as_ref().unwrap(),
-                ) {
+                let ident = DisplayVarName(closure_param.ident, shadow);
+                let ty = param.ty_expr.ty.borrow();
+                let ty = ty.as_ref().unwrap();
+                if self.backend_writer.write_param_decl(self.string, sep, param.is_inout, &ident, ty) {
+                    param_decls.push(ParamDecl {is_inout: param.is_inout, ident: ident.to_string(), ty: ty.clone()});
                     sep = ", ";
                 }
             }
+            return_ty
         }
         else {
             panic!()
-        }
+        };
         
         for sym in self.closure_def.closed_over_syms.borrow().as_ref().unwrap() {
-            if self.backend_writer.write_var_decl(
-                &mut self.string,
-                sep,
-                false,
-                false,
-                &DisplayVarName(sym.ident, sym.shadow),
-                &sym.ty,
-            ) {
+            let ident = DisplayVarName(sym.ident, sym.shadow);
+            if self.backend_writer.write_param_decl(self.string, sep, false, &ident, &sym.ty) {
+                param_decls.push(ParamDecl {is_inout: false, ident: ident.to_string(), ty: sym.ty.clone()});
                 sep = ", ";
             }
         } 
@@ -1219,25 +1463,9 @@ impl<'a> ClosureDefGenerator<'a> {
         merged_hidden_args.extend(self.call_def.hidden_args.borrow().as_ref().unwrap().iter().cloned());
         self.backend_writer.write_fn_def_hidden_params(self.string, &merged_hidden_args, sep);
         
-        writeln!(self.string, ") {{").unwrap();
+        self.backend_writer.write_fn_def_end(self.string, &return_ty);
         
-        match &self.closure_def.kind {
-            ClosureDefKind::Expr(expr) => {
-                write!(self.string, "    return ").unwrap();
-                self.generate_expr(expr);
-                writeln!(self.string, ";").unwrap();
-                writeln!(self.string, "}}").unwrap();
-            }
-            ClosureDefKind::Block(block) => {
-                self.generate_block(block);
-                writeln!(self.string).unwrap();
-            }
-        }
-        //self.visited.insert(self.decl.ident_path);
-    }
-    
-    fn generate_block(&mut self, block: &Block) {
-        BlockGenerator {
+        let mut block_generator = BlockGenerator {
             shader_registry: self.shader_registry,
             closure_site_info: None,
             //env: self.env,
@@ -1245,23 +1473,24 @@ impl<'a> ClosureDefGenerator<'a> {
             backend_writer: self.backend_writer,
             const_table_offset: self.const_table_offset,
             indent_level: 0,
+            param_decls: &param_decls,
             string: self.string,
+        };
+        match &self.closure_def.kind {
+            ClosureDefKind::Expr(expr) => {
+                write!(block_generator.string, "{{\n").unwrap();
+                block_generator.backend_writer.write_fn_def_prologue(block_generator.string, &param_decls);
+                block_generator.indent_level = 1;
+                block_generator.write_indent();
+                block_generator.generate_return_stmt(expr.span, Some(expr));
+                write!(block_generator.string, "\n}}\n").unwrap();
+            }
+            ClosureDefKind::Block(block) => {
+                block_generator.generate_fn_body(block, &return_ty);
+                writeln!(block_generator.string).unwrap();
+            }
         }
-        .generate_block(block)
-    }
-    
-    
-    fn generate_expr(&mut self, expr: &Expr) {
-        ExprGenerator {
-            shader_registry: self.shader_registry,
-            closure_site_info: None,
-            //env: self.env,
-            fn_def: Some(self.fn_def),
-            backend_writer: self.backend_writer,
-            const_table_offset: self.const_table_offset,
-            string: self.string,
-        }
-        .generate_expr(expr)
+        //self.visited.insert(self.decl.ident_path);
     }
 }
 
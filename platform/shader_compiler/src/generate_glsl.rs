@@ -16,6 +16,9 @@ use {
     }
 };
 
+/// The most iterations a `while` or `loop` runs in GLSL, which has no unbounded loops.
+pub const GLSL_MAX_LOOP_ITERATIONS: usize = 1024;
+
 pub fn generate_vertex_shader(draw_shader_def: &DrawShaderDef, const_table: &DrawShaderConstTable, shader_registry: &ShaderRegistry) -> String {
     let mut string = String::new();
     DrawShaderGenerator {
@@ -227,7 +230,8 @@ impl<'a> DrawShaderGenerator<'a> {
                             fn_def,
                             call_def,
                             self.backend_writer,
-                            const_table_offset
+                            const_table_offset,
+                            self.const_table.offsets.get(call_iter).cloned()
                         );
                     }
                 }
@@ -330,12 +334,21 @@ impl<'a> DrawShaderGenerator<'a> {
             for (index, _item) in vec {
                 let field = &self.draw_shader_def.fields[index];
                 
-                write!(self.string, "    {} = ", &DisplayDsIdent(field.ident)).unwrap();
-                
                 let ty_expr = field.ty_expr.ty.borrow();
                 
-                self.write_uniform_ty_unpack(ty_expr.as_ref().unwrap(), &table, slots);
-                write!(self.string, ";\n").unwrap();
+                if let Some(Ty::Array {elem_ty, len}) = ty_expr.as_ref() {
+                    // arrays can't be constructed in GLSL ES 1.00, so unpack them element by element
+                    for i in 0..*len {
+                        write!(self.string, "    {}[{}] = ", &DisplayDsIdent(field.ident), i).unwrap();
+                        self.write_uniform_ty_unpack(elem_ty, &table, slots + i * elem_ty.slots());
+                        write!(self.string, ";\n").unwrap();
+                    }
+                }
+                else {
+                    write!(self.string, "    {} = ", &DisplayDsIdent(field.ident)).unwrap();
+                    self.write_uniform_ty_unpack(ty_expr.as_ref().unwrap(), &table, slots);
+                    write!(self.string, ";\n").unwrap();
+                }
                 slots += ty_expr.as_ref().unwrap().slots();
             }
             write!(self.string, "\n").unwrap();
@@ -451,6 +464,10 @@ impl<'a> DrawShaderGenerator<'a> {
             &DisplayDsIdent(decl.ident),
             decl.ty_expr.ty.borrow().as_ref().unwrap(),
         );
+        if let Some(Ty::Array {..}) = decl.ty_expr.ty.borrow().as_ref() {
+            writeln!(self.string, ";").unwrap();
+            return
+        }
         write!(self.string, " = ").unwrap();
         self.write_ty_init(decl.ty_expr.ty.borrow().as_ref().unwrap());
         writeln!(self.string, ";").unwrap();
@@ -781,6 +798,16 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
         true
     }
     
+    // GLSL ES 1.00 only has for loops with constant bounds, and only constant or loop index
+    // expressions can index arrays that aren't uniforms in vertex shaders
+    fn needs_select_for_dynamic_index(&self) -> bool {
+        true
+    }
+    
+    fn max_loop_iterations(&self) -> Option<usize> {
+        Some(GLSL_MAX_LOOP_ITERATIONS)
+    }
+    
     fn needs_mul_fn_for_matrix_multiplication(&self) -> bool {
         false
     }
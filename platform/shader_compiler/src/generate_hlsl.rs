@@ -95,7 +95,8 @@ impl<'a> DrawShaderGenerator<'a> {
                             fn_def,
                             call_def,
                             self.backend_writer,
-                            const_table_offset
+                            const_table_offset,
+                            self.const_table.offsets.get(call_iter).cloned()
                         );
                     }
                 }
@@ -0,0 +1,759 @@
+use {
+    std::{
+        fmt::Write,
+        fmt,
+        collections::{BTreeMap, BTreeSet}
+    },
+    crate::{
+        makepad_live_id::*,
+        shader_ast::*,
+        generate::*,
+        shader_registry::ShaderRegistry,
+    }
+};
+
+pub struct WgslGeneratedShader{
+    pub wgsl: String,
+    pub fields_as_uniform_blocks:BTreeMap<Ident, Vec<(usize, Ident) >>
+}
+
+/// Generates a WGSL module with a `vertex_main` and a `fragment_main` entry point.
+///
+/// Vertex buffer 0 holds the geometries and buffer 1 the instances, with the geometry attributes
+/// at the first locations. Bind group 0 has the live uniforms at binding 0, the const table at
+/// binding 1 and the uniform blocks from binding 2 on, in the order of `fields_as_uniform_blocks`.
+/// Bind group 1 has a sampler at binding 0 and the textures from binding 1 on, in field order.
+/// Bindings for empty structs and tables are left out.
+pub fn generate_shader(draw_shader_def: &DrawShaderDef, const_table:&DrawShaderConstTable, shader_registry: &ShaderRegistry) -> WgslGeneratedShader {
+    let mut string = String::new();
+    let fields_as_uniform_blocks = draw_shader_def.fields_as_uniform_blocks();
+    DrawShaderGenerator {
+        draw_shader_def,
+        shader_registry,
+        const_table,
+        string: &mut string,
+        fields_as_uniform_blocks: &fields_as_uniform_blocks,
+        backend_writer: &WgslBackendWriter {shader_registry, draw_shader_def, const_table}
+    }
+    .generate_shader();
+    WgslGeneratedShader{
+        wgsl:string,
+        fields_as_uniform_blocks
+    }
+}
+
+struct DrawShaderGenerator<'a> {
+    draw_shader_def: &'a DrawShaderDef,
+    shader_registry: &'a ShaderRegistry,
+    string: &'a mut String,
+    fields_as_uniform_blocks: &'a BTreeMap<Ident, Vec<(usize, Ident) >>,
+    backend_writer: &'a dyn BackendWriter,
+    const_table: &'a DrawShaderConstTable
+}
+
+impl<'a> DrawShaderGenerator<'a> {
+    fn generate_shader(&mut self) {
+        let mut all_constructor_fns = BTreeSet::new();
+        let mut all_builtins = BTreeSet::new();
+
+        for fn_iter in self.draw_shader_def.all_fns.borrow().iter() {
+            let fn_def = self.shader_registry.all_fns.get(fn_iter).unwrap();
+            all_constructor_fns.extend(fn_def.constructor_fn_deps.borrow().as_ref().unwrap().iter().cloned());
+            all_builtins.extend(fn_def.builtin_deps.borrow().as_ref().unwrap().iter().cloned());
+        }
+
+        self.generate_struct_defs();
+        self.generate_uniform_structs();
+        self.generate_geometry_struct();
+        self.generate_instance_struct();
+        self.generate_varying_struct();
+        self.generate_bindings();
+        self.generate_builtin_polyfills(&all_builtins);
+
+        for (ty_lit, ref param_tys) in all_constructor_fns{
+            generate_cons_fn(self.backend_writer, self.string, ty_lit, param_tys);
+        }
+
+        let all_fns = self.draw_shader_def.all_fns.borrow();
+        for fn_iter in all_fns.iter().rev() {
+            let const_table_offset = self.const_table.offsets.get(fn_iter).cloned();
+            let fn_def = self.shader_registry.all_fns.get(fn_iter).unwrap();
+            if fn_def.has_closure_args() {
+                for call_iter in all_fns.iter().rev() {
+                    // any function that depends on us, will have the closures we need
+                    let call_def = self.shader_registry.all_fns.get(call_iter).unwrap();
+                    if call_def.callees.borrow().as_ref().unwrap().contains(fn_iter) {
+                        FnDefWithClosureArgsGenerator::generate_fn_def_with_all_closures(
+                            self.string,
+                            self.shader_registry,
+                            fn_def,
+                            call_def,
+                            self.backend_writer,
+                            const_table_offset,
+                            self.const_table.offsets.get(call_iter).cloned()
+                        );
+                    }
+                }
+                continue
+            }
+            FnDefGenerator {
+                fn_def,
+                const_table_offset,
+                shader_registry: self.shader_registry,
+                backend_writer: self.backend_writer,
+                string: self.string,
+            }
+            .generate_fn_def()
+        }
+        self.generate_vertex_main();
+        self.generate_pixel_main();
+    }
+
+    fn generate_struct_defs(&mut self) {
+        // we have all the structs already from analyse
+        for struct_ptr in self.draw_shader_def.all_structs.borrow().iter().rev() {
+            let struct_def = self.shader_registry.structs.get(struct_ptr).unwrap();
+            writeln!(self.string, "struct {} {{", struct_ptr).unwrap();
+            for field in &struct_def.fields {
+                write!(self.string, "    ").unwrap();
+                self.write_var_decl(&DisplayStructField(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                writeln!(self.string, ",").unwrap();
+            }
+            writeln!(self.string, "}}").unwrap();
+        }
+    }
+
+    fn generate_uniform_structs(&mut self) {
+        // WGSL has no empty structs
+        if !self.draw_shader_def.all_live_refs.borrow().is_empty() {
+            writeln!(self.string, "struct LiveUniforms {{").unwrap();
+            for (value_node_ptr, ty) in self.draw_shader_def.all_live_refs.borrow().iter() {
+                write!(self.string, "    ").unwrap();
+                self.write_var_decl(value_node_ptr, ty);
+                writeln!(self.string, ",").unwrap();
+            }
+            writeln!(self.string, "}}").unwrap();
+        }
+
+        for (ident, vec) in self.fields_as_uniform_blocks {
+            writeln!(self.string, "struct Uniforms_{} {{", ident).unwrap();
+            for (index, _item) in vec {
+                let field = &self.draw_shader_def.fields[*index];
+                write!(self.string, "    ").unwrap();
+                self.write_var_decl(&DisplayDsIdent(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                writeln!(self.string, ",").unwrap();
+            }
+            writeln!(self.string, "}}").unwrap();
+        }
+    }
+
+    fn has_geometries(&self) -> bool {
+        self.draw_shader_def.fields.iter().any( | field | matches!(field.kind, DrawShaderFieldKind::Geometry {..}))
+    }
+
+    fn has_instances(&self) -> bool {
+        self.draw_shader_def.fields.iter().any( | field | matches!(field.kind, DrawShaderFieldKind::Instance {..}))
+    }
+
+    fn generate_geometry_struct(&mut self) {
+        if !self.has_geometries() {
+            return
+        }
+        writeln!(self.string, "struct Geometries {{").unwrap();
+        let mut location = 0;
+        for field in &self.draw_shader_def.fields {
+            if let DrawShaderFieldKind::Geometry {..} = field.kind {
+                write!(self.string, "    @location({}) ", location).unwrap();
+                self.write_var_decl(&DisplayDsIdent(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                writeln!(self.string, ",").unwrap();
+                location += 1;
+            }
+        }
+        writeln!(self.string, "}}").unwrap();
+    }
+
+    fn generate_instance_struct(&mut self) {
+        if !self.has_instances() {
+            return
+        }
+        // the instance attributes come after the geometry attributes
+        let mut location = self.draw_shader_def.fields.iter().filter( | field | matches!(field.kind, DrawShaderFieldKind::Geometry {..})).count();
+        let mut padding = 0;
+        writeln!(self.string, "struct Instances {{").unwrap();
+        for field in &self.draw_shader_def.fields {
+            if let DrawShaderFieldKind::Instance {..} = field.kind {
+                match field.ty_expr.ty.borrow().as_ref().unwrap() {
+                    Ty::Float | Ty::Vec2 | Ty::Vec3 | Ty::Vec4 => {
+                        write!(self.string, "    @location({}) ", location).unwrap();
+                        if field.ident == Ident(LiveId(0)){
+                            self.write_var_decl(&DisplayPadding(padding), field.ty_expr.ty.borrow().as_ref().unwrap());
+                            padding += 1;
+                        }
+                        else{
+                            self.write_var_decl(&DisplayDsIdent(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                        }
+                        writeln!(self.string, ",").unwrap();
+                        location += 1;
+                    },
+                    Ty::Mat4 => {
+                        for i in 0..4 {
+                            writeln!(self.string, "    @location({}) {}{}: vec4<f32>,", location, DisplayDsIdent(field.ident), i).unwrap();
+                            location += 1;
+                        }
+                    },
+                    Ty::Mat3 => {
+                        for i in 0..3 {
+                            writeln!(self.string, "    @location({}) {}{}: vec3<f32>,", location, DisplayDsIdent(field.ident), i).unwrap();
+                            location += 1;
+                        }
+                    },
+                    Ty::Mat2 => {
+                        writeln!(self.string, "    @location({}) {}: vec4<f32>,", location, DisplayDsIdent(field.ident)).unwrap();
+                        location += 1;
+                    },
+                    Ty::Enum(v) =>{
+                        write!(self.string, "    @location({}) ", location).unwrap();
+                        self.write_var_decl(&DisplayDsIdent(field.ident), &Ty::Enum(*v));
+                        writeln!(self.string, ",").unwrap();
+                        location += 1;
+                    }
+                    _ => panic!("unsupported type in generate_instance_struct")
+                }
+            }
+        }
+        writeln!(self.string, "}}").unwrap();
+    }
+
+    fn generate_varying_struct(&mut self) {
+        let mut location = 0;
+        let mut write_varying = | string: &mut String, ident: &dyn fmt::Display, ty: &Ty | {
+            // integers can't be interpolated
+            let interpolate = if let Ty::Int | Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4 | Ty::Enum(_) = ty {"@interpolate(flat) "} else {""};
+            write!(string, "    @location({}) {}", location, interpolate).unwrap();
+            self.backend_writer.write_var_decl(string, "", false, false, ident, ty);
+            writeln!(string, ",").unwrap();
+            location += 1;
+        };
+        writeln!(self.string, "struct Varyings {{").unwrap();
+        writeln!(self.string, "    @builtin(position) position: vec4<f32>,").unwrap();
+        for field in &self.draw_shader_def.fields {
+            match &field.kind {
+                DrawShaderFieldKind::Geometry {is_used_in_pixel_shader, ..} if is_used_in_pixel_shader.get() => {
+                    write_varying(self.string, &DisplayDsIdent(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                }
+                DrawShaderFieldKind::Instance {is_used_in_pixel_shader, ..} if is_used_in_pixel_shader.get() => {
+                    match field.ty_expr.ty.borrow().as_ref().unwrap() {
+                        Ty::Float | Ty::Vec2 | Ty::Vec3 | Ty::Vec4 | Ty::Enum(_) => {
+                            write_varying(self.string, &DisplayDsIdent(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                        },
+                        Ty::Mat4 => {
+                            for i in 0..4 {
+                                write_varying(self.string, &format!("{}{}", DisplayDsIdent(field.ident), i), &Ty::Vec4);
+                            }
+                        },
+                        Ty::Mat3 => {
+                            for i in 0..3 {
+                                write_varying(self.string, &format!("{}{}", DisplayDsIdent(field.ident), i), &Ty::Vec3);
+                            }
+                        },
+                        Ty::Mat2 => {
+                            write_varying(self.string, &DisplayDsIdent(field.ident), &Ty::Vec4);
+                        },
+                        _ => panic!("unsupported type in generate_varying_struct")
+                    }
+                }
+                DrawShaderFieldKind::Varying {..} => {
+                    write_varying(self.string, &DisplayDsIdent(field.ident), field.ty_expr.ty.borrow().as_ref().unwrap());
+                }
+                _ => {}
+            }
+        }
+        writeln!(self.string, "}}").unwrap();
+    }
+
+    fn generate_bindings(&mut self) {
+        // the shader stages see the inputs through module scope variables, so no hidden args are needed
+        if self.has_geometries() {
+            writeln!(self.string, "var<private> geometries: Geometries;").unwrap();
+        }
+        if self.has_instances() {
+            writeln!(self.string, "var<private> instances: Instances;").unwrap();
+        }
+        writeln!(self.string, "var<private> varyings: Varyings;").unwrap();
+
+        if !self.draw_shader_def.all_live_refs.borrow().is_empty() {
+            writeln!(self.string, "@group(0) @binding(0) var<uniform> live_uniforms: LiveUniforms;").unwrap();
+        }
+        if !self.const_table.table.is_empty() {
+            writeln!(self.string, "@group(0) @binding(1) var<uniform> const_table: array<vec4<f32>, {}>;", self.const_table.table.len() >> 2).unwrap();
+        }
+        for (binding, ident) in self.fields_as_uniform_blocks.keys().enumerate() {
+            writeln!(self.string, "@group(0) @binding({}) var<uniform> uniforms_{1}: Uniforms_{1};", binding + 2, ident).unwrap();
+        }
+
+        let mut binding = 1;
+        for field in &self.draw_shader_def.fields {
+            if let DrawShaderFieldKind::Texture {..} = field.kind {
+                assert_ne!(*field.ty_expr.ty.borrow().as_ref().unwrap(), Ty::TextureOES, "TextureOES is only available on Android");
+                assert_eq!(*field.ty_expr.ty.borrow().as_ref().unwrap(), Ty::Texture2D);
+                if binding == 1 {
+                    writeln!(self.string, "@group(1) @binding(0) var default_sampler: sampler;").unwrap();
+                }
+                writeln!(self.string, "@group(1) @binding({}) var {}: texture_2d<f32>;", binding, DisplayDsIdent(field.ident)).unwrap();
+                binding += 1;
+            }
+        }
+    }
+
+    fn generate_builtin_polyfills(&mut self, all_builtins: &BTreeSet<Ident>) {
+        for ident in all_builtins {
+            let builtin = self.shader_registry.builtins.get(ident).unwrap();
+            let mut signatures: Vec<_> = builtin.return_tys.iter().collect();
+            signatures.sort_by_key( | (arg_tys, _) | DisplayPolyfillName(*ident, arg_tys).to_string());
+            for (arg_tys, return_ty) in signatures {
+                if let Some(body) = builtin_polyfill_body(*ident, arg_tys) {
+                    self.backend_writer.write_fn_def_start(self.string, &DisplayPolyfillName(*ident, arg_tys), return_ty);
+                    let mut sep = "";
+                    for (index, arg_ty) in arg_tys.iter().enumerate() {
+                        self.backend_writer.write_var_decl(self.string, sep, false, false, &DisplaConstructorArg(index), arg_ty);
+                        sep = ", ";
+                    }
+                    self.backend_writer.write_fn_def_end(self.string, return_ty);
+                    writeln!(self.string, "{{").unwrap();
+                    writeln!(self.string, "    {}", body).unwrap();
+                    writeln!(self.string, "}}").unwrap();
+                }
+            }
+        }
+    }
+
+    fn generate_vertex_main(&mut self) {
+        writeln!(self.string, "@vertex").unwrap();
+        write!(self.string, "fn vertex_main(").unwrap();
+        let mut sep = "";
+        if self.has_geometries() {
+            write!(self.string, "in_geometries: Geometries").unwrap();
+            sep = ", ";
+        }
+        if self.has_instances() {
+            write!(self.string, "{}in_instances: Instances", sep).unwrap();
+        }
+        writeln!(self.string, ") -> Varyings {{").unwrap();
+        if self.has_geometries() {
+            writeln!(self.string, "    geometries = in_geometries;").unwrap();
+        }
+        if self.has_instances() {
+            writeln!(self.string, "    instances = in_instances;").unwrap();
+        }
+
+        for decl in &self.draw_shader_def.fields {
+            match &decl.kind {
+                DrawShaderFieldKind::Geometry {is_used_in_pixel_shader, ..} if is_used_in_pixel_shader.get() => {
+                    writeln!(self.string, "    varyings.{0} = geometries.{0};", DisplayDsIdent(decl.ident)).unwrap();
+                }
+                DrawShaderFieldKind::Instance {is_used_in_pixel_shader, ..} if is_used_in_pixel_shader.get() => {
+                    match decl.ty_expr.ty.borrow().as_ref().unwrap() {
+                        Ty::Mat4 => {
+                            for i in 0..4 {
+                                writeln!(self.string, "    varyings.{0}{1} = instances.{0}{1};", DisplayDsIdent(decl.ident), i).unwrap();
+                            }
+                        }
+                        Ty::Mat3 => {
+                            for i in 0..3 {
+                                writeln!(self.string, "    varyings.{0}{1} = instances.{0}{1};", DisplayDsIdent(decl.ident), i).unwrap();
+                            }
+                        }
+                        _ => {
+                            writeln!(self.string, "    varyings.{0} = instances.{0};", DisplayDsIdent(decl.ident)).unwrap();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let vertex_def = self.shader_registry.draw_shader_method_decl_from_ident(self.draw_shader_def, Ident(live_id!(vertex))).unwrap();
+        writeln!(self.string, "    varyings.position = {}();", DisplayFnName(vertex_def.fn_ptr, vertex_def.ident)).unwrap();
+        writeln!(self.string, "    return varyings;").unwrap();
+        writeln!(self.string, "}}").unwrap();
+    }
+
+    fn generate_pixel_main(&mut self) {
+        writeln!(self.string, "@fragment").unwrap();
+        writeln!(self.string, "fn fragment_main(in_varyings: Varyings) -> @location(0) vec4<f32> {{").unwrap();
+        writeln!(self.string, "    varyings = in_varyings;").unwrap();
+        let pixel_def = self.shader_registry.draw_shader_method_decl_from_ident(self.draw_shader_def, Ident(live_id!(pixel))).unwrap();
+        writeln!(self.string, "    return {}();", DisplayFnName(pixel_def.fn_ptr, pixel_def.ident)).unwrap();
+        writeln!(self.string, "}}").unwrap();
+    }
+
+    fn write_var_decl(&mut self, ident: &dyn fmt::Display, ty: &Ty) {
+        self.backend_writer.write_var_decl(self.string, "", false, false, ident, ty);
+    }
+}
+
+pub struct DisplayPolyfillName<'a>(pub Ident, pub &'a [Ty]);
+impl<'a> fmt::Display for DisplayPolyfillName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        for ty in self.1 {
+            write!(f, "_{}", ty)?;
+        }
+        Ok(())
+    }
+}
+
+fn wgsl_ty(ty: &Ty) -> String {
+    let mut string = String::new();
+    write_wgsl_ty(&mut string, ty);
+    string
+}
+
+fn write_wgsl_ty(string: &mut String, ty: &Ty) {
+    match ty {
+        Ty::Void => panic!(),
+        Ty::Array {elem_ty, len} => {
+            write!(string, "array<").unwrap();
+            write_wgsl_ty(string, elem_ty);
+            write!(string, ", {}>", len).unwrap();
+        }
+        Ty::Struct(struct_ptr) => write!(string, "{}", struct_ptr).unwrap(),
+        Ty::Enum(_) => write!(string, "u32").unwrap(),
+        Ty::Texture2D => write!(string, "texture_2d<f32>").unwrap(),
+        Ty::TextureOES => panic!("TextureOES is only available on Android"),
+        Ty::DrawShader(_) | Ty::ClosureDecl | Ty::ClosureDef {..} => panic!(),
+        _ => write!(string, "{}", wgsl_ty_lit(ty.maybe_ty_lit().unwrap())).unwrap(),
+    }
+}
+
+fn wgsl_ty_lit(ty_lit: TyLit) -> &'static str {
+    match ty_lit {
+        TyLit::Bool => "bool",
+        TyLit::Int => "i32",
+        TyLit::Float => "f32",
+        TyLit::Bvec2 => "vec2<bool>",
+        TyLit::Bvec3 => "vec3<bool>",
+        TyLit::Bvec4 => "vec4<bool>",
+        TyLit::Ivec2 => "vec2<i32>",
+        TyLit::Ivec3 => "vec3<i32>",
+        TyLit::Ivec4 => "vec4<i32>",
+        TyLit::Vec2 => "vec2<f32>",
+        TyLit::Vec3 => "vec3<f32>",
+        TyLit::Vec4 => "vec4<f32>",
+        TyLit::Mat2 => "mat2x2<f32>",
+        TyLit::Mat3 => "mat3x3<f32>",
+        TyLit::Mat4 => "mat4x4<f32>",
+        TyLit::Texture2D => "texture_2d<f32>",
+        TyLit::TextureOES => panic!("TextureOES is only available on Android"),
+    }
+}
+
+fn is_scalar(ty: &Ty) -> bool {
+    matches!(ty, Ty::Bool | Ty::Int | Ty::Float)
+}
+
+// the body of a function standing in for a GLSL builtin that WGSL lacks, or only has for
+// arguments of the same type
+fn builtin_polyfill_body(ident: Ident, arg_tys: &[Ty]) -> Option<String> {
+    let is_mixed = arg_tys.iter().any(is_scalar) && arg_tys.iter().any( | ty | !is_scalar(ty));
+    let vec_ty = arg_tys.iter().find( | ty | !is_scalar(ty)).map(wgsl_ty);
+    Some(match ident {
+        Ident(live_id!(mod)) => "return x0 - x1 * floor(x0 / x1);".to_string(),
+        Ident(live_id!(min)) if is_mixed => format!("return min(x0, {}(x1));", vec_ty.unwrap()),
+        Ident(live_id!(max)) if is_mixed => format!("return max(x0, {}(x1));", vec_ty.unwrap()),
+        Ident(live_id!(clamp)) if is_mixed => format!("return clamp(x0, {0}(x1), {0}(x2));", vec_ty.unwrap()),
+        Ident(live_id!(step)) if is_mixed => format!("return step({}(x0), x1);", vec_ty.unwrap()),
+        Ident(live_id!(smoothstep)) if is_mixed => format!("return smoothstep({0}(x0), {0}(x1), x2);", vec_ty.unwrap()),
+        Ident(live_id!(lessThan)) => "return x0 < x1;".to_string(),
+        Ident(live_id!(lessThanEqual)) => "return x0 <= x1;".to_string(),
+        Ident(live_id!(greaterThan)) => "return x0 > x1;".to_string(),
+        Ident(live_id!(greaterThanEqual)) => "return x0 >= x1;".to_string(),
+        Ident(live_id!(equal)) => "return x0 == x1;".to_string(),
+        Ident(live_id!(notEqual)) => "return x0 != x1;".to_string(),
+        Ident(live_id!(not)) => "return !x0;".to_string(),
+        Ident(live_id!(matrixCompMult)) => {
+            let size = match arg_tys[0] {Ty::Mat2 => 2, Ty::Mat3 => 3, _ => 4};
+            let columns: Vec<_> = (0..size).map( | i | format!("x0[{0}] * x1[{0}]", i)).collect();
+            format!("return {}({});", wgsl_ty(&arg_tys[0]), columns.join(", "))
+        }
+        Ident(live_id!(inverse)) => INVERSE_MAT4.to_string(),
+        Ident(live_id!(dot)) if is_scalar(&arg_tys[0]) => "return x0 * x1;".to_string(),
+        Ident(live_id!(normalize)) if is_scalar(&arg_tys[0]) => "return sign(x0);".to_string(),
+        Ident(live_id!(reflect)) if is_scalar(&arg_tys[0]) => "return x0 - 2.0 * x1 * x0 * x1;".to_string(),
+        Ident(live_id!(refract)) if is_scalar(&arg_tys[0]) => {
+            "let k = 1.0 - x2 * x2 * (1.0 - x1 * x0 * x1 * x0); return select(x2 * x0 - (x2 * x1 * x0 + sqrt(k)) * x1, 0.0, k < 0.0);".to_string()
+        }
+        Ident(live_id!(faceforward)) if is_scalar(&arg_tys[0]) => "return select(-x0, x0, x2 * x1 < 0.0);".to_string(),
+        Ident(live_id!(sample2d)) | Ident(live_id!(sample2d_rt)) => {
+            "return textureSampleLevel(x0, default_sampler, x1, 0.0);".to_string()
+        }
+        Ident(live_id!(sample2dOES)) => panic!("TextureOES is only available on Android"),
+        _ => return None
+    })
+}
+
+const INVERSE_MAT4: &str = "let a00 = x0[0][0]; let a01 = x0[0][1]; let a02 = x0[0][2]; let a03 = x0[0][3];
+    let a10 = x0[1][0]; let a11 = x0[1][1]; let a12 = x0[1][2]; let a13 = x0[1][3];
+    let a20 = x0[2][0]; let a21 = x0[2][1]; let a22 = x0[2][2]; let a23 = x0[2][3];
+    let a30 = x0[3][0]; let a31 = x0[3][1]; let a32 = x0[3][2]; let a33 = x0[3][3];
+    let b00 = a00 * a11 - a01 * a10; let b01 = a00 * a12 - a02 * a10; let b02 = a00 * a13 - a03 * a10;
+    let b03 = a01 * a12 - a02 * a11; let b04 = a01 * a13 - a03 * a11; let b05 = a02 * a13 - a03 * a12;
+    let b06 = a20 * a31 - a21 * a30; let b07 = a20 * a32 - a22 * a30; let b08 = a20 * a33 - a23 * a30;
+    let b09 = a21 * a32 - a22 * a31; let b10 = a21 * a33 - a23 * a31; let b11 = a22 * a33 - a23 * a32;
+    let det = b00 * b11 - b01 * b10 + b02 * b09 + b03 * b08 - b04 * b07 + b05 * b06;
+    return mat4x4<f32>(
+        a11 * b11 - a12 * b10 + a13 * b09, a02 * b10 - a01 * b11 - a03 * b09, a31 * b05 - a32 * b04 + a33 * b03, a22 * b04 - a21 * b05 - a23 * b03,
+        a12 * b08 - a10 * b11 - a13 * b07, a00 * b11 - a02 * b08 + a03 * b07, a32 * b02 - a30 * b05 - a33 * b01, a20 * b05 - a22 * b02 + a23 * b01,
+        a10 * b10 - a11 * b08 + a13 * b06, a01 * b08 - a00 * b10 - a03 * b06, a30 * b04 - a31 * b02 + a33 * b00, a21 * b02 - a20 * b04 - a23 * b00,
+        a11 * b07 - a10 * b09 - a12 * b06, a00 * b09 - a01 * b07 + a02 * b06, a31 * b01 - a30 * b03 - a32 * b00, a20 * b03 - a21 * b01 + a22 * b00
+    ) * (1.0 / det);";
+
+// the number of scalars in a type as it's named in a constructor fn name
+fn cons_name_ty_slots(ty_name: &str) -> Option<usize> {
+    let size = ty_name.chars().last()?.to_digit(10).unwrap_or(1) as usize;
+    match ty_name.trim_end_matches(char::is_numeric) {
+        "float" | "int" | "bool" => Some(1),
+        "vec" | "ivec" | "bvec" => Some(size),
+        "mat" => Some(size * size),
+        _ => None
+    }
+}
+
+struct WgslBackendWriter<'a> {
+    pub shader_registry: &'a ShaderRegistry,
+    pub draw_shader_def: &'a DrawShaderDef,
+    pub const_table: &'a DrawShaderConstTable,
+}
+
+impl<'a> BackendWriter for WgslBackendWriter<'a> {
+
+    fn get_struct_cons_type(&self) -> StructConsType {
+        StructConsType::Paren
+    }
+
+    fn needs_mul_fn_for_matrix_multiplication(&self) -> bool {
+        false
+    }
+
+    fn needs_unpack_for_matrix_multiplication(&self) -> bool {
+        false
+    }
+
+    fn enum_is_float(&self)->bool{
+        false
+    }
+
+    fn const_table_is_vec4(&self) -> bool {
+        true
+    }
+
+    fn use_cons_fn(&self, what: &str) -> bool {
+        // WGSL constructors take the exact number of components, and a matrix takes either all
+        // scalars or all columns
+        let mut tys = what.split('_').skip(1);
+        let ty = tys.next().unwrap();
+        let param_tys: Vec<_> = tys.collect();
+        let slots = cons_name_ty_slots(ty).unwrap();
+        let param_slots: Vec<_> = param_tys.iter().map( | ty | cons_name_ty_slots(ty).unwrap()).collect();
+        if ty.starts_with("mat") {
+            let column_slots = (slots as f64).sqrt() as usize;
+            !(param_tys.len() == 1 && param_tys[0] == ty
+                || param_slots.iter().all( | slots | *slots == 1) && param_slots.len() == slots
+                || param_slots.iter().all( | slots | *slots == column_slots) && param_slots.len() == column_slots)
+        }
+        else if param_slots.len() == 1 {
+            param_slots[0] != 1 && param_slots[0] != slots
+        }
+        else {
+            param_slots.iter().sum::<usize>() != slots
+        }
+    }
+
+    fn needs_select_for_cond_expr(&self) -> bool {
+        true
+    }
+
+    fn assign_is_stmt(&self) -> bool {
+        true
+    }
+
+    fn needs_phony_assign_for_expr_stmt(&self) -> bool {
+        true
+    }
+
+    fn write_var_decl(
+        &self,
+        string: &mut String,
+        sep: &'static str,
+        is_inout: bool,
+        _is_packed: bool,
+        ident: &dyn fmt::Display,
+        ty: &Ty,
+    ) -> bool {
+        match ty {
+            Ty::DrawShader(_) | Ty::ClosureDef {..} | Ty::ClosureDecl => return false,
+            _ => ()
+        }
+        write!(string, "{}{}: ", sep, ident).unwrap();
+        if is_inout {
+            write!(string, "ptr<function, ").unwrap();
+            write_wgsl_ty(string, ty);
+            write!(string, ">").unwrap();
+        }
+        else {
+            write_wgsl_ty(string, ty);
+        }
+        true
+    }
+
+    fn write_fn_def_start(&self, string: &mut String, ident: &dyn fmt::Display, _return_ty: &Ty) {
+        write!(string, "fn {}(", ident).unwrap();
+    }
+
+    fn write_fn_def_end(&self, string: &mut String, return_ty: &Ty) {
+        if let Ty::Void = return_ty {
+            write!(string, ") ").unwrap();
+        }
+        else {
+            write!(string, ") -> {} ", wgsl_ty(return_ty)).unwrap();
+        }
+    }
+
+    fn write_param_decl(&self, string: &mut String, sep: &'static str, is_inout: bool, ident: &dyn fmt::Display, ty: &Ty) -> bool {
+        // parameters are immutable, so they come in under another name and get copied to a var.
+        // textures can't be copied, and can't be assigned to either.
+        if let Ty::Texture2D = ty {
+            return self.write_var_decl(string, sep, is_inout, false, ident, ty)
+        }
+        self.write_var_decl(string, sep, is_inout, false, &format!("p_{}", ident), ty)
+    }
+
+    fn write_let_decl(&self, string: &mut String, ident: &dyn fmt::Display, ty: &Ty) {
+        write!(string, "var ").unwrap();
+        self.write_var_decl(string, "", false, false, ident, ty);
+    }
+
+    fn write_fn_def_prologue(&self, string: &mut String, params: &[ParamDecl]) {
+        for param in params {
+            if let Ty::Texture2D = param.ty {
+                continue
+            }
+            if param.is_inout {
+                writeln!(string, "    var {0} = *p_{0};", param.ident).unwrap();
+            }
+            else {
+                writeln!(string, "    var {0} = p_{0};", param.ident).unwrap();
+            }
+        }
+    }
+
+    fn write_fn_def_epilogue(&self, string: &mut String, params: &[ParamDecl]) {
+        for param in params {
+            if param.is_inout {
+                write!(string, "*p_{0} = {0}; ", param.ident).unwrap();
+            }
+        }
+    }
+
+    fn write_inout_arg_prefix(&self, string: &mut String) {
+        write!(string, "&").unwrap();
+    }
+
+    fn write_call_expr_hidden_args(&self, _string: &mut String, _hidden_args: &BTreeSet<HiddenArgKind >, _sep: &str) {
+    }
+
+    fn write_fn_def_hidden_params(&self, _string: &mut String, _hidden_args: &BTreeSet<HiddenArgKind >, _sep: &str) {
+    }
+
+    fn generate_live_value_prefix(&self, string: &mut String) {
+        write!(string, "live_uniforms.").unwrap();
+    }
+
+    fn generate_draw_shader_field_expr(&self, string: &mut String, field_ident: Ident, ty: &Ty) {
+        let field_def = self.draw_shader_def.find_field(field_ident).unwrap();
+
+        match &field_def.kind {
+            DrawShaderFieldKind::Geometry {is_used_in_pixel_shader, ..} => {
+                if is_used_in_pixel_shader.get() {
+                    write!(string, "varyings.").unwrap()
+                }
+                else {
+                    write!(string, "geometries.").unwrap()
+                }
+            }
+            DrawShaderFieldKind::Instance {is_used_in_pixel_shader, ..} => {
+                let prefix = if is_used_in_pixel_shader.get() {
+                    "varyings"
+                }
+                else {
+                    "instances"
+                };
+
+                // matrices are passed in as their rows
+                match ty {
+                    Ty::Mat4 => {
+                        write!(string, "transpose(mat4x4<f32>({0}.{1}0, {0}.{1}1, {0}.{1}2, {0}.{1}3))", prefix, DisplayDsIdent(field_ident)).unwrap();
+                        return
+                    },
+                    Ty::Mat3 => {
+                        write!(string, "transpose(mat3x3<f32>({0}.{1}0, {0}.{1}1, {0}.{1}2))", prefix, DisplayDsIdent(field_ident)).unwrap();
+                        return
+                    },
+                    Ty::Mat2 => {
+                        write!(string, "mat2x2<f32>({0}.{1}.x, {0}.{1}.y, {0}.{1}.z, {0}.{1}.w)", prefix, DisplayDsIdent(field_ident)).unwrap();
+                        return
+                    },
+                    _ => {
+                        write!(string, "{}.", prefix).unwrap();
+                    }
+                }
+            }
+            DrawShaderFieldKind::Varying {..} => {
+                write!(string, "varyings.").unwrap()
+            }
+            DrawShaderFieldKind::Texture {..} => {
+            }
+            DrawShaderFieldKind::Uniform {block_ident, ..} => {
+                write!(string, "uniforms_{}.", block_ident).unwrap()
+            }
+        }
+        write!(string, "{}", &DisplayDsIdent(field_ident)).unwrap();
+    }
+
+    fn write_ty_lit(&self, string: &mut String, ty_lit: TyLit) {
+        write!(string, "{}", wgsl_ty_lit(ty_lit)).unwrap();
+    }
+
+    fn write_builtin_call_ident(&self, string: &mut String, ident: Ident, arg_exprs: &[Expr]) {
+        let arg_tys: Vec<_> = arg_exprs.iter().map( | arg_expr | arg_expr.ty.borrow().clone().unwrap()).collect();
+        if builtin_polyfill_body(ident, &arg_tys).is_some() {
+            write!(string, "{}", DisplayPolyfillName(ident, &arg_tys)).unwrap();
+            return
+        }
+        match ident {
+            Ident(live_id!(atan)) => {
+                if arg_exprs.len() == 2 {
+                    write!(string, "atan2").unwrap();
+                }
+                else {
+                    write!(string, "atan").unwrap();
+                }
+            }
+            Ident(live_id!(dFdx)) => {
+                write!(string, "dpdx").unwrap();
+            }
+            Ident(live_id!(dFdy)) => {
+                write!(string, "dpdy").unwrap();
+            }
+            Ident(live_id!(inversesqrt)) => {
+                write!(string, "inverseSqrt").unwrap();
+            }
+            Ident(live_id!(faceforward)) => {
+                write!(string, "faceForward").unwrap();
+            }
+            _ => {
+                write!(string, "{}", ident).unwrap()
+            }
+        }
+    }
+}
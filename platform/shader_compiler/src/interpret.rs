@@ -0,0 +1,973 @@
+// A CPU interpreter for the type checked shader AST, so draw shaders can be
+// evaluated without a GPU. It follows the GLSL semantics the backends generate:
+// component-wise ops with scalar broadcast, column-major matrices and the
+// fine derivatives of a 2x2 pixel quad.
+use {
+    std::{
+        collections::{BTreeMap, HashMap},
+        rc::Rc,
+    },
+    crate::{
+        makepad_live_compiler::{live_error_origin, LiveError, LiveErrorOrigin, TokenSpan},
+        makepad_live_id::*,
+        makepad_math::*,
+        shader_ast::*,
+        shader_registry::ShaderRegistry,
+        swizzle::Swizzle,
+        generate_glsl::GLSL_MAX_LOOP_ITERATIONS,
+    }
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderValue {
+    Void,
+    // bools, ints, floats, vectors, matrices and enums, matrices column-major
+    Num(Ty, [f32; 16]),
+    Struct(Vec<ShaderValue>),
+    Array(Vec<ShaderValue>),
+    // the index of the texture in the draw shader
+    Texture(usize),
+}
+
+impl ShaderValue {
+    pub fn from_slots(ty: Ty, slots: &[f32]) -> Self {
+        if let Ty::Array {elem_ty, ..} = &ty {
+            return ShaderValue::Array(slots.chunks(elem_ty.slots()).map( | slots | {
+                Self::from_slots((**elem_ty).clone(), slots)
+            }).collect())
+        }
+        let mut values = [0.0; 16];
+        values[..slots.len()].copy_from_slice(slots);
+        ShaderValue::Num(ty, values)
+    }
+
+    pub fn bool(v: bool) -> Self {Self::from_slots(Ty::Bool, &[if v {1.0} else {0.0}])}
+    pub fn int(v: i32) -> Self {Self::from_slots(Ty::Int, &[v as f32])}
+    pub fn float(v: f32) -> Self {Self::from_slots(Ty::Float, &[v])}
+    pub fn vec2(v: Vec2) -> Self {Self::from_slots(Ty::Vec2, &[v.x, v.y])}
+    pub fn vec4(v: Vec4) -> Self {Self::from_slots(Ty::Vec4, &[v.x, v.y, v.z, v.w])}
+
+    pub fn zero(shader_registry: &ShaderRegistry, ty: &Ty) -> Self {
+        match ty {
+            Ty::Struct(struct_ptr) => ShaderValue::Struct(
+                shader_registry.structs.get(struct_ptr).unwrap().fields.iter().map( | field | {
+                    Self::zero(shader_registry, field.ty_expr.ty.borrow().as_ref().unwrap())
+                }).collect()
+            ),
+            Ty::Array {elem_ty, len} => ShaderValue::Array(vec![Self::zero(shader_registry, elem_ty); *len]),
+            Ty::Void
+                | Ty::Texture2D
+                | Ty::TextureOES
+                | Ty::DrawShader(_)
+                | Ty::ClosureDecl
+                | Ty::ClosureDef(_) => ShaderValue::Void,
+            _ => ShaderValue::Num(ty.clone(), [0.0; 16])
+        }
+    }
+
+    fn map(ty: &Ty, f: impl Fn(usize) -> f32) -> Self {
+        let mut values = [0.0; 16];
+        for (index, value) in values.iter_mut().enumerate().take(ty.slots()) {
+            *value = convert_scalar(ty, f(index));
+        }
+        ShaderValue::Num(ty.clone(), values)
+    }
+
+    pub fn ty(&self) -> Option<&Ty> {
+        if let ShaderValue::Num(ty, _) = self {Some(ty)} else {None}
+    }
+
+    pub fn slots(&self) -> &[f32] {
+        match self {
+            ShaderValue::Num(ty, values) => &values[..ty.slots()],
+            _ => &[]
+        }
+    }
+
+    // a component, where scalars broadcast to every component
+    fn comp(&self, index: usize) -> f32 {
+        let slots = self.slots();
+        if slots.len() == 1 {slots[0]} else {slots[index]}
+    }
+
+    pub fn to_f32(&self) -> f32 {
+        self.slots().first().cloned().unwrap_or(0.0)
+    }
+
+    pub fn to_bool(&self) -> bool {
+        self.to_f32() != 0.0
+    }
+
+    pub fn to_vec2(&self) -> Vec2 {
+        vec2(self.comp(0), self.comp(1))
+    }
+
+    pub fn to_vec4(&self) -> Vec4 {
+        vec4(self.comp(0), self.comp(1), self.comp(2), self.comp(3))
+    }
+}
+
+// values stored in bool and int types are kept exact
+fn convert_scalar(ty: &Ty, v: f32) -> f32 {
+    match ty {
+        Ty::Bool | Ty::Bvec2 | Ty::Bvec3 | Ty::Bvec4 => if v != 0.0 {1.0} else {0.0},
+        Ty::Int | Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4 => v.trunc(),
+        _ => v
+    }
+}
+
+fn is_int(ty: &Ty) -> bool {
+    matches!(ty, Ty::Int | Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4)
+}
+
+fn mat_dim(ty: &Ty) -> usize {
+    match ty {
+        Ty::Mat2 => 2,
+        Ty::Mat3 => 3,
+        Ty::Mat4 => 4,
+        _ => 0
+    }
+}
+
+/// An RGBA texture sampled with nearest filtering and clamp to edge, like the
+/// samplers the backends set up.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderTexture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec4>,
+}
+
+impl ShaderTexture {
+    pub fn sample(&self, pos: Vec2) -> Vec4 {
+        if self.width == 0 || self.height == 0 {
+            return Vec4::default()
+        }
+        let x = ((pos.x * self.width as f32).floor().max(0.0) as usize).min(self.width - 1);
+        let y = ((pos.y * self.height as f32).floor().max(0.0) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// The values a shader invocation reads through `self` and from live values.
+/// Varyings written by the vertex stage end up in `fields` as well.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderInputs {
+    pub fields: HashMap<LiveId, ShaderValue>,
+    pub live_values: BTreeMap<ValuePtr, ShaderValue>,
+}
+
+pub struct ShaderInterpreter<'a> {
+    pub shader_registry: &'a ShaderRegistry,
+    pub draw_shader_def: &'a DrawShaderDef,
+    // indexed like the texture fields of the draw shader
+    pub textures: Vec<Option<ShaderTexture >>,
+}
+
+impl<'a> ShaderInterpreter<'a> {
+    pub fn new(shader_registry: &'a ShaderRegistry, draw_shader_def: &'a DrawShaderDef) -> Self {
+        Self {
+            shader_registry,
+            draw_shader_def,
+            textures: Vec::new()
+        }
+    }
+
+    /// Runs the vertex function, returning the clip space position. The
+    /// fields it writes (the varyings) are stored back into `inputs`. Fails if
+    /// the shader calls a builtin the interpreter doesn't support.
+    pub fn vertex(&self, inputs: &mut ShaderInputs) -> Result<Vec4, LiveError> {
+        let mut invocation = Invocation {
+            interpreter: self,
+            inputs: std::mem::take(inputs),
+            pixel: None,
+            probe: None,
+            derivatives: 0
+        };
+        let position = invocation.run(live_id!(vertex));
+        *inputs = invocation.inputs;
+        Ok(position.or_else(Unwind::into_result) ?.to_vec4())
+    }
+
+    /// Runs the pixel function for the pixel at `x`, `y`. The neighbouring
+    /// pixels of its 2x2 quad are evaluated through `inputs_at` when the shader
+    /// takes derivatives.
+    pub fn pixel(&self, x: usize, y: usize, inputs_at: &dyn Fn(usize, usize) -> ShaderInputs) -> Result<Vec4, LiveError> {
+        let mut invocation = Invocation {
+            interpreter: self,
+            inputs: inputs_at(x, y),
+            pixel: Some(PixelPos {x, y, inputs_at}),
+            probe: None,
+            derivatives: 0
+        };
+        Ok(invocation.run(live_id!(pixel)).or_else(Unwind::into_result) ?.to_vec4())
+    }
+
+    // runs the pixel function up to derivative call `index` and returns its argument
+    fn probe(&self, pos: PixelPos, index: usize) -> Result<Option<ShaderValue>, LiveError> {
+        let mut invocation = Invocation {
+            interpreter: self,
+            inputs: (pos.inputs_at)(pos.x, pos.y),
+            pixel: Some(pos),
+            probe: Some(index),
+            derivatives: 0
+        };
+        match invocation.run(live_id!(pixel)) {
+            Err(Unwind::Probed(value)) => Ok(Some(value)),
+            Err(Unwind::Error(error)) => Err(error),
+            Ok(_) => Ok(None)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PixelPos<'b> {
+    x: usize,
+    y: usize,
+    inputs_at: &'b dyn Fn(usize, usize) -> ShaderInputs,
+}
+
+// why an invocation stops early: a probing invocation reached the derivative
+// it looks for, or the shader does something the interpreter doesn't support
+enum Unwind {
+    Probed(ShaderValue),
+    Error(LiveError),
+}
+
+impl Unwind {
+    // probes only unwind from the invocations started by `probe`
+    fn into_result(self) -> Result<ShaderValue, LiveError> {
+        match self {
+            Unwind::Probed(_) => Ok(ShaderValue::Void),
+            Unwind::Error(error) => Err(error)
+        }
+    }
+}
+
+type Locals = HashMap<(Ident, ScopeSymShadow), ShaderValue>;
+
+struct Closure<'a> {
+    fn_def: &'a FnDef,
+    closure_def: &'a ClosureDef,
+    locals: Locals,
+    closures: HashMap<usize, Rc<Closure<'a >>>,
+}
+
+struct Frame<'a> {
+    fn_def: &'a FnDef,
+    locals: Locals,
+    closures: HashMap<usize, Rc<Closure<'a >>>,
+}
+
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(ShaderValue),
+}
+
+struct Invocation<'a, 'b> {
+    interpreter: &'b ShaderInterpreter<'a>,
+    inputs: ShaderInputs,
+    pixel: Option<PixelPos<'b >>,
+    probe: Option<usize>,
+    derivatives: usize,
+}
+
+impl<'a, 'b> Invocation<'a, 'b> {
+    fn run(&mut self, entry: LiveId) -> Result<ShaderValue, Unwind> {
+        let shader_registry = self.interpreter.shader_registry;
+        let fn_def = shader_registry.draw_shader_method_decl_from_ident(self.interpreter.draw_shader_def, Ident(entry)).unwrap();
+        let args = vec![ShaderValue::Void; fn_def.params.len()];
+        Ok(self.call_fn(fn_def, args, HashMap::new()) ?.0)
+    }
+
+    fn ty(expr: &Expr) -> Ty {
+        expr.ty.borrow().clone().unwrap_or(Ty::Void)
+    }
+
+    // returns the return value and the final values of the params, for inout
+    fn call_fn(
+        &mut self,
+        fn_def: &'a FnDef,
+        args: Vec<ShaderValue>,
+        closures: HashMap<usize, Rc<Closure<'a >>>
+    ) -> Result<(ShaderValue, Vec<ShaderValue>), Unwind> {
+        let mut frame = Frame {
+            fn_def,
+            locals: Locals::new(),
+            closures
+        };
+        for (param, arg) in fn_def.params.iter().zip(args) {
+            if let Some(shadow) = param.shadow.get() {
+                frame.locals.insert((param.ident, shadow), arg);
+            }
+        }
+        let ret = match self.exec_block(&mut frame, &fn_def.block) ? {
+            Flow::Return(value) => value,
+            _ => ShaderValue::Void
+        };
+        let params = fn_def.params.iter().map( | param | {
+            param.shadow.get().and_then( | shadow | frame.locals.get(&(param.ident, shadow)).cloned()).unwrap_or(ShaderValue::Void)
+        }).collect();
+        Ok((ret, params))
+    }
+
+    fn exec_block(&mut self, frame: &mut Frame<'a>, block: &'a Block) -> Result<Flow, Unwind> {
+        for stmt in &block.stmts {
+            match self.exec_stmt(frame, stmt) ? {
+                Flow::Next => (),
+                flow => return Ok(flow)
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    // bounded like the loops of the GLSL backend, so a shader that never breaks can't hang
+    fn exec_loop(&mut self, frame: &mut Frame<'a>, expr: Option<&'a Expr>, block: &'a Block) -> Result<Flow, Unwind> {
+        for _ in 0..GLSL_MAX_LOOP_ITERATIONS {
+            if let Some(expr) = expr {
+                if !self.eval_expr(frame, expr) ?.to_bool() {
+                    break
+                }
+            }
+            match self.exec_block(frame, block) ? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                _ => ()
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec_stmt(&mut self, frame: &mut Frame<'a>, stmt: &'a Stmt) -> Result<Flow, Unwind> {
+        match stmt {
+            Stmt::Break {..} => Ok(Flow::Break),
+            Stmt::Continue {..} => Ok(Flow::Continue),
+            Stmt::For {ident, from_expr, to_expr, step_expr, block, shadow, ..} => {
+                let from = self.eval_expr(frame, from_expr) ?.to_f32() as i32;
+                let to = self.eval_expr(frame, to_expr) ?.to_f32() as i32;
+                let step = if let Some(step_expr) = step_expr {
+                    self.eval_expr(frame, step_expr) ?.to_f32() as i32
+                } else if from < to {1} else {-1};
+                // same bounds as the generated loops
+                let mut index = if from <= to {from} else {from - 1};
+                while step != 0 && if from <= to {index < to} else {index >= to} {
+                    frame.locals.insert((*ident, shadow.get().unwrap()), ShaderValue::int(index));
+                    match self.exec_block(frame, block) ? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        _ => ()
+                    }
+                    index += step;
+                }
+                Ok(Flow::Next)
+            }
+            Stmt::While {expr, block, ..} => self.exec_loop(frame, Some(expr), block),
+            Stmt::Loop {block, ..} => self.exec_loop(frame, None, block),
+            Stmt::If {expr, block_if_true, block_if_false, ..} => {
+                if self.eval_expr(frame, expr) ?.to_bool() {
+                    self.exec_block(frame, block_if_true)
+                }
+                else if let Some(block_if_false) = block_if_false {
+                    self.exec_block(frame, block_if_false)
+                }
+                else {
+                    Ok(Flow::Next)
+                }
+            }
+            Stmt::Match {expr, matches, ..} => {
+                let value = self.eval_expr(frame, expr) ?.to_f32();
+                for match_item in matches {
+                    if (value - match_item.enum_value.get().unwrap() as f32).abs() < 0.5 {
+                        return self.exec_block(frame, &match_item.block)
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            Stmt::Let {ty, shadow, ident, expr, ..} => {
+                let value = if let Some(expr) = expr {
+                    self.eval_expr(frame, expr) ?
+                }
+                else {
+                    ShaderValue::zero(self.interpreter.shader_registry, ty.borrow().as_ref().unwrap())
+                };
+                frame.locals.insert((*ident, shadow.get().unwrap()), value);
+                Ok(Flow::Next)
+            }
+            Stmt::Return {expr, ..} => {
+                let value = if let Some(expr) = expr {
+                    self.eval_expr(frame, expr) ?
+                }
+                else {
+                    ShaderValue::Void
+                };
+                Ok(Flow::Return(value))
+            }
+            Stmt::Block {block, ..} => self.exec_block(frame, block),
+            Stmt::Expr {expr, ..} => {
+                self.eval_expr(frame, expr) ?;
+                Ok(Flow::Next)
+            }
+        }
+    }
+
+    fn eval_expr(&mut self, frame: &mut Frame<'a>, expr: &'a Expr) -> Result<ShaderValue, Unwind> {
+        let ty = Self::ty(expr);
+        match &expr.kind {
+            ExprKind::Cond {expr, expr_if_true, expr_if_false, ..} => {
+                if self.eval_expr(frame, expr) ?.to_bool() {
+                    self.eval_expr(frame, expr_if_true)
+                }
+                else {
+                    self.eval_expr(frame, expr_if_false)
+                }
+            }
+            ExprKind::Bin {op, left_expr, right_expr, ..} => self.eval_bin_expr(frame, *op, left_expr, right_expr, &ty),
+            ExprKind::Un {op, expr, ..} => {
+                let value = self.eval_expr(frame, expr) ?;
+                Ok(match op {
+                    UnOp::Not => ShaderValue::map(&ty, | index | if value.comp(index) != 0.0 {0.0} else {1.0}),
+                    UnOp::Neg => ShaderValue::map(&ty, | index | -value.comp(index)),
+                })
+            }
+            ExprKind::Field {expr, field_ident, ..} => {
+                if let Some(Ty::DrawShader(_)) = expr.ty.borrow().as_ref() {
+                    return Ok(self.read_draw_shader_field(*field_ident, &ty))
+                }
+                let value = self.eval_expr(frame, expr) ?;
+                Ok(self.read_field(&Self::ty(expr), value, *field_ident, &ty))
+            }
+            ExprKind::Index {expr, index_expr, ..} => {
+                let value = self.eval_expr(frame, expr) ?;
+                let index = self.eval_expr(frame, index_expr) ?.to_f32() as usize;
+                Ok(read_index(value, index, &ty))
+            }
+            ExprKind::MethodCall {ident, arg_exprs, ..} => {
+                let shader_registry = self.interpreter.shader_registry;
+                match arg_exprs[0].ty.borrow().as_ref().unwrap() {
+                    Ty::Struct(struct_ptr) => {
+                        let fn_def = shader_registry.struct_method_decl_from_ident(
+                            shader_registry.structs.get(struct_ptr).unwrap(),
+                            *ident
+                        ).unwrap();
+                        self.eval_call(frame, fn_def, arg_exprs)
+                    }
+                    Ty::DrawShader(shader_ptr) => {
+                        let fn_def = shader_registry.draw_shader_method_decl_from_ident(
+                            shader_registry.draw_shader_defs.get(shader_ptr).unwrap(),
+                            *ident
+                        ).unwrap();
+                        self.eval_call(frame, fn_def, &arg_exprs[1..])
+                    }
+                    _ => panic!(),
+                }
+            }
+            ExprKind::PlainCall {fn_ptr, param_index, arg_exprs, ..} => {
+                if let Some(param_index) = param_index.get() {
+                    return self.eval_closure_call(frame, param_index, arg_exprs)
+                }
+                let fn_def = self.interpreter.shader_registry.all_fns.get(&fn_ptr.unwrap()).unwrap();
+                self.eval_call(frame, fn_def, arg_exprs)
+            }
+            ExprKind::BuiltinCall {ident, arg_exprs, ..} => self.eval_builtin_call(frame, expr.span, *ident, arg_exprs, &ty),
+            ExprKind::ClosureDef(_) => Ok(ShaderValue::Void),
+            ExprKind::ConsCall {ty_lit, arg_exprs, ..} => {
+                let mut args = Vec::new();
+                for arg_expr in arg_exprs {
+                    args.push(self.eval_expr(frame, arg_expr) ?);
+                }
+                Ok(construct(&ty_lit.to_ty(), &args))
+            }
+            ExprKind::StructCons {struct_ptr, args, ..} => {
+                let struct_def = self.interpreter.shader_registry.structs.get(struct_ptr).unwrap();
+                let mut fields = Vec::new();
+                for field in &struct_def.fields {
+                    let (_, arg_expr) = args.iter().find( | (ident, _) | field.ident == *ident).unwrap();
+                    fields.push(self.eval_expr(frame, arg_expr) ?);
+                }
+                Ok(ShaderValue::Struct(fields))
+            }
+            ExprKind::Var {kind, ..} => Ok(match kind.get() {
+                Some(VarKind::Local {ident, shadow}) | Some(VarKind::MutLocal {ident, shadow}) => {
+                    frame.locals.get(&(ident, shadow)).cloned().unwrap_or(ShaderValue::Void)
+                }
+                Some(VarKind::LiveValue(value_ptr)) => match self.inputs.live_values.get(&value_ptr) {
+                    Some(value) => value.clone(),
+                    None => ShaderValue::zero(self.interpreter.shader_registry, &ty)
+                },
+                None => ShaderValue::Void
+            }),
+            ExprKind::Lit {lit, ..} => Ok(match lit {
+                Lit::Bool(v) => ShaderValue::bool(*v),
+                Lit::Int(v) => ShaderValue::int(*v),
+                Lit::Float(v) => ShaderValue::float(*v),
+                Lit::Color(v) => ShaderValue::vec4(Vec4::from_u32(*v)),
+            }),
+        }
+    }
+
+    fn eval_bin_expr(
+        &mut self,
+        frame: &mut Frame<'a>,
+        op: BinOp,
+        left_expr: &'a Expr,
+        right_expr: &'a Expr,
+        ty: &Ty
+    ) -> Result<ShaderValue, Unwind> {
+        let op = match op {
+            BinOp::Assign => {
+                let value = self.eval_expr(frame, right_expr) ?;
+                self.assign(frame, left_expr, value) ?;
+                return Ok(ShaderValue::Void)
+            }
+            BinOp::AddAssign | BinOp::SubAssign | BinOp::MulAssign | BinOp::DivAssign => {
+                let left = self.eval_expr(frame, left_expr) ?;
+                let right = self.eval_expr(frame, right_expr) ?;
+                let op = match op {
+                    BinOp::AddAssign => BinOp::Add,
+                    BinOp::SubAssign => BinOp::Sub,
+                    BinOp::MulAssign => BinOp::Mul,
+                    _ => BinOp::Div
+                };
+                let value = arith(op, &left, &right, &Self::ty(left_expr));
+                self.assign(frame, left_expr, value) ?;
+                return Ok(ShaderValue::Void)
+            }
+            BinOp::Or => {
+                let value = self.eval_expr(frame, left_expr) ?.to_bool() || self.eval_expr(frame, right_expr) ?.to_bool();
+                return Ok(ShaderValue::bool(value))
+            }
+            BinOp::And => {
+                let value = self.eval_expr(frame, left_expr) ?.to_bool() && self.eval_expr(frame, right_expr) ?.to_bool();
+                return Ok(ShaderValue::bool(value))
+            }
+            op => op
+        };
+        let left = self.eval_expr(frame, left_expr) ?;
+        let right = self.eval_expr(frame, right_expr) ?;
+        Ok(match op {
+            BinOp::Eq => ShaderValue::bool(left.slots() == right.slots()),
+            BinOp::Ne => ShaderValue::bool(left.slots() != right.slots()),
+            BinOp::Lt => ShaderValue::bool(left.to_f32() < right.to_f32()),
+            BinOp::Le => ShaderValue::bool(left.to_f32() <= right.to_f32()),
+            BinOp::Gt => ShaderValue::bool(left.to_f32() > right.to_f32()),
+            BinOp::Ge => ShaderValue::bool(left.to_f32() >= right.to_f32()),
+            _ => arith(op, &left, &right, ty)
+        })
+    }
+
+    fn eval_call(&mut self, frame: &mut Frame<'a>, fn_def: &'a FnDef, arg_exprs: &'a [Expr]) -> Result<ShaderValue, Unwind> {
+        // a draw shader self param has no argument expression
+        let param_offset = fn_def.params.len() - arg_exprs.len();
+        let mut args = vec![ShaderValue::Void; param_offset];
+        let mut closures = HashMap::new();
+        for (index, arg_expr) in arg_exprs.iter().enumerate() {
+            if let ExprKind::ClosureDef(closure_def_index) = arg_expr.kind {
+                closures.insert(param_offset + index, Rc::new(Closure {
+                    fn_def: frame.fn_def,
+                    closure_def: &frame.fn_def.closure_defs[closure_def_index.0],
+                    locals: frame.locals.clone(),
+                    closures: frame.closures.clone(),
+                }));
+                args.push(ShaderValue::Void);
+            }
+            else {
+                args.push(self.eval_expr(frame, arg_expr) ?);
+            }
+        }
+        let (ret, params) = self.call_fn(fn_def, args, closures) ?;
+        for (index, arg_expr) in arg_exprs.iter().enumerate() {
+            if fn_def.params[param_offset + index].is_inout {
+                self.assign(frame, arg_expr, params[param_offset + index].clone()) ?;
+            }
+        }
+        Ok(ret)
+    }
+
+    fn eval_closure_call(&mut self, frame: &mut Frame<'a>, param_index: usize, arg_exprs: &'a [Expr]) -> Result<ShaderValue, Unwind> {
+        let closure = frame.closures.get(&param_index).unwrap().clone();
+        let mut closure_frame = Frame {
+            fn_def: closure.fn_def,
+            locals: closure.locals.clone(),
+            closures: closure.closures.clone(),
+        };
+        for (param, arg_expr) in closure.closure_def.params.iter().zip(arg_exprs) {
+            let value = self.eval_expr(frame, arg_expr) ?;
+            closure_frame.locals.insert((param.ident, param.shadow.get().unwrap()), value);
+        }
+        let ret = match &closure.closure_def.kind {
+            ClosureDefKind::Expr(expr) => self.eval_expr(&mut closure_frame, expr) ?,
+            ClosureDefKind::Block(block) => match self.exec_block(&mut closure_frame, block) ? {
+                Flow::Return(value) => value,
+                _ => ShaderValue::Void
+            }
+        };
+        if let TyExprKind::ClosureDecl {params, ..} = &frame.fn_def.params[param_index].ty_expr.kind {
+            for ((param, closure_param), arg_expr) in params.iter().zip(&closure.closure_def.params).zip(arg_exprs) {
+                if param.is_inout {
+                    let value = closure_frame.locals[&(closure_param.ident, closure_param.shadow.get().unwrap())].clone();
+                    self.assign(frame, arg_expr, value) ?;
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    fn read_draw_shader_field(&self, field_ident: Ident, ty: &Ty) -> ShaderValue {
+        let draw_shader_def = self.interpreter.draw_shader_def;
+        let texture_index = draw_shader_def.fields.iter()
+            .filter( | field | matches!(field.kind, DrawShaderFieldKind::Texture {..}))
+            .position( | field | field.ident == field_ident);
+        if let Some(texture_index) = texture_index {
+            return ShaderValue::Texture(texture_index)
+        }
+        match self.inputs.fields.get(&field_ident.0) {
+            Some(value) => value.clone(),
+            None => ShaderValue::zero(self.interpreter.shader_registry, ty)
+        }
+    }
+
+    fn read_field(&self, base_ty: &Ty, value: ShaderValue, field_ident: Ident, ty: &Ty) -> ShaderValue {
+        match (base_ty, value) {
+            (Ty::Struct(struct_ptr), ShaderValue::Struct(mut fields)) => {
+                let struct_def = self.interpreter.shader_registry.structs.get(struct_ptr).unwrap();
+                let index = struct_def.fields.iter().position( | field | field.ident == field_ident).unwrap();
+                fields.swap_remove(index)
+            }
+            (_, value) => {
+                let swizzle = Swizzle::parse(field_ident).unwrap();
+                let indices: Vec<usize> = swizzle.iter().cloned().collect();
+                ShaderValue::map(ty, | index | value.comp(indices[index]))
+            }
+        }
+    }
+
+    // writes through locals, struct fields, swizzles, indices and draw shader fields
+    fn assign(&mut self, frame: &mut Frame<'a>, expr: &'a Expr, value: ShaderValue) -> Result<(), Unwind> {
+        match &expr.kind {
+            ExprKind::Var {kind, ..} => match kind.get() {
+                Some(VarKind::Local {ident, shadow}) | Some(VarKind::MutLocal {ident, shadow}) => {
+                    frame.locals.insert((ident, shadow), value);
+                }
+                _ => panic!(),
+            },
+            ExprKind::Field {expr: base_expr, field_ident, ..} => {
+                let base_ty = Self::ty(base_expr);
+                if let Ty::DrawShader(_) = base_ty {
+                    self.inputs.fields.insert(field_ident.0, value);
+                    return Ok(())
+                }
+                let base = self.eval_expr(frame, base_expr) ?;
+                let base = match (&base_ty, base) {
+                    (Ty::Struct(struct_ptr), ShaderValue::Struct(mut fields)) => {
+                        let struct_def = self.interpreter.shader_registry.structs.get(struct_ptr).unwrap();
+                        let index = struct_def.fields.iter().position( | field | field.ident == *field_ident).unwrap();
+                        fields[index] = value;
+                        ShaderValue::Struct(fields)
+                    }
+                    (_, ShaderValue::Num(ty, mut values)) => {
+                        let swizzle = Swizzle::parse(*field_ident).unwrap();
+                        for (index, slot) in swizzle.iter().enumerate() {
+                            values[*slot] = value.comp(index);
+                        }
+                        ShaderValue::Num(ty, values)
+                    }
+                    _ => panic!()
+                };
+                self.assign(frame, base_expr, base) ?;
+            }
+            ExprKind::Index {expr: base_expr, index_expr, ..} => {
+                let base = self.eval_expr(frame, base_expr) ?;
+                let index = self.eval_expr(frame, index_expr) ?.to_f32() as usize;
+                let base = match base {
+                    ShaderValue::Array(mut elems) => {
+                        if let Some(elem) = elems.get_mut(index) {
+                            *elem = value;
+                        }
+                        ShaderValue::Array(elems)
+                    }
+                    ShaderValue::Num(ty, mut values) => {
+                        let dim = mat_dim(&ty);
+                        if dim > 0 {
+                            if index < dim {
+                                for row in 0..dim {
+                                    values[index * dim + row] = value.comp(row);
+                                }
+                            }
+                        }
+                        else if index < ty.slots() {
+                            values[index] = value.to_f32();
+                        }
+                        ShaderValue::Num(ty, values)
+                    }
+                    _ => panic!()
+                };
+                self.assign(frame, base_expr, base) ?;
+            }
+            _ => panic!(),
+        }
+        Ok(())
+    }
+
+    fn eval_builtin_call(&mut self, frame: &mut Frame<'a>, span: TokenSpan, ident: Ident, arg_exprs: &'a [Expr], ty: &Ty) -> Result<ShaderValue, Unwind> {
+        match ident.0 {
+            live_id!(dFdx) => return self.eval_derivative(frame, &arg_exprs[0], true),
+            live_id!(dFdy) => return self.eval_derivative(frame, &arg_exprs[0], false),
+            _ => ()
+        }
+        let mut args = Vec::new();
+        for arg_expr in arg_exprs {
+            args.push(self.eval_expr(frame, arg_expr) ?);
+        }
+        let a = &args[0];
+        let b = || &args[1];
+        let c = || &args[2];
+        let map1 = | f: fn(f32) -> f32 | ShaderValue::map(ty, | i | f(a.comp(i)));
+        let map2 = | f: fn(f32, f32) -> f32 | ShaderValue::map(ty, | i | f(a.comp(i), b().comp(i)));
+        let map3 = | f: fn(f32, f32, f32) -> f32 | ShaderValue::map(ty, | i | f(a.comp(i), b().comp(i), c().comp(i)));
+        let dot = | x: &ShaderValue, y: &ShaderValue | x.slots().iter().zip(y.slots()).map( | (x, y) | x * y).sum::<f32>();
+        Ok(match ident.0 {
+            live_id!(abs) => map1(f32::abs),
+            live_id!(acos) => map1(f32::acos),
+            live_id!(asin) => map1(f32::asin),
+            live_id!(atan) => if args.len() == 2 {map2(f32::atan2)} else {map1(f32::atan)},
+            live_id!(ceil) => map1(f32::ceil),
+            live_id!(clamp) => map3( | x, lo, hi | x.max(lo).min(hi)),
+            live_id!(cos) => map1(f32::cos),
+            live_id!(degrees) => map1(f32::to_degrees),
+            live_id!(exp) => map1(f32::exp),
+            live_id!(exp2) => map1(f32::exp2),
+            live_id!(floor) => map1(f32::floor),
+            live_id!(fract) => map1( | x | x - x.floor()),
+            live_id!(inversesqrt) => map1( | x | 1.0 / x.sqrt()),
+            live_id!(log) => map1(f32::ln),
+            live_id!(log2) => map1(f32::log2),
+            live_id!(max) => map2(f32::max),
+            live_id!(min) => map2(f32::min),
+            live_id!(mix) => map3( | x, y, t | x + (y - x) * t),
+            live_id!(mod) => map2( | x, y | x - y * (x / y).floor()),
+            live_id!(pow) => map2(f32::powf),
+            live_id!(radians) => map1(f32::to_radians),
+            live_id!(sign) => map1( | x | if x > 0.0 {1.0} else if x < 0.0 {-1.0} else {0.0}),
+            live_id!(sin) => map1(f32::sin),
+            live_id!(smoothstep) => map3( | e0, e1, x | {
+                let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }),
+            live_id!(sqrt) => map1(f32::sqrt),
+            live_id!(step) => map2( | edge, x | if x < edge {0.0} else {1.0}),
+            live_id!(tan) => map1(f32::tan),
+            live_id!(cross) => {
+                let (x, y) = (a.slots(), b().slots());
+                ShaderValue::from_slots(Ty::Vec3, &[
+                    x[1] * y[2] - x[2] * y[1],
+                    x[2] * y[0] - x[0] * y[2],
+                    x[0] * y[1] - x[1] * y[0]
+                ])
+            }
+            live_id!(distance) => {
+                let d = arith(BinOp::Sub, a, b(), a.ty().unwrap());
+                ShaderValue::float(dot(&d, &d).sqrt())
+            }
+            live_id!(dot) => ShaderValue::float(dot(a, b())),
+            live_id!(faceforward) => {
+                let sign = if dot(c(), b()) < 0.0 {1.0} else {-1.0};
+                ShaderValue::map(ty, | i | a.comp(i) * sign)
+            }
+            live_id!(length) => ShaderValue::float(dot(a, a).sqrt()),
+            live_id!(normalize) => {
+                let length = dot(a, a).sqrt();
+                ShaderValue::map(ty, | i | a.comp(i) / length)
+            }
+            live_id!(reflect) => {
+                let d = dot(b(), a);
+                ShaderValue::map(ty, | i | a.comp(i) - 2.0 * d * b().comp(i))
+            }
+            live_id!(refract) => {
+                let (d, eta) = (dot(b(), a), c().to_f32());
+                let k = 1.0 - eta * eta * (1.0 - d * d);
+                if k < 0.0 {
+                    ShaderValue::map(ty, | _ | 0.0)
+                }
+                else {
+                    ShaderValue::map(ty, | i | eta * a.comp(i) - (eta * d + k.sqrt()) * b().comp(i))
+                }
+            }
+            live_id!(all) => ShaderValue::bool(a.slots().iter().all( | x | *x != 0.0)),
+            live_id!(any) => ShaderValue::bool(a.slots().iter().any( | x | *x != 0.0)),
+            live_id!(not) => map1( | x | if x != 0.0 {0.0} else {1.0}),
+            live_id!(equal) => map2( | x, y | if x == y {1.0} else {0.0}),
+            live_id!(notEqual) => map2( | x, y | if x != y {1.0} else {0.0}),
+            live_id!(greaterThan) => map2( | x, y | if x > y {1.0} else {0.0}),
+            live_id!(greaterThanEqual) => map2( | x, y | if x >= y {1.0} else {0.0}),
+            live_id!(lessThan) => map2( | x, y | if x < y {1.0} else {0.0}),
+            live_id!(lessThanEqual) => map2( | x, y | if x <= y {1.0} else {0.0}),
+            live_id!(matrixCompMult) => map2( | x, y | x * y),
+            live_id!(transpose) => {
+                let dim = mat_dim(ty);
+                ShaderValue::map(ty, | i | a.comp((i % dim) * dim + i / dim))
+            }
+            live_id!(inverse) => inverse(a),
+            live_id!(sample2d) | live_id!(sample2dOES) | live_id!(sample2d_rt) => {
+                let color = match a {
+                    ShaderValue::Texture(index) => match self.interpreter.textures.get(*index) {
+                        Some(Some(texture)) => texture.sample(b().to_vec2()),
+                        _ => Vec4::default()
+                    }
+                    _ => Vec4::default()
+                };
+                ShaderValue::vec4(color)
+            }
+            _ => return Err(Unwind::Error(LiveError {
+                origin: live_error_origin!(),
+                span: span.into(),
+                message: format!("builtin {} is not supported by the interpreter", ident),
+                notes: Vec::new(),
+            }))
+        })
+    }
+
+    // fine derivatives across the 2x2 quad of the pixel, from re-running the
+    // pixel function on the neighbouring pixel up to this same call
+    fn eval_derivative(&mut self, frame: &mut Frame<'a>, arg_expr: &'a Expr, is_x: bool) -> Result<ShaderValue, Unwind> {
+        let index = self.derivatives;
+        self.derivatives += 1;
+        let value = self.eval_expr(frame, arg_expr) ?;
+        if self.probe == Some(index) {
+            return Err(Unwind::Probed(value))
+        }
+        let ty = value.ty().cloned().unwrap_or(Ty::Float);
+        let pos = match self.pixel {
+            Some(pos) => pos,
+            None => return Ok(ShaderValue::map(&ty, | _ | 0.0))
+        };
+        let (is_even, neighbour) = if is_x {
+            (pos.x & 1 == 0, PixelPos {x: pos.x ^ 1, ..pos})
+        }
+        else {
+            (pos.y & 1 == 0, PixelPos {y: pos.y ^ 1, ..pos})
+        };
+        Ok(match self.interpreter.probe(neighbour, index).map_err(Unwind::Error) ? {
+            Some(other) if is_even => ShaderValue::map(&ty, | i | other.comp(i) - value.comp(i)),
+            Some(other) => ShaderValue::map(&ty, | i | value.comp(i) - other.comp(i)),
+            // the neighbour took a different path
+            None => ShaderValue::map(&ty, | _ | 0.0)
+        })
+    }
+}
+
+fn arith(op: BinOp, left: &ShaderValue, right: &ShaderValue, ty: &Ty) -> ShaderValue {
+    let (left_ty, right_ty) = (left.ty().unwrap(), right.ty().unwrap());
+    if let BinOp::Mul = op {
+        let (left_dim, right_dim) = (mat_dim(left_ty), mat_dim(right_ty));
+        // linear algebra, anything with a scalar is component-wise
+        if left_dim > 0 && right_dim > 0 {
+            let dim = left_dim;
+            return ShaderValue::map(ty, | i | {
+                let (col, row) = (i / dim, i % dim);
+                (0..dim).map( | k | left.comp(k * dim + row) * right.comp(col * dim + k)).sum()
+            })
+        }
+        if left_dim > 0 && right_ty.is_vector() {
+            let dim = left_dim;
+            return ShaderValue::map(ty, | row | (0..dim).map( | k | left.comp(k * dim + row) * right.comp(k)).sum())
+        }
+        if right_dim > 0 && left_ty.is_vector() {
+            let dim = right_dim;
+            return ShaderValue::map(ty, | col | (0..dim).map( | k | left.comp(k) * right.comp(col * dim + k)).sum())
+        }
+    }
+    let int = is_int(ty);
+    ShaderValue::map(ty, | i | {
+        let (x, y) = (left.comp(i), right.comp(i));
+        match op {
+            BinOp::Add => x + y,
+            BinOp::Sub => x - y,
+            BinOp::Mul => x * y,
+            BinOp::Div if int => (x as i32).checked_div(y as i32).unwrap_or(0) as f32,
+            BinOp::Div => x / y,
+            _ => panic!()
+        }
+    })
+}
+
+fn read_index(value: ShaderValue, index: usize, ty: &Ty) -> ShaderValue {
+    match value {
+        ShaderValue::Array(mut elems) => {
+            if index < elems.len() {elems.swap_remove(index)} else {ShaderValue::Void}
+        }
+        ShaderValue::Num(base_ty, values) => {
+            let dim = mat_dim(&base_ty);
+            if dim > 0 {
+                let index = index.min(dim - 1);
+                ShaderValue::map(ty, | row | values[index * dim + row])
+            }
+            else {
+                ShaderValue::map(ty, | _ | values[index.min(base_ty.slots() - 1)])
+            }
+        }
+        _ => ShaderValue::Void
+    }
+}
+
+// the GLSL constructor rules: splat a scalar, a scalar on the diagonal of a
+// matrix, a matrix resized from a matrix, otherwise the components in order
+fn construct(ty: &Ty, args: &[ShaderValue]) -> ShaderValue {
+    let dim = mat_dim(ty);
+    if let [arg] = args {
+        let arg_dim = arg.ty().map(mat_dim).unwrap_or(0);
+        if arg.slots().len() == 1 {
+            return if dim > 0 {
+                ShaderValue::map(ty, | i | if i / dim == i % dim {arg.comp(0)} else {0.0})
+            }
+            else {
+                ShaderValue::map(ty, | _ | arg.comp(0))
+            }
+        }
+        if dim > 0 && arg_dim > 0 {
+            return ShaderValue::map(ty, | i | {
+                let (col, row) = (i / dim, i % dim);
+                if col < arg_dim && row < arg_dim {arg.comp(col * arg_dim + row)}
+                else if col == row {1.0}
+                else {0.0}
+            })
+        }
+    }
+    let slots: Vec<f32> = args.iter().flat_map( | arg | arg.slots().iter().cloned()).collect();
+    ShaderValue::map(ty, | i | slots.get(i).cloned().unwrap_or(0.0))
+}
+
+// Gauss-Jordan elimination with partial pivoting, singular matrices give zero
+fn inverse(value: &ShaderValue) -> ShaderValue {
+    let ty = value.ty().unwrap();
+    let dim = mat_dim(ty);
+    let mut m = [[0.0f32; 8]; 4];
+    for (row, m_row) in m.iter_mut().enumerate().take(dim) {
+        for (col, v) in m_row.iter_mut().enumerate().take(dim) {
+            *v = value.comp(col * dim + row);
+        }
+        m_row[dim + row] = 1.0;
+    }
+    for col in 0..dim {
+        let pivot = (col..dim).max_by( | a, b | m[*a][col].abs().partial_cmp(&m[*b][col].abs()).unwrap()).unwrap();
+        if m[pivot][col] == 0.0 {
+            return ShaderValue::map(ty, | _ | 0.0)
+        }
+        m.swap(col, pivot);
+        let scale = m[col][col];
+        for v in m[col].iter_mut() {
+            *v /= scale;
+        }
+        let pivot_row = m[col];
+        for (row, m_row) in m.iter_mut().enumerate().take(dim) {
+            if row != col {
+                let factor = m_row[col];
+                for (v, p) in m_row.iter_mut().zip(pivot_row.iter()) {
+                    *v -= factor * p;
+                }
+            }
+        }
+    }
+    ShaderValue::map(ty, | i | m[i % dim][dim + i / dim])
+}
@@ -82,6 +82,7 @@ impl<'a> LhsChecker<'a> {
             origin: live_error_origin!(),
             span:span.into(),
             message: String::from("expression is not a valid left hand side"),
+            notes: Vec::new(),
         });
     }
     
@@ -96,6 +97,7 @@ impl<'a> LhsChecker<'a> {
             origin: live_error_origin!(),
             span:span.into(),
             message: String::from("expression is not a valid left hand side"),
+            notes: Vec::new(),
         });
     }
     
@@ -110,6 +112,7 @@ impl<'a> LhsChecker<'a> {
             origin:live_error_origin!(),
             span:span.into(),
             message: String::from("expression is not a valid left hand side"),
+            notes: Vec::new(),
         });
     }
     
@@ -118,6 +121,7 @@ impl<'a> LhsChecker<'a> {
             origin:live_error_origin!(),
             span:span.into(),
             message: String::from("expression is not a valid left hand side"),
+            notes: Vec::new(),
         });
     }
     
@@ -129,6 +133,7 @@ impl<'a> LhsChecker<'a> {
             origin:live_error_origin!(),
             span:span.into(),
             message: String::from("expression is not a valid left hand side"),
+            notes: Vec::new(),
         });
     }
     
@@ -151,6 +156,7 @@ impl<'a> LhsChecker<'a> {
                             origin:live_error_origin!(),
                             span:span.into(),
                             message: String::from("Can only assign to varying values for shader self"),
+                            notes: Vec::new(),
                         })
                     }
                 }
@@ -180,6 +186,7 @@ impl<'a> LhsChecker<'a> {
             origin:live_error_origin!(),
             span:span.into(),
             message: String::from("expression is not a valid left hand side"),
+            notes: Vec::new(),
         });
     }
     
@@ -196,6 +203,7 @@ impl<'a> LhsChecker<'a> {
                 origin:live_error_origin!(),
                 span:span.into(),
                 message: String::from("expression is not a valid left hand side"),
+                notes: Vec::new(),
             })
         }
     }
@@ -210,7 +218,7 @@ impl<'a> LhsChecker<'a> {
         return Err(LiveError {
             span,
             message: String::from("liveid is not a valid left hand side"),
-        });
+, notes: Vec::new() });
     }*/
     
     fn lhs_check_lit_expr(&mut self, _span: TokenSpan, _lit: Lit) -> Result<(), LiveError> {
@@ -21,6 +21,9 @@ pub mod generate_glsl;
 pub mod generate_metal;
 #[cfg(any(target_os = "windows"))]
 pub mod generate_hlsl;
+pub mod generate_wgsl;
+pub mod interpret;
+pub mod rasterize;
 
 pub use makepad_live_compiler;
 pub use makepad_live_compiler::makepad_math;
@@ -44,7 +47,8 @@ pub use {
         shader_registry::{
             ShaderEnum,
             ShaderRegistry,
-            DrawShaderQuery
+            DrawShaderQuery,
+            MAX_USER_UNIFORM_SLOTS
         }
     }
 };
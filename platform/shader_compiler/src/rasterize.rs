@@ -0,0 +1,154 @@
+// A software rasterizer that draws triangles through the shader interpreter,
+// with the state the GPU backends use for 2D drawing: no culling, a LEQUAL
+// depth test and premultiplied alpha blending.
+use {
+    crate::{
+        makepad_live_compiler::LiveError,
+        makepad_math::*,
+        shader_ast::*,
+        interpret::{ShaderInputs, ShaderInterpreter, ShaderValue},
+    }
+};
+
+/// A color and depth buffer. Row 0 is the top of the image, and colors are
+/// premultiplied like the GPU framebuffers.
+#[derive(Clone, Debug)]
+pub struct SoftwareFramebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vec4>,
+    pub depth: Vec<f32>,
+}
+
+impl SoftwareFramebuffer {
+    pub fn new(width: usize, height: usize, clear_color: Vec4) -> Self {
+        Self {
+            width,
+            height,
+            color: vec![clear_color; width * height],
+            depth: vec![1.0; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Vec4 {
+        self.color[y * self.width + x]
+    }
+
+    /// The color buffer as tightly packed RGBA bytes.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.color.len() * 4);
+        for color in &self.color {
+            for v in [color.x, color.y, color.z, color.w] {
+                out.push((v.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+        out
+    }
+
+    /// Runs the vertex stage on every vertex and rasterizes the indexed
+    /// triangles, running the pixel stage on every covered pixel center.
+    /// Stops at the first error of the interpreter.
+    pub fn draw_triangles(&mut self, interpreter: &ShaderInterpreter, vertices: &[ShaderInputs], indices: &[u32]) -> Result<(), LiveError> {
+        let mut outputs = Vec::with_capacity(vertices.len());
+        for vertex in vertices {
+            let mut inputs = vertex.clone();
+            let position = interpreter.vertex(&mut inputs) ?;
+            outputs.push((position, inputs));
+        }
+        for triangle in indices.chunks_exact(3) {
+            let corners = [
+                &outputs[triangle[0] as usize],
+                &outputs[triangle[1] as usize],
+                &outputs[triangle[2] as usize]
+            ];
+            self.draw_triangle(interpreter, corners) ?;
+        }
+        Ok(())
+    }
+
+    fn draw_triangle(&mut self, interpreter: &ShaderInterpreter, corners: [&(Vec4, ShaderInputs); 3]) -> Result<(), LiveError> {
+        if corners.iter().any( | (pos, _) | pos.w <= 0.0) {
+            return Ok(())
+        }
+        // window coordinates, with ndc y = 1 at the top row
+        let screen: Vec<Vec4> = corners.iter().map( | (pos, _) | vec4(
+            (pos.x / pos.w * 0.5 + 0.5) * self.width as f32,
+            (0.5 - pos.y / pos.w * 0.5) * self.height as f32,
+            pos.z / pos.w,
+            1.0 / pos.w
+        )).collect();
+        let edge = | a: Vec4, b: Vec4, x: f32, y: f32 | (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
+        let area = edge(screen[0], screen[1], screen[2].x, screen[2].y);
+        if area == 0.0 {
+            return Ok(())
+        }
+        let barycentric = | x: f32, y: f32 | [
+            edge(screen[1], screen[2], x, y) / area,
+            edge(screen[2], screen[0], x, y) / area,
+            edge(screen[0], screen[1], x, y) / area,
+        ];
+        // pixels exactly on an edge belong to the triangle on its top or left side
+        let is_top_left = | a: Vec4, b: Vec4 | {
+            let (dx, dy) = if area > 0.0 {(b.x - a.x, b.y - a.y)} else {(a.x - b.x, a.y - b.y)};
+            (dy == 0.0 && dx > 0.0) || dy < 0.0
+        };
+        let owns_edge = [
+            is_top_left(screen[1], screen[2]),
+            is_top_left(screen[2], screen[0]),
+            is_top_left(screen[0], screen[1]),
+        ];
+
+        let min_x = screen.iter().map( | p | p.x).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let min_y = screen.iter().map( | p | p.y).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let max_x = (screen.iter().map( | p | p.x).fold(f32::MIN, f32::max).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (screen.iter().map( | p | p.y).fold(f32::MIN, f32::max).ceil().max(0.0) as usize).min(self.height);
+
+        let draw_shader_def = interpreter.draw_shader_def;
+        // the perspective correct interpolation of the varyings, the other
+        // fields are flat and come from the provoking (last) vertex
+        let inputs_at = | x: usize, y: usize | {
+            let weights = barycentric(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [weights[0] * screen[0].w, weights[1] * screen[1].w, weights[2] * screen[2].w];
+            let sum = weights[0] + weights[1] + weights[2];
+            let mut inputs = corners[2].1.clone();
+            for field in &draw_shader_def.fields {
+                if !matches!(field.kind, DrawShaderFieldKind::Varying {..} | DrawShaderFieldKind::Geometry {..}) {
+                    continue
+                }
+                let values: Vec<_> = corners.iter().map( | (_, inputs) | inputs.fields.get(&field.ident.0)).collect();
+                if let [Some(ShaderValue::Num(ty, a)), Some(ShaderValue::Num(_, b)), Some(ShaderValue::Num(_, c))] = values[..] {
+                    let mut value = [0.0; 16];
+                    for i in 0..ty.slots() {
+                        value[i] = (a[i] * weights[0] + b[i] * weights[1] + c[i] * weights[2]) / sum;
+                    }
+                    inputs.fields.insert(field.ident.0, ShaderValue::Num(ty.clone(), value));
+                }
+            }
+            inputs
+        };
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let weights = barycentric(x as f32 + 0.5, y as f32 + 0.5);
+                let inside = (0..3).all( | i | weights[i] > 0.0 || (weights[i] == 0.0 && owns_edge[i]));
+                if !inside {
+                    continue
+                }
+                let z = weights[0] * screen[0].z + weights[1] * screen[1].z + weights[2] * screen[2].z;
+                if !(-1.0..=1.0).contains(&z) {
+                    continue
+                }
+                let depth = z * 0.5 + 0.5;
+                let index = y * self.width + x;
+                if depth > self.depth[index] {
+                    continue
+                }
+                let src = interpreter.pixel(x, y, &inputs_at) ?;
+                let dst = self.color[index];
+                self.color[index] = src + dst * (1.0 - src.w);
+                self.depth[index] = depth;
+            }
+        }
+        Ok(())
+    }
+}
@@ -311,6 +311,16 @@ pub enum Stmt {
         to_expr: Expr,
         step_expr: Option<Expr>,
         block: Box<Block>,
+        shadow: Cell<Option<ScopeSymShadow >>,
+    },
+    While {
+        span: TokenSpan,
+        expr: Expr,
+        block: Box<Block>,
+    },
+    Loop {
+        span: TokenSpan,
+        block: Box<Block>,
     },
     If {
         span: TokenSpan,
@@ -858,6 +868,10 @@ impl DrawShaderDef {
 }
 
 impl BinOp {
+    pub fn is_assign(&self) -> bool {
+        matches!(self, BinOp::Assign | BinOp::AddAssign | BinOp::SubAssign | BinOp::MulAssign | BinOp::DivAssign)
+    }
+    
     pub fn from_assign_op(token: LiveToken) -> Option<BinOp> {
         match token {
             LiveToken::Punct(live_id!( =)) => Some(BinOp::Assign),
@@ -1121,7 +1135,8 @@ impl Ty {
                         return Err(LiveError {
                             origin: live_error_origin!(),
                             message: format!("Expression return type does not resolve to a shader {:?}", v),
-                            span: nodes[index].origin.token_id().unwrap().into()
+                            span: nodes[index].origin.token_id().unwrap().into(),
+                            notes: Vec::new(),
                         })
                     }
                 }
@@ -1139,7 +1154,8 @@ impl Ty {
                     return Err(LiveError {
                         origin: live_error_origin!(),
                         message: format!("Id does not resolve to a shader type {}", id),
-                        span: nodes[index].origin.token_id().unwrap().into()
+                        span: nodes[index].origin.token_id().unwrap().into(),
+                        notes: Vec::new(),
                     })
                 }
             }
@@ -1151,10 +1167,37 @@ impl Ty {
             LiveValue::Vec2(_) => Self::Vec2,
             LiveValue::Vec3(_) => Self::Vec3,
             LiveValue::Vec4(_) => Self::Vec4,
+            LiveValue::Array => {
+                // uniform arrays hold vec4 elements, these have the same layout in every backend
+                let mut len = 0;
+                let mut node_iter = nodes.first_child(index);
+                while let Some(elem_index) = node_iter {
+                    if Self::from_live_node(live_registry, elem_index, nodes) ? != Self::Vec4 {
+                        return Err(LiveError {
+                            origin: live_error_origin!(),
+                            message: String::from("Array elements have to be colors or vec4 values"),
+                            span: nodes[elem_index].origin.token_id().unwrap().into(),
+                            notes: Vec::new(),
+                        })
+                    }
+                    len += 1;
+                    node_iter = nodes.next_child(elem_index);
+                }
+                if len == 0 {
+                    return Err(LiveError {
+                        origin: live_error_origin!(),
+                        message: String::from("Array can't be empty"),
+                        span: nodes[index].origin.token_id().unwrap().into(),
+                        notes: Vec::new(),
+                    })
+                }
+                Self::Array {elem_ty: Rc::new(Self::Vec4), len}
+            }
             _ => return Err(LiveError {
                 origin: live_error_origin!(),
                 message: format!("Live value {:?} does not resolve to a shader type", nodes[index].value),
-                span: nodes[index].origin.token_id().unwrap().into()
+                span: nodes[index].origin.token_id().unwrap().into(),
+                notes: Vec::new(),
             })
         })
     }
@@ -106,6 +106,7 @@ impl<'a> ShaderParser<'a> {
             origin,
             span: self.token_with_span.span.into(),
             message,
+            notes: Vec::new(),
         }
     }
     
@@ -596,6 +597,8 @@ impl<'a> ShaderParser<'a> {
             LiveToken::Ident(live_id!(break)) => self.expect_break_stmt(),
             LiveToken::Ident(live_id!(continue)) => self.expect_continue_stmt(),
             LiveToken::Ident(live_id!(for)) => self.expect_for_stmt(),
+            LiveToken::Ident(live_id!(while)) => self.expect_while_stmt(),
+            LiveToken::Ident(live_id!(loop)) => self.expect_loop_stmt(),
             LiveToken::Ident(live_id!(if)) => self.expect_if_stmt(),
             LiveToken::Ident(live_id!(match)) => self.expect_match_stmt(),
             LiveToken::Ident(live_id!(let)) => self.expect_let_stmt(),
@@ -644,9 +647,31 @@ impl<'a> ShaderParser<'a> {
             to_expr,
             step_expr,
             block,
+            shadow: Cell::new(None),
+        }))
+    }
+    
+    fn expect_while_stmt(&mut self) -> Result<Stmt, LiveError> {
+        let span = self.begin_span();
+        self.expect_token(LiveToken::Ident(live_id!(while))) ?;
+        let expr = self.expect_expr() ?;
+        let block = Box::new(self.expect_block() ?);
+        Ok(span.end(self, | span | Stmt::While {
+            span,
+            expr,
+            block,
         }))
     }
     
+    fn expect_loop_stmt(&mut self) -> Result<Stmt, LiveError> {
+        let span = self.begin_span();
+        self.expect_token(LiveToken::Ident(live_id!(loop))) ?;
+        let block = Box::new(self.expect_block() ?);
+        Ok(span.end(self, | span | Stmt::Loop {
+            span,
+            block,
+        }))
+    }
     
     fn expect_if_stmt(&mut self) -> Result<Stmt, LiveError> {
         let span = self.begin_span();
@@ -1021,7 +1046,7 @@ impl<'a> ShaderParser<'a> {
                         })
                     }
                 }
-                LiveToken::Close(Delim::Bracket) => {
+                LiveToken::Open(Delim::Bracket) => {
                     self.skip_token();
                     let expr = Box::new(acc);
                     let index_expr = Box::new(self.expect_expr() ?);
@@ -1435,6 +1460,7 @@ impl SpanTracker {
                 end: parser.token_end(),
             }.into(),
             message,
+            notes: Vec::new(),
         }
     }
 }
\ No newline at end of file
@@ -15,6 +15,9 @@ use {
 };
 
 
+/// The number of floats a draw call has for the user uniforms of its shader.
+pub const MAX_USER_UNIFORM_SLOTS: usize = 32;
+
 pub struct ShaderRegistry {
     pub all_fns: HashMap<FnPtr, FnDef>,
     pub draw_shader_defs: HashMap<DrawShaderPtr, DrawShaderDef>,
@@ -207,7 +210,8 @@ impl ShaderRegistry {
                                 LiveError {
                                     origin: live_error_origin!(),
                                     message: format!("Type of eval result not valid for shader"),
-                                    span: nodes[index].origin.token_id().unwrap().into()
+                                    span: nodes[index].origin.token_id().unwrap().into(),
+                                    notes: Vec::new(),
                                 }
                             );
                         }
@@ -425,7 +429,8 @@ impl ShaderRegistry {
                                     return Err(LiveError {
                                         origin: live_error_origin!(),
                                         span: prop.origin.token_id().unwrap().into(),
-                                        message: format!("Unexpected DSL node")
+                                        message: format!("Unexpected DSL node"),
+                                        notes: Vec::new(),
                                     })
                                     /*
                                     let def = parser.expect_field(Ident(id), VarDefPtr(prop_ptr)) ?;
@@ -451,7 +456,8 @@ impl ShaderRegistry {
                                 return Err(LiveError {
                                     origin: live_error_origin!(),
                                     span: prop.origin.token_id().unwrap().into(),
-                                    message: format!("Type not found for struct field {}", type_name)
+                                    message: format!("Type not found for struct field {}", type_name),
+                                    notes: Vec::new(),
                                 })
                             }
                         },
@@ -459,7 +465,8 @@ impl ShaderRegistry {
                             return Err(LiveError {
                                 origin: live_error_origin!(),
                                 span: prop.origin.token_id().unwrap().into(),
-                                message: format!("Cannot use {:?} in struct", prop.value)
+                                message: format!("Cannot use {:?} in struct", prop.value),
+                                notes: Vec::new(),
                             })
                         }
                     }
@@ -490,6 +497,37 @@ impl ShaderRegistry {
         Ok(())
     }
     
+    /// Checks that the user uniforms fit in a draw call, laid out with the padding of the backend
+    /// that needs the most: a vec3 takes a whole row of 4 floats, and arrays and values that
+    /// would cross a row start on a new one.
+    fn check_user_uniform_slots(draw_shader_def: &DrawShaderDef) -> Result<(), LiveError> {
+        let mut total_slots = 0;
+        for field in &draw_shader_def.fields {
+            let DrawShaderFieldKind::Uniform {block_ident, ..} = field.kind else {
+                continue
+            };
+            if block_ident.0 != live_id!(user) {
+                continue
+            }
+            let ty = field.ty_expr.ty.borrow().clone().unwrap();
+            let slots = ty.slots();
+            let aligned_slots = if slots == 3 {4} else {slots};
+            if matches!(ty, Ty::Array {..}) || (total_slots & 3) + aligned_slots > 4 {
+                total_slots = (total_slots + 3) & !3;
+            }
+            total_slots += aligned_slots;
+            if total_slots > MAX_USER_UNIFORM_SLOTS {
+                return Err(LiveError {
+                    origin: live_error_origin!(),
+                    span: field.span.into(),
+                    message: format!("Uniforms need more than the {} floats a draw call has", MAX_USER_UNIFORM_SLOTS),
+                    notes: Vec::new(),
+                })
+            }
+        }
+        Ok(())
+    }
+    
     // lets compile the thing
     pub fn analyse_draw_shader<F>(&mut self, live_registry: &LiveRegistry, draw_shader_ptr: DrawShaderPtr, mut ext_self: F) -> Result<(),
         LiveError>
@@ -552,7 +590,8 @@ impl ShaderRegistry {
                                 return Err(LiveError {
                                     origin: live_error_origin!(),
                                     span: prop.origin.token_id().unwrap().into(),
-                                    message: format!("Can only support field colon : values don't use =")
+                                    message: format!("Can only support field colon : values don't use ="),
+                                    notes: Vec::new(),
                                 })
                             }
                             if prop.id == live_id!(size){
@@ -662,11 +701,24 @@ impl ShaderRegistry {
                                     return Err(LiveError {
                                         origin: live_error_origin!(),
                                         span: first_def.into(),
-                                        message: format!("Unexpected variable prefix {:?}", before)
+                                        message: format!("Unexpected variable prefix {:?}", before),
+                                        notes: Vec::new(),
                                     })
                                 }
                             };
                         }
+                        LiveValue::Array if live_registry.get_node_prefix(prop.origin) == Some(live_id!(uniform)) => {
+                            let ty = ShaderTy::from_live_node(live_registry, node_index, &doc.nodes) ?;
+                            draw_shader_def.fields.push(DrawShaderFieldDef {
+                                kind: DrawShaderFieldKind::Uniform {
+                                    var_def_ptr: Some(VarDefPtr(prop_ptr)),
+                                    block_ident: Ident(live_id!(user)),
+                                },
+                                span: prop.origin.first_def().unwrap().into(),
+                                ident: Ident(prop.id),
+                                ty_expr: ty.to_ty_expr()
+                            });
+                        }
                         LiveValue::Class {live_type, ..} => {
                             if prop.id == live_id!(geometry) {
                                 ext_self(
@@ -711,7 +763,8 @@ impl ShaderRegistry {
                                     return Err(LiveError {
                                         origin: live_error_origin!(),
                                         span: token.span.into(),
-                                        message: format!("Unexpected in shader body {}", token)
+                                        message: format!("Unexpected in shader body {}", token),
+                                        notes: Vec::new(),
                                     })
                                     /*
                                     let decl = parser.expect_self_decl(Ident(prop.id), prop_ptr) ?;
@@ -760,7 +813,8 @@ impl ShaderRegistry {
                             return Err(LiveError {
                                 origin: live_error_origin!(),
                                 span: field_a.span.into(),
-                                message: format!("Field double declaration  {}",field_a.ident)
+                                message: format!("Field double declaration  {}",field_a.ident),
+                                notes: Vec::new(),
                             })
                         }
                     }
@@ -772,7 +826,8 @@ impl ShaderRegistry {
                     return Err(LiveError {
                         origin: live_error_origin!(),
                         span: class_node.origin.token_id().unwrap().into(),
-                        message: format!("analyse_draw_shader missing vertex method")
+                        message: format!("analyse_draw_shader missing vertex method"),
+                        notes: Vec::new(),
                     })
                 }
                 
@@ -780,7 +835,8 @@ impl ShaderRegistry {
                     return Err(LiveError {
                         origin: live_error_origin!(),
                         span: class_node.origin.token_id().unwrap().into(),
-                        message: format!("analyse_draw_shader missing pixel method")
+                        message: format!("analyse_draw_shader missing pixel method"),
+                        notes: Vec::new(),
                     })
                 }
                 
@@ -797,13 +853,15 @@ impl ShaderRegistry {
                     }
                 };
                 sa.analyse_shader() ?;
+                Self::check_user_uniform_slots(draw_shader_def) ?;
                 // ok we have all structs
                 return Ok(())
             }
             x => return Err(LiveError {
                 origin: live_error_origin!(),
                 span: class_node.origin.token_id().unwrap().into(),
-                message: format!("analyse_draw_shader could not find shader class {:?}", x)
+                message: format!("analyse_draw_shader could not find shader class {:?}", x),
+                notes: Vec::new(),
             })
         }
     }
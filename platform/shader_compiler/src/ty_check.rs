@@ -82,13 +82,14 @@ impl<'a> TyChecker<'a> {
         if &actual_ty != expected_ty {
             return Err(LiveError {
                 origin: live_error_origin!(),
-                span:span.into(),
+                span:expr.span.into(),
                 message: format!(
-                    "can't match expected type `{}` with actual type `{}",
+                    "can't match expected type `{}` with actual type `{}`",
                     expected_ty,
                     actual_ty
                 ),
-            });
+                notes: Vec::new(),
+            }.with_note(span, format!("expected `{}` because of this", expected_ty)));
         }
         Ok(actual_ty)
     }
@@ -362,6 +363,7 @@ impl<'a> TyChecker<'a> {
                 right_ty
             )
                 .into(),
+            notes: Vec::new(),
         })
     }
     
@@ -390,6 +392,7 @@ impl<'a> TyChecker<'a> {
                 ty
             )
                 .into(),
+            notes: Vec::new(),
         })
     }
     
@@ -415,6 +418,7 @@ impl<'a> TyChecker<'a> {
                             origin: live_error_origin!(),
                             span,
                             message: format!("Cannot pass closures to closures, please implement"),
+                            notes: Vec::new(),
                         })
                     }
                     outer_param_index.set(Some(*param_index));
@@ -428,6 +432,7 @@ impl<'a> TyChecker<'a> {
             origin: live_error_origin!(),
             span,
             message: format!("Closure call `{}` is not defined on", ident),
+            notes: Vec::new(),
         })
     }
     */
@@ -457,6 +462,7 @@ impl<'a> TyChecker<'a> {
                                 origin: live_error_origin!(),
                                 span:span.into(),
                                 message: format!("Cannot pass closures to closures, please implement"),
+                                notes: Vec::new(),
                             })
                         }
                         outer_param_index.set(Some(*param_index));
@@ -481,6 +487,7 @@ impl<'a> TyChecker<'a> {
             origin: live_error_origin!(),
             span:span.into(),
             message: format!("Function not found {}", ident.unwrap()),
+            notes: Vec::new(),
         }) 
     }
     
@@ -514,6 +521,7 @@ impl<'a> TyChecker<'a> {
                         origin: live_error_origin!(),
                         span:span.into(),
                         message: format!("shader method `{}` is not type checked `{}`", ident, ty),
+                        notes: Vec::new(),
                     });
                 }
             },
@@ -537,6 +545,7 @@ impl<'a> TyChecker<'a> {
                         origin: live_error_origin!(),
                         span:span.into(),
                         message: format!("struct method `{}` is not type checked `{}`", ident, ty),
+                        notes: Vec::new(),
                     });
                 }
             },
@@ -546,6 +555,7 @@ impl<'a> TyChecker<'a> {
             origin: live_error_origin!(),
             span:span.into(),
             message: format!("method `{}` is not defined on type `{}`", ident, ty),
+            notes: Vec::new(),
         })
     }
     
@@ -583,7 +593,7 @@ impl<'a> TyChecker<'a> {
                 write!(message, "{}{}", sep, arg_ty).unwrap();
                 sep = ", ";
             }
-            LiveError {origin: live_error_origin!(), span:span.into(), message}
+            LiveError {origin: live_error_origin!(), span:span.into(), message, notes: Vec::new() }
         }) ? .clone())
     }
     
@@ -598,9 +608,9 @@ impl<'a> TyChecker<'a> {
         match self.check_params_against_args(span, &fn_def.params, arg_exprs) {
            Err(err)=> Err(LiveError {
                 origin: live_error_origin!(),
-                span:span.into(),
-                message: format!("function: `{}`: {}", self.shader_registry.fn_ident_from_ptr(self.live_registry, fn_ptr), err.message)
-            }),
+                message: format!("function: `{}`: {}", self.shader_registry.fn_ident_from_ptr(self.live_registry, fn_ptr), err.message),
+                ..err
+            }.with_note(fn_def.span, format!("function `{}` is defined here", fn_def.ident))),
             Ok(closure_args)=>{
                 if closure_args.len()>0{
                     let mut ci = self.scopes.closure_sites.borrow_mut();
@@ -608,7 +618,8 @@ impl<'a> TyChecker<'a> {
                         return Err(LiveError {
                             origin: live_error_origin!(),
                             span:span.into(),
-                            message: format!("Closures not supported here {}", self.shader_registry.fn_ident_from_ptr(self.live_registry, fn_ptr))
+                            message: format!("Closures not supported here {}", self.shader_registry.fn_ident_from_ptr(self.live_registry, fn_ptr)),
+                            notes: Vec::new(),
                         });
                     }
                     closure_site_index.unwrap().set(Some(ci.len()));
@@ -639,6 +650,7 @@ impl<'a> TyChecker<'a> {
                     arg_exprs.len(),
                 )
                     .into(),
+                notes: Vec::new(),
             });
         }
         if arg_exprs.len() > params.len() {
@@ -651,6 +663,7 @@ impl<'a> TyChecker<'a> {
                     arg_exprs.len()
                 )
                     .into(),
+                notes: Vec::new(),
             });
         }
         let mut closure_args= Vec::new();
@@ -676,14 +689,15 @@ impl<'a> TyChecker<'a> {
             if arg_ty != param_ty {
                 return Err(LiveError {
                     origin: live_error_origin!(),
-                    span:span.into(),
+                    span:arg_expr.span.into(),
                     message: format!(
                         "wrong type for argument {} expected `{}`, got `{}`",
                         param_index + 1,
                         param_ty,
                         arg_ty,
-                    ).into()
-                });
+                    ).into(),
+                    notes: Vec::new(),
+                }.with_note(param.span, format!("parameter `{}` is declared as `{}` here", param.ident, param_ty)));
             }
             if param.is_inout {
                 self.lhs_checker().lhs_check_expr(arg_expr) ?;
@@ -721,6 +735,7 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("field `{}` is not defined on type `{}`", field_ident, ty),
+                    notes: Vec::new(),
                 }) ?;
                 Ok(match ty {
                     Ty::Bvec2 | Ty::Bvec3 | Ty::Bvec4 => match swizzle.len() {
@@ -752,6 +767,7 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("field `{}` is not defined on type `{:?}`", field_ident, struct_ptr),
+                    notes: Vec::new(),
                 }) ? .ty_expr .ty .borrow() .as_ref() .unwrap() .clone())
             },
             Ty::DrawShader(shader_ptr) => {
@@ -759,12 +775,14 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("field `{}` is not defined on shader `{:?}`", field_ident, shader_ptr),
+                    notes: Vec::new(),
                 }) ? .ty_expr .ty .borrow() .as_ref() .unwrap() .clone())
             }
             _ => Err(LiveError {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: format!("can't access field on value of type `{}`", ty).into(),
+                notes: Vec::new(),
             }),
         }
     }
@@ -784,19 +802,24 @@ impl<'a> TyChecker<'a> {
             Ty::Mat2 => Ty::Vec2,
             Ty::Mat3 => Ty::Vec3,
             Ty::Mat4 => Ty::Vec4,
+            Ty::Array {ref elem_ty, ..} => (**elem_ty).clone(),
             _ => {
                 return Err(LiveError {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("can't index into value of type `{}`", ty).into(),
+                    notes: Vec::new(),
                 })
             }
         };
-        if index_ty != Ty::Int {
+        // integer literals are floats, so arrays can be indexed with floats as well
+        let is_array = matches!(ty, Ty::Array {..});
+        if index_ty != Ty::Int && !(is_array && index_ty == Ty::Float) {
             return Err(LiveError {
                 origin: live_error_origin!(),
                 span:span.into(),
                 message: "index is not an integer".into(),
+                notes: Vec::new(),
             });
         }
         Ok(elem_ty)
@@ -846,7 +869,8 @@ impl<'a> TyChecker<'a> {
                             actual_slots,
                             expected_slots,
                         )
-                            .into()
+                            .into(),
+                        notes: Vec::new(),
                     });
                 }
                 if actual_slots > expected_slots {
@@ -860,6 +884,7 @@ impl<'a> TyChecker<'a> {
                             actual_slots,
                         )
                             .into(),
+                        notes: Vec::new(),
                     });
                 }
                 Ok(ty.clone())
@@ -873,6 +898,7 @@ impl<'a> TyChecker<'a> {
                     CommaSep(&arg_tys)
                 )
                     .into(),
+                notes: Vec::new(),
             }),
         }
     }
@@ -906,6 +932,7 @@ impl<'a> TyChecker<'a> {
                                 origin: live_error_origin!(),
                                 span:span.into(),
                                 message: format!("`{}` is a closure and cannot be used as a variable", ident),
+                                notes: Vec::new(),
                             })
                         }
                     }
@@ -924,6 +951,7 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("`{}` implement using functions as closure args", ident.unwrap()),
+                    notes: Vec::new(),
                 })
             }
             VarResolve::NotFound => {
@@ -932,6 +960,7 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("`{}` is not defined in this scope", ident.unwrap()),
+                    notes: Vec::new(),
                 })
             }
         }
@@ -957,6 +986,7 @@ impl<'a> TyChecker<'a> {
                         origin: live_error_origin!(),
                         span:span.into(),
                         message: format!("field `{}` is the wrong type {} instead of {}", ident, my_ty.as_ref().unwrap(), field_ty.as_ref().unwrap()),
+                        notes: Vec::new(),
                     })
                 }
             }
@@ -965,6 +995,7 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("`{}` is not a valid struct field", ident),
+                    notes: Vec::new(),
                 })
             }
         }
@@ -975,6 +1006,7 @@ impl<'a> TyChecker<'a> {
                     origin: live_error_origin!(),
                     span:span.into(),
                     message: format!("`{}` field is missing", field.ident),
+                    notes: Vec::new(),
                 })
             }
         }
@@ -985,6 +1017,7 @@ impl<'a> TyChecker<'a> {
                         origin: live_error_origin!(),
                         span:span.into(),
                         message: format!("`{}` field is duplicated", args[i].0),
+                        notes: Vec::new(),
                     })
                 }
             }
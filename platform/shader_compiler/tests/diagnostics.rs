@@ -0,0 +1,80 @@
+use makepad_shader_compiler::{
+    makepad_live_compiler::*,
+    makepad_live_id::*,
+    DrawShaderPtr,
+    ShaderRegistry,
+};
+
+struct TestShader;
+
+// analyses `Shader = {{TestShader}} {<body>}` and returns its error
+fn analyse_error(body: &str) -> (String, LiveFileError) {
+    let source = format!("Shader = {{{{TestShader}}}} {{\n{}\n}}", body);
+    let module_id = LiveModuleId::from_str("test").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file("diagnostics.rs", "", module_id, source.clone(), vec![LiveTypeInfo {
+        live_type: LiveType::of::<TestShader>(),
+        type_name: live_id!(TestShader),
+        module_id,
+        live_ignore: true,
+        fields: Vec::new()
+    }], TextPos::default()).unwrap_or_else( | err | panic!("{}", err));
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let mut shader_registry = ShaderRegistry::new();
+    let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(Shader)).unwrap());
+    let err = shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, _, _, _, _ | {})
+        .expect_err("the shader should not type check");
+    (source, live_registry.live_error_to_live_file_error(err))
+}
+
+// the source text of a token span, the columns of tokens after the first on a line are 1-based
+fn span_text(source: &str, span: TextSpan) -> &str {
+    assert_eq!(span.start.line, span.end.line);
+    let line = source.lines().nth(span.start.line as usize).unwrap();
+    &line[span.start.column as usize - 1..span.end.column as usize - 1]
+}
+
+#[test]
+fn test_expected_type_note() {
+    let (source, err) = analyse_error("
+        fn vertex(self) -> vec4 {
+            return vec4(0.0);
+        }
+        fn pixel(self) -> vec4 {
+            let v: vec2 = 1.0;
+            return vec4(v, 0.0, 1.0);
+        }
+    ");
+    assert_eq!(err.message, "can't match expected type `vec2` with actual type `float`");
+    assert_eq!(span_text(&source, err.span), "1.0");
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!(err.notes[0].message, "expected `vec2` because of this");
+    assert_eq!(err.notes[0].file, "diagnostics.rs");
+    assert_eq!(span_text(&source, err.notes[0].span), "vec2");
+    let note = format!("\n   note: diagnostics.rs: 7:{} - expected `vec2` because of this", err.notes[0].span.start.column + 1);
+    assert!(err.to_string().ends_with(&note), "{}", err);
+}
+
+#[test]
+fn test_argument_type_notes() {
+    let (source, err) = analyse_error("
+        fn scale(v: vec2, s: float) -> vec2 {
+            return v * s;
+        }
+        fn vertex(self) -> vec4 {
+            return vec4(0.0);
+        }
+        fn pixel(self) -> vec4 {
+            let v = scale(vec2(1.0), vec2(2.0));
+            return vec4(v, 0.0, 1.0);
+        }
+    ");
+    assert_eq!(err.message, "function: `scale`: wrong type for argument 2 expected `float`, got `vec2`");
+    assert_eq!(span_text(&source, err.span), "vec2");
+    let notes: Vec<_> = err.notes.iter().map( | note | note.message.as_str()).collect();
+    assert_eq!(notes, ["parameter `s` is declared as `float` here", "function `scale` is defined here"]);
+    assert_eq!(span_text(&source, err.notes[0].span), "s");
+}
@@ -1,3 +1,13 @@
+use makepad_shader_compiler::{
+    interpret::*,
+    makepad_live_compiler::*,
+    makepad_live_id::*,
+    makepad_math::*,
+    DrawShaderPtr,
+    ShaderRegistry,
+    ShaderTy,
+};
+
 /*const SOURCE: &'static str = r#"
     DrawQuad: DrawShader {
         uniform uni1: float
@@ -472,3 +482,179 @@ fn main() {
 #[test]
 fn main() {
 }
+
+struct TestShader;
+
+const LOOP_SOURCE: &str = r#"
+    Shader = {{TestShader}} {
+        geometry geom_pos: vec2
+        uniform stops: [#f00, #0f0, #00f, #fff]
+        instance t: float
+
+        fn vertex(self) -> vec4 {
+            return vec4(self.geom_pos, 0.0, 1.0);
+        }
+
+        fn pixel(self) -> vec4 {
+            let i = 0;
+            while float(i) < self.t * 3.0 {
+                i = i + 1;
+            }
+            let steps = 0.0;
+            loop {
+                steps += 1.0;
+                if steps > 2.5 {
+                    break;
+                }
+            }
+            let first = self.stops[0];
+            return vec4(self.stops[i].xyz + first.yzw, steps / 4.0);
+        }
+    }
+"#;
+
+fn analyse_loops() -> (ShaderRegistry, DrawShaderPtr) {
+    analyse(LOOP_SOURCE).unwrap_or_else( | err | panic!("{}", err))
+}
+
+fn analyse(source: &str) -> Result<(ShaderRegistry, DrawShaderPtr), String> {
+    let module_id = LiveModuleId::from_str("test").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file("integration.rs", "", module_id, source.to_string(), vec![LiveTypeInfo {
+        live_type: LiveType::of::<TestShader>(),
+        type_name: live_id!(TestShader),
+        module_id,
+        live_ignore: true,
+        fields: Vec::new()
+    }], TextPos::default()).unwrap_or_else( | err | panic!("{}", err));
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let mut shader_registry = ShaderRegistry::new();
+    let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(Shader)).unwrap());
+    shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, _, _, _, _ | {})
+        .map_err( | err | live_registry.live_error_to_live_file_error(err).to_string())?;
+    Ok((shader_registry, draw_shader_ptr))
+}
+
+#[test]
+fn test_loops_and_uniform_arrays() {
+    let (shader_registry, draw_shader_ptr) = analyse_loops();
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let stops_ty = ShaderTy::Array {elem_ty: std::rc::Rc::new(ShaderTy::Vec4), len: 4};
+    let field = draw_shader_def.fields.iter().find( | field | field.ident.0 == live_id!(stops)).unwrap();
+    assert_eq!(field.ty_expr.ty.borrow().as_ref(), Some(&stops_ty));
+
+    // stops set from rust are tightly packed vec4s
+    let stops = [1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+    let mut inputs = ShaderInputs::default();
+    inputs.fields.insert(live_id!(geom_pos), ShaderValue::vec2(vec2(0.0, 0.0)));
+    inputs.fields.insert(live_id!(stops), ShaderValue::from_slots(stops_ty, &stops));
+    inputs.fields.insert(live_id!(t), ShaderValue::float(0.5));
+    let interpreter = ShaderInterpreter::new(&shader_registry, draw_shader_def);
+    interpreter.vertex(&mut inputs).unwrap();
+
+    // the while loop stops at the third stop and the loop breaks after three steps
+    let color = interpreter.pixel(0, 0, &| _, _ | inputs.clone()).unwrap();
+    assert_eq!(color, vec4(0.0, 0.0, 2.0, 0.75));
+}
+
+fn uniforms_source(uniforms: &str) -> String {
+    format!(r#"
+        Shader = {{{{TestShader}}}} {{
+            geometry geom_pos: vec2
+            {}
+
+            fn vertex(self) -> vec4 {{
+                return vec4(self.geom_pos, 0.0, 1.0);
+            }}
+
+            fn pixel(self) -> vec4 {{
+                return #f;
+            }}
+        }}
+    "#, uniforms)
+}
+
+#[test]
+fn test_user_uniforms_fit_in_a_draw_call() {
+    // 7 stops and a float take 29 of the 32 floats a draw call has
+    let stops = ["#fff"; 7].join(", ");
+    assert!(analyse(&uniforms_source(&format!("uniform stops: [{}]\n uniform scale: float", stops))).is_ok());
+
+    // an array starts on a new row, so after a float 8 stops need 36 floats
+    let stops = ["#fff"; 8].join(", ");
+    let Err(err) = analyse(&uniforms_source(&format!("uniform scale: float\n uniform stops: [{}]", stops))) else {
+        panic!("8 stops after a float don't fit")
+    };
+    assert!(err.contains("Uniforms need more than the 32 floats a draw call has"), "{}", err);
+    // a vec3 takes 4 floats, so 8 of them and a float need 33
+    let vec3s: String = (0..8).map( | i | format!("uniform v{}: vec3\n", i)).collect();
+    let Err(err) = analyse(&uniforms_source(&format!("{} uniform scale: float", vec3s))) else {
+        panic!("8 vec3s and a float don't fit")
+    };
+    assert!(err.contains("32 floats"), "{}", err);
+}
+
+#[cfg(any(target_os = "android", target_os = "linux", target_arch = "wasm32"))]
+#[test]
+fn test_glsl_loops_and_uniform_arrays() {
+    use makepad_shader_compiler::generate_glsl;
+    let (shader_registry, draw_shader_ptr) = analyse_loops();
+    let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let pixel = generate_glsl::generate_pixel_shader(draw_shader_def, &const_table, &shader_registry);
+
+    // the array is unpacked element by element from the flat uniform table
+    assert!(pixel.contains("uniform float user_table[16];"), "{}", pixel);
+    assert!(pixel.contains("vec4 ds_stops[4];"), "{}", pixel);
+    assert!(pixel.contains("ds_stops[2] = vec4(user_table[8], user_table[9], user_table[10], user_table[11]);"), "{}", pixel);
+    // while and loop become bounded for loops
+    assert!(!pixel.contains("while"), "{}", pixel);
+    assert_eq!(pixel.matches(&format!("< {}; ", generate_glsl::GLSL_MAX_LOOP_ITERATIONS)).count(), 2, "{}", pixel);
+    // dynamic indices select between constant ones, constant indices are kept
+    assert!(pixel.contains("(int(var_i_0) == 2 ? ds_stops[2] : ds_stops[3])"), "{}", pixel);
+    assert!(pixel.contains("ds_stops[0];"), "{}", pixel);
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "tvos"))]
+#[test]
+fn test_metal_loops_and_uniform_arrays() {
+    use makepad_shader_compiler::generate_metal;
+    let (shader_registry, draw_shader_ptr) = analyse_loops();
+    let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let shader = generate_metal::generate_shader(draw_shader_def, &const_table, &shader_registry).mtlsl;
+
+    assert!(shader.contains("float4 ds_stops[4];"), "{}", shader);
+    assert!(shader.contains("while ("), "{}", shader);
+    assert!(shader.contains("while (true)"), "{}", shader);
+}
+
+#[cfg(target_os = "windows")]
+#[test]
+fn test_hlsl_loops_and_uniform_arrays() {
+    use makepad_shader_compiler::generate_hlsl;
+    let (shader_registry, draw_shader_ptr) = analyse_loops();
+    let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let shader = generate_hlsl::generate_shader(draw_shader_def, &const_table, &shader_registry);
+
+    assert!(shader.contains("float4 ds_stops[4];"), "{}", shader);
+    assert!(shader.contains("while ("), "{}", shader);
+    assert!(shader.contains("while (true)"), "{}", shader);
+}
+
+#[test]
+fn test_wgsl_loops_and_uniform_arrays() {
+    use makepad_shader_compiler::generate_wgsl;
+    let (shader_registry, draw_shader_ptr) = analyse_loops();
+    let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let shader = generate_wgsl::generate_shader(draw_shader_def, &const_table, &shader_registry).wgsl;
+
+    assert!(shader.contains("ds_stops: array<vec4<f32>, 4>,"), "{}", shader);
+    assert!(shader.contains("while ("), "{}", shader);
+    assert!(shader.contains("while (true)"), "{}", shader);
+}
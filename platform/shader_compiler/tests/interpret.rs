@@ -0,0 +1,172 @@
+use makepad_shader_compiler::{
+    interpret::*,
+    makepad_live_compiler::*,
+    makepad_live_id::*,
+    makepad_math::*,
+    rasterize::SoftwareFramebuffer,
+    DrawShaderPtr,
+    ShaderRegistry,
+};
+
+struct TestShader;
+
+const SOURCE: &str = r#"
+    Shader = {{TestShader}} {
+        geometry geom_pos: vec2
+        instance rect: vec4
+        instance fill: vec4
+        instance mode: float
+        varying pos: vec2
+        texture image: texture2d
+
+        Acc = struct {
+            field sum: vec2
+            field count: float
+
+            fn add(inout self, v: vec2) {
+                self.sum += v;
+                self.count += 1.0;
+            }
+        }
+
+        fn apply(self, v: float, f: fn(x: float) -> float) -> float {
+            return f(v);
+        }
+
+        fn vertex(self) -> vec4 {
+            self.pos = self.geom_pos;
+            let p = self.rect.xy + self.geom_pos * self.rect.zw;
+            return vec4(p.x * 2.0 - 1.0, 1.0 - p.y * 2.0, 0.0, 1.0);
+        }
+
+        fn pixel(self) -> vec4 {
+            if self.mode < 0.5 {
+                return self.fill;
+            }
+            if self.mode < 1.5 {
+                let acc = Acc {sum: vec2(0.0), count: 0.0};
+                for i in 0..10 {
+                    if float(i) == 2.0 {
+                        continue;
+                    }
+                    if float(i) == 5.0 {
+                        break;
+                    }
+                    acc.add(vec2(float(i), 1.0));
+                }
+                let scale = 3.0;
+                let k = self.apply(acc.count, | x | x * scale + 1.0);
+                let m = mat2(1.0, 2.0, 3.0, 4.0) * vec2(1.0, 1.0);
+                let c = vec4(0.0);
+                c.xz = vec2(acc.sum.x, k);
+                c.yw = m;
+                return c;
+            }
+            if self.mode < 2.5 {
+                return vec4(dFdx(self.pos.x), dFdy(self.pos.y), 0.0, 1.0);
+            }
+            return sample2d(self.image, self.pos);
+        }
+    }
+"#;
+
+fn analyse() -> (ShaderRegistry, DrawShaderPtr) {
+    let module_id = LiveModuleId::from_str("test").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file("interpret.rs", "", module_id, SOURCE.to_string(), vec![LiveTypeInfo {
+        live_type: LiveType::of::<TestShader>(),
+        type_name: live_id!(TestShader),
+        module_id,
+        live_ignore: true,
+        fields: Vec::new()
+    }], TextPos::default()).unwrap_or_else( | err | panic!("{}", err));
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let mut shader_registry = ShaderRegistry::new();
+    let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(Shader)).unwrap());
+    shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, _, _, _, _ | {})
+        .unwrap_or_else( | err | panic!("{}", live_registry.live_error_to_live_file_error(err)));
+    (shader_registry, draw_shader_ptr)
+}
+
+// the four corners of a quad over `rect` (in 0..1 of the framebuffer), as two triangles
+fn quad(rect: Vec4, fill: Vec4, mode: f32) -> (Vec<ShaderInputs>, Vec<u32>) {
+    let vertices = [vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)].iter().map( | geom_pos | {
+        let mut inputs = ShaderInputs::default();
+        inputs.fields.insert(live_id!(geom_pos), ShaderValue::vec2(*geom_pos));
+        inputs.fields.insert(live_id!(rect), ShaderValue::vec4(rect));
+        inputs.fields.insert(live_id!(fill), ShaderValue::vec4(fill));
+        inputs.fields.insert(live_id!(mode), ShaderValue::float(mode));
+        inputs
+    }).collect();
+    (vertices, vec![0, 1, 2, 2, 3, 0])
+}
+
+#[test]
+fn test_interpret_language() {
+    let (shader_registry, draw_shader_ptr) = analyse();
+    let interpreter = ShaderInterpreter::new(&shader_registry, shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap());
+    let (vertices, _) = quad(vec4(0.0, 0.0, 1.0, 1.0), Vec4::default(), 1.0);
+
+    let mut inputs = vertices[2].clone();
+    let position = interpreter.vertex(&mut inputs).unwrap();
+    assert_eq!(position, vec4(1.0, -1.0, 0.0, 1.0));
+    assert_eq!(inputs.fields[&live_id!(pos)], ShaderValue::vec2(vec2(1.0, 1.0)));
+
+    // the loop skips 2 and stops at 5, the closure reads a local of its caller
+    // and the matrix is column-major
+    let color = interpreter.pixel(0, 0, &| _, _ | inputs.clone()).unwrap();
+    assert_eq!(color, vec4(8.0, 4.0, 13.0, 6.0));
+}
+
+#[test]
+fn test_rasterize_blending_and_coverage() {
+    let (shader_registry, draw_shader_ptr) = analyse();
+    let interpreter = ShaderInterpreter::new(&shader_registry, shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap());
+    let mut framebuffer = SoftwareFramebuffer::new(8, 8, Vec4::default());
+    let rect = vec4(0.25, 0.25, 0.5, 0.5);
+    for fill in [vec4(0.5, 0.0, 0.0, 0.5), vec4(0.0, 0.5, 0.0, 0.5)] {
+        let (vertices, indices) = quad(rect, fill, 0.0);
+        framebuffer.draw_triangles(&interpreter, &vertices, &indices).unwrap();
+    }
+    for y in 0..8 {
+        for x in 0..8 {
+            // pixels on the diagonal shared by the triangles are drawn once
+            let expected = if (2..6).contains(&x) && (2..6).contains(&y) {
+                vec4(0.25, 0.5, 0.0, 0.75)
+            }
+            else {
+                Vec4::default()
+            };
+            assert_eq!(framebuffer.pixel(x, y), expected, "pixel {} {}", x, y);
+        }
+    }
+    assert_eq!(&framebuffer.to_rgba8()[(2 * 8 + 2) * 4..][..4], &[64, 128, 0, 191]);
+}
+
+#[test]
+fn test_rasterize_derivatives_and_textures() {
+    let (shader_registry, draw_shader_ptr) = analyse();
+    let mut interpreter = ShaderInterpreter::new(&shader_registry, shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap());
+    let full = vec4(0.0, 0.0, 1.0, 1.0);
+
+    let mut framebuffer = SoftwareFramebuffer::new(4, 4, Vec4::default());
+    let (vertices, indices) = quad(full, Vec4::default(), 2.0);
+    framebuffer.draw_triangles(&interpreter, &vertices, &indices).unwrap();
+    for color in &framebuffer.color {
+        assert!((color.x - 0.25).abs() < 1e-5 && (color.y - 0.25).abs() < 1e-5, "{:?}", color);
+    }
+
+    let texels = [vec4(1.0, 0.0, 0.0, 1.0), vec4(0.0, 1.0, 0.0, 1.0), vec4(0.0, 0.0, 1.0, 1.0), vec4(1.0, 1.0, 1.0, 1.0)];
+    interpreter.textures = vec![Some(ShaderTexture {width: 2, height: 2, pixels: texels.to_vec()})];
+    let mut framebuffer = SoftwareFramebuffer::new(4, 4, Vec4::default());
+    let (vertices, indices) = quad(full, Vec4::default(), 3.0);
+    framebuffer.draw_triangles(&interpreter, &vertices, &indices).unwrap();
+    for y in 0..4 {
+        for x in 0..4 {
+            assert_eq!(framebuffer.pixel(x, y), texels[(y / 2) * 2 + x / 2], "pixel {} {}", x, y);
+        }
+    }
+}
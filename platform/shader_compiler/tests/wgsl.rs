@@ -0,0 +1,189 @@
+use makepad_shader_compiler::{
+    generate_wgsl,
+    makepad_live_compiler::*,
+    makepad_live_id::*,
+    DrawShaderPtr,
+    DrawShaderQuery,
+    ShaderEnum,
+    ShaderRegistry,
+    ShaderTy,
+};
+
+struct TestShader;
+struct Shape;
+
+const SOURCE: &str = r#"
+    OFFSET = 0.5
+
+    Shader = {{TestShader}} {
+        geometry geom_pos: vec2
+        uniform tint: vec4
+        instance scale: float
+        varying uv: vec2
+        texture image: texture2d
+
+        Acc = struct {
+            field sum: vec2
+            field count: float
+
+            fn add(inout self, v: vec2) {
+                self.sum += v;
+                self.count += 1.0;
+            }
+        }
+
+        fn blend(self, c: fn(x: float) -> float) -> float {
+            return c(self.scale);
+        }
+
+        fn vertex(self) -> vec4 {
+            self.uv = self.geom_pos;
+            let p = self.transform * vec3(self.geom_pos, 1.0);
+            return vec4(p.xy * self.scale, 0.0, 1.0);
+        }
+
+        fn pixel(self) -> vec4 {
+            let acc = Acc {sum: vec2(0.0), count: 0.0};
+            for i in 0..4 {
+                acc.add(self.uv * float(i));
+            }
+            let offset = OFFSET;
+            let b = self.blend( | x | x * offset + 1.0);
+            let color = sample2d(self.image, mod(acc.sum, 1.0));
+            color.rg = min(color.rg, 0.5);
+            match self.shape {
+                Shape::Box => {
+                    color.a = b;
+                }
+                Shape::Circle => {
+                    color.a = self.scale > 0.5 ? 1.0 : 0.0;
+                }
+            }
+            return color * self.tint;
+        }
+    }
+"#;
+
+fn generate() -> String {
+    let module_id = LiveModuleId::from_str("test").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file("wgsl.rs", "", module_id, SOURCE.to_string(), vec![LiveTypeInfo {
+        live_type: LiveType::of::<TestShader>(),
+        type_name: live_id!(TestShader),
+        module_id,
+        live_ignore: true,
+        fields: Vec::new()
+    }], TextPos::default()).unwrap_or_else( | err | panic!("{}", err));
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let mut shader_registry = ShaderRegistry::new();
+    shader_registry.register_enum(LiveType::of::<Shape>(), ShaderEnum {
+        enum_name: live_id!(Shape),
+        variants: vec![live_id!(Box), live_id!(Circle)]
+    });
+    let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(Shader)).unwrap());
+    shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, span, query, _, draw_shader_def | {
+        if let DrawShaderQuery::DrawShader = query {
+            draw_shader_def.add_instance(live_id!(transform), ShaderTy::Mat3, span, LiveFieldKind::Calc);
+            draw_shader_def.add_instance(live_id!(shape), ShaderTy::Enum(LiveType::of::<Shape>()), span, LiveFieldKind::Calc);
+        }
+    }).unwrap_or_else( | err | panic!("{}", live_registry.live_error_to_live_file_error(err)));
+
+    let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    generate_wgsl::generate_shader(draw_shader_def, &const_table, &shader_registry).wgsl
+}
+
+// the identifiers directly followed by an opening parenthesis
+fn called_idents(wgsl: &str) -> Vec<&str> {
+    let mut idents = Vec::new();
+    let bytes = wgsl.as_bytes();
+    let mut start = None;
+    for (index, byte) in bytes.iter().enumerate() {
+        if byte.is_ascii_alphanumeric() || *byte == b'_' {
+            start.get_or_insert(index);
+            continue
+        }
+        if let Some(start) = start.take() {
+            if *byte == b'(' && !bytes[start].is_ascii_digit() {
+                idents.push(&wgsl[start..index]);
+            }
+        }
+    }
+    idents
+}
+
+#[test]
+fn test_wgsl_structure() {
+    let wgsl = generate();
+
+    // every call goes to a function in the module or a WGSL builtin
+    let defined: Vec<_> = wgsl.split("fn ").skip(1).map( | rest | rest.split('(').next().unwrap()).collect();
+    for ident in called_idents(&wgsl) {
+        let is_builtin = matches!(ident,
+            "vec2" | "vec3" | "vec4" | "mat2x2" | "mat3x3" | "mat4x4" | "f32" | "i32" | "u32" | "bool" | "array"
+                | "location" | "builtin" | "group" | "binding" | "interpolate" | "select" | "transpose" | "min"
+                | "floor" | "textureSampleLevel" | "if" | "for"
+        ) || ident.starts_with("struct_");
+        assert!(is_builtin || defined.contains(&ident), "call to undefined `{}` in\n{}", ident, wgsl);
+    }
+    // the constants all fit in the const table
+    let table_len: usize = wgsl.split("array<vec4<f32>, ").nth(1).unwrap().split('>').next().unwrap().parse().unwrap();
+    for index in wgsl.split("const_table[").skip(1) {
+        assert!(index.split(']').next().unwrap().parse::<usize>().unwrap() < table_len);
+    }
+    for (open, close) in [('{', '}'), ('(', ')'), ('[', ']')] {
+        assert_eq!(wgsl.matches(open).count(), wgsl.matches(close).count(), "unbalanced {}{} in\n{}", open, close, wgsl);
+    }
+    // none of the C-like syntax of the other backends
+    for c_ism in ["inout", "float ", "vec4 ", "int ", "?", "void", "uniform "] {
+        assert!(!wgsl.contains(c_ism), "`{}` in\n{}", c_ism, wgsl);
+    }
+}
+
+#[test]
+fn test_wgsl_features() {
+    let wgsl = generate();
+    let expect = | fragment: &str | assert!(wgsl.contains(fragment), "no `{}` in\n{}", fragment, wgsl);
+
+    // entry points and io
+    expect("@vertex\nfn vertex_main(in_geometries: Geometries, in_instances: Instances) -> Varyings {");
+    expect("@fragment\nfn fragment_main(in_varyings: Varyings) -> @location(0) vec4<f32> {");
+    expect("    @location(0) ds_geom_pos: vec2<f32>,");
+    expect("@builtin(position) position: vec4<f32>,");
+    expect("@interpolate(flat) ds_shape: u32,");
+    expect("transpose(mat3x3<f32>(instances.ds_transform0, instances.ds_transform1, instances.ds_transform2))");
+
+    // resources
+    expect("@group(0) @binding(0) var<uniform> live_uniforms: LiveUniforms;");
+    expect("var<uniform> uniforms_user: Uniforms_user;");
+    expect("@group(1) @binding(0) var default_sampler: sampler;");
+    expect("@group(1) @binding(1) var ds_image: texture_2d<f32>;");
+    expect("textureSampleLevel(x0, default_sampler, x1, 0.0)");
+
+    // inout parameters are pointers that are copied in and out
+    expect(": ptr<function, struct_");
+    expect("*p_var_self_0 = var_self_0;");
+    expect("(&var_acc_");
+
+    // closures, loops, matches and polyfilled builtins
+    expect("fn closure_0_in_");
+    expect("for (var var_i_0: i32 = 0; var_i_0 < 4; var_i_0 += 1)");
+    expect("if(varyings.ds_shape == 1){");
+    expect("fn sample2d_texture2D_vec2(x0: texture_2d<f32>, x1: vec2<f32>) -> vec4<f32> {");
+    expect("select(const_table[2].y, const_table[2].x, (varyings.ds_scale > const_table[1].w))");
+    expect("{let swizzle_val = min_vec2_float(var_color_0.rg, const_table[1].z); var_color_0.x = swizzle_val.x; var_color_0.y = swizzle_val.y;}");
+    expect("fn mod_vec2_float(x0: vec2<f32>, x1: f32) -> vec2<f32> {");
+}
+
+#[test]
+fn test_wgsl_validates() {
+    let wgsl = generate();
+    let module = naga::front::wgsl::parse_str(&wgsl)
+        .unwrap_or_else( | err | panic!("{}\n{}", err.emit_to_string(&wgsl), wgsl));
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+        .validate(&module)
+        .unwrap_or_else( | err | panic!("{}\n{}", err.emit_to_string(&wgsl), wgsl));
+}
@@ -16,6 +16,18 @@ macro_rules!app_main {
                 return
             }
             
+            if std::env::args().any(|v| v == "--check-shaders") {
+                let mut cx = Cx::new(Box::new(|_, _| {}));
+                live_design(&mut cx);
+                cx.check_shaders_and_exit();
+            }
+            
+            if std::env::args().any(|v| v == "--live-language-server") {
+                let mut cx = Cx::new(Box::new(|_, _| {}));
+                live_design(&mut cx);
+                cx.live_language_server_and_exit();
+            }
+            
             let app = std::rc::Rc::new(std::cell::RefCell::new(None));
             let mut cx = std::rc::Rc::new(std::cell::RefCell::new(Cx::new(Box::new(move | cx, event | {
                 if let Event::Startup = event {
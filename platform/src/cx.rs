@@ -25,6 +25,7 @@ use {
         event::{
             DrawEvent,
             CxFingers,
+            CxGestures,
             CxDragDrop,
             Event,
             Trigger,
@@ -81,6 +82,7 @@ pub struct Cx {
     
     pub keyboard: CxKeyboard,
     pub fingers: CxFingers,
+    pub gestures: CxGestures,
     pub (crate) ime_area: Area,
     pub (crate) drag_drop: CxDragDrop,
     
@@ -240,6 +242,7 @@ impl Cx {
             
             keyboard: Default::default(),
             fingers: Default::default(),
+            gestures: Default::default(),
             drag_drop: Default::default(),
             ime_area: Default::default(),
             platform_ops: Default::default(),
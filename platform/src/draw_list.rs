@@ -124,7 +124,7 @@ impl DrawUniforms {
  
 pub enum CxDrawKind{
     SubList(DrawListId),
-    DrawCall(CxDrawCall),
+    DrawCall(Box<CxDrawCall>),
     Empty
 }
 
@@ -355,7 +355,7 @@ impl CxDrawList {
     pub fn append_draw_call(&mut self, redraw_id: u64, sh: &CxDrawShader, draw_vars: &DrawVars) -> &mut CxDrawItem {
         self.draw_items.push_item(
             redraw_id,
-            CxDrawKind::DrawCall(CxDrawCall::new(&sh.mapping, draw_vars))
+            CxDrawKind::DrawCall(Box::new(CxDrawCall::new(&sh.mapping, draw_vars)))
         )
     }
     
@@ -8,11 +8,13 @@ use {
         },
     },
     crate::{
-        makepad_live_compiler::{LiveValue, LiveFieldKind, LiveNode, LivePtr, LiveNodeSliceApi},
+        makepad_live_compiler::{LiveValue, LiveFieldKind, LiveNode, LivePtr, LiveNodeSliceApi, LiveRegistry, LiveType, TokenSpan},
+        makepad_math::*,
         makepad_shader_compiler::*,
         makepad_live_id::*,
         live_traits::*,
         draw_vars::DrawVars,
+        geometry::{GeometryField, GeometryFields},
         os::CxOsDrawShader,
         cx::Cx
     }
@@ -69,6 +71,7 @@ pub struct CxDrawShaders {
     pub fingerprints: Vec<DrawShaderFingerprint>,
     pub error_set: HashSet<DrawShaderPtr>,
     pub error_fingerprints: Vec<Vec<LiveNode >>,
+    pub geometry_fields: HashMap<LiveType, Vec<GeometryField >>,
 }
 
 impl CxDrawShaders{
@@ -78,6 +81,97 @@ impl CxDrawShaders{
         self.error_set.clear();
         self.error_fingerprints.clear();
     }
+    
+    /// Remembers the geometry fields of a geometry type, so shaders drawn with it
+    /// can be analysed without an instance of it, see `Cx::check_shaders`.
+    pub fn register_geometry_fields(&mut self, geometry_fields: &dyn GeometryFields) {
+        let mut fields = Vec::new();
+        geometry_fields.geometry_fields(&mut fields);
+        self.geometry_fields.insert(geometry_fields.live_type_check(), fields);
+    }
+    
+    /// Answers the queries `ShaderRegistry::analyse_draw_shader` makes about the
+    /// rust side of a draw shader. Returns false for a geometry type that wasn't
+    /// registered.
+    pub fn answer_draw_shader_query(
+        &self,
+        live_registry: &LiveRegistry,
+        shader_registry: &ShaderRegistry,
+        span: TokenSpan,
+        draw_shader_query: DrawShaderQuery,
+        live_type: LiveType,
+        draw_shader_def: &mut DrawShaderDef
+    ) -> bool {
+        fn live_type_to_shader_ty(live_type: LiveType) -> Option<ShaderTy> {
+            if live_type == LiveType::of::<f32>() {Some(ShaderTy::Float)}
+            else if live_type == LiveType::of::<Vec2>() {Some(ShaderTy::Vec2)}
+            else if live_type == LiveType::of::<Vec3>() {Some(ShaderTy::Vec3)}
+            else if live_type == LiveType::of::<Vec4>() {Some(ShaderTy::Vec4)}
+            else {None}
+        }
+        match draw_shader_query {
+            DrawShaderQuery::DrawShader => {
+                fn recur_expand(
+                    live_registry: &LiveRegistry,
+                    shader_registry: &ShaderRegistry,
+                    level: usize,
+                    after_draw_vars: &mut bool,
+                    live_type: LiveType,
+                    draw_shader_def: &mut DrawShaderDef,
+                    span: TokenSpan
+                ) {
+                    if let Some(lf) = live_registry.live_type_infos.get(&live_type) {
+                        
+                        let mut slots = 0;
+                        for field in &lf.fields {
+                            if let LiveFieldKind::Deref = field.live_field_kind {
+                                if field.live_type_info.live_type != LiveType::of::<DrawVars>() {
+                                    recur_expand(live_registry, shader_registry, level + 1, after_draw_vars, field.live_type_info.live_type, draw_shader_def, span);
+                                    continue
+                                }
+                                else{
+                                    *after_draw_vars = true;
+                                    continue
+                                }
+                            }
+                            if *after_draw_vars {
+                                // lets count sizes
+                                //
+                                let live_type = field.live_type_info.live_type;
+                                if shader_registry.enums.contains_key(&live_type) {
+                                    slots += 1;
+                                    //draw_shader_def.enums
+                                    
+                                    draw_shader_def.add_instance(field.id, ShaderTy::Enum(live_type), span, field.live_field_kind);
+                                }
+                                else {
+                                    let ty = live_type_to_shader_ty(live_type).expect("Please only put shader-understandable instance fields after draw_vars");
+                                    slots += ty.slots();
+                                    draw_shader_def.add_instance(field.id, ty, span, field.live_field_kind);
+                                }
+                            }
+                        }
+                        // insert padding
+                        if level >0 && slots % 2 == 1 {
+                            draw_shader_def.add_instance(LiveId(0), ShaderTy::Float, span, LiveFieldKind::Calc);
+                        }
+                    }
+                }
+                recur_expand(live_registry, shader_registry, 0, &mut false, live_type, draw_shader_def, span);
+            }
+            DrawShaderQuery::Geometry => {
+                if let Some(fields) = self.geometry_fields.get(&live_type) {
+                    for field in fields {
+                        draw_shader_def.add_geometry(field.id, field.ty.clone(), span);
+                    }
+                }
+                else {
+                    return false
+                }
+            }
+        }
+        true
+    }
 }
 
 impl Cx {
@@ -217,6 +311,21 @@ impl DrawShaderInputs {
                 });
                 self.total_slots += slots;
             }
+            DrawShaderInputPacking::UniformsHLSL |
+            DrawShaderInputPacking::UniformsMetal if matches!(ty, ShaderTy::Array {..}) => {
+                // arrays start on a new 16 byte row, their vec4 elements fill whole rows
+                if self.total_slots & 3 > 0 {
+                    self.total_slots += 4 - (self.total_slots & 3);
+                }
+                self.inputs.push(DrawShaderInput {
+                    id,
+                    offset: self.total_slots,
+                    slots,
+                    ty,
+                    live_ptr
+                });
+                self.total_slots += slots;
+            }
             DrawShaderInputPacking::UniformsHLSL => {
                 if (self.total_slots & 3) + slots > 4 { // goes over the boundary
                     self.total_slots += 4 - (self.total_slots & 3); // make jump to new slot
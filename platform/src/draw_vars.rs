@@ -1,14 +1,10 @@
 use {
     crate::{
         makepad_live_compiler::{
-            LiveRegistry,
-            TokenSpan,
             LivePtr,
             LiveValue,
             LiveTypeInfo,
-            LiveFieldKind,
             LiveModuleId,
-            LiveType,
             LiveId,
             LiveNode,
             LiveNodeSliceApi
@@ -80,7 +76,7 @@ pub const fn shader_enum(i: u32) -> u32 {
     i
 }
 
-pub const DRAW_CALL_USER_UNIFORMS: usize = 16;
+pub const DRAW_CALL_USER_UNIFORMS: usize = MAX_USER_UNIFORM_SLOTS;
 pub const DRAW_CALL_TEXTURE_SLOTS: usize = 4;
 pub const DRAW_CALL_VAR_INSTANCES: usize = 32;
 
@@ -191,81 +187,16 @@ impl DrawVars {
                 return;
             }
 
-            fn live_type_to_shader_ty(live_type: LiveType) -> Option<ShaderTy> {
-                if live_type == LiveType::of::<f32>() {Some(ShaderTy::Float)}
-                else if live_type == LiveType::of::<Vec2>() {Some(ShaderTy::Vec2)}
-                else if live_type == LiveType::of::<Vec3>() {Some(ShaderTy::Vec3)}
-                else if live_type == LiveType::of::<Vec4>() {Some(ShaderTy::Vec4)}
-                else {None}
-            }
             // ok ! we have to compile it
             //let live_factories = &cx.live_factories;
             let live_registry_cp = cx.live_registry.clone();
             let live_registry = live_registry_cp.borrow();
             
+            cx.draw_shaders.register_geometry_fields(geometry_fields);
+            let draw_shaders = &cx.draw_shaders;
             let result = cx.shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | live_registry, shader_registry, span, draw_shader_query, live_type, draw_shader_def | {
-                match draw_shader_query {
-                    DrawShaderQuery::DrawShader => {
-                        fn recur_expand(
-                            live_registry: &LiveRegistry,
-                            shader_registry: &ShaderRegistry,
-                            level: usize,
-                            after_draw_vars: &mut bool,
-                            live_type: LiveType,
-                            draw_shader_def: &mut DrawShaderDef,
-                            span: TokenSpan
-                        ) {
-                            if let Some(lf) = live_registry.live_type_infos.get(&live_type) {
-                                
-                                let mut slots = 0;
-                                for field in &lf.fields {
-                                    if let LiveFieldKind::Deref = field.live_field_kind {
-                                        if field.live_type_info.live_type != LiveType::of::<DrawVars>() {
-                                            recur_expand(live_registry, shader_registry, level + 1, after_draw_vars, field.live_type_info.live_type, draw_shader_def, span);
-                                            continue
-                                        }
-                                        else{
-                                            *after_draw_vars = true;
-                                            continue
-                                        }
-                                    }
-                                    if *after_draw_vars {
-                                        // lets count sizes
-                                        //
-                                        let live_type = field.live_type_info.live_type;
-                                        if shader_registry.enums.get(&live_type).is_some() {
-                                            slots += 1;
-                                            //draw_shader_def.enums
-                                            
-                                            draw_shader_def.add_instance(field.id, ShaderTy::Enum(live_type), span, field.live_field_kind);
-                                        }
-                                        else {
-                                            let ty = live_type_to_shader_ty(live_type).expect("Please only put shader-understandable instance fields after draw_vars");
-                                            slots += ty.slots();
-                                            draw_shader_def.add_instance(field.id, ty, span, field.live_field_kind);
-                                        }
-                                    }
-                                }
-                                // insert padding
-                                if level >0 && slots % 2 == 1 {
-                                    draw_shader_def.add_instance(LiveId(0), ShaderTy::Float, span, LiveFieldKind::Calc);
-                                }
-                            }
-                        }
-                        recur_expand(live_registry, shader_registry, 0, &mut false, live_type, draw_shader_def, span);
-                    }
-                    DrawShaderQuery::Geometry => {
-                        if live_type == geometry_fields.live_type_check() {
-                            let mut fields = Vec::new();
-                            geometry_fields.geometry_fields(&mut fields);
-                            for field in fields {
-                                draw_shader_def.add_geometry(field.id, field.ty, span);
-                            }
-                        }
-                        else {
-                            eprintln!("lf.get_type() != geometry_fields.live_type_check()");
-                        }
-                    }
+                if !draw_shaders.answer_draw_shader_query(live_registry, shader_registry, span, draw_shader_query, live_type, draw_shader_def) {
+                    eprintln!("lf.get_type() != geometry_fields.live_type_check()");
                 }
             });
             // ok lets print an error
@@ -285,6 +216,17 @@ impl DrawVars {
                             err.message,
                             crate::log::LogLevel::Error
                         );
+                        for note in err.notes {
+                            crate::log::log_with_level(
+                                &note.file,
+                                note.span.start.line,
+                                note.span.start.column,
+                                note.span.end.line,
+                                note.span.end.column,
+                                format!("note: {}", note.message),
+                                crate::log::LogLevel::Log
+                            );
+                        }
                     }
                     else{
                         log!("Error {}", err);
@@ -559,6 +501,20 @@ impl DrawVars {
     }
     
     pub fn apply_slots(cx: &mut Cx, slots: usize, output: &mut [f32], offset: usize, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
+        if let LiveValue::Array = nodes[index].value {
+            // uniform arrays hold vec4 elements
+            let mut elem_offset = offset;
+            let mut node_iter = nodes.first_child(index);
+            while let Some(elem_index) = node_iter {
+                if elem_offset + 4 > offset + slots {
+                    break
+                }
+                Self::apply_slots(cx, 4, output, elem_offset, from, elem_index, nodes);
+                elem_offset += 4;
+                node_iter = nodes.next_child(elem_index);
+            }
+            return nodes.skip_node(index)
+        }
         match slots {
             1 => {
                 let mut v: f32 = 0.0;
@@ -10,6 +10,7 @@ use {
         //midi::{Midi1InputData, MidiInputInfo},
         event::{
             finger::*,
+            gesture::*,
             keyboard::*,
             window::*,
             xr::*,
@@ -55,6 +56,7 @@ pub enum Event {
     MouseLeave(MouseLeaveEvent),
     TouchUpdate(TouchUpdateEvent),
     Scroll(ScrollEvent), // this is the MouseWheel / touch scroll event sent by the OS
+    Gesture(GestureEvent), // recognised from the touch/scroll events before it
     
     Timer(TimerEvent),
     
@@ -66,6 +68,7 @@ pub enum Event {
     KeyDown(KeyEvent),
     KeyUp(KeyEvent),
     TextInput(TextInputEvent),
+    TextComposition(TextCompositionEvent),
     TextCopy(TextClipboardEvent),
     TextCut(TextClipboardEvent),
     
@@ -153,6 +156,8 @@ impl Event{
             45=>"TextureHandleReady",
             46=>"MouseLeave",
             47=>"Actions",
+            49=>"Gesture",
+            50=>"TextComposition",
                                                  
             #[cfg(target_arch = "wasm32")]
             48=>"ToWasmMsg",
@@ -218,6 +223,8 @@ impl Event{
             Self::TextureHandleReady(_)=>45,
             Self::MouseLeave(_)=>46,
             Self::Actions(_)=>47,
+            Self::Gesture(_)=>49,
+            Self::TextComposition(_)=>50,
                                      
             #[cfg(target_arch = "wasm32")]
             Self::ToWasmMsg(_)=>48,
@@ -233,6 +240,7 @@ pub enum Hit{
     KeyUp(KeyEvent),
     Trigger(TriggerHitEvent),
     TextInput(TextInputEvent),
+    TextComposition(TextCompositionEvent),
     TextCopy(TextClipboardEvent),
     TextCut(TextClipboardEvent),
     
@@ -244,6 +252,12 @@ pub enum Hit{
     FingerHoverOut(FingerHoverEvent),
     FingerUp(FingerUpEvent),
     
+    Pinch(PinchEvent),
+    Rotate(RotateEvent),
+    Pan(PanEvent),
+    LongPress(LongPressEvent),
+    DoubleTap(DoubleTapEvent),
+    
     Nothing
 }
 
@@ -262,6 +276,7 @@ impl Event{
             Self::MouseMove(_)|
             Self::MouseUp(_)|
             Self::TouchUpdate(_)|
+            Self::Gesture(_)|
             Self::Scroll(_)=>true,
             _=>false
         }
@@ -21,7 +21,8 @@ use {
         makepad_math::*,
         makepad_live_id::{FromLiveId, live_id, live_id_num},
         event::{
-            event::{Event, Hit}
+            event::{Event, Hit},
+            gesture::gesture_hits,
         },
         window::WindowId,
         cx::Cx,
@@ -656,6 +657,9 @@ impl Event {
                     return Hit::TextInput(ti.clone())
                 }
             },
+            Event::TextComposition(tc) if cx.keyboard.has_key_focus(area) => {
+                return Hit::TextComposition(tc.clone())
+            },
             Event::TextCopy(tc) => {
                 if cx.keyboard.has_key_focus(area) {
                     return Hit::TextCopy(tc.clone());
@@ -1037,6 +1041,9 @@ impl Event {
                     return event
                 }
             },
+            Event::Gesture(e) => {
+                return gesture_hits(cx, area, e, &options, hit_test)
+            }
             Event::MouseLeave(e) => {
                 if cx.fingers.test_sweep_lock(options.sweep_area) {
                     return Hit::Nothing;
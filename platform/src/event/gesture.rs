@@ -0,0 +1,827 @@
+use {
+    std::cell::Cell,
+    std::f64::consts::PI,
+    crate::{
+        makepad_math::*,
+        event::{
+            event::{Event, Hit, NextFrame},
+            finger::*,
+        },
+        window::WindowId,
+        cx::Cx,
+        area::Area,
+    },
+};
+
+// Gesture recognition
+//
+// the recognizer watches the raw touch updates (and trackpad scroll/magnify on desktop)
+// and turns them into pinch, rotate, pan, long-press and double-tap gestures. These are sent
+// as an Event::Gesture right after the event that caused them, and hit-tested like fingers
+
+#[derive(Clone, Debug)]
+pub struct GestureConfig {
+    /// how far the fingers have to move together before a pan starts
+    pub pan_distance: f64,
+    /// how much the distance between two fingers has to change (relative) before a pinch starts
+    pub pinch_scale: f64,
+    /// how far two fingers have to turn (in radians) before a rotate starts
+    pub rotate_angle: f64,
+    /// how long a finger has to be held still for a long press
+    pub long_press_time: f64,
+    /// how far a finger can move and still be a tap or long press
+    pub tap_distance: f64,
+    /// how long a finger can be down and still be a tap
+    pub tap_time: f64,
+    /// the maximum time between the two taps of a double tap
+    pub double_tap_time: f64,
+    /// the maximum distance between the two taps of a double tap
+    pub double_tap_distance: f64,
+    /// how long after the last trackpad event a trackpad gesture ends
+    pub trackpad_end_time: f64,
+    /// the scale per unit of control+scroll, which is what trackpad pinches are on most desktops
+    pub scroll_pinch_speed: f64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            pan_distance: 10.0,
+            pinch_scale: 0.05,
+            rotate_angle: 0.1,
+            long_press_time: 0.5,
+            tap_distance: TAP_COUNT_DISTANCE,
+            tap_time: TAP_COUNT_TIME,
+            double_tap_time: 0.3,
+            double_tap_distance: 20.0,
+            trackpad_end_time: 0.15,
+            scroll_pinch_speed: 0.01,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GestureState {
+    Start,
+    Update,
+    End
+}
+
+#[derive(Clone, Debug)]
+pub struct PinchEvent {
+    pub window_id: WindowId,
+    pub state: GestureState,
+    /// the centre between the fingers
+    pub abs: DVec2,
+    pub abs_start: DVec2,
+    /// the distance between the fingers relative to the start of the gesture
+    pub scale: f64,
+    /// the scale relative to the previous event of the gesture
+    pub scale_delta: f64,
+    pub is_trackpad: bool,
+    pub modifiers: KeyModifiers,
+    pub time: f64,
+    pub handled: Cell<Area>,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug)]
+pub struct RotateEvent {
+    pub window_id: WindowId,
+    pub state: GestureState,
+    pub abs: DVec2,
+    pub abs_start: DVec2,
+    /// the angle turned since the start of the gesture, in radians and clockwise on screen
+    pub angle: f64,
+    /// the angle turned since the previous event of the gesture
+    pub angle_delta: f64,
+    pub is_trackpad: bool,
+    pub modifiers: KeyModifiers,
+    pub time: f64,
+    pub handled: Cell<Area>,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug)]
+pub struct PanEvent {
+    pub window_id: WindowId,
+    pub state: GestureState,
+    /// the centre of the fingers
+    pub abs: DVec2,
+    pub abs_start: DVec2,
+    /// the movement since the previous event of the gesture
+    pub delta: DVec2,
+    pub touch_count: usize,
+    pub is_trackpad: bool,
+    pub modifiers: KeyModifiers,
+    pub time: f64,
+    pub handled: Cell<Area>,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug)]
+pub struct LongPressEvent {
+    pub window_id: WindowId,
+    pub abs: DVec2,
+    pub modifiers: KeyModifiers,
+    pub time: f64,
+    pub handled: Cell<Area>,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug)]
+pub struct DoubleTapEvent {
+    pub window_id: WindowId,
+    pub abs: DVec2,
+    pub modifiers: KeyModifiers,
+    pub time: f64,
+    pub handled: Cell<Area>,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug)]
+pub enum GestureEvent {
+    Pinch(PinchEvent),
+    Rotate(RotateEvent),
+    Pan(PanEvent),
+    LongPress(LongPressEvent),
+    DoubleTap(DoubleTapEvent),
+}
+
+/// A trackpad gesture as the OS reports it, only macOS has these
+#[derive(Clone, Debug)]
+pub struct TrackpadGestureEvent {
+    pub window_id: WindowId,
+    pub abs: DVec2,
+    /// the relative change in scale
+    pub magnification: f64,
+    /// the change in angle, in radians and clockwise on screen
+    pub rotation: f64,
+    pub is_end: bool,
+    pub modifiers: KeyModifiers,
+    pub time: f64,
+}
+
+#[derive(Clone, Debug)]
+struct GestureTouch {
+    uid: u64,
+    abs: DVec2,
+}
+
+#[derive(Clone, Debug)]
+struct PanTrack {
+    uids: Vec<u64>,
+    abs_start: DVec2,
+    // the centroid of the fingers the last time we looked, the gesture is the sum of its movement
+    last: DVec2,
+    moved: DVec2,
+    started: bool,
+}
+
+#[derive(Clone, Debug)]
+struct PairTrack {
+    uids: (u64, u64),
+    abs_start: DVec2,
+    abs: DVec2,
+    distance_start: f64,
+    last_angle: f64,
+    scale: f64,
+    angle: f64,
+    pinching: bool,
+    last_scale: f64,
+    rotating: bool,
+    last_rotate: f64,
+}
+
+#[derive(Clone, Debug)]
+struct PressTrack {
+    uid: u64,
+    abs: DVec2,
+    time: f64,
+    valid: bool,
+}
+
+#[derive(Clone, Debug)]
+struct TrackpadTrack {
+    window_id: WindowId,
+    modifiers: KeyModifiers,
+    abs_start: DVec2,
+    abs: DVec2,
+    value: f64,
+    last_time: f64,
+}
+
+/// Turns touch updates and trackpad events into gestures. It does not know about areas,
+/// so it can be fed synthetic events
+#[derive(Default)]
+pub struct GestureRecognizer {
+    pub config: GestureConfig,
+    window_id: Option<WindowId>,
+    modifiers: KeyModifiers,
+    touches: Vec<GestureTouch>,
+    pan: Option<PanTrack>,
+    pair: Option<PairTrack>,
+    long_press: Option<PressTrack>,
+    tap: Option<PressTrack>,
+    last_tap: Option<(DVec2, f64)>,
+    trackpad_pan: Option<TrackpadTrack>,
+    trackpad_pinch: Option<TrackpadTrack>,
+    trackpad_rotate: Option<TrackpadTrack>,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// returns true if there are gestures that can fire or end without further input,
+    /// in which case handle_time should be called
+    pub fn needs_time(&self) -> bool {
+        self.long_press.is_some()
+            || self.trackpad_pan.is_some()
+            || self.trackpad_pinch.is_some()
+            || self.trackpad_rotate.is_some()
+    }
+
+    pub fn handle_touch_update(&mut self, e: &TouchUpdateEvent) -> Vec<GestureEvent> {
+        let mut out = Vec::new();
+        self.window_id = Some(e.window_id);
+        self.modifiers = e.modifiers;
+
+        for t in &e.touches {
+            match t.state {
+                TouchState::Start => {
+                    self.touches.retain( | gt | gt.uid != t.uid);
+                    self.touches.push(GestureTouch {uid: t.uid, abs: t.abs});
+                    // a second finger makes it a multi finger gesture, no tap or long press
+                    if self.touches.len() == 1 {
+                        let press = PressTrack {uid: t.uid, abs: t.abs, time: e.time, valid: true};
+                        self.long_press = Some(press.clone());
+                        self.tap = Some(press);
+                    }
+                    else {
+                        self.long_press = None;
+                        if let Some(tap) = &mut self.tap {
+                            tap.valid = false;
+                        }
+                    }
+                }
+                TouchState::Move | TouchState::Stable | TouchState::Stop => {
+                    if let Some(gt) = self.touches.iter_mut().find( | gt | gt.uid == t.uid) {
+                        gt.abs = t.abs;
+                    }
+                    if let Some(tap) = &mut self.tap {
+                        if tap.uid == t.uid && tap.abs.distance(&t.abs) > self.config.tap_distance {
+                            tap.valid = false;
+                        }
+                    }
+                    if let Some(press) = &self.long_press {
+                        if press.uid == t.uid && press.abs.distance(&t.abs) > self.config.tap_distance {
+                            self.long_press = None;
+                        }
+                    }
+                }
+            }
+        }
+
+        // a finger held long enough fires before we look at it being lifted
+        self.check_long_press(e.time, &mut out);
+        self.update_pan(e.time, &mut out);
+        self.update_pair(e.time, &mut out);
+
+        for t in &e.touches {
+            if let TouchState::Stop = t.state {
+                self.touches.retain( | gt | gt.uid != t.uid);
+                if self.long_press.as_ref().is_some_and( | press | press.uid == t.uid) {
+                    self.long_press = None;
+                }
+                if let Some(tap) = self.tap.take() {
+                    if tap.uid != t.uid {
+                        self.tap = Some(tap);
+                    }
+                    else if tap.valid && e.time - tap.time < self.config.tap_time {
+                        self.process_tap(t.abs, e.time, &mut out);
+                    }
+                }
+            }
+        }
+        if self.touches.len() < 2 {
+            self.end_pair(e.time, &mut out);
+        }
+        if self.touches.is_empty() {
+            self.end_pan(e.time, &mut out);
+        }
+        out
+    }
+
+    /// trackpad scrolls pan, and control+scroll pinches as browsers, windows and linux send it
+    pub fn handle_scroll(&mut self, e: &ScrollEvent) -> Vec<GestureEvent> {
+        let mut out = Vec::new();
+        if e.is_mouse {
+            return out
+        }
+        if e.modifiers.control {
+            let scale = (-e.scroll.y * self.config.scroll_pinch_speed).exp();
+            self.trackpad_pinch(e.window_id, e.modifiers, e.abs, scale, e.time, &mut out);
+        }
+        else {
+            self.trackpad_pan(e.window_id, e.modifiers, e.abs, -e.scroll, e.time, &mut out);
+        }
+        out
+    }
+
+    pub fn handle_trackpad_gesture(&mut self, e: &TrackpadGestureEvent) -> Vec<GestureEvent> {
+        let mut out = Vec::new();
+        if e.magnification != 0.0 {
+            self.trackpad_pinch(e.window_id, e.modifiers, e.abs, 1.0 + e.magnification, e.time, &mut out);
+        }
+        if e.rotation != 0.0 {
+            self.trackpad_rotate(e.window_id, e.modifiers, e.abs, e.rotation, e.time, &mut out);
+        }
+        if e.is_end {
+            self.end_trackpad_pinch(e.time, &mut out);
+            self.end_trackpad_rotate(e.time, &mut out);
+        }
+        out
+    }
+
+    /// fires long presses and ends trackpad gestures that have gone quiet
+    pub fn handle_time(&mut self, time: f64) -> Vec<GestureEvent> {
+        let mut out = Vec::new();
+        self.check_long_press(time, &mut out);
+        let end_time = self.config.trackpad_end_time;
+        let expired = | track: &Option<TrackpadTrack> | track.as_ref().is_some_and( | t | time - t.last_time >= end_time);
+        if expired(&self.trackpad_pan) {
+            self.end_trackpad_pan(time, &mut out);
+        }
+        if expired(&self.trackpad_pinch) {
+            self.end_trackpad_pinch(time, &mut out);
+        }
+        if expired(&self.trackpad_rotate) {
+            self.end_trackpad_rotate(time, &mut out);
+        }
+        out
+    }
+
+    fn window_id(&self) -> WindowId {
+        self.window_id.unwrap_or_default()
+    }
+
+    fn check_long_press(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some(press) = &self.long_press {
+            if time - press.time >= self.config.long_press_time {
+                out.push(GestureEvent::LongPress(LongPressEvent {
+                    window_id: self.window_id(),
+                    abs: press.abs,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+                // a long press is not also a tap
+                self.long_press = None;
+                self.tap = None;
+            }
+        }
+    }
+
+    fn process_tap(&mut self, abs: DVec2, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some((last_abs, last_time)) = self.last_tap.take() {
+            if time - last_time < self.config.double_tap_time
+                && abs.distance(&last_abs) < self.config.double_tap_distance {
+                out.push(GestureEvent::DoubleTap(DoubleTapEvent {
+                    window_id: self.window_id(),
+                    abs,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+                return
+            }
+        }
+        self.last_tap = Some((abs, time));
+    }
+
+    fn centroid(&self) -> DVec2 {
+        let mut sum = DVec2::default();
+        for t in &self.touches {
+            sum += t.abs;
+        }
+        sum / self.touches.len() as f64
+    }
+
+    fn update_pan(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if self.touches.is_empty() {
+            return
+        }
+        let uids: Vec<u64> = self.touches.iter().map( | t | t.uid).collect();
+        let centroid = self.centroid();
+        let window_id = self.window_id();
+        let pan = self.pan.get_or_insert_with( || PanTrack {
+            uids: uids.clone(),
+            abs_start: centroid,
+            last: centroid,
+            moved: DVec2::default(),
+            started: false,
+        });
+        // fingers coming or going make the centroid jump, so start measuring again from there
+        if pan.uids != uids {
+            pan.uids = uids;
+            pan.last = centroid;
+            return
+        }
+        let delta = centroid - pan.last;
+        pan.last = centroid;
+        pan.moved += delta;
+        let state = if pan.started {
+            if delta == DVec2::default() {
+                return
+            }
+            GestureState::Update
+        }
+        else if pan.moved.length() > self.config.pan_distance {
+            pan.started = true;
+            GestureState::Start
+        }
+        else {
+            return
+        };
+        out.push(GestureEvent::Pan(PanEvent {
+            window_id,
+            state,
+            abs: centroid,
+            abs_start: pan.abs_start,
+            // the start carries everything moved up to the threshold
+            delta: if state == GestureState::Start {pan.moved} else {delta},
+            touch_count: self.touches.len(),
+            is_trackpad: false,
+            modifiers: self.modifiers,
+            time,
+            handled: Cell::new(Area::Empty),
+            rect: Rect::default(),
+        }));
+    }
+
+    fn end_pan(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some(pan) = self.pan.take() {
+            if pan.started {
+                out.push(GestureEvent::Pan(PanEvent {
+                    window_id: self.window_id(),
+                    state: GestureState::End,
+                    abs: pan.last,
+                    abs_start: pan.abs_start,
+                    delta: DVec2::default(),
+                    touch_count: 0,
+                    is_trackpad: false,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+            }
+        }
+    }
+
+    fn update_pair(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if self.touches.len() < 2 {
+            return
+        }
+        let (a, b) = (&self.touches[0], &self.touches[1]);
+        let uids = (a.uid, b.uid);
+        let abs = (a.abs + b.abs) * 0.5;
+        let d = b.abs - a.abs;
+        let distance = d.length();
+        let angle = d.y.atan2(d.x);
+        let window_id = self.window_id();
+        if self.pair.as_ref().is_some_and( | pair | pair.uids != uids) {
+            self.end_pair(time, out);
+        }
+        let pair = self.pair.get_or_insert(PairTrack {
+            uids,
+            abs_start: abs,
+            abs,
+            distance_start: distance,
+            last_angle: angle,
+            scale: 1.0,
+            angle: 0.0,
+            pinching: false,
+            last_scale: 1.0,
+            rotating: false,
+            last_rotate: 0.0,
+        });
+        if pair.distance_start <= 0.0 {
+            pair.distance_start = distance;
+            return
+        }
+        pair.abs = abs;
+        pair.scale = distance / pair.distance_start;
+        // accumulate the turn in steps so it does not wrap around at pi
+        let mut turn = angle - pair.last_angle;
+        if turn > PI {
+            turn -= 2.0 * PI;
+        }
+        else if turn < -PI {
+            turn += 2.0 * PI;
+        }
+        pair.last_angle = angle;
+        pair.angle += turn;
+
+        if pair.pinching || (pair.scale - 1.0).abs() > self.config.pinch_scale {
+            let state = if pair.pinching {GestureState::Update} else {GestureState::Start};
+            if state == GestureState::Start || pair.scale != pair.last_scale {
+                out.push(GestureEvent::Pinch(PinchEvent {
+                    window_id,
+                    state,
+                    abs,
+                    abs_start: pair.abs_start,
+                    scale: pair.scale,
+                    scale_delta: pair.scale / pair.last_scale,
+                    is_trackpad: false,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+            }
+            pair.pinching = true;
+            pair.last_scale = pair.scale;
+        }
+        if pair.rotating || pair.angle.abs() > self.config.rotate_angle {
+            let state = if pair.rotating {GestureState::Update} else {GestureState::Start};
+            if state == GestureState::Start || pair.angle != pair.last_rotate {
+                out.push(GestureEvent::Rotate(RotateEvent {
+                    window_id,
+                    state,
+                    abs,
+                    abs_start: pair.abs_start,
+                    angle: pair.angle,
+                    angle_delta: pair.angle - pair.last_rotate,
+                    is_trackpad: false,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+            }
+            pair.rotating = true;
+            pair.last_rotate = pair.angle;
+        }
+    }
+
+    fn end_pair(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some(pair) = self.pair.take() {
+            if pair.pinching {
+                out.push(GestureEvent::Pinch(PinchEvent {
+                    window_id: self.window_id(),
+                    state: GestureState::End,
+                    abs: pair.abs,
+                    abs_start: pair.abs_start,
+                    scale: pair.last_scale,
+                    scale_delta: 1.0,
+                    is_trackpad: false,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+            }
+            if pair.rotating {
+                out.push(GestureEvent::Rotate(RotateEvent {
+                    window_id: self.window_id(),
+                    state: GestureState::End,
+                    abs: pair.abs,
+                    abs_start: pair.abs_start,
+                    angle: pair.last_rotate,
+                    angle_delta: 0.0,
+                    is_trackpad: false,
+                    modifiers: self.modifiers,
+                    time,
+                    handled: Cell::new(Area::Empty),
+                    rect: Rect::default(),
+                }));
+            }
+        }
+    }
+
+    fn trackpad_pan(&mut self, window_id: WindowId, modifiers: KeyModifiers, abs: DVec2, delta: DVec2, time: f64, out: &mut Vec<GestureEvent>) {
+        let (state, track) = track_trackpad(&mut self.trackpad_pan, window_id, modifiers, abs, 0.0, time);
+        track.abs += delta;
+        out.push(GestureEvent::Pan(PanEvent {
+            window_id,
+            state,
+            abs: track.abs,
+            abs_start: track.abs_start,
+            delta,
+            touch_count: 2,
+            is_trackpad: true,
+            modifiers,
+            time,
+            handled: Cell::new(Area::Empty),
+            rect: Rect::default(),
+        }));
+    }
+
+    fn trackpad_pinch(&mut self, window_id: WindowId, modifiers: KeyModifiers, abs: DVec2, scale_delta: f64, time: f64, out: &mut Vec<GestureEvent>) {
+        let (state, track) = track_trackpad(&mut self.trackpad_pinch, window_id, modifiers, abs, 1.0, time);
+        track.abs = abs;
+        track.value *= scale_delta;
+        out.push(GestureEvent::Pinch(PinchEvent {
+            window_id,
+            state,
+            abs,
+            abs_start: track.abs_start,
+            scale: track.value,
+            scale_delta,
+            is_trackpad: true,
+            modifiers,
+            time,
+            handled: Cell::new(Area::Empty),
+            rect: Rect::default(),
+        }));
+    }
+
+    fn trackpad_rotate(&mut self, window_id: WindowId, modifiers: KeyModifiers, abs: DVec2, angle_delta: f64, time: f64, out: &mut Vec<GestureEvent>) {
+        let (state, track) = track_trackpad(&mut self.trackpad_rotate, window_id, modifiers, abs, 0.0, time);
+        track.abs = abs;
+        track.value += angle_delta;
+        out.push(GestureEvent::Rotate(RotateEvent {
+            window_id,
+            state,
+            abs,
+            abs_start: track.abs_start,
+            angle: track.value,
+            angle_delta,
+            is_trackpad: true,
+            modifiers,
+            time,
+            handled: Cell::new(Area::Empty),
+            rect: Rect::default(),
+        }));
+    }
+
+    fn end_trackpad_pan(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some(track) = self.trackpad_pan.take() {
+            out.push(GestureEvent::Pan(PanEvent {
+                window_id: track.window_id,
+                state: GestureState::End,
+                abs: track.abs,
+                abs_start: track.abs_start,
+                delta: DVec2::default(),
+                touch_count: 0,
+                is_trackpad: true,
+                modifiers: track.modifiers,
+                time,
+                handled: Cell::new(Area::Empty),
+                rect: Rect::default(),
+            }));
+        }
+    }
+
+    fn end_trackpad_pinch(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some(track) = self.trackpad_pinch.take() {
+            out.push(GestureEvent::Pinch(PinchEvent {
+                window_id: track.window_id,
+                state: GestureState::End,
+                abs: track.abs,
+                abs_start: track.abs_start,
+                scale: track.value,
+                scale_delta: 1.0,
+                is_trackpad: true,
+                modifiers: track.modifiers,
+                time,
+                handled: Cell::new(Area::Empty),
+                rect: Rect::default(),
+            }));
+        }
+    }
+
+    fn end_trackpad_rotate(&mut self, time: f64, out: &mut Vec<GestureEvent>) {
+        if let Some(track) = self.trackpad_rotate.take() {
+            out.push(GestureEvent::Rotate(RotateEvent {
+                window_id: track.window_id,
+                state: GestureState::End,
+                abs: track.abs,
+                abs_start: track.abs_start,
+                angle: track.value,
+                angle_delta: 0.0,
+                is_trackpad: true,
+                modifiers: track.modifiers,
+                time,
+                handled: Cell::new(Area::Empty),
+                rect: Rect::default(),
+            }));
+        }
+    }
+}
+
+fn track_trackpad(track: &mut Option<TrackpadTrack>, window_id: WindowId, modifiers: KeyModifiers, abs: DVec2, value: f64, time: f64) -> (GestureState, &mut TrackpadTrack) {
+    let state = if track.is_some() {GestureState::Update} else {GestureState::Start};
+    let track = track.get_or_insert_with( || TrackpadTrack {
+        window_id,
+        modifiers,
+        abs_start: abs,
+        abs,
+        value,
+        last_time: time,
+    });
+    track.last_time = time;
+    track.modifiers = modifiers;
+    (state, track)
+}
+
+/// The gestures of a Cx: the recognizer, and which area each running gesture belongs to
+#[derive(Default)]
+pub struct CxGestures {
+    pub recognizer: GestureRecognizer,
+    pub (crate) next_frame: NextFrame,
+    pub (crate) pinch_area: Area,
+    pub (crate) rotate_area: Area,
+    pub (crate) pan_area: Area,
+}
+
+impl Cx {
+    pub (crate) fn handle_gestures(&mut self, event: &Event) {
+        let gestures = match event {
+            Event::TouchUpdate(e) => self.gestures.recognizer.handle_touch_update(e),
+            Event::Scroll(e) => self.gestures.recognizer.handle_scroll(e),
+            Event::NextFrame(e) if e.set.contains(&self.gestures.next_frame) => {
+                self.gestures.recognizer.handle_time(e.time)
+            }
+            _ => return
+        };
+        self.call_gesture_events(gestures);
+    }
+    
+    #[allow(dead_code)]
+    pub (crate) fn handle_trackpad_gesture(&mut self, e: &TrackpadGestureEvent) {
+        let gestures = self.gestures.recognizer.handle_trackpad_gesture(e);
+        self.call_gesture_events(gestures);
+    }
+    
+    fn call_gesture_events(&mut self, gestures: Vec<GestureEvent>) {
+        for gesture in gestures {
+            self.call_event_handler(&Event::Gesture(gesture));
+        }
+        // long presses and trackpad gestures fire or end by time, so keep the frames coming
+        if self.gestures.recognizer.needs_time() && !self.new_next_frames.contains(&self.gestures.next_frame) {
+            self.gestures.next_frame = self.new_next_frame();
+        }
+    }
+}
+
+fn claim_gesture(claimed: &mut Area, area: Area, state: GestureState, handled: &Cell<Area>, hit: impl FnOnce() -> bool) -> bool {
+    if state == GestureState::Start {
+        if handled.get().is_empty() && hit() {
+            handled.set(area);
+            *claimed = area;
+            return true
+        }
+        return false
+    }
+    *claimed == area
+}
+
+pub (crate) fn gesture_hits<F>(cx: &mut Cx, area: Area, e: &GestureEvent, options: &HitOptions, hit_test: F) -> Hit
+where F: Fn(DVec2, &Rect, &Option<Margin>) -> bool
+{
+    if cx.fingers.test_sweep_lock(options.sweep_area) {
+        return Hit::Nothing
+    }
+    let rect = area.clipped_rect(cx);
+    let gestures = &mut cx.gestures;
+    // a gesture belongs to the area it started in, for as long as it runs
+    match e {
+        GestureEvent::Pinch(e) => {
+            if claim_gesture(&mut gestures.pinch_area, area, e.state, &e.handled, || hit_test(e.abs_start, &rect, &options.margin)) {
+                return Hit::Pinch(PinchEvent {rect, ..e.clone()})
+            }
+        }
+        GestureEvent::Rotate(e) => {
+            if claim_gesture(&mut gestures.rotate_area, area, e.state, &e.handled, || hit_test(e.abs_start, &rect, &options.margin)) {
+                return Hit::Rotate(RotateEvent {rect, ..e.clone()})
+            }
+        }
+        GestureEvent::Pan(e) => {
+            if claim_gesture(&mut gestures.pan_area, area, e.state, &e.handled, || hit_test(e.abs_start, &rect, &options.margin)) {
+                return Hit::Pan(PanEvent {rect, ..e.clone()})
+            }
+        }
+        GestureEvent::LongPress(e) => {
+            if e.handled.get().is_empty() && hit_test(e.abs, &rect, &options.margin) {
+                e.handled.set(area);
+                return Hit::LongPress(LongPressEvent {rect, ..e.clone()})
+            }
+        }
+        GestureEvent::DoubleTap(e) => {
+            if e.handled.get().is_empty() && hit_test(e.abs, &rect, &options.margin) {
+                e.handled.set(area);
+                return Hit::DoubleTap(DoubleTapEvent {rect, ..e.clone()})
+            }
+        }
+    }
+    Hit::Nothing
+}
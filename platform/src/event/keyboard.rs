@@ -120,6 +120,17 @@ pub struct TextInputEvent {
     pub was_paste: bool
 }
 
+/// IME composition (preedit) state for the key focus. The preedit text is shown in place
+/// until it is committed, which replaces it with the final text, or cancelled.
+#[derive(Clone, Debug, SerBin, DeBin, SerJson, DeJson, PartialEq)]
+pub enum TextCompositionEvent {
+    Start,
+    /// the whole preedit text, with the IME caret/selection as a byte range into it
+    Update {text: String, cursor_start: usize, cursor_end: usize},
+    Commit {text: String},
+    Cancel,
+}
+
 #[derive(Clone, Debug)]
 pub struct TextClipboardEvent {
     pub response: Rc<RefCell<Option<String>>>
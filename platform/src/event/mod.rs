@@ -1,5 +1,6 @@
 pub mod event;
 pub mod finger;
+pub mod gesture;
 pub mod keyboard;
 pub mod window;
 pub mod xr;
@@ -9,6 +10,7 @@ pub mod video_playback;
 
 pub use event::*;
 pub use finger::*;
+pub use gesture::*;
 pub use keyboard::*;
 pub use window::*;
 pub use xr::*;
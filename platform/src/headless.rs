@@ -0,0 +1,179 @@
+use {
+    std::{
+        rc::Rc,
+        cell::{RefCell, RefMut},
+    },
+    crate::{
+        makepad_math::*,
+        app_main::AppMain,
+        live_traits::LiveNew,
+        cx_api::{CxOsApi, CxOsOp},
+        event::{Event, WindowGeom},
+        cx::Cx,
+    }
+};
+
+/// A piece of text drawn while running headless, with the rect it was laid out in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeadlessText {
+    pub text: String,
+    pub rect: Rect,
+}
+
+/// The state of a headless `Cx`, kept as a global: the size the windows get and
+/// the texts drawn by the last draw.
+#[derive(Default)]
+pub struct CxHeadless {
+    pub window_size: DVec2,
+    pub dpi_factor: f64,
+    pub texts: Vec<HeadlessText>,
+}
+
+impl Cx {
+    /// Runs `self` without an OS window or GPU context. Windows are created with
+    /// `window_size` at `dpi_factor`, draw events run the turtle and fill the draw
+    /// lists as usual, so areas can be queried for their rects afterwards.
+    /// Call this after registering the live design, it sends `Event::Startup`.
+    pub fn start_headless(&mut self, window_size: DVec2, dpi_factor: f64) {
+        self.set_global(CxHeadless {
+            window_size,
+            dpi_factor,
+            texts: Vec::new(),
+        });
+        self.init_cx_os();
+        self.call_event_handler(&Event::Startup);
+        self.redraw_all();
+        self.headless_draw();
+    }
+
+    pub fn is_headless(&mut self) -> bool {
+        self.has_global::<CxHeadless>()
+    }
+
+    /// The texts drawn by the last headless draw, in drawing order.
+    pub fn headless_texts(&mut self) -> &[HeadlessText] {
+        &self.get_global::<CxHeadless>().texts
+    }
+
+    /// Records a text a draw type put on screen, if we are headless.
+    pub fn headless_text(&mut self, rect: Rect, text: &str) {
+        if self.has_global::<CxHeadless>() {
+            self.get_global::<CxHeadless>().texts.push(HeadlessText {
+                text: text.to_string(),
+                rect
+            });
+        }
+    }
+
+    /// Sends an event to the app and draws what it asked to redraw.
+    pub fn headless_event(&mut self, event: &Event) {
+        self.call_event_handler(event);
+        self.headless_draw();
+    }
+
+    /// Sends the pending next frame event, if any, at `time` and draws.
+    pub fn headless_next_frame(&mut self, time: f64) {
+        if !self.new_next_frames.is_empty() {
+            self.call_next_frame_event(time);
+        }
+        self.headless_draw();
+    }
+
+    /// Handles the platform ops and draws until nothing asks for a redraw anymore.
+    /// The drawn texts are those of the draw lists redrawn by this call.
+    pub fn headless_draw(&mut self) {
+        self.headless_handle_platform_ops();
+        if !self.need_redrawing() {
+            return
+        }
+        self.get_global::<CxHeadless>().texts.clear();
+        let mut counter = 0;
+        while self.need_redrawing() {
+            counter += 1;
+            self.call_draw_event();
+            // there is no gpu to compile the shaders for
+            self.draw_shaders.compile_set.clear();
+            self.headless_handle_platform_ops();
+            if counter > 100 {
+                crate::error!("Redraw feedback loop detected");
+                break
+            }
+        }
+    }
+
+    fn headless_handle_platform_ops(&mut self) {
+        let (window_size, dpi_factor) = {
+            let headless = self.get_global::<CxHeadless>();
+            (headless.window_size, headless.dpi_factor)
+        };
+        while let Some(op) = self.platform_ops.pop() {
+            match op {
+                CxOsOp::CreateWindow(window_id) => {
+                    let window = &mut self.windows[window_id];
+                    window.window_geom = WindowGeom {
+                        dpi_factor,
+                        inner_size: window_size,
+                        outer_size: window_size,
+                        ..Default::default()
+                    };
+                    window.is_created = true;
+                }
+                CxOsOp::CloseWindow(window_id) => {
+                    self.windows[window_id].is_created = false;
+                }
+                _ => ()
+            }
+        }
+    }
+}
+
+/// An app running on a headless `Cx`, for testing the layout of widget trees in
+/// `cargo test`. `live_design` registers the live design of the app, like the
+/// `live_design` function `app_main!` calls.
+pub struct HeadlessApp<A> {
+    pub cx: Cx,
+    app: Rc<RefCell<Option<A>>>,
+}
+
+impl<A: AppMain + LiveNew + 'static> HeadlessApp<A> {
+    pub fn new(live_design: fn(&mut Cx), window_size: DVec2) -> Self {
+        let app = Rc::new(RefCell::new(None));
+        let app_inner = app.clone();
+        let mut cx = Cx::new(Box::new(move | cx, event | {
+            if let Event::Startup = event {
+                *app_inner.borrow_mut() = Some(A::new_main(cx));
+            }
+            <dyn AppMain>::handle_event(app_inner.borrow_mut().as_mut().unwrap(), cx, event);
+        }));
+        live_design(&mut cx);
+        cx.start_headless(window_size, 1.0);
+        Self {cx, app}
+    }
+
+    pub fn app(&self) -> RefMut<'_, A> {
+        RefMut::map(self.app.borrow_mut(), | app | app.as_mut().unwrap())
+    }
+
+    /// Runs `f` with the app and the `Cx`, for calls on the app that need the `Cx`.
+    pub fn with_app<R>(&mut self, f: impl FnOnce(&mut A, &mut Cx) -> R) -> R {
+        f(self.app.borrow_mut().as_mut().unwrap(), &mut self.cx)
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        self.cx.headless_event(event);
+    }
+
+    pub fn redraw_all(&mut self) {
+        self.cx.redraw_all();
+        self.cx.headless_draw();
+    }
+
+    pub fn texts(&mut self) -> &[HeadlessText] {
+        self.cx.headless_texts()
+    }
+
+    /// The rect of the first text drawn that equals `text`.
+    pub fn text_rect(&mut self, text: &str) -> Option<Rect> {
+        self.cx.headless_texts().iter().find( | t | t.text == text).map( | t | t.rect)
+    }
+}
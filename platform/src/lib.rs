@@ -41,6 +41,9 @@ mod geometry;
 mod debug;
 mod component_map;
 mod performance_stats;
+mod software_render;
+mod shader_check;
+mod headless;
 pub mod studio;
 
 pub mod web_socket;
@@ -118,6 +121,7 @@ pub use {
         ShaderEnum,
         DrawShaderPtr,
         ShaderTy,
+        rasterize::SoftwareFramebuffer,
     },
     crate::{
         os::*,
@@ -181,6 +185,7 @@ pub use {
             KeyEvent,
             KeyFocusEvent,
             TextInputEvent,
+            TextCompositionEvent,
             TextClipboardEvent,
             WindowCloseRequestedEvent,
             WindowClosedEvent,
@@ -195,6 +200,12 @@ pub use {
             DragItem,
             DragResponse,
             HitOptions,
+            GestureState,
+            PinchEvent,
+            RotateEvent,
+            PanEvent,
+            LongPressEvent,
+            DoubleTapEvent,
             DragHitEvent,
             DropHitEvent,
         },
@@ -208,7 +219,7 @@ pub use {
         cursor::MouseCursor,
         macos_menu::MacosMenu,
         draw_matrix::DrawMatrix,
-        window::WindowHandle,
+        window::{WindowHandle, WindowId},
         pass::{
             PassId,
             CxPassParent,
@@ -260,6 +271,11 @@ pub use {
             Geometry,
         },
         gpu_info::GpuPerformance,       
+        headless::{
+            CxHeadless,
+            HeadlessText,
+            HeadlessApp,
+        },
     },
 };
 
@@ -15,6 +15,7 @@ use {
             LivePtr,
             /*LiveTokenId,*/
             LiveFileId,
+            LiveLanguageServer,
         },
         makepad_live_compiler::LiveTypeInfo,
         /*makepad_math::*,*/
@@ -158,7 +159,8 @@ impl Cx {
             let err = LiveError {
                 origin,
                 message,
-                span: (*token_id).into()
+                span: (*token_id).into(),
+                notes: Vec::new(),
             };
             #[cfg(not(lines))]
             line_nr_error_once();
@@ -288,6 +290,18 @@ impl Cx {
         }
     }
     
+    /// The `--live-language-server` mode of `app_main!`: serves completion, hover,
+    /// go-to-definition and diagnostics for the `live_design!` blocks of this app
+    /// over stdio, using the field metadata of the types it registered.
+    pub fn live_language_server_and_exit(&mut self) -> ! {
+        let mut server = LiveLanguageServer::new();
+        let result = server.run(&mut self.live_registry.borrow_mut(), std::io::stdin(), std::io::stdout());
+        if let Err(err) = result {
+            eprintln!("live language server: {}", err);
+        }
+        std::process::exit(if server.is_shutdown() {0} else {1})
+    }
+    
     pub fn live_scan_dependencies(&mut self) {
         let live_registry = self.live_registry.borrow();
         for file in &live_registry.live_files {
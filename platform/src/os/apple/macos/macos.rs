@@ -245,6 +245,7 @@ impl Cx {
             MacosEvent::MouseMove(_) |
             MacosEvent::MouseUp(_) |
             MacosEvent::Scroll(_) |
+            MacosEvent::TrackpadGesture(_) |
             MacosEvent::KeyDown(_) |
             MacosEvent::KeyUp(_) |
             MacosEvent::TextInput(_) => {
@@ -366,6 +367,9 @@ impl Cx {
             MacosEvent::Scroll(e) => {
                 self.call_event_handler(&Event::Scroll(e.into()))
             }
+            MacosEvent::TrackpadGesture(e) => {
+                self.handle_trackpad_gesture(&e)
+            }
             MacosEvent::WindowDragQuery(e) => {
                 self.call_event_handler(&Event::WindowDragQuery(e))
             }
@@ -511,6 +511,30 @@ impl MacosApp {
                     cocoa_window.send_scroll(DVec2 {x: -dx * 32., y: -dy * 32.}, get_event_key_modifier(ns_event), true);
                 }
             },
+            NSEventType::NSEventTypeMagnify | NSEventType::NSEventTypeRotate => {
+                let window: ObjcId = msg_send![ns_event, window];
+                if window == nil {
+                    return
+                }
+                let window_delegate: ObjcId = msg_send![window, delegate];
+                if window_delegate == nil {
+                    return
+                }
+                let ptr: *mut c_void = *(*window_delegate).get_ivar("macos_window_ptr");
+                let cocoa_window = &mut *(ptr as *mut MacosWindow);
+                let phase: u64 = msg_send![ns_event, phase];
+                // NSEventPhaseEnded | NSEventPhaseCancelled
+                let is_end = phase & (0x8 | 0x10) != 0;
+                if ev_type == NSEventType::NSEventTypeMagnify {
+                    let magnification: f64 = msg_send![ns_event, magnification];
+                    cocoa_window.send_trackpad_gesture(magnification, 0.0, is_end, get_event_key_modifier(ns_event));
+                }
+                else {
+                    // degrees counterclockwise, we go clockwise on screen like the touch rotate
+                    let rotation: f32 = msg_send![ns_event, rotation];
+                    cocoa_window.send_trackpad_gesture(0.0, -(rotation as f64).to_radians(), is_end, get_event_key_modifier(ns_event));
+                }
+            },
             NSEventType::NSEventTypePressure => {
             },
             _ => (),
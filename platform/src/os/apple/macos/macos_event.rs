@@ -7,6 +7,7 @@ use crate::{
         MouseUpEvent,
         MouseMoveEvent,
         ScrollEvent,
+        TrackpadGestureEvent,
         WindowGeomChangeEvent,
         WindowDragQueryEvent,
         WindowCloseRequestedEvent,
@@ -34,6 +35,7 @@ pub enum MacosEvent {
     MouseUp(MouseUpEvent),
     MouseMove(MouseMoveEvent),
     Scroll(ScrollEvent),
+    TrackpadGesture(TrackpadGestureEvent),
     
     WindowDragQuery(WindowDragQueryEvent),
     WindowCloseRequested(WindowCloseRequestedEvent),
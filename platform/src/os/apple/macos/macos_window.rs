@@ -25,6 +25,7 @@ use {
         area::Area,
         event::{
             ScrollEvent,
+            TrackpadGestureEvent,
             MouseUpEvent,
             MouseDownEvent,
             MouseMoveEvent,
@@ -380,6 +381,20 @@ impl MacosWindow {
         //get_macos_app_global().ns_event = ptr::null_mut();
     }
     
+    pub fn send_trackpad_gesture(&mut self, magnification: f64, rotation: f64, is_end: bool, modifiers: KeyModifiers){
+        self.do_callback(
+            MacosEvent::TrackpadGesture(TrackpadGestureEvent {
+                window_id: self.window_id,
+                abs: self.last_mouse_pos,
+                magnification,
+                rotation,
+                is_end,
+                modifiers,
+                time: self.time_now(),
+            })
+        );
+    }
+    
     pub fn send_scroll(&mut self, scroll:DVec2, modifiers: KeyModifiers, is_mouse:bool){
         self.do_callback(
             MacosEvent::Scroll(ScrollEvent {
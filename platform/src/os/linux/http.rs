@@ -0,0 +1,51 @@
+use {
+    std::sync::mpsc::Sender,
+    makepad_http::client::HttpClientRequest,
+    crate::{
+        makepad_live_id::LiveId,
+        thread::Signal,
+        event::{HttpRequest, HttpResponse, NetworkResponse, NetworkResponseItem},
+    },
+};
+
+/// Runs `request` on a thread of its own, sending progress and the response to
+/// `networking_sender` and waking up the UI thread for each of them.
+pub fn make_http_request(request_id: LiveId, request: HttpRequest, networking_sender: Sender<NetworkResponseItem>) {
+    let client_request = HttpClientRequest {
+        method: request.method.to_string().into(),
+        url: request.url,
+        headers: request.headers.into_iter().map( | (key, values) | (key, values.join(", "))).collect(),
+        body: request.body,
+        timeout: None,
+    };
+    let metadata_id = request.metadata_id;
+    std::thread::spawn(move || {
+        let send = | response | {
+            let _ = networking_sender.send(NetworkResponseItem {request_id, response});
+            Signal::set_ui_signal();
+        };
+        let result = client_request.send(&mut | loaded, total | {
+            send(NetworkResponse::HttpProgress {
+                loaded: loaded.min(u32::MAX as u64) as u32,
+                total: total.unwrap_or(0).min(u32::MAX as u64) as u32
+            });
+        });
+        match result {
+            Ok(client_response) => {
+                let mut response = HttpResponse::new(
+                    metadata_id,
+                    client_response.status_code,
+                    "".to_string(),
+                    Some(client_response.body),
+                );
+                for (key, value) in client_response.headers {
+                    response.set_header(key, value);
+                }
+                send(NetworkResponse::HttpResponse(response));
+            }
+            Err(error) => {
+                send(NetworkResponse::HttpRequestError(error.to_string()));
+            }
+        }
+    });
+}
@@ -32,6 +32,8 @@ pub mod pulse_sys;
 
 #[cfg(not(target_os="android"))]
 mod web_socket;
+#[cfg(not(target_os="android"))]
+pub mod http;
 
 #[cfg(target_os="android")]
 pub mod android;
@@ -1,16 +1,76 @@
+use {
+    std::sync::mpsc::{channel, Sender},
+    makepad_http::{
+        websocket::WebSocketMessage as FrameMessage,
+        websocket_client::WebSocketClient,
+    },
+    crate::{
+        event::HttpRequest,
+        web_socket::WebSocketMessage,
+    },
+};
 
-use crate::event::HttpRequest;
-use crate::web_socket::{WebSocketMessage};
-use std::sync::mpsc::{Sender};
+/// A websocket connection, which connects and reads on threads of its own so that the websocket
+/// thread never blocks. Errors, including failing to connect, arrive as `WebSocketMessage::Error`.
 pub struct OsWebSocket{
+    sender: Sender<WebSocketMessage>,
 }
 
 impl OsWebSocket{
-    pub fn send_message(&mut self, _message:WebSocketMessage)->Result<(),()>{
-        todo!();
+    pub fn send_message(&mut self, message:WebSocketMessage)->Result<(),()>{
+        self.sender.send(message).map_err( | _ | ())
     }
-                    
-    pub fn open(_request: HttpRequest, _rx_sender:Sender<WebSocketMessage>)->OsWebSocket{
-        todo!();
+
+    pub fn open(request: HttpRequest, rx_sender:Sender<WebSocketMessage>)->OsWebSocket{
+        let (sender, receiver) = channel::<WebSocketMessage>();
+        let headers: Vec<(String, String)> = request.headers.into_iter().map( | (key, values) | (key, values.join(", "))).collect();
+        let url = request.url;
+        std::thread::spawn(move || {
+            let mut client = match WebSocketClient::connect(&url, &headers){
+                Ok(client)=>client,
+                Err(error)=>{
+                    let _ = rx_sender.send(WebSocketMessage::Error(format!("cannot connect to {}: {}", url, error)));
+                    // keep accepting messages until we are dropped, sending them goes nowhere
+                    while receiver.recv().is_ok(){}
+                    return
+                }
+            };
+            let client_sender = client.sender();
+            let read_sender = rx_sender.clone();
+            std::thread::spawn(move || {
+                let result = client.read_messages(&mut | message | {
+                    match message{
+                        FrameMessage::Text(text)=>{
+                            let _ = read_sender.send(WebSocketMessage::String(text.to_string()));
+                        }
+                        FrameMessage::Binary(data)=>{
+                            let _ = read_sender.send(WebSocketMessage::Binary(data.to_vec()));
+                        }
+                        _=>()
+                    }
+                });
+                if let Err(error) = result{
+                    let _ = read_sender.send(WebSocketMessage::Error(error.to_string()));
+                }
+                let _ = read_sender.send(WebSocketMessage::Closed);
+            });
+            while let Ok(message) = receiver.recv(){
+                let result = match message{
+                    WebSocketMessage::Binary(data)=>client_sender.send_binary(&data),
+                    WebSocketMessage::String(text)=>client_sender.send_text(&text),
+                    WebSocketMessage::Closed=>client_sender.send_close(),
+                    WebSocketMessage::Error(_)=>Ok(()),
+                };
+                if let Err(error) = result{
+                    let _ = rx_sender.send(WebSocketMessage::Error(error.to_string()));
+                }
+            }
+            // the socket was dropped
+            let _ = client_sender.send_close();
+            client_sender.shutdown();
+        });
+        OsWebSocket{
+            sender
+        }
     }
-}
\ No newline at end of file
+}
@@ -14,6 +14,7 @@ use {
         event::WindowGeom,
         texture::{Texture, TextureFormat},
         thread::Signal,
+        os::linux::http::make_http_request,
         os::cx_stdin::{aux_chan, HostToStdin, PresentableDraw, StdinToHost, Swapchain, PollTimer},
         pass::{CxPassParent, PassClearColor, CxPassColorTexture},
         cx_api::CxOsOp,
@@ -263,6 +264,9 @@ impl Cx {
                 CxOsOp::StopTimer(timer_id) => {
                     self.os.stdin_timers.timers.remove(&timer_id);
                 },
+                CxOsOp::HttpRequest{request_id, request} => {
+                    make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
                 _ => ()
                 /*
                 CxOsOp::CloseWindow(_window_id) => {},
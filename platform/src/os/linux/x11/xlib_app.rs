@@ -12,6 +12,7 @@ use {
         xlib_event::XlibEvent,
         xlib_window::*,
         super::select_timer::SelectTimers,
+        super::libc_sys,
     },
     crate::{
         makepad_math::DVec2,
@@ -63,6 +64,9 @@ impl XlibApp {
         unsafe {
             let display = x11_sys::XOpenDisplay(ptr::null());
             let display_fd = x11_sys::XConnectionNumber(display);
+            // the input method is picked from the locale and XMODIFIERS
+            libc_sys::setlocale(libc_sys::LC_CTYPE, c"".as_ptr());
+            x11_sys::XSetLocaleModifiers(c"".as_ptr());
             let xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
             //let mut signal_fds = [0, 0];
             //libc_sys::pipe(signal_fds.as_mut_ptr());
@@ -96,6 +100,10 @@ impl XlibApp {
             let mut event = mem::MaybeUninit::uninit();
             x11_sys::XNextEvent(self.display, event.as_mut_ptr());
             let mut event = event.assume_init();
+            // the input method eats the key events it uses for composing
+            if x11_sys::XFilterEvent(&mut event, 0) != 0 {
+                continue;
+            }
             match event.type_ as u32 {
                 x11_sys::SelectionNotify => {
                     let selection = event.xselection;
@@ -478,23 +486,38 @@ impl XlibApp {
                         
                         if !block_text {
                             // decode the character
-                            let mut buffer = [0u8; 32];
+                            let mut buffer = [0u8; 256];
                             let mut keysym = mem::MaybeUninit::uninit();
                             let mut status = mem::MaybeUninit::uninit();
-                            let count = x11_sys::Xutf8LookupString(
-                                window.xic.unwrap(),
-                                &mut event.xkey,
-                                buffer.as_mut_ptr() as *mut c_char,
-                                buffer.len() as c_int,
-                                keysym.as_mut_ptr(),
-                                status.as_mut_ptr(),
-                            );
+                            let xic = window.xic.unwrap_or(ptr::null_mut());
+                            let count = if xic.is_null() {
+                                x11_sys::XLookupString(
+                                    &mut event.xkey,
+                                    buffer.as_mut_ptr() as *mut c_char,
+                                    buffer.len() as c_int,
+                                    keysym.as_mut_ptr(),
+                                    ptr::null_mut(),
+                                )
+                            }
+                            else {
+                                x11_sys::Xutf8LookupString(
+                                    xic,
+                                    &mut event.xkey,
+                                    buffer.as_mut_ptr() as *mut c_char,
+                                    buffer.len() as c_int,
+                                    keysym.as_mut_ptr(),
+                                    status.as_mut_ptr(),
+                                )
+                            };
                             //let keysym = keysym.assume_init();
-                            let status = status.assume_init();
-                            if status != x11_sys::XBufferOverflow {
+                            if xic.is_null() || status.assume_init() != x11_sys::XBufferOverflow {
                                 let utf8 = std::str::from_utf8(&buffer[..count as usize]).unwrap_or("").to_string();
                                 let char_code = utf8.chars().next().unwrap_or('\0');
-                                if char_code >= ' ' && char_code != 127 as char {
+                                if window.ime_composing && !utf8.is_empty() {
+                                    // the input method commits the composed text as a lookup
+                                    window.send_text_composition(TextCompositionEvent::Commit {text: utf8});
+                                }
+                                else if char_code >= ' ' && char_code != 127 as char {
                                     self.do_callback(XlibEvent::TextInput(TextInputEvent {
                                         input: utf8,
                                         was_paste: false,
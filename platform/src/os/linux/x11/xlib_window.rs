@@ -3,7 +3,7 @@ use {
         mem,
         cell::Cell,
         rc::Rc,
-        os::raw::{c_ulong, c_long, c_void, c_char},
+        os::raw::{c_ulong, c_long, c_void, c_char, c_int, c_short},
         ptr,
         ffi::{CStr,CString}, 
     },
@@ -34,6 +34,10 @@ pub struct XlibWindow {
     pub last_window_geom: WindowGeom,
     
     pub ime_spot: DVec2,
+    pub ime_composing: bool,
+    pub ime_preedit: Vec<char>,
+    pub ime_feedback: Vec<c_ulong>,
+    pub ime_caret: usize,
     pub current_cursor: MouseCursor,
     pub last_mouse_pos: DVec2,
 }
@@ -62,6 +66,10 @@ impl XlibWindow {
             last_window_geom: WindowGeom::default(),
             last_nc_mode: None,
             ime_spot: DVec2::default(),
+            ime_composing: false,
+            ime_preedit: Vec::new(),
+            ime_feedback: Vec::new(),
+            ime_caret: 0,
             current_cursor: MouseCursor::Default,
             last_mouse_pos: DVec2::default(),
         }
@@ -150,16 +158,10 @@ impl XlibWindow {
             let title_bytes = format!("{}\0", title);
             x11_sys::XStoreName(display, window, title_bytes.as_bytes().as_ptr() as *const c_char);
             
-            let xic = x11_sys::XCreateIC(
-                get_xlib_app_global().xim,
-                x11_sys::XNInputStyle.as_ptr(),
-                (x11_sys::XIMPreeditNothing | x11_sys::XIMStatusNothing) as i32,
-                x11_sys::XNClientWindow.as_ptr(),
-                window,
-                x11_sys::XNFocusWindow.as_ptr(),
-                window,
-                ptr::null_mut() as *mut c_void
-            );
+            let xic = self.create_xic(window);
+            if !xic.is_null() {
+                x11_sys::XSetICFocus(xic);
+            }
             
             // Create a window
             get_xlib_app_global().window_map.insert(window, self);
@@ -297,8 +299,91 @@ impl XlibWindow {
         maximized
     }
     
+    unsafe fn create_xic(&mut self, window: c_ulong) -> x11_sys::XIC {
+        let xim = get_xlib_app_global().xim;
+        if xim.is_null() {
+            return ptr::null_mut()
+        }
+        // let the input method send us the preedit text so we can draw it inline
+        let client_data = self as *mut XlibWindow as x11_sys::XPointer;
+        let start = x11_sys::XICCallback {client_data, callback: Some(xim_preedit_start)};
+        let done = x11_sys::XICCallback {client_data, callback: Some(xim_preedit_done)};
+        let draw = x11_sys::XICCallback {client_data, callback: Some(xim_preedit_draw)};
+        let caret = x11_sys::XICCallback {client_data, callback: Some(xim_preedit_caret)};
+        let spot = x11_sys::XPoint {x: 0, y: 0};
+        let preedit_attributes = x11_sys::XVaCreateNestedList(
+            0,
+            x11_sys::XNSpotLocation.as_ptr(),
+            &spot as *const x11_sys::XPoint,
+            x11_sys::XNPreeditStartCallback.as_ptr(),
+            &start as *const x11_sys::XICCallback,
+            x11_sys::XNPreeditDoneCallback.as_ptr(),
+            &done as *const x11_sys::XICCallback,
+            x11_sys::XNPreeditDrawCallback.as_ptr(),
+            &draw as *const x11_sys::XICCallback,
+            x11_sys::XNPreeditCaretCallback.as_ptr(),
+            &caret as *const x11_sys::XICCallback,
+            ptr::null_mut() as *mut c_void
+        );
+        let xic = x11_sys::XCreateIC(
+            xim,
+            x11_sys::XNInputStyle.as_ptr(),
+            (x11_sys::XIMPreeditCallbacks | x11_sys::XIMStatusNothing) as c_int,
+            x11_sys::XNClientWindow.as_ptr(),
+            window,
+            x11_sys::XNFocusWindow.as_ptr(),
+            window,
+            x11_sys::XNPreeditAttributes.as_ptr(),
+            preedit_attributes,
+            ptr::null_mut() as *mut c_void
+        );
+        x11_sys::XFree(preedit_attributes);
+        if !xic.is_null() {
+            return xic
+        }
+        // the input method can't do callbacks, so it will draw the preedit itself
+        x11_sys::XCreateIC(
+            xim,
+            x11_sys::XNInputStyle.as_ptr(),
+            (x11_sys::XIMPreeditNothing | x11_sys::XIMStatusNothing) as c_int,
+            x11_sys::XNClientWindow.as_ptr(),
+            window,
+            x11_sys::XNFocusWindow.as_ptr(),
+            window,
+            ptr::null_mut() as *mut c_void
+        )
+    }
+    
     pub fn set_ime_spot(&mut self, spot: DVec2) {
+        if self.ime_spot == spot {
+            return
+        }
         self.ime_spot = spot;
+        if let Some(xic) = self.xic {
+            if xic.is_null() {
+                return
+            }
+            unsafe {
+                let dpi_factor = self.get_dpi_factor();
+                let spot = x11_sys::XPoint {
+                    x: (spot.x * dpi_factor) as c_short,
+                    y: (spot.y * dpi_factor) as c_short
+                };
+                let preedit_attributes = x11_sys::XVaCreateNestedList(
+                    0,
+                    x11_sys::XNSpotLocation.as_ptr(),
+                    &spot as *const x11_sys::XPoint,
+                    ptr::null_mut() as *mut c_void
+                );
+                x11_sys::XSetICValues(
+                    xic,
+                    x11_sys::XNPreeditAttributes.as_ptr(),
+                    preedit_attributes,
+                    ptr::null_mut() as *mut c_void
+                );
+                x11_sys::XFree(preedit_attributes);
+            }
+        }
     }
     
     pub fn get_position(&self) -> DVec2 {
@@ -470,6 +555,115 @@ impl XlibWindow {
         }))
     }
     
+    pub fn send_text_composition(&mut self, event: TextCompositionEvent) {
+        match &event {
+            TextCompositionEvent::Start => {
+                self.ime_composing = true;
+            }
+            TextCompositionEvent::Update {..} => (),
+            TextCompositionEvent::Commit {..} | TextCompositionEvent::Cancel => {
+                self.ime_composing = false;
+                self.ime_preedit.clear();
+                self.ime_feedback.clear();
+                self.ime_caret = 0;
+            }
+        }
+        self.do_callback(XlibEvent::TextComposition(event))
+    }
+    
+    fn send_text_composition_update(&mut self) {
+        if !self.ime_composing {
+            self.send_text_composition(TextCompositionEvent::Start);
+        }
+        let byte_index = | char_index: usize | -> usize {
+            self.ime_preedit[..char_index.min(self.ime_preedit.len())].iter().map( | c | c.len_utf8()).sum()
+        };
+        // the segment being converted is shown reversed, otherwise it is just the caret
+        let (cursor_start, cursor_end) = match (
+            self.ime_feedback.iter().position( | f | f & x11_sys::XIMReverse != 0),
+            self.ime_feedback.iter().rposition( | f | f & x11_sys::XIMReverse != 0)
+        ) {
+            (Some(first), Some(last)) => (byte_index(first), byte_index(last + 1)),
+            _ => (byte_index(self.ime_caret), byte_index(self.ime_caret))
+        };
+        let text = self.ime_preedit.iter().collect();
+        self.send_text_composition(TextCompositionEvent::Update {text, cursor_start, cursor_end})
+    }
+}
+
+unsafe fn xim_text_chars(text: &x11_sys::XIMText) -> Vec<char> {
+    if text.encoding_is_wchar != 0 {
+        if text.string.wide_char.is_null() {
+            return Vec::new()
+        }
+        std::slice::from_raw_parts(text.string.wide_char, text.length as usize)
+            .iter()
+            .filter_map( | c | char::from_u32(*c as u32))
+            .collect()
+    }
+    else {
+        if text.string.multi_byte.is_null() {
+            return Vec::new()
+        }
+        CStr::from_ptr(text.string.multi_byte).to_string_lossy().chars().collect()
+    }
+}
+
+unsafe extern "C" fn xim_preedit_start(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) -> c_int {
+    let window = &mut *(client_data as *mut XlibWindow);
+    window.ime_preedit.clear();
+    window.ime_feedback.clear();
+    window.ime_caret = 0;
+    window.send_text_composition(TextCompositionEvent::Start);
+    // no limit on the preedit length
+    -1
+}
+
+unsafe extern "C" fn xim_preedit_done(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) -> c_int {
+    let window = &mut *(client_data as *mut XlibWindow);
+    // a commit comes in as a KeyPress and already ended the composition
+    if window.ime_composing {
+        window.send_text_composition(TextCompositionEvent::Cancel);
+    }
+    0
+}
+
+unsafe extern "C" fn xim_preedit_draw(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, call_data: x11_sys::XPointer) -> c_int {
+    let window = &mut *(client_data as *mut XlibWindow);
+    let draw = &*(call_data as *const x11_sys::XIMPreeditDrawCallbackStruct);
+    let len = window.ime_preedit.len();
+    let first = (draw.chg_first.max(0) as usize).min(len);
+    let end = (first + draw.chg_length.max(0) as usize).min(len);
+    let (chars, feedback) = if draw.text.is_null() {
+        (Vec::new(), Vec::new())
+    }
+    else {
+        let text = &*draw.text;
+        let chars = xim_text_chars(text);
+        let feedback = if text.feedback.is_null() {
+            vec![0; chars.len()]
+        }
+        else {
+            std::slice::from_raw_parts(text.feedback, text.length as usize).to_vec()
+        };
+        (chars, feedback)
+    };
+    window.ime_preedit.splice(first..end, chars);
+    window.ime_feedback.splice(first..end, feedback);
+    window.ime_feedback.resize(window.ime_preedit.len(), 0);
+    window.ime_caret = draw.caret.max(0) as usize;
+    window.send_text_composition_update();
+    0
+}
+
+unsafe extern "C" fn xim_preedit_caret(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, call_data: x11_sys::XPointer) -> c_int {
+    let window = &mut *(client_data as *mut XlibWindow);
+    let caret = &*(call_data as *const x11_sys::XIMPreeditCaretCallbackStruct);
+    if caret.direction == x11_sys::XIMAbsolutePosition && window.ime_composing {
+        window.ime_caret = caret.position.max(0) as usize;
+        window.send_text_composition_update();
+    }
+    0
 }
 
 
@@ -0,0 +1,158 @@
+use {
+    crate::{
+        cx::Cx,
+        draw_shader::DrawShaderFingerprint,
+        draw_vars::DrawVars,
+        makepad_live_compiler::{LiveFieldKind, LiveFileError, LiveFileId, LivePtr, LiveRegistry, LiveType, LiveValue, TextSpan},
+        makepad_micro_serde::*,
+        makepad_shader_compiler::DrawShaderPtr,
+    }
+};
+
+impl Cx {
+    /// Analyses every draw shader in the expanded live documents without drawing
+    /// anything, so shader errors can be reported before an app runs. Shaders
+    /// drawn with a geometry that wasn't registered with
+    /// `CxDrawShaders::register_geometry_fields` are skipped.
+    pub fn check_shaders(&mut self) -> Vec<LiveFileError> {
+        let live_registry_rc = self.live_registry.clone();
+        let live_registry = live_registry_rc.borrow();
+
+        let mut draw_shader_ptrs = Vec::new();
+        for (file_index, live_file) in live_registry.live_files.iter().enumerate() {
+            for (index, node) in live_file.expanded.nodes.iter().enumerate() {
+                if let LiveValue::Class {live_type, ..} = node.value {
+                    if is_draw_shader_type(&live_registry, live_type) {
+                        draw_shader_ptrs.push(DrawShaderPtr(LivePtr::from_index(LiveFileId::new(file_index), index, live_file.generation)));
+                    }
+                }
+            }
+        }
+
+        let mut errors = Vec::new();
+        let mut fingerprints = Vec::new();
+        for draw_shader_ptr in draw_shader_ptrs {
+            // classes that only override instance values share the shader of their base
+            let fingerprint = DrawShaderFingerprint::from_ptr(self, draw_shader_ptr);
+            if fingerprints.contains(&fingerprint) {
+                continue
+            }
+            fingerprints.push(fingerprint);
+
+            let mut geometry_known = true;
+            let draw_shaders = &self.draw_shaders;
+            let result = self.shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | live_registry, shader_registry, span, draw_shader_query, live_type, draw_shader_def | {
+                geometry_known &= draw_shaders.answer_draw_shader_query(live_registry, shader_registry, span, draw_shader_query, live_type, draw_shader_def);
+            });
+            if let Err(err) = result {
+                let err = live_registry.live_error_to_live_file_error(err);
+                if geometry_known && !errors.contains(&err) {
+                    errors.push(err);
+                }
+            }
+        }
+        errors
+    }
+
+    /// The `--check-shaders` mode of `app_main!`: expands the live design, checks
+    /// the draw shaders and prints the errors, as json with `--message-format=json`.
+    /// Exits the process with status 1 if there were errors.
+    pub fn check_shaders_and_exit(&mut self) -> ! {
+        let mut errs = Vec::new();
+        self.live_registry.borrow_mut().expand_all_documents(&mut errs);
+        let mut errors: Vec<_> = {
+            let live_registry = self.live_registry.borrow();
+            errs.into_iter().map( | err | live_registry.live_error_to_live_file_error(err)).collect()
+        };
+        if errors.is_empty() {
+            errors = self.check_shaders();
+        }
+
+        let json = std::env::args().any( | v | v == "--message-format=json");
+        for err in &errors {
+            if json {
+                println!("{}", CheckMessage::from_live_file_error(err).serialize_json());
+            }
+            else {
+                println!("Error {}", err);
+            }
+        }
+        std::process::exit(if errors.is_empty() {0} else {1})
+    }
+}
+
+fn is_draw_shader_type(live_registry: &LiveRegistry, live_type: LiveType) -> bool {
+    if let Some(live_type_info) = live_registry.live_type_infos.get(&live_type) {
+        for field in &live_type_info.fields {
+            if let LiveFieldKind::Deref = field.live_field_kind {
+                let field_type = field.live_type_info.live_type;
+                return field_type == LiveType::of::<DrawVars>() || is_draw_shader_type(live_registry, field_type)
+            }
+        }
+    }
+    false
+}
+
+// the subset of the cargo json diagnostics studio reads, see `RustcCompilerMessage`
+#[derive(SerJson)]
+struct CheckMessage {
+    reason: String,
+    message: CheckDiagnostic,
+}
+
+#[derive(SerJson)]
+struct CheckDiagnostic {
+    message: String,
+    level: String,
+    spans: Vec<CheckSpan>,
+    children: Vec<CheckDiagnostic>,
+}
+
+#[derive(SerJson)]
+struct CheckSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    text: Vec<String>,
+}
+
+impl CheckMessage {
+    fn from_live_file_error(err: &LiveFileError) -> Self {
+        Self {
+            reason: "makepad-error-log".into(),
+            message: CheckDiagnostic {
+                message: err.message.clone(),
+                level: "error".into(),
+                spans: vec![CheckSpan::new(&err.file, err.span)],
+                children: err.notes.iter().map( | note | CheckDiagnostic {
+                    message: note.message.clone(),
+                    level: "note".into(),
+                    spans: vec![CheckSpan::new(&note.file, note.span)],
+                    children: Vec::new(),
+                }).collect(),
+            }
+        }
+    }
+}
+
+impl CheckSpan {
+    // lines are 1-based in the json, like the columns of live tokens already are
+    fn new(file_name: &str, span: TextSpan) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            byte_start: 0,
+            byte_end: 0,
+            line_start: span.start.line as usize + 1,
+            line_end: span.end.line as usize + 1,
+            column_start: span.start.column as usize,
+            column_end: span.end.column as usize,
+            is_primary: true,
+            text: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use {
+    crate::{
+        cx::Cx,
+        draw_list::DrawListId,
+        draw_shader::DrawShaderInputs,
+        makepad_math::*,
+        makepad_live_compiler::LiveError,
+        makepad_shader_compiler::{
+            interpret::{ShaderInputs, ShaderInterpreter, ShaderTexture, ShaderValue},
+            rasterize::SoftwareFramebuffer,
+            ShaderTy,
+            ValuePtr,
+        },
+        pass::{CxPass, PassClearColor, PassId},
+        texture::{TextureFormat, TextureId},
+    }
+};
+
+impl Cx {
+    /// Draws a draw list and the lists nested in it on the CPU, interpreting the
+    /// shaders, the way a pass of `size` at `dpi_factor` draws it on the GPU. This
+    /// makes widget rendering testable on machines without a GPU. Textures that
+    /// are render targets are drawn the same way, from the pass drawing into
+    /// them. Fails if a shader calls a builtin the interpreter doesn't support.
+    pub fn render_draw_list_to_image(&mut self, draw_list_id: DrawListId, size: DVec2, dpi_factor: f64) -> Result<SoftwareFramebuffer, LiveError> {
+        let mut pass = CxPass::default();
+        pass.set_dpi_factor(dpi_factor);
+        pass.set_matrix(DVec2::default(), size);
+        let mut framebuffer = SoftwareFramebuffer::new(
+            (size.x * dpi_factor) as usize,
+            (size.y * dpi_factor) as usize,
+            Vec4::default()
+        );
+        let mut zbias = 0.0;
+        self.software_render_view(&pass, draw_list_id, &mut zbias, &mut framebuffer) ?;
+        Ok(framebuffer)
+    }
+
+    // draws the pass that has the texture as a color target, like the GPU
+    // backends do in draw_pass_to_texture
+    fn software_render_target(&mut self, texture_id: TextureId) -> Result<Option<ShaderTexture>, LiveError> {
+        let pass_id = self.passes.id_iter().find( | pass_id | {
+            self.passes[*pass_id].color_textures.iter().any( | color_texture | color_texture.texture.texture_id() == texture_id)
+        });
+        let pass_id: PassId = if let Some(pass_id) = pass_id {pass_id} else {return Ok(None)};
+        let draw_list_id = if let Some(draw_list_id) = self.passes[pass_id].main_draw_list_id {draw_list_id} else {return Ok(None)};
+        let dpi_factor = self.passes[pass_id].dpi_factor.unwrap_or(1.0);
+        let pass_rect = if let Some(pass_rect) = self.get_pass_rect(pass_id, dpi_factor) {pass_rect} else {return Ok(None)};
+        let clear_color = self.passes[pass_id].color_textures.iter().find( | color_texture | {
+            color_texture.texture.texture_id() == texture_id
+        }).map( | color_texture | match color_texture.clear_color {
+            PassClearColor::InitWith(color) | PassClearColor::ClearWith(color) => color
+        }).unwrap_or_default();
+
+        let mut pass = CxPass {
+            matrix_mode: self.passes[pass_id].matrix_mode.clone(),
+            ..CxPass::default()
+        };
+        pass.set_dpi_factor(dpi_factor);
+        pass.set_matrix(pass_rect.pos, pass_rect.size);
+        let mut framebuffer = SoftwareFramebuffer::new(
+            (pass_rect.size.x * dpi_factor) as usize,
+            (pass_rect.size.y * dpi_factor) as usize,
+            clear_color
+        );
+        let mut zbias = 0.0;
+        self.software_render_view(&pass, draw_list_id, &mut zbias, &mut framebuffer) ?;
+        Ok(Some(ShaderTexture {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            pixels: framebuffer.color
+        }))
+    }
+
+    // mirrors render_view of the GPU backends
+    fn software_render_view(&mut self, pass: &CxPass, draw_list_id: DrawListId, zbias: &mut f32, framebuffer: &mut SoftwareFramebuffer) -> Result<(), LiveError> {
+        let draw_items_len = self.draw_lists[draw_list_id].draw_items.len();
+        self.draw_lists[draw_list_id].uniform_view_transform(&Mat4::identity());
+
+        for draw_item_id in 0..draw_items_len {
+            if let Some(sub_list_id) = self.draw_lists[draw_list_id].draw_items[draw_item_id].kind.sub_list() {
+                self.software_render_view(pass, sub_list_id, zbias, framebuffer) ?;
+                continue;
+            }
+            let draw_list = &mut self.draw_lists[draw_list_id];
+            let draw_item = &mut draw_list.draw_items[draw_item_id];
+            let draw_call = if let Some(draw_call) = draw_item.kind.draw_call_mut() {
+                draw_call
+            }else {
+                continue;
+            };
+            draw_call.draw_uniforms.set_zbias(*zbias);
+            *zbias += pass.zbias_step;
+            let texture_ids: Vec<_> = draw_call.texture_slots.iter().map( | texture | {
+                texture.as_ref().map( | texture | texture.texture_id())
+            }).collect();
+            let mut textures = Vec::new();
+            for texture_id in texture_ids {
+                textures.push(match texture_id {
+                    Some(texture_id) if self.textures[texture_id].format.is_render() => self.software_render_target(texture_id) ?,
+                    Some(texture_id) => software_texture(&self.textures[texture_id].format),
+                    None => None
+                });
+            }
+
+            let draw_list = &self.draw_lists[draw_list_id];
+            let draw_item = &draw_list.draw_items[draw_item_id];
+            let draw_call = draw_item.kind.draw_call().unwrap();
+            let mapping = &self.draw_shaders.shaders[draw_call.draw_shader.draw_shader_id].mapping;
+            let draw_shader_def = if let Some(def) = self.shader_registry.draw_shader_defs.get(&draw_call.draw_shader.draw_shader_ptr) {
+                def
+            }else {
+                continue;
+            };
+            let geometry = if let Some(geometry_id) = draw_call.geometry_id {
+                &self.geometries[geometry_id]
+            }else {
+                continue;
+            };
+            let instances = draw_item.instances.as_ref().unwrap();
+            if mapping.instances.total_slots == 0 || mapping.geometries.total_slots == 0 {
+                continue;
+            }
+
+            let mut uniforms = ShaderInputs::default();
+            read_inputs(&mut uniforms, &mapping.pass_uniforms, pass.pass_uniforms.as_slice());
+            read_inputs(&mut uniforms, &mapping.view_uniforms, draw_list.draw_list_uniforms.as_slice());
+            read_inputs(&mut uniforms, &mapping.draw_uniforms, draw_call.draw_uniforms.as_slice());
+            read_inputs(&mut uniforms, &mapping.user_uniforms, &draw_call.user_uniforms);
+            for input in &mapping.live_uniforms.inputs {
+                let slots = &mapping.live_uniforms_buf[input.offset..input.offset + input.slots];
+                uniforms.live_values.insert(ValuePtr(input.live_ptr.unwrap()), ShaderValue::from_slots(input.ty.clone(), slots));
+            }
+
+            let mut interpreter = ShaderInterpreter::new(&self.shader_registry, draw_shader_def);
+            textures.resize(mapping.textures.len(), None);
+            interpreter.textures = textures;
+
+            for instance in instances.chunks_exact(mapping.instances.total_slots) {
+                let vertices: Vec<_> = geometry.vertices.chunks_exact(mapping.geometries.total_slots).map( | vertex | {
+                    let mut inputs = uniforms.clone();
+                    read_inputs(&mut inputs, &mapping.instances, instance);
+                    read_inputs(&mut inputs, &mapping.geometries, vertex);
+                    inputs
+                }).collect();
+                framebuffer.draw_triangles(&interpreter, &vertices, &geometry.indices) ?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_inputs(into: &mut ShaderInputs, inputs: &DrawShaderInputs, slots: &[f32]) {
+    for input in &inputs.inputs {
+        if let Some(slots) = slots.get(input.offset..input.offset + input.slots) {
+            let value = if let ShaderTy::Enum(_) = input.ty {
+                ShaderValue::from_slots(input.ty.clone(), &[decode_shader_enum(slots[0])])
+            }
+            else {
+                ShaderValue::from_slots(input.ty.clone(), slots)
+            };
+            into.fields.insert(input.id, value);
+        }
+    }
+}
+
+// the inverse of shader_enum
+#[cfg(any(target_arch = "wasm32", target_os="android", target_os="linux"))]
+fn decode_shader_enum(v: f32) -> f32 {
+    v
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os="android", target_os="linux")))]
+fn decode_shader_enum(v: f32) -> f32 {
+    v.to_bits() as f32
+}
+
+// the texel values as the samplers return them, for the textures filled on the cpu
+fn software_texture(format: &TextureFormat) -> Option<ShaderTexture> {
+    let u8_to_f32 = | v: u8 | v as f32 / 255.0;
+    let (width, height, pixels) = match format {
+        TextureFormat::VecBGRAu8_32 {width, height, data}
+            | TextureFormat::VecMipBGRAu8_32 {width, height, data, ..} => (*width, *height, data.iter().map( | v | vec4(
+            u8_to_f32((v >> 16) as u8),
+            u8_to_f32((v >> 8) as u8),
+            u8_to_f32(*v as u8),
+            u8_to_f32((v >> 24) as u8)
+        )).collect()),
+        TextureFormat::VecRGBAf32 {width, height, data} => (*width, *height, data.chunks_exact(4).map( | v | {
+            vec4(v[0], v[1], v[2], v[3])
+        }).collect()),
+        TextureFormat::VecRu8 {width, height, data, unpack_row_length} => {
+            let stride = unpack_row_length.unwrap_or(*width);
+            (*width, *height, (0..width * height).map( | i | {
+                vec4(u8_to_f32(data[(i / width) * stride + i % width]), 0.0, 0.0, 1.0)
+            }).collect())
+        }
+        TextureFormat::VecRGu8 {width, height, data, unpack_row_length} => {
+            let stride = unpack_row_length.unwrap_or(*width);
+            (*width, *height, (0..width * height).map( | i | {
+                let offset = ((i / width) * stride + i % width) * 2;
+                vec4(u8_to_f32(data[offset]), u8_to_f32(data[offset + 1]), 0.0, 1.0)
+            }).collect())
+        }
+        TextureFormat::VecRf32 {width, height, data} => (*width, *height, data.iter().map( | v | {
+            vec4(*v, 0.0, 0.0, 1.0)
+        }).collect()),
+        _ => return None
+    };
+    Some(ShaderTexture {width, height, pixels})
+}
@@ -16,7 +16,7 @@ use {
 
 pub struct WindowHandle(PoolId);
 
-#[derive(Clone, Debug, PartialEq, Copy)]
+#[derive(Clone, Debug, Default, PartialEq, Copy)]
 pub struct WindowId(usize, u64);
 
 impl WindowHandle {
@@ -0,0 +1,206 @@
+use makepad_platform::event::*;
+use makepad_platform::*;
+use std::cell::Cell;
+
+fn touch(state: TouchState, uid: u64, x: f64, y: f64) -> TouchPoint {
+    TouchPoint {
+        state,
+        abs: dvec2(x, y),
+        time: 0.0,
+        uid,
+        rotation_angle: 0.0,
+        force: 0.0,
+        radius: dvec2(1.0, 1.0),
+        handled: Cell::new(Area::Empty),
+        sweep_lock: Cell::new(Area::Empty),
+    }
+}
+
+fn update(recognizer: &mut GestureRecognizer, time: f64, touches: Vec<TouchPoint>) -> Vec<GestureEvent> {
+    recognizer.handle_touch_update(&TouchUpdateEvent {
+        time,
+        window_id: WindowId::default(),
+        modifiers: KeyModifiers::default(),
+        touches,
+    })
+}
+
+fn scroll(recognizer: &mut GestureRecognizer, time: f64, scroll: DVec2, control: bool) -> Vec<GestureEvent> {
+    recognizer.handle_scroll(&ScrollEvent {
+        window_id: WindowId::default(),
+        scroll,
+        abs: dvec2(50.0, 50.0),
+        modifiers: KeyModifiers {control, ..Default::default()},
+        handled_x: Cell::new(false),
+        handled_y: Cell::new(false),
+        is_mouse: false,
+        time,
+    })
+}
+
+fn assert_near(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+}
+
+#[test]
+fn test_pan() {
+    let mut gr = GestureRecognizer::default();
+    assert!(update(&mut gr, 0.0, vec![touch(TouchState::Start, 1, 0.0, 0.0)]).is_empty());
+    // below the threshold nothing happens yet
+    assert!(update(&mut gr, 0.1, vec![touch(TouchState::Move, 1, 5.0, 0.0)]).is_empty());
+    match &update(&mut gr, 0.2, vec![touch(TouchState::Move, 1, 20.0, 0.0)])[..] {
+        [GestureEvent::Pan(e)] => {
+            assert_eq!(e.state, GestureState::Start);
+            assert_eq!((e.abs, e.abs_start, e.delta, e.touch_count), (dvec2(20.0, 0.0), dvec2(0.0, 0.0), dvec2(20.0, 0.0), 1));
+        }
+        e => panic!("expected a pan start, got {:?}", e)
+    }
+    // a second finger moves the centroid but is not a pan
+    assert!(update(&mut gr, 0.3, vec![touch(TouchState::Start, 2, 40.0, 0.0)]).is_empty());
+    match &update(&mut gr, 0.4, vec![touch(TouchState::Move, 1, 20.0, 10.0), touch(TouchState::Move, 2, 40.0, 10.0)])[..] {
+        [GestureEvent::Pan(e)] => {
+            assert_eq!(e.state, GestureState::Update);
+            assert_eq!((e.abs, e.delta, e.touch_count), (dvec2(30.0, 10.0), dvec2(0.0, 10.0), 2));
+        }
+        e => panic!("expected a pan update, got {:?}", e)
+    }
+    assert!(update(&mut gr, 0.5, vec![touch(TouchState::Stop, 2, 40.0, 10.0)]).is_empty());
+    match &update(&mut gr, 0.6, vec![touch(TouchState::Stop, 1, 20.0, 10.0)])[..] {
+        [GestureEvent::Pan(e)] => assert_eq!(e.state, GestureState::End),
+        e => panic!("expected a pan end, got {:?}", e)
+    }
+}
+
+#[test]
+fn test_pinch_rotate() {
+    let mut gr = GestureRecognizer::default();
+    update(&mut gr, 0.0, vec![touch(TouchState::Start, 1, 0.0, 0.0), touch(TouchState::Start, 2, 100.0, 0.0)]);
+    // spreading the fingers symmetrically keeps the centre, so it is a pinch only
+    let events = update(&mut gr, 0.1, vec![touch(TouchState::Move, 1, -25.0, 0.0), touch(TouchState::Move, 2, 125.0, 0.0)]);
+    match &events[..] {
+        [GestureEvent::Pinch(e)] => {
+            assert_eq!(e.state, GestureState::Start);
+            assert_eq!(e.abs, dvec2(50.0, 0.0));
+            assert_near(e.scale, 1.5);
+            assert_near(e.scale_delta, 1.5);
+        }
+        e => panic!("expected a pinch start, got {:?}", e)
+    }
+    // turn a quarter clockwise around the centre, at the same distance
+    let events = update(&mut gr, 0.2, vec![touch(TouchState::Move, 1, 50.0, -75.0), touch(TouchState::Move, 2, 50.0, 75.0)]);
+    match &events[..] {
+        [GestureEvent::Rotate(e)] => {
+            assert_eq!(e.state, GestureState::Start);
+            assert_near(e.angle, std::f64::consts::FRAC_PI_2);
+        }
+        e => panic!("expected a rotate start, got {:?}", e)
+    }
+    let events = update(&mut gr, 0.3, vec![touch(TouchState::Move, 1, 50.0, -150.0), touch(TouchState::Move, 2, 50.0, 150.0)]);
+    match &events[..] {
+        [GestureEvent::Pinch(e)] => {
+            assert_eq!(e.state, GestureState::Update);
+            assert_near(e.scale, 3.0);
+            assert_near(e.scale_delta, 2.0);
+        }
+        e => panic!("expected a pinch update, got {:?}", e)
+    }
+    let events = update(&mut gr, 0.4, vec![touch(TouchState::Stop, 1, 50.0, -150.0)]);
+    let states: Vec<_> = events.iter().map( | e | match e {
+        GestureEvent::Pinch(e) => ("pinch", e.state),
+        GestureEvent::Rotate(e) => ("rotate", e.state),
+        e => panic!("unexpected {:?}", e)
+    }).collect();
+    assert_eq!(states, vec![("pinch", GestureState::End), ("rotate", GestureState::End)]);
+}
+
+#[test]
+fn test_long_press() {
+    let mut gr = GestureRecognizer::default();
+    update(&mut gr, 0.0, vec![touch(TouchState::Start, 1, 10.0, 10.0)]);
+    assert!(gr.needs_time());
+    assert!(gr.handle_time(0.3).is_empty());
+    match &gr.handle_time(0.6)[..] {
+        [GestureEvent::LongPress(e)] => assert_eq!(e.abs, dvec2(10.0, 10.0)),
+        e => panic!("expected a long press, got {:?}", e)
+    }
+    assert!(!gr.needs_time());
+    // lifting after a long press is not a tap, so two of them are no double tap
+    assert!(update(&mut gr, 0.7, vec![touch(TouchState::Stop, 1, 10.0, 10.0)]).is_empty());
+    update(&mut gr, 0.8, vec![touch(TouchState::Start, 1, 10.0, 10.0)]);
+    assert!(update(&mut gr, 0.85, vec![touch(TouchState::Stop, 1, 10.0, 10.0)]).is_empty());
+
+    // moving cancels it
+    update(&mut gr, 2.0, vec![touch(TouchState::Start, 1, 10.0, 10.0)]);
+    update(&mut gr, 2.1, vec![touch(TouchState::Move, 1, 30.0, 10.0)]);
+    assert!(!gr.needs_time());
+    assert!(gr.handle_time(3.0).is_empty());
+}
+
+#[test]
+fn test_double_tap() {
+    let mut gr = GestureRecognizer::default();
+    update(&mut gr, 0.0, vec![touch(TouchState::Start, 1, 10.0, 10.0)]);
+    assert!(update(&mut gr, 0.05, vec![touch(TouchState::Stop, 1, 10.0, 10.0)]).is_empty());
+    update(&mut gr, 0.15, vec![touch(TouchState::Start, 2, 14.0, 12.0)]);
+    match &update(&mut gr, 0.2, vec![touch(TouchState::Stop, 2, 14.0, 12.0)])[..] {
+        [GestureEvent::DoubleTap(e)] => assert_eq!((e.abs, e.time), (dvec2(14.0, 12.0), 0.2)),
+        e => panic!("expected a double tap, got {:?}", e)
+    }
+    // a third tap starts over
+    update(&mut gr, 0.3, vec![touch(TouchState::Start, 3, 14.0, 12.0)]);
+    assert!(update(&mut gr, 0.35, vec![touch(TouchState::Stop, 3, 14.0, 12.0)]).is_empty());
+    // too slow
+    update(&mut gr, 1.0, vec![touch(TouchState::Start, 4, 14.0, 12.0)]);
+    assert!(update(&mut gr, 1.05, vec![touch(TouchState::Stop, 4, 14.0, 12.0)]).is_empty());
+    // too far apart
+    update(&mut gr, 1.1, vec![touch(TouchState::Start, 5, 60.0, 12.0)]);
+    assert!(update(&mut gr, 1.15, vec![touch(TouchState::Stop, 5, 60.0, 12.0)]).is_empty());
+}
+
+#[test]
+fn test_trackpad() {
+    let mut gr = GestureRecognizer::new(GestureConfig {
+        scroll_pinch_speed: 0.01,
+        ..Default::default()
+    });
+    match &scroll(&mut gr, 0.0, dvec2(0.0, -100.0), true)[..] {
+        [GestureEvent::Pinch(e)] => {
+            assert!(e.is_trackpad);
+            assert_eq!(e.state, GestureState::Start);
+            assert_near(e.scale, 1.0f64.exp());
+        }
+        e => panic!("expected a pinch, got {:?}", e)
+    }
+    match &scroll(&mut gr, 0.05, dvec2(0.0, 100.0), true)[..] {
+        [GestureEvent::Pinch(e)] => {
+            assert_eq!(e.state, GestureState::Update);
+            assert_near(e.scale, 1.0);
+        }
+        e => panic!("expected a pinch, got {:?}", e)
+    }
+    // plain trackpad scrolling pans the other way the content scrolls
+    match &scroll(&mut gr, 0.1, dvec2(3.0, 4.0), false)[..] {
+        [GestureEvent::Pan(e)] => {
+            assert_eq!(e.state, GestureState::Start);
+            assert_eq!((e.delta, e.abs), (dvec2(-3.0, -4.0), dvec2(47.0, 46.0)));
+        }
+        e => panic!("expected a pan, got {:?}", e)
+    }
+    // they end once the trackpad goes quiet
+    assert!(gr.needs_time());
+    let events = gr.handle_time(0.3);
+    assert_eq!(events.len(), 2);
+    assert!(!gr.needs_time());
+
+    // and mouse wheels are not gestures
+    assert!(gr.handle_scroll(&ScrollEvent {
+        window_id: WindowId::default(),
+        scroll: dvec2(0.0, 10.0),
+        abs: dvec2(0.0, 0.0),
+        modifiers: KeyModifiers::default(),
+        handled_x: Cell::new(false),
+        handled_y: Cell::new(false),
+        is_mouse: true,
+        time: 1.0,
+    }).is_empty());
+}
@@ -0,0 +1,275 @@
+use crate::makepad_micro_serde::{SerBin, DeBin, DeBinErr};
+
+/// A change to a text, used to synchronize a file between the collab server and its participants.
+///
+/// A delta is a sequence of operations that together span the entire text it is applied to, so
+/// the length of that text is the sum of the lengths of all retain and delete operations. All
+/// lengths are in bytes, and always fall on character boundaries.
+///
+/// Deltas can be composed, so that several deltas can be sent as one, and transformed against each
+/// other. Given two deltas `a` and `b` that were made concurrently to the same text,
+/// `a.transform(&b)` returns `(a', b')` such that applying `a` and then `b'` results in the same
+/// text as applying `b` and then `a'`. This is what allows participants to make edits without
+/// waiting for each other.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub struct Delta {
+    operations: Vec<Operation>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub enum Operation {
+    Retain(usize),
+    Insert(String),
+    Delete(usize),
+}
+
+impl Delta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Returns `true` if applying this delta does not change the text.
+    pub fn is_identity(&self) -> bool {
+        self.operations.iter().all( | operation | matches!(operation, Operation::Retain(_)))
+    }
+
+    /// The length of the text this delta can be applied to.
+    pub fn base_len(&self) -> usize {
+        self.operations.iter().map( | operation | match operation {
+            Operation::Retain(len) | Operation::Delete(len) => *len,
+            Operation::Insert(_) => 0,
+        }).sum()
+    }
+
+    /// The length of the text after applying this delta.
+    pub fn target_len(&self) -> usize {
+        self.operations.iter().map( | operation | match operation {
+            Operation::Retain(len) => *len,
+            Operation::Insert(string) => string.len(),
+            Operation::Delete(_) => 0,
+        }).sum()
+    }
+
+    pub fn retain(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        if let Some(Operation::Retain(last_len)) = self.operations.last_mut() {
+            *last_len += len;
+        } else {
+            self.operations.push(Operation::Retain(len));
+        }
+    }
+
+    pub fn insert(&mut self, string: &str) {
+        if string.is_empty() {
+            return;
+        }
+        // Inserts always come before deletes at the same position, so that equivalent deltas
+        // have the same operations.
+        let index = match self.operations.last() {
+            Some(Operation::Delete(_)) => self.operations.len() - 1,
+            _ => self.operations.len(),
+        };
+        if index > 0 {
+            if let Operation::Insert(prev_string) = &mut self.operations[index - 1] {
+                prev_string.push_str(string);
+                return;
+            }
+        }
+        self.operations.insert(index, Operation::Insert(string.to_string()));
+    }
+
+    pub fn delete(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        if let Some(Operation::Delete(last_len)) = self.operations.last_mut() {
+            *last_len += len;
+        } else {
+            self.operations.push(Operation::Delete(len));
+        }
+    }
+
+    /// Applies this delta to `text`. Returns `None` if the delta does not span `text`.
+    pub fn apply(&self, text: &str) -> Option<String> {
+        if self.base_len() != text.len() {
+            return None;
+        }
+        let mut result = String::with_capacity(self.target_len());
+        let mut index = 0;
+        for operation in &self.operations {
+            match operation {
+                Operation::Retain(len) => {
+                    result.push_str(text.get(index..index + len) ?);
+                    index += len;
+                }
+                Operation::Insert(string) => result.push_str(string),
+                Operation::Delete(len) => {
+                    text.get(index..index + len) ?;
+                    index += len;
+                }
+            }
+        }
+        Some(result)
+    }
+
+    /// Returns a delta that has the same effect as applying this delta and then `other`. Returns
+    /// `None` if `other` does not apply to the result of this delta.
+    pub fn compose(&self, other: &Delta) -> Option<Delta> {
+        let mut result = Delta::new();
+        let mut operations_0 = self.operations.iter().cloned();
+        let mut operations_1 = other.operations.iter().cloned();
+        let mut operation_0 = operations_0.next();
+        let mut operation_1 = operations_1.next();
+        loop {
+            match (operation_0, operation_1) {
+                (None, None) => break,
+                (Some(Operation::Delete(len)), next_operation_1) => {
+                    result.delete(len);
+                    operation_0 = operations_0.next();
+                    operation_1 = next_operation_1;
+                }
+                (next_operation_0, Some(Operation::Insert(string))) => {
+                    result.insert(&string);
+                    operation_0 = next_operation_0;
+                    operation_1 = operations_1.next();
+                }
+                (None, _) | (_, None) => return None,
+                (Some(Operation::Retain(len_0)), Some(Operation::Retain(len_1))) => {
+                    let len = len_0.min(len_1);
+                    result.retain(len);
+                    operation_0 = split_retain(len_0, len, &mut operations_0);
+                    operation_1 = split_retain(len_1, len, &mut operations_1);
+                }
+                (Some(Operation::Retain(len_0)), Some(Operation::Delete(len_1))) => {
+                    let len = len_0.min(len_1);
+                    result.delete(len);
+                    operation_0 = split_retain(len_0, len, &mut operations_0);
+                    operation_1 = split_delete(len_1, len, &mut operations_1);
+                }
+                (Some(Operation::Insert(string)), Some(Operation::Retain(len_1))) => {
+                    let len = string.len().min(len_1);
+                    result.insert(string.get(..len) ?);
+                    operation_0 = split_insert(string, len, &mut operations_0) ?;
+                    operation_1 = split_retain(len_1, len, &mut operations_1);
+                }
+                (Some(Operation::Insert(string)), Some(Operation::Delete(len_1))) => {
+                    // The inserted text is deleted again, so neither ends up in the result.
+                    let len = string.len().min(len_1);
+                    operation_0 = split_insert(string, len, &mut operations_0) ?;
+                    operation_1 = split_delete(len_1, len, &mut operations_1);
+                }
+            }
+        }
+        Some(result)
+    }
+
+    /// Transforms this delta and `other`, which were made concurrently to the same text, so that
+    /// each of them can be applied after the other. When both deltas insert at the same position,
+    /// the text inserted by this delta comes first. Returns `None` if the deltas don't apply to
+    /// the same text.
+    pub fn transform(&self, other: &Delta) -> Option<(Delta, Delta)> {
+        let mut result_0 = Delta::new();
+        let mut result_1 = Delta::new();
+        let mut operations_0 = self.operations.iter().cloned();
+        let mut operations_1 = other.operations.iter().cloned();
+        let mut operation_0 = operations_0.next();
+        let mut operation_1 = operations_1.next();
+        loop {
+            match (operation_0, operation_1) {
+                (None, None) => break,
+                (Some(Operation::Insert(string)), next_operation_1) => {
+                    result_0.insert(&string);
+                    result_1.retain(string.len());
+                    operation_0 = operations_0.next();
+                    operation_1 = next_operation_1;
+                }
+                (next_operation_0, Some(Operation::Insert(string))) => {
+                    result_0.retain(string.len());
+                    result_1.insert(&string);
+                    operation_0 = next_operation_0;
+                    operation_1 = operations_1.next();
+                }
+                (None, _) | (_, None) => return None,
+                (Some(Operation::Retain(len_0)), Some(Operation::Retain(len_1))) => {
+                    let len = len_0.min(len_1);
+                    result_0.retain(len);
+                    result_1.retain(len);
+                    operation_0 = split_retain(len_0, len, &mut operations_0);
+                    operation_1 = split_retain(len_1, len, &mut operations_1);
+                }
+                (Some(Operation::Delete(len_0)), Some(Operation::Delete(len_1))) => {
+                    // Both deltas delete the same text, so neither has to do it again.
+                    let len = len_0.min(len_1);
+                    operation_0 = split_delete(len_0, len, &mut operations_0);
+                    operation_1 = split_delete(len_1, len, &mut operations_1);
+                }
+                (Some(Operation::Delete(len_0)), Some(Operation::Retain(len_1))) => {
+                    let len = len_0.min(len_1);
+                    result_0.delete(len);
+                    operation_0 = split_delete(len_0, len, &mut operations_0);
+                    operation_1 = split_retain(len_1, len, &mut operations_1);
+                }
+                (Some(Operation::Retain(len_0)), Some(Operation::Delete(len_1))) => {
+                    let len = len_0.min(len_1);
+                    result_1.delete(len);
+                    operation_0 = split_retain(len_0, len, &mut operations_0);
+                    operation_1 = split_delete(len_1, len, &mut operations_1);
+                }
+            }
+        }
+        Some((result_0, result_1))
+    }
+
+    /// Returns where the byte at `index` in the text this delta applies to ends up after applying
+    /// it. Text inserted at `index` moves it forward.
+    pub fn transform_index(&self, index: usize) -> usize {
+        let mut new_index = index;
+        let mut base_index = 0;
+        for operation in &self.operations {
+            if base_index > index {
+                break;
+            }
+            match operation {
+                Operation::Retain(len) => base_index += len,
+                Operation::Insert(string) => new_index += string.len(),
+                Operation::Delete(len) => {
+                    new_index -= (index - base_index).min(*len);
+                    base_index += len;
+                }
+            }
+        }
+        new_index
+    }
+}
+
+fn split_retain(len: usize, split_len: usize, operations: &mut impl Iterator<Item = Operation>) -> Option<Operation> {
+    if len > split_len {
+        Some(Operation::Retain(len - split_len))
+    } else {
+        operations.next()
+    }
+}
+
+fn split_delete(len: usize, split_len: usize, operations: &mut impl Iterator<Item = Operation>) -> Option<Operation> {
+    if len > split_len {
+        Some(Operation::Delete(len - split_len))
+    } else {
+        operations.next()
+    }
+}
+
+// Returns `None` if `split_len` does not fall on a character boundary, and `Some(None)` if there
+// are no more operations.
+fn split_insert(string: String, split_len: usize, operations: &mut impl Iterator<Item = Operation>) -> Option<Option<Operation>> {
+    if string.len() > split_len {
+        Some(Some(Operation::Insert(string.get(split_len..) ?.to_string())))
+    } else {
+        Some(operations.next())
+    }
+}
@@ -2,6 +2,7 @@ use {
     crate::{
         makepad_live_id::*,
         makepad_micro_serde::{SerBin, DeBin, DeBinErr},
+        delta::Delta,
     },
 };
 
@@ -36,10 +37,31 @@ pub enum FileRequest {
     /// If the client is the first participant for the file, this also causes the file to be opened
     /// on the server.
     OpenFile(String, u64),
+    /// Requests the collab server to remove the client as a participant from the file with the
+    /// given path. If the client is the last participant for the file, this also causes the file
+    /// to be closed on the server.
+    CloseFile(String),
     /// Requests the collab server to apply the given delta to the given revision of the file with
-    /// the given id.
-    SaveFile(String, String, u64),
+    /// the given path. The file is only written to disk when it is saved.
+    ApplyDelta(String, usize, Delta),
+    /// Requests the collab server to write the newest revision of the file with the given path to
+    /// disk.
+    SaveFile(String),
+    /// Informs the collab server of the selections of the client in the given revision of the file
+    /// with the given path, so they can be shown to the other participants.
+    SetSelections(String, usize, Vec<TextRange>),
+    /// Requests the collab server to search all files in its file tree for the given pattern.
+    Search(SearchQuery),
+}
 
+/// A type for representing a search over all files in the file tree.
+#[derive(Clone, Debug, Default, SerBin, DeBin)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Whether `pattern` is a regular expression, or should be matched literally.
+    pub is_regex: bool,
+    pub case_insensitive: bool,
+    pub whole_word: bool,
 }
 
 /// A type for representing either a response or a notification from the collab server.
@@ -57,11 +79,42 @@ pub enum FileResponse {
     /// The result of requesting the collab server to return its file tree.
     LoadFileTree(Result<FileTreeData, FileError>),
     /// The result of requesting the collab server to add the client as a participant to the file
-    /// with the given id.
-    OpenFile(Result<(String, String, u64), FileError>),
+    /// with the given id. Contains the path, the contents and the current revision of the file.
+    OpenFile(Result<(String, String, u64, usize), FileError>),
+    /// The result of requesting the collab server to remove the client as a participant from the
+    /// file with the given path.
+    CloseFile(Result<String, FileError>),
     /// The result of requesting the collab server to apply a delta to a revision of the file with
-    /// the given id.
-    SaveFile(Result<(String,String,String, u64), FileError>),
+    /// the given path. Contains the path and the new revision.
+    ApplyDelta(Result<(String, usize), FileError>),
+    /// The result of requesting the collab server to save the file with the given path. Contains
+    /// the path, and the contents of the file on disk before and after saving.
+    SaveFile(Result<(String, String, String), FileError>),
+    /// The result of informing the collab server of the selections of the client.
+    SetSelections(Result<String, FileError>),
+    /// The result of requesting the collab server to search all files in its file tree.
+    Search(Result<Vec<SearchResult>, FileError>),
+}
+
+/// A type for representing a selection in a file, as byte offsets into its contents.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A type for representing a single match of a search.
+///
+/// Matches never span more than one line. `start` and `end` are byte offsets into `line`.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct SearchResult {
+    /// The path of the file, relative to the root of the file tree.
+    pub path: String,
+    pub line_index: usize,
+    pub start: usize,
+    pub end: usize,
+    /// The contents of the matching line.
+    pub line: String,
 }
 
 /// A type for representing data about a file tree.
@@ -97,16 +150,26 @@ pub struct DirectoryEntry {
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileNotification {
     FileChangedOnDisk,
-    // Notifies the client that another client applied the given delta to the file with the given
-    // id. This is only sent for files for which the client is a participant.
-   // DeltaWasApplied(TextFileId),
+    /// Notifies the client that another client applied the given delta to the file with the given
+    /// path, resulting in the given revision. This is only sent for files for which the client is a
+    /// participant.
+    DeltaWasApplied(String, usize, Delta),
+    /// Notifies the client that the participant with the given id changed its selections in the
+    /// file with the given path. The selections are in the given revision of the file.
+    SelectionsChanged(String, usize, usize, Vec<TextRange>),
+    /// Notifies the client that the participant with the given id is no longer a participant for
+    /// the file with the given path.
+    ParticipantLeft(String, usize),
 }
 
 /// A type for representing errors from the collab server.
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileError {
     Unknown(String),
-    CannotOpen(String)
+    CannotOpen(String),
+    InvalidPattern(String),
+    /// The delta or revision in a request did not match the contents of the file.
+    OutOfSync(String),
 }
 
 /// An identifier for files on the collab server.
@@ -1,5 +1,7 @@
+pub mod delta;
 pub mod file_protocol;
 
+pub use delta::*;
 pub use file_protocol::*;
 pub use makepad_live_id;
 pub use makepad_micro_serde;
@@ -0,0 +1,64 @@
+use makepad_file_protocol::Delta;
+
+fn replace(base_len: usize, start: usize, end: usize, string: &str) -> Delta {
+    let mut delta = Delta::new();
+    delta.retain(start);
+    delta.delete(end - start);
+    delta.insert(string);
+    delta.retain(base_len - end);
+    delta
+}
+
+#[test]
+fn test_apply() {
+    let delta = replace(11, 6, 11, "there");
+    assert_eq!(delta.apply("hello world").unwrap(), "hello there");
+    assert!(delta.apply("hello").is_none());
+}
+
+#[test]
+fn test_compose() {
+    let a = replace(5, 5, 5, " world");
+    let b = replace(11, 0, 1, "J");
+    let ab = a.compose(&b).unwrap();
+    assert_eq!(ab.apply("hello").unwrap(), "Jello world");
+    assert!(b.compose(&a).is_none());
+}
+
+#[test]
+fn test_transform_converges() {
+    let text = "fn main() {}";
+    let deltas = [
+        replace(12, 3, 7, "start"),
+        replace(12, 11, 11, "x"),
+        replace(12, 0, 12, ""),
+        replace(12, 3, 3, "a"),
+        replace(12, 5, 10, "b"),
+    ];
+    for a in &deltas {
+        for b in &deltas {
+            let (a_prime, b_prime) = a.transform(b).unwrap();
+            let ab = b_prime.apply(&a.apply(text).unwrap()).unwrap();
+            let ba = a_prime.apply(&b.apply(text).unwrap()).unwrap();
+            assert_eq!(ab, ba);
+        }
+    }
+}
+
+#[test]
+fn test_transform_orders_concurrent_inserts() {
+    let a = replace(2, 1, 1, "a");
+    let b = replace(2, 1, 1, "b");
+    let (a_prime, b_prime) = a.transform(&b).unwrap();
+    assert_eq!(b_prime.apply(&a.apply("xy").unwrap()).unwrap(), "xaby");
+    assert_eq!(a_prime.apply(&b.apply("xy").unwrap()).unwrap(), "xaby");
+}
+
+#[test]
+fn test_transform_index() {
+    let delta = replace(10, 2, 5, "abcd");
+    assert_eq!(delta.transform_index(1), 1);
+    assert_eq!(delta.transform_index(2), 6);
+    assert_eq!(delta.transform_index(4), 6);
+    assert_eq!(delta.transform_index(7), 8);
+}
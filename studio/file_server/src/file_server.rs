@@ -1,6 +1,7 @@
 use {
     crate::{
         makepad_file_protocol::{
+            Delta,
             DirectoryEntry,
             FileNodeData,
             FileTreeData,
@@ -8,14 +9,19 @@ use {
             FileNotification,
             FileRequest,
             FileResponse,
+            SearchQuery,
+            SearchResult,
+            TextRange,
         },
+        makepad_regex::{Regex, RegexOptions},
     },
     std::{
         cmp::Ordering,
+        collections::HashMap,
         fmt,
         fs,
         path::{Path, PathBuf},
-        sync::{Arc, RwLock},
+        sync::{Arc, Mutex},
     },
 };
 
@@ -23,7 +29,7 @@ pub struct FileServer {
     // The id for the next connection
     next_connection_id: usize,
     // State that is shared between every connection
-    shared: Arc<RwLock<Shared >>,
+    shared: Arc<Mutex<Shared >>,
 }
 
 impl FileServer {
@@ -31,8 +37,10 @@ impl FileServer {
     pub fn new<P: Into<PathBuf >> (root_path: P) -> FileServer {
         FileServer {
             next_connection_id: 0,
-            shared: Arc::new(RwLock::new(Shared {
+            shared: Arc::new(Mutex::new(Shared {
                 root_path: root_path.into(),
+                notification_senders: HashMap::new(),
+                open_files: HashMap::new(),
             })),
         }
     }
@@ -44,10 +52,10 @@ impl FileServer {
     pub fn connect(&mut self, notification_sender: Box<dyn NotificationSender>) -> FileServerConnection {
         let connection_id = ConnectionId(self.next_connection_id);
         self.next_connection_id += 1;
+        self.shared.lock().unwrap().notification_senders.insert(connection_id, notification_sender);
         FileServerConnection {
-            _connection_id:connection_id,
+            connection_id,
             shared: self.shared.clone(),
-            _notification_sender: notification_sender
         }
     }
 }
@@ -55,11 +63,9 @@ impl FileServer {
 /// A connection to a collab server.
 pub struct FileServerConnection {
     // The id for this connection.
-    _connection_id: ConnectionId,
+    connection_id: ConnectionId,
     // State is shared between every connection.
-    shared: Arc<RwLock<Shared >>,
-    // Used to send notifications for this connection.
-    _notification_sender: Box<dyn NotificationSender>,
+    shared: Arc<Mutex<Shared >>,
 }
 
 impl FileServerConnection {
@@ -73,7 +79,11 @@ impl FileServerConnection {
         match request {
             FileRequest::LoadFileTree {with_data} => FileResponse::LoadFileTree(self.load_file_tree(with_data)),
             FileRequest::OpenFile(path,id) => FileResponse::OpenFile(self.open_file(path, id)),
-            FileRequest::SaveFile(path, delta, id) => FileResponse::SaveFile(self.save_file(path, delta, id)),
+            FileRequest::CloseFile(path) => FileResponse::CloseFile(self.close_file(path)),
+            FileRequest::ApplyDelta(path, revision, delta) => FileResponse::ApplyDelta(self.apply_delta(path, revision, delta)),
+            FileRequest::SaveFile(path) => FileResponse::SaveFile(self.save_file(path)),
+            FileRequest::SetSelections(path, revision, selections) => FileResponse::SetSelections(self.set_selections(path, revision, selections)),
+            FileRequest::Search(query) => FileResponse::Search(self.search(query)),
         }
     }
     
@@ -150,7 +160,7 @@ impl FileServerConnection {
             Ok(entries)
         }
         
-        let root_path = self.shared.read().unwrap().root_path.clone();
+        let root_path = self.shared.lock().unwrap().root_path.clone();
         
         let root = FileNodeData::Directory {
             entries: get_directory_entries(&root_path, with_data) ?,
@@ -158,49 +168,229 @@ impl FileServerConnection {
         Ok(FileTreeData {root_path: "".into(), root})
     }
     
+    // Handles a `Search` request.
+    fn search(&self, query: SearchQuery) -> Result<Vec<SearchResult>, FileError> {
+        // A recursive helper function for searching all files in a directory. The directories
+        // that are skipped are the same as for `load_file_tree`.
+        fn search_directory(
+            path: &Path,
+            child_path: &str,
+            regex: &Regex,
+            results: &mut Vec<SearchResult>
+        ) -> Result<(), FileError> {
+            for entry in fs::read_dir(path).map_err( | error | FileError::Unknown(error.to_string())) ? {
+                let entry = entry.map_err( | error | FileError::Unknown(error.to_string())) ?;
+                let entry_path = entry.path();
+                let Ok(name) = entry.file_name().into_string() else {
+                    continue;
+                };
+                if entry_path.is_dir() && name == "target" || name.starts_with('.') {
+                    continue;
+                }
+                let entry_child_path = if child_path.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", child_path, name)
+                };
+                if entry_path.is_dir() {
+                    search_directory(&entry_path, &entry_child_path, regex, results) ?;
+                } else if entry_path.is_file() {
+                    // Skip over files that can't be read or are not valid UTF-8, which are
+                    // most likely binary files.
+                    let Ok(text) = fs::read_to_string(&entry_path) else {
+                        continue;
+                    };
+                    for (line_index, line) in text.lines().enumerate() {
+                        for m in regex.find_iter(line) {
+                            if m.is_empty() {
+                                continue;
+                            }
+                            results.push(SearchResult {
+                                path: entry_child_path.clone(),
+                                line_index,
+                                start: m.start,
+                                end: m.end,
+                                line: line.to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        
+        let mut pattern = if query.is_regex {
+            query.pattern
+        } else {
+            makepad_regex::escape(&query.pattern)
+        };
+        if query.whole_word {
+            pattern = format!("\\b(?:{})\\b", pattern);
+        }
+        let regex = Regex::with_options(&pattern, RegexOptions {
+            case_insensitive: query.case_insensitive
+        }).map_err( | error | FileError::InvalidPattern(error.to_string())) ?;
+        
+        let root_path = self.shared.lock().unwrap().root_path.clone();
+        let mut results = Vec::new();
+        search_directory(&root_path, "", &regex, &mut results) ?;
+        results.sort_by( | result_0, result_1 | result_0.path.cmp(&result_1.path));
+        Ok(results)
+    }
+    
     fn make_full_path(&self, child_path:&String)->PathBuf{
-        let mut path = self.shared.read().unwrap().root_path.clone();
+        let mut path = self.shared.lock().unwrap().root_path.clone();
         path.push(child_path);
         path
     }
     
     // Handles an `OpenFile` request.
-    fn open_file(&self, child_path: String, id:u64) -> Result<(String, String, u64), FileError> {
+    fn open_file(&self, child_path: String, id:u64) -> Result<(String, String, u64, usize), FileError> {
         let path = self.make_full_path(&child_path);
-        
-        let bytes = fs::read(&path).map_err(
-            | error | FileError::Unknown(error.to_string())
-        ) ?;
-        // Converts the file contents to a `Text`. This is necessarily a lossy conversion
-        // because `Text` assumes everything is UTF-8 encoded, and this isn't always the
-        // case for files on disk (is this a problem?)
-        /*let text: Text = Text::from_lines(String::from_utf8_lossy(&bytes)
-            .lines()
-            .map( | line | line.chars().collect::<Vec<_ >> ())
-            .collect::<Vec<_ >>());*/
-        
-        let text = String::from_utf8_lossy(&bytes);
-        Ok((child_path, text.to_string(), id))
+        let mut shared = self.shared.lock().unwrap();
+        if !shared.open_files.contains_key(&child_path) {
+            let bytes = fs::read(&path).map_err(
+                | error | FileError::Unknown(error.to_string())
+            ) ?;
+            // Converts the file contents to a `String`. This is necessarily a lossy conversion
+            // because we assume everything is UTF-8 encoded, and this isn't always the case for
+            // files on disk (is this a problem?)
+            let text = String::from_utf8_lossy(&bytes).to_string();
+            shared.open_files.insert(child_path.clone(), OpenFile {
+                text,
+                base_revision: 0,
+                deltas: Vec::new(),
+                participants: HashMap::new(),
+                selections: HashMap::new(),
+            });
+        }
+        let shared = &mut *shared;
+        let file = shared.open_files.get_mut(&child_path).unwrap();
+        let revision = file.revision();
+        file.participants.insert(self.connection_id, revision);
+        // Let the new participant know where everyone else is.
+        for (connection_id, selections) in &file.selections {
+            if *connection_id != self.connection_id {
+                shared.notification_senders[&self.connection_id].send_notification(
+                    FileNotification::SelectionsChanged(child_path.clone(), connection_id.0, revision, selections.clone())
+                );
+            }
+        }
+        Ok((child_path, file.text.clone(), id, revision))
+    }
+    
+    // Handles a `CloseFile` request.
+    fn close_file(&self, child_path: String) -> Result<String, FileError> {
+        let mut shared = self.shared.lock().unwrap();
+        shared.remove_participant(&child_path, self.connection_id);
+        Ok(child_path)
     }
     
     // Handles an `ApplyDelta` request.
-    fn save_file(
+    fn apply_delta(
         &self,
         child_path: String,
-        new_content: String,
-        id: u64
-    ) -> Result<(String, String, String, u64), FileError> {
+        revision: usize,
+        delta: Delta,
+    ) -> Result<(String, usize), FileError> {
+        let mut shared = self.shared.lock().unwrap();
+        let shared = &mut *shared;
+        let file = shared.open_files.get_mut(&child_path).filter(
+            | file | file.participants.contains_key(&self.connection_id)
+        ).ok_or_else( || FileError::OutOfSync(format!("{} is not open", child_path))) ?;
+        
+        // Transform the delta against all deltas that were applied since the revision it is
+        // based on, so that it can be applied to the newest revision.
+        let mut delta = delta;
+        for applied_delta in file.deltas_since(revision) ? {
+            delta = applied_delta.transform(&delta).ok_or_else(
+                || FileError::OutOfSync(format!("invalid delta for {}", child_path))
+            ) ?.1;
+        }
+        let new_content = delta.apply(&file.text).ok_or_else(
+            || FileError::OutOfSync(format!("invalid delta for {}", child_path))
+        ) ?;
+        file.text = new_content;
+        for selections in file.selections.values_mut() {
+            for selection in selections {
+                selection.start = delta.transform_index(selection.start);
+                selection.end = delta.transform_index(selection.end);
+            }
+        }
+        file.deltas.push(delta.clone());
+        let new_revision = file.revision();
+        // The participant that sent the delta has seen every revision up to and including its
+        // own.
+        file.participants.insert(self.connection_id, new_revision);
+        for connection_id in file.participants.keys() {
+            if *connection_id != self.connection_id {
+                shared.notification_senders[connection_id].send_notification(
+                    FileNotification::DeltaWasApplied(child_path.clone(), new_revision, delta.clone())
+                );
+            }
+        }
+        file.remove_seen_deltas();
+        Ok((child_path, new_revision))
+    }
+    
+    // Handles a `SaveFile` request.
+    fn save_file(&self, child_path: String) -> Result<(String, String, String), FileError> {
         let path = self.make_full_path(&child_path);
+        let shared = self.shared.lock().unwrap();
+        let file = shared.open_files.get(&child_path).filter(
+            | file | file.participants.contains_key(&self.connection_id)
+        ).ok_or_else( || FileError::OutOfSync(format!("{} is not open", child_path))) ?;
         
         let old_content = String::from_utf8_lossy(&fs::read(&path).map_err(
             | error | FileError::Unknown(error.to_string())
         ) ?).to_string();
-
-        fs::write(&path, &new_content).map_err(
-            | error | FileError::Unknown(error.to_string())
-        ) ?;
+        if old_content != file.text {
+            fs::write(&path, &file.text).map_err(
+                | error | FileError::Unknown(error.to_string())
+            ) ?;
+        }
+        Ok((child_path, old_content, file.text.clone()))
+    }
+    
+    // Handles a `SetSelections` request.
+    fn set_selections(&self, child_path: String, revision: usize, selections: Vec<TextRange>) -> Result<String, FileError> {
+        let mut shared = self.shared.lock().unwrap();
+        let shared = &mut *shared;
+        let file = shared.open_files.get_mut(&child_path).filter(
+            | file | file.participants.contains_key(&self.connection_id)
+        ).ok_or_else( || FileError::OutOfSync(format!("{} is not open", child_path))) ?;
         
-        Ok((child_path, old_content, new_content, id))
+        let mut selections = selections;
+        for applied_delta in file.deltas_since(revision) ? {
+            for selection in &mut selections {
+                selection.start = applied_delta.transform_index(selection.start);
+                selection.end = applied_delta.transform_index(selection.end);
+            }
+        }
+        let current_revision = file.revision();
+        let seen_revision = file.participants.get_mut(&self.connection_id).unwrap();
+        *seen_revision = (*seen_revision).max(revision);
+        for connection_id in file.participants.keys() {
+            if *connection_id != self.connection_id {
+                shared.notification_senders[connection_id].send_notification(
+                    FileNotification::SelectionsChanged(child_path.clone(), self.connection_id.0, current_revision, selections.clone())
+                );
+            }
+        }
+        file.selections.insert(self.connection_id, selections);
+        file.remove_seen_deltas();
+        Ok(child_path)
+    }
+}
+
+impl Drop for FileServerConnection {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        let paths: Vec<String> = shared.open_files.keys().cloned().collect();
+        for path in paths {
+            shared.remove_participant(&path, self.connection_id);
+        }
+        shared.notification_senders.remove(&self.connection_id);
     }
 }
 
@@ -239,6 +429,68 @@ impl fmt::Debug for dyn NotificationSender {
 #[derive(Debug)]
 struct Shared {
     root_path: PathBuf,
+    notification_senders: HashMap<ConnectionId, Box<dyn NotificationSender>>,
+    // The files that have at least one participant, by path.
+    open_files: HashMap<String, OpenFile>,
+}
+
+impl Shared {
+    fn remove_participant(&mut self, path: &str, connection_id: ConnectionId) {
+        let Some(file) = self.open_files.get_mut(path) else {
+            return
+        };
+        if file.participants.remove(&connection_id).is_none() {
+            return
+        }
+        file.selections.remove(&connection_id);
+        if file.participants.is_empty() {
+            self.open_files.remove(path);
+            return
+        }
+        for other_connection_id in file.participants.keys() {
+            self.notification_senders[other_connection_id].send_notification(
+                FileNotification::ParticipantLeft(path.to_string(), connection_id.0)
+            );
+        }
+        file.remove_seen_deltas();
+    }
+}
+
+// A file that is open on the collab server.
+#[derive(Debug)]
+struct OpenFile {
+    // The contents of the newest revision.
+    text: String,
+    // The revision that the first delta in `deltas` applies to.
+    base_revision: usize,
+    deltas: Vec<Delta>,
+    // The newest revision that each participant is known to have seen.
+    participants: HashMap<ConnectionId, usize>,
+    // The selections of each participant, in the newest revision.
+    selections: HashMap<ConnectionId, Vec<TextRange>>,
+}
+
+impl OpenFile {
+    fn revision(&self) -> usize {
+        self.base_revision + self.deltas.len()
+    }
+    
+    fn deltas_since(&self, revision: usize) -> Result<&[Delta], FileError> {
+        if revision < self.base_revision || revision > self.revision() {
+            return Err(FileError::OutOfSync(format!("unknown revision {}", revision)));
+        }
+        Ok(&self.deltas[revision - self.base_revision..])
+    }
+    
+    // Removes the deltas that every participant has seen, since no request can be based on a
+    // revision before them anymore.
+    fn remove_seen_deltas(&mut self) {
+        let Some(revision) = self.participants.values().min().cloned() else {
+            return
+        };
+        self.deltas.drain(..revision - self.base_revision);
+        self.base_revision = revision;
+    }
 }
 
 /// An identifier for a connection.
@@ -0,0 +1,81 @@
+use {
+    makepad_file_protocol::{Delta, FileNotification, FileRequest, FileResponse, TextRange},
+    makepad_file_server::FileServer,
+    std::{fs, sync::mpsc},
+};
+
+fn insert(base_len: usize, index: usize, string: &str) -> Delta {
+    let mut delta = Delta::new();
+    delta.retain(index);
+    delta.insert(string);
+    delta.retain(base_len - index);
+    delta
+}
+
+#[test]
+fn test_concurrent_deltas_converge() {
+    let root_path = std::env::temp_dir().join(format!("makepad_collab_{}", std::process::id()));
+    fs::create_dir_all(&root_path).unwrap();
+    fs::write(root_path.join("main.rs"), "ac").unwrap();
+
+    let mut server = FileServer::new(&root_path);
+    let (notification_sender_0, notification_receiver_0) = mpsc::channel();
+    let connection_0 = server.connect(Box::new(move |notification| {
+        notification_sender_0.send(notification).unwrap()
+    }));
+    let (notification_sender_1, notification_receiver_1) = mpsc::channel();
+    let connection_1 = server.connect(Box::new(move |notification| {
+        notification_sender_1.send(notification).unwrap()
+    }));
+
+    for connection in [&connection_0, &connection_1] {
+        match connection.handle_request(FileRequest::OpenFile("main.rs".into(), 0)) {
+            FileResponse::OpenFile(Ok((_, text, _, revision))) => {
+                assert_eq!(text, "ac");
+                assert_eq!(revision, 0);
+            }
+            response => panic!("{:?}", response),
+        }
+    }
+
+    // Both participants edit revision 0 at the same time.
+    match connection_0.handle_request(FileRequest::ApplyDelta("main.rs".into(), 0, insert(2, 1, "b"))) {
+        FileResponse::ApplyDelta(Ok((_, revision))) => assert_eq!(revision, 1),
+        response => panic!("{:?}", response),
+    }
+    match connection_1.handle_request(FileRequest::ApplyDelta("main.rs".into(), 0, insert(2, 2, "d"))) {
+        FileResponse::ApplyDelta(Ok((_, revision))) => assert_eq!(revision, 2),
+        response => panic!("{:?}", response),
+    }
+    // Deltas are only written to disk when the file is saved.
+    assert_eq!(fs::read_to_string(root_path.join("main.rs")).unwrap(), "ac");
+    match connection_1.handle_request(FileRequest::SaveFile("main.rs".into())) {
+        FileResponse::SaveFile(Ok((_, old_content, new_content))) => {
+            assert_eq!(old_content, "ac");
+            assert_eq!(new_content, "abcd");
+        }
+        response => panic!("{:?}", response),
+    }
+    assert_eq!(fs::read_to_string(root_path.join("main.rs")).unwrap(), "abcd");
+
+    // Each participant is told about the delta of the other, as it was applied to the server.
+    match notification_receiver_1.try_recv().unwrap() {
+        FileNotification::DeltaWasApplied(_, 1, delta) => assert_eq!(delta.apply("ac").unwrap(), "abc"),
+        notification => panic!("{:?}", notification),
+    }
+    match notification_receiver_0.try_recv().unwrap() {
+        FileNotification::DeltaWasApplied(_, 2, delta) => assert_eq!(delta.apply("abc").unwrap(), "abcd"),
+        notification => panic!("{:?}", notification),
+    }
+
+    // Selections made in an old revision are moved to the newest one.
+    connection_0.handle_request(FileRequest::SetSelections("main.rs".into(), 1, vec![TextRange { start: 3, end: 3 }]));
+    match notification_receiver_1.try_recv().unwrap() {
+        FileNotification::SelectionsChanged(_, _, 2, selections) => assert_eq!(selections, [TextRange { start: 4, end: 4 }]),
+        notification => panic!("{:?}", notification),
+    }
+
+    drop(connection_0);
+    assert!(matches!(notification_receiver_1.try_recv().unwrap(), FileNotification::ParticipantLeft(..)));
+    fs::remove_dir_all(&root_path).unwrap();
+}
@@ -3,6 +3,7 @@ use crate::{
     makepad_widgets::*,
     makepad_micro_serde::*,
     makepad_widgets::file_tree::*,
+    makepad_file_protocol::SearchQuery,
     file_system::file_system::*,
     studio_editor::*,
     run_view::*,
@@ -38,6 +39,7 @@ impl LiveRegister for App{
         crate::makepad_widgets::live_design(cx);
         crate::makepad_code_editor::live_design(cx);
         crate::run_list::live_design(cx);
+        crate::test_list::live_design(cx);
         crate::log_list::live_design(cx);
         crate::profiler::live_design(cx);
         crate::run_view::live_design(cx);
@@ -76,6 +78,7 @@ pub enum AppAction{
     JumpTo(JumpTo),
     RedrawLog,
     RedrawProfiler,
+    RedrawTests,
     RedrawFile(FileNodeId),
     StartRecompile,
     ReloadFileTree,
@@ -87,15 +90,26 @@ pub enum AppAction{
 impl MatchEvent for App{
     fn handle_startup(&mut self, cx:&mut Cx){
         let mut root = "./".to_string();
+        let mut lsp = None;
         for arg in std::env::args(){
             if let Some(prefix) = arg.strip_prefix("--root="){
                 root = prefix.to_string();
-                break;
+            }
+            else if let Some(prefix) = arg.strip_prefix("--lsp="){
+                lsp = Some(prefix.to_string());
             }
         }
         let root_path = env::current_dir().unwrap().join(root);
                 
         self.data.file_system.init(cx, &root_path);
+        if let Some(lsp) = lsp {
+            // --lsp="rust-analyzer" or --lsp="my-server --stdio"
+            let mut parts = lsp.split_whitespace().map(|s| s.to_string());
+            if let Some(command) = parts.next() {
+                let args: Vec<String> = parts.collect();
+                self.data.file_system.start_language_server(&command, &args);
+            }
+        }
         self.data.build_manager.init(cx, &root_path);
         self.data.build_manager.discover_external_ip(cx);
         self.data.build_manager.start_http_server();
@@ -106,6 +120,7 @@ impl MatchEvent for App{
         let file_tree = self.ui.view(id!(file_tree));
         let log_list = self.ui.view(id!(log_list));
         let run_list = self.ui.view(id!(run_list));
+        let test_list = self.ui.view(id!(test_list));
         let profiler = self.ui.view(id!(profiler));
         match action.cast(){
             AppAction::JumpTo(jt)=>{
@@ -148,6 +163,9 @@ impl MatchEvent for App{
             AppAction::RedrawLog=>{
                 log_list.redraw(cx);
             }
+            AppAction::RedrawTests=>{
+                test_list.redraw(cx);
+            }
             AppAction::StartRecompile=>{
                 self.data.build_manager.start_recompile(cx);
             }
@@ -185,6 +203,10 @@ impl MatchEvent for App{
                 self.data.build_manager.clear_log(cx, &dock, &mut self.data.file_system);
                 log_list.redraw(cx);
             }
+            FileSystemAction::SearchResults => {
+                self.data.build_manager.show_search_results(&self.data.file_system.search_results);
+                log_list.redraw(cx);
+            }
             FileSystemAction::None=>()
         }
                 
@@ -207,7 +229,29 @@ impl MatchEvent for App{
             match action.cast(){
                 CodeEditorAction::TextDidChange => {
                     // lets write the file
-                    self.data.file_system.request_save_file(action.path.get(0))
+                    self.data.file_system.request_save_file(action.path.get(0));
+                    self.data.file_system.schedule_lsp_change(cx, action.path.get(0))
+                }
+                CodeEditorAction::GoToDefinition(position) => {
+                    self.data.file_system.request_definition(action.path.get(0), position)
+                }
+                CodeEditorAction::RequestHover(position) => {
+                    self.data.file_system.request_hover(action.path.get(0), position)
+                }
+                CodeEditorAction::RequestCompletion(position) => {
+                    self.data.file_system.request_completion(action.path.get(0), position)
+                }
+                CodeEditorAction::SearchWorkspace => {
+                    // Searches the workspace for the selected text.
+                    if let Some(session) = self.data.file_system.get_session_mut(action.path.get(0)) {
+                        let pattern = session.copy();
+                        if !pattern.is_empty() && !pattern.contains('\n') {
+                            self.data.file_system.request_search(SearchQuery {
+                                pattern,
+                                ..SearchQuery::default()
+                            });
+                        }
+                    }
                 }
                 CodeEditorAction::None=>{}
             }
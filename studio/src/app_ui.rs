@@ -9,6 +9,7 @@ live_design!{
     import makepad_studio::run_view::RunView;
     import makepad_studio::log_list::LogList;
     import makepad_studio::run_list::RunList;
+    import makepad_studio::test_list::TestList;
     import makepad_studio::profiler::Profiler;
     
     ICO_SEARCH = dep("crate://self/resources/icons/Icon_Search.svg")
@@ -108,7 +109,7 @@ live_design!{
             }
                 
             file_tree_tabs = Tabs {
-                tabs: [file_tree, search, run_list],
+                tabs: [file_tree, search, run_list, test_list],
                 selected: 2
             }
                 
@@ -157,6 +158,12 @@ live_design!{
                 kind: RunList
             }
                 
+            test_list = Tab {
+                name: "Test"
+                closable: false,
+                kind: TestList
+            }
+                
             file1 = Tab {
                 name: "app.rs",
                 closable: true,
@@ -222,6 +229,8 @@ live_design!{
             }
             RunList = <RunList> {
             }
+            TestList = <TestList> {
+            }
             Search = <RectView> {
                 draw_bg: {color: #2}
                 //  margin:{left: 0, top: 0}
@@ -4,6 +4,7 @@ use {
         file_system::file_system::FileSystem,
         makepad_micro_serde::*,
         makepad_widgets::*,
+        makepad_widgets::file_tree::FileNodeId,
         makepad_platform::makepad_live_compiler::LiveFileChange,
         makepad_platform::os::cx_stdin::{
             HostToStdin,
@@ -11,22 +12,23 @@ use {
         },
         makepad_platform::studio::{AppToStudioVec,AppToStudio,ProfileSampleEvent},
         makepad_platform::log::LogLevel,
+        makepad_file_protocol::SearchResult,
         build_manager::{
             build_protocol::*,
-            build_client::BuildClient
+            build_profile::{BuildProfile, BuildProfiles},
+            build_client::BuildClient,
+            test_explorer::{TestExplorer, TestLocation},
         },
         run_view::*,
         app::AppAction,
         makepad_shell::*,
     },
-    makepad_code_editor::{text, decoration::{Decoration, DecorationType}},
-    makepad_http::server::*,
+    makepad_code_editor::{text, decoration::{Decoration, DecorationSource, DecorationType}},
+    makepad_http::{server::*, router::{HttpRouter, StaticFiles}},
     std::{
         collections::HashMap,
-        io::prelude::*,
         path::PathBuf,
         path::Path,
-        fs::File,
     },
     std::sync::mpsc,
     std::thread,
@@ -86,6 +88,8 @@ pub struct BuildManager {
     recompile_timeout: f64,
     recompile_timer: Timer,
     pub binaries: Vec<BuildBinary>,
+    pub build_profiles: Vec<BuildProfile>,
+    pub tests: TestExplorer,
     pub active: ActiveBuilds,
     pub studio_http: String,
     pub recv_studio_msg: ToUIReceiver<(LiveId,AppToStudioVec)>,
@@ -126,6 +130,7 @@ impl BuildManager {
     }
     
     pub fn update_run_list(&mut self, _cx: &mut Cx) {
+        self.load_build_profiles();
         self.binaries.clear();
         match shell_env_cap(&[], &self.root_path, "cargo", &["run", "--bin"]) {
             Ok(_) => {}
@@ -148,6 +153,57 @@ impl BuildManager {
                 }
             }
         }
+        self.tests.set_packages(self.binaries.iter().map( | binary | binary.name.as_str()));
+    }
+    
+    /// Lists or runs tests of the package with the given index, replacing any test run of that
+    /// package that is still going.
+    ///
+    /// The failures of the tests that are run again are outdated, so the test decorations of
+    /// their files are cleared. Failures of other tests in those files are decorated again.
+    pub fn run_tests(&mut self, cx: &mut Cx, package_index: usize, run: TestRun, file_system: &mut FileSystem) {
+        let file_names = self.tests.failure_file_names(package_index, &run);
+        let process = self.tests.start_run(package_index, run);
+        for file_name in &file_names {
+            if let Some(file_id) = file_system.path_to_file_node_id(file_name) {
+                file_system.clear_decorations(&file_id, DecorationSource::Test);
+                cx.action(AppAction::RedrawFile(file_id))
+            }
+        }
+        for location in self.tests.failure_locations() {
+            if file_names.contains(&location.file_name) {
+                add_test_failure_decoration(file_system, &location);
+            }
+        }
+        let cmd_id = process.as_id();
+        self.log.retain(|(id, _)| *id != cmd_id);
+        self.clients[0].send_cmd_with_id(cmd_id, BuildCmd::Stop);
+        self.clients[0].send_cmd_with_id(cmd_id, BuildCmd::Run(process, self.studio_http.clone()));
+    }
+    
+    /// Loads the build profiles of the project. Errors in the profiles file are shown in the log.
+    pub fn load_build_profiles(&mut self) {
+        self.log.retain(|(id, _)| *id != live_id!(build_profiles));
+        match BuildProfiles::load(&self.root_path) {
+            Ok(profiles) => self.build_profiles = profiles.profiles,
+            Err(error) => {
+                self.build_profiles.clear();
+                self.log.push((live_id!(build_profiles), LogItem::Bare(LogItemBare {
+                    level: LogLevel::Error,
+                    line: error
+                })));
+            }
+        }
+    }
+    
+    /// The targets `binary` can be run with: the built-in ones, followed by the build profiles
+    /// of the project that apply to it.
+    pub fn build_targets(&self, binary: &str) -> Vec<BuildTarget> {
+        BuildTarget::builtin().chain(
+            self.build_profiles.iter()
+                .filter( | profile | profile.applies_to(binary))
+                .map( | profile | BuildTarget::Custom(Box::new(profile.clone())))
+        ).collect()
     }
     
     pub fn handle_tab_close(&mut self, tab_id: LiveId) -> bool {
@@ -191,6 +247,27 @@ impl BuildManager {
         self.profile.clear();
     }
     
+    /// Shows the results of a workspace search in the log, replacing the results of any previous
+    /// search, so that they can be jumped to like any other log location.
+    pub fn show_search_results(&mut self, results: &[SearchResult]) {
+        self.log.retain(|(id, _)| *id != live_id!(search));
+        for result in results {
+            self.log.push((live_id!(search), LogItem::Location(LogItemLocation {
+                level: LogLevel::Log,
+                file_name: result.path.clone(),
+                start: text::Position {
+                    line_index: result.line_index,
+                    byte_index: result.start
+                },
+                end: text::Position {
+                    line_index: result.line_index,
+                    byte_index: result.end
+                },
+                message: result.line.trim().to_string()
+            })));
+        }
+    }
+    
     pub fn start_recompile_timer(&mut self, cx: &mut Cx, ui: &WidgetRef) {
         cx.stop_timer(self.recompile_timer);
         self.recompile_timer = cx.start_timeout(self.recompile_timeout);
@@ -238,7 +315,7 @@ impl BuildManager {
                             if let Some(file_id) = file_system.path_to_file_node_id(&item.file_name) {
                                 match item.level{
                                     LogLevel::Warning=>{
-                                        file_system.add_decoration(file_id, Decoration::new(
+                                        file_system.add_decoration(file_id, DecorationSource::Build, Decoration::new(
                                             0,
                                             start,
                                             end,
@@ -247,7 +324,7 @@ impl BuildManager {
                                         cx.action(AppAction::RedrawFile(file_id))
                                     }
                                     LogLevel::Error=>{
-                                        file_system.add_decoration(file_id, Decoration::new(
+                                        file_system.add_decoration(file_id, DecorationSource::Build, Decoration::new(
                                             0,
                                             start,
                                             end,
@@ -283,7 +360,7 @@ impl BuildManager {
                         if let Some(file_id) = file_system.path_to_file_node_id(&loc.file_name) {
                             match loc.level{
                                 LogLevel::Warning=>{
-                                    file_system.add_decoration(file_id, Decoration::new(
+                                    file_system.add_decoration(file_id, DecorationSource::Build, Decoration::new(
                                         0,
                                         loc.start,
                                         loc.end,
@@ -292,7 +369,7 @@ impl BuildManager {
                                     cx.action(AppAction::RedrawFile(file_id))
                                 }
                                 LogLevel::Error=>{
-                                    file_system.add_decoration(file_id, Decoration::new(
+                                    file_system.add_decoration(file_id, DecorationSource::Build, Decoration::new(
                                         0,
                                         loc.start,
                                         loc.end,
@@ -334,6 +411,34 @@ impl BuildManager {
                             }
                         }
                     }
+                    BuildClientMessage::Test(message) => {
+                        let Some(package_index) = self.tests.package_index(wrap.cmd_id) else {
+                            continue
+                        };
+                        if let Some(failure) = self.tests.handle_message(package_index, message) {
+                            if let Some(location) = failure.location {
+                                let end = test_failure_end(&location);
+                                if let Some(file_id) = add_test_failure_decoration(file_system, &location) {
+                                    cx.action(AppAction::RedrawFile(file_id))
+                                }
+                                log.push((wrap.cmd_id, LogItem::Location(LogItemLocation {
+                                    level: LogLevel::Panic,
+                                    file_name: location.file_name,
+                                    start: location.start,
+                                    end,
+                                    message: format!("{}: {}", failure.test_name, failure.message)
+                                })));
+                            }
+                            else {
+                                log.push((wrap.cmd_id, LogItem::Bare(LogItemBare {
+                                    level: LogLevel::Panic,
+                                    line: format!("{}: {}", failure.test_name, failure.message)
+                                })));
+                            }
+                            cx.action(AppAction::RedrawLog)
+                        }
+                        cx.action(AppAction::RedrawTests)
+                    }
                     BuildClientMessage::AuxChanHostEndpointCreated(aux_chan_host_endpoint) => {
                         if let Some(active_build) = active.builds.get_mut(&wrap.cmd_id){
                             active_build.aux_chan_host_endpoint = Some(aux_chan_host_endpoint);                        
@@ -384,15 +489,30 @@ impl BuildManager {
                     break;
                 }
             }
+            let mut router = HttpRouter::new();
+            router.get("/$watch", | _ | {
+                HttpServerResponse::new(200, &[("Cache-Control", "max-age=0")], Vec::new())
+            });
+            router.get("/favicon.ico", | _ | HttpServerResponse::new(200, &[], Vec::new()));
             let remaps = [
                 (format!("/makepad/{}/", abs_makepad_path), makepad_path.clone()),
-                (format!("/makepad/{}/", std::env::current_dir().unwrap().display()), "".to_string()),
-                ("/makepad//".to_string(), format!("{}/{}",root,makepad_path.clone())),
+                (format!("/makepad/{}/", std::env::current_dir().unwrap().display()), ".".to_string()),
                 ("/makepad/".to_string(), format!("{}/{}",root,makepad_path.clone())),
-                ("/".to_string(), "".to_string())
+                ("/".to_string(), ".".to_string())
             ];
+            for (prefix, dir) in remaps {
+                router.static_files(&prefix, StaticFiles::new(dir)
+                    .with_header("Cross-Origin-Embedder-Policy", "require-corp")
+                    .with_header("Cross-Origin-Opener-Policy", "same-origin")
+                    .with_header("Cache-Control", "max-age=0")
+                    .with_extensions(&["html", "wasm", "css", "js", "ttf", "png", "jpg", "svg"]));
+            }
             let mut socket_id_to_build_id = HashMap::new();
             while let Ok(message) = rx_request.recv() {
+                // the router answers gets and posts, the rest is about websockets
+                let Some(message) = router.handle(message) else {
+                    continue
+                };
                 // only store last change, fix later
                 match message {
                     HttpServerRequest::ConnectWebSocket {web_socket_id, response_sender: _,headers} => {
@@ -411,76 +531,8 @@ impl BuildManager {
                                 let _ = studio_sender.send((*id,msg));
                             }
                         }
-                        //println!("GOT BINARY MESSAGE");
-                        // new incombing message from client
-                    }
-                    HttpServerRequest::Get {headers, response_sender} => {
-                        let path = &headers.path;
-                        // ok so this live connection.. where do we do it
-                        // i mean its just a network event msg. we can ignore that
-                        // we could just handle this in 'window'
-                        // or where shall we handle it
-                        // lets give live edit an api so you can codegen/live edit shaders?
-                        
-                        // alright wasm http server
-                        if path == "/$watch" {
-                            let header = "HTTP/1.1 200 OK\r\n\
-                                Cache-Control: max-age:0\r\n\
-                                Connection: close\r\n\r\n".to_string();
-                            let _ = response_sender.send(HttpServerResponse {header, body: vec![]});
-                            continue
-                        }
-                        if path == "/favicon.ico" {
-                            let header = "HTTP/1.1 200 OK\r\n\r\n".to_string();
-                            let _ = response_sender.send(HttpServerResponse {header, body: vec![]});
-                            continue
-                        }
-                        
-                        let mime_type = if path.ends_with(".html") {"text/html"}
-                        else if path.ends_with(".wasm") {"application/wasm"}
-                        else if path.ends_with(".css") {"text/css"}
-                        else if path.ends_with(".js") {"text/javascript"}
-                        else if path.ends_with(".ttf") {"application/ttf"}
-                        else if path.ends_with(".png") {"image/png"}
-                        else if path.ends_with(".jpg") {"image/jpg"}
-                        else if path.ends_with(".svg") {"image/svg+xml"}
-                        else {continue};
-                        
-                        if path.contains("..") || path.contains('\\') {
-                            continue
-                        }
-                        
-                        let mut strip = None;
-                        for remap in &remaps {
-                            if let Some(s) = path.strip_prefix(&remap.0) {
-                                strip = Some(format!("{}{}", remap.1, s));
-                                break;
-                            }
-                        }
-                        if let Some(base) = strip {
-                            if let Ok(mut file_handle) = File::open(base) {
-                                let mut body = Vec::<u8>::new();
-                                if file_handle.read_to_end(&mut body).is_ok() {
-                                    let header = format!(
-                                        "HTTP/1.1 200 OK\r\n\
-                                            Content-Type: {}\r\n\
-                                            Cross-Origin-Embedder-Policy: require-corp\r\n\
-                                            Cross-Origin-Opener-Policy: same-origin\r\n\
-                                            Content-encoding: none\r\n\
-                                            Cache-Control: max-age:0\r\n\
-                                            Content-Length: {}\r\n\
-                                            Connection: close\r\n\r\n",
-                                        mime_type,
-                                        body.len()
-                                    );
-                                    let _ = response_sender.send(HttpServerResponse {header, body});
-                                }
-                            }
-                        }
-                        
-                    }
-                    HttpServerRequest::Post {..} => { //headers, body, response}=>{
                     }
+                    HttpServerRequest::Get {..} | HttpServerRequest::Post {..} => ()
                 }
             }
         });
@@ -525,4 +577,23 @@ impl BuildManager {
             }
         });
     }
-}
\ No newline at end of file
+}
+
+// A failing assertion is marked by the character its location points at.
+fn test_failure_end(location: &TestLocation) -> text::Position {
+    text::Position {
+        line_index: location.start.line_index,
+        byte_index: location.start.byte_index + 1
+    }
+}
+
+fn add_test_failure_decoration(file_system: &mut FileSystem, location: &TestLocation) -> Option<FileNodeId> {
+    let file_id = file_system.path_to_file_node_id(&location.file_name) ?;
+    file_system.add_decoration(file_id, DecorationSource::Test, Decoration::new(
+        0,
+        location.start,
+        test_failure_end(location),
+        DecorationType::Error
+    ));
+    Some(file_id)
+}
@@ -0,0 +1,128 @@
+use {
+    crate::makepad_micro_serde::*,
+    std::{
+        fs,
+        io,
+        path::Path,
+    },
+};
+
+/// The file in the root of a project that holds its build profiles.
+pub const BUILD_PROFILES_FILE: &str = "makepad_builds.ron";
+
+/// A user defined build configuration, which is shown in the run list next to the built-in
+/// targets. Every field except the name is optional, so a profile only has to mention what it
+/// changes, for instance:
+///
+/// ```ron
+/// (profiles: [
+///     (name: "Nightly", features: ["nightly"]),
+///     (name: "Counter example", cargo_args: ["--example", "counter"], in_studio: true),
+/// ])
+/// ```
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, SerRon, DeRon)]
+pub struct BuildProfile {
+    pub name: String,
+    /// The cargo subcommand, `run` if not given.
+    pub command: Option<String>,
+    /// The rustup toolchain, `nightly` if not given.
+    pub toolchain: Option<String>,
+    pub release: Option<bool>,
+    pub features: Option<Vec<String>>,
+    pub no_default_features: Option<bool>,
+    /// The target triple to build for.
+    pub target: Option<String>,
+    pub env: Option<Vec<(String, String)>>,
+    pub cargo_args: Option<Vec<String>>,
+    /// Arguments for the binary itself, after the `--`.
+    pub run_args: Option<Vec<String>>,
+    /// Whether the binary is shown in a run view tab inside studio.
+    pub in_studio: Option<bool>,
+    /// The binaries the profile is shown for, all of them if not given.
+    pub binaries: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, SerRon, DeRon)]
+pub struct BuildProfiles {
+    pub profiles: Vec<BuildProfile>,
+}
+
+impl BuildProfile {
+    pub fn runs_in_studio(&self) -> bool {
+        self.in_studio.unwrap_or(false)
+    }
+
+    pub fn applies_to(&self, binary: &str) -> bool {
+        self.binaries.as_ref().is_none_or( | binaries | binaries.iter().any( | name | name == binary))
+    }
+
+    /// The arguments for `rustup` that build and run `binary` with this profile.
+    pub fn args(&self, binary: &str) -> Vec<String> {
+        let command = self.command.as_deref().unwrap_or("run");
+        let mut args: Vec<String> = vec![
+            "run".into(),
+            self.toolchain.clone().unwrap_or_else( || "nightly".into()),
+            "cargo".into(),
+            command.into(),
+            "-p".into(),
+            binary.into(),
+            "--message-format=json".into(),
+        ];
+        if self.release.unwrap_or(false) {
+            args.push("--release".into());
+        }
+        if let Some(features) = &self.features {
+            if !features.is_empty() {
+                args.push(format!("--features={}", features.join(",")));
+            }
+        }
+        if self.no_default_features.unwrap_or(false) {
+            args.push("--no-default-features".into());
+        }
+        if let Some(target) = &self.target {
+            args.push(format!("--target={}", target));
+        }
+        args.extend(self.cargo_args.iter().flatten().cloned());
+        // Only `cargo run` passes arguments on to the binary.
+        if command == "run" {
+            args.push("--".into());
+            args.push("--message-format=json".into());
+            if self.runs_in_studio() {
+                args.push("--stdin-loop".into());
+            }
+            args.extend(self.run_args.iter().flatten().cloned());
+        }
+        args
+    }
+
+    pub fn env(&self) -> &[(String, String)] {
+        self.env.as_deref().unwrap_or(&[])
+    }
+}
+
+impl BuildProfiles {
+    /// Loads the build profiles of the project in `root_path`. A project without a profiles file
+    /// has no profiles.
+    pub fn load(root_path: &Path) -> Result<Self, String> {
+        let path = root_path.join(BUILD_PROFILES_FILE);
+        let string = match fs::read_to_string(&path) {
+            Ok(string) => string,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(format!("Cannot read {}: {}", path.display(), error)),
+        };
+        Self::parse(&string).map_err( | error | format!("{}: {}", path.display(), error))
+    }
+
+    pub fn parse(string: &str) -> Result<Self, String> {
+        let profiles = Self::deserialize_ron(string).map_err( | error | format!("{:?}", error)) ?;
+        for (index, profile) in profiles.profiles.iter().enumerate() {
+            if profile.name.is_empty() {
+                return Err(format!("profile {} has no name", index));
+            }
+            if profiles.profiles[..index].iter().any( | other | other.name == profile.name) {
+                return Err(format!("there is more than one profile named {:?}", profile.name));
+            }
+        }
+        Ok(profiles)
+    }
+}
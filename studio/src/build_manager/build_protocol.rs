@@ -2,6 +2,10 @@ use crate::{
     makepad_live_id::LiveId,
     makepad_platform::log::LogLevel,
     makepad_code_editor::text::{Position},
+    build_manager::{
+        build_profile::BuildProfile,
+        libtest_json::LibtestEvent,
+    },
 };
 
 
@@ -22,6 +26,109 @@ pub enum BuildTarget {
     CheckWindows,
     CheckLinux,
     CheckAll,
+    /// Checks the draw shaders of the app without opening a window, see `Cx::check_shaders`.
+    CheckShaders,
+    /// A build profile from the project, see `BuildProfile`.
+    Custom(Box<BuildProfile>),
+    /// Runs the tests of the package with `cargo test`, see `TestRun`.
+    Test(Box<TestRun>),
+}
+
+/// A test binary of a package, as run by `cargo test`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TestTarget {
+    /// The unit tests of the library.
+    Lib,
+    /// The unit tests of the binaries.
+    Bins,
+    /// An integration test, by the name of its target.
+    Test(String),
+    /// The documentation tests.
+    Doc,
+}
+
+impl TestTarget {
+    /// The name shown in the test explorer.
+    pub fn label(&self) -> String {
+        match self {
+            Self::Lib => "lib".to_string(),
+            Self::Bins => "bins".to_string(),
+            Self::Test(name) => format!("tests/{}", name),
+            Self::Doc => "doc".to_string(),
+        }
+    }
+    
+    /// The arguments for `cargo test` that select this test binary.
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            Self::Lib => vec!["--lib".into()],
+            Self::Bins => vec!["--bins".into()],
+            Self::Test(name) => vec!["--test".into(), name.clone()],
+            Self::Doc => vec!["--doc".into()],
+        }
+    }
+}
+
+/// Which tests a test target runs. Without a target the tests of all test binaries of the
+/// package run, and without a filter all tests of those binaries.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TestRun {
+    pub target: Option<TestTarget>,
+    pub filter: Option<String>,
+    /// Whether the filter has to match the whole test name rather than a part of it.
+    pub exact: bool,
+    /// Only lists the tests instead of running them.
+    pub list: bool,
+}
+
+impl TestRun {
+    pub fn matches(&self, target: &TestTarget, test_name: &str) -> bool {
+        if self.target.as_ref().is_some_and( | run_target | run_target != target) {
+            return false
+        }
+        match &self.filter {
+            None => true,
+            Some(filter) if self.exact => test_name == filter,
+            Some(filter) => test_name.contains(filter.as_str()),
+        }
+    }
+    
+    /// The arguments for `rustup` that run the tests of `package`.
+    pub fn args(&self, package: &str) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "run".into(),
+            "nightly".into(),
+            "cargo".into(),
+            "test".into(),
+            "-p".into(),
+            package.into(),
+        ];
+        if let Some(target) = &self.target {
+            args.extend(target.args());
+        }
+        args.extend([
+            "--message-format=json".into(),
+            "--".into(),
+        ]);
+        if self.list {
+            args.extend(["--list".into(), "--format".into(), "terse".into()]);
+            return args
+        }
+        if let Some(filter) = &self.filter {
+            args.push(filter.clone());
+            if self.exact {
+                args.push("--exact".into());
+            }
+        }
+        args.extend([
+            "-Z".into(),
+            "unstable-options".into(),
+            "--format".into(),
+            "json".into(),
+            "--report-time".into(),
+        ]);
+        args
+    }
 }
 
 impl BuildTarget {
@@ -29,6 +136,7 @@ impl BuildTarget {
         match self{
             Self::ReleaseStudio=>true,
             Self::DebugStudio=>true,
+            Self::Custom(profile)=>profile.runs_in_studio(),
             _=>false
         }
     }
@@ -48,8 +156,17 @@ impl BuildTarget {
     pub const CHECK_WINDOWS:u64 = 12;
     pub const CHECK_LINUX:u64 = 13;
     pub const CHECK_ALL:u64 = 14;
-    pub fn len() -> u64 {Self::CHECK_ALL+1}
-    pub fn name(&self) -> &'static str {
+    pub const CHECK_SHADERS:u64 = 15;
+    pub const TEST:u64 = 16;
+    /// The number of built-in targets shown in the run list.
+    pub fn len() -> u64 {Self::CHECK_SHADERS+1}
+    
+    /// The built-in targets, in the order they are shown in the run list.
+    pub fn builtin() -> impl Iterator<Item = Self> {
+        (0..Self::len()).map( | id | Self::from_id(id).unwrap())
+    }
+    
+    pub fn name(&self) -> &str {
         match self {
             Self::ReleaseStudio=>"Release Studio",
             Self::DebugStudio=>"Debug Studio",
@@ -66,6 +183,9 @@ impl BuildTarget {
             Self::CheckWindows=>"Check Windows",
             Self::CheckLinux=>"Check Linux",
             Self::CheckAll=>"Check All",
+            Self::CheckShaders=>"Check Shaders",
+            Self::Custom(profile)=>&profile.name,
+            Self::Test(_)=>"Test",
         }
     }
     pub fn as_id(&self) -> u64 {
@@ -84,11 +204,19 @@ impl BuildTarget {
             Self::CheckMacos=>Self::CHECK_MACOS,
             Self::CheckWindows=>Self::CHECK_WINDOWS,
             Self::CheckLinux=>Self::CHECK_LINUX,
-            Self::CheckAll=>Self::CHECK_ALL
+            Self::CheckAll=>Self::CHECK_ALL,
+            Self::CheckShaders=>Self::CHECK_SHADERS,
+            // Profile names are unique within a project, so their hash is too. It can't clash
+            // with the small numbers of the built-in targets in practice.
+            Self::Custom(profile)=>LiveId::from_str(&profile.name).0,
+            // All test runs of a package share an id, so a new run replaces the previous one.
+            Self::Test(_)=>Self::TEST,
         }
     }
-    pub fn from_id(tgt:u64) -> Self {
-        match tgt {
+    /// Returns the built-in target with the given id. Custom and test targets can't be found by
+    /// id, as they carry more than their id.
+    pub fn from_id(tgt:u64) -> Option<Self> {
+        Some(match tgt {
             Self::RELEASE => Self::Release,
             Self::DEBUG => Self::Debug,
             Self::RELEASE_STUDIO => Self::ReleaseStudio,
@@ -104,8 +232,9 @@ impl BuildTarget {
             Self::CHECK_WINDOWS => Self::CheckWindows,
             Self::CHECK_LINUX => Self::CheckLinux,
             Self::CHECK_ALL => Self::CheckAll,
-            _ => panic!()
-        }
+            Self::CHECK_SHADERS => Self::CheckShaders,
+            _ => return None
+        })
     }
 }
 
@@ -163,8 +292,18 @@ pub enum LogItem {
     StdinToHost(String),
 }
 
+#[derive(Clone, Debug)]
+pub enum TestMessage {
+    /// A test found by listing the tests of a package.
+    Listed(TestTarget, String),
+    Event(TestTarget, Box<LibtestEvent>),
+    /// The test process exited.
+    Finished,
+}
+
 #[derive(Clone)]
 pub enum BuildClientMessage{
     LogItem(LogItem),
+    Test(TestMessage),
     AuxChanHostEndpointCreated(crate::makepad_platform::cx_stdin::aux_chan::HostEndpoint),
 }
@@ -12,6 +12,7 @@ use {
                 ChildStdIO
             },
             rustc_json::*,
+            libtest_json::*,
         },
     },
     std::{
@@ -279,16 +280,34 @@ impl BuildConnection {
                 what.binary.clone(),
                 "--release".into(),
                 "--message-format=json".into(),
-            ]
+            ],
+            BuildTarget::CheckShaders => vec![
+                "run".into(),
+                "nightly".into(),
+                "cargo".into(),
+                "run".into(),
+                "-p".into(),
+                what.binary.clone(),
+                "--message-format=json".into(),
+                "--".into(),
+                "--message-format=json".into(),
+                "--check-shaders".into(),
+            ],
+            BuildTarget::Custom(profile) => profile.args(&what.binary),
+            BuildTarget::Test(run) => run.args(&what.binary),
         };
         
         let http = format!("{}/{}", http, cmd_id.0);
-        let env = [
+        let mut env = vec![
             ("MAKEPAD_STUDIO_HTTP", http.as_str()),
             ("MAKEPAD", "lines")
         ];
+        if let BuildTarget::Custom(profile) = &what.target {
+            env.extend(profile.env().iter().map( | (key, value) | (key.as_str(), value.as_str())));
+        }
 
         let process = ChildProcess::start("rustup", &args, path, &env).expect("Cannot start process");
+        let test_run = if let BuildTarget::Test(run) = &what.target {Some(run.clone())} else {None};
         
         shared.write().unwrap().processes.insert(
             what,
@@ -311,11 +330,25 @@ impl BuildConnection {
        // let mut stderr_state = StdErrState::First;
         //let stdin_sender = process.stdin_sender.clone();
         std::thread::spawn(move || {
+            // the test binary that runs, which cargo prints on stderr before it starts it
+            let mut test_target = TestTarget::Lib;
             // lets create a BuildProcess and run it
             while let Ok(line) = process.line_receiver.recv() {
                 
                 match line {
                     ChildStdIO::StdOut(line) => {
+                        if let Some(test_run) = &test_run {
+                            if test_run.list {
+                                if let Some(name) = parse_test_list_line(&line) {
+                                    msg_sender.send_test_message(cmd_id, TestMessage::Listed(test_target.clone(), name.to_string()));
+                                    continue;
+                                }
+                            }
+                            else if let Ok(event) = LibtestEvent::deserialize_json(&line) {
+                                msg_sender.send_test_message(cmd_id, TestMessage::Event(test_target.clone(), Box::new(event)));
+                                continue;
+                            }
+                        }
                         let comp_msg: Result<RustcCompilerMessage, DeJsonErr> = DeJson::deserialize_json(&line);
                         match comp_msg {
                             Ok(msg) => {
@@ -342,7 +375,11 @@ impl BuildConnection {
                         }                        
                     }
                     ChildStdIO::StdErr(line) => {
-                        if line.trim().starts_with("Running ") {
+                        if let Some(target) = test_run.as_ref().and_then( | _ | parse_test_target_line(&line)) {
+                            test_target = target;
+                            msg_sender.send_bare_message(cmd_id, LogLevel::Wait, line);
+                        }
+                        else if line.trim().starts_with("Running ") {
                            msg_sender.send_bare_message(cmd_id, LogLevel::Wait, line);
                         }
                         else if line.trim().starts_with("Compiling ") {
@@ -363,6 +400,9 @@ impl BuildConnection {
                     }
                     ChildStdIO::Term => {
                         msg_sender.send_bare_message(cmd_id, LogLevel::Log, "process terminated".into());
+                        if test_run.is_some() {
+                            msg_sender.send_test_message(cmd_id, TestMessage::Finished);
+                        }
                         break;
                     }
                     ChildStdIO::Kill => {
@@ -418,6 +458,13 @@ pub trait MsgSender: Send {
         });
     }
     
+    fn send_test_message(&self, cmd_id: LiveId, message: TestMessage) {
+        self.send_message(BuildClientMessageWrap{
+            cmd_id,
+            message:BuildClientMessage::Test(message)
+        });
+    }
+    
     fn send_stdin_to_host_msg(&self, cmd_id: LiveId, line: String) {
         self.send_message(BuildClientMessageWrap{
             cmd_id,
@@ -456,6 +503,12 @@ pub trait MsgSender: Send {
             };
             if let Some(span) = msg.spans.iter().find( | span | span.is_primary) {
                 self.send_location_msg(cmd_id, level, span.file_name.clone(),span.start(), span.end(), msg.message.clone());
+                // notes that point somewhere else, like where an expected type comes from
+                for child in &msg.children {
+                    if let Some(span) = child.spans.iter().find( | span | span.is_primary) {
+                        self.send_location_msg(cmd_id, LogLevel::Log, span.file_name.clone(), span.start(), span.end(), format!("{}: {}", child.level, child.message));
+                    }
+                }
                 /*
                 if let Some(label) = &span.label {
                     self.send_location_msg(cmd_id, level, span.file_name.clone(), range, label.clone());
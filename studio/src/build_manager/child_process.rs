@@ -1,16 +1,17 @@
 use std::{
     process::{Command, Child, Stdio},
-    sync::mpsc::{self, Sender, Receiver},
+    sync::{Arc, Mutex, mpsc::{self, Sender, Receiver}},
     thread,
     io::prelude::*,
     io::BufReader,
     str,
     path::PathBuf,
+    time::Duration,
 };
 use crate::makepad_platform::cx_stdin::aux_chan;
 
 pub struct ChildProcess {
-    pub child: Child,
+    pub child: Arc<Mutex<Child>>,
     pub stdin_sender: Sender<ChildStdIn>,
     pub line_sender: Sender<ChildStdIO>,
     pub line_receiver: Receiver<ChildStdIO>,
@@ -64,9 +65,8 @@ impl ChildProcess {
         let stdout = child.stdout.take().expect("stdout cannot be taken!");
         let stderr = child.stderr.take().expect("stderr cannot be taken!");
         
-        let _stdout_thread = {
+        let stdout_thread = {
             let line_sender = line_sender.clone();
-            let stdin_sender = stdin_sender.clone();
             thread::spawn(move || {
                 let mut reader = BufReader::new(stdout);
                 loop{
@@ -80,15 +80,13 @@ impl ChildProcess {
                         }
                     }
                     else{
-                        let _ = line_sender.send(ChildStdIO::Term);
-                        let _ = stdin_sender.send(ChildStdIn::Term);
                         break;
                     }
                 }
             })
         };
         
-        let _stderr_thread = {
+        let stderr_thread = {
             let line_sender = line_sender.clone();
             thread::spawn(move || {
                 let mut reader = BufReader::new(stderr);
@@ -106,7 +104,7 @@ impl ChildProcess {
                         break;
                     }
                 }
-            });
+            })
         };
 
         let _stdin_thread = {
@@ -126,6 +124,30 @@ impl ChildProcess {
                 }
             });
         };
+        
+        let child = Arc::new(Mutex::new(child));
+        let _wait_thread = {
+            let child = child.clone();
+            let line_sender = line_sender.clone();
+            let stdin_sender = stdin_sender.clone();
+            thread::spawn(move || {
+                // the pipes are closed once the process exits, so by the time both readers are
+                // done all of its output was sent, and Term is the last thing on the line channel
+                let _ = stdout_thread.join();
+                let _ = stderr_thread.join();
+                // poll instead of blocking in wait, which would keep the child locked so that
+                // kill couldn't get to it
+                loop {
+                    match child.lock().unwrap().try_wait() {
+                        Ok(None) => (),
+                        Ok(Some(_)) | Err(_) => break
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                let _ = stdin_sender.send(ChildStdIn::Term);
+                let _ = line_sender.send(ChildStdIO::Term);
+            })
+        };
         Ok(ChildProcess {
             stdin_sender,
             line_sender,
@@ -135,13 +157,14 @@ impl ChildProcess {
         })
     }
     
-    pub fn wait(mut self) {
-        let _ = self.child.wait();
+    pub fn wait(self) {
+        let _ = self.child.lock().unwrap().wait();
     }
     
-    pub fn kill(mut self) {
+    pub fn kill(self) {
         let _ = self.stdin_sender.send(ChildStdIn::Term);
-        let _ = self.child.kill();
-        let _ = self.child.wait();
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
     }
 }
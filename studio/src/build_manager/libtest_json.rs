@@ -0,0 +1,60 @@
+use {
+    crate::{
+        makepad_micro_serde::*,
+        build_manager::build_protocol::TestTarget,
+    },
+    std::path::Path,
+};
+
+// libtest json output, as printed by test binaries run with `-Z unstable-options --format json`
+#[derive(Clone, DeJson, Debug, Default)]
+pub struct LibtestEvent {
+    pub _type: String,
+    pub event: String,
+    pub name: Option<String>,
+    pub test_count: Option<usize>,
+    pub passed: Option<usize>,
+    pub failed: Option<usize>,
+    pub ignored: Option<usize>,
+    pub measured: Option<usize>,
+    pub filtered_out: Option<usize>,
+    pub exec_time: Option<f64>,
+    pub stdout: Option<String>,
+    pub message: Option<String>,
+    pub shuffle_seed: Option<u64>,
+    pub median: Option<u64>,
+    pub deviation: Option<u64>,
+    pub mib_per_second: Option<u64>,
+}
+
+impl LibtestEvent {
+    pub fn is_test(&self) -> bool {
+        self._type == "test"
+    }
+}
+
+// a line of `--list --format terse` output looks like `module::name: test`
+pub fn parse_test_list_line(line: &str) -> Option<&str> {
+    line.trim_end().strip_suffix(": test")
+}
+
+// cargo prints the test binary it runs next on stderr, in a line that looks like
+// `Running unittests src/lib.rs (target/debug/deps/app-0123)`, `Running tests/app.rs (...)` or
+// `Doc-tests app`
+pub fn parse_test_target_line(line: &str) -> Option<TestTarget> {
+    let line = line.trim();
+    if line.starts_with("Doc-tests ") {
+        return Some(TestTarget::Doc)
+    }
+    let source = line.strip_prefix("Running ")?;
+    let source = source.split(" (").next().unwrap_or(source);
+    if let Some(source) = source.strip_prefix("unittests ") {
+        let path = Path::new(source);
+        let is_bin = path.ends_with("src/main.rs") || path.parent().is_some_and( | parent | parent.ends_with("src/bin"));
+        return Some(if is_bin {TestTarget::Bins} else {TestTarget::Lib})
+    }
+    // an integration test is either `tests/name.rs` or `tests/name/main.rs`
+    let path = Path::new(source);
+    let path = if path.file_stem()? == "main" {path.parent()?} else {path};
+    Some(TestTarget::Test(path.file_stem()?.to_str()?.to_string()))
+}
@@ -1,7 +1,10 @@
 pub mod build_client;
+pub mod build_profile;
 pub mod build_protocol;
 pub mod build_server;
 pub mod build_manager;
 pub mod child_process;
+pub mod libtest_json;
 pub mod rustc_json;
+pub mod test_explorer;
  
\ No newline at end of file
@@ -0,0 +1,425 @@
+use crate::{
+    makepad_live_id::LiveId,
+    makepad_code_editor::text::Position,
+    build_manager::build_protocol::*,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStatus {
+    Unknown,
+    Running,
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestLocation {
+    pub file_name: String,
+    pub start: Position,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestFailure {
+    pub test_name: String,
+    pub message: String,
+    pub location: Option<TestLocation>,
+}
+
+/// A test binary, module or test in the tree of a package. The children of a package are its
+/// test binaries, whose path is empty. The path of the other nodes is their full name as known to
+/// libtest, for instance `tests::test_parse`.
+#[derive(Clone, Debug)]
+pub struct TestNode {
+    pub name: String,
+    /// The test binary the node is in, which is `None` for the package itself.
+    pub target: Option<TestTarget>,
+    pub path: String,
+    pub is_test: bool,
+    pub is_open: bool,
+    pub test_status: TestStatus,
+    pub failure: Option<TestFailure>,
+    pub children: Vec<TestNode>,
+}
+
+impl TestNode {
+    fn new(name: &str, target: Option<TestTarget>, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            target,
+            path: path.to_string(),
+            is_test: false,
+            is_open: true,
+            test_status: TestStatus::Unknown,
+            failure: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// The status of this node, which for a module is the combined status of its tests.
+    pub fn status(&self) -> TestStatus {
+        let mut any_unknown = false;
+        let mut any_running = false;
+        let mut all_ignored = true;
+        let own = if self.is_test {Some(self.test_status)} else {None};
+        for status in own.into_iter().chain(self.children.iter().map( | child | child.status())) {
+            match status {
+                TestStatus::Failed => return TestStatus::Failed,
+                TestStatus::Running => any_running = true,
+                TestStatus::Unknown => any_unknown = true,
+                TestStatus::Passed => (),
+                TestStatus::Ignored => continue,
+            }
+            all_ignored = false;
+        }
+        if any_running {
+            TestStatus::Running
+        }
+        else if any_unknown || (own.is_none() && self.children.is_empty()) {
+            TestStatus::Unknown
+        }
+        else if all_ignored {
+            TestStatus::Ignored
+        }
+        else {
+            TestStatus::Passed
+        }
+    }
+
+    /// Whether this is a test that `run` runs.
+    fn is_run_by(&self, run: &TestRun) -> bool {
+        self.target.as_ref().is_some_and( | target | run.matches(target, &self.path))
+    }
+
+    /// Finds the node with the given path in the test binary of this node.
+    pub fn find(&self, path: &str) -> Option<&TestNode> {
+        if path.is_empty() {
+            return Some(self)
+        }
+        let mut node = self;
+        for (index, _) in path.match_indices("::").chain(Some((path.len(), ""))) {
+            node = node.children.iter().find( | child | child.path == path[..index]) ?;
+        }
+        Some(node)
+    }
+
+    fn find_mut(&mut self, path: &str) -> Option<&mut TestNode> {
+        if path.is_empty() {
+            return Some(self)
+        }
+        let mut node = self;
+        for (index, _) in path.match_indices("::").chain(Some((path.len(), ""))) {
+            node = node.children.iter_mut().find( | child | child.path == path[..index]) ?;
+        }
+        Some(node)
+    }
+
+    fn insert_test(&mut self, path: &str) -> &mut TestNode {
+        let mut node = self;
+        let mut start = 0;
+        for (index, _) in path.match_indices("::").chain(Some((path.len(), ""))) {
+            let child_index = match node.children.iter().position( | child | child.path == path[..index]) {
+                Some(child_index) => child_index,
+                None => {
+                    let child = TestNode::new(&path[start..index], node.target.clone(), &path[..index]);
+                    node.children.push(child);
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[child_index];
+            start = index + 2;
+        }
+        node.is_test = true;
+        node
+    }
+
+    fn for_each_test(&self, f: &mut impl FnMut(&TestNode)) {
+        if self.is_test {
+            f(self);
+        }
+        for child in &self.children {
+            child.for_each_test(f);
+        }
+    }
+
+    fn for_each_test_mut(&mut self, f: &mut impl FnMut(&mut TestNode)) {
+        if self.is_test {
+            f(self);
+        }
+        for child in &mut self.children {
+            child.for_each_test_mut(f);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TestPackage {
+    pub root: TestNode,
+    pub is_listed: bool,
+    pub is_running: bool,
+}
+
+impl TestPackage {
+    pub fn name(&self) -> &str {
+        &self.root.name
+    }
+
+    /// Finds the node with the given path in the given test binary, or the package itself if
+    /// there is no test binary.
+    pub fn find(&self, target: Option<&TestTarget>, path: &str) -> Option<&TestNode> {
+        let Some(target) = target else {
+            return Some(&self.root)
+        };
+        self.root.children.iter().find( | child | child.target.as_ref() == Some(target))?.find(path)
+    }
+
+    fn find_mut(&mut self, target: Option<&TestTarget>, path: &str) -> Option<&mut TestNode> {
+        let Some(target) = target else {
+            return Some(&mut self.root)
+        };
+        self.root.children.iter_mut().find( | child | child.target.as_ref() == Some(target))?.find_mut(path)
+    }
+
+    fn insert_test(&mut self, target: TestTarget, path: &str) -> &mut TestNode {
+        let children = &mut self.root.children;
+        let index = match children.iter().position( | child | child.target.as_ref() == Some(&target)) {
+            Some(index) => index,
+            None => {
+                children.push(TestNode::new(&target.label(), Some(target), ""));
+                children.len() - 1
+            }
+        };
+        children[index].insert_test(path)
+    }
+}
+
+/// A row of the test explorer, see `TestExplorer::rows`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestRow {
+    pub package_index: usize,
+    /// The test binary of the node, which is `None` for the package itself.
+    pub target: Option<TestTarget>,
+    /// The path of the node, which is empty for the package and its test binaries.
+    pub path: String,
+    pub name: String,
+    pub depth: usize,
+    pub status: TestStatus,
+    pub is_test: bool,
+    pub has_children: bool,
+    pub is_open: bool,
+}
+
+impl TestRow {
+    pub fn item_id(&self, package_name: &str) -> LiveId {
+        let target = self.target.as_ref().map( | target | target.label()).unwrap_or_default();
+        LiveId::from_str(package_name)
+            .bytes_append(target.as_bytes())
+            .bytes_append(b"/")
+            .bytes_append(self.path.as_bytes())
+    }
+}
+
+/// The tests of the packages in the project, and the results of running them.
+#[derive(Default)]
+pub struct TestExplorer {
+    pub packages: Vec<TestPackage>,
+}
+
+impl TestExplorer {
+    /// Sets the packages whose tests are shown, keeping what we know about packages that remain.
+    pub fn set_packages<'a>(&mut self, names: impl Iterator<Item = &'a str>) {
+        let mut old_packages = std::mem::take(&mut self.packages);
+        for name in names {
+            let package = match old_packages.iter().position( | package | package.name() == name) {
+                Some(index) => old_packages.remove(index),
+                None => {
+                    let mut root = TestNode::new(name, None, "");
+                    root.is_open = false;
+                    TestPackage {
+                        root,
+                        is_listed: false,
+                        is_running: false
+                    }
+                }
+            };
+            self.packages.push(package);
+        }
+    }
+
+    pub fn package_index(&self, cmd_id: LiveId) -> Option<usize> {
+        self.packages.iter().position( | package | Self::process(package.name(), TestRun::default()).as_id() == cmd_id)
+    }
+
+    /// The build process that runs, or lists, the tests of `package`. All test runs of a package
+    /// have the same id, so that starting one stops the previous one.
+    pub fn process(package: &str, run: TestRun) -> BuildProcess {
+        BuildProcess {
+            binary: package.to_string(),
+            target: BuildTarget::Test(Box::new(run))
+        }
+    }
+
+    /// The files with failures of the tests that `run` will run, without duplicates.
+    pub fn failure_file_names(&self, package_index: usize, run: &TestRun) -> Vec<String> {
+        let mut file_names = Vec::new();
+        self.packages[package_index].root.for_each_test(&mut | node | {
+            if !run.list && node.is_run_by(run) {
+                if let Some(location) = node.failure.as_ref().and_then( | failure | failure.location.as_ref()) {
+                    if !file_names.contains(&location.file_name) {
+                        file_names.push(location.file_name.clone());
+                    }
+                }
+            }
+        });
+        file_names
+    }
+
+    /// The locations of the failures of all tests that have one.
+    pub fn failure_locations(&self) -> Vec<TestLocation> {
+        let mut locations = Vec::new();
+        for package in &self.packages {
+            package.root.for_each_test(&mut | node | {
+                if let Some(location) = node.failure.as_ref().and_then( | failure | failure.location.as_ref()) {
+                    locations.push(location.clone());
+                }
+            });
+        }
+        locations
+    }
+
+    /// Resets the tests that `run` will run, and returns the process to start.
+    pub fn start_run(&mut self, package_index: usize, run: TestRun) -> BuildProcess {
+        let package = &mut self.packages[package_index];
+        package.is_running = true;
+        if !run.list {
+            package.root.for_each_test_mut(&mut | node | {
+                if node.is_run_by(&run) {
+                    node.test_status = TestStatus::Unknown;
+                    node.failure = None;
+                }
+            });
+        }
+        Self::process(package.name(), run)
+    }
+
+    /// Updates the tests with a message from a test run of the package with the given index.
+    /// Returns the failure if a test failed.
+    pub fn handle_message(&mut self, package_index: usize, message: TestMessage) -> Option<TestFailure> {
+        let package = &mut self.packages[package_index];
+        match message {
+            TestMessage::Listed(target, name) => {
+                package.is_listed = true;
+                package.insert_test(target, &name);
+            }
+            TestMessage::Event(target, event) => {
+                let name = event.name.as_deref().filter( | _ | event.is_test()) ?;
+                let node = package.insert_test(target, name);
+                node.test_status = match event.event.as_str() {
+                    "started" => TestStatus::Running,
+                    "ok" => TestStatus::Passed,
+                    "ignored" => TestStatus::Ignored,
+                    "failed" | "timeout" => TestStatus::Failed,
+                    _ => return None
+                };
+                if node.test_status == TestStatus::Failed {
+                    let output = event.stdout.as_deref().or(event.message.as_deref()).unwrap_or("");
+                    let failure = parse_test_failure(name, output);
+                    node.failure = Some(failure.clone());
+                    return Some(failure)
+                }
+            }
+            TestMessage::Finished => {
+                package.is_running = false;
+                // Tests that didn't finish, for instance because the build failed, didn't run.
+                package.root.for_each_test_mut(&mut | node | {
+                    if node.test_status == TestStatus::Running {
+                        node.test_status = TestStatus::Unknown;
+                    }
+                });
+            }
+        }
+        None
+    }
+
+    pub fn node(&self, package_index: usize, target: Option<&TestTarget>, path: &str) -> Option<&TestNode> {
+        self.packages.get(package_index)?.find(target, path)
+    }
+
+    pub fn set_open(&mut self, package_index: usize, target: Option<&TestTarget>, path: &str, is_open: bool) {
+        if let Some(node) = self.packages.get_mut(package_index).and_then( | package | package.find_mut(target, path)) {
+            node.is_open = is_open;
+        }
+    }
+
+    /// The nodes that are visible, that is whose parents are all open, in the order they are
+    /// shown.
+    pub fn rows(&self) -> Vec<TestRow> {
+        fn add_rows(rows: &mut Vec<TestRow>, package_index: usize, node: &TestNode, depth: usize) {
+            rows.push(TestRow {
+                package_index,
+                target: node.target.clone(),
+                path: node.path.clone(),
+                name: node.name.clone(),
+                depth,
+                status: node.status(),
+                is_test: node.is_test,
+                has_children: !node.children.is_empty(),
+                is_open: node.is_open,
+            });
+            if node.is_open {
+                for child in &node.children {
+                    add_rows(rows, package_index, child, depth + 1);
+                }
+            }
+        }
+        let mut rows = Vec::new();
+        for (package_index, package) in self.packages.iter().enumerate() {
+            add_rows(&mut rows, package_index, &package.root, 0);
+        }
+        rows
+    }
+}
+
+/// Extracts the message and location of the panic from the output of a failed test.
+pub fn parse_test_failure(test_name: &str, output: &str) -> TestFailure {
+    let mut failure = TestFailure {
+        test_name: test_name.to_string(),
+        message: output.trim().to_string(),
+        location: None,
+    };
+    let Some(index) = output.find("panicked at ") else {
+        return failure
+    };
+    let rest = &output[index + "panicked at ".len()..];
+    let (message, location) = if let Some(rest) = rest.strip_prefix('\'') {
+        // Before Rust 1.73: panicked at 'message', src/lib.rs:1:2
+        let Some(end) = rest.find("', ") else {
+            return failure
+        };
+        (rest[..end].to_string(), rest[end + 3..].lines().next().unwrap_or(""))
+    }
+    else {
+        // panicked at src/lib.rs:1:2:
+        // message
+        let mut lines = rest.lines();
+        let location = lines.next().unwrap_or("").trim_end_matches(':');
+        let message: Vec<&str> = lines
+            .take_while( | line | !line.starts_with("note:") && !line.starts_with("stack backtrace:"))
+            .collect();
+        (message.join("\n"), location)
+    };
+    failure.message = message.trim().to_string();
+    let mut parts = location.trim().rsplitn(3, ':');
+    if let (Some(column), Some(line), Some(file_name)) = (parts.next(), parts.next(), parts.next()) {
+        if let (Ok(column), Ok(line)) = (column.parse::<usize>(), line.parse::<usize>()) {
+            failure.location = Some(TestLocation {
+                file_name: file_name.to_string(),
+                start: Position {
+                    line_index: line.saturating_sub(1),
+                    byte_index: column.saturating_sub(1),
+                }
+            });
+        }
+    }
+    failure
+}
@@ -0,0 +1,302 @@
+use {
+    std::sync::mpsc::{self, Receiver},
+    crate::{
+        makepad_code_editor::{
+            Document,
+            decoration::{Decoration, DecorationSet, DecorationType},
+            text::{Change, Drift, Edit, Position, Text},
+        },
+        makepad_file_protocol::{Delta, FileError, Operation, TextRange},
+    },
+};
+
+/// The client side of a file that is edited together with the other participants of the collab
+/// server.
+///
+/// Local edits are sent to the server one delta at a time. While a delta is waiting to be
+/// acknowledged, further local edits are composed into a buffer, and deltas from other
+/// participants are transformed against both, so that they can be applied to the document.
+///
+/// Every delta to send is returned together with the revision it is based on, which is the
+/// revision that has to be sent along with it.
+pub struct CollabFile {
+    // The newest revision of the server we know about.
+    revision: usize,
+    // The delta we sent to the server that it hasn't acknowledged yet.
+    outstanding: Option<Delta>,
+    // The local edits that weren't sent to the server yet.
+    buffer: Option<Delta>,
+    // The text of the document as of the last local edit we received. Deltas are relative to this.
+    text: Text,
+    local_edit_receiver: Receiver<Vec<Edit >>,
+    // The selections we last told the other participants about.
+    sent_selections: Vec<TextRange>,
+    // Whether the file should be saved once the buffered edits are sent.
+    is_save_pending: bool,
+}
+
+impl CollabFile {
+    pub fn new(document: &Document, revision: usize) -> Self {
+        let (local_edit_sender, local_edit_receiver) = mpsc::channel();
+        document.set_local_edit_sender(local_edit_sender);
+        Self {
+            revision,
+            outstanding: None,
+            buffer: None,
+            text: document.as_text().clone(),
+            local_edit_receiver,
+            sent_selections: Vec::new(),
+            is_save_pending: false,
+        }
+    }
+
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Collects the edits made to the document since the last call. Returns the delta to send to
+    /// the server and the revision it is based on, if any.
+    pub fn take_local_edits(&mut self) -> Option<(usize, Delta)> {
+        self.receive_local_edits();
+        self.send_buffer()
+    }
+
+    /// Handles the server acknowledging our outstanding delta as the given revision. Returns the
+    /// next delta to send and the revision it is based on, if any.
+    pub fn handle_ack(&mut self, revision: usize) -> Option<(usize, Delta)> {
+        // We may have acknowledged it already, see `handle_remote_delta`.
+        if !self.acknowledge(revision) {
+            return None
+        }
+        self.receive_local_edits();
+        self.send_buffer()
+    }
+
+    /// Handles a delta from another participant that turned revision `revision - 1` into
+    /// `revision`, and applies it to the document. Returns the next delta to send and the
+    /// revision it is based on, if any, or an error if the delta doesn't fit the text we have, in
+    /// which case the file has to be opened again.
+    pub fn handle_remote_delta(&mut self, document: &Document, revision: usize, delta: Delta) -> Result<Option<(usize, Delta)>, FileError> {
+        // Edits that were made since we last looked are concurrent with the remote delta, so they
+        // have to be in the buffer before it is transformed.
+        self.receive_local_edits();
+        // Notifications for other participants can overtake the response to our own delta, so if
+        // there is a revision missing, it can only be ours.
+        if revision > self.revision + 1 && self.outstanding.is_some() {
+            self.acknowledge(revision - 1);
+        }
+        self.revision = revision;
+        // The server applied the remote delta before ours, so it transforms ours against it, and
+        // where both insert at the same position, the remote text comes first. We have to do the
+        // same to end up with the same text.
+        let mut delta = delta;
+        if let Some(outstanding) = &self.outstanding {
+            let (new_delta, new_outstanding) = delta.transform(outstanding).ok_or_else( || out_of_sync(revision)) ?;
+            self.outstanding = Some(new_outstanding);
+            delta = new_delta;
+        }
+        if let Some(buffer) = &self.buffer {
+            let (new_delta, new_buffer) = delta.transform(buffer).ok_or_else( || out_of_sync(revision)) ?;
+            self.buffer = Some(new_buffer);
+            delta = new_delta;
+        }
+        if delta.base_len() != position_to_offset(&self.text, Position::zero() + self.text.length()) {
+            return Err(out_of_sync(revision))
+        }
+        let edits = delta_to_edits(&mut self.text, &delta);
+        document.apply_remote_edits(edits);
+        Ok(self.send_buffer())
+    }
+
+    /// Replaces the selections of the given participant, which are relative to `revision` of the
+    /// server, with `selections`. Returns the next delta to send and the revision it is based on,
+    /// if any.
+    pub fn set_remote_selections(&mut self, document: &Document, participant: usize, revision: usize, selections: &[TextRange]) -> Option<(usize, Delta)> {
+        self.receive_local_edits();
+        if revision > self.revision && self.outstanding.is_some() {
+            self.acknowledge(revision);
+        }
+        let mut remote_selections = remote_selections_without(document, participant);
+        for selection in selections {
+            let (mut start, mut end) = (selection.start, selection.end);
+            for delta in self.outstanding.iter().chain(self.buffer.iter()) {
+                start = delta.transform_index(start);
+                end = delta.transform_index(end);
+            }
+            let (start, end) = widen_empty_range(&self.text, offset_to_position(&self.text, start), offset_to_position(&self.text, end));
+            remote_selections.add_decoration(Decoration::new(participant, start, end, DecorationType::RemoteSelection));
+        }
+        document.replace_remote_selections(remote_selections);
+        self.send_buffer()
+    }
+
+    pub fn remove_remote_selections(&self, document: &Document, participant: usize) {
+        document.replace_remote_selections(remote_selections_without(document, participant));
+    }
+
+    /// Converts a selection in the document to a range of the server text, as of `revision()`.
+    pub fn to_text_range(&self, start: Position, end: Position) -> Option<TextRange> {
+        // Positions are only meaningful for the server once our own edits are acknowledged.
+        if self.outstanding.is_some() || self.buffer.is_some() {
+            return None
+        }
+        Some(TextRange {
+            start: position_to_offset(&self.text, start),
+            end: position_to_offset(&self.text, end),
+        })
+    }
+
+    /// Remembers the selections that are sent to the server. Returns `false` if they didn't change
+    /// since the last time, so there is no need to send them.
+    pub fn set_sent_selections(&mut self, selections: &[TextRange]) -> bool {
+        if self.sent_selections == selections {
+            return false
+        }
+        self.sent_selections = selections.to_vec();
+        true
+    }
+
+    /// Asks for the file to be saved once all local edits are sent. Requests are handled in
+    /// order, so this is as soon as nothing is left in the buffer, even if the server hasn't
+    /// acknowledged the outstanding delta yet.
+    pub fn request_save(&mut self) {
+        self.is_save_pending = true;
+    }
+
+    /// Returns `true` if a save was requested that didn't happen yet.
+    pub fn is_save_pending(&self) -> bool {
+        self.is_save_pending
+    }
+
+    /// Returns `true` if the file should be saved now, see `request_save`.
+    pub fn take_save(&mut self) -> bool {
+        if !self.is_save_pending || self.buffer.is_some() {
+            return false
+        }
+        self.is_save_pending = false;
+        true
+    }
+
+    fn receive_local_edits(&mut self) {
+        while let Ok(edits) = self.local_edit_receiver.try_recv() {
+            for edit in edits {
+                let delta = edit_to_delta(&self.text, &edit.change);
+                self.text.apply_change(edit.change);
+                self.buffer = Some(match self.buffer.take() {
+                    Some(buffer) => buffer.compose(&delta).unwrap(),
+                    None => delta,
+                });
+            }
+        }
+    }
+
+    // Returns `false` if the revision was acknowledged already.
+    fn acknowledge(&mut self, revision: usize) -> bool {
+        if revision <= self.revision {
+            return false
+        }
+        self.revision = revision;
+        self.outstanding = None;
+        true
+    }
+
+    fn send_buffer(&mut self) -> Option<(usize, Delta)> {
+        if self.outstanding.is_some() {
+            return None
+        }
+        self.outstanding = self.buffer.take();
+        Some((self.revision, self.outstanding.clone() ?))
+    }
+}
+
+fn out_of_sync(revision: usize) -> FileError {
+    FileError::OutOfSync(format!("delta for revision {} does not apply to the local text", revision))
+}
+
+fn remote_selections_without(document: &Document, participant: usize) -> DecorationSet {
+    let mut remote_selections = DecorationSet::new();
+    for decoration in document.remote_selections().iter() {
+        if decoration.id != participant {
+            remote_selections.add_decoration(*decoration);
+        }
+    }
+    remote_selections
+}
+
+// Empty decorations aren't drawn, so a remote cursor is shown as the character after it.
+fn widen_empty_range(text: &Text, start: Position, end: Position) -> (Position, Position) {
+    if start != end {
+        return (start, end)
+    }
+    let line = &text.as_lines()[start.line_index];
+    if let Some(char) = line[start.byte_index..].chars().next() {
+        return (start, Position {byte_index: start.byte_index + char.len_utf8(), ..start})
+    }
+    if let Some(char) = line[..start.byte_index].chars().next_back() {
+        return (Position {byte_index: start.byte_index - char.len_utf8(), ..start}, start)
+    }
+    (start, end)
+}
+
+fn position_to_offset(text: &Text, position: Position) -> usize {
+    text.as_lines().range(..position.line_index).map( | line | line.len() + 1).sum::<usize>() + position.byte_index
+}
+
+fn offset_to_position(text: &Text, offset: usize) -> Position {
+    let mut offset = offset;
+    for (line_index, line) in text.as_lines().iter().enumerate() {
+        if offset <= line.len() {
+            return Position {line_index, byte_index: offset}
+        }
+        offset -= line.len() + 1;
+    }
+    Position::zero() + text.length()
+}
+
+fn edit_to_delta(text: &Text, change: &Change) -> Delta {
+    let text_len = position_to_offset(text, Position::zero() + text.length());
+    let mut delta = Delta::new();
+    match change {
+        Change::Insert(position, inserted_text) => {
+            let offset = position_to_offset(text, *position);
+            delta.retain(offset);
+            delta.insert(&inserted_text.to_string());
+            delta.retain(text_len - offset);
+        }
+        Change::Delete(start, length) => {
+            let start_offset = position_to_offset(text, *start);
+            let end_offset = position_to_offset(text, *start + *length);
+            delta.retain(start_offset);
+            delta.delete(end_offset - start_offset);
+            delta.retain(text_len - end_offset);
+        }
+    }
+    delta
+}
+
+// Applies `delta` to `text`, and returns the equivalent edits.
+fn delta_to_edits(text: &mut Text, delta: &Delta) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    for operation in delta.operations() {
+        let change = match operation {
+            Operation::Retain(len) => {
+                offset += len;
+                continue
+            }
+            Operation::Insert(string) => {
+                let change = Change::Insert(offset_to_position(text, offset), Text::from(string.as_str()));
+                offset += string.len();
+                change
+            }
+            Operation::Delete(len) => {
+                let start = offset_to_position(text, offset);
+                let end = offset_to_position(text, offset + len);
+                Change::Delete(start, end - start)
+            }
+        };
+        text.apply_change(change.clone());
+        edits.push(Edit {change, drift: Drift::Before});
+    }
+    edits
+}
@@ -8,7 +8,7 @@ use {
     std::{
         //env,
         io::{Read, Write},
-        net::{ TcpStream},
+        net::{TcpListener, TcpStream},
         sync::mpsc::{self, Receiver, Sender, TryRecvError},
         thread,
         path::Path,
@@ -32,7 +32,19 @@ pub struct FileClientInner {
 impl FileClient {
     pub fn init(&mut self, _cx:&mut Cx, path:&Path){
         if self.inner.is_none() {
-            self.inner = Some(FileClientInner::new_with_local_server(path))
+            // --collab-connect=addr edits the files of another studio instead of our own, and
+            // --collab-listen=addr lets other studios connect to ours.
+            let mut listen = None;
+            for arg in std::env::args(){
+                if let Some(addr) = arg.strip_prefix("--collab-connect="){
+                    self.inner = Some(FileClientInner::new_connect_remote(addr));
+                    return
+                }
+                if let Some(addr) = arg.strip_prefix("--collab-listen="){
+                    listen = Some(addr.to_string());
+                }
+            }
+            self.inner = Some(FileClientInner::new_with_local_server(path, listen.as_deref()))
         }
     }
     
@@ -60,7 +72,7 @@ impl FileClient {
 }
 
 impl FileClientInner {
-    pub fn new_with_local_server(path:&Path) -> Self {
+    pub fn new_with_local_server(path:&Path, listen: Option<&str>) -> Self {
         let (request_sender, request_receiver) = mpsc::channel();
         let message_signal = Signal::new();
         let (message_sender, message_receiver) = mpsc::channel();
@@ -90,7 +102,12 @@ impl FileClientInner {
             message_signal.clone(),
             message_sender,
         );
-        //spawn_connection_listener(TcpListener::bind("127.0.0.1:0").unwrap(), server);
+        if let Some(listen) = listen {
+            match TcpListener::bind(listen) {
+                Ok(listener) => spawn_connection_listener(listener, server),
+                Err(err) => log!("Cannot listen for collaborators on {}: {}", listen, err)
+            }
+        }
         
         Self {
             request_sender,
@@ -116,72 +133,83 @@ impl FileClientInner {
     }
     
 }
-/*
-fn _spawn_connection_listener(listener: TcpListener, mut server: FileServer) {
+fn spawn_connection_listener(listener: TcpListener, mut server: FileServer) {
     thread::spawn(move || {
         log!("Server listening on {}", listener.local_addr().unwrap());
         for stream in listener.incoming() {
-            let stream = stream.unwrap();
+            let Ok(stream) = stream else {
+                continue
+            };
             log!("Incoming connection from {}", stream.peer_addr().unwrap());
-            let (action_sender, action_receiver) = mpsc::channel();
-            let _connection = server.connect(Box::new({
-                let action_sender = action_sender.clone();
+            let (message_sender, message_receiver) = mpsc::channel();
+            let connection = server.connect(Box::new({
+                let message_sender = message_sender.clone();
                 move | notification | {
-                    action_sender.send(FileClientAction::Notification(notification)).unwrap();
+                    let _ = message_sender.send(FileClientMessage::Notification(notification));
                 }
             }));
             spawn_remote_request_handler(
                 connection,
                 stream.try_clone().unwrap(),
-                action_sender,
+                message_sender,
             );
-            spawn_response_or_notification_sender(action_receiver, stream);
+            spawn_response_or_notification_sender(message_receiver, stream);
         }
     });
-}*/
+}
 
-fn _spawn_remote_request_handler(
+fn spawn_remote_request_handler(
     connection: FileServerConnection,
     mut stream: TcpStream,
     message_sender: Sender<FileClientMessage>,
 ) {
+    // The connection is dropped when the remote studio disconnects, which removes it from all the
+    // files it had open.
     thread::spawn(move || loop {
-        let mut len_bytes = [0; 4];
-        stream.read_exact(&mut len_bytes).unwrap();
-        let len = u32::from_be_bytes(len_bytes);
-        let mut request_bytes = vec![0; len as usize];
-        stream.read_exact(&mut request_bytes).unwrap();
-        
-        let request = DeBin::deserialize_bin(request_bytes.as_slice()).unwrap();
+        let Some(request_bytes) = read_message(&mut stream) else {
+            break
+        };
+        let Ok(request) = DeBin::deserialize_bin(request_bytes.as_slice()) else {
+            break
+        };
         let response = connection.handle_request(request);
-        message_sender.send(FileClientMessage::Response(response)).unwrap();
+        if message_sender.send(FileClientMessage::Response(response)).is_err() {
+            break
+        }
     });
 }
 
-fn _spawn_response_or_notification_sender(
+fn spawn_response_or_notification_sender(
     message_receiver: Receiver<FileClientMessage>,
     mut stream: TcpStream,
 ) {
-    thread::spawn(move || loop {
-        let message = message_receiver.recv().unwrap();
-        let mut message_bytes = Vec::new();
-        
-        message.ser_bin(&mut message_bytes);
-        
-        let len_bytes = message_bytes.len().to_be_bytes();
-        stream.write_all(&len_bytes).unwrap();
-        stream.write_all(&message_bytes).unwrap();
+    thread::spawn(move || while let Ok(message) = message_receiver.recv() {
+        if !write_message(&mut stream, &message.serialize_bin()) {
+            break
+        }
     });
 }
 
+// Messages are sent as a 4 byte big endian length, followed by the serialized message.
+fn write_message(stream: &mut TcpStream, bytes: &[u8]) -> bool {
+    let len_bytes = (bytes.len() as u32).to_be_bytes();
+    stream.write_all(&len_bytes).is_ok() && stream.write_all(bytes).is_ok()
+}
+
+fn read_message(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut len_bytes = [0; 4];
+    stream.read_exact(&mut len_bytes).ok() ?;
+    let len = u32::from_be_bytes(len_bytes);
+    let mut bytes = vec![0; len as usize];
+    stream.read_exact(&mut bytes).ok() ?;
+    Some(bytes)
+}
+
 fn spawn_request_sender(request_receiver: Receiver<FileRequest>, mut stream: TcpStream) {
-    thread::spawn(move || loop {
-        let request = request_receiver.recv().unwrap();
-        let mut request_bytes = Vec::new();
-        request.ser_bin(&mut request_bytes);
-        let len_bytes = request_bytes.len().to_be_bytes();
-        stream.write_all(&len_bytes).unwrap();
-        stream.write_all(&request_bytes).unwrap();
+    thread::spawn(move || while let Ok(request) = request_receiver.recv() {
+        if !write_message(&mut stream, &request.serialize_bin()) {
+            break
+        }
     });
 }
 
@@ -191,14 +219,12 @@ fn spawn_response_or_notification_receiver(
     message_sender: Sender<FileClientMessage>,
 ) {
     thread::spawn(move || loop {
-        let mut len_bytes = [0; 4];
-        stream.read_exact(&mut len_bytes).unwrap();
-        
-        let len = u32::from_be_bytes(len_bytes);
-        let mut action_bytes = vec![0; len as usize];
-        stream.read_exact(&mut action_bytes).unwrap();
-        let action = DeBin::deserialize_bin(action_bytes.as_slice()).unwrap();
-        message_sender.send(action).unwrap();
+        let Some(message_bytes) = read_message(&mut stream) else {
+            log!("Lost connection to the file server");
+            break
+        };
+        let message = DeBin::deserialize_bin(message_bytes.as_slice()).unwrap();
+        message_sender.send(message).unwrap();
         message_signal.set()
     });
 }
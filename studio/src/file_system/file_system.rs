@@ -1,19 +1,35 @@
 use {
-    std::collections::{HashMap, hash_map},
-    std::path::Path,
+    std::collections::{BTreeMap, HashMap, HashSet, hash_map},
+    std::path::{Path, PathBuf},
+    std::fs,
     crate::{
-        makepad_code_editor::{Document, decoration::{Decoration, DecorationSet}, Session},
+        makepad_code_editor::{
+            Document,
+            History,
+            completion::Hover,
+            decoration::{Decoration, DecorationSet, DecorationSource},
+            lsp::{self, LspClient, LspEvent, LspRequestId},
+            text::{Position, Text},
+            Session
+        },
+        app::AppAction,
+        log_list::JumpTo,
         makepad_platform::makepad_live_compiler::LiveFileChange,
         makepad_widgets::*,
         makepad_widgets::file_tree::*,
-        file_system::FileClient,
+        makepad_micro_serde::*,
+        file_system::{FileClient, collab::CollabFile},
         makepad_file_protocol::{
+            Delta,
             FileRequest,
             FileError,
             FileResponse,
             FileClientMessage,
+            FileNotification,
             FileNodeData,
             FileTreeData,
+            SearchQuery,
+            SearchResult,
         },
     },
 };
@@ -26,11 +42,31 @@ pub struct FileSystem {
     pub path_to_file_node_id: HashMap<String, FileNodeId>,
     pub tab_id_to_file_node_id: HashMap<LiveId, FileNodeId>,
     pub tab_id_to_session: HashMap<LiveId, Session>,
-    pub open_documents: HashMap<FileNodeId, OpenDoc>
+    pub open_documents: HashMap<FileNodeId, OpenDoc>,
+    pub collab_files: HashMap<FileNodeId, CollabFile>,
+    pub workspace_path: PathBuf,
+    pub lsp_client: Option<LspClient>,
+    pub lsp_versions: HashMap<FileNodeId, u64>,
+    lsp_requests: HashMap<LspRequestId, LspRequest>,
+    // The files whose changes have not been sent to the language server yet.
+    lsp_changed_files: HashSet<FileNodeId>,
+    lsp_change_timer: Timer,
+    pub search_results: Vec<SearchResult>,
+}
+
+// How long the language server is left alone after an edit, before it is sent the new text.
+const LSP_CHANGE_DELAY: f64 = 0.3;
+
+// What a request to the language server was for, to handle its response.
+enum LspRequest {
+    Hover {tab_id: LiveId, position: Position},
+    Completion {tab_id: LiveId, position: Position},
+    InlayHints {file_id: FileNodeId, version: u64},
 }
 
 pub enum OpenDoc {
-    Decorations(DecorationSet),
+    // The decorations of a file that isn't open yet, by source.
+    Decorations(BTreeMap<DecorationSource, DecorationSet>),
     Document(Document)
 }
 
@@ -59,22 +95,278 @@ pub enum FileSystemAction {
     TreeLoaded,
     RecompileNeeded,
     LiveReloadNeeded(LiveFileChange),
+    SearchResults,
     None
 }
 
 impl FileSystem {
     pub fn init(&mut self, cx: &mut Cx, path:&Path) {
+        self.workspace_path = path.to_path_buf();
         self.file_client.init(cx, path);
         self.reload_file_tree();
     }
     
+    pub fn start_language_server(&mut self, command: &str, args: &[String]) {
+        match LspClient::spawn(command, args, &self.workspace_path) {
+            Ok(mut lsp_client) => {
+                lsp_client.initialize(&self.workspace_path);
+                self.lsp_client = Some(lsp_client);
+            }
+            Err(err) => {
+                log!("Cannot start language server {}: {}", command, err);
+            }
+        }
+    }
+    
+    fn file_node_uri(&self, file_id: FileNodeId) -> String {
+        lsp::path_to_uri(&self.workspace_path.join(self.file_node_path(file_id)))
+    }
+    
+    fn uri_to_file_node_id(&self, uri: &str) -> Option<FileNodeId> {
+        let path = lsp::uri_to_path(uri)?;
+        let path = path.strip_prefix(&self.workspace_path).ok()?;
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.path_to_file_node_id(&path)
+    }
+    
+    pub fn request_definition(&mut self, tab_id: LiveId, position: Position) {
+        let Some(file_id) = self.tab_id_to_file_node_id.get(&tab_id).cloned() else {
+            return
+        };
+        self.send_lsp_change(file_id);
+        let uri = self.file_node_uri(file_id);
+        if let (Some(lsp_client), Some(OpenDoc::Document(doc))) = (&mut self.lsp_client, self.open_documents.get(&file_id)) {
+            lsp_client.definition(&uri, position, &doc.as_text());
+        }
+    }
+    
+    pub fn request_hover(&mut self, tab_id: LiveId, position: Position) {
+        let Some(file_id) = self.tab_id_to_file_node_id.get(&tab_id).cloned() else {
+            return
+        };
+        self.send_lsp_change(file_id);
+        let uri = self.file_node_uri(file_id);
+        if let (Some(lsp_client), Some(OpenDoc::Document(doc))) = (&mut self.lsp_client, self.open_documents.get(&file_id)) {
+            let request_id = lsp_client.hover(&uri, position, &doc.as_text());
+            self.lsp_requests.insert(request_id, LspRequest::Hover {tab_id, position});
+        }
+    }
+    
+    pub fn request_completion(&mut self, tab_id: LiveId, position: Position) {
+        let Some(file_id) = self.tab_id_to_file_node_id.get(&tab_id).cloned() else {
+            return
+        };
+        self.send_lsp_change(file_id);
+        let uri = self.file_node_uri(file_id);
+        if let (Some(lsp_client), Some(OpenDoc::Document(doc))) = (&mut self.lsp_client, self.open_documents.get(&file_id)) {
+            let request_id = lsp_client.completion(&uri, position, &doc.as_text());
+            self.lsp_requests.insert(request_id, LspRequest::Completion {tab_id, position});
+        }
+    }
+    
+    /// Tells the language server about the edits in a tab once the user pauses typing, so it is
+    /// not sent the whole text on every keystroke.
+    pub fn schedule_lsp_change(&mut self, cx: &mut Cx, tab_id: LiveId) {
+        if self.lsp_client.is_none() {
+            return
+        }
+        if let Some(file_id) = self.tab_id_to_file_node_id.get(&tab_id) {
+            self.lsp_changed_files.insert(*file_id);
+            cx.stop_timer(self.lsp_change_timer);
+            self.lsp_change_timer = cx.start_timeout(LSP_CHANGE_DELAY);
+        }
+    }
+    
+    fn send_lsp_changes(&mut self) {
+        let file_ids: Vec<FileNodeId> = self.lsp_changed_files.iter().cloned().collect();
+        for file_id in file_ids {
+            self.send_lsp_change(file_id);
+        }
+    }
+    
+    // Sends the text of a file to the language server if it has changed since it was last sent,
+    // and asks for the inlay hints for the new text.
+    fn send_lsp_change(&mut self, file_id: FileNodeId) {
+        if !self.lsp_changed_files.remove(&file_id) {
+            return
+        }
+        let uri = self.file_node_uri(file_id);
+        if let (Some(lsp_client), Some(OpenDoc::Document(doc))) = (&mut self.lsp_client, self.open_documents.get(&file_id)) {
+            let version = self.lsp_versions.entry(file_id).or_insert(0);
+            *version += 1;
+            let text = doc.as_text();
+            lsp_client.did_change(&uri, *version, &text);
+            let request_id = lsp_client.inlay_hints(&uri, &text);
+            self.lsp_requests.insert(request_id, LspRequest::InlayHints {file_id, version: *version});
+        }
+    }
+    
+    fn send_lsp_save(&mut self, file_id: FileNodeId) {
+        if !self.lsp_versions.contains_key(&file_id) {
+            return
+        }
+        self.send_lsp_change(file_id);
+        let uri = self.file_node_uri(file_id);
+        if let Some(lsp_client) = &mut self.lsp_client {
+            lsp_client.did_save(&uri);
+        }
+    }
+    
+    fn handle_lsp_events(&mut self, cx: &mut Cx) {
+        let Some(lsp_client) = &mut self.lsp_client else {
+            return
+        };
+        if !lsp_client.signal().check_and_clear() {
+            return
+        }
+        let events = lsp_client.poll_events();
+        for event in events {
+            match event {
+                LspEvent::Diagnostics {uri, diagnostics} => {
+                    if let Some(file_id) = self.uri_to_file_node_id(&uri) {
+                        if let Some(OpenDoc::Document(doc)) = self.open_documents.get_mut(&file_id) {
+                            let decorations = lsp::diagnostics_to_decorations(&diagnostics, &doc.as_text());
+                            doc.replace_decorations(DecorationSource::LanguageServer, decorations);
+                            cx.action(AppAction::RedrawFile(file_id))
+                        }
+                    }
+                }
+                LspEvent::Definition {locations, ..} => {
+                    if let Some(location) = locations.first() {
+                        if let Some(path) = lsp::uri_to_path(&location.uri) {
+                            if let Ok(rel_path) = path.strip_prefix(&self.workspace_path) {
+                                let file_name = rel_path.to_string_lossy().replace('\\', "/");
+                                // the utf16 column needs the line it is on, which we read from disk if the target is not open
+                                let start = match self.path_to_file_node_id(&file_name).and_then(|file_id| self.open_documents.get(&file_id)) {
+                                    Some(OpenDoc::Document(doc)) => location.range.start.to_position(doc.as_text().as_lines()),
+                                    _ => match fs::read_to_string(&path) {
+                                        Ok(data) => location.range.start.to_position(Text::from(data).as_lines()),
+                                        Err(_) => Position {
+                                            line_index: location.range.start.line,
+                                            byte_index: 0
+                                        }
+                                    }
+                                };
+                                cx.action(AppAction::JumpTo(JumpTo {file_name, start}));
+                            }
+                        }
+                    }
+                }
+                LspEvent::Hover {request_id, contents, ..} => {
+                    let Some(LspRequest::Hover {tab_id, position}) = self.lsp_requests.remove(&request_id) else {
+                        continue
+                    };
+                    if let Some(text) = contents.filter( | text | !text.trim().is_empty()) {
+                        if let Some(session) = self.tab_id_to_session.get(&tab_id) {
+                            session.set_hover(Hover {position, text: text.trim().to_string()});
+                            cx.action(AppAction::RedrawFile(self.tab_id_to_file_node_id[&tab_id]))
+                        }
+                    }
+                }
+                LspEvent::Completion {request_id, items} => {
+                    let Some(LspRequest::Completion {tab_id, position}) = self.lsp_requests.remove(&request_id) else {
+                        continue
+                    };
+                    if let Some(session) = self.tab_id_to_session.get(&tab_id) {
+                        let start = lsp::completion_start(&items, session.document().as_text().as_lines(), position);
+                        session.show_completions(start, items.iter().map( | item | item.to_completion()).collect());
+                        cx.action(AppAction::RedrawFile(self.tab_id_to_file_node_id[&tab_id]))
+                    }
+                }
+                LspEvent::InlayHints {request_id, hints} => {
+                    let Some(LspRequest::InlayHints {file_id, version}) = self.lsp_requests.remove(&request_id) else {
+                        continue
+                    };
+                    // hints for text that has since changed would end up in the wrong place
+                    if self.lsp_versions.get(&file_id) != Some(&version) || self.lsp_changed_files.contains(&file_id) {
+                        continue
+                    }
+                    if let Some(OpenDoc::Document(doc)) = self.open_documents.get(&file_id) {
+                        let inlays = hints.iter().map( | hint | hint.to_inlay(doc.as_text().as_lines())).collect();
+                        doc.replace_inline_inlays(inlays);
+                        cx.action(AppAction::RedrawFile(file_id))
+                    }
+                }
+                LspEvent::Error {request_id: Some(request_id), message} => {
+                    self.lsp_requests.remove(&request_id);
+                    log!("Language server error: {}", message);
+                }
+                LspEvent::Message {message, ..} => {
+                    log!("Language server: {}", message);
+                }
+                LspEvent::Error {message, ..} => {
+                    log!("Language server error: {}", message);
+                }
+                _ => ()
+            }
+        }
+    }
+    
     pub fn reload_file_tree(&mut self) {
         self.file_client.send_request(FileRequest::LoadFileTree {with_data: false});
     }
     
     pub fn remove_tab(&mut self, tab_id: LiveId) {
-        self.tab_id_to_file_node_id.remove(&tab_id);
+        let file_id = self.tab_id_to_file_node_id.remove(&tab_id);
         self.tab_id_to_session.remove(&tab_id);
+        // The file is closed once the last tab showing it is.
+        if let Some(file_id) = file_id {
+            if self.file_node_id_to_tab_id(file_id).is_none() {
+                self.close_lsp_file(file_id);
+                // Edits that could still be sent make it to the other participants, the rest is
+                // dropped like an unsaved change.
+                self.request_apply_local_edits(file_id);
+                if self.collab_files.get(&file_id).is_some_and( | collab | collab.is_save_pending()) {
+                    let path = self.file_node_path(file_id);
+                    self.file_client.send_request(FileRequest::SaveFile(path));
+                }
+                self.close_collab_file(file_id);
+            }
+        }
+    }
+    
+    // Leaves the collab session of a file, so the server doesn't keep its deltas around for us.
+    // The document is dropped, opening the file again gets its text from the server.
+    fn close_collab_file(&mut self, file_id: FileNodeId) {
+        if self.collab_files.remove(&file_id).is_none() {
+            return
+        }
+        if let Some(OpenDoc::Document(doc)) = self.open_documents.remove(&file_id) {
+            self.open_documents.insert(file_id, OpenDoc::Decorations(doc.decorations_by_source()));
+        }
+        let path = self.file_node_path(file_id);
+        self.file_client.send_request(FileRequest::CloseFile(path));
+    }
+    
+    // Gets the text of a file from the collab server again, after our copy got out of sync.
+    fn reopen_collab_file(&mut self, file_id: FileNodeId) {
+        self.close_lsp_file(file_id);
+        self.close_collab_file(file_id);
+        self.tab_id_to_session.retain( | tab_id, _ | self.tab_id_to_file_node_id.get(tab_id) != Some(&file_id));
+        let path = self.file_node_path(file_id);
+        self.file_client.send_request(FileRequest::OpenFile(path, file_id.0.0));
+    }
+    
+    fn open_lsp_file(&mut self, file_id: FileNodeId) {
+        if self.lsp_client.is_none() || self.lsp_versions.contains_key(&file_id) {
+            return
+        }
+        let uri = self.file_node_uri(file_id);
+        if let (Some(lsp_client), Some(OpenDoc::Document(doc))) = (&mut self.lsp_client, self.open_documents.get(&file_id)) {
+            self.lsp_versions.insert(file_id, 0);
+            lsp_client.did_open(&uri, language_id(&uri), 0, &doc.as_text());
+        }
+    }
+    
+    fn close_lsp_file(&mut self, file_id: FileNodeId) {
+        self.lsp_changed_files.remove(&file_id);
+        if self.lsp_versions.remove(&file_id).is_none() {
+            return
+        }
+        let uri = self.file_node_uri(file_id);
+        if let Some(lsp_client) = &mut self.lsp_client {
+            lsp_client.did_close(&uri);
+        }
     }
     
     pub fn path_to_file_node_id(&self, path: &str) -> Option<FileNodeId> {
@@ -105,7 +397,12 @@ impl FileSystem {
     
     pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, ui: &WidgetRef) {
         
+        if self.lsp_change_timer.is_event(event).is_some() {
+            self.send_lsp_changes();
+        }
+        
         if let Event::Signal = event{
+            self.handle_lsp_events(cx);
             while let Ok(message) = self.file_client.inner.as_mut().unwrap().message_receiver.try_recv() {
                 match message {
                     FileClientMessage::Response(response) => match response {
@@ -116,7 +413,7 @@ impl FileSystem {
                         }
                         FileResponse::OpenFile(result) => {
                             match result {
-                                Ok((_unix_path, data, id)) => {
+                                Ok((unix_path, data, id, revision)) => {
                                     let file_id = FileNodeId(LiveId(id));
                                     let dock = ui.dock(id!(dock));
                                     for (tab_id, file_id) in &self.tab_id_to_file_node_id {
@@ -125,9 +422,23 @@ impl FileSystem {
                                         }
                                     }
                                     if let Some(OpenDoc::Decorations(dec)) = self.open_documents.get(&file_id) {
-                                        let dec = dec.clone();
-                                        self.open_documents.insert(file_id, OpenDoc::Document(Document::new(data.into(), dec)));
+                                        let mut decs = dec.clone();
+                                        let dec = decs.remove(&DecorationSource::Build).unwrap_or_default();
+                                        let extension = Path::new(&unix_path).extension().map_or(String::new(), |ext| ext.to_string_lossy().to_string());
+                                        let text: Text = data.into();
+                                        // Restore the undo history from the last session, unless the file was changed
+                                        // outside of the studio since then.
+                                        let mut document = match self.load_history(&unix_path) {
+                                            Some(history) if *history.as_text() == text => Document::with_history(history, dec, &extension),
+                                            _ => Document::with_extension(text, dec, &extension)
+                                        };
+                                        for (source, dec) in decs {
+                                            document.replace_decorations(source, dec);
+                                        }
+                                        self.collab_files.insert(file_id, CollabFile::new(&document, revision));
+                                        self.open_documents.insert(file_id, OpenDoc::Document(document));
                                     }else {panic!()}
+                                    self.open_lsp_file(file_id);
                                     ui.redraw(cx);
                                 }
                                 Err(FileError::CannotOpen(_unix_path)) => {
@@ -136,10 +447,30 @@ impl FileSystem {
                                     log!("File error unknown {}", err);
                                     // ignore
                                 }
+                                Err(FileError::InvalidPattern(_)) | Err(FileError::OutOfSync(_)) => {
+                                }
+                            }
+                        }
+                        FileResponse::ApplyDelta(result) => match result {
+                            Ok((path, revision)) => {
+                                if let Some(file_id) = self.path_to_file_node_id(&path) {
+                                    if let Some((revision, delta)) = self.collab_files.get_mut(&file_id).and_then( | collab | collab.handle_ack(revision)) {
+                                        self.request_apply_delta(file_id, revision, delta);
+                                    }
+                                    self.request_pending_save(file_id);
+                                    self.request_set_selections(file_id);
+                                }
+                            }
+                            Err(err) => {
+                                log!("Cannot apply edits {:?}", err);
                             }
                         }
                         FileResponse::SaveFile(result) => match result {
-                            Ok((path, old, new, _id)) => {
+                            Ok((path, old, new)) => {
+                                // the language server checks the file on disk once it is saved
+                                if let Some(file_id) = self.path_to_file_node_id(&path) {
+                                    self.send_lsp_save(file_id);
+                                }
                                 // alright file has been saved
                                 // now we need to check if a live_design!{} changed or something outside it
                                 if old != new {
@@ -170,26 +501,125 @@ impl FileSystem {
                                     }
                                 }
                             }
-                            Err(_) => {}
-                            // ok we saved a file, we should check however what changed
-                            // to see if we need a recompile
-                            
+                            Err(err) => {
+                                log!("Cannot save file {:?}", err);
+                            }
+                        }
+                        FileResponse::CloseFile(_) | FileResponse::SetSelections(_) => {}
+                        FileResponse::Search(result) => match result {
+                            Ok(results) => {
+                                self.search_results = results;
+                                cx.action(FileSystemAction::SearchResults)
+                            }
+                            Err(err) => {
+                                log!("Search failed {:?}", err);
+                            }
                         }
                     },
-                    FileClientMessage::Notification(_notification) => {
-                        //self.editors.handle_collab_notification(cx, &mut state.editor_state, notification)
+                    FileClientMessage::Notification(notification) => {
+                        self.handle_notification(cx, notification, ui);
                     }
                 }
             }
         }
     }
 
+    fn handle_notification(&mut self, cx: &mut Cx, notification: FileNotification, ui: &WidgetRef) {
+        let (path, delta_to_send) = match notification {
+            FileNotification::DeltaWasApplied(path, revision, delta) => {
+                let Some((file_id, document, collab)) = self.collab_file(&path) else {return};
+                match collab.handle_remote_delta(&document, revision, delta) {
+                    Ok(delta_to_send) => (file_id, delta_to_send),
+                    Err(err) => {
+                        log!("Reopening {}: {:?}", path, err);
+                        self.reopen_collab_file(file_id);
+                        return
+                    }
+                }
+            }
+            FileNotification::SelectionsChanged(path, participant, revision, selections) => {
+                let Some((file_id, document, collab)) = self.collab_file(&path) else {return};
+                let delta_to_send = collab.set_remote_selections(&document, participant, revision, &selections);
+                (file_id, delta_to_send)
+            }
+            FileNotification::ParticipantLeft(path, participant) => {
+                let Some((file_id, document, collab)) = self.collab_file(&path) else {return};
+                collab.remove_remote_selections(&document, participant);
+                (file_id, None)
+            }
+            FileNotification::FileChangedOnDisk => return
+        };
+        if let Some((revision, delta)) = delta_to_send {
+            self.request_apply_delta(path, revision, delta);
+        }
+        self.request_pending_save(path);
+        // The remote edits move the selections of our sessions
+        self.handle_sessions();
+        self.redraw_view_by_file_id(cx, path, &ui.dock(id!(dock)));
+    }
+    
+    fn collab_file(&mut self, path: &str) -> Option<(FileNodeId, Document, &mut CollabFile)> {
+        let file_id = self.path_to_file_node_id(path) ?;
+        let Some(OpenDoc::Document(document)) = self.open_documents.get(&file_id) else {
+            return None
+        };
+        Some((file_id, document.clone(), self.collab_files.get_mut(&file_id) ?))
+    }
+    
+    fn request_apply_delta(&mut self, file_id: FileNodeId, revision: usize, delta: Delta) {
+        let path = self.file_node_path(file_id);
+        self.file_client.send_request(FileRequest::ApplyDelta(path, revision, delta));
+    }
+    
+    /// Sends the edits made to the given file to the collab server, so that the other
+    /// participants see them as they are typed.
+    pub fn request_apply_local_edits(&mut self, file_id: FileNodeId) {
+        if let Some((revision, delta)) = self.collab_files.get_mut(&file_id).and_then( | collab | collab.take_local_edits()) {
+            self.request_apply_delta(file_id, revision, delta);
+        }
+    }
+    
+    // Saves the file once the last local edits are sent, see `CollabFile::request_save`.
+    fn request_pending_save(&mut self, file_id: FileNodeId) {
+        if self.collab_files.get_mut(&file_id).is_some_and( | collab | collab.take_save()) {
+            let path = self.file_node_path(file_id);
+            self.file_client.send_request(FileRequest::SaveFile(path));
+        }
+    }
+    
+    /// Lets the other participants know where the cursors of the given file are.
+    pub fn request_set_selections(&mut self, file_id: FileNodeId) {
+        let path = self.file_node_path(file_id);
+        let Some(collab) = self.collab_files.get_mut(&file_id) else {return};
+        let mut selections = Vec::new();
+        for (tab_id, tab_file_id) in &self.tab_id_to_file_node_id {
+            if *tab_file_id != file_id {
+                continue
+            }
+            let Some(session) = self.tab_id_to_session.get(tab_id) else {continue};
+            for selection in session.selections().iter() {
+                let Some(range) = collab.to_text_range(selection.start(), selection.end()) else {
+                    return
+                };
+                selections.push(range);
+            }
+        }
+        if collab.set_sent_selections(&selections) {
+            let revision = collab.revision();
+            self.file_client.send_request(FileRequest::SetSelections(path, revision, selections));
+        }
+    }
+    
     pub fn handle_sessions(&mut self) {
         for session in self.tab_id_to_session.values_mut() {
             session.handle_changes();
         }
     }
     
+    pub fn request_search(&mut self, query: SearchQuery) {
+        self.file_client.send_request(FileRequest::Search(query));
+    }
+    
     pub fn request_open_file(&mut self, tab_id: LiveId, file_id: FileNodeId) {
         // ok lets see if we have a document
         // ifnot, we create a new one
@@ -203,9 +633,10 @@ impl FileSystem {
                 panic!()
             },
             Some(OpenDoc::Document(_))=>{
+                self.open_lsp_file(file_id);
                 return
             }
-            None=>DecorationSet::new()
+            None=>BTreeMap::new()
         };
         self.open_documents.insert(file_id, OpenDoc::Decorations(dec));
         let path = self.file_node_path(file_id);
@@ -213,24 +644,53 @@ impl FileSystem {
     }
     
     
+    // Undo histories are stored in the target directory, so they don't show up in the file tree or
+    // in version control.
+    fn history_path(&self, path: &str) -> PathBuf {
+        self.workspace_path.join("target/makepad_history").join(format!("{}.bin", path))
+    }
+    
+    fn load_history(&self, path: &str) -> Option<History> {
+        let bytes = fs::read(self.history_path(path)).ok()?;
+        History::deserialize_bin(&bytes).ok()
+    }
+    
+    fn save_history(&self, path: &str, history: &History) {
+        let history_path = self.history_path(path);
+        if let Some(parent) = history_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(err) = fs::write(&history_path, history.serialize_bin()) {
+            log!("Cannot save undo history for {}: {}", path, err);
+        }
+    }
+    
     pub fn request_save_file(&mut self, tab_id: LiveId) {
         // ok lets see if we have a document
         // ifnot, we create a new one
         if let Some(file_id) = self.tab_id_to_file_node_id.get(&tab_id) {
             if let Some(OpenDoc::Document(doc)) = self.open_documents.get(&file_id) {
-                let text = doc.as_text().to_string();
                 let path = self.file_node_path(*file_id);
-                self.file_client.send_request(FileRequest::SaveFile(path.clone(), text, file_id.0.0));
+                self.save_history(&path, &doc.history());
+                let file_id = *file_id;
+                // The server writes the file once it has applied the edits.
+                self.request_apply_local_edits(file_id);
+                if let Some(collab) = self.collab_files.get_mut(&file_id) {
+                    collab.request_save();
+                }
+                self.request_pending_save(file_id);
             }
         };
     }
     
-    pub fn clear_decorations(&mut self, file_node_id: &FileNodeId) {
+    pub fn clear_decorations(&mut self, file_node_id: &FileNodeId, source: DecorationSource) {
         // ok lets see if we have a document
         // ifnot, we create a new one
         match self.open_documents.get_mut(file_node_id) {
-            Some(OpenDoc::Decorations(dec)) => dec.clear(),
-            Some(OpenDoc::Document(doc)) => doc.clear_decorations(),
+            Some(OpenDoc::Decorations(dec)) => {
+                dec.remove(&source);
+            }
+            Some(OpenDoc::Document(doc)) => doc.clear_decorations(source),
             None => ()
         };
     }
@@ -240,8 +700,10 @@ impl FileSystem {
         // ifnot, we create a new one
         for document in self.open_documents.values_mut() {
             match document {
-                OpenDoc::Decorations(dec) => dec.clear(),
-                OpenDoc::Document(doc) => doc.clear_decorations(),
+                OpenDoc::Decorations(dec) => {
+                    dec.remove(&DecorationSource::Build);
+                }
+                OpenDoc::Document(doc) => doc.clear_decorations(DecorationSource::Build),
             }
         }
     }
@@ -260,18 +722,18 @@ impl FileSystem {
         }
     }
     
-    pub fn add_decoration(&mut self, file_id: FileNodeId, dec: Decoration) {
+    pub fn add_decoration(&mut self, file_id: FileNodeId, source: DecorationSource, dec: Decoration) {
         // ok lets see if we have a document
         // ifnot, we create a new one
         match self.open_documents.get_mut(&file_id) {
-            Some(OpenDoc::Decorations(decs)) => decs.add_decoration(dec),
+            Some(OpenDoc::Decorations(decs)) => decs.entry(source).or_default().add_decoration(dec),
             Some(OpenDoc::Document(doc)) => {
-                doc.add_decoration(dec);
+                doc.add_decoration(source, dec);
             }
             None => {
                 let mut set = DecorationSet::new();
                 set.add_decoration(dec);
-                self.open_documents.insert(file_id, OpenDoc::Decorations(set));
+                self.open_documents.insert(file_id, OpenDoc::Decorations(BTreeMap::from([(source, set)])));
             }
         };
     }
@@ -442,4 +904,16 @@ impl FileSystem {
             tree_data.root,
         );
     }
-}
\ No newline at end of file
+}
+
+fn language_id(uri: &str) -> &'static str {
+    match uri.rsplit('.').next() {
+        Some("rs") => "rust",
+        Some("toml") => "toml",
+        Some("md") => "markdown",
+        Some("json") => "json",
+        Some("js") => "javascript",
+        Some("html") => "html",
+        _ => "plaintext"
+    }
+}
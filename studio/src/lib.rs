@@ -8,6 +8,7 @@ pub mod studio_editor;
 pub mod studio_file_tree;
 pub mod log_list;
 pub mod run_list;
+pub mod test_list;
 pub mod run_view;
 pub mod profiler;
 //pub use makepad_code_editor;
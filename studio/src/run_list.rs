@@ -165,16 +165,16 @@ impl RunList{
             counter += 1;
                             
             if binary.open>0.001 {
-                for i in 0..BuildTarget::len() {
+                for target in build_manager.build_targets(&binary.name) {
                     
                     let is_even = counter & 1 == 0;
-                    let item_id = LiveId::from_str(&binary.name).bytes_append(&i.to_be_bytes());
+                    let item_id = LiveId::from_str(&binary.name).bytes_append(&target.as_id().to_be_bytes());
                     let item = list.item(cx, item_id, live_id!(Target)).unwrap().as_view();
                     let height = 25.0 * binary.open;
                     item.apply_over(cx, live!{
                         height: (height)
                         draw_bg: {is_even: (if is_even {1.0} else {0.0})}
-                        check = {text: (BuildTarget::from_id(i).name())}
+                        check = {text: (target.name())}
                     });
                     item.check_box(id!(check)).set_selected(cx, build_manager.active.item_id_active(item_id));
                     item.draw_all(cx, &mut Scope::empty());
@@ -203,8 +203,10 @@ impl WidgetMatchEvent for RunList{
         let build_manager = &mut scope.data.get_mut::<AppData>().build_manager;
         let run_list = self.view.flat_list(id!(list));
         for (item_id, item) in run_list.items_with_actions(&actions) {
-            for binary in &mut build_manager.binaries {
-                let binary_name = binary.name.clone();
+            for binary_index in 0..build_manager.binaries.len() {
+                let binary_name = build_manager.binaries[binary_index].name.clone();
+                let targets = build_manager.build_targets(&binary_name);
+                let binary = &mut build_manager.binaries[binary_index];
                 let id = LiveId::from_str(&binary.name);
                 if item_id == id{
                     if let Some(v) = item.fold_button(id!(fold)).animating(&actions) {
@@ -213,31 +215,32 @@ impl WidgetMatchEvent for RunList{
                     }
                     if let Some(change) = item.check_box(id!(check)).changed(&actions) {
                         run_list.redraw(cx);
-                        for i in 0..if change{1}else{BuildTarget::len()} {
-                            if change{
-                                BuildManager::start_active_build(cx, build_manager.studio_http.clone(), &mut build_manager.active, &build_manager.clients[0], &binary_name, i);
-                            }
-                            else{
-                                BuildManager::stop_active_build(cx, &mut build_manager.active, &build_manager.clients[0], &binary_name, i);
+                        if change{
+                            BuildManager::start_active_build(cx, build_manager.studio_http.clone(), &mut build_manager.active, &build_manager.clients[0], &binary_name, BuildTarget::ReleaseStudio);
+                        }
+                        else{
+                            for target in targets{
+                                BuildManager::stop_active_build(cx, &mut build_manager.active, &build_manager.clients[0], &binary_name, target);
                             }
-                            cx.action(AppAction::ClearLog);
                         }
+                        cx.action(AppAction::ClearLog);
                     };
                 }
                 else{
-                    for i in 0..BuildTarget::len() {
-                        let id = LiveId::from_str(&binary.name).bytes_append(&i.to_be_bytes());
+                    for target in targets {
+                        let id = LiveId::from_str(&binary_name).bytes_append(&target.as_id().to_be_bytes());
                         if item_id == id{
                             if let Some(change) = item.check_box(id!(check)).changed(&actions) {
                                 run_list.redraw(cx);
                                 if change{
-                                    BuildManager::start_active_build(cx, build_manager.studio_http.clone(), &mut build_manager.active, &build_manager.clients[0], &binary_name, i);
+                                    BuildManager::start_active_build(cx, build_manager.studio_http.clone(), &mut build_manager.active, &build_manager.clients[0], &binary_name, target);
                                 }
                                 else{
-                                    BuildManager::stop_active_build(cx, &mut build_manager.active, &build_manager.clients[0], &binary_name, i);
+                                    BuildManager::stop_active_build(cx, &mut build_manager.active, &build_manager.clients[0], &binary_name, target);
                                 }
                                 cx.action(AppAction::ClearLog);
                             }
+                            break;
                         }
                     }
                 }
@@ -266,11 +269,10 @@ impl Widget for RunList {
 impl BuildManager {
 
     pub fn run_app(&mut self, cx:&mut Cx, binary_name:&str){
-        Self::start_active_build(cx, self.studio_http.clone(), &mut self.active, &self.clients[0], &binary_name, 0);
+        Self::start_active_build(cx, self.studio_http.clone(), &mut self.active, &self.clients[0], binary_name, BuildTarget::ReleaseStudio);
     }
     
-    pub fn start_active_build(cx:&mut Cx, studio_http:String, active:&mut ActiveBuilds, client:&BuildClient,  binary: &str, tgt: u64) {
-        let target = BuildTarget::from_id(tgt);
+    pub fn start_active_build(cx:&mut Cx, studio_http:String, active:&mut ActiveBuilds, client:&BuildClient,  binary: &str, target: BuildTarget) {
         let process = BuildProcess {
             binary: binary.to_string(),
             target
@@ -294,8 +296,7 @@ impl BuildManager {
         }
     }
     
-    pub fn stop_active_build(cx:&mut Cx, active:&mut ActiveBuilds, client:&BuildClient, binary: &str, tgt: u64) {
-        let target = BuildTarget::from_id(tgt);
+    pub fn stop_active_build(cx:&mut Cx, active:&mut ActiveBuilds, client:&BuildClient, binary: &str, target: BuildTarget) {
         let process = BuildProcess {
             binary: binary.to_string(),
             target
@@ -44,6 +44,10 @@ impl Widget for StudioEditor {
                 cx.widget_action(uid, &scope.path, action);
             }
             data.file_system.handle_sessions();
+            if let Some(file_id) = data.file_system.tab_id_to_file_node_id.get(&session_id).cloned() {
+                data.file_system.request_apply_local_edits(file_id);
+                data.file_system.request_set_selections(file_id);
+            }
         }
     }
 }
\ No newline at end of file
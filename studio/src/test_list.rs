@@ -0,0 +1,333 @@
+use {
+    crate::{
+        build_manager::{
+            build_manager::*,
+            build_protocol::*,
+            test_explorer::*,
+        },
+        app::{AppAction, AppData},
+        log_list::JumpTo,
+        makepad_widgets::*,
+    },
+};
+
+live_design!{
+    import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    StatusIcon = <View> {
+        show_bg: true,
+        width: 10,
+        height: 10
+    }
+
+    TestStatusIcon = <PageFlip> {
+        active_page: unknown
+        lazy_init: true,
+        width: Fit,
+        height: Fit,
+        margin: {top: 7, left: 5, right: 5}
+        unknown = <StatusIcon> {
+            draw_bg: {
+                fn pixel(self) -> vec4 {
+                    let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                    sdf.circle(5., 5., 3.5)
+                    sdf.stroke(THEME_COLOR_TEXT_META, 1.)
+                    return sdf.result
+                }
+            }
+        }
+        running = <StatusIcon> {
+            draw_bg: {
+                fn pixel(self) -> vec4 {
+                    let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                    sdf.circle(5., 5., 4.)
+                    sdf.fill(THEME_COLOR_TEXT_META)
+                    sdf.move_to(3., 5.)
+                    sdf.line_to(3., 5.)
+                    sdf.move_to(5., 5.)
+                    sdf.line_to(5., 5.)
+                    sdf.move_to(7., 5.)
+                    sdf.line_to(7., 5.)
+                    sdf.stroke(#0, 0.8)
+                    return sdf.result
+                }
+            }
+        }
+        passed = <StatusIcon> {
+            draw_bg: {
+                fn pixel(self) -> vec4 {
+                    let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                    sdf.circle(5., 5., 4.5)
+                    sdf.fill(#3a3)
+                    sdf.move_to(2.75, 5.)
+                    sdf.line_to(4.5, 6.75)
+                    sdf.line_to(7.25, 3.25)
+                    sdf.stroke(#0, 0.8)
+                    return sdf.result
+                }
+            }
+        }
+        failed = <StatusIcon> {
+            draw_bg: {
+                fn pixel(self) -> vec4 {
+                    let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                    sdf.circle(5., 5., 4.5);
+                    sdf.fill(THEME_COLOR_ERROR);
+                    let sz = 1.5;
+                    sdf.move_to(5. - sz, 5. - sz);
+                    sdf.line_to(5. + sz, 5. + sz);
+                    sdf.move_to(5. - sz, 5. + sz);
+                    sdf.line_to(5. + sz, 5. - sz);
+                    sdf.stroke(#0, 0.8)
+                    return sdf.result
+                }
+            }
+        }
+        ignored = <StatusIcon> {
+            draw_bg: {
+                fn pixel(self) -> vec4 {
+                    let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                    sdf.circle(5., 5., 4.)
+                    sdf.fill(THEME_COLOR_TEXT_META)
+                    sdf.move_to(2.5, 5.)
+                    sdf.line_to(7.5, 5.)
+                    sdf.stroke(#0, 0.8)
+                    return sdf.result
+                }
+            }
+        }
+    }
+
+    TestItem = <RectView> {
+        height: 25,
+        width: Fill
+        flow: Right
+        padding: {top: 0, bottom: 0}
+
+        draw_bg: {
+            instance is_even: 0.0
+            fn pixel(self) -> vec4 {
+                return mix(
+                    THEME_COLOR_BG_EDITOR,
+                    THEME_COLOR_BG_ODD,
+                    self.is_even
+                );
+            }
+        }
+    }
+
+    RunTestButton = <Button> {
+        width: 20,
+        height: 25,
+        margin: {left: 5},
+        padding: 0,
+        text: ""
+        draw_bg: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                let c = vec2(self.rect_size.x * 0.5, self.rect_size.y * 0.5);
+                sdf.move_to(c.x - 3., c.y - 4.);
+                sdf.line_to(c.x + 4., c.y);
+                sdf.line_to(c.x - 3., c.y + 4.);
+                sdf.close_path();
+                sdf.fill(mix(mix(#8, #f, self.hover), #a, self.pressed));
+                return sdf.result
+            }
+        }
+    }
+
+    TestFoldButton = <FoldButton> {
+        height: 25, width: 15 margin: {left: 5}
+        draw_bg: {
+            uniform size: 4.0;
+            instance open: 0.0
+
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                let sz = self.size;
+                sdf.box(0, sz * 3.0, sz * 2.5, sz * 0.5, 1.0);
+                sdf.fill_keep(mix(#8F, #FF, self.hover));
+                sdf.box(sz, sz * 2.0, sz * 0.5, sz * 2.5, 1.0);
+                sdf.fill_keep(mix(mix(#8F, #FF, self.hover), #FFF0, self.open))
+                return sdf.result
+            }
+        }
+    }
+
+    TestList = {{TestList}}{
+        height: Fill,
+        width: Fill
+        list = <FlatList> {
+            grab_key_focus: true
+            drag_scrolling: false
+            height: Fill,
+            width: Fill
+            flow: Down
+            Group = <TestItem> {
+                fold = <TestFoldButton> {}
+                run = <RunTestButton> {}
+                status = <TestStatusIcon> {}
+                name = <Label> {width: Fill, margin: {top: 7}, padding: 0, draw_text: {wrap: Ellipsis}}
+            }
+            Test = <TestItem> {
+                run = <RunTestButton> {margin: {left: 20}}
+                status = <TestStatusIcon> {}
+                name = <LinkLabel> {margin: {top: 2}, text: ""}
+            }
+            Empty = <TestItem> {
+                cursor: Default
+                height: 24,
+                width: Fill
+            }
+        }
+    }
+}
+
+#[derive(Live, LiveHook, Widget)]
+struct TestList{
+    #[deref] view:View
+}
+
+fn status_page(status: TestStatus) -> LiveId {
+    match status {
+        TestStatus::Unknown => live_id!(unknown),
+        TestStatus::Running => live_id!(running),
+        TestStatus::Passed => live_id!(passed),
+        TestStatus::Failed => live_id!(failed),
+        TestStatus::Ignored => live_id!(ignored),
+    }
+}
+
+impl TestList{
+    fn draw_test_list(&mut self, cx: &mut Cx2d, list:&mut FlatList, build_manager:&mut BuildManager){
+        let mut counter = 0u32;
+        for row in build_manager.tests.rows() {
+            let is_even = counter & 1 == 0;
+            let package_name = build_manager.tests.packages[row.package_index].name();
+            let item_id = row.item_id(package_name);
+            let template = if row.is_test && !row.has_children {live_id!(Test)} else {live_id!(Group)};
+            let item = list.item(cx, item_id, template).unwrap().as_view();
+            let status = if row.target.is_none() && build_manager.tests.packages[row.package_index].is_running {
+                TestStatus::Running
+            }
+            else {
+                row.status
+            };
+            item.apply_over(cx, live!{
+                padding: {left: (row.depth as f64 * 15.0)}
+                name = {text: (&row.name)}
+                status = {active_page: (status_page(status))}
+                draw_bg: {is_even: (if is_even {1.0} else {0.0})}
+            });
+            if template == live_id!(Group) {
+                item.apply_over(cx, live!{
+                    fold = {draw_bg: {open: (if row.is_open {1.0} else {0.0})}}
+                });
+            }
+            item.draw_all(cx, &mut Scope::empty());
+            counter += 1;
+        }
+        while list.space_left(cx)>0.0 {
+            let is_even = counter & 1 == 0;
+            let item_id = LiveId::from_str("empty").bytes_append(&counter.to_be_bytes());
+            let item = list.item(cx, item_id, live_id!(Empty)).unwrap().as_view();
+            let height = list.space_left(cx).min(20.0);
+            item.apply_over(cx, live!{
+                height: (height)
+                draw_bg: {is_even: (if is_even {1.0} else {0.0})}
+            });
+            item.draw_all(cx, &mut Scope::empty());
+            counter += 1;
+        }
+    }
+}
+
+impl WidgetMatchEvent for TestList{
+    fn handle_actions(&mut self, cx: &mut Cx, actions: &Actions, scope: &mut Scope){
+        let AppData {build_manager, file_system} = scope.data.get_mut::<AppData>();
+        let test_list = self.view.flat_list(id!(list));
+        let items = test_list.items_with_actions(actions);
+        if items.is_empty() {
+            return
+        }
+        let rows = build_manager.tests.rows();
+        for (item_id, item) in items {
+            let Some(row) = rows.iter().find( | row | {
+                row.item_id(build_manager.tests.packages[row.package_index].name()) == item_id
+            }) else {
+                continue
+            };
+            let fold = item.fold_button(id!(fold));
+            if fold.opening(actions) || fold.closing(actions) {
+                build_manager.tests.set_open(row.package_index, row.target.as_ref(), &row.path, !row.is_open);
+                // Tests are discovered the first time a package is opened.
+                let package = &build_manager.tests.packages[row.package_index];
+                if row.target.is_none() && !row.is_open && !package.is_listed && !package.is_running {
+                    build_manager.run_tests(cx, row.package_index, TestRun {
+                        list: true,
+                        ..TestRun::default()
+                    }, file_system);
+                }
+                test_list.redraw(cx);
+            }
+            if item.button(id!(run)).clicked(actions) {
+                let run = if row.is_test && !row.has_children {
+                    TestRun {
+                        target: row.target.clone(),
+                        filter: Some(row.path.clone()),
+                        exact: true,
+                        list: false
+                    }
+                }
+                else if row.path.is_empty() {
+                    // The package runs all its test binaries, a test binary all its tests.
+                    TestRun {
+                        target: row.target.clone(),
+                        ..TestRun::default()
+                    }
+                }
+                else {
+                    TestRun {
+                        target: row.target.clone(),
+                        filter: Some(format!("{}::", row.path)),
+                        exact: false,
+                        list: false
+                    }
+                };
+                build_manager.run_tests(cx, row.package_index, run, file_system);
+                test_list.redraw(cx);
+                cx.action(AppAction::RedrawLog);
+            }
+            if item.link_label(id!(name)).pressed(actions) {
+                let location = build_manager.tests.node(row.package_index, row.target.as_ref(), &row.path)
+                    .and_then( | node | node.failure.as_ref())
+                    .and_then( | failure | failure.location.clone());
+                if let Some(location) = location {
+                    cx.action(AppAction::JumpTo(JumpTo {
+                        file_name: location.file_name,
+                        start: location.start
+                    }));
+                }
+            }
+        }
+    }
+}
+
+impl Widget for TestList {
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk:Walk)->DrawStep{
+        while let Some(item) = self.view.draw_walk(cx, scope, walk).step(){
+            if let Some(mut list) = item.as_flat_list().borrow_mut(){
+                self.draw_test_list(cx, &mut list, &mut scope.data.get_mut::<AppData>().build_manager)
+            }
+        }
+        DrawStep::done()
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        self.widget_match_event(cx, event, scope);
+        self.view.handle_event(cx, event, scope);
+    }
+}
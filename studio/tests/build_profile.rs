@@ -0,0 +1,65 @@
+use makepad_studio::build_manager::{
+    build_profile::{BuildProfile, BuildProfiles},
+    build_protocol::BuildTarget,
+};
+
+#[test]
+fn test_parse_build_profiles() {
+    let profiles = BuildProfiles::parse(r#"(
+        profiles: [
+            (name: "Nightly", features: ["nightly", "simd"], release: true),
+            (
+                name: "Counter",
+                cargo_args: ["--example", "counter"],
+                run_args: ["--fast"],
+                env: [("RUST_LOG", "debug")],
+                in_studio: true,
+                binaries: ["makepad-example-simple"],
+            ),
+        ]
+    )"#).unwrap().profiles;
+    assert_eq!(profiles.len(), 2);
+    assert_eq!(profiles[0].args("app"), [
+        "run", "nightly", "cargo", "run", "-p", "app", "--message-format=json", "--release",
+        "--features=nightly,simd", "--", "--message-format=json",
+    ]);
+    assert_eq!(profiles[1].args("app"), [
+        "run", "nightly", "cargo", "run", "-p", "app", "--message-format=json", "--example",
+        "counter", "--", "--message-format=json", "--stdin-loop", "--fast",
+    ]);
+    assert_eq!(profiles[1].env(), [("RUST_LOG".to_string(), "debug".to_string())]);
+    assert!(profiles[1].applies_to("makepad-example-simple"));
+    assert!(!profiles[1].applies_to("makepad-studio"));
+    
+    assert!(BuildProfiles::parse("(profiles: [(name: \"a\"), (name: \"a\")])").is_err());
+    assert!(BuildProfiles::parse("(profiles: [(features: [])])").is_err());
+}
+
+#[test]
+fn test_check_profile_has_no_run_args() {
+    let profile = BuildProfile {
+        name: "Check wasm".into(),
+        command: Some("check".into()),
+        target: Some("wasm32-unknown-unknown".into()),
+        run_args: Some(vec!["--ignored".into()]),
+        ..BuildProfile::default()
+    };
+    assert_eq!(profile.args("app"), [
+        "run", "nightly", "cargo", "check", "-p", "app", "--message-format=json",
+        "--target=wasm32-unknown-unknown",
+    ]);
+}
+
+#[test]
+fn test_build_target_ids() {
+    for target in BuildTarget::builtin() {
+        assert_eq!(BuildTarget::from_id(target.as_id()), Some(target));
+    }
+    assert_eq!(BuildTarget::from_id(BuildTarget::len()), None);
+    let custom = BuildTarget::Custom(Box::new(BuildProfile {
+        name: "Nightly".into(),
+        ..BuildProfile::default()
+    }));
+    assert_eq!(custom.name(), "Nightly");
+    assert!(custom.as_id() >= BuildTarget::len());
+}
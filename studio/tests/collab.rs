@@ -0,0 +1,190 @@
+use {
+    makepad_studio::{
+        file_system::collab::CollabFile,
+        makepad_code_editor::{
+            decoration::DecorationSet,
+            selection::Affinity,
+            session::SelectionMode,
+            text::{Position, Text},
+            Document, Session,
+        },
+        makepad_file_protocol::{Delta, FileNotification, FileRequest, FileResponse},
+        makepad_file_server::{FileServer, FileServerConnection},
+    },
+    std::{fs, path::Path, sync::mpsc::{self, Receiver}},
+};
+
+// A participant of the collab server, with the server connection standing in for the network.
+struct Client {
+    connection: FileServerConnection,
+    notification_receiver: Receiver<FileNotification>,
+    session: Session,
+    collab: CollabFile,
+}
+
+impl Client {
+    fn open(server: &mut FileServer) -> Self {
+        let (notification_sender, notification_receiver) = mpsc::channel();
+        let connection = server.connect(Box::new(move | notification | {
+            notification_sender.send(notification).unwrap()
+        }));
+        let (text, revision) = match connection.handle_request(FileRequest::OpenFile("main.rs".into(), 0)) {
+            FileResponse::OpenFile(Ok((_, text, _, revision))) => (text, revision),
+            response => panic!("{:?}", response),
+        };
+        let document = Document::new(Text::from(text.as_str()), DecorationSet::new());
+        let collab = CollabFile::new(&document, revision);
+        Self {
+            connection,
+            notification_receiver,
+            session: Session::new(document),
+            collab,
+        }
+    }
+
+    fn type_text(&mut self, byte_index: usize, text: &str) {
+        self.session.set_selection(Position {line_index: 0, byte_index}, Affinity::Before, SelectionMode::Simple);
+        self.session.insert(Text::from(text));
+        self.session.handle_changes();
+    }
+
+    fn text(&self) -> String {
+        self.session.document().as_text().to_string()
+    }
+
+    // Sends a delta to the server, and returns the revision it was applied as.
+    fn send(&self, (revision, delta): (usize, Delta)) -> usize {
+        match self.connection.handle_request(FileRequest::ApplyDelta("main.rs".into(), revision, delta)) {
+            FileResponse::ApplyDelta(Ok((_, revision))) => revision,
+            response => panic!("{:?}", response),
+        }
+    }
+
+    // Handles the notifications that arrived so far, and returns the deltas to send.
+    fn receive(&mut self) -> Vec<(usize, Delta)> {
+        let mut deltas_to_send = Vec::new();
+        while let Ok(notification) = self.notification_receiver.try_recv() {
+            if let FileNotification::DeltaWasApplied(_, revision, delta) = notification {
+                let document = self.session.document().clone();
+                deltas_to_send.extend(self.collab.handle_remote_delta(&document, revision, delta).unwrap());
+                self.session.handle_changes();
+            }
+        }
+        deltas_to_send
+    }
+}
+
+fn with_file(name: &str, text: &str, f: impl FnOnce(&Path)) {
+    let root_path = std::env::temp_dir().join(format!("makepad_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&root_path).unwrap();
+    fs::write(root_path.join("main.rs"), text).unwrap();
+    f(&root_path);
+    fs::remove_dir_all(&root_path).unwrap();
+}
+
+#[test]
+fn test_clients_converge() {
+    with_file("collab_clients", "ac", | root_path | {
+        let mut server = FileServer::new(root_path);
+        let mut client_0 = Client::open(&mut server);
+        let mut client_1 = Client::open(&mut server);
+
+        // Both participants type at the same time, and the delta of client 1 arrives first.
+        client_0.type_text(1, "b");
+        let delta_0 = client_0.collab.take_local_edits().unwrap();
+        assert_eq!(delta_0.0, 0);
+        client_1.type_text(2, "d");
+        let delta_1 = client_1.collab.take_local_edits().unwrap();
+        let revision = client_1.send(delta_1);
+        assert_eq!(revision, 1);
+        assert!(client_1.collab.handle_ack(revision).is_none());
+
+        // Client 0 types again, and hears about the delta of client 1 while its own is still
+        // underway, so both its deltas have to be transformed.
+        client_0.type_text(0, "x");
+        assert!(client_0.receive().is_empty());
+        assert_eq!(client_0.text(), "xabcd");
+        let revision = client_0.send(delta_0);
+        assert_eq!(revision, 2);
+        let delta_0 = client_0.collab.handle_ack(revision).unwrap();
+        assert_eq!(delta_0.0, 2);
+
+        // Client 1 types before it hears about the first delta of client 0, so the edit it sends
+        // then has to be transformed against that delta, and be based on its revision.
+        client_1.type_text(3, "e");
+        let mut deltas_1 = client_1.receive();
+        assert_eq!(deltas_1.len(), 1);
+        assert_eq!(deltas_1[0].0, 2);
+        assert_eq!(client_1.text(), "abcde");
+
+        let revision = client_0.send(delta_0);
+        assert!(client_0.collab.handle_ack(revision).is_none());
+        // The server notifies client 1 about the delta of client 0 before it responds to the
+        // delta of client 1.
+        let revision = client_1.send(deltas_1.remove(0));
+        assert!(client_1.receive().is_empty());
+        assert!(client_1.collab.handle_ack(revision).is_none());
+        assert!(client_0.receive().is_empty());
+
+        assert_eq!(client_0.text(), "xabcde");
+        assert_eq!(client_1.text(), "xabcde");
+        match client_0.connection.handle_request(FileRequest::SaveFile("main.rs".into())) {
+            FileResponse::SaveFile(Ok((_, _, new_content))) => assert_eq!(new_content, "xabcde"),
+            response => panic!("{:?}", response),
+        }
+    });
+}
+
+#[test]
+fn test_inserts_at_same_position_converge() {
+    with_file("collab_same_position", "ac", | root_path | {
+        let mut server = FileServer::new(root_path);
+        let mut client_0 = Client::open(&mut server);
+        let mut client_1 = Client::open(&mut server);
+        let mut client_2 = Client::open(&mut server);
+
+        // All participants insert at the same position. The delta of client 1 is underway when
+        // it hears about the one of client 0, the edit of client 2 is still in its buffer.
+        client_0.type_text(1, "X");
+        client_1.type_text(1, "Y");
+        client_2.type_text(1, "Z");
+        let delta_0 = client_0.collab.take_local_edits().unwrap();
+        let delta_1 = client_1.collab.take_local_edits().unwrap();
+        let revision = client_0.send(delta_0);
+        assert!(client_0.collab.handle_ack(revision).is_none());
+        assert!(client_1.receive().is_empty());
+        let revision = client_1.send(delta_1);
+        assert!(client_1.collab.handle_ack(revision).is_none());
+        let mut deltas_2 = client_2.receive();
+        assert_eq!(deltas_2.len(), 1);
+        let revision = client_2.send(deltas_2.remove(0));
+        assert!(client_2.collab.handle_ack(revision).is_none());
+        assert!(client_0.receive().is_empty());
+        assert!(client_1.receive().is_empty());
+
+        // The server puts the text that was applied first first, and so do the clients.
+        assert_eq!(client_0.text(), "aXYZc");
+        assert_eq!(client_1.text(), "aXYZc");
+        assert_eq!(client_2.text(), "aXYZc");
+
+        // Undo only takes back the own edit.
+        assert!(client_1.session.undo());
+        client_1.session.handle_changes();
+        assert_eq!(client_1.text(), "aXZc");
+        assert!(!client_1.session.undo());
+    });
+}
+
+#[test]
+fn test_delta_that_does_not_apply_is_an_error() {
+    with_file("collab_out_of_sync", "ac", | root_path | {
+        let mut server = FileServer::new(root_path);
+        let mut client = Client::open(&mut server);
+        let document = client.session.document().clone();
+        let mut delta = Delta::new();
+        delta.retain(5);
+        delta.insert("x");
+        assert!(client.collab.handle_remote_delta(&document, 1, delta).is_err());
+        assert_eq!(client.text(), "ac");
+    });
+}
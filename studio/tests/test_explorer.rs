@@ -0,0 +1,202 @@
+use makepad_studio::{
+    makepad_code_editor::text::Position,
+    makepad_micro_serde::*,
+    build_manager::{
+        build_protocol::{TestMessage, TestRun, TestTarget},
+        libtest_json::{self, LibtestEvent},
+        test_explorer::{self, TestExplorer, TestLocation, TestStatus},
+    },
+};
+
+fn event(json: &str) -> TestMessage {
+    TestMessage::Event(TestTarget::Lib, Box::new(LibtestEvent::deserialize_json(json).unwrap()))
+}
+
+fn listed(name: &str) -> TestMessage {
+    TestMessage::Listed(TestTarget::Lib, name.to_string())
+}
+
+fn status(tests: &TestExplorer, path: &str) -> TestStatus {
+    tests.node(0, Some(&TestTarget::Lib), path).unwrap().status()
+}
+
+#[test]
+fn test_parse_libtest_events() {
+    let suite = LibtestEvent::deserialize_json(r#"{ "type": "suite", "event": "started", "test_count": 2 }"#).unwrap();
+    assert!(!suite.is_test());
+    assert_eq!(suite.test_count, Some(2));
+
+    let failed = LibtestEvent::deserialize_json(
+        r#"{ "type": "test", "name": "tests::parse", "event": "failed", "exec_time": 0.001, "stdout": "thread 'tests::parse' panicked at src/lib.rs:10:5:\nboom\n" }"#
+    ).unwrap();
+    assert!(failed.is_test());
+    assert_eq!(failed.name.as_deref(), Some("tests::parse"));
+    assert_eq!(failed.event, "failed");
+
+    assert_eq!(libtest_json::parse_test_list_line("tests::parse: test\n"), Some("tests::parse"));
+    assert_eq!(libtest_json::parse_test_list_line("2 tests, 0 benchmarks"), None);
+}
+
+#[test]
+fn test_parse_test_failure() {
+    let failure = test_explorer::parse_test_failure(
+        "tests::parse",
+        "thread 'tests::parse' panicked at src/lib.rs:10:5:\nassertion `left == right` failed\n  left: 1\n right: 2\nnote: run with `RUST_BACKTRACE=1`\n",
+    );
+    assert_eq!(failure.message, "assertion `left == right` failed\n  left: 1\n right: 2");
+    assert_eq!(failure.location, Some(TestLocation {
+        file_name: "src/lib.rs".to_string(),
+        start: Position {line_index: 9, byte_index: 4},
+    }));
+
+    // The format used before Rust 1.73.
+    let failure = test_explorer::parse_test_failure(
+        "parse",
+        "thread 'parse' panicked at 'boom', studio/tests/a.rs:3:9\n",
+    );
+    assert_eq!(failure.message, "boom");
+    assert_eq!(failure.location.unwrap().start, Position {line_index: 2, byte_index: 8});
+
+    let failure = test_explorer::parse_test_failure("parse", "test timed out\n");
+    assert_eq!(failure.message, "test timed out");
+    assert_eq!(failure.location, None);
+}
+
+#[test]
+fn test_explorer_tree_and_status() {
+    let mut tests = TestExplorer::default();
+    tests.set_packages(["app"].into_iter());
+    for name in ["tests::a", "tests::b", "tests::nested::c", "d"] {
+        tests.handle_message(0, listed(name));
+    }
+    assert!(tests.packages[0].is_listed);
+    assert_eq!(tests.node(0, None, "").unwrap().status(), TestStatus::Unknown);
+
+    // Packages start closed, so only the package itself is shown.
+    assert_eq!(tests.rows().len(), 1);
+    tests.set_open(0, None, "", true);
+    let rows: Vec<(String, usize)> = tests.rows().into_iter().map( | row | (row.path, row.depth)).collect();
+    assert_eq!(rows, [
+        ("".to_string(), 0),
+        ("".to_string(), 1),
+        ("tests".to_string(), 2),
+        ("tests::a".to_string(), 3),
+        ("tests::b".to_string(), 3),
+        ("tests::nested".to_string(), 3),
+        ("tests::nested::c".to_string(), 4),
+        ("d".to_string(), 2),
+    ]);
+
+    tests.start_run(0, TestRun::default());
+    assert!(tests.packages[0].is_running);
+    tests.handle_message(0, event(r#"{ "type": "test", "event": "started", "name": "tests::a" }"#));
+    assert_eq!(status(&tests, "tests"), TestStatus::Running);
+    tests.handle_message(0, event(r#"{ "type": "test", "event": "ok", "name": "tests::a" }"#));
+    tests.handle_message(0, event(r#"{ "type": "test", "event": "ignored", "name": "tests::b" }"#));
+    tests.handle_message(0, event(r#"{ "type": "test", "event": "ok", "name": "tests::nested::c" }"#));
+    assert_eq!(status(&tests, "tests"), TestStatus::Passed);
+    assert_eq!(status(&tests, "tests::b"), TestStatus::Ignored);
+
+    let failure = tests.handle_message(0, event(
+        r#"{ "type": "test", "event": "failed", "name": "d", "stdout": "thread 'd' panicked at src/main.rs:1:1:\nboom\n" }"#
+    )).unwrap();
+    assert_eq!(failure.test_name, "d");
+    assert_eq!(failure.message, "boom");
+    assert_eq!(tests.node(0, None, "").unwrap().status(), TestStatus::Failed);
+
+    tests.handle_message(0, TestMessage::Finished);
+    assert!(!tests.packages[0].is_running);
+
+    // Rerunning a single test only resets that test.
+    tests.start_run(0, TestRun {target: Some(TestTarget::Lib), filter: Some("d".to_string()), exact: true, list: false});
+    assert_eq!(status(&tests, "d"), TestStatus::Unknown);
+    assert_eq!(status(&tests, "tests::a"), TestStatus::Passed);
+    assert!(tests.node(0, Some(&TestTarget::Lib), "d").unwrap().failure.is_none());
+
+    // Tests that were still running when the process exited didn't finish.
+    tests.handle_message(0, event(r#"{ "type": "test", "event": "started", "name": "d" }"#));
+    tests.handle_message(0, TestMessage::Finished);
+    assert_eq!(status(&tests, "d"), TestStatus::Unknown);
+}
+
+#[test]
+fn test_explorer_packages() {
+    let mut tests = TestExplorer::default();
+    tests.set_packages(["a", "b"].into_iter());
+    tests.handle_message(1, listed("x"));
+    let cmd_id = TestExplorer::process("b", TestRun {target: None, filter: Some("x".to_string()), exact: true, list: false}).as_id();
+    assert_eq!(tests.package_index(cmd_id), Some(1));
+
+    // What we know about a package is kept when the packages are updated.
+    tests.set_packages(["b", "c"].into_iter());
+    assert_eq!(tests.packages.len(), 2);
+    assert!(tests.packages[0].is_listed);
+    assert_eq!(tests.package_index(cmd_id), Some(0));
+
+    assert!(TestRun::default().args("b").ends_with(&["-Z".to_string(), "unstable-options".into(), "--format".into(), "json".into(), "--report-time".into()]));
+    assert_eq!(TestRun {target: None, filter: Some("x".to_string()), exact: true, list: false}.args("b")[8..10], ["x", "--exact"]);
+}
+
+#[test]
+fn test_explorer_failure_files() {
+    let mut tests = TestExplorer::default();
+    tests.set_packages(["app"].into_iter());
+    tests.start_run(0, TestRun::default());
+    for (name, file_name) in [("a", "src/main.rs"), ("b", "src/main.rs"), ("c", "src/lib.rs")] {
+        tests.handle_message(0, event(&format!(
+            r#"{{ "type": "test", "event": "failed", "name": "{}", "stdout": "thread '{}' panicked at {}:2:5:\nboom\n" }}"#,
+            name, name, file_name
+        )));
+    }
+    tests.handle_message(0, TestMessage::Finished);
+
+    // Only the decorations in the files of the tests that are run again have to be cleared, and
+    // the failures of the other tests in those files decorated again.
+    let run = TestRun {target: Some(TestTarget::Lib), filter: Some("a".to_string()), exact: true, list: false};
+    assert_eq!(tests.failure_file_names(0, &run), ["src/main.rs"]);
+    assert_eq!(tests.failure_file_names(0, &TestRun::default()), ["src/main.rs", "src/lib.rs"]);
+    tests.start_run(0, run);
+    let file_names: Vec<String> = tests.failure_locations().into_iter().map( | location | location.file_name).collect();
+    assert_eq!(file_names, ["src/main.rs", "src/lib.rs"]);
+    assert!(tests.node(0, Some(&TestTarget::Lib), "a").unwrap().failure.is_none());
+}
+
+#[test]
+fn test_explorer_test_binaries() {
+    assert_eq!(libtest_json::parse_test_target_line("     Running unittests src/lib.rs (target/debug/deps/app-0123)\n"), Some(TestTarget::Lib));
+    assert_eq!(libtest_json::parse_test_target_line("     Running unittests src/main.rs (target/debug/deps/app-4567)"), Some(TestTarget::Bins));
+    assert_eq!(libtest_json::parse_test_target_line("     Running unittests src/bin/tool.rs (target/debug/deps/tool-89ab)"), Some(TestTarget::Bins));
+    assert_eq!(libtest_json::parse_test_target_line("     Running tests/parse.rs (target/debug/deps/parse-cdef)"), Some(TestTarget::Test("parse".to_string())));
+    assert_eq!(libtest_json::parse_test_target_line("     Running tests/render/main.rs (target/debug/deps/render-0123)"), Some(TestTarget::Test("render".to_string())));
+    assert_eq!(libtest_json::parse_test_target_line("   Doc-tests app"), Some(TestTarget::Doc));
+    assert_eq!(libtest_json::parse_test_target_line("   Compiling app v0.1.0"), None);
+
+    // Tests with the same name in different test binaries are different tests.
+    let mut tests = TestExplorer::default();
+    tests.set_packages(["app"].into_iter());
+    let parse = TestTarget::Test("parse".to_string());
+    tests.handle_message(0, listed("a"));
+    tests.handle_message(0, TestMessage::Listed(parse.clone(), "a".to_string()));
+    tests.start_run(0, TestRun::default());
+    tests.handle_message(0, event(r#"{ "type": "test", "event": "ok", "name": "a" }"#));
+    tests.handle_message(0, TestMessage::Event(parse.clone(), Box::new(LibtestEvent::deserialize_json(
+        r#"{ "type": "test", "event": "failed", "name": "a", "stdout": "thread 'a' panicked at tests/parse.rs:2:5:\nboom\n" }"#
+    ).unwrap())));
+    assert_eq!(status(&tests, "a"), TestStatus::Passed);
+    assert_eq!(tests.node(0, Some(&parse), "a").unwrap().status(), TestStatus::Failed);
+
+    // Rerunning a test only resets it in its own test binary, and only runs that binary.
+    let run = TestRun {target: Some(parse.clone()), filter: Some("a".to_string()), exact: true, list: false};
+    assert_eq!(tests.failure_file_names(0, &run), ["tests/parse.rs"]);
+    assert_eq!(run.args("app")[6..8], ["--test", "parse"]);
+    tests.start_run(0, run);
+    assert_eq!(status(&tests, "a"), TestStatus::Passed);
+    assert_eq!(tests.node(0, Some(&parse), "a").unwrap().status(), TestStatus::Unknown);
+
+    tests.set_open(0, None, "", true);
+    let rows = tests.rows();
+    assert_ne!(rows[2].item_id("app"), rows[4].item_id("app"));
+    assert_eq!(rows[1].name, "lib");
+    assert_eq!(rows[3].name, "tests/parse");
+    assert_eq!(TestRun {target: Some(TestTarget::Lib), ..TestRun::default()}.args("app")[6], "--lib");
+}
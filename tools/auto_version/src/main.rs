@@ -88,7 +88,7 @@ fn main() {
     for c in crates {
         let cargo_str = fs::read_to_string(&c.cargo).unwrap();
         
-        let toml = makepad_toml_parser::parse_toml(&cargo_str).unwrap();
+        let toml = makepad_toml_parser::parse_toml(&cargo_str).unwrap().flatten();
 
         let old_sha1 = if let Some(Toml::Str(ver, _)) = toml.get("package.metadata.makepad-auto-version") {
             ver.to_string()
@@ -178,17 +178,19 @@ fn main() {
 
 fn patch_cargo(cargo: &Path, toml_path: &str, with: &str, write: bool) {
     let old_cargo = fs::read_to_string(cargo).unwrap();
-    let toml = makepad_toml_parser::parse_toml(&old_cargo).unwrap();
+    let toml = makepad_toml_parser::parse_toml(&old_cargo).unwrap().flatten();
     
     if let Some(Toml::Str(_, span)) = toml.get(toml_path) {
+        // the span includes the quotes
+        let (start, end) = (span.start + 1, span.start + span.len - 1);
         let mut new_cargo = String::new();
         for (i, c) in old_cargo.chars().enumerate() {
-            if i == span.start {
+            if i == start {
                 for c in with.chars() {
                     new_cargo.push(c);
                 }
             }
-            if i < span.start || i >= span.start + span.len - 2 {
+            if i < start || i >= end {
                 new_cargo.push(c);
             }
         }
@@ -1,10 +1,8 @@
-use makepad_http::server::*;
+use makepad_http::{server::*, router::{HttpRouter, StaticFiles}};
 
 use std::{
     net::SocketAddr,
     sync::mpsc,
-    io::prelude::*,
-    fs::File,
 };
 
 fn main() {
@@ -35,86 +33,26 @@ fn main() {
     
     
     let abs_makepad_path = std::env::current_dir().unwrap().join(makepad_path.clone()).canonicalize().unwrap().to_str().unwrap().to_string();
+    let mut router = HttpRouter::new();
+    router.get("/$watch", | _ | {
+        HttpServerResponse::new(200, &[("Cache-Control", "max-age=0")], Vec::new())
+    });
+    router.get("/favicon.ico", | _ | HttpServerResponse::new(200, &[], Vec::new()));
     let remaps = [
         (format!("/makepad/{}/",abs_makepad_path),makepad_path.clone()),
-        (format!("/makepad/{}/",std::env::current_dir().unwrap().display()),"".to_string()),
-        ("/makepad//".to_string(),makepad_path.clone()),
+        (format!("/makepad/{}/",std::env::current_dir().unwrap().display()),".".to_string()),
         ("/makepad/".to_string(),makepad_path.clone()),
-        ("/".to_string(),"".to_string())
+        ("/".to_string(),".".to_string())
     ];
+    for (prefix, dir) in remaps{
+        router.static_files(&prefix, StaticFiles::new(dir)
+            .with_header("Cross-Origin-Embedder-Policy", "require-corp")
+            .with_header("Cross-Origin-Opener-Policy", "same-origin")
+            .with_header("Cache-Control", "max-age=0")
+            .with_extensions(&["html", "wasm", "css", "js", "ttf", "png", "jpg", "svg"]));
+    }
     while let Ok(message) = rx_request.recv() {
-        match message{
-            HttpServerRequest::ConnectWebSocket {web_socket_id:_, response_sender:_, headers:_}=>{
-                
-            },
-            HttpServerRequest::DisconnectWebSocket {web_socket_id:_}=>{
-                
-            },
-            HttpServerRequest::BinaryMessage {web_socket_id:_, response_sender:_, data:_}=>{
-                
-            }
-            HttpServerRequest::Get{headers, response_sender}=>{
-                let path = &headers.path;
-                
-                
-                if path == "/$watch"{
-                    let header = "HTTP/1.1 200 OK\r\n\
-                            Cache-Control: max-age:0\r\n\
-                            Connection: close\r\n\r\n".to_string();
-                    let _ = response_sender.send(HttpServerResponse{header, body:vec![]});
-                    continue
-                }
-                
-                if path == "/favicon.ico"{
-                    let header = "HTTP/1.1 200 OK\r\n\r\n".to_string();
-                    let _ = response_sender.send(HttpServerResponse{header, body:vec![]});
-                    continue
-                }
-                
-                let mime_type = if path.ends_with(".html") {"text/html"}
-                else if path.ends_with(".wasm") {"application/wasm"}
-                else if path.ends_with(".css") {"text/css"}
-                else if path.ends_with(".js") {"text/javascript"}
-                else if path.ends_with(".ttf") {"application/ttf"}
-                else if path.ends_with(".png") {"image/png"}
-                else if path.ends_with(".jpg") {"image/jpg"}
-                else if path.ends_with(".svg") {"image/svg+xml"}
-                else {continue};
-
-                if path.contains("..") || path.contains('\\'){
-                    continue
-                }
-                
-                let mut strip = None;
-                for remap in &remaps{
-                    if let Some(s) = path.strip_prefix(&remap.0){
-                        strip = Some(format!("{}{}",remap.1, s));
-                        break;
-                    }
-                }
-                if let Some(base) = strip{
-                    if let Ok(mut file_handle) = File::open(base) {
-                        let mut body = Vec::<u8>::new();
-                        if file_handle.read_to_end(&mut body).is_ok() {
-                            let header = format!(
-                                "HTTP/1.1 200 OK\r\n\
-                                Content-Type: {}\r\n\
-                                Cross-Origin-Embedder-Policy: require-corp\r\n\
-                                Cross-Origin-Opener-Policy: same-origin\r\n\
-                                Content-encoding: none\r\n\
-                                Cache-Control: max-age:0\r\n\
-                                Content-Length: {}\r\n\
-                                Connection: close\r\n\r\n",
-                                mime_type,
-                                body.len()
-                            );
-                            let _ = response_sender.send(HttpServerResponse{header, body});
-                        }
-                    }
-                }
-            }
-            HttpServerRequest::Post{..}=>{//headers, body, response}=>{
-            }
-        }
+        // websockets aren't used here
+        router.handle(message);
     }
 }
@@ -38,6 +38,7 @@ pub fn derive_widget_node_impl(input: TokenStream) ->  TokenStream {
         let mut wrap_field = None;
         let mut find_fields = Vec::new();
         let mut redraw_fields = Vec::new();
+        let mut area_field = None;
         for field in &mut fields {
             if field.attrs.iter().find(|v| v.name == "walk").is_some(){
                 walk_field = Some(field.name.clone());
@@ -47,6 +48,10 @@ pub fn derive_widget_node_impl(input: TokenStream) ->  TokenStream {
             }
             if field.attrs.iter().find(|v| v.name == "redraw").is_some(){
                 redraw_fields.push(field.name.clone());
+                // the first field marked redraw is the area of the widget
+                if area_field.is_none(){
+                    area_field = Some((field.name.clone(), field.ty.to_string() == "Area"));
+                }
             }
             if field.attrs.iter().find(|v| v.name == "find").is_some(){
                 find_fields.push(field.name.clone());
@@ -60,6 +65,7 @@ pub fn derive_widget_node_impl(input: TokenStream) ->  TokenStream {
         if let Some(wrap_field) = &wrap_field{
             tb.add("    fn walk(&mut self, cx:&mut Cx) -> Walk { self.").ident(&wrap_field).add(".walk(cx)}");            
             tb.add("    fn redraw(&mut self, cx:&mut Cx) { self.").ident(&wrap_field).add(".redraw(cx)}");
+            tb.add("    fn area(&self) -> Area { self.").ident(&wrap_field).add(".area()}");
             tb.add("    fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet){self.").ident(&wrap_field).add(".find_widgets(path, cached, results)}");
         }
         else{
@@ -85,6 +91,17 @@ pub fn derive_widget_node_impl(input: TokenStream) ->  TokenStream {
             else{
                 return error("Need either a field marked redraw or deref or wrap to find redraw method")
             }
+            if let Some((area_field, is_area)) = &area_field{
+                if *is_area{
+                    tb.add("    fn area(&self) -> Area { self.").ident(&area_field).add("}");
+                }
+                else{
+                    tb.add("    fn area(&self) -> Area { self.").ident(&area_field).add(".area()}");
+                }
+            }
+            else if let Some(deref_field) = &deref_field{
+                tb.add("    fn area(&self) -> Area { self.").ident(&deref_field).add(".area()}");
+            }
             if find_fields.len()>0{
                 tb.add("    fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet){");
                 for find_field in find_fields{
@@ -178,6 +178,7 @@ impl FileTreeNode {
                 right: depth as f64 * 4.0,
                 bottom: 0.0,
             },
+            ..Walk::default()
         }
     }
     
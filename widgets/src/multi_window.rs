@@ -52,6 +52,8 @@ impl WidgetNode for MultiWindow{
         }
     }
     fn walk(&mut self, _cx:&mut Cx) -> Walk {Walk::default()}
+    
+    fn area(&self) -> Area {Area::Empty}
         
     fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results:&mut WidgetSet){
         for window in self.windows.values_mut() {
@@ -97,6 +97,10 @@ impl WidgetNode for PageFlip{
     fn redraw(&mut self, cx: &mut Cx){
         self.area.redraw(cx)
     }
+    
+    fn area(&self) -> Area{
+        self.area
+    }
         
     fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
         if let Some(page) = self.pages.get_mut(&path[0]) {
@@ -313,7 +313,8 @@ impl PortalList {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + self.first_scroll)),
                         margin: Default::default(),
                         width: Size::Fill,
-                        height: Size::Fit
+                        height: Size::Fit,
+                        ..Walk::default()
                     }, Layout::flow_down());
                     return Some(self.first_id)
                 }
@@ -342,7 +343,8 @@ impl PortalList {
                                 abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                                 margin: Default::default(),
                                 width: Size::Fill,
-                                height: Size::Fit
+                                height: Size::Fit,
+                                ..Walk::default()
                             }, Layout::flow_down());
                             return Some(self.first_id - 1);
                         }
@@ -369,7 +371,8 @@ impl PortalList {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + pos + rect.size.index(vi))),
                         margin: Default::default(),
                         width: Size::Fill,
-                        height: Size::Fit
+                        height: Size::Fit,
+                        ..Walk::default()
                     }, Layout::flow_down());
                     return Some(index + 1)
                 }
@@ -401,7 +404,8 @@ impl PortalList {
                                     abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                                     margin: Default::default(),
                                     width: Size::Fill,
-                                    height: Size::Fit
+                                    height: Size::Fit,
+                                    ..Walk::default()
                                 }, Layout::flow_down());
                                 return Some(last_index + 1);
                             }
@@ -426,7 +430,8 @@ impl PortalList {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                         margin: Default::default(),
                         width: Size::Fill,
-                        height: Size::Fit
+                        height: Size::Fit,
+                        ..Walk::default()
                     }, Layout::flow_down());
                     
                     return Some(index - 1);
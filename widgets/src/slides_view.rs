@@ -32,6 +32,10 @@ impl WidgetNode for SlidesView{
         self.area.redraw(cx)
     }
     
+    fn area(&self) -> Area{
+        self.area
+    }
+    
     fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
         for child in self.children.values_mut() {
             child.find_widgets(path, cached, results);
@@ -157,7 +161,8 @@ impl Widget for SlidesView {
                 abs_pos: None,
                 margin: Default::default(),
                 width: Size::Fill,
-                height: Size::Fill
+                height: Size::Fill,
+                ..Walk::default()
             }, Layout::flow_down().with_scroll(
                 dvec2(rect.size.x * self.current_slide.fract(), 0.0)
             ));
@@ -179,7 +184,8 @@ impl Widget for SlidesView {
                 abs_pos: None,
                 margin: Default::default(),
                 width: Size::Fill,
-                height: Size::Fill
+                height: Size::Fill,
+                ..Walk::default()
             }, Layout::flow_down().with_scroll(
                 dvec2(-rect.size.x * (1.0-self.current_slide.fract()), 0.0)
             ));
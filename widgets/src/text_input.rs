@@ -19,6 +19,15 @@ struct UndoItem {
     cursor_tail: usize
 }
 
+// the IME preedit is shown in the text while composing, on top of the text it started from
+#[derive(Clone)]
+struct Composition {
+    before: UndoItem,
+    preedit: String,
+    start: usize,
+    len: usize
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum UndoGroup {
     TextInput(u64),
@@ -71,6 +80,7 @@ pub struct TextInput {
     #[rust] last_undo: Option<UndoItem>,
     #[rust] undo_stack: Vec<UndoItem>,
     #[rust] redo_stack: Vec<UndoItem>,
+    #[rust] composition: Option<Composition>,
     #[rust] cursor_tail: usize,
     #[rust] cursor_head: usize
 }
@@ -81,6 +91,7 @@ impl Widget for TextInput {
         self.animator_handle_event(cx, event);
         match event.hits(cx, self.draw_bg.area()) {
             Hit::KeyFocusLost(_) => {
+                self.finish_composition(cx, uid, scope);
                 self.animator_play(cx, id!(focus.off));
                 cx.hide_text_ime();
                 cx.widget_action(uid, &scope.path, TextInputAction::Return(self.text.clone()));
@@ -97,27 +108,40 @@ impl Widget for TextInput {
                 cx.widget_action(uid, &scope.path, TextInputAction::KeyFocus);
             }
             Hit::TextInput(te) => {
-                let mut input = String::new();
-                self.filter_input(&te.input, Some(&mut input));
-                if input.len() == 0 {
+                // text that arrives while composing replaces the preedit
+                if let Some(composition) = self.composition.take() {
+                    self.consume_undo_item(composition.before);
+                    self.undo_id += 1;
+                }
+                self.text_input(cx, uid, scope, &te.input, te.replace_last);
+            }
+            Hit::TextComposition(tc) => {
+                if self.read_only {
                     return
                 }
-                let last_undo = self.last_undo.take();
-                if te.replace_last {
-                    self.undo_id += 1;
-                    self.create_undo(UndoGroup::TextInput(self.undo_id));
-                    if let Some(item) = last_undo {
-                        self.consume_undo_item(item);
+                match tc {
+                    TextCompositionEvent::Start => {
+                        self.start_composition();
                     }
-                }
-                else {
-                    if input == " " {
+                    TextCompositionEvent::Update {text, cursor_start, cursor_end} => {
+                        self.update_composition(&text, cursor_start, cursor_end);
+                    }
+                    TextCompositionEvent::Commit {text} => {
+                        if let Some(composition) = self.composition.take() {
+                            self.consume_undo_item(composition.before);
+                        }
+                        // a composition is a single undo step
+                        self.undo_id += 1;
+                        self.text_input(cx, uid, scope, &text, false);
                         self.undo_id += 1;
                     }
-                    // if this one follows a space, it still needs to eat it
-                    self.create_undo(UndoGroup::TextInput(self.undo_id));
+                    TextCompositionEvent::Cancel => {
+                        if let Some(composition) = self.composition.take() {
+                            self.consume_undo_item(composition.before);
+                        }
+                    }
                 }
-                if self.change(cx, &input){self.push_change_action(uid, scope, cx)}
+                self.draw_bg.redraw(cx);
             }
             Hit::TextCopy(ce) => {
                 self.undo_id += 1;
@@ -131,6 +155,8 @@ impl Widget for TextInput {
                     if self.change(cx, ""){self.push_change_action(uid, scope, cx)}
                 }
             }
+            // the IME owns the keys while composing
+            Hit::KeyDown(_) if self.composition.is_some() => (),
             Hit::KeyDown(ke) => match ke.key_code {
                                 
                 KeyCode::Tab => {
@@ -255,6 +281,7 @@ impl Widget for TextInput {
                 self.animator_play(cx, id!(hover.off));
             },
             Hit::FingerDown(fe) => {
+                self.finish_composition(cx, uid, scope);
                 cx.set_cursor(MouseCursor::Text);
                 self.set_key_focus(cx);
                 // ok so we need to calculate where we put the cursor down.
@@ -476,6 +503,69 @@ impl TextInput {
         self.text = new;
     }
     
+    fn text_input(&mut self, cx: &mut Cx, uid: WidgetUid, scope: &mut Scope, input: &str, replace_last: bool) {
+        let mut filtered = String::new();
+        self.filter_input(input, Some(&mut filtered));
+        if filtered.is_empty() {
+            return
+        }
+        let last_undo = self.last_undo.take();
+        if replace_last {
+            self.undo_id += 1;
+            self.create_undo(UndoGroup::TextInput(self.undo_id));
+            if let Some(item) = last_undo {
+                self.consume_undo_item(item);
+            }
+        }
+        else {
+            if filtered == " " {
+                self.undo_id += 1;
+            }
+            // if this one follows a space, it still needs to eat it
+            self.create_undo(UndoGroup::TextInput(self.undo_id));
+        }
+        if self.change(cx, &filtered){self.push_change_action(uid, scope, cx)}
+    }
+    
+    fn start_composition(&mut self) {
+        if self.composition.is_none() {
+            let before = self.create_undo_item(UndoGroup::TextInput(self.undo_id));
+            self.composition = Some(Composition {
+                before,
+                preedit: String::new(),
+                start: self.sorted_cursor().0,
+                len: 0
+            });
+        }
+    }
+    
+    fn update_composition(&mut self, text: &str, cursor_start: usize, cursor_end: usize) {
+        self.start_composition();
+        let mut composition = self.composition.take().unwrap();
+        let mut preedit = String::new();
+        self.filter_input(text, Some(&mut preedit));
+        // the preedit is not part of the undo history until it is committed
+        self.consume_undo_item(composition.before.clone());
+        self.replace_text(&preedit);
+        let len = preedit.chars().count();
+        let char_index = | byte_index: usize | text.get(..byte_index).map_or(len, | s | s.chars().count()).min(len);
+        self.cursor_tail = composition.start + char_index(cursor_start);
+        self.cursor_head = composition.start + char_index(cursor_end);
+        composition.preedit = preedit;
+        composition.len = len;
+        self.composition = Some(composition);
+    }
+    
+    fn finish_composition(&mut self, cx: &mut Cx, uid: WidgetUid, scope: &mut Scope) {
+        // keep what was composed so far as if the IME committed it
+        if let Some(composition) = self.composition.take() {
+            self.consume_undo_item(composition.before);
+            self.undo_id += 1;
+            self.text_input(cx, uid, scope, &composition.preedit, false);
+            self.undo_id += 1;
+        }
+    }
+    
     pub fn select_word(&mut self, around: usize) {
         let mut first_ws = Some(0);
         let mut last_ws = None;
@@ -606,11 +696,25 @@ impl TextInput {
                 self.draw_select.draw_abs(cx, rect);
             }
         }
+        
+        // underline the preedit text
+        if let Some(composition) = &self.composition {
+            let bottom_drop = self.draw_text.get_font_size() * 0.1;
+            let rects = self.draw_text.get_selection_rects(cx, composition.start, composition.start + composition.len, dvec2(0.0, 0.0), dvec2(0.0, bottom_drop));
+            for rect in rects {
+                self.draw_cursor.draw_abs(cx, Rect {
+                    pos: dvec2(rect.pos.x, rect.pos.y + rect.size.y - self.cursor_size),
+                    size: dvec2(rect.size.x, self.cursor_size)
+                });
+            }
+        }
         self.draw_bg.end(cx);
         
         if  cx.has_key_focus(self.draw_bg.area()) {
             // ok so. if we have the IME we should inject a tracking point
-            let ime_x = self.draw_text.get_cursor_pos(cx, 0.5, self.cursor_head)
+            // and keep it at the start of the preedit while composing
+            let ime_pos = self.composition.as_ref().map_or(self.cursor_head, | c | c.start);
+            let ime_x = self.draw_text.get_cursor_pos(cx, 0.5, ime_pos)
                 .unwrap_or(dvec2(turtle.pos.x, 0.0)).x;
             
             if self.numeric_only {
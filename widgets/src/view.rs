@@ -456,6 +456,10 @@ impl WidgetNode for View{
         }
     }
     
+    fn area(&self) -> Area{
+        self.area
+    }
+    
     fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
         match cached {
             WidgetCache::Yes | WidgetCache::Clear => {
@@ -840,7 +844,8 @@ impl View {
             abs_pos: walk.abs_pos,
             width: if walk.width.is_fill() {walk.width}else {Size::Fixed(view_size.x)},
             height: if walk.height.is_fill() {walk.height}else {Size::Fixed(view_size.y)},
-            margin: walk.margin
+            margin: walk.margin,
+            ..walk
         }
     }
     
@@ -27,6 +27,7 @@ pub trait WidgetNode: LiveApply{
     fn find_widgets(&mut self, _path: &[LiveId], _cached: WidgetCache, _results: &mut WidgetSet);
     fn walk(&mut self, _cx:&mut Cx) -> Walk;
     fn redraw(&mut self, _cx: &mut Cx);
+    fn area(&self) -> Area;
 }
 
 pub trait Widget: WidgetNode {
@@ -593,6 +594,13 @@ impl WidgetRef {
         }
     }
     
+    pub fn area(&self) -> Area {
+        if let Some(inner) = self.0.borrow().as_ref() {
+            return inner.widget.area()
+        }
+        Area::Empty
+    }
+    
     pub fn is_visible(&self) -> bool {
         if let Some(inner) = self.0.borrow().as_ref() {
             return inner.widget.is_visible()
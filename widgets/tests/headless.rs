@@ -0,0 +1,72 @@
+use makepad_widgets::*;
+
+live_design!{
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    App = {{App}} {
+        ui: <Window> {
+            body = <View> {
+                flow: Down,
+                padding: 10,
+                spacing: 5,
+                title = <Label> {
+                    draw_text: {text_style: {font: {path: dep("crate://self/resources/IBMPlexSans-Text.ttf")}}}
+                    height: 20,
+                    text: "Hello"
+                }
+                row = <View> {
+                    width: Fill,
+                    height: 40,
+                    left = <View> {width: 100, height: Fill, show_bg: true}
+                    right = <View> {width: Fill, height: Fill, show_bg: true}
+                }
+            }
+        }
+    }
+}
+
+#[derive(Live, LiveHook)]
+pub struct App {
+    #[live] ui: WidgetRef,
+}
+
+impl LiveRegister for App {
+    fn live_register(cx: &mut Cx) {
+        makepad_widgets::live_design(cx);
+    }
+}
+
+impl AppMain for App {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event) {
+        self.ui.handle_event(cx, event, &mut Scope::empty());
+    }
+}
+
+fn rect(x: f64, y: f64, w: f64, h: f64) -> Rect {
+    Rect {pos: dvec2(x, y), size: dvec2(w, h)}
+}
+
+fn widget_rect(app: &mut HeadlessApp<App>, path: &[LiveId]) -> Rect {
+    app.with_app( | app, cx | app.ui.widget(path).area().rect(cx))
+}
+
+#[test]
+fn test_headless_layout() {
+    let mut app = HeadlessApp::<App>::new(live_design, dvec2(400.0, 300.0));
+    let title = app.text_rect("Hello").unwrap();
+    assert_eq!((title.pos, title.size.y), (dvec2(10.0, 10.0), 20.0));
+    assert!(title.size.x > 0.0);
+    assert_eq!(widget_rect(&mut app, id!(left)), rect(10.0, 35.0, 100.0, 40.0));
+    assert_eq!(widget_rect(&mut app, id!(right)), rect(110.0, 35.0, 280.0, 40.0));
+}
+
+#[test]
+fn test_headless_redraw() {
+    let mut app = HeadlessApp::<App>::new(live_design, dvec2(400.0, 300.0));
+    app.with_app( | app, cx | app.ui.label(id!(title)).set_text_and_redraw(cx, "Hello world"));
+    app.cx.headless_draw();
+    assert!(app.text_rect("Hello world").is_some());
+    assert!(app.text_rect("Hello").is_none());
+    assert_eq!(widget_rect(&mut app, id!(right)).size.x, 280.0);
+}
@@ -0,0 +1,121 @@
+use {
+    std::{fs, path::PathBuf},
+    makepad_widgets::{
+        *,
+        font_atlas::CxFontsAtlasRc,
+        makepad_shader_compiler::rasterize::SoftwareFramebuffer,
+    },
+};
+
+live_design!{
+    Scene = {{Scene}} {
+        draw_quad: {
+            fn pixel(self) -> vec4 {
+                return mix(#f00, #00f, self.pos.x)
+            }
+        }
+        draw_text: {
+            color: #fff
+            text_style: {
+                font: {path: dep("crate://self/resources/IBMPlexSans-Text.ttf")}
+                font_size: 12.0
+            }
+        }
+    }
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+pub struct Scene {
+    #[live] draw_quad: DrawQuad,
+    #[live] draw_text: DrawText,
+}
+
+const SIZE: DVec2 = DVec2 {x: 64.0, y: 24.0};
+
+// Draws the scene into a pass of SIZE and renders its draw list on the CPU.
+fn render(draw: impl FnOnce(&mut Cx2d, &mut Scene)) -> SoftwareFramebuffer {
+    let mut cx = Cx::new(Box::new( | _, _ | {}));
+    makepad_widgets::live_design(&mut cx);
+    crate::live_design(&mut cx);
+    cx.start_headless(SIZE, 1.0);
+    // the font atlas is drawn in software as well, a small one keeps that cheap
+    Cx2d::lazy_construct_font_atlas(&mut cx);
+    cx.get_global::<CxFontsAtlasRc>().0.borrow_mut().alloc.texture_size = dvec2(256.0, 256.0);
+
+    let mut scene = Scene::new_from_module(&mut cx, LiveModuleId::from_str(module_path!()).unwrap(), live_id!(Scene)).unwrap();
+    let pass = Pass::new(&mut cx);
+    let mut draw_list = DrawList2d::new(&mut cx);
+    let draw_event = DrawEvent::default();
+    {
+        let cx = &mut Cx2d::new(&mut cx, &draw_event);
+        cx.begin_pass(&pass, Some(1.0));
+        pass.set_size(cx, SIZE);
+        draw_list.begin_always(cx);
+        cx.begin_pass_sized_turtle(Layout::default());
+        draw(cx, &mut scene);
+        cx.end_pass_sized_turtle();
+        draw_list.end(cx);
+        cx.end_pass(&pass);
+    }
+    cx.render_draw_list_to_image(draw_list.draw_list_id(), SIZE, 1.0).unwrap()
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.pam", name))
+}
+
+// Compares the image with tests/golden/<name>.pam, a PAM file of RGBA bytes. Set
+// MAKEPAD_UPDATE_GOLDEN to write the image there instead. Channels may be off by
+// a little, for the floating point differences between machines.
+fn assert_golden(name: &str, framebuffer: &SoftwareFramebuffer) {
+    let rgba = framebuffer.to_rgba8();
+    if std::env::var_os("MAKEPAD_UPDATE_GOLDEN").is_some() {
+        let mut pam = format!(
+            "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+            framebuffer.width,
+            framebuffer.height
+        ).into_bytes();
+        pam.extend_from_slice(&rgba);
+        fs::write(golden_path(name), pam).unwrap();
+        return
+    }
+    let pam = fs::read(golden_path(name)).unwrap();
+    let header_len = pam.windows(7).position( | w | w == b"ENDHDR\n").unwrap() + 7;
+    let header = std::str::from_utf8(&pam[..header_len]).unwrap();
+    let field = | name: &str | header.lines().find_map( | line | line.strip_prefix(name)).unwrap().trim().parse::<usize>().unwrap();
+    assert_eq!((field("WIDTH"), field("HEIGHT")), (framebuffer.width, framebuffer.height));
+    let golden = &pam[header_len..];
+    assert_eq!(golden.len(), rgba.len());
+    for (index, (pixel, golden_pixel)) in rgba.chunks(4).zip(golden.chunks(4)).enumerate() {
+        assert!(
+            pixel.iter().zip(golden_pixel).all( | (a, b) | a.abs_diff(*b) <= 2),
+            "{}: pixel ({}, {}) is {:?} instead of {:?}",
+            name,
+            index % framebuffer.width,
+            index / framebuffer.width,
+            pixel,
+            golden_pixel
+        );
+    }
+}
+
+#[test]
+fn test_draw_quad_golden() {
+    let framebuffer = render( | cx, scene | {
+        scene.draw_quad.draw_abs(cx, Rect {pos: dvec2(8.0, 4.0), size: dvec2(48.0, 16.0)});
+    });
+    // the gradient runs from red on the left to blue on the right, outside is clear
+    assert_eq!(framebuffer.to_rgba8()[..4], [0, 0, 0, 0]);
+    assert!(framebuffer.pixel(8, 12).x > 0.9 && framebuffer.pixel(55, 12).z > 0.9);
+    assert_golden("draw_quad", &framebuffer);
+}
+
+#[test]
+fn test_draw_text_golden() {
+    let framebuffer = render( | cx, scene | {
+        scene.draw_text.draw_abs(cx, dvec2(4.0, 4.0), "Ag");
+    });
+    // the glyphs come out of the font atlas, which is a render target
+    assert!(framebuffer.color.iter().any( | color | color.w > 0.5));
+    assert_golden("draw_text", &framebuffer);
+}